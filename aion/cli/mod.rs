@@ -200,6 +200,18 @@ usage! {
             "--sync-boot-nodes-only",
             "Indicates if only sync from bootnodes.",
 
+            FLAG flag_fast_sync: (bool) = false, or |c: &Config| c.network.as_ref()?.fast_sync.clone(),
+            "--fast-sync",
+            "Download the state of a recent block from peers instead of executing every block from genesis. Only applies to an empty database.",
+
+            FLAG flag_light: (bool) = false, or |c: &Config| c.network.as_ref()?.light.clone(),
+            "--light",
+            "Run as a light client: sync headers only and verify state and receipts with proofs served by full nodes instead of executing blocks.",
+
+            FLAG flag_discovery: (bool) = false, or |c: &Config| c.network.as_ref()?.discovery.clone(),
+            "--discovery",
+            "Discover peers over udp, on the port of the local node, instead of only asking connected peers for theirs.",
+
             ARG arg_max_peers: (u32) = 64u32, or |c: &Config| c.network.as_ref()?.max_peers.clone(),
             "--max-peers=[NUM]",
             "Allow up to NUM peers.",
@@ -221,11 +233,71 @@ usage! {
             "--black_ip_list=[IPs]",
             "IP list whose connecting requests are to be rejected.",
 
+            ARG arg_max_frame_size: (u32) = 67108864u32, or |c: &Config| c.network.as_ref()?.max_frame_size.clone(),
+            "--max-frame-size=[BYTES]",
+            "Disconnect peers sending a message larger than BYTES.",
+
+            ARG arg_max_request_frame_size: (u32) = 65536u32, or |c: &Config| c.network.as_ref()?.max_request_frame_size.clone(),
+            "--max-request-frame-size=[BYTES]",
+            "Disconnect peers sending a request larger than BYTES.",
+
+            ARG arg_max_headers_per_request: (u32) = 192u32, or |c: &Config| c.network.as_ref()?.max_headers_per_request.clone(),
+            "--max-headers-per-request=[NUM]",
+            "Serve up to NUM block headers for a request of a peer.",
+
+            ARG arg_max_bodies_per_request: (u32) = 128u32, or |c: &Config| c.network.as_ref()?.max_bodies_per_request.clone(),
+            "--max-bodies-per-request=[NUM]",
+            "Serve up to NUM block bodies for a request of a peer.",
+
+            ARG arg_request_rate: (u32) = 100u32, or |c: &Config| c.network.as_ref()?.request_rate.clone(),
+            "--request-rate=[NUM]",
+            "Disconnect peers sending more than NUM requests of a kind a second, once over the burst allowed.",
+
+            ARG arg_request_burst: (u32) = 200u32, or |c: &Config| c.network.as_ref()?.request_burst.clone(),
+            "--request-burst=[NUM]",
+            "Allow peers to send up to NUM requests of a kind at once.",
+
         ["Rpc Options"]
             ARG arg_rpc_processing_threads: (Option<usize>) = None, or |c: &Config| c.rpc.as_ref()?.processing_threads,
             "--rpc--processing-threads=[NUM]",
             "Turn on additional processing threads for JSON-RPC servers (for all severs i.e for websocket and ipc). Setting this to a non-zero value allows parallel execution of cpu-heavy queries.",
 
+            ARG arg_rpc_max_payload: (usize) = 5usize, or |c: &Config| c.rpc.as_ref()?.max_payload.clone(),
+            "--rpc-max-payload=[MB]",
+            "Specify the maximum size in MB of requests to the HTTP and WebSockets servers.",
+
+            ARG arg_rpc_max_batch_size: (Option<usize>) = None, or |c: &Config| c.rpc.as_ref()?.max_batch_size,
+            "--rpc-max-batch-size=[NUM]",
+            "Reject batch requests to the HTTP, WebSockets and IPC servers holding more than NUM calls.",
+
+            ARG arg_rpc_max_requests_per_second: (Option<usize>) = None, or |c: &Config| c.rpc.as_ref()?.max_requests_per_second,
+            "--rpc-max-requests-per-second=[NUM]",
+            "Allow each client of the HTTP, WebSockets and IPC servers up to NUM calls per second. Clients are told apart by API key or token, else by WebSockets or IPC connection, or by the address reported by a trusted proxy (see --http-trust-proxy-headers); other HTTP requests without a key share one budget.",
+
+            ARG arg_rpc_max_logs_block_range: (Option<u64>) = None, or |c: &Config| c.rpc.as_ref()?.max_logs_block_range,
+            "--rpc-max-logs-block-range=[BLOCKS]",
+            "Reject eth_getLogs and log filter queries spanning more than BLOCKS blocks.",
+
+            ARG arg_rpc_max_logs: (Option<usize>) = None, or |c: &Config| c.rpc.as_ref()?.max_logs,
+            "--rpc-max-logs=[NUM]",
+            "Reject eth_getLogs and log filter queries returning more than NUM logs.",
+
+            ARG arg_rpc_call_timeout: (Option<u64>) = None, or |c: &Config| c.rpc.as_ref()?.call_timeout,
+            "--rpc-call-timeout=[MS]",
+            "Give up eth_call, eth_estimateGas and eth_callBundle executions taking longer than MS milliseconds.",
+
+            ARG arg_rpc_max_concurrent_calls: (usize) = 8usize, or |c: &Config| c.rpc.as_ref()?.max_concurrent_calls.clone(),
+            "--rpc-max-concurrent-calls=[NUM]",
+            "Reject eth_call, eth_estimateGas and eth_callBundle while NUM of them are executing, including those which timed out but are still running.",
+
+            ARG arg_rpc_max_bundle_transactions: (usize) = 100usize, or |c: &Config| c.rpc.as_ref()?.max_bundle_transactions.clone(),
+            "--rpc-max-bundle-transactions=[NUM]",
+            "Reject eth_callBundle and eth_callMany requests simulating more than NUM transactions.",
+
+            ARG arg_rpc_slow_call_threshold: (Option<u64>) = None, or |c: &Config| c.rpc.as_ref()?.slow_call_threshold,
+            "--rpc-slow-call-threshold=[MS]",
+            "Log RPC and wallet API calls taking MS milliseconds or more, with a digest of their params. The most recent ones are also returned by rpc_stats.",
+
         ["Http Options"]
             FLAG flag_no_http: (bool) = false, or |c: &Config| c.http.as_ref()?.disable.clone(),
             "--no-http",
@@ -255,6 +327,14 @@ usage! {
             "--http-server-threads=[NUM]",
             "Enables multiple threads handling incoming connections for HTTP JSON-RPC server.",
 
+            ARG arg_http_auth_file: (Option<String>) = None, or |c: &Config| c.http.as_ref()?.auth_file.clone(),
+            "--http-auth-file=[PATH]",
+            "Require requests to the HTTP interface to carry an API key or JSON web token from PATH as `Authorization: Bearer <token>`. Each line of PATH is `key <API key> <APIS>` or `jwt <hex HS256 secret> <APIS>`, where APIS are the APIs the secret gives access to, in the format of --http-apis. JSON web tokens must carry an exp claim.",
+
+            FLAG flag_http_trust_proxy_headers: (bool) = false, or |c: &Config| c.http.as_ref()?.trust_proxy_headers.clone(),
+            "--http-trust-proxy-headers",
+            "Tell HTTP clients apart by the address a reverse proxy reports in the X-Real-IP or X-Forwarded-For header, for --rpc-max-requests-per-second. Only enable behind a proxy which sets these headers.",
+
         ["WebSockets Options"]
             FLAG flag_no_ws: (bool) = false, or |c: &Config| c.websockets.as_ref()?.disable.clone(),
             "--no-ws",
@@ -284,6 +364,10 @@ usage! {
             "--ws-max-connections=[CONN]",
             "Maximum number of allowed concurrent WebSockets JSON-RPC connections.",
 
+            ARG arg_ws_auth_file: (Option<String>) = None, or |c: &Config| c.websockets.as_ref()?.auth_file.clone(),
+            "--ws-auth-file=[PATH]",
+            "Require requests to the WebSockets interface to carry an API key or JSON web token from PATH, sent as `Authorization: Bearer <token>` when opening the connection, or as the WebSockets subprotocol by browsers. See --http-auth-file for the format of PATH.",
+
         ["IPC Options"]
             FLAG flag_no_ipc: (bool) = false, or |c: &Config| c.ipc.as_ref()?.disable.clone(),
             "--no-ipc",
@@ -297,6 +381,10 @@ usage! {
             "--ipc-apis=[APIS]...",
             "Specify custom API set available via JSON-RPC over IPC. Possible name are web3, eth, stratum, net, personal, rpc, pubsub.",
 
+            ARG arg_ipc_auth_file: (Option<String>) = None, or |c: &Config| c.ipc.as_ref()?.auth_file.clone(),
+            "--ipc-auth-file=[PATH]",
+            "Require IPC connections to authenticate with an API key or JSON web token from PATH, by calling rpc_authenticate with it before any other request. See --http-auth-file for the format of PATH.",
+
         ["Wallet Options"]
             FLAG flag_enable_wallet: (bool) = false, or |c: &Config| c.wallet.as_ref()?.disable.clone().map(|a| !a),
             "--enable-wallet",
@@ -436,11 +524,23 @@ usage! {
             "--extra-data=[STRING]",
             "Specify a custom extra-data for authored blocks, no more than 32 characters.",
 
+            ARG arg_staker: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.staker.clone(),
+            "--staker=[ADDRESS]",
+            "Seal blocks with the stake of ADDRESS once the unity fork is active. The account has to be unlocked.",
+
         ["Database Options"]
             FLAG flag_no_persistent_txqueue: (bool) = false, or |c: &Config| c.db.as_ref()?.no_persistent_txqueue,
             "--no-persistent-txqueue",
             "Don't save pending local transactions to disk to be restored whenever the node restarts.",
 
+            FLAG flag_tx_queue_journal: (bool) = false, or |c: &Config| c.db.as_ref()?.tx_queue_journal,
+            "--tx-queue-journal",
+            "Journal every transaction entering or leaving the transaction queue, not only local ones, and restore the whole queue whenever the node restarts.",
+
+            FLAG flag_tx_address_index: (bool) = false, or |c: &Config| c.db.as_ref()?.tx_address_index,
+            "--tx-address-index",
+            "Index the transactions sent from, sent to or creating every address, to serve eth_getTransactionsByAddress. Blocks imported before enabling it are indexed in the background.",
+
             FLAG flag_disable_wal: (bool) = false, or |c: &Config| c.db.as_ref()?.disable_wal.clone(),
             "--disable-wal",
             "Disables DB WAL, which gives a significant speed up but means an unclean exit is unrecoverable.",
@@ -554,17 +654,35 @@ struct Account {
 #[serde(deny_unknown_fields)]
 struct Network {
     sync_from_boot_nodes_only: Option<bool>,
+    fast_sync: Option<bool>,
+    light: Option<bool>,
+    discovery: Option<bool>,
     max_peers: Option<u32>,
     net_id: Option<u32>,
     local_node: Option<String>,
     boot_nodes: Option<Vec<String>>,
     ip_black_list: Option<Vec<String>>,
+    max_frame_size: Option<u32>,
+    max_request_frame_size: Option<u32>,
+    max_headers_per_request: Option<u32>,
+    max_bodies_per_request: Option<u32>,
+    request_rate: Option<u32>,
+    request_burst: Option<u32>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
 #[serde(deny_unknown_fields)]
 struct Rpc {
     processing_threads: Option<usize>,
+    max_payload: Option<usize>,
+    max_batch_size: Option<usize>,
+    max_requests_per_second: Option<usize>,
+    max_logs_block_range: Option<u64>,
+    max_logs: Option<usize>,
+    call_timeout: Option<u64>,
+    max_concurrent_calls: Option<usize>,
+    max_bundle_transactions: Option<usize>,
+    slow_call_threshold: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -577,6 +695,8 @@ struct Http {
     apis: Option<Vec<String>>,
     hosts: Option<Vec<String>>,
     server_threads: Option<usize>,
+    auth_file: Option<String>,
+    trust_proxy_headers: Option<bool>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -589,6 +709,7 @@ struct Ws {
     origins: Option<Vec<String>>,
     hosts: Option<Vec<String>>,
     max_connections: Option<usize>,
+    auth_file: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -597,6 +718,7 @@ struct Ipc {
     disable: Option<bool>,
     path: Option<String>,
     apis: Option<Vec<String>>,
+    auth_file: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -626,6 +748,7 @@ struct Mining {
     gas_floor_target: Option<String>,
     gas_cap: Option<String>,
     extra_data: Option<String>,
+    staker: Option<String>,
     tx_queue_mem_limit: Option<u32>,
     tx_queue_strategy: Option<String>,
     tx_queue_ban_count: Option<u16>,
@@ -652,6 +775,8 @@ struct Stratum {
 #[serde(deny_unknown_fields)]
 struct Database {
     no_persistent_txqueue: Option<bool>,
+    tx_queue_journal: Option<bool>,
+    tx_address_index: Option<bool>,
     pruning: Option<String>,
     pruning_history: Option<u64>,
     pruning_memory: Option<usize>,
@@ -862,11 +987,29 @@ mod tests {
                 arg_local_node: "p2p://12345678-9abc-def0-1234-56789abcdef0@2.3.3.3:3333".into(),
                 arg_net_id: 128u32,
                 flag_sync_from_boot_nodes_only: true,
+                flag_fast_sync: true,
+                flag_light: false,
+                flag_discovery: false,
                 arg_ip_black_list: vec!["ip1".into(), "ip2".into()],
+                arg_max_frame_size: 1048576u32,
+                arg_max_request_frame_size: 65536u32,
+                arg_max_headers_per_request: 192u32,
+                arg_max_bodies_per_request: 128u32,
+                arg_request_rate: 50u32,
+                arg_request_burst: 200u32,
 
                 // -- API and Console Options
                 // RPC
                 arg_rpc_processing_threads: Some(3usize),
+                arg_rpc_max_payload: 10usize,
+                arg_rpc_max_batch_size: Some(100usize),
+                arg_rpc_max_requests_per_second: Some(50usize),
+                arg_rpc_max_logs_block_range: Some(10000u64),
+                arg_rpc_max_logs: Some(5000usize),
+                arg_rpc_call_timeout: Some(5000u64),
+                arg_rpc_max_concurrent_calls: 16usize,
+                arg_rpc_max_bundle_transactions: 50usize,
+                arg_rpc_slow_call_threshold: Some(1000u64),
 
                 // Http
                 flag_no_http: true,
@@ -876,6 +1019,8 @@ mod tests {
                 arg_http_apis: vec!["api1".into(), "api2".into()],
                 arg_http_hosts: vec!["host1".into(), "host2".into()],
                 arg_http_server_threads: Some(5usize),
+                arg_http_auth_file: Some("http_auth".into()),
+                flag_http_trust_proxy_headers: true,
 
                 // WS
                 flag_no_ws: true,
@@ -885,11 +1030,13 @@ mod tests {
                 arg_ws_origins: vec!["origin1".into(), "origin2".into()],
                 arg_ws_hosts: vec!["host1".into(), "host2".into()],
                 arg_ws_max_connections: 12usize,
+                arg_ws_auth_file: Some("ws_auth".into()),
 
                 // IPC
                 flag_no_ipc: true,
                 arg_ipc_path: "$HOME/.aion/jsonrpc.ipc".into(),
                 arg_ipc_apis: vec!["api1".into(), "api2".into()],
+                arg_ipc_auth_file: Some("ipc_auth".into()),
 
                 // Wallet
                 arg_wallet_interface: "local".into(),
@@ -914,6 +1061,7 @@ mod tests {
                 arg_gas_floor_target: "4700000".into(),
                 arg_gas_cap: "6283184".into(),
                 arg_extra_data: Some("Aion".into()),
+                arg_staker: Some("0xdeadbeefcafe0000000000000000000000000002".into()),
                 arg_tx_queue_mem_limit: 2u32,
                 arg_tx_queue_strategy: "gas_factor".into(),
                 arg_tx_queue_ban_count: 1u16,
@@ -933,6 +1081,8 @@ mod tests {
 
                 // -- Database Options
                 flag_no_persistent_txqueue: true,
+                flag_tx_queue_journal: true,
+                flag_tx_address_index: true,
                 arg_pruning: "auto".into(),
                 arg_pruning_history: 64u64,
                 arg_pruning_memory: 500usize,
@@ -1014,7 +1164,16 @@ mod tests {
                     local_node: None,
                     boot_nodes: None,
                     sync_from_boot_nodes_only: None,
+                    fast_sync: None,
+                    light: None,
+                    discovery: None,
                     ip_black_list: None,
+                    max_frame_size: None,
+                    max_request_frame_size: None,
+                    max_headers_per_request: None,
+                    max_bodies_per_request: None,
+                    request_rate: None,
+                    request_burst: None,
                 }),
                 websockets: Some(Ws {
                     disable: Some(true),
@@ -1024,6 +1183,7 @@ mod tests {
                     origins: Some(vec!["none".into()]),
                     hosts: None,
                     max_connections: None,
+                    auth_file: None,
                 }),
                 rpc: None,
                 http: Some(Http {
@@ -1034,11 +1194,14 @@ mod tests {
                     apis: None,
                     hosts: None,
                     server_threads: None,
+                    auth_file: None,
+                    trust_proxy_headers: None,
                 }),
                 ipc: Some(Ipc {
                     disable: None,
                     path: None,
                     apis: Some(vec!["rpc".into(), "eth".into()]),
+                    auth_file: None,
                 }),
                 wallet: Some(WalletApi {
                     disable: None,
@@ -1067,6 +1230,7 @@ mod tests {
                     tx_gas_limit: None,
                     tx_time_limit: None,
                     extra_data: None,
+                    staker: None,
                     remove_solved: None,
                     infinite_pending_block: None,
                     blk_price_window: None,
@@ -1076,6 +1240,8 @@ mod tests {
                 }),
                 db: Some(Database {
                     no_persistent_txqueue: None,
+                    tx_queue_journal: None,
+                    tx_address_index: None,
                     pruning: Some("fast".into()),
                     pruning_history: Some(64),
                     pruning_memory: None,
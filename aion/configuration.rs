@@ -32,6 +32,7 @@ use acore::verification::queue::VerifierSettings;
 use pb::WalletApiConfiguration;
 use rpc::{IpcConfiguration, HttpConfiguration, WsConfiguration,};
 use aion_rpc::dispatch::DynamicGasPrice;
+use aion_rpc::{CallLimits, CallSlots, RequestLimits};
 use cache::CacheConfig;
 use helpers::{to_block_id, to_u256, to_pending_set, aion_ipc_path,parse_log_target,
 to_addresses, to_address, to_queue_strategy,validate_log_level};
@@ -239,6 +240,13 @@ impl Configuration {
                 check_seal: !self.args.flag_no_seal_check,
                 verifier_settings: verifier_settings,
                 no_persistent_txqueue: self.args.flag_no_persistent_txqueue,
+                tx_queue_journal: self.args.flag_tx_queue_journal,
+                tx_address_index: self.args.flag_tx_address_index,
+                call_limits: self.rpc_call_limits(),
+                rpc_slow_call_threshold: self
+                    .args
+                    .arg_rpc_slow_call_threshold
+                    .map(Duration::from_millis),
             };
             Cmd::Run(run_cmd)
         };
@@ -255,6 +263,7 @@ impl Configuration {
             extra_data: self.extra_data()?,
             gas_floor_target: to_u256(&self.args.arg_gas_floor_target)?,
             gas_ceil_target: to_u256(&self.args.arg_gas_cap)?,
+            staker: self.staker()?,
         };
 
         Ok(extras)
@@ -262,6 +271,13 @@ impl Configuration {
 
     fn author(&self) -> Result<Address, String> { to_address(self.args.arg_author.clone()) }
 
+    fn staker(&self) -> Result<Option<Address>, String> {
+        match self.args.arg_staker {
+            Some(ref staker) => to_address(Some(staker.clone())).map(Some),
+            None => Ok(None),
+        }
+    }
+
     fn format(&self) -> Result<Option<DataFormat>, String> {
         match self
             .args
@@ -420,8 +436,17 @@ impl Configuration {
         ret.local_node = self.args.arg_local_node.clone();
         ret.boot_nodes = self.args.arg_boot_nodes.clone();
         ret.sync_from_boot_nodes_only = self.args.flag_sync_from_boot_nodes_only;
+        ret.fast_sync = self.args.flag_fast_sync;
+        ret.light = self.args.flag_light;
+        ret.discovery = self.args.flag_discovery;
         ret.net_id = self.args.arg_net_id.clone();
         ret.ip_black_list = self.args.arg_ip_black_list.clone();
+        ret.max_frame_size = self.args.arg_max_frame_size;
+        ret.max_request_frame_size = self.args.arg_max_request_frame_size;
+        ret.max_headers_per_request = self.args.arg_max_headers_per_request;
+        ret.max_bodies_per_request = self.args.arg_max_bodies_per_request;
+        ret.request_rate = self.args.arg_request_rate;
+        ret.request_burst = self.args.arg_request_burst;
         Ok(ret)
     }
 
@@ -475,11 +500,35 @@ impl Configuration {
         Self::parse_hosts(&self.args.arg_ws_origins.join(","))
     }
 
+    fn rpc_auth_file(&self, path: &Option<String>) -> Option<String> {
+        path.as_ref()
+            .map(|path| replace_home(&self.directories().base, path))
+    }
+
+    fn rpc_request_limits(&self) -> RequestLimits {
+        RequestLimits {
+            max_batch_size: self.args.arg_rpc_max_batch_size,
+            max_requests_per_second: self.args.arg_rpc_max_requests_per_second,
+        }
+    }
+
+    fn rpc_call_limits(&self) -> CallLimits {
+        CallLimits {
+            max_logs_block_range: self.args.arg_rpc_max_logs_block_range,
+            max_logs: self.args.arg_rpc_max_logs,
+            call_timeout: self.args.arg_rpc_call_timeout.map(Duration::from_millis),
+            call_slots: CallSlots::new(self.args.arg_rpc_max_concurrent_calls),
+            max_bundle_transactions: Some(self.args.arg_rpc_max_bundle_transactions),
+        }
+    }
+
     fn ipc_config(&self) -> Result<IpcConfiguration, String> {
         let conf = IpcConfiguration {
             enabled: !self.args.flag_no_ipc,
             socket_addr: self.ipc_path(),
             apis: self.args.arg_ipc_apis.join(",").parse()?,
+            auth_file: self.rpc_auth_file(&self.args.arg_ipc_auth_file),
+            limits: self.rpc_request_limits(),
         };
 
         Ok(conf)
@@ -513,6 +562,10 @@ impl Configuration {
                 Some(threads) if threads > 0 => threads,
                 _ => 4,
             },
+            auth_file: self.rpc_auth_file(&self.args.arg_http_auth_file),
+            max_payload: self.args.arg_rpc_max_payload,
+            limits: self.rpc_request_limits(),
+            trust_proxy_headers: self.args.flag_http_trust_proxy_headers,
         };
 
         Ok(conf)
@@ -527,6 +580,9 @@ impl Configuration {
             hosts: self.ws_hosts(),
             origins: self.ws_origins(),
             max_connections: self.args.arg_ws_max_connections,
+            auth_file: self.rpc_auth_file(&self.args.arg_ws_auth_file),
+            max_payload: self.args.arg_rpc_max_payload,
+            limits: self.rpc_request_limits(),
         };
 
         Ok(conf)
@@ -829,6 +885,14 @@ mod tests {
             check_seal: true,
             verifier_settings: Default::default(),
             no_persistent_txqueue: false,
+            tx_queue_journal: false,
+            tx_address_index: false,
+            call_limits: CallLimits {
+                call_slots: CallSlots::new(8),
+                max_bundle_transactions: Some(100),
+                ..Default::default()
+            },
+            rpc_slow_call_threshold: None,
         };
         assert_eq!(conf.into_command().unwrap().cmd, Cmd::Run(expected));
     }
@@ -874,6 +938,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_parse_rpc_limits() {
+        // given
+
+        // when
+        let conf0 = parse(&["aion"]);
+        let conf1 = parse(&[
+            "aion",
+            "--rpc-max-batch-size",
+            "10",
+            "--rpc-max-requests-per-second",
+            "20",
+            "--rpc-max-logs-block-range",
+            "1000",
+            "--rpc-call-timeout",
+            "500",
+        ]);
+
+        // then
+        assert_eq!(conf0.rpc_request_limits(), RequestLimits::default());
+        assert_eq!(
+            conf0.rpc_call_limits(),
+            CallLimits {
+                call_slots: CallSlots::new(8),
+                max_bundle_transactions: Some(100),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            conf1.rpc_request_limits(),
+            RequestLimits {
+                max_batch_size: Some(10),
+                max_requests_per_second: Some(20),
+            }
+        );
+        assert_eq!(
+            conf1.rpc_call_limits(),
+            CallLimits {
+                max_logs_block_range: Some(1000),
+                max_logs: None,
+                call_timeout: Some(Duration::from_millis(500)),
+                call_slots: CallSlots::new(8),
+                max_bundle_transactions: Some(100),
+            }
+        );
+    }
+
     #[test]
     fn should_use_correct_cache_path_if_base_is_set() {
         let std = parse(&["aion"]);
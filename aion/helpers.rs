@@ -116,7 +116,16 @@ pub fn default_network_config() -> ::sync::p2p::NetworkConfig {
         local_node: "p2p://00000000-0000-0000-0000-000000000000@0.0.0.0:30303".to_string(),
         net_id: 256,
         sync_from_boot_nodes_only: false,
+        fast_sync: false,
+        light: false,
+        discovery: false,
         ip_black_list: Vec::new(),
+        max_frame_size: 64 * 1024 * 1024,
+        max_request_frame_size: 64 * 1024,
+        max_headers_per_request: 192,
+        max_bodies_per_request: 128,
+        request_rate: 100,
+        request_burst: 200,
     }
 }
 
@@ -63,6 +63,7 @@ extern crate aion_version;
 extern crate path;
 extern crate blake2b;
 extern crate journaldb;
+extern crate db as kvdb;
 extern crate aion_pb_apiserver as pb;
 extern crate tokio;
 #[macro_use]
@@ -22,29 +22,38 @@
 use std::sync::Arc;
 
 use sync::p2p::NetworkConfig;
-use sync::sync::{Sync, SyncConfig, NetworkManager, Params, SyncProvider};
+use sync::sync::{Sync, SyncConfig, NetworkManager, Params, SyncProvider, LightProvider};
 use sync::sync::error::SyncError;
 use acore::client::BlockChainClient;
+use kvdb::KeyValueDB;
 
 pub use acore::client::ChainNotify;
 
-pub type SyncModules = (Arc<SyncProvider>, Arc<NetworkManager>, Arc<ChainNotify>);
+pub type SyncModules = (
+    Arc<SyncProvider>,
+    Arc<NetworkManager>,
+    Arc<ChainNotify>,
+    Arc<LightProvider>,
+);
 
 pub fn sync(
     sync_cfg: SyncConfig,
     net_cfg: NetworkConfig,
     cli: Arc<BlockChainClient>,
+    db: Arc<KeyValueDB>,
 ) -> Result<SyncModules, SyncError>
 {
     let sync = Sync::get_instance(Params {
         config: sync_cfg,
         client: cli,
         network_config: net_cfg,
+        db: db,
     });
 
     Ok((
         sync.clone() as Arc<SyncProvider>,
         sync.clone() as Arc<NetworkManager>,
         sync.clone() as Arc<ChainNotify>,
+        sync.clone() as Arc<LightProvider>,
     ))
 }
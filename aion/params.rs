@@ -165,6 +165,7 @@ pub struct MinerExtras {
     pub extra_data: Vec<u8>,
     pub gas_floor_target: U256,
     pub gas_ceil_target: U256,
+    pub staker: Option<Address>,
 }
 
 impl Default for MinerExtras {
@@ -174,6 +175,7 @@ impl Default for MinerExtras {
             extra_data: "AION".as_bytes().to_vec(),
             gas_floor_target: U256::from(15_000_000),
             gas_ceil_target: U256::from(20_000_000),
+            staker: None,
         }
     }
 }
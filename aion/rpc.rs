@@ -19,6 +19,7 @@
  *
  ******************************************************************************/
 
+use std::fs;
 use std::io;
 use std::sync::Arc;
 use std::path::PathBuf;
@@ -27,8 +28,9 @@ use std::collections::HashSet;
 use helpers::aion_ipc_path;
 use jsonrpc_core::MetaIoHandler;
 use aion_rpc::informant::{RpcStats, Middleware};
-use aion_rpc::{self as rpc, Metadata, DomainsValidation};
+use aion_rpc::{self as rpc, Authorization, Metadata, DomainsValidation, RequestLimits, Secret};
 use rpc_apis::{self, ApiSet};
+use rustc_hex::FromHex;
 use tokio::runtime::TaskExecutor;
 pub use aion_rpc::{IpcServer, HttpServer, WsServer, RequestMiddleware, WsError, WsErrorKind};
 
@@ -42,6 +44,10 @@ pub struct HttpConfiguration {
     pub hosts: Option<Vec<String>>,
     pub server_threads: usize,
     pub processing_threads: usize,
+    pub auth_file: Option<String>,
+    pub max_payload: usize,
+    pub limits: RequestLimits,
+    pub trust_proxy_headers: bool,
 }
 
 impl Default for HttpConfiguration {
@@ -55,6 +61,10 @@ impl Default for HttpConfiguration {
             hosts: Some(vec![]),
             server_threads: 1,
             processing_threads: 4,
+            auth_file: None,
+            max_payload: 5,
+            limits: RequestLimits::default(),
+            trust_proxy_headers: false,
         }
     }
 }
@@ -64,6 +74,8 @@ pub struct IpcConfiguration {
     pub enabled: bool,
     pub socket_addr: String,
     pub apis: ApiSet,
+    pub auth_file: Option<String>,
+    pub limits: RequestLimits,
 }
 
 impl Default for IpcConfiguration {
@@ -77,6 +89,8 @@ impl Default for IpcConfiguration {
                 aion_ipc_path(&data_dir, "$BASE/jsonrpc.ipc")
             },
             apis: ApiSet::IpcContext,
+            auth_file: None,
+            limits: RequestLimits::default(),
         }
     }
 }
@@ -90,6 +104,9 @@ pub struct WsConfiguration {
     pub origins: Option<Vec<String>>,
     pub hosts: Option<Vec<String>>,
     pub max_connections: usize,
+    pub auth_file: Option<String>,
+    pub max_payload: usize,
+    pub limits: RequestLimits,
 }
 
 impl Default for WsConfiguration {
@@ -102,6 +119,9 @@ impl Default for WsConfiguration {
             origins: Some(Vec::new()),
             hosts: Some(Vec::new()),
             max_connections: 100,
+            auth_file: None,
+            max_payload: 5,
+            limits: RequestLimits::default(),
         }
     }
 }
@@ -126,7 +146,8 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
         .parse()
         .map_err(|_| format!("Invalid WebSockets listen host/port given: {}", url))?;
 
-    let handler = setup_apis(conf.apis, deps);
+    let authorization = load_authorization(&conf.auth_file)?;
+    let handler = setup_apis(conf.apis, deps, authorization, conf.limits);
 
     let allowed_origins = into_domains(with_domain(conf.origins));
     let allowed_hosts = into_domains(with_domain(conf.hosts));
@@ -138,6 +159,7 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
         rpc::RpcExtractor,
         executor,
         conf.max_connections,
+        conf.max_payload,
     );
 
     match start_result {
@@ -170,7 +192,8 @@ pub fn new_http<D: rpc_apis::Dependencies>(
     let addr = url
         .parse()
         .map_err(|_| format!("Invalid {} listen host/port given: {}", id, url))?;
-    let handler = setup_apis(conf.apis, deps);
+    let authorization = load_authorization(&conf.auth_file)?;
+    let handler = setup_apis(conf.apis, deps, authorization, conf.limits);
 
     let cors_domains = into_domains(conf.cors);
     let allowed_hosts = into_domains(with_domain(conf.hosts));
@@ -180,8 +203,11 @@ pub fn new_http<D: rpc_apis::Dependencies>(
         cors_domains,
         allowed_hosts,
         handler,
-        rpc::RpcExtractor,
+        rpc::HttpExtractor {
+            trust_proxy_headers: conf.trust_proxy_headers,
+        },
         conf.server_threads,
+        conf.max_payload,
         executor,
     );
 
@@ -209,7 +235,8 @@ pub fn new_ipc<D: rpc_apis::Dependencies>(
         return Ok(None);
     }
 
-    let handler = setup_apis(conf.apis, dependencies);
+    let authorization = load_authorization(&conf.auth_file)?;
+    let handler = setup_apis(conf.apis, dependencies, authorization, conf.limits);
     let path = PathBuf::from(&conf.socket_addr);
     // Make sure socket file can be created on unix-like OS.
     // Windows pipe paths are not on the FS.
@@ -252,9 +279,65 @@ fn with_domain(items: Option<Vec<String>>) -> Option<Vec<String>> {
     })
 }
 
+/// Load the secrets an interface requires requests to be sent with. Each line of the file holds
+/// `key <API key> <APIS>` or `jwt <hex HS256 secret> <APIS>`, APIS being the APIs the secret
+/// gives access to, in the format of `--http-apis`. Empty lines and lines starting with `#` are
+/// ignored.
+fn load_authorization(path: &Option<String>) -> Result<Option<Arc<Authorization>>, String> {
+    let path = match *path {
+        Some(ref path) => path,
+        None => return Ok(None),
+    };
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read RPC auth file {}: {}", path, e))?;
+    let authorization = parse_authorization(&content)
+        .map_err(|e| format!("Invalid RPC auth file {}: {}", path, e))?;
+    Ok(Some(Arc::new(authorization)))
+}
+
+fn parse_authorization(content: &str) -> Result<Authorization, String> {
+    let mut authorization = Authorization::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts.len() != 3 {
+            return Err(format!(
+                "line {}: expected `<key|jwt> <secret> <APIS>`",
+                number + 1
+            ));
+        }
+        let secret = match parts[0] {
+            "key" => Secret::ApiKey(parts[1].into()),
+            "jwt" => {
+                let secret = parts[1].trim_left_matches("0x");
+                Secret::Jwt(
+                    secret
+                        .from_hex()
+                        .map_err(|e| format!("line {}: invalid secret: {}", number + 1, e))?,
+                )
+            }
+            kind => return Err(format!("line {}: unknown kind {}", number + 1, kind)),
+        };
+        let apis = parts[2]
+            .parse::<ApiSet>()
+            .map_err(|e| format!("line {}: {}", number + 1, e))?;
+        let modules = rpc_apis::to_modules(&apis.list_apis())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        authorization.add(secret, modules);
+    }
+    Ok(authorization)
+}
+
 fn setup_apis<D>(
     apis: ApiSet,
     deps: &Dependencies<D>,
+    authorization: Option<Arc<Authorization>>,
+    limits: RequestLimits,
 ) -> MetaIoHandler<Metadata, Middleware<D::Notifier>>
 where
     D: rpc_apis::Dependencies,
@@ -262,9 +345,53 @@ where
     let mut handler = MetaIoHandler::with_middleware(Middleware::new(
         deps.stats.clone(),
         deps.apis.activity_notifier(),
+        authorization,
+        limits,
     ));
     let apis = apis.list_apis();
     deps.apis.extend_with_set(&mut handler, &apis);
 
     handler
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_authorization;
+    use aion_rpc::{Authorization, Secret};
+
+    #[test]
+    fn should_parse_authorization() {
+        let content = r#"
+            # read-only dashboard
+            key dashboard web3,eth,net
+            jwt 0x0102 all,-personal
+        "#;
+        let mut expected = Authorization::new();
+        expected.add(
+            Secret::ApiKey("dashboard".into()),
+            vec!["web3".into(), "eth".into(), "net".into()]
+                .into_iter()
+                .collect(),
+        );
+        expected.add(
+            Secret::Jwt(vec![1, 2]),
+            vec![
+                "web3".into(),
+                "net".into(),
+                "eth".into(),
+                "stratum".into(),
+                "rpc".into(),
+                "pubsub".into(),
+                "ping".into(),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(parse_authorization(content), Ok(expected));
+
+        assert!(parse_authorization("key dashboard").is_err());
+        assert!(parse_authorization("token dashboard eth").is_err());
+        assert!(parse_authorization("jwt zz eth").is_err());
+        assert!(parse_authorization("key dashboard eth,foo").is_err());
+    }
+}
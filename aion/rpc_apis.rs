@@ -24,15 +24,15 @@ use std::collections::{BTreeMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 
-use sync::sync::{SyncProvider};
+use sync::sync::{SyncProvider, LightProvider};
 use acore::account_provider::AccountProvider;
 use acore::client::Client;
 use acore::miner::Miner;
 use jsonrpc_core::{self as core, MetaIoHandler};
 use acore::miner::external::ExternalMiner;
 use aion_rpc::dispatch::{FullDispatcher,DynamicGasPrice};
-use aion_rpc::informant::{ActivityNotifier, ClientNotifier};
-use aion_rpc::{Metadata};
+use aion_rpc::informant::{ActivityNotifier, ClientNotifier, RpcStats};
+use aion_rpc::{CallLimits, Metadata};
 use parking_lot::Mutex;
 use tokio::runtime::TaskExecutor;
 
@@ -123,7 +123,7 @@ impl FromStr for ApiSet {
     }
 }
 
-fn to_modules(apis: &HashSet<Api>) -> BTreeMap<String, String> {
+pub fn to_modules(apis: &HashSet<Api>) -> BTreeMap<String, String> {
     let mut modules = BTreeMap::new();
     for api in apis {
         let (name, version) = match *api {
@@ -157,10 +157,13 @@ pub trait Dependencies {
 pub struct FullDependencies {
     pub client: Arc<Client>,
     pub sync: Arc<SyncProvider>,
+    pub light: Option<Arc<LightProvider>>,
     pub account_store: Option<Arc<AccountProvider>>,
     pub miner: Arc<Miner>,
     pub external_miner: Arc<ExternalMiner>,
     pub dynamic_gas_price: Option<DynamicGasPrice>,
+    pub call_limits: CallLimits,
+    pub rpc_stats: Arc<RpcStats>,
     pub executor: TaskExecutor,
 }
 
@@ -217,12 +220,17 @@ impl FullDependencies {
                         &self.miner,
                         &self.external_miner,
                         self.dynamic_gas_price.clone(),
+                        self.call_limits.clone(),
+                        self.light.clone(),
                     );
-                    handler.extend_with(client.to_delegate());
+                    handler.extend_with(client.to_delegate_with_state_override());
 
                     if !for_generic_pubsub {
-                        let filter_client =
-                            EthFilterClient::new(self.client.clone(), self.miner.clone());
+                        let filter_client = EthFilterClient::new(
+                            self.client.clone(),
+                            self.miner.clone(),
+                            self.call_limits.clone(),
+                        );
                         handler.extend_with(filter_client.to_delegate());
 
                         add_signing_methods!(EthSigning, handler, self, nonces.clone());
@@ -239,8 +247,11 @@ impl FullDependencies {
                 }
                 Api::EthPubSub => {
                     if !for_generic_pubsub {
-                        let client =
-                            EthPubSubClient::new(self.client.clone(), self.executor.clone());
+                        let client = EthPubSubClient::new(
+                            self.client.clone(),
+                            self.sync.clone(),
+                            self.executor.clone(),
+                        );
                         let h = client.handler();
                         self.miner
                             .add_transactions_listener(Box::new(move |hashes| {
@@ -266,7 +277,9 @@ impl FullDependencies {
                 }
                 Api::Rpc => {
                     let modules = to_modules(&apis);
-                    handler.extend_with(RpcClient::new(modules).to_delegate());
+                    handler.extend_with(
+                        RpcClient::new(modules, self.rpc_stats.clone()).to_delegate(),
+                    );
                 }
                 Api::Ping => {
                     handler.extend_with(PingClient::new().to_delegate());
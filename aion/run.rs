@@ -31,7 +31,7 @@ use acore::miner::{Stratum, StratumOptions};
 use acore::service::ClientService;
 use acore::transaction::local_transactions::TxIoMessage;
 use acore::verification::queue::VerifierSettings;
-use aion_rpc::{dispatch::DynamicGasPrice, impls::EthClient, informant};
+use aion_rpc::{dispatch::DynamicGasPrice, impls::EthClient, informant, CallLimits};
 use aion_version::version;
 use ansi_term::Colour;
 use cache::CacheConfig;
@@ -49,7 +49,7 @@ use parking_lot::{Condvar, Mutex};
 use pb::{new_pb, WalletApiConfiguration};
 use rpc;
 use rpc_apis;
-use sync::p2p::{NetworkConfig, P2pMgr};
+use sync::p2p::NetworkConfig;
 use sync::sync::SyncConfig;
 use tokio;
 use tokio::prelude::*;
@@ -86,6 +86,10 @@ pub struct RunCmd {
     pub check_seal: bool,
     pub verifier_settings: VerifierSettings,
     pub no_persistent_txqueue: bool,
+    pub tx_queue_journal: bool,
+    pub tx_address_index: bool,
+    pub call_limits: CallLimits,
+    pub rpc_slow_call_threshold: Option<Duration>,
 }
 
 // node info fetcher for the local store.
@@ -108,6 +112,19 @@ impl ::local_store::NodeInfo for FullNodeInfo {
             .filter(|tx| local_txs.contains_key(&tx.hash()))
             .collect()
     }
+
+    fn queued_transactions(&self) -> Vec<::acore::transaction::PendingTransaction> {
+        let miner = match self.miner.as_ref() {
+            Some(m) => m,
+            None => return Vec::new(),
+        };
+
+        miner
+            .pending_transactions()
+            .into_iter()
+            .chain(miner.future_transactions())
+            .collect()
+    }
 }
 
 pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
@@ -179,6 +196,7 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
     miner.set_gas_floor_target(cmd.miner_extras.gas_floor_target);
     miner.set_gas_ceil_target(cmd.miner_extras.gas_ceil_target);
     miner.set_extra_data(cmd.miner_extras.extra_data);
+    miner.set_staker(cmd.miner_extras.staker);
     // create client config
     let mut client_config = to_client_config(
         &cmd.cache_config,
@@ -194,9 +212,11 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
     );
 
     client_config.queue.verifier_settings = cmd.verifier_settings;
+    client_config.tx_address_index = cmd.tx_address_index;
 
     // set up bootnodes
     let net_conf = cmd.net_conf;
+    let light = net_conf.light;
 
     // create client service.
     let service = ClientService::start(
@@ -286,6 +306,52 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
         .register_io_handler(store)
         .map_err(|_| "Unable to register local store handler".to_owned())?;
 
+    // initialize the journal of the whole transaction queue.
+    let queue_journal = if cmd.tx_queue_journal {
+        let node_info = FullNodeInfo {
+            miner: Some(miner.clone()),
+        };
+
+        let journal =
+            ::local_store::journal::create(service.db(), ::acore::db::COL_TX_JOURNAL, node_info);
+
+        // re-queue journaled transactions, re-validating them against the current state.
+        match journal.transactions() {
+            Ok(queued) => {
+                let len = queued.len();
+                if len > 0 {
+                    info!(target: "run","Importing the journaled transaction queue ...");
+                    let imported = miner
+                        .import_external_transactions(
+                            &*client,
+                            queued
+                                .into_iter()
+                                .map(|pending_tx| pending_tx.transaction.into())
+                                .collect(),
+                        )
+                        .into_iter()
+                        .filter(|result| result.is_ok())
+                        .count();
+                    info!(target: "run","Import completed, total = {}, valid = {}", len, imported);
+                }
+            }
+            Err(e) => warn!(target: "run","Error loading transaction journal from disk: {}", e),
+        }
+
+        if let Err(e) = journal.compact() {
+            warn!(target: "run","Error compacting transaction journal: {}", e);
+        }
+
+        miner.add_queue_listener(journal.listener());
+        Some(journal)
+    } else {
+        if let Err(e) = ::local_store::journal::clear(&*service.db(), ::acore::db::COL_TX_JOURNAL)
+        {
+            warn!(target: "run","Error clearing transaction journal: {}", e);
+        }
+        None
+    };
+
     // create external miner
     let external_miner = Arc::new(ExternalMiner::default());
 
@@ -298,22 +364,26 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
     // create sync object
     let sync_config = SyncConfig::default();
 
-    let (sync_provider, network_manager, chain_notify) = modules::sync(
+    let (sync_provider, network_manager, chain_notify, light_provider) = modules::sync(
         sync_config,
         net_conf,
         client.clone() as Arc<BlockChainClient>,
+        service.db(),
     )
     .map_err(|e| format!("Sync error: {}", e))?;
 
     service.add_notify(chain_notify.clone());
 
+    // in light mode the local state stays at genesis, so state lookups go to the network
+    let light_provider = if light { Some(light_provider) } else { None };
+
     // spin up rpc eventloop
     let runtime_rpc = tokio::runtime::Builder::new()
         .name_prefix("rpc-eventloop-")
         .build()
         .expect("runtime_rpc init failed");
     // set up dependencies for rpc servers
-    let rpc_stats = Arc::new(informant::RpcStats::default());
+    let rpc_stats = Arc::new(informant::RpcStats::new(cmd.rpc_slow_call_threshold));
     let account_store = Some(account_provider.clone());
     let pb_client = EthClient::new(
         &client.clone(),
@@ -322,18 +392,33 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
         &miner.clone(),
         &external_miner.clone(),
         cmd.dynamic_gas_price.clone(),
+        cmd.call_limits.clone(),
+        light_provider.clone(),
     );
 
-    // start pb server
+    // start pb server, whose synchronous calls can't wait on the network for state
+    let mut wallet_api_conf = cmd.wallet_api_conf;
+    if light && wallet_api_conf.enabled {
+        warn!(target: "run", "Wallet api is not available in light mode");
+        wallet_api_conf.enabled = false;
+    }
     let pb_handles = Arc::new(pb_client);
-    let pb_server = new_pb(cmd.wallet_api_conf, pb_handles, tx_status_service)?;
+    let pb_server = new_pb(
+        wallet_api_conf,
+        pb_handles,
+        tx_status_service,
+        rpc_stats.clone(),
+    )?;
     let deps_for_rpc_apis = Arc::new(rpc_apis::FullDependencies {
         client: client.clone(),
         sync: sync_provider.clone(),
+        light: light_provider,
         account_store,
         miner: miner.clone(),
         external_miner: external_miner.clone(),
         dynamic_gas_price: cmd.dynamic_gas_price.clone(),
+        call_limits: cmd.call_limits.clone(),
+        rpc_stats: rpc_stats.clone(),
         executor: runtime_rpc.executor(),
     });
 
@@ -383,7 +468,7 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
     network_manager.start_network();
 
     if let Some(config_path) = cmd.dirs.config {
-        let local_node = P2pMgr::get_local_node();
+        let local_node = network_manager.local_node();
         fill_back_local_node(
             config_path,
             format!(
@@ -411,7 +496,7 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
     network_manager.stop_network();
 
     // close/drop this stuff as soon as exit detected.
-    drop((sync_provider, network_manager, chain_notify, pb_server));
+    drop((sync_provider, network_manager, chain_notify, pb_server, queue_journal));
 
     thread::sleep(Duration::from_secs(5));
 
@@ -30,6 +30,9 @@ random_string, StoreAccountRef, OpaqueSecretEd25519};
 use keychain::accounts_dir::MemoryDirectory;
 use keychain::ethkey::{Address, Ed25519Secret, generate_keypair, Message, Ed25519Signature};
 use ajson::misc::AccountMeta;
+use bytes::i64_to_bytes;
+use trace_time::to_epoch_micro;
+use transaction::{SignedTransaction, Transaction};
 pub use keychain::{Derivation, IndexDerivation, KeyFile};
 
 /// Type of unlock.
@@ -461,6 +464,27 @@ impl AccountProvider {
         }
     }
 
+    /// Signs the transaction for the given chain. If password is not provided the account must be unlocked.
+    pub fn sign_transaction(
+        &self,
+        address: Address,
+        password: Option<String>,
+        transaction: Transaction,
+        chain_id: Option<u64>,
+    ) -> Result<SignedTransaction, SignError>
+    {
+        let timestamp = i64_to_bytes(to_epoch_micro());
+        let signature = self.sign(address, password, transaction.hash(chain_id, &timestamp))?;
+        Ok(
+            SignedTransaction::new(transaction.with_signature(
+                signature,
+                chain_id,
+                timestamp,
+            ))
+            .expect("signature was produced by the account's secret; qed"),
+        )
+    }
+
     /// Signs given message with supplied token. Returns a token to use in next signing within this session.
     pub fn sign_with_token(
         &self,
@@ -601,6 +625,26 @@ mod tests {
         assert!(ap.sign(kp.address(), None, Default::default()).is_err());
     }
 
+    #[test]
+    fn sign_transaction_for_chain() {
+        use transaction::Transaction;
+
+        let kp = generate_keypair();
+        let ap = AccountProvider::transient_provider();
+        assert!(
+            ap.insert_account_ed25519(kp.secret().clone(), "test")
+                .is_ok()
+        );
+
+        let t = ap
+            .sign_transaction(kp.address(), Some("test".into()), Transaction::default(), Some(32))
+            .unwrap();
+        assert_eq!(t.sender(), kp.address());
+        assert_eq!(t.chain_id(), Some(32));
+        assert!(t.verify_basic(Some(32)).is_ok());
+        assert!(t.verify_basic(Some(256)).is_err());
+    }
+
     #[test]
     fn should_not_return_blacklisted_account() {
         // given
@@ -0,0 +1,284 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Index of the transactions touching each address.
+
+use std::sync::Arc;
+use parking_lot::RwLock;
+use byteorder::{BigEndian, ByteOrder};
+use aion_types::{Address, H256};
+use kvdb::{DBTransaction, KeyValueDB};
+use db::COL_ADDRESS_INDEX;
+use executive::contract_address;
+use header::BlockNumber;
+use transaction::Action;
+use views::BlockView;
+
+/// Key of the lowest block number of the indexed range.
+const LOWEST_KEY: &'static [u8] = b"lowest";
+/// Key of the highest block number of the indexed range.
+const HIGHEST_KEY: &'static [u8] = b"highest";
+/// Length of the key of an entry: address, inverted block number and inverted transaction index.
+/// Inverting the numbers makes the iteration over an address go from the newest transaction.
+const ENTRY_KEY_LEN: usize = 32 + 8 + 4;
+/// Maximum number of transactions `transactions` skips. Each query walks the entries of the
+/// address from its newest transaction, so the offset bounds its cost like the limit does.
+pub const MAX_SKIPPED_TRANSACTIONS: usize = 10_000;
+
+/// Transaction found in the index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedTransaction {
+    /// Number of the block of the transaction.
+    pub block_number: BlockNumber,
+    /// Hash of the block of the transaction.
+    pub block_hash: H256,
+    /// Position of the transaction in its block.
+    pub transaction_index: usize,
+    /// Hash of the transaction.
+    pub transaction_hash: H256,
+}
+
+/// Index of the transactions sent from, sent to or creating each address.
+///
+/// Entries are added for enacted blocks and removed for retracted ones. The canonical blocks
+/// in the range returned by `range` are known to be indexed, the ones outside of it are
+/// backfilled by the client.
+pub struct AddressIndex {
+    db: Arc<KeyValueDB>,
+    range: RwLock<(BlockNumber, BlockNumber)>,
+}
+
+impl AddressIndex {
+    /// Open the index of the given database. A new index starts with an empty range right
+    /// above `best_block`.
+    pub fn new(db: Arc<KeyValueDB>, best_block: BlockNumber) -> Self {
+        let range = match (
+            Self::read_number(&*db, LOWEST_KEY),
+            Self::read_number(&*db, HIGHEST_KEY),
+        ) {
+            (Some(lowest), Some(highest)) => (lowest, highest),
+            _ => (best_block + 1, best_block),
+        };
+        AddressIndex {
+            db: db,
+            range: RwLock::new(range),
+        }
+    }
+
+    /// Lowest and highest number of the indexed blocks. The range is empty when the lowest
+    /// number is greater than the highest.
+    pub fn range(&self) -> (BlockNumber, BlockNumber) { *self.range.read() }
+
+    /// Update the range of the indexed blocks.
+    pub fn set_range(&self, batch: &mut DBTransaction, lowest: BlockNumber, highest: BlockNumber) {
+        let mut value = [0u8; 8];
+        BigEndian::write_u64(&mut value, lowest);
+        batch.put(COL_ADDRESS_INDEX, LOWEST_KEY, &value);
+        BigEndian::write_u64(&mut value, highest);
+        batch.put(COL_ADDRESS_INDEX, HIGHEST_KEY, &value);
+        *self.range.write() = (lowest, highest);
+    }
+
+    /// Add the transactions of a canonical block.
+    pub fn insert(&self, batch: &mut DBTransaction, block: &BlockView) {
+        for (key, value) in Self::entries(block) {
+            batch.put(COL_ADDRESS_INDEX, &key, &value);
+        }
+    }
+
+    /// Remove the transactions of a block retracted from the canonical chain.
+    pub fn remove(&self, batch: &mut DBTransaction, block: &BlockView) {
+        for (key, _) in Self::entries(block) {
+            batch.delete(COL_ADDRESS_INDEX, &key);
+        }
+    }
+
+    /// Transactions of an address, newest first. The first `skip` transactions in canonical
+    /// blocks are skipped and at most `limit` are returned, nothing is returned if `skip` is
+    /// above `MAX_SKIPPED_TRANSACTIONS`. `is_canon` tells whether the block of the given
+    /// number and hash is canonical, entries of other blocks are ignored.
+    pub fn transactions<F>(
+        &self,
+        address: &Address,
+        skip: usize,
+        limit: usize,
+        is_canon: F,
+    ) -> Vec<IndexedTransaction>
+    where
+        F: Fn(BlockNumber, &H256) -> bool,
+    {
+        if skip > MAX_SKIPPED_TRANSACTIONS {
+            return Vec::new();
+        }
+        self.db
+            .iter_from_prefix(COL_ADDRESS_INDEX, &address[..])
+            .take_while(|&(ref key, _)| key.starts_with(&address[..]))
+            .filter(|&(ref key, ref value)| key.len() == ENTRY_KEY_LEN && value.len() == 64)
+            .map(|(key, value)| {
+                IndexedTransaction {
+                    block_number: !BigEndian::read_u64(&key[32..40]),
+                    block_hash: H256::from_slice(&value[0..32]),
+                    transaction_index: !BigEndian::read_u32(&key[40..44]) as usize,
+                    transaction_hash: H256::from_slice(&value[32..64]),
+                }
+            })
+            .filter(|t| is_canon(t.block_number, &t.block_hash))
+            .skip(skip)
+            .take(limit)
+            .collect()
+    }
+
+    fn read_number(db: &KeyValueDB, key: &[u8]) -> Option<BlockNumber> {
+        match db.get(COL_ADDRESS_INDEX, key) {
+            Ok(Some(value)) if value.len() == 8 => Some(BigEndian::read_u64(&value)),
+            _ => None,
+        }
+    }
+
+    /// Keys and values of the entries of a block.
+    fn entries(block: &BlockView) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let header = block.header_view();
+        let block_number = header.number();
+        let block_hash = header.hash();
+
+        let mut entries = Vec::new();
+        for mut t in block.localized_transactions() {
+            let sender = t.sender();
+            let mut addresses = vec![sender];
+            match t.action {
+                Action::Call(ref to) => addresses.push(to.clone()),
+                Action::Create => addresses.push(contract_address(&sender, &t.nonce).0),
+            }
+            addresses.dedup();
+
+            let mut value = Vec::with_capacity(64);
+            value.extend_from_slice(&block_hash);
+            value.extend_from_slice(&t.hash());
+            for address in addresses {
+                let mut key = vec![0u8; ENTRY_KEY_LEN];
+                key[..32].copy_from_slice(&address);
+                BigEndian::write_u64(&mut key[32..40], !block_number);
+                BigEndian::write_u32(&mut key[40..44], !(t.transaction_index as u32));
+                entries.push((key, value.clone()));
+            }
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use aion_types::Address;
+    use kvdb::{DBTransaction, KeyValueDB, MockDbRepository};
+    use blockchain::generator::{BlockBuilder, BlockGenerator};
+    use executive::contract_address;
+    use transaction::{Transaction, Action, DEFAULT_TRANSACTION_TYPE};
+    use views::BlockView;
+    use keychain;
+    use db;
+    use super::{AddressIndex, MAX_SKIPPED_TRANSACTIONS};
+
+    fn new_db() -> Arc<KeyValueDB> {
+        let mut db_configs = Vec::new();
+        for db_name in db::DB_NAMES.to_vec() {
+            db_configs.push(db_name.into());
+        }
+        Arc::new(MockDbRepository::init(db_configs))
+    }
+
+    fn transaction(nonce: u64, action: Action) -> Transaction {
+        Transaction {
+            nonce: nonce.into(),
+            gas_price: 0.into(),
+            gas: 100_000.into(),
+            action: action,
+            value: 0.into(),
+            data: Vec::new(),
+            nonce_bytes: Vec::new(),
+            gas_price_bytes: Vec::new(),
+            gas_bytes: Vec::new(),
+            value_bytes: Vec::new(),
+            transaction_type: DEFAULT_TRANSACTION_TYPE,
+        }
+    }
+
+    #[test]
+    fn indexes_senders_recipients_and_created_contracts() {
+        let keypair = keychain::ethkey::generate_keypair();
+        let sender = keypair.address();
+        let recipient = Address::from(5);
+        let t1 = transaction(0, Action::Call(recipient)).sign(keypair.secret(), None);
+        let t2 = transaction(1, Action::Create).sign(keypair.secret(), None);
+        let created = contract_address(&sender, &1.into()).0;
+        let (hash1, hash2) = (t1.hash(), t2.hash());
+
+        let genesis = BlockBuilder::genesis();
+        let b1 = genesis.add_block_with_transactions(vec![t1, t2]);
+        let b1_hash = b1.last().hash();
+        let encoded = b1.last().encoded();
+
+        let db = new_db();
+        let index = AddressIndex::new(db.clone(), 0);
+        assert_eq!(index.range(), (1, 0));
+
+        let mut batch = DBTransaction::new();
+        index.insert(&mut batch, &BlockView::new(&encoded));
+        index.set_range(&mut batch, 1, 1);
+        db.write(batch).unwrap();
+        assert_eq!(index.range(), (1, 1));
+        assert_eq!(AddressIndex::new(db.clone(), 5).range(), (1, 1));
+
+        let all = |address: &Address| {
+            index
+                .transactions(address, 0, 10, |_, _| true)
+                .into_iter()
+                .map(|t| t.transaction_hash)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(all(&sender), vec![hash2, hash1]);
+        assert_eq!(all(&recipient), vec![hash1]);
+        assert_eq!(all(&created), vec![hash2]);
+        assert_eq!(all(&Address::from(6)), vec![]);
+
+        let paged = index.transactions(&sender, 1, 10, |_, _| true);
+        assert_eq!(paged.len(), 1);
+        assert_eq!(paged[0].block_number, 1);
+        assert_eq!(paged[0].block_hash, b1_hash);
+        assert_eq!(paged[0].transaction_index, 0);
+        assert!(
+            index
+                .transactions(&sender, 0, 10, |_, hash| hash != &b1_hash)
+                .is_empty()
+        );
+        assert!(
+            index
+                .transactions(&sender, MAX_SKIPPED_TRANSACTIONS + 1, 10, |_, _| true)
+                .is_empty()
+        );
+
+        let mut batch = DBTransaction::new();
+        index.remove(&mut batch, &BlockView::new(&encoded));
+        db.write(batch).unwrap();
+        assert_eq!(all(&sender), vec![]);
+    }
+}
@@ -150,6 +150,43 @@ pub trait BlockProvider {
         })
     }
 
+    /// Returns logs of the block with given hash matching given filter, in block order.
+    /// Works for blocks which are not a part of the canon chain.
+    fn block_logs<F>(&self, hash: &H256, matches: F) -> Vec<LocalizedLogEntry>
+    where
+        F: Fn(&LogEntry) -> bool,
+        Self: Sized,
+    {
+        let number = match self.block_number(hash) {
+            Some(number) => number,
+            None => return Vec::new(),
+        };
+        let (receipts, hashes) = match (self.block_receipts(hash), self.block_body(hash)) {
+            (Some(receipts), Some(body)) => (receipts.receipts, body.transaction_hashes()),
+            _ => return Vec::new(),
+        };
+
+        let mut log_index = 0;
+        let mut logs = Vec::new();
+        for (index, (receipt, tx_hash)) in receipts.into_iter().zip(hashes).enumerate() {
+            for (i, log) in receipt.logs().iter().enumerate() {
+                if matches(log) {
+                    logs.push(LocalizedLogEntry {
+                        entry: log.clone(),
+                        block_hash: *hash,
+                        block_number: number,
+                        transaction_hash: tx_hash,
+                        transaction_index: index,
+                        transaction_log_index: i,
+                        log_index: log_index,
+                    });
+                }
+                log_index += 1;
+            }
+        }
+        logs
+    }
+
     /// Returns reference to genesis hash.
     fn genesis_hash(&self) -> H256 {
         self.block_hash(0)
@@ -2435,6 +2472,11 @@ mod tests {
                 log_index: 0,
             }]
         );
+
+        // logs of a single block keep their indexes when some are filtered out
+        let block_logs = bc.block_logs(&b1_hash, |entry| entry.data != vec![2]);
+        assert_eq!(block_logs, vec![logs1[0].clone(), logs1[2].clone()]);
+        assert!(bc.block_logs(&H256::default(), |_| true).is_empty());
     }
 
     #[test]
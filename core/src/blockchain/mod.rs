@@ -22,6 +22,7 @@
 
 //! Blockchain database.
 
+mod address_index;
 mod best_block;
 mod block_info;
 mod blockchain;
@@ -34,6 +35,7 @@ mod update;
 #[cfg(test)]
 pub mod generator;
 
+pub use self::address_index::{AddressIndex, IndexedTransaction, MAX_SKIPPED_TRANSACTIONS};
 pub use self::blockchain::{BlockProvider, BlockChain};
 pub use self::cache::CacheSize;
 pub use self::config::Config;
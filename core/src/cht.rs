@@ -0,0 +1,215 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Canonical hash trie definitions and helper functions.
+//!
+//! Each CHT is a trie mapping block numbers to canonical hashes and total difficulty.
+//! One is generated for every `SIZE` blocks, allowing light clients to discard those
+//! headers in favour of the trie root and to have any of them proved by a full node later.
+
+use aion_types::{H256, U256};
+use bytes::Bytes;
+use kvdb::{HashStore, MemoryDB};
+use rlp::{self, RlpStream, UntrustedRlp};
+use trie::recorder::Recorder;
+use trie::{self, Trie, TrieDB, TrieDBMut, TrieMut};
+use triehash::trie_root;
+
+/// The size of each CHT.
+pub const SIZE: u64 = 2048;
+
+/// A canonical hash trie, built in memory to prove its entries.
+pub struct CHT {
+    db: MemoryDB,
+    root: H256,
+    number: u64,
+}
+
+impl CHT {
+    /// Get the root of this CHT.
+    pub fn root(&self) -> H256 { self.root }
+
+    /// Get the number of this CHT.
+    pub fn number(&self) -> u64 { self.number }
+
+    /// Generate an inclusion proof for the entry at a specific block.
+    /// Returns the trie nodes in order from the root, or `None` if the block is not covered by
+    /// this CHT.
+    pub fn prove(&self, num: u64) -> trie::Result<Option<Vec<Bytes>>> {
+        if block_to_cht_number(num) != Some(self.number) {
+            return Ok(None);
+        }
+
+        let mut recorder = Recorder::new();
+        let trie = TrieDB::new(&self.db, &self.root)?;
+        trie.get_with(&key(num), &mut recorder)?;
+
+        Ok(Some(recorder.drain().into_iter().map(|r| r.data).collect()))
+    }
+}
+
+/// Build an in-memory CHT from a closure which provides the canonical hash and total
+/// difficulty of a block by number. Returns `None` if any block of the CHT is unknown.
+pub fn build<F>(cht_num: u64, mut fetcher: F) -> Option<CHT>
+where F: FnMut(u64) -> Option<(H256, U256)> {
+    let mut db = MemoryDB::new();
+    let mut root = H256::default();
+    {
+        let mut trie = TrieDBMut::new(&mut db, &mut root);
+        for num in start_number(cht_num)..end_number(cht_num) + 1 {
+            let (hash, total_difficulty) = fetcher(num)?;
+            if trie.insert(&key(num), &value(hash, total_difficulty)).is_err() {
+                return None;
+            }
+        }
+    }
+
+    Some(CHT {
+        db: db,
+        root: root,
+        number: cht_num,
+    })
+}
+
+/// Compute the root of a CHT from the canonical hashes and total difficulties of all its
+/// blocks, in order. Returns `None` if there are too few of them.
+pub fn compute_root<I>(cht_num: u64, iterable: I) -> Option<H256>
+where I: IntoIterator<Item = (H256, U256)> {
+    let start = start_number(cht_num);
+    let entries: Vec<_> = iterable
+        .into_iter()
+        .take(SIZE as usize)
+        .enumerate()
+        .map(|(i, (hash, total_difficulty))| {
+            (key(start + i as u64), value(hash, total_difficulty))
+        })
+        .collect();
+
+    if entries.len() == SIZE as usize {
+        Some(trie_root(entries))
+    } else {
+        None
+    }
+}
+
+/// Check a proof for a CHT, returning the canonical hash and total difficulty of block `num`,
+/// or `None` if the proof does not lead there from `root`.
+pub fn check_proof(proof: &[Bytes], num: u64, root: H256) -> Option<(H256, U256)> {
+    let mut db = MemoryDB::new();
+    for node in proof {
+        db.insert(&node[..]);
+    }
+
+    let res = match TrieDB::new(&db, &root) {
+        Ok(trie) => {
+            trie.get_with(&key(num), |val: &[u8]| {
+                let rlp = UntrustedRlp::new(val);
+                match (rlp.val_at::<H256>(0), rlp.val_at::<U256>(1)) {
+                    (Ok(hash), Ok(total_difficulty)) => Some((hash, total_difficulty)),
+                    _ => None,
+                }
+            })
+        }
+        Err(_) => return None,
+    };
+
+    match res {
+        Ok(Some(Some(entry))) => Some(entry),
+        _ => None,
+    }
+}
+
+/// Convert a block number to a CHT number.
+/// Returns `None` for the genesis block, which every node knows.
+pub fn block_to_cht_number(block_num: u64) -> Option<u64> {
+    match block_num {
+        0 => None,
+        n => Some((n - 1) / SIZE),
+    }
+}
+
+/// Get the starting block of a given CHT.
+/// CHT 0 includes block 1...SIZE,
+/// CHT 1 includes block SIZE + 1 ... 2*SIZE
+/// More generally: CHT N includes block (1 + N*SIZE)...((N+1)*SIZE).
+/// This is because the genesis hash is assumed to be known
+/// and including it would be redundant.
+pub fn start_number(cht_num: u64) -> u64 { (cht_num * SIZE) + 1 }
+
+/// Get the ending block of a given CHT.
+pub fn end_number(cht_num: u64) -> u64 { (cht_num + 1) * SIZE }
+
+fn key(num: u64) -> Vec<u8> { rlp::encode(&num).into_vec() }
+
+fn value(hash: H256, total_difficulty: U256) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(&hash).append(&total_difficulty);
+    stream.out()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blake2b::blake2b;
+
+    fn entry(num: u64) -> Option<(H256, U256)> {
+        Some((blake2b(&key(num)), U256::from(num) * U256::from(10)))
+    }
+
+    #[test]
+    fn size_is_lt_usize() {
+        // to ensure safe casting on the target platform.
+        assert!(SIZE < usize::max_value() as u64)
+    }
+
+    #[test]
+    fn block_to_cht_number_works() {
+        assert_eq!(block_to_cht_number(0), None);
+        assert_eq!(block_to_cht_number(1), Some(0));
+        assert_eq!(block_to_cht_number(SIZE), Some(0));
+        assert_eq!(block_to_cht_number(SIZE + 1), Some(1));
+        assert_eq!(start_number(1), SIZE + 1);
+        assert_eq!(end_number(1), 2 * SIZE);
+    }
+
+    #[test]
+    fn build_and_check_cht() {
+        let cht = build(1, entry).unwrap();
+        let entries = (start_number(1)..end_number(1) + 1).map(|num| entry(num).unwrap());
+        assert_eq!(compute_root(1, entries), Some(cht.root()));
+
+        let num = start_number(1) + 17;
+        let proof = cht.prove(num).unwrap().unwrap();
+        assert_eq!(check_proof(&proof, num, cht.root()), entry(num));
+        // a proof of one block does not prove another, nor under another root.
+        assert_eq!(check_proof(&proof, num + 1000, cht.root()), None);
+        assert_eq!(check_proof(&proof, num, H256::default()), None);
+        // blocks out of the CHT are not proved.
+        assert!(cht.prove(start_number(2)).unwrap().is_none());
+    }
+
+    #[test]
+    fn build_fails_on_missing_block() {
+        assert!(build(0, |num| if num == 5 { None } else { entry(num) }).is_none());
+        assert!(compute_root(0, (1..SIZE).map(|num| entry(num).unwrap())).is_none());
+    }
+}
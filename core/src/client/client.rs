@@ -39,12 +39,16 @@ use util_error::UtilError;
 // other
 use aion_types::{Address, H128, H256, H264, U256};
 use block::*;
-use blockchain::{BlockChain, BlockProvider, ImportRoute, TransactionAddress, TreeRoute};
+use cht;
+use blockchain::{
+    AddressIndex, BlockChain, BlockProvider, ImportRoute, TransactionAddress, TreeRoute,
+};
 use client::ancient_import::AncientVerifier;
 use client::Error as ClientError;
 use client::{
-    BlockChainClient, BlockId, BlockImportError, CallAnalytics, ChainNotify, ClientConfig,
-    MiningBlockChainClient, ProvingBlockChainClient, PruningInfo, TransactionId,
+    BlockChainClient, BlockId, BlockImportError, BlockOverride, CallAnalytics, ChainNotify,
+    ClientConfig, MiningBlockChainClient, ProvingBlockChainClient, PruningInfo, StateOverride,
+    StorageValue, TransactionId,
 };
 use encoded;
 use engines::{EpochTransition, EthEngine};
@@ -55,6 +59,7 @@ use header::{BlockNumber, Header, Seal};
 use io::*;
 use log_entry::LocalizedLogEntry;
 use miner::{Miner, MinerService};
+use proof;
 use parking_lot::{Mutex, RwLock};
 use rand::OsRng;
 use receipt::{LocalizedReceipt, Receipt};
@@ -66,6 +71,7 @@ use state_db::StateDB;
 use transaction::{
     Action, LocalizedTransaction, PendingTransaction, SignedTransaction, Transaction,
 };
+use transaction::transaction_queue::QueueListener;
 use types::filter::Filter;
 use verification;
 use verification::queue::BlockQueue;
@@ -83,6 +89,9 @@ use_contract!(registry, "Registry", "res/contracts/registrar.json");
 
 const MIN_HISTORY_SIZE: u64 = 8;
 
+/// Maximum number of blocks added to the address index on each tick while catching up.
+const ADDRESS_INDEX_BACKFILL_BLOCKS: u64 = 1000;
+
 /// Report on the status of a client.
 #[derive(Default, Clone, Debug, Eq, PartialEq)]
 pub struct ClientReport {
@@ -143,6 +152,7 @@ pub struct Client {
     ancient_verifier: Mutex<Option<AncientVerifier>>,
     registrar: registry::Registry,
     registrar_address: Option<Address>,
+    address_index: Option<AddressIndex>,
 }
 
 impl Client {
@@ -209,6 +219,12 @@ impl Client {
             );
         }
 
+        let address_index = if config.tx_address_index {
+            Some(AddressIndex::new(db.clone(), chain.best_block_number()))
+        } else {
+            None
+        };
+
         let engine = spec.engine.clone();
 
         let block_queue = BlockQueue::new(
@@ -246,6 +262,7 @@ impl Client {
             ancient_verifier: Mutex::new(None),
             registrar: registry::Registry::default(),
             registrar_address,
+            address_index,
         });
 
         // prune old states.
@@ -684,6 +701,9 @@ impl Client {
             .expect("DB commit failed");
         trace!(target: "block", "insert block number: {:?}", number);
         let route = chain.insert_block(&mut batch, block_data, receipts.clone());
+        if let Some(ref index) = self.address_index {
+            Self::update_address_index(index, &mut batch, &chain, &route, number, block_data);
+        }
 
         let is_canon = route.enacted.last().map_or(false, |h| h == hash);
         state.sync_cache(&route.enacted, &route.retracted, is_canon);
@@ -702,6 +722,128 @@ impl Client {
         route
     }
 
+    // index the transactions of the enacted blocks and drop the ones of the retracted blocks.
+    fn update_address_index(
+        index: &AddressIndex,
+        batch: &mut DBTransaction,
+        chain: &BlockChain,
+        route: &ImportRoute,
+        number: BlockNumber,
+        block_data: &[u8],
+    )
+    {
+        if route.enacted.is_empty() {
+            return;
+        }
+        let view = BlockView::new(block_data);
+        let hash = view.header_view().hash();
+
+        for retracted in &route.retracted {
+            if let Some(block) = chain.block(retracted) {
+                index.remove(batch, &block.view());
+            }
+        }
+        for enacted in &route.enacted {
+            if *enacted == hash {
+                index.insert(batch, &view);
+            } else if let Some(block) = chain.block(enacted) {
+                index.insert(batch, &block.view());
+            }
+        }
+
+        // the range only grows if it reaches the common ancestor, otherwise the blocks in
+        // between are left to the backfill.
+        let (lowest, highest) = index.range();
+        if highest + route.enacted.len() as u64 >= number {
+            index.set_range(batch, lowest, number);
+        }
+    }
+
+    // add the canonical blocks missing from the address index, newest first. The import lock
+    // is only held to pick the blocks and to commit them. If the chain changed meanwhile, the
+    // work is dropped and done again on the next tick.
+    fn backfill_address_index(&self) {
+        let index = match self.address_index {
+            Some(ref index) => index,
+            None => return,
+        };
+        let range = index.range();
+        let (mut above, mut below) = {
+            let _import_lock = self.import_lock.lock();
+            let chain = self.chain.read();
+            let (lowest, highest) = range;
+            let mut above = Vec::new();
+            let mut number = highest + 1;
+            while number <= chain.best_block_number()
+                && (above.len() as u64) < ADDRESS_INDEX_BACKFILL_BLOCKS
+            {
+                match chain.block_hash(number) {
+                    Some(hash) => above.push((number, hash)),
+                    None => break,
+                }
+                number += 1;
+            }
+            let mut below = Vec::new();
+            let mut number = lowest;
+            while number > 0 && ((above.len() + below.len()) as u64) < ADDRESS_INDEX_BACKFILL_BLOCKS
+            {
+                number -= 1;
+                match chain.block_hash(number) {
+                    Some(hash) => below.push((number, hash)),
+                    None => break,
+                }
+            }
+            (above, below)
+        };
+
+        let mut batch = DBTransaction::new();
+        let indexed = self.index_blocks(index, &mut batch, &above);
+        above.truncate(indexed);
+        let indexed = self.index_blocks(index, &mut batch, &below);
+        below.truncate(indexed);
+        if above.is_empty() && below.is_empty() {
+            return;
+        }
+
+        let _import_lock = self.import_lock.lock();
+        let chain = self.chain.read();
+        let is_canon = above
+            .iter()
+            .chain(below.iter())
+            .all(|&(number, ref hash)| chain.block_hash(number).as_ref() == Some(hash));
+        if index.range() != range || !is_canon {
+            debug!(target: "client", "Chain changed while backfilling the address index, retrying");
+            return;
+        }
+        let (lowest, highest) = (
+            range.0 - below.len() as BlockNumber,
+            range.1 + above.len() as BlockNumber,
+        );
+        index.set_range(&mut batch, lowest, highest);
+        self.db.read().write_buffered(batch);
+        debug!(target: "client", "Address index covers blocks #{} to #{}", lowest, highest);
+    }
+
+    // add the given blocks to the address index in order, up to the first one not found.
+    // Returns the number of blocks added.
+    fn index_blocks(
+        &self,
+        index: &AddressIndex,
+        batch: &mut DBTransaction,
+        blocks: &[(BlockNumber, H256)],
+    ) -> usize
+    {
+        let mut indexed = 0;
+        for &(_, ref hash) in blocks {
+            match self.chain.read().block(hash) {
+                Some(block) => index.insert(batch, &block.view()),
+                None => break,
+            }
+            indexed += 1;
+        }
+        indexed
+    }
+
     // check for epoch end signal and write pending transition if it occurs.
     // state for the given block must be available.
     fn check_epoch_end_signal(
@@ -903,6 +1045,17 @@ impl Client {
         *self.io_channel.lock() = io_channel;
     }
 
+    // the CHT is rebuilt from the canonical hashes and total difficulties on every call.
+    fn build_cht(&self, cht_number: u64) -> Option<cht::CHT> {
+        let chain = self.chain.read();
+        cht::build(cht_number, |number| {
+            let hash = chain.block_hash(number)?;
+            chain
+                .block_details(&hash)
+                .map(|details| (hash, details.total_difficulty))
+        })
+    }
+
     /// Attempt to get a copy of a specific block's final state.
     ///
     /// This will not fail if given BlockId::Latest.
@@ -988,7 +1141,10 @@ impl Client {
 
     /// Tick the client.
     // TODO: manage by real events.
-    pub fn tick(&self) { self.check_garbage(); }
+    pub fn tick(&self) {
+        self.check_garbage();
+        self.backfill_address_index();
+    }
 
     fn check_garbage(&self) {
         self.chain.read().collect_garbage();
@@ -1092,6 +1248,53 @@ impl Client {
         call(state, env_info, machine, state_diff, t)
     }
 
+    /// Apply the overrides of a call to its copy of the state.
+    fn apply_state_override(
+        state: &mut State<StateDB>,
+        state_override: &StateOverride,
+    ) -> Result<(), CallError>
+    {
+        fn set_storage(
+            state: &mut State<StateDB>,
+            address: &Address,
+            key: H128,
+            value: StorageValue,
+        ) -> ::trie::Result<()>
+        {
+            match value {
+                StorageValue::Word(value) => state.set_storage(address, key, value),
+                StorageValue::DWord(value) => state.set_storage_dword(address, key, value),
+            }
+        }
+
+        let apply = |state: &mut State<StateDB>| -> ::trie::Result<()> {
+            for (address, account) in state_override {
+                if let Some(balance) = account.balance {
+                    state.set_balance(address, balance)?;
+                }
+                if let Some(nonce) = account.nonce {
+                    state.set_nonce(address, nonce)?;
+                }
+                if let Some(ref code) = account.code {
+                    state.reset_code(address, code.clone())?;
+                }
+                if let Some(ref storage) = account.state {
+                    state.reset_storage(address)?;
+                    for (key, value) in storage {
+                        set_storage(state, address, *key, *value)?;
+                    }
+                }
+                if let Some(ref storage) = account.state_diff {
+                    for (key, value) in storage {
+                        set_storage(state, address, *key, *value)?;
+                    }
+                }
+            }
+            Ok(())
+        };
+        apply(state).map_err(|e| CallError::from(ExecutionError::from(e)))
+    }
+
     fn block_number_ref(&self, id: &BlockId) -> Option<BlockNumber> {
         match *id {
             BlockId::Number(number) => Some(number),
@@ -1219,6 +1422,7 @@ impl BlockChainClient for Client {
         transaction: &SignedTransaction,
         analytics: CallAnalytics,
         block: BlockId,
+        state_override: &StateOverride,
     ) -> Result<Executed, CallError>
     {
         let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
@@ -1226,6 +1430,7 @@ impl BlockChainClient for Client {
 
         // that's just a copy of the state.
         let mut state = self.state_at(block).ok_or(CallError::StatePruned)?;
+        Self::apply_state_override(&mut state, state_override)?;
         let machine = self.engine.machine();
 
         Self::do_virtual_call(machine, &env_info, &mut state, transaction, analytics)
@@ -1235,10 +1440,20 @@ impl BlockChainClient for Client {
         &self,
         transactions: &[(SignedTransaction, CallAnalytics)],
         block: BlockId,
+        block_override: &BlockOverride,
     ) -> Result<Vec<Executed>, CallError>
     {
         let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
         env_info.gas_limit = U256::max_value();
+        if let Some(number) = block_override.number {
+            env_info.number = number;
+        }
+        if let Some(timestamp) = block_override.timestamp {
+            env_info.timestamp = timestamp;
+        }
+        if let Some(author) = block_override.author {
+            env_info.author = author;
+        }
 
         // that's just a copy of the state.
         let mut state = self.state_at(block).ok_or(CallError::StatePruned)?;
@@ -1254,7 +1469,13 @@ impl BlockChainClient for Client {
         Ok(results)
     }
 
-    fn estimate_gas(&self, t: &SignedTransaction, block: BlockId) -> Result<U256, CallError> {
+    fn estimate_gas(
+        &self,
+        t: &SignedTransaction,
+        block: BlockId,
+        state_override: &StateOverride,
+    ) -> Result<U256, CallError>
+    {
         let (mut upper, max_upper, env_info) = {
             let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
             let init = env_info.gas_limit;
@@ -1264,7 +1485,8 @@ impl BlockChainClient for Client {
         };
 
         // that's just a copy of the state.
-        let original_state = self.state_at(block).ok_or(CallError::StatePruned)?;
+        let mut original_state = self.state_at(block).ok_or(CallError::StatePruned)?;
+        Self::apply_state_override(&mut original_state, state_override)?;
         let sender = t.sender();
 
         let cond = |gas| {
@@ -1370,6 +1592,11 @@ impl BlockChainClient for Client {
 
     fn best_block_header(&self) -> encoded::Header { self.chain.read().best_block_header() }
 
+    fn signing_chain_id(&self) -> Option<u64> {
+        self.engine
+            .signing_chain_id(self.chain.read().best_block_number() + 1)
+    }
+
     fn block_header(&self, id: BlockId) -> Option<::encoded::Header> {
         let chain = self.chain.read();
 
@@ -1598,6 +1825,34 @@ impl BlockChainClient for Client {
         self.transaction_address(id).map(|addr| addr.block_hash)
     }
 
+    fn transactions_by_address(
+        &self,
+        address: &Address,
+        skip: usize,
+        limit: usize,
+    ) -> Option<(Vec<LocalizedTransaction>, (BlockNumber, BlockNumber))>
+    {
+        let index = self.address_index.as_ref()?;
+        let (lowest, highest) = index.range();
+        let indexed = {
+            let chain = self.chain.read();
+            index.transactions(address, skip, limit, |number, hash| {
+                number >= lowest && number <= highest
+                    && chain.block_hash(number).as_ref() == Some(hash)
+            })
+        };
+        let transactions = indexed
+            .into_iter()
+            .filter_map(|t| {
+                self.transaction(TransactionId::Location(
+                    BlockId::Hash(t.block_hash),
+                    t.transaction_index,
+                ))
+            })
+            .collect();
+        Some((transactions, (lowest, highest)))
+    }
+
     fn transaction_receipt(&self, id: TransactionId) -> Option<LocalizedReceipt> {
         let chain = self.chain.read();
         self.transaction_address(id).and_then(|address| {
@@ -1629,6 +1884,37 @@ impl BlockChainClient for Client {
         })
     }
 
+    fn localized_block_receipts(&self, id: BlockId) -> Option<Vec<LocalizedReceipt>> {
+        let chain = self.chain.read();
+        let hash = Self::block_hash(&chain, &self.miner, id)?;
+        let number = chain.block_number(&hash)?;
+        let transactions = chain
+            .block_body(&hash)?
+            .view()
+            .localized_transactions(&hash, number);
+        let receipts = chain.block_receipts(&hash)?.receipts;
+        if transactions.len() != receipts.len() {
+            return None;
+        }
+
+        let mut prior_gas_used = U256::zero();
+        let mut no_of_logs = 0;
+        Some(
+            transactions
+                .into_iter()
+                .zip(receipts.into_iter())
+                .map(|(tx, receipt)| {
+                    let gas_used = receipt.gas_used;
+                    let logs = receipt.logs().len();
+                    let receipt = localized_receipt(tx, receipt, prior_gas_used, no_of_logs);
+                    prior_gas_used = prior_gas_used + gas_used;
+                    no_of_logs += logs;
+                    receipt
+                })
+                .collect(),
+        )
+    }
+
     fn tree_route(&self, from: &H256, to: &H256) -> Option<TreeRoute> {
         let chain = self.chain.read();
         match chain.is_known(from) && chain.is_known(to) {
@@ -1641,6 +1927,105 @@ impl BlockChainClient for Client {
         self.state_db.read().journal_db().state(hash)
     }
 
+    fn has_state_data(&self, address_hash: Option<&H256>, hash: &H256) -> bool {
+        let state_db = self.state_db.read();
+        match address_hash {
+            Some(address_hash) => {
+                self.factories
+                    .accountdb
+                    .readonly(state_db.as_hashstore(), address_hash.clone())
+                    .contains(hash)
+            }
+            None => state_db.as_hashstore().contains(hash),
+        }
+    }
+
+    fn import_state_data(&self, data: Vec<(Option<H256>, Bytes)>) -> Result<(), BlockImportError> {
+        let _import_lock = self.import_lock.lock();
+        let mut state_db = self.state_db.write();
+        for (address_hash, value) in data {
+            let hash = blake2b(&value);
+            let value = DBValue::from_vec(value);
+            match address_hash {
+                Some(address_hash) => {
+                    self.factories
+                        .accountdb
+                        .create(state_db.as_hashstore_mut(), address_hash)
+                        .emplace(hash, value)
+                }
+                None => state_db.as_hashstore_mut().emplace(hash, value),
+            }
+        }
+
+        // downloaded nodes are final, they bypass the journal.
+        let mut batch = DBTransaction::new();
+        state_db
+            .journal_db_mut()
+            .inject(&mut batch)
+            .map_err(|e| BlockImportError::Other(format!("state data injection failed: {}", e)))?;
+        self.db
+            .read()
+            .write(batch)
+            .map_err(|e| BlockImportError::Other(format!("state data write failed: {:?}", e)))?;
+        state_db.journal_db().flush();
+        Ok(())
+    }
+
+    fn import_state_pivot(
+        &self,
+        parent_bytes: Bytes,
+        block_bytes: Bytes,
+        total_difficulty: U256,
+    ) -> Result<H256, BlockImportError>
+    {
+        let parent = BlockView::new(&parent_bytes).header();
+        let header = BlockView::new(&block_bytes).header();
+        let hash = header.hash();
+        if header.parent_hash() != &parent.hash() {
+            return Err(BlockImportError::Other(
+                "pivot block does not extend the given parent".into(),
+            ));
+        }
+        if !self.has_state_data(None, header.state_root()) {
+            return Err(BlockImportError::Other(format!(
+                "state of block #{} is not available",
+                header.number()
+            )));
+        }
+        let own_difficulty = *header.difficulty() + *parent.difficulty();
+        if total_difficulty < own_difficulty {
+            return Err(BlockImportError::Other(
+                "total difficulty lower than the one of the pivot blocks".into(),
+            ));
+        }
+
+        let _import_lock = self.import_lock.lock();
+        {
+            let chain = self.chain.read();
+            // the parent is the first block known after genesis, its total difficulty is
+            // derived from the one of the pivot.
+            let blocks = vec![
+                (parent_bytes, Some(total_difficulty - own_difficulty)),
+                (block_bytes, None),
+            ];
+            for (bytes, parent_td) in blocks {
+                let mut batch = DBTransaction::new();
+                chain.insert_unordered_block(
+                    &mut batch,
+                    &bytes,
+                    Vec::new(),
+                    parent_td,
+                    true,
+                    false,
+                );
+                self.db.read().write_buffered(batch);
+                chain.commit();
+            }
+        }
+        self.db.read().flush().expect("DB flush failed.");
+        Ok(hash)
+    }
+
     fn block_receipts(&self, hash: &H256) -> Option<Bytes> {
         self.chain
             .read()
@@ -1738,6 +2123,12 @@ impl BlockChainClient for Client {
             .logs(blocks, |entry| filter.matches(entry), filter.limit)
     }
 
+    fn block_logs(&self, hash: &H256, filter: &Filter) -> Vec<LocalizedLogEntry> {
+        self.chain
+            .read()
+            .block_logs(hash, |entry| filter.matches(entry))
+    }
+
     fn last_hashes(&self) -> LastHashes {
         (*self.build_last_hashes(self.chain.read().best_block_hash())).clone()
     }
@@ -1756,6 +2147,14 @@ impl BlockChainClient for Client {
         self.miner.ready_transactions(number, timestamp)
     }
 
+    fn queued_transaction(&self, hash: &H256) -> Option<PendingTransaction> {
+        self.miner.queued_transaction(hash)
+    }
+
+    fn add_transaction_queue_listener(&self, listener: QueueListener) {
+        self.miner.add_queue_listener(listener);
+    }
+
     fn queue_consensus_message(&self, message: Bytes) {
         let channel = self.io_channel.lock().clone();
         if let Err(e) = channel.send(ClientIoMessage::NewMessage(message)) {
@@ -1789,7 +2188,7 @@ impl BlockChainClient for Client {
     {
         let transaction = self.contract_call_tx(block_id, address, data);
 
-        self.call(&transaction, Default::default(), block_id)
+        self.call(&transaction, Default::default(), block_id, &StateOverride::new())
             .map_err(|e| format!("{:?}", e))
             .map(|executed| executed.output)
     }
@@ -1811,6 +2210,21 @@ impl BlockChainClient for Client {
             .ok()
             .and_then(|a| if a.is_zero() { None } else { Some(a) })
     }
+
+    fn as_proving_client(&self) -> Option<&ProvingBlockChainClient> { Some(self) }
+
+    fn verify_header_basic(&self, header: &Header) -> Result<(), ::error::Error> {
+        self.engine.verify_block_basic(header)
+    }
+
+    fn verify_detached_header(
+        &self,
+        header: &Header,
+        ancestor: &Fn(&H256) -> Option<Header>,
+    ) -> Result<(), ::error::Error>
+    {
+        self.engine.verify_block_detached(header, ancestor)
+    }
 }
 
 impl MiningBlockChainClient for Client {
@@ -2002,6 +2416,29 @@ impl ProvingBlockChainClient for Client {
             .get_pending_transition(hash)
             .map(|pending| pending.proof)
     }
+
+    fn prove_receipt(&self, hash: H256, index: usize) -> Option<Vec<Bytes>> {
+        let receipts = self.chain.read().block_receipts(&hash)?;
+        proof::prove_ordered_trie(
+            receipts
+                .receipts
+                .iter()
+                .map(|r| r.simple_receipt().rlp_bytes()),
+            index,
+        )
+    }
+
+    fn cht_root(&self, cht_number: u64) -> Option<H256> {
+        self.build_cht(cht_number).map(|cht| cht.root())
+    }
+
+    fn prove_header(&self, number: BlockNumber) -> Option<(Vec<Bytes>, H256)> {
+        let cht = cht::block_to_cht_number(number).and_then(|n| self.build_cht(n))?;
+        match cht.prove(number) {
+            Ok(Some(proof)) => Some((proof, cht.root())),
+            _ => None,
+        }
+    }
 }
 
 impl Drop for Client {
@@ -2010,24 +2447,32 @@ impl Drop for Client {
 
 /// Returns `LocalizedReceipt` given `LocalizedTransaction`
 /// and a vector of receipts from given block up to transaction index.
-fn transaction_receipt(
-    mut tx: LocalizedTransaction,
-    mut receipts: Vec<Receipt>,
-) -> LocalizedReceipt
-{
+fn transaction_receipt(tx: LocalizedTransaction, mut receipts: Vec<Receipt>) -> LocalizedReceipt {
     assert_eq!(
         receipts.len(),
         tx.transaction_index + 1,
         "All previous receipts are provided."
     );
 
-    let sender = tx.sender();
     let receipt = receipts.pop().expect("Current receipt is provided; qed");
     let prior_gas_used = receipts.iter().fold(0.into(), |b, r| b + r.gas_used);
     let no_of_logs = receipts
         .into_iter()
         .map(|receipt| receipt.logs().len())
         .sum::<usize>();
+    localized_receipt(tx, receipt, prior_gas_used, no_of_logs)
+}
+
+/// Returns `LocalizedReceipt` given `LocalizedTransaction`, its receipt, the gas used
+/// and the number of logs emitted by the transactions before it in the block.
+fn localized_receipt(
+    mut tx: LocalizedTransaction,
+    receipt: Receipt,
+    prior_gas_used: U256,
+    no_of_logs: usize,
+) -> LocalizedReceipt
+{
+    let sender = tx.sender();
     let transaction_hash = tx.hash();
     let block_hash = tx.block_hash;
     let block_number = tx.block_number;
@@ -2067,6 +2512,7 @@ fn transaction_receipt(
             Action::Create => None,
             Action::Call(ref address) => Some(address.clone().into()),
         },
+        fee_payer: tx.fee_payer(),
         gas_price: tx.gas_price,
         gas_limit: tx.gas,
         output: receipt.output,
@@ -104,6 +104,8 @@ pub struct ClientConfig {
     pub history_mem: usize,
     /// Check seal valididity on block import
     pub check_seal: bool,
+    /// Index the transactions of every address?
+    pub tx_address_index: bool,
 }
 
 #[cfg(test)]
@@ -41,6 +41,7 @@ pub use types::ids::*;
 pub use types::trace_filter::Filter as TraceFilter;
 pub use types::pruning_info::PruningInfo;
 pub use types::call_analytics::CallAnalytics;
+pub use types::state_override::{AccountOverride, BlockOverride, StateOverride, StorageValue};
 
 pub use executive::{Executed, Executive};
 pub use vms::{EnvInfo, LastHashes};
@@ -40,11 +40,12 @@ use rlp::*;
 use key::{generate_keypair, public_to_address_ed25519};
 use tempdir::TempDir;
 use transaction::{self, Transaction, LocalizedTransaction, PendingTransaction, SignedTransaction, Action, DEFAULT_TRANSACTION_TYPE};
+use transaction::transaction_queue::QueueListener;
 use blockchain::{TreeRoute, BlockReceipts};
 use client::{
     BlockChainClient, MiningBlockChainClient, BlockChainInfo, BlockStatus, BlockId,
     TransactionId, LastHashes, CallAnalytics, BlockImportError,
-    ProvingBlockChainClient,
+    ProvingBlockChainClient, BlockOverride, StateOverride,
 };
 use db::{COL_STATE, DB_NAMES};
 use header::{Header as BlockHeader, BlockNumber};
@@ -222,7 +223,7 @@ impl TestBlockChainClient {
     /// Set timestamp assigned to latest sealed block
     pub fn set_latest_block_timestamp(&self, ts: u64) { *self.latest_block_timestamp.write() = ts; }
 
-    /// Set logs to return for each logs call.
+    /// Set logs of the blocks. Logs calls return those of canon blocks in the filter range.
     pub fn set_logs(&self, logs: Vec<LocalizedLogEntry>) { *self.logs.write() = logs; }
 
     /// Add blocks to test client.
@@ -270,6 +271,74 @@ impl TestBlockChainClient {
         }
     }
 
+    /// Add a block on top of the best one, using `gas_used` of its gas limit of 1_000_000 and
+    /// holding a transaction for each of `gas_prices`, in order.
+    pub fn add_block_with_gas_prices(&self, gas_used: U256, gas_prices: &[U256]) {
+        let number = self.numbers.read().len();
+        let mut header = BlockHeader::new();
+        header.set_difficulty(From::from(number));
+        header.set_parent_hash(self.last_hash.read().clone());
+        header.set_number(number as BlockNumber);
+        header.set_gas_limit(U256::from(1_000_000));
+        header.set_gas_used(gas_used);
+        header.set_extra_data(self.extra_data.clone());
+
+        let mut txs = RlpStream::new_list(gas_prices.len());
+        for gas_price in gas_prices {
+            let keypair = generate_keypair();
+            let tx = Transaction {
+                action: Action::Create,
+                value: U256::from(100),
+                value_bytes: Vec::new(),
+                data: "3331600055".from_hex().unwrap(),
+                gas: U256::from(100_000),
+                gas_bytes: Vec::new(),
+                gas_price: *gas_price,
+                gas_price_bytes: Vec::new(),
+                nonce: U256::zero(),
+                nonce_bytes: Vec::new(),
+                transaction_type: DEFAULT_TRANSACTION_TYPE,
+            };
+            txs.append(&tx.sign(&keypair.secret().0, None));
+        }
+
+        let mut rlp = RlpStream::new_list(2);
+        rlp.append(&header);
+        rlp.append_raw(&txs.out(), 1);
+        self.import_block(rlp.out()).unwrap();
+    }
+
+    /// Add `count` plain blocks on top of canon block `from`. Returns the enacted and retracted
+    /// blocks, lowest number first, when the fork gets longer than the canon chain.
+    pub fn add_fork(&self, from: BlockNumber, count: usize) -> (Vec<H256>, Vec<H256>) {
+        let best = self.chain_info().best_block_number;
+        let retracted = (from + 1..best + 1)
+            .filter_map(|n| self.block_hash(BlockId::Number(n)))
+            .collect::<Vec<_>>();
+        let mut parent_hash = self
+            .block_hash(BlockId::Number(from))
+            .expect("Fork from a known block.");
+        let mut enacted = Vec::new();
+        for n in from + 1..from + 1 + count as BlockNumber {
+            let mut header = BlockHeader::new();
+            header.set_difficulty(From::from(n));
+            header.set_parent_hash(parent_hash);
+            header.set_number(n);
+            header.set_gas_limit(U256::from(1_000_000));
+            header.set_extra_data(b"fork".to_vec());
+            let mut rlp = RlpStream::new_list(2);
+            rlp.append(&header);
+            rlp.append_raw(&::rlp::EMPTY_LIST_RLP, 1);
+            parent_hash = self.import_block(rlp.out()).unwrap();
+            enacted.push(parent_hash);
+        }
+        if enacted.len() > retracted.len() {
+            (enacted, retracted)
+        } else {
+            (Vec::new(), Vec::new())
+        }
+    }
+
     /// Make a bad block by setting invalid extra data.
     pub fn corrupt_block(&self, n: BlockNumber) {
         let hash = self.block_hash(BlockId::Number(n)).unwrap();
@@ -426,6 +495,7 @@ impl BlockChainClient for TestBlockChainClient {
         _t: &SignedTransaction,
         _analytics: CallAnalytics,
         _block: BlockId,
+        _state_override: &StateOverride,
     ) -> Result<Executed, CallError>
     {
         self.execution_result.read().clone().unwrap()
@@ -435,16 +505,23 @@ impl BlockChainClient for TestBlockChainClient {
         &self,
         txs: &[(SignedTransaction, CallAnalytics)],
         block: BlockId,
+        _block_override: &BlockOverride,
     ) -> Result<Vec<Executed>, CallError>
     {
         let mut res = Vec::with_capacity(txs.len());
         for &(ref tx, analytics) in txs {
-            res.push(self.call(tx, analytics, block)?);
+            res.push(self.call(tx, analytics, block, &StateOverride::new())?);
         }
         Ok(res)
     }
 
-    fn estimate_gas(&self, _t: &SignedTransaction, _block: BlockId) -> Result<U256, CallError> {
+    fn estimate_gas(
+        &self,
+        _t: &SignedTransaction,
+        _block: BlockId,
+        _state_override: &StateOverride,
+    ) -> Result<U256, CallError>
+    {
         Ok(21000.into())
     }
 
@@ -566,12 +643,48 @@ impl BlockChainClient for TestBlockChainClient {
         None // Simple default.
     }
 
+    fn transactions_by_address(
+        &self,
+        _address: &Address,
+        _skip: usize,
+        _limit: usize,
+    ) -> Option<(Vec<LocalizedTransaction>, (BlockNumber, BlockNumber))>
+    {
+        None
+    }
+
     fn transaction_receipt(&self, id: TransactionId) -> Option<LocalizedReceipt> {
         self.receipts.read().get(&id).cloned()
     }
 
+    fn localized_block_receipts(&self, id: BlockId) -> Option<Vec<LocalizedReceipt>> {
+        let hash = Self::block_hash(self, id)?;
+        let mut receipts: Vec<LocalizedReceipt> = self
+            .receipts
+            .read()
+            .values()
+            .filter(|receipt| receipt.block_hash == hash)
+            .cloned()
+            .collect();
+        receipts.sort_by_key(|receipt| receipt.transaction_index);
+        Some(receipts)
+    }
+
     fn logs(&self, filter: Filter) -> Vec<LocalizedLogEntry> {
-        let mut logs = self.logs.read().clone();
+        let from = self.block_number(filter.from_block.clone()).unwrap_or(0);
+        let to = self
+            .block_number(filter.to_block.clone())
+            .unwrap_or_else(|| self.chain_info().best_block_number);
+        let mut logs: Vec<_> = self
+            .logs
+            .read()
+            .iter()
+            .filter(|log| log.block_number >= from && log.block_number <= to)
+            .filter(|log| {
+                self.block_hash(BlockId::Number(log.block_number)) == Some(log.block_hash)
+            })
+            .cloned()
+            .collect();
         let len = logs.len();
         match filter.limit {
             Some(limit) if limit <= len => logs.split_off(len - limit),
@@ -579,6 +692,15 @@ impl BlockChainClient for TestBlockChainClient {
         }
     }
 
+    fn block_logs(&self, hash: &H256, filter: &Filter) -> Vec<LocalizedLogEntry> {
+        self.logs
+            .read()
+            .iter()
+            .filter(|log| log.block_hash == *hash && filter.matches(&log.entry))
+            .cloned()
+            .collect()
+    }
+
     fn last_hashes(&self) -> LastHashes {
         unimplemented!();
     }
@@ -588,6 +710,8 @@ impl BlockChainClient for TestBlockChainClient {
             .expect("Best block always has header.")
     }
 
+    fn signing_chain_id(&self) -> Option<u64> { None }
+
     fn block_header(&self, id: BlockId) -> Option<encoded::Header> {
         self.block_hash(id)
             .and_then(|hash| {
@@ -599,7 +723,9 @@ impl BlockChainClient for TestBlockChainClient {
             .map(encoded::Header::new)
     }
 
-    fn block_number(&self, _id: BlockId) -> Option<BlockNumber> { unimplemented!() }
+    fn block_number(&self, id: BlockId) -> Option<BlockNumber> {
+        self.block_header(id).map(|header| header.number())
+    }
 
     fn block_body(&self, id: BlockId) -> Option<encoded::Body> {
         self.block_hash(id).and_then(|hash| {
@@ -763,6 +889,22 @@ impl BlockChainClient for TestBlockChainClient {
         self.import_block(b)
     }
 
+    fn has_state_data(&self, _address_hash: Option<&H256>, _hash: &H256) -> bool { false }
+
+    fn import_state_data(&self, _data: Vec<(Option<H256>, Bytes)>) -> Result<(), BlockImportError> {
+        unimplemented!()
+    }
+
+    fn import_state_pivot(
+        &self,
+        _parent_bytes: Bytes,
+        _block_bytes: Bytes,
+        _total_difficulty: U256,
+    ) -> Result<H256, BlockImportError>
+    {
+        unimplemented!()
+    }
+
     fn queue_info(&self) -> QueueInfo {
         QueueInfo {
             verified_queue_size: self.queue_size.load(AtomicOrder::Relaxed),
@@ -781,7 +923,7 @@ impl BlockChainClient for TestBlockChainClient {
     fn additional_params(&self) -> BTreeMap<String, String> { Default::default() }
 
     fn chain_info(&self) -> BlockChainInfo {
-        let number = self.blocks.read().len() as BlockNumber - 1;
+        let number = self.numbers.read().len() as BlockNumber - 1;
         BlockChainInfo {
             total_difficulty: *self.difficulty.read(),
             pending_total_difficulty: *self.difficulty.read(),
@@ -808,6 +950,14 @@ impl BlockChainClient for TestBlockChainClient {
             .ready_transactions(info.best_block_number, info.best_block_timestamp)
     }
 
+    fn queued_transaction(&self, hash: &H256) -> Option<PendingTransaction> {
+        self.miner.queued_transaction(hash)
+    }
+
+    fn add_transaction_queue_listener(&self, listener: QueueListener) {
+        self.miner.add_queue_listener(listener);
+    }
+
     fn spec_name(&self) -> String { "foundation".into() }
 
     fn disable(&self) {
@@ -840,6 +990,21 @@ impl BlockChainClient for TestBlockChainClient {
     fn registrar_address(&self) -> Option<Address> { None }
 
     fn registry_address(&self, _name: String, _block: BlockId) -> Option<Address> { None }
+
+    fn as_proving_client(&self) -> Option<&ProvingBlockChainClient> { Some(self) }
+
+    fn verify_header_basic(&self, header: &BlockHeader) -> Result<(), ::error::Error> {
+        self.spec.engine.verify_block_basic(header)
+    }
+
+    fn verify_detached_header(
+        &self,
+        header: &BlockHeader,
+        ancestor: &Fn(&H256) -> Option<BlockHeader>,
+    ) -> Result<(), ::error::Error>
+    {
+        self.spec.engine.verify_block_detached(header, ancestor)
+    }
 }
 
 impl ProvingBlockChainClient for TestBlockChainClient {
@@ -852,6 +1017,12 @@ impl ProvingBlockChainClient for TestBlockChainClient {
     }
 
     fn epoch_signal(&self, _: H256) -> Option<Vec<u8>> { None }
+
+    fn prove_receipt(&self, _: H256, _: usize) -> Option<Vec<Bytes>> { None }
+
+    fn cht_root(&self, _: u64) -> Option<H256> { None }
+
+    fn prove_header(&self, _: BlockNumber) -> Option<(Vec<Bytes>, H256)> { None }
 }
 
 impl super::traits::EngineClient for TestBlockChainClient {
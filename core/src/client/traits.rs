@@ -26,15 +26,16 @@ use std::time::Duration;
 use block::{OpenBlock, SealedBlock, ClosedBlock};
 use blockchain::TreeRoute;
 use encoded;
-use error::{ImportResult, CallError, BlockImportError};
+use error::{Error, ImportResult, CallError, BlockImportError};
 use vms::LastHashes;
 use factory::VmFactory;
 use executive::Executed;
 use filter::Filter;
-use header::{BlockNumber};
+use header::{BlockNumber, Header};
 use log_entry::LocalizedLogEntry;
 use receipt::LocalizedReceipt;
 use transaction::{LocalizedTransaction, PendingTransaction, SignedTransaction};
+use transaction::transaction_queue::QueueListener;
 use verification::queue::QueueInfo as BlockQueueInfo;
 
 use aion_types::{H256, H128, U256, Address};
@@ -44,6 +45,7 @@ use kvdb::DBValue;
 use types::ids::*;
 use types::basic_account::BasicAccount;
 use types::call_analytics::CallAnalytics;
+use types::state_override::{BlockOverride, StateOverride};
 use types::blockchain_info::BlockChainInfo;
 use types::block_status::BlockStatus;
 use types::pruning_info::PruningInfo;
@@ -155,15 +157,48 @@ pub trait BlockChainClient: Sync + Send {
     /// Get the hash of block that contains the transaction, if any.
     fn transaction_block(&self, id: TransactionId) -> Option<H256>;
 
+    /// Get the canonical transactions sent from, sent to or creating the given address, newest
+    /// first, skipping the first `skip` ones, along with the lowest and highest number of the
+    /// indexed blocks. Only transactions of the indexed blocks are returned, the others may not
+    /// be backfilled yet. `None` if the address index is disabled.
+    fn transactions_by_address(
+        &self,
+        address: &Address,
+        skip: usize,
+        limit: usize,
+    ) -> Option<(Vec<LocalizedTransaction>, (BlockNumber, BlockNumber))>;
+
     /// Get transaction receipt with given hash.
     fn transaction_receipt(&self, id: TransactionId) -> Option<LocalizedReceipt>;
 
+    /// Get the receipts of all transactions in the given block, in transaction order.
+    fn localized_block_receipts(&self, id: BlockId) -> Option<Vec<LocalizedReceipt>>;
+
     /// Get a tree route between `from` and `to`.
     /// See `BlockChain::tree_route`.
     fn tree_route(&self, from: &H256, to: &H256) -> Option<TreeRoute>;
     /// Get latest state node
     fn state_data(&self, hash: &H256) -> Option<Bytes>;
 
+    /// Whether a state trie node or code, along with everything it refers to, is in the state
+    /// database. Storage trie nodes and code are looked up under the hash of their account's
+    /// address.
+    fn has_state_data(&self, address_hash: Option<&H256>, hash: &H256) -> bool;
+
+    /// Write state trie nodes and code, given with the hash of their account's address if they
+    /// belong to one, straight to the state database.
+    fn import_state_data(&self, data: Vec<(Option<H256>, Bytes)>) -> Result<(), BlockImportError>;
+
+    /// Make a block whose state is already in the state database the best block, without
+    /// executing it. Its parent is stored along so that the next block can be verified.
+    /// `total_difficulty` is the one of the block itself.
+    fn import_state_pivot(
+        &self,
+        parent_bytes: Bytes,
+        block_bytes: Bytes,
+        total_difficulty: U256,
+    ) -> Result<H256, BlockImportError>;
+
     /// Get raw block receipts data by block header hash.
     fn block_receipts(&self, hash: &H256) -> Option<Bytes>;
 
@@ -195,27 +230,44 @@ pub trait BlockChainClient: Sync + Send {
     /// Get the best block header.
     fn best_block_header(&self) -> encoded::Header;
 
+    /// Chain id new transactions should be signed for, `None` before the chain id transition.
+    fn signing_chain_id(&self) -> Option<u64>;
+
     /// Returns logs matching given filter.
     fn logs(&self, filter: Filter) -> Vec<LocalizedLogEntry>;
 
-    /// Makes a non-persistent transaction call.
+    /// Returns logs of the block with given hash matching given filter, in block order.
+    /// Block range of the filter is ignored and the block need not be canonical.
+    fn block_logs(&self, hash: &H256, filter: &Filter) -> Vec<LocalizedLogEntry>;
+
+    /// Makes a non-persistent transaction call, on a copy of the state with the given
+    /// accounts overridden.
     fn call(
         &self,
         tx: &SignedTransaction,
         analytics: CallAnalytics,
         block: BlockId,
+        state_override: &StateOverride,
     ) -> Result<Executed, CallError>;
 
     /// Makes multiple non-persistent but dependent transaction calls.
     /// Returns a vector of successes or a failure if any of the transaction fails.
+    /// The environment of the block is overridden as given.
     fn call_many(
         &self,
         txs: &[(SignedTransaction, CallAnalytics)],
         block: BlockId,
+        block_override: &BlockOverride,
     ) -> Result<Vec<Executed>, CallError>;
 
-    /// Estimates how much gas will be necessary for a call.
-    fn estimate_gas(&self, t: &SignedTransaction, block: BlockId) -> Result<U256, CallError>;
+    /// Estimates how much gas will be necessary for a call, on a copy of the state with the
+    /// given accounts overridden.
+    fn estimate_gas(
+        &self,
+        t: &SignedTransaction,
+        block: BlockId,
+        state_override: &StateOverride,
+    ) -> Result<U256, CallError>;
 
     /// Replays a given transaction for inspection.
     fn replay(&self, t: TransactionId, analytics: CallAnalytics) -> Result<Executed, CallError>;
@@ -239,6 +291,13 @@ pub trait BlockChainClient: Sync + Send {
     /// List all transactions that are allowed into the next block.
     fn ready_transactions(&self) -> Vec<PendingTransaction>;
 
+    /// Transaction waiting in the transaction queue.
+    fn queued_transaction(&self, hash: &H256) -> Option<PendingTransaction>;
+
+    /// Register a callback told about every transaction entering or leaving the transaction
+    /// queue.
+    fn add_transaction_queue_listener(&self, listener: QueueListener);
+
     /// Sorted list of block prices from at least last blk_price_window blocks.
     fn gas_price_corpus(
         &self,
@@ -298,6 +357,21 @@ pub trait BlockChainClient: Sync + Send {
 
     /// Get the address of a particular blockchain service, if available.
     fn registry_address(&self, name: String, block: BlockId) -> Option<Address>;
+
+    /// Get a reference to the `ProvingBlockChainClient`, if the client can serve proofs.
+    fn as_proving_client(&self) -> Option<&ProvingBlockChainClient>;
+
+    /// Cheap checks of a header received from the network, before its ancestors are known: its
+    /// version, against the forks active at its number, and its proof of work.
+    fn verify_header_basic(&self, header: &Header) -> Result<(), Error>;
+
+    /// Verify the seal and the difficulty of a header which is not going to be imported, such
+    /// as one synced in light mode. `ancestor` looks up its ancestors by hash.
+    fn verify_detached_header(
+        &self,
+        header: &Header,
+        ancestor: &Fn(&H256) -> Option<Header>,
+    ) -> Result<(), Error>;
 }
 
 /// Extended client interface used for mining
@@ -387,4 +461,16 @@ pub trait ProvingBlockChainClient: BlockChainClient {
 
     /// Get an epoch change signal by block hash.
     fn epoch_signal(&self, hash: H256) -> Option<Vec<u8>>;
+
+    /// Prove the receipt at `index` of the block with the given hash.
+    /// Returns a vector of raw nodes (in order from the root) of the block's receipts trie.
+    fn prove_receipt(&self, hash: H256, index: usize) -> Option<Vec<Bytes>>;
+
+    /// Get the root of the canonical hash trie with the given number, once all of its blocks
+    /// are in chain.
+    fn cht_root(&self, cht_number: u64) -> Option<H256>;
+
+    /// Prove the canonical hash and total difficulty of a block within its CHT.
+    /// Returns a vector of raw trie nodes (in order from the root) and the CHT root.
+    fn prove_header(&self, number: BlockNumber) -> Option<(Vec<Bytes>, H256)>;
 }
@@ -39,18 +39,24 @@ pub const COL_HEADERS: &'static str = "headers";
 pub const COL_BODIES: &'static str = "bodies";
 /// Column for Extras
 pub const COL_EXTRA: &'static str = "extra";
+/// Column for the transactions of every address
+pub const COL_ADDRESS_INDEX: &'static str = "address_index";
 /// Column for the empty accounts bloom filter.
 pub const COL_ACCOUNT_BLOOM: &'static str = "account_bloom";
 /// Column for general information from the local node which can persist.
 pub const COL_NODE_INFO: &'static str = "node_info";
+/// Column for the journal of the transaction queue.
+pub const COL_TX_JOURNAL: &'static str = "tx_journal";
 
-pub const DB_NAMES: [&'static str; 6] = [
+pub const DB_NAMES: [&'static str; 8] = [
     "headers",
     "bodies",
     "state",
     "extra",
+    "address_index",
     "account_bloom",
     "node_info",
+    "tx_journal",
 ];
 /// Modes for updating caches.
 #[derive(Clone, Copy)]
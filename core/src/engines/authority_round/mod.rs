@@ -0,0 +1,704 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Authority round proof-of-authority engine.
+//!
+//! Time is divided into steps of fixed duration and each step has a single proposer taken
+//! from the validator set in round-robin order. Blocks are sealed with the step and an
+//! ed25519 signature of the proposer; the chain which skipped the fewest steps wins.
+
+mod validator_set;
+
+pub use self::validator_set::{ValidatorSet, ValidatorContract};
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::cmp;
+
+use account_provider::AccountProvider;
+use aion_machine::{LiveBlock, WithBalances};
+use aion_types::{H256, U256, Address};
+use ajson;
+use blake2b::blake2b;
+use block::ExecutedBlock;
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{u64_to_bytes, to_hex};
+use client::EngineClient;
+use engines::{
+    ConstructedVerifier, Engine, EngineError, EpochChange, EpochVerifier, Headers,
+    PendingTransitionStore, Proof, Seal
+};
+use error::{BlockError, Error};
+use header::Header;
+use io::{IoContext, IoHandler, IoService, TimerToken};
+use key::{public_to_address_ed25519, verify_signature_ed25519, Ed25519Signature};
+use machine::{AuxiliaryData, AuxiliaryRequest, Call, EthereumMachine};
+use parking_lot::RwLock;
+use rlp::RlpStream;
+use unexpected::{Mismatch, OutOfBounds};
+
+use self::validator_set::{decode_proof, encode_proof};
+
+/// Steps a block may be ahead of the local clock before it is rejected outright.
+const REJECTED_STEP_DRIFT: u64 = 4;
+
+const ENGINE_TIMEOUT_TOKEN: TimerToken = 23;
+
+/// `AuthorityRound` params.
+pub struct AuthorityRoundParams {
+    /// Time to wait before next block or authority switching, in seconds.
+    pub step_duration: u64,
+    /// Valid authorities.
+    pub validators: ValidatorSet,
+    /// Starting step. Calibrated against the system clock when not given.
+    pub start_step: Option<u64>,
+    /// Reward paid to the proposer of a block.
+    pub block_reward: U256,
+}
+
+impl From<ajson::spec::AuthorityRoundParams> for AuthorityRoundParams {
+    fn from(p: ajson::spec::AuthorityRoundParams) -> Self {
+        let step_duration: u64 = p.step_duration.into();
+        AuthorityRoundParams {
+            // steps shorter than a second would break the timestamp rules.
+            step_duration: cmp::max(step_duration, 1),
+            validators: p.validators.into(),
+            start_step: p.start_step.map(Into::into),
+            block_reward: p.block_reward.map_or_else(Default::default, Into::into),
+        }
+    }
+}
+
+/// Current step, advanced by the step timer.
+struct Step {
+    calibrate: bool,
+    inner: AtomicUsize,
+    duration: u64,
+}
+
+impl Step {
+    fn load(&self) -> u64 { self.inner.load(AtomicOrdering::SeqCst) as u64 }
+
+    fn duration_remaining(&self) -> Duration {
+        let now = unix_now();
+        let step_end = Duration::from_secs((self.load() + 1) * self.duration);
+        if step_end > now {
+            step_end - now
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+
+    fn increment(&self) { self.inner.fetch_add(1, AtomicOrdering::SeqCst); }
+
+    fn calibrate(&self) {
+        if self.calibrate {
+            let new_step = unix_now().as_secs() / self.duration;
+            self.inner.store(new_step as usize, AtomicOrdering::SeqCst);
+        }
+    }
+
+    // `Err(None)` if the step is too far ahead to ever become valid.
+    fn check_future(&self, given: u64) -> Result<(), Option<OutOfBounds<u64>>> {
+        let current = self.load();
+        if given > current + REJECTED_STEP_DRIFT {
+            Err(None)
+        } else if given > current {
+            Err(Some(OutOfBounds {
+                min: None,
+                max: Some(current * self.duration),
+                found: given * self.duration,
+            }))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn unix_now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+}
+
+fn header_step(header: &Header) -> Result<u64, Error> {
+    if header.number() == 0 {
+        return Ok(0);
+    }
+    let seal = header.seal();
+    match seal.first() {
+        Some(step) if step.len() == 8 => Ok(BigEndian::read_u64(step)),
+        Some(step) => {
+            Err(EngineError::BadSealFieldSize(OutOfBounds {
+                min: Some(8),
+                max: Some(8),
+                found: step.len(),
+            })
+            .into())
+        }
+        None => {
+            Err(BlockError::InvalidSealArity(Mismatch {
+                expected: 2,
+                found: 0,
+            })
+            .into())
+        }
+    }
+}
+
+fn header_signature(header: &Header) -> Result<Ed25519Signature, Error> {
+    match header.seal().get(1) {
+        Some(signature) if signature.len() == 96 => Ok(Ed25519Signature::from(signature.clone())),
+        Some(signature) => {
+            Err(EngineError::BadSealFieldSize(OutOfBounds {
+                min: Some(96),
+                max: Some(96),
+                found: signature.len(),
+            })
+            .into())
+        }
+        None => {
+            Err(BlockError::InvalidSealArity(Mismatch {
+                expected: 2,
+                found: header.seal().len(),
+            })
+            .into())
+        }
+    }
+}
+
+/// Message signed by the proposer: the bare header together with the step.
+fn seal_hash(header: &Header, step: u64) -> H256 {
+    let mut s = RlpStream::new_list(2);
+    s.append(&header.bare_hash()).append(&step);
+    blake2b(s.out())
+}
+
+/// Score of a block; the chain skipping the fewest steps is the heaviest.
+fn calculate_score(parent_step: u64, current_step: u64) -> U256 {
+    ((U256::one() << 128) - U256::one()) + U256::from(parent_step) - U256::from(current_step)
+}
+
+fn step_proposer(validators: &[Address], step: u64) -> Address {
+    validators[(step % validators.len() as u64) as usize]
+}
+
+// check that the header is signed by the proposer of its step.
+fn verify_signer(validators: &[Address], step: u64, header: &Header) -> Result<(), Error> {
+    if validators.is_empty() {
+        return Err(EngineError::InsufficientProof("Empty validator set.".into()).into());
+    }
+
+    let proposer = step_proposer(validators, step);
+    let signature = header_signature(header)?;
+    let public = signature.get_public();
+    let signer = public_to_address_ed25519(&public);
+
+    if signer != proposer {
+        trace!(target: "engine", "verify_signer: block {} from {} at step {}, expected {}", header.number(), signer, step, proposer);
+        return Err(EngineError::NotProposer(Mismatch {
+            expected: proposer,
+            found: signer,
+        })
+        .into());
+    }
+    if *header.author() != signer {
+        return Err(EngineError::NotAuthorized(*header.author()).into());
+    }
+    if !verify_signature_ed25519(public, signature, &seal_hash(header, step)) {
+        return Err(BlockError::InvalidSeal.into());
+    }
+    Ok(())
+}
+
+/// Verifies headers of an epoch against its validator set.
+struct ValidatorsVerifier {
+    validators: Vec<Address>,
+}
+
+impl EpochVerifier<EthereumMachine> for ValidatorsVerifier {
+    fn verify_light(&self, header: &Header) -> Result<(), Error> {
+        let step = header_step(header)?;
+        verify_signer(&self.validators, step, header)
+    }
+}
+
+struct TransitionHandler {
+    engine: Weak<AuthorityRound>,
+}
+
+impl TransitionHandler {
+    fn set_timeout(&self, io: &IoContext<()>, engine: &AuthorityRound) {
+        let remaining = engine.step.duration_remaining();
+        let ms = remaining.as_secs() * 1000 + remaining.subsec_nanos() as u64 / 1_000_000;
+        io.register_timer_once(ENGINE_TIMEOUT_TOKEN, ms)
+            .unwrap_or_else(|e| warn!(target: "engine", "Failed to start consensus step timer: {}.", e))
+    }
+}
+
+impl IoHandler<()> for TransitionHandler {
+    fn initialize(&self, io: &IoContext<()>) {
+        if let Some(engine) = self.engine.upgrade() {
+            self.set_timeout(io, &engine);
+        }
+    }
+
+    fn timeout(&self, io: &IoContext<()>, timer: TimerToken) {
+        if timer == ENGINE_TIMEOUT_TOKEN {
+            if let Some(engine) = self.engine.upgrade() {
+                engine.step();
+                self.set_timeout(io, &engine);
+            }
+        }
+    }
+}
+
+/// Engine using `AuthorityRound` proof-of-authority BFT consensus.
+pub struct AuthorityRound {
+    transition_service: IoService<()>,
+    step: Arc<Step>,
+    can_propose: AtomicBool,
+    client: RwLock<Option<Weak<EngineClient>>>,
+    signer: RwLock<Option<(Arc<AccountProvider>, Address)>>,
+    validators: ValidatorSet,
+    block_reward: U256,
+    machine: EthereumMachine,
+}
+
+impl AuthorityRound {
+    /// Create a new instance of AuthorityRound engine.
+    pub fn new(params: AuthorityRoundParams, machine: EthereumMachine) -> Result<Arc<Self>, Error> {
+        let should_timeout = params.start_step.is_none();
+        let initial_step = params
+            .start_step
+            .unwrap_or_else(|| unix_now().as_secs() / params.step_duration);
+        let engine = Arc::new(AuthorityRound {
+            transition_service: IoService::<()>::start()?,
+            step: Arc::new(Step {
+                calibrate: should_timeout,
+                inner: AtomicUsize::new(initial_step as usize),
+                duration: params.step_duration,
+            }),
+            can_propose: AtomicBool::new(true),
+            client: RwLock::new(None),
+            signer: RwLock::new(None),
+            validators: params.validators,
+            block_reward: params.block_reward,
+            machine: machine,
+        });
+
+        // Do not initialize timeouts for tests.
+        if should_timeout {
+            let handler = TransitionHandler {
+                engine: Arc::downgrade(&engine),
+            };
+            engine
+                .transition_service
+                .register_handler(Arc::new(handler))?;
+        }
+        Ok(engine)
+    }
+
+    // move on to the next step and give the proposer a chance to seal.
+    fn step(&self) {
+        self.step.increment();
+        self.can_propose.store(true, AtomicOrdering::SeqCst);
+        if let Some(ref weak) = *self.client.read() {
+            if let Some(client) = weak.upgrade() {
+                client.update_sealing();
+            }
+        }
+    }
+}
+
+impl Engine<EthereumMachine> for AuthorityRound {
+    fn name(&self) -> &str { "AuthorityRound" }
+
+    fn machine(&self) -> &EthereumMachine { &self.machine }
+
+    /// Two fields - the step and the signature of the proposer.
+    fn seal_fields(&self, _header: &Header) -> usize { 2 }
+
+    fn extra_info(&self, header: &Header) -> BTreeMap<String, String> {
+        let mut info = BTreeMap::new();
+        if let Ok(step) = header_step(header) {
+            info.insert("step".into(), step.to_string());
+        }
+        if let Some(signature) = header.seal().get(1) {
+            info.insert("signature".into(), to_hex(signature));
+        }
+        info
+    }
+
+    fn seals_internally(&self) -> Option<bool> { Some(self.signer.read().is_some()) }
+
+    /// Attempt to seal the block internally.
+    ///
+    /// This operation is synchronous and may (quite reasonably) not be available, in which case
+    /// `Seal::None` will be returned.
+    fn generate_seal(&self, block: &ExecutedBlock, parent: &Header) -> Seal {
+        // only one block may be proposed per step.
+        if !self.can_propose.load(AtomicOrdering::SeqCst) {
+            return Seal::None;
+        }
+
+        let (accounts, address) = match *self.signer.read() {
+            Some((ref accounts, address)) => (accounts.clone(), address),
+            None => return Seal::None,
+        };
+
+        let header = LiveBlock::header(block);
+        let step = self.step.load();
+        let parent_step = match header_step(parent) {
+            Ok(parent_step) => parent_step,
+            Err(e) => {
+                warn!(target: "engine", "Unable to read step of parent block {}: {}", parent.hash(), e);
+                return Seal::None;
+            }
+        };
+        if step <= parent_step {
+            trace!(target: "engine", "generate_seal: already sealed a block at step {}", step);
+            return Seal::None;
+        }
+
+        // the score commits to the step the block was prepared in.
+        if *header.difficulty() != calculate_score(parent_step, step) {
+            trace!(target: "engine", "generate_seal: block was prepared in an earlier step");
+            return Seal::None;
+        }
+
+        let validators = match self.validators.validators_at(header.parent_hash()) {
+            Ok(validators) => validators,
+            Err(e) => {
+                warn!(target: "engine", "Unable to get validator set: {}", e);
+                return Seal::None;
+            }
+        };
+        if validators.is_empty() || step_proposer(&validators, step) != address {
+            trace!(target: "engine", "generate_seal: not the proposer of step {}", step);
+            return Seal::None;
+        }
+        if *header.author() != address {
+            warn!(target: "engine", "Block author {} is not the engine signer {}, not sealing.", header.author(), address);
+            return Seal::None;
+        }
+
+        match accounts.sign(address, None, seal_hash(header, step)) {
+            Ok(signature) => {
+                trace!(target: "engine", "generate_seal: issuing a block for step {}", step);
+                self.can_propose.store(false, AtomicOrdering::SeqCst);
+                Seal::Regular(vec![u64_to_bytes(step), signature.to_vec()])
+            }
+            Err(e) => {
+                warn!(target: "engine", "generate_seal: failed to sign block: {}", e);
+                Seal::None
+            }
+        }
+    }
+
+    fn populate_from_parent(
+        &self,
+        header: &mut Header,
+        parent: &Header,
+        _grant_parent: Option<&Header>,
+    )
+    {
+        let parent_step = header_step(parent).unwrap_or(0);
+        let current_step = cmp::max(self.step.load(), parent_step + 1);
+        header.set_difficulty(calculate_score(parent_step, current_step));
+    }
+
+    fn on_close_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
+        let author = *LiveBlock::header(&*block).author();
+        let reward = self.block_reward;
+        if reward.is_zero() {
+            return Ok(());
+        }
+
+        block.header_mut().set_reward(reward);
+        self.machine.add_balance(block, &author, &reward)?;
+        self.machine.note_rewards(block, &[(author, reward)])
+    }
+
+    /// Internally generated seals are always valid.
+    fn verify_local_seal(&self, _header: &Header) -> Result<(), Error> { Ok(()) }
+
+    fn verify_block_basic(&self, header: &Header) -> Result<(), Error> {
+        let step = header_step(header)?;
+        header_signature(header)?;
+
+        match self.step.check_future(step) {
+            Ok(()) => Ok(()),
+            Err(None) => {
+                warn!(target: "engine", "verify_block_basic: block {} is from the far future (step {})", header.number(), step);
+                Err(BlockError::InvalidSeal.into())
+            }
+            Err(Some(oob)) => {
+                // the local clock may be behind; it will be accepted once the step comes.
+                self.step.calibrate();
+                Err(BlockError::TemporarilyInvalid(oob).into())
+            }
+        }
+    }
+
+    fn verify_block_family(
+        &self,
+        header: &Header,
+        parent: &Header,
+        _grant_parent: Option<&Header>,
+    ) -> Result<(), Error>
+    {
+        let step = header_step(header)?;
+        let parent_step = header_step(parent)?;
+
+        if step == parent_step {
+            warn!(target: "engine", "Multiple blocks proposed for step {}.", step);
+            return Err(EngineError::DoubleVote(*header.author()).into());
+        }
+        if step < parent_step {
+            return Err(BlockError::InvalidSeal.into());
+        }
+
+        let expected_score = calculate_score(parent_step, step);
+        if *header.difficulty() != expected_score {
+            return Err(BlockError::InvalidDifficulty(Mismatch {
+                expected: expected_score,
+                found: *header.difficulty(),
+            })
+            .into());
+        }
+
+        let validators = self.validators.validators_at(header.parent_hash())?;
+        verify_signer(&validators, step, header)
+    }
+
+    fn genesis_epoch_data<'a>(&self, _header: &Header, call: &Call<'a>) -> Result<Vec<u8>, String> {
+        self.validators.genesis_epoch_data(call)
+    }
+
+    fn signals_epoch_end<'a>(
+        &self,
+        header: &Header,
+        aux: AuxiliaryData<'a>,
+    ) -> EpochChange<EthereumMachine>
+    {
+        match self.validators.signals_change(header.parent_hash(), &aux) {
+            None => EpochChange::Unsure(AuxiliaryRequest::Receipts),
+            Some(None) => EpochChange::No,
+            Some(Some(validators)) => {
+                debug!(target: "engine", "Block {} signals a new validator set of {} authorities.", header.number(), validators.len());
+                EpochChange::Yes(Proof::Known(encode_proof(&validators)))
+            }
+        }
+    }
+
+    /// A signalled validator set is enacted at the child of the signalling block.
+    fn is_epoch_end(
+        &self,
+        chain_head: &Header,
+        _chain: &Headers<Header>,
+        transition_store: &PendingTransitionStore,
+    ) -> Option<Vec<u8>>
+    {
+        if chain_head.number() == 0 {
+            return None;
+        }
+        transition_store(*chain_head.parent_hash()).map(|pending| pending.proof)
+    }
+
+    fn epoch_verifier<'a>(
+        &self,
+        _header: &Header,
+        proof: &'a [u8],
+    ) -> ConstructedVerifier<'a, EthereumMachine>
+    {
+        match decode_proof(proof) {
+            Ok(validators) => {
+                ConstructedVerifier::Trusted(Box::new(ValidatorsVerifier {
+                    validators: validators,
+                }))
+            }
+            Err(e) => ConstructedVerifier::Err(e),
+        }
+    }
+
+    fn register_client(&self, client: Weak<EngineClient>) {
+        self.validators.register_client(client.clone());
+        *self.client.write() = Some(client);
+    }
+
+    fn set_signer(&self, accounts: Arc<AccountProvider>, address: Address) {
+        *self.signer.write() = Some((accounts, address));
+    }
+
+    fn stop(&self) { self.transition_service.stop() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use account_provider::AccountProvider;
+    use aion_types::Address;
+    use block::*;
+    use bytes::u64_to_bytes;
+    use engines::{EngineError, Seal};
+    use error::{BlockError, Error};
+    use header::Header;
+    use key::Ed25519Secret;
+    use kvdb::MemoryDBRepository;
+    use spec::Spec;
+    use tests::helpers::*;
+    use super::{calculate_score, seal_hash};
+
+    fn accounts() -> (Arc<AccountProvider>, Address, Address) {
+        let tap = Arc::new(AccountProvider::transient_provider());
+        let addr1 = tap
+            .insert_account_ed25519(Ed25519Secret::from_str("7ea8af7d0982509cd815096d35bc3a295f57b2a078e4e25731e3ea977b9544626702b86f33072a55f46003b1e3e242eb18556be54c5ab12044c3c20829e0abb5").unwrap(), "1")
+            .unwrap();
+        let addr2 = tap
+            .insert_account_ed25519(Ed25519Secret::from_str("5a90d8e67da5d1dfbf17916ae83bae04ef334f53ce8763932eba2c1116a62426fff4317ae351bda5e4fa24352904a9366d3a89e38d1ffa51498ba9acfbc65724").unwrap(), "2")
+            .unwrap();
+        tap.unlock_account_permanently(addr1, "1".into()).unwrap();
+        tap.unlock_account_permanently(addr2, "2".into()).unwrap();
+        (tap, addr1, addr2)
+    }
+
+    fn sealed_header(tap: &AccountProvider, author: Address, signer: Address, step: u64) -> Header {
+        let mut header = Header::default();
+        header.set_number(2);
+        header.set_author(author);
+        header.set_difficulty(calculate_score(1, step));
+        let signature = tap.sign(signer, None, seal_hash(&header, step)).unwrap();
+        header.set_seal(vec![u64_to_bytes(step), signature.to_vec()]);
+        header
+    }
+
+    #[test]
+    fn has_valid_metadata() {
+        let engine = Spec::new_test_round().engine;
+        assert_eq!(engine.name(), "AuthorityRound");
+        assert_eq!(engine.seal_fields(&Header::default()), 2);
+    }
+
+    #[test]
+    fn generates_seal_once_per_step() {
+        let (tap, addr1, addr2) = accounts();
+        let spec = Spec::new_test_round();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let last_hashes = Arc::new(vec![genesis_header.hash()]);
+        let open_block = |author: Address| {
+            let db = spec
+                .ensure_db_good(get_temp_state_db(), &Default::default())
+                .unwrap();
+            OpenBlock::new(
+                engine,
+                Default::default(),
+                db,
+                &genesis_header,
+                None,
+                last_hashes.clone(),
+                author,
+                (3141562.into(), 31415620.into()),
+                vec![],
+                false,
+                Arc::new(MemoryDBRepository::new()),
+            )
+            .unwrap()
+            .close_and_lock()
+        };
+        let b1 = open_block(addr1);
+        let b2 = open_block(addr2);
+
+        // step 2 belongs to the first validator.
+        engine.set_signer(tap.clone(), addr2);
+        assert_eq!(engine.generate_seal(b2.block(), &genesis_header), Seal::None);
+
+        engine.set_signer(tap.clone(), addr1);
+        match engine.generate_seal(b1.block(), &genesis_header) {
+            Seal::Regular(seal) => {
+                // no second block at the same step.
+                assert_eq!(engine.generate_seal(b1.block(), &genesis_header), Seal::None);
+                let sealed = b1.try_seal(engine, seal).ok().unwrap();
+                assert!(
+                    engine
+                        .verify_block_family(sealed.header(), &genesis_header, None)
+                        .is_ok()
+                );
+            }
+            _ => panic!("expected a regular seal"),
+        }
+    }
+
+    #[test]
+    fn rejects_seal_of_wrong_proposer() {
+        let (tap, addr1, addr2) = accounts();
+        let engine = Spec::new_test_round().engine;
+        let mut parent = Header::default();
+        parent.set_number(1);
+        parent.set_seal(vec![u64_to_bytes(1), vec![0u8; 96]]);
+
+        // step 3 belongs to the second validator.
+        let header = sealed_header(&tap, addr1, addr1, 3);
+        match engine.verify_block_family(&header, &parent, None) {
+            Err(Error::Engine(EngineError::NotProposer(_))) => {}
+            _ => panic!("should be rejected as not proposer"),
+        }
+
+        let header = sealed_header(&tap, addr1, addr2, 3);
+        match engine.verify_block_family(&header, &parent, None) {
+            Err(Error::Engine(EngineError::NotAuthorized(_))) => {}
+            _ => panic!("should be rejected as not authorized"),
+        }
+
+        let header = sealed_header(&tap, addr2, addr2, 3);
+        assert!(engine.verify_block_family(&header, &parent, None).is_ok());
+
+        let header = sealed_header(&tap, addr2, addr2, 1);
+        match engine.verify_block_family(&header, &parent, None) {
+            Err(Error::Engine(EngineError::DoubleVote(_))) => {}
+            _ => panic!("should be rejected as double vote"),
+        }
+    }
+
+    #[test]
+    fn rejects_future_steps() {
+        let (tap, _, addr2) = accounts();
+        let engine = Spec::new_test_round().engine;
+
+        // the test spec is fixed at step 2.
+        let header = sealed_header(&tap, addr2, addr2, 3);
+        match engine.verify_block_basic(&header) {
+            Err(Error::Block(BlockError::TemporarilyInvalid(_))) => {}
+            _ => panic!("should be temporarily invalid"),
+        }
+
+        let header = sealed_header(&tap, addr2, addr2, 11);
+        match engine.verify_block_basic(&header) {
+            Err(Error::Block(BlockError::InvalidSeal)) => {}
+            _ => panic!("should be invalid"),
+        }
+    }
+}
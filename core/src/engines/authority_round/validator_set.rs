@@ -0,0 +1,203 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Validator sets of the authority round engine.
+//!
+//! A set is either a fixed list taken from the spec or read from a contract. Contract sets
+//! change through the `InitiateChange` event; the new set is stored as the proof of the epoch
+//! transition and picked up again through `EngineClient::epoch_transition_for`.
+
+use std::sync::Weak;
+
+use abi::RawLog;
+use aion_types::{H256, Address};
+use ajson;
+use client::EngineClient;
+use engines::EngineError;
+use error::Error;
+use machine::{AuxiliaryData, Call};
+use parking_lot::{Mutex, RwLock};
+use receipt::Receipt;
+use rlp::{self, UntrustedRlp};
+
+use_contract!(validator_set, "ValidatorSet", "res/contracts/validator_set.json");
+
+/// Source of the authorities.
+pub enum ValidatorSet {
+    /// Fixed list of authorities.
+    List(Vec<Address>),
+    /// Authorities given by a contract.
+    Contract(ValidatorContract),
+}
+
+impl From<ajson::spec::ValidatorSet> for ValidatorSet {
+    fn from(set: ajson::spec::ValidatorSet) -> Self {
+        match set {
+            ajson::spec::ValidatorSet::List(list) => {
+                ValidatorSet::List(list.into_iter().map(Into::into).collect())
+            }
+            ajson::spec::ValidatorSet::Contract(address) => {
+                ValidatorSet::Contract(ValidatorContract::new(address.into()))
+            }
+        }
+    }
+}
+
+impl ValidatorSet {
+    /// Register the client used to look up epoch transitions.
+    pub fn register_client(&self, client: Weak<EngineClient>) {
+        if let ValidatorSet::Contract(ref contract) = *self {
+            *contract.client.write() = Some(client);
+        }
+    }
+
+    /// Proof of the validator set at genesis.
+    pub fn genesis_epoch_data(&self, call: &Call) -> Result<Vec<u8>, String> {
+        match *self {
+            ValidatorSet::List(ref list) => Ok(encode_proof(list)),
+            ValidatorSet::Contract(ref contract) => {
+                contract.get_validators(call).map(|list| encode_proof(&list))
+            }
+        }
+    }
+
+    /// New validator set signalled by the given block, if any.
+    /// `None` is returned when the receipts are needed but missing.
+    pub fn signals_change(
+        &self,
+        parent_hash: &H256,
+        aux: &AuxiliaryData,
+    ) -> Option<Option<Vec<Address>>>
+    {
+        match *self {
+            ValidatorSet::List(_) => Some(None),
+            ValidatorSet::Contract(ref contract) => {
+                aux.receipts
+                    .map(|receipts| contract.extract_change(parent_hash, receipts))
+            }
+        }
+    }
+
+    /// Validators of the epoch the child of `parent_hash` belongs to.
+    pub fn validators_at(&self, parent_hash: &H256) -> Result<Vec<Address>, Error> {
+        match *self {
+            ValidatorSet::List(ref list) => Ok(list.clone()),
+            ValidatorSet::Contract(ref contract) => contract.validators_at(parent_hash),
+        }
+    }
+}
+
+/// Validator set read from a contract.
+pub struct ValidatorContract {
+    address: Address,
+    contract: validator_set::ValidatorSet,
+    client: RwLock<Option<Weak<EngineClient>>>,
+    /// Set of the latest epoch looked up, keyed by the hash of its transition block.
+    cache: Mutex<Option<(H256, Vec<Address>)>>,
+}
+
+impl ValidatorContract {
+    /// Create a new contract validator set at the given address.
+    pub fn new(address: Address) -> Self {
+        ValidatorContract {
+            address: address,
+            contract: validator_set::ValidatorSet::default(),
+            client: RwLock::new(None),
+            cache: Mutex::new(None),
+        }
+    }
+
+    fn get_validators(&self, call: &Call) -> Result<Vec<Address>, String> {
+        let address = self.address;
+        self.contract
+            .functions()
+            .get_validators()
+            .call(&|data| call(address, data).map(|(output, _proof)| output))
+            .map_err(|e| format!("Unable to read validator set: {}", e))
+    }
+
+    fn extract_change(&self, parent_hash: &H256, receipts: &[Receipt]) -> Option<Vec<Address>> {
+        let event = self.contract.events().initiate_change();
+        receipts
+            .iter()
+            .flat_map(|receipt| receipt.logs().iter())
+            .filter(|log| log.address == self.address)
+            .filter(|log| log.topics.get(1) == Some(parent_hash))
+            .filter_map(|log| {
+                event
+                    .parse_log(RawLog::from((log.topics.clone(), log.data.clone())))
+                    .ok()
+            })
+            .map(|log| log.new_set)
+            .last()
+    }
+
+    fn validators_at(&self, parent_hash: &H256) -> Result<Vec<Address>, Error> {
+        let client = self
+            .client
+            .read()
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .ok_or(EngineError::RequiresClient)?;
+
+        let transition = client.epoch_transition_for(*parent_hash).ok_or_else(|| {
+            EngineError::InsufficientProof(format!("No epoch transition for {}", parent_hash))
+        })?;
+
+        let mut cache = self.cache.lock();
+        if let Some((ref hash, ref list)) = *cache {
+            if *hash == transition.block_hash {
+                return Ok(list.clone());
+            }
+        }
+
+        let list = decode_proof(&transition.proof)?;
+        *cache = Some((transition.block_hash, list.clone()));
+        Ok(list)
+    }
+}
+
+/// Encode a validator set as an epoch transition proof.
+pub fn encode_proof(validators: &[Address]) -> Vec<u8> {
+    rlp::encode_list::<Address, _>(validators).into_vec()
+}
+
+/// Decode a validator set from an epoch transition proof.
+pub fn decode_proof(proof: &[u8]) -> Result<Vec<Address>, Error> {
+    UntrustedRlp::new(proof).as_list().map_err(|e| {
+        EngineError::InsufficientProof(format!("Invalid validator set proof: {}", e)).into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use aion_types::Address;
+    use super::{encode_proof, decode_proof};
+
+    #[test]
+    fn proof_roundtrip() {
+        let validators: Vec<Address> = vec![1.into(), 2.into(), 3.into()];
+        let proof = encode_proof(&validators);
+        assert_eq!(decode_proof(&proof).unwrap(), validators);
+        assert!(decode_proof(&[0x01, 0x02]).is_err());
+    }
+}
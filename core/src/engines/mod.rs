@@ -24,10 +24,12 @@
 
 mod instant_seal;
 mod null_engine;
+pub mod authority_round;
 pub mod pow_equihash_engine;
 
 pub mod epoch;
 
+pub use self::authority_round::AuthorityRound;
 pub use self::epoch::{EpochVerifier, Transition as EpochTransition};
 pub use self::instant_seal::InstantSeal;
 pub use self::null_engine::NullEngine;
@@ -39,6 +41,7 @@ use std::fmt;
 
 use self::epoch::PendingTransition;
 
+use account_provider::AccountProvider;
 use precompiled::builtin::BuiltinContract;
 use error::Error;
 use header::{Header, BlockNumber};
@@ -241,6 +244,17 @@ pub trait Engine<M: Machine>: Sync + Send {
     /// Should only be called when `register_client` has been called previously.
     fn verify_block_external(&self, _header: &M::Header) -> Result<(), Error> { Ok(()) }
 
+    /// Verify a header which is not going to be imported, such as one synced in light mode:
+    /// its seal, and its difficulty against the ancestors `ancestor` looks up by hash.
+    fn verify_block_detached(
+        &self,
+        _header: &M::Header,
+        _ancestor: &Fn(&H256) -> Option<M::Header>,
+    ) -> Result<(), Error>
+    {
+        Ok(())
+    }
+
     /// Genesis epoch data.
     fn genesis_epoch_data<'a>(
         &self,
@@ -308,6 +322,18 @@ pub trait Engine<M: Machine>: Sync + Send {
     {
     }
 
+    /// Turn a freshly opened block into one sealed by the engine signer's stake, if the signer
+    /// may seal it now. Returns whether the header was changed.
+    fn prepare_staking_block(
+        &self,
+        _header: &mut M::Header,
+        _parent: &M::Header,
+        _grant_parent: Option<&M::Header>,
+    ) -> bool
+    {
+        false
+    }
+
     /// Handle any potential consensus messages;
     /// updating consensus state and potentially issuing a new one.
     fn handle_message(&self, _message: &[u8]) -> Result<(), EngineError> {
@@ -321,6 +347,9 @@ pub trait Engine<M: Machine>: Sync + Send {
     /// Add Client which can be used for sealing, potentially querying the state and sending messages.
     fn register_client(&self, _client: Weak<M::EngineClient>) {}
 
+    /// Register an account which signs consensus messages.
+    fn set_signer(&self, _account_provider: Arc<AccountProvider>, _address: Address) {}
+
     //    /// Trigger next step of the consensus engine.
     //    fn step(&self) {}
     //
@@ -366,8 +395,18 @@ pub trait EthEngine: Engine<::machine::EthereumMachine> {
     /// Additional verification for transactions in blocks.
     // TODO: Add flags for which bits of the transaction to check.
     // TODO: consider including State in the params.
-    fn verify_transaction_basic(&self, t: &UnverifiedTransaction) -> Result<(), Error> {
-        self.machine().verify_transaction_basic(t)
+    fn verify_transaction_basic(
+        &self,
+        t: &UnverifiedTransaction,
+        header: &Header,
+    ) -> Result<(), Error>
+    {
+        self.machine().verify_transaction_basic(t, header)
+    }
+
+    /// The chain id transactions included at the given block must be signed for, if any.
+    fn signing_chain_id(&self, block_number: BlockNumber) -> Option<u64> {
+        self.machine().signing_chain_id(block_number)
     }
 
     /// Additional information.
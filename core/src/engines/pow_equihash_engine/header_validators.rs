@@ -19,7 +19,7 @@
  *
  ******************************************************************************/
 
-use header::{Header, V1};
+use header::{Header, V1, V2};
 use equihash::EquihashValidator;
 use blake2b::Blake2b;
 use aion_types::U256;
@@ -79,11 +79,15 @@ impl HeaderValidator for EnergyConsumedValidator {
         Ok(())
     }
 }
-pub struct VersionValidator;
+/// Checks the header version. Staking-sealed (v2) headers are only accepted once the unity
+/// fork is active.
+pub struct VersionValidator {
+    pub unity_active: bool,
+}
 impl HeaderValidator for VersionValidator {
     fn validate(&self, header: &Header) -> Result<(), Error> {
         let version = header.version();
-        if version != V1 {
+        if version != V1 && !(self.unity_active && version == V2) {
             error!(target: "equihash", "Invalid header version, found version {}, expected {}", version, V1);
             return Err(BlockError::InvalidHeaderVersion(Mismatch {
                 expected: V1,
@@ -22,15 +22,22 @@
 mod header_validators;
 mod dependent_header_validators;
 mod grant_parent_header_validators;
+mod staking;
 
 use ajson;
 use machine::EthereumMachine;
-use std::sync::Arc;
-use engines::Engine;
-use aion_types::U256;
-use header::Header;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Weak};
+use std::time::{SystemTime, UNIX_EPOCH};
+use account_provider::AccountProvider;
+use engines::{Engine, EngineError, Seal};
+use aion_types::{H256, U256, Address};
+use header::{Header, SealType, V1};
 use block::ExecutedBlock;
-use error::Error;
+use client::{BlockId, EngineClient};
+use error::{BlockError, Error};
+use parking_lot::RwLock;
+use unexpected::{Mismatch, OutOfBounds};
 use std::cmp;
 
 use equihash::EquihashValidator;
@@ -49,6 +56,10 @@ use self::header_validators::{
     EquihashSolutionValidator
 };
 use self::grant_parent_header_validators::{GrantParentHeaderValidator, DifficultyValidator};
+use self::staking::{Stakes, StakingRegistry};
+
+/// How far back the previous blocks of the same seal type are looked up.
+const MAX_SEAL_PARENT_DISTANCE: u64 = 64;
 
 #[derive(Debug, PartialEq)]
 pub struct POWEquihashEngineParams {
@@ -62,10 +73,55 @@ pub struct POWEquihashEngineParams {
     pub block_time_lower_bound: u64,
     pub block_time_upper_bound: u64,
     pub minimum_difficulty: U256,
+    pub unity: Option<UnityParams>,
+}
+
+/// Parameters of the unity hybrid consensus, where blocks are sealed either by proof of work
+/// or by a staker.
+#[derive(Debug, PartialEq)]
+pub struct UnityParams {
+    /// First block which may be sealed by a staker.
+    pub fork_block: u64,
+    pub stake_block_time_lower_bound: u64,
+    pub stake_block_time_upper_bound: u64,
+    pub stake_minimum_difficulty: U256,
+    /// Difficulty of the first staking block.
+    pub stake_initial_difficulty: U256,
+    /// Staking registry contract. Takes precedence over `stakers`.
+    pub staking_registry: Option<Address>,
+    pub stakers: BTreeMap<Address, U256>,
 }
 
 impl From<ajson::spec::POWEquihashEngineParams> for POWEquihashEngineParams {
     fn from(p: ajson::spec::POWEquihashEngineParams) -> Self {
+        let block_time_lower_bound = p.block_time_lower_bound.map_or(5u64, Into::into);
+        let block_time_upper_bound = p.block_time_upper_bound.map_or(15u64, Into::into);
+        let unity = p.unity_fork_block.map(|fork_block| {
+            UnityParams {
+                fork_block: fork_block,
+                stake_block_time_lower_bound: p
+                    .stake_block_time_lower_bound
+                    .unwrap_or(block_time_lower_bound),
+                stake_block_time_upper_bound: p
+                    .stake_block_time_upper_bound
+                    .unwrap_or(block_time_upper_bound),
+                stake_minimum_difficulty: p
+                    .stake_minimum_difficulty
+                    .map_or(U256::from(16), Into::into),
+                stake_initial_difficulty: p.stake_initial_difficulty.map_or(
+                    U256::from(10_000_000_000u64) * U256::from(1_000_000_000u64),
+                    Into::into,
+                ),
+                staking_registry: p.staking_registry.clone().map(Into::into),
+                stakers: p
+                    .stakers
+                    .clone()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(staker, stake)| (staker.into(), stake.into()))
+                    .collect(),
+            }
+        });
         POWEquihashEngineParams {
             rampup_upper_bound: p.rampup_upper_bound.map_or(U256::from(259200), Into::into),
             rampup_lower_bound: p.rampup_lower_bound.map_or(U256::zero(), Into::into),
@@ -84,9 +140,10 @@ impl From<ajson::spec::POWEquihashEngineParams> for POWEquihashEngineParams {
             difficulty_bound_divisor: p
                 .difficulty_bound_divisor
                 .map_or(U256::from(2048), Into::into),
-            block_time_lower_bound: p.block_time_lower_bound.map_or(5u64, Into::into),
-            block_time_upper_bound: p.block_time_upper_bound.map_or(15u64, Into::into),
+            block_time_lower_bound: block_time_lower_bound,
+            block_time_upper_bound: block_time_upper_bound,
             minimum_difficulty: p.minimum_difficulty.map_or(U256::from(16), Into::into),
+            unity: unity,
         }
     }
 }
@@ -97,17 +154,45 @@ pub struct DifficultyCalc {
     block_time_lower_bound: u64,
     block_time_upper_bound: u64,
     minimum_difficulty: U256,
+    stake_block_time_lower_bound: u64,
+    stake_block_time_upper_bound: u64,
+    stake_minimum_difficulty: U256,
+    stake_initial_difficulty: U256,
 }
 
 impl DifficultyCalc {
     pub fn new(params: &POWEquihashEngineParams) -> DifficultyCalc {
+        let (stake_lower_bound, stake_upper_bound, stake_minimum, stake_initial) =
+            match params.unity {
+                Some(ref unity) => {
+                    (
+                        unity.stake_block_time_lower_bound,
+                        unity.stake_block_time_upper_bound,
+                        unity.stake_minimum_difficulty,
+                        unity.stake_initial_difficulty,
+                    )
+                }
+                None => {
+                    (
+                        params.block_time_lower_bound,
+                        params.block_time_upper_bound,
+                        params.minimum_difficulty,
+                        params.minimum_difficulty,
+                    )
+                }
+            };
         DifficultyCalc {
             difficulty_bound_divisor: params.difficulty_bound_divisor,
             block_time_lower_bound: params.block_time_lower_bound,
             block_time_upper_bound: params.block_time_upper_bound,
             minimum_difficulty: params.minimum_difficulty,
+            stake_block_time_lower_bound: stake_lower_bound,
+            stake_block_time_upper_bound: stake_upper_bound,
+            stake_minimum_difficulty: stake_minimum,
+            stake_initial_difficulty: stake_initial,
         }
     }
+
     pub fn calculate_difficulty(
         &self,
         header: &Header,
@@ -125,8 +210,74 @@ impl DifficultyCalc {
             panic!("grant_parent must exist.");
         }
 
-        let parent_difficulty = *parent.difficulty();
+        let delta = parent.timestamp() - grant_parent.unwrap().timestamp();
+        self.adjust(
+            *parent.difficulty(),
+            delta,
+            self.block_time_lower_bound,
+            self.block_time_upper_bound,
+            self.minimum_difficulty,
+        )
+    }
+
+    /// Difficulty of a block after the unity fork, given the last two blocks with the same
+    /// seal type. Each seal type keeps its own difficulty track.
+    pub fn calculate_seal_difficulty(
+        &self,
+        seal_type: SealType,
+        seal_parent: Option<&Header>,
+        seal_grant_parent: Option<&Header>,
+    ) -> U256
+    {
+        let seal_parent = match seal_parent {
+            Some(seal_parent) => seal_parent,
+            None => {
+                return match seal_type {
+                    SealType::PoW => self.minimum_difficulty,
+                    SealType::PoS => self.stake_initial_difficulty,
+                };
+            }
+        };
+        let seal_grant_parent = match seal_grant_parent {
+            Some(seal_grant_parent) => seal_grant_parent,
+            None => return *seal_parent.difficulty(),
+        };
+
+        let (lower_bound, upper_bound, minimum_difficulty) = match seal_type {
+            SealType::PoW => {
+                (
+                    self.block_time_lower_bound,
+                    self.block_time_upper_bound,
+                    self.minimum_difficulty,
+                )
+            }
+            SealType::PoS => {
+                (
+                    self.stake_block_time_lower_bound,
+                    self.stake_block_time_upper_bound,
+                    self.stake_minimum_difficulty,
+                )
+            }
+        };
+        let delta = seal_parent.timestamp() - seal_grant_parent.timestamp();
+        self.adjust(
+            *seal_parent.difficulty(),
+            delta,
+            lower_bound,
+            upper_bound,
+            minimum_difficulty,
+        )
+    }
 
+    fn adjust(
+        &self,
+        parent_difficulty: U256,
+        delta: u64,
+        block_time_lower_bound: u64,
+        block_time_upper_bound: u64,
+        minimum_difficulty: U256,
+    ) -> U256
+    {
         let mut diff_base = parent_difficulty / self.difficulty_bound_divisor;
 
         // if smaller than our bound divisor, always round up
@@ -134,31 +285,27 @@ impl DifficultyCalc {
             diff_base = U256::one();
         }
 
-        let current_timestamp = parent.timestamp();
-        let parent_timestamp = grant_parent.unwrap().timestamp();
-
-        let delta = current_timestamp - parent_timestamp;
         let bound_domain = 10;
 
         // split into our ranges 0 <= x <= min_block_time, min_block_time < x <
         // max_block_time, max_block_time < x
         let mut output_difficulty: U256;
-        if delta <= self.block_time_lower_bound {
+        if delta <= block_time_lower_bound {
             output_difficulty = parent_difficulty + diff_base;
-        } else if self.block_time_lower_bound < delta && delta < self.block_time_upper_bound {
+        } else if block_time_lower_bound < delta && delta < block_time_upper_bound {
             output_difficulty = parent_difficulty;
         } else {
             let bound_quotient =
-                U256::from(((delta - self.block_time_upper_bound) / bound_domain) + 1);
+                U256::from(((delta - block_time_upper_bound) / bound_domain) + 1);
             let lower_bound = U256::from(99);
             let multiplier = cmp::min(bound_quotient, lower_bound);
             if parent_difficulty > multiplier * diff_base {
                 output_difficulty = parent_difficulty - multiplier * diff_base;
             } else {
-                output_difficulty = self.minimum_difficulty;
+                output_difficulty = minimum_difficulty;
             }
         }
-        output_difficulty = cmp::max(output_difficulty, self.minimum_difficulty);
+        output_difficulty = cmp::max(output_difficulty, minimum_difficulty);
         output_difficulty
     }
 }
@@ -202,10 +349,17 @@ impl RewardsCalculator {
 }
 
 /// Engine using Equihash proof-of-work concensus algorithm.
+///
+/// From the unity fork block on, blocks may alternatively be sealed by stakers. Two staking
+/// blocks can't follow each other, so the chain keeps going on proof of work alone.
 pub struct POWEquihashEngine {
     machine: EthereumMachine,
     rewards_calculator: RewardsCalculator,
     difficulty_calc: DifficultyCalc,
+    unity_fork_block: Option<u64>,
+    stakes: Stakes,
+    client: RwLock<Option<Weak<EngineClient>>>,
+    signer: RwLock<Option<(Arc<AccountProvider>, Address)>>,
 }
 
 impl POWEquihashEngine {
@@ -213,13 +367,31 @@ impl POWEquihashEngine {
     pub fn new(params: POWEquihashEngineParams, machine: EthereumMachine) -> Arc<Self> {
         let rewards_calculator = RewardsCalculator::new(&params);
         let difficulty_calc = DifficultyCalc::new(&params);
+        let unity_fork_block = params.unity.as_ref().map(|unity| unity.fork_block);
+        let stakes = match params.unity {
+            Some(UnityParams {
+                staking_registry: Some(address),
+                ..
+            }) => Stakes::Registry(StakingRegistry::new(address)),
+            Some(unity) => Stakes::Fixed(unity.stakers),
+            None => Stakes::Fixed(BTreeMap::new()),
+        };
         Arc::new(POWEquihashEngine {
             machine,
             rewards_calculator,
             difficulty_calc,
+            unity_fork_block,
+            stakes,
+            client: RwLock::new(None),
+            signer: RwLock::new(None),
         })
     }
 
+    fn is_unity(&self, number: u64) -> bool {
+        self.unity_fork_block
+            .map_or(false, |fork_block| number >= fork_block)
+    }
+
     fn calculate_difficulty(
         &self,
         header: &Header,
@@ -227,26 +399,167 @@ impl POWEquihashEngine {
         grant_parent: Option<&Header>,
     ) -> U256
     {
-        self.difficulty_calc
-            .calculate_difficulty(header, parent, grant_parent)
+        if self.is_unity(header.number()) {
+            let seal_type = header.seal_type();
+            let (seal_parent, seal_grant_parent) = self.seal_parents(
+                seal_type,
+                parent,
+                grant_parent,
+                &|hash: &H256| self.known_header(hash),
+            );
+            self.difficulty_calc.calculate_seal_difficulty(
+                seal_type,
+                seal_parent.as_ref(),
+                seal_grant_parent.as_ref(),
+            )
+        } else {
+            self.difficulty_calc
+                .calculate_difficulty(header, parent, grant_parent)
+        }
     }
 
-    fn calculate_reward(&self, header: &Header) -> U256 {
-        self.rewards_calculator.calculate_reward(header)
+    /// Header of a block known to the client.
+    fn known_header(&self, hash: &H256) -> Option<Header> {
+        let client = self.client.read().as_ref().and_then(Weak::upgrade)?;
+        client
+            .block_header(BlockId::Hash(*hash))
+            .map(|header| header.decode())
     }
 
-    pub fn validate_block_header(header: &Header) -> Result<(), Error> {
-        let mut block_header_validators: Vec<Box<HeaderValidator>> = Vec::with_capacity(4);
-        block_header_validators.push(Box::new(VersionValidator {}));
-        block_header_validators.push(Box::new(EnergyConsumedValidator {}));
-        block_header_validators.push(Box::new(POWValidator {}));
+    /// The last two ancestors sealed the same way as `seal_type`, starting from `parent`.
+    /// Ancestors older than the parent and grant parent are looked up by hash with `ancestor`.
+    fn seal_parents(
+        &self,
+        seal_type: SealType,
+        parent: &Header,
+        grant_parent: Option<&Header>,
+        ancestor: &Fn(&H256) -> Option<Header>,
+    ) -> (Option<Header>, Option<Header>)
+    {
+        let fork_block = self.unity_fork_block.unwrap_or(u64::max_value());
+        let number = parent.number() + 1;
 
-        for v in block_header_validators.iter() {
-            v.validate(header)?;
+        let mut found = Vec::with_capacity(2);
+        let mut grant_parent = grant_parent.cloned();
+        let mut next = Some(parent.clone());
+        while let Some(header) = next {
+            if number - header.number() > MAX_SEAL_PARENT_DISTANCE {
+                break;
+            }
+            // no block before the fork is sealed by a staker.
+            if seal_type == SealType::PoS && header.number() < fork_block {
+                break;
+            }
+            next = match grant_parent.take() {
+                Some(grant_parent) => Some(grant_parent),
+                None if header.number() == 0 => None,
+                None => ancestor(header.parent_hash()),
+            };
+            if header.seal_type() == seal_type {
+                found.push(header);
+                if found.len() == 2 {
+                    break;
+                }
+            }
+        }
+
+        let mut found = found.into_iter();
+        (found.next(), found.next())
+    }
+
+    /// Check a header against its parent and grant parent. Older ancestors are looked up
+    /// by hash with `ancestor`.
+    fn verify_family(
+        &self,
+        header: &Header,
+        parent: &Header,
+        grant_parent: Option<&Header>,
+        ancestor: &Fn(&H256) -> Option<Header>,
+    ) -> Result<(), Error>
+    {
+        // verify parent
+        let mut parent_validators: Vec<Box<DependentHeaderValidator>> = Vec::with_capacity(3);
+        parent_validators.push(Box::new(NumberValidator {}));
+        parent_validators.push(Box::new(TimestampValidator {}));
+        for v in parent_validators.iter() {
+            v.validate(header, parent)?;
+        }
+
+        if self.is_unity(header.number()) {
+            return self.verify_unity_family(header, parent, grant_parent, ancestor);
         }
 
+        // verify grant parent
+        let mut grant_validators: Vec<Box<GrantParentHeaderValidator>> = Vec::with_capacity(1);
+        grant_validators.push(Box::new(DifficultyValidator {
+            difficulty_calc: &self.difficulty_calc,
+        }));
+        for v in grant_validators.iter() {
+            v.validate(header, parent, grant_parent)?;
+        }
+
+        Ok(())
+    }
+
+    fn verify_unity_family(
+        &self,
+        header: &Header,
+        parent: &Header,
+        grant_parent: Option<&Header>,
+        ancestor: &Fn(&H256) -> Option<Header>,
+    ) -> Result<(), Error>
+    {
+        let seal_type = header.seal_type();
+        if seal_type == SealType::PoS && parent.seal_type() == SealType::PoS {
+            trace!(target: "equihash", "block {}: staking block on top of a staking block", header.number());
+            return Err(BlockError::InvalidHeaderVersion(Mismatch {
+                expected: V1,
+                found: header.version(),
+            })
+            .into());
+        }
+
+        let (seal_parent, seal_grant_parent) =
+            self.seal_parents(seal_type, parent, grant_parent, ancestor);
+        let difficulty = *header.difficulty();
+        let calc_difficulty = self.difficulty_calc.calculate_seal_difficulty(
+            seal_type,
+            seal_parent.as_ref(),
+            seal_grant_parent.as_ref(),
+        );
+        if difficulty != calc_difficulty {
+            return Err(BlockError::InvalidDifficulty(Mismatch {
+                expected: calc_difficulty,
+                found: difficulty,
+            })
+            .into());
+        }
+
+        if seal_type == SealType::PoW {
+            return Ok(());
+        }
+
+        staking::verify_seed(header, seal_parent.as_ref().map_or(&[][..], staking::seed))?;
+        let stake = self.stakes.stake_of(header.author(), &parent.hash())?;
+        if stake.is_zero() {
+            return Err(EngineError::NotAuthorized(*header.author()).into());
+        }
+        let delay = staking::staking_delay(&difficulty, staking::seed(header), &stake);
+        let earliest = parent.timestamp().saturating_add(delay);
+        if header.timestamp() < earliest {
+            return Err(BlockError::InvalidTimestamp(OutOfBounds {
+                min: Some(earliest),
+                max: None,
+                found: header.timestamp(),
+            })
+            .into());
+        }
         Ok(())
     }
+
+    fn calculate_reward(&self, header: &Header) -> U256 {
+        self.rewards_calculator.calculate_reward(header)
+    }
 }
 
 impl Engine<EthereumMachine> for Arc<POWEquihashEngine> {
@@ -271,6 +584,116 @@ impl Engine<EthereumMachine> for Arc<POWEquihashEngine> {
         header.set_difficulty(difficulty);
     }
 
+    fn prepare_staking_block(
+        &self,
+        header: &mut Header,
+        parent: &Header,
+        grant_parent: Option<&Header>,
+    ) -> bool
+    {
+        if !self.is_unity(header.number()) || parent.seal_type() == SealType::PoS {
+            return false;
+        }
+        let (accounts, staker) = match *self.signer.read() {
+            Some((ref accounts, staker)) => (accounts.clone(), staker),
+            None => return false,
+        };
+
+        let stake = match self.stakes.stake_of(&staker, &parent.hash()) {
+            Ok(stake) => stake,
+            Err(e) => {
+                warn!(target: "equihash", "Unable to read stake of {}: {}", staker, e);
+                return false;
+            }
+        };
+        if stake.is_zero() {
+            trace!(target: "equihash", "prepare_staking_block: {} has no stake", staker);
+            return false;
+        }
+
+        let (seal_parent, seal_grant_parent) = self.seal_parents(
+            SealType::PoS,
+            parent,
+            grant_parent,
+            &|hash: &H256| self.known_header(hash),
+        );
+        let parent_seed = seal_parent.as_ref().map_or(&[][..], staking::seed);
+        let seed = match accounts.sign(staker, None, staking::seed_message(parent_seed)) {
+            Ok(seed) => seed,
+            Err(e) => {
+                warn!(target: "equihash", "prepare_staking_block: failed to sign seed: {}", e);
+                return false;
+            }
+        };
+        let difficulty = self.difficulty_calc.calculate_seal_difficulty(
+            SealType::PoS,
+            seal_parent.as_ref(),
+            seal_grant_parent.as_ref(),
+        );
+        let timestamp = parent
+            .timestamp()
+            .saturating_add(staking::staking_delay(&difficulty, &seed[..], &stake));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if timestamp > now {
+            trace!(target: "equihash", "prepare_staking_block: {} may seal in {}s", staker, timestamp - now);
+            return false;
+        }
+
+        header.set_seal_type(SealType::PoS);
+        header.set_difficulty(difficulty);
+        header.set_author(staker);
+        header.set_timestamp(timestamp);
+        true
+    }
+
+    fn generate_seal(&self, block: &ExecutedBlock, parent: &Header) -> Seal {
+        use aion_machine::LiveBlock;
+
+        let header = LiveBlock::header(block);
+        if header.seal_type() != SealType::PoS {
+            return Seal::None;
+        }
+        let (accounts, staker) = match *self.signer.read() {
+            Some((ref accounts, staker)) => (accounts.clone(), staker),
+            None => return Seal::None,
+        };
+        if *header.author() != staker {
+            warn!(target: "equihash", "Block author {} is not the staker {}, not sealing.", header.author(), staker);
+            return Seal::None;
+        }
+
+        let (seal_parent, _) = self.seal_parents(SealType::PoS, parent, None, &|hash: &H256| {
+            self.known_header(hash)
+        });
+        let parent_seed = seal_parent.as_ref().map_or(&[][..], staking::seed);
+        let seal = accounts
+            .sign(staker, None, staking::seed_message(parent_seed))
+            .and_then(|seed| {
+                accounts
+                    .sign(staker, None, header.bare_hash())
+                    .map(|signature| vec![seed.to_vec(), signature.to_vec()])
+            });
+        match seal {
+            Ok(seal) => Seal::Regular(seal),
+            Err(e) => {
+                warn!(target: "equihash", "generate_seal: failed to sign staking block: {}", e);
+                Seal::None
+            }
+        }
+    }
+
+    fn register_client(&self, client: Weak<EngineClient>) {
+        self.stakes.register_client(client.clone());
+        *self.client.write() = Some(client);
+    }
+
+    fn set_signer(&self, accounts: Arc<AccountProvider>, address: Address) {
+        *self.signer.write() = Some((accounts, address));
+    }
+
     fn on_close_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
         use aion_machine::{LiveBlock, WithBalances};
 
@@ -295,18 +718,30 @@ impl Engine<EthereumMachine> for Arc<POWEquihashEngine> {
 
     fn verify_block_basic(&self, header: &Header) -> Result<(), Error> {
         let mut cheap_validators: Vec<Box<HeaderValidator>> = Vec::with_capacity(4);
-        cheap_validators.push(Box::new(VersionValidator {}));
+        cheap_validators.push(Box::new(VersionValidator {
+            unity_active: self.is_unity(header.number()),
+        }));
         cheap_validators.push(Box::new(EnergyConsumedValidator {}));
-        cheap_validators.push(Box::new(POWValidator {}));
+        if header.seal_type() == SealType::PoW {
+            cheap_validators.push(Box::new(POWValidator {}));
+        }
 
         for v in cheap_validators.iter() {
             v.validate(header)?;
         }
 
+        if header.seal_type() == SealType::PoS {
+            staking::verify_seal_fields(header)?;
+        }
+
         Ok(())
     }
 
     fn verify_block_unordered(&self, header: &Header) -> Result<(), Error> {
+        if header.seal_type() == SealType::PoS {
+            return staking::verify_block_signature(header).map(|_| ());
+        }
+
         let mut costly_validators: Vec<Box<HeaderValidator>> = Vec::with_capacity(1);
         costly_validators.push(Box::new(EquihashSolutionValidator {
             solution_validator: EquihashValidator::new(210, 9),
@@ -324,33 +759,51 @@ impl Engine<EthereumMachine> for Arc<POWEquihashEngine> {
         grant_parent: Option<&Header>,
     ) -> Result<(), Error>
     {
-        // verify parent
-        let mut parent_validators: Vec<Box<DependentHeaderValidator>> = Vec::with_capacity(3);
-        parent_validators.push(Box::new(NumberValidator {}));
-        parent_validators.push(Box::new(TimestampValidator {}));
-        for v in parent_validators.iter() {
-            v.validate(header, parent)?;
-        }
-
-        // verify grant parent
-        let mut grant_validators: Vec<Box<GrantParentHeaderValidator>> = Vec::with_capacity(1);
-        grant_validators.push(Box::new(DifficultyValidator {
-            difficulty_calc: &self.difficulty_calc,
-        }));
-        for v in grant_validators.iter() {
-            v.validate(header, parent, grant_parent)?;
-        }
+        self.verify_family(header, parent, grant_parent, &|hash: &H256| {
+            self.known_header(hash)
+        })
+    }
 
-        Ok(())
+    fn verify_block_detached(
+        &self,
+        header: &Header,
+        ancestor: &Fn(&H256) -> Option<Header>,
+    ) -> Result<(), Error>
+    {
+        self.verify_block_basic(header)?;
+        self.verify_block_unordered(header)?;
+        let parent = ancestor(header.parent_hash())
+            .ok_or_else(|| BlockError::UnknownParent(*header.parent_hash()))?;
+        let grant_parent = match parent.number() {
+            0 => None,
+            _ => ancestor(parent.parent_hash()),
+        };
+        // stakes kept in a registry are read from the state of the parent, which a node
+        // without that state can't check; such staking blocks fail here.
+        self.verify_family(header, &parent, grant_parent.as_ref(), ancestor)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use account_provider::AccountProvider;
+    use aion_types::{Address, H256};
+    use block::*;
+    use engines::{EngineError, Seal};
+    use error::{BlockError, Error};
+    use header::{SealType, V2};
+    use key::Ed25519Secret;
+    use kvdb::MemoryDBRepository;
+    use spec::Spec;
+    use tests::helpers::*;
     use super::Header;
     use super::U256;
     use super::RewardsCalculator;
     use super::POWEquihashEngineParams;
+    use super::UnityParams;
     use super::DifficultyCalc;
 
     #[test]
@@ -366,6 +819,7 @@ mod tests {
             block_time_lower_bound: 0u64,
             block_time_upper_bound: 0u64,
             minimum_difficulty: U256::zero(),
+            unity: None,
         };
         let calculator = RewardsCalculator::new(&params);
         let mut header = Header::default();
@@ -389,6 +843,7 @@ mod tests {
             block_time_lower_bound: 0u64,
             block_time_upper_bound: 0u64,
             minimum_difficulty: U256::zero(),
+            unity: None,
         };
         let calculator = RewardsCalculator::new(&params);
         let mut header = Header::default();
@@ -412,6 +867,7 @@ mod tests {
             block_time_lower_bound: 0u64,
             block_time_upper_bound: 0u64,
             minimum_difficulty: U256::zero(),
+            unity: None,
         };
         let calculator = RewardsCalculator::new(&params);
         let mut header = Header::default();
@@ -435,6 +891,7 @@ mod tests {
             block_time_lower_bound: 0u64,
             block_time_upper_bound: 0u64,
             minimum_difficulty: U256::zero(),
+            unity: None,
         };
         let calculator = RewardsCalculator::new(&params);
         let mut header = Header::default();
@@ -458,6 +915,7 @@ mod tests {
             block_time_lower_bound: 5u64,
             block_time_upper_bound: 15u64,
             minimum_difficulty: U256::from(16),
+            unity: None,
         };
         let calculator = DifficultyCalc::new(&params);
         let mut header = Header::default();
@@ -486,6 +944,7 @@ mod tests {
             block_time_lower_bound: 5u64,
             block_time_upper_bound: 15u64,
             minimum_difficulty: U256::from(16),
+            unity: None,
         };
         let calculator = DifficultyCalc::new(&params);
         let mut header = Header::default();
@@ -514,6 +973,7 @@ mod tests {
             block_time_lower_bound: 5u64,
             block_time_upper_bound: 15u64,
             minimum_difficulty: U256::from(16),
+            unity: None,
         };
         let calculator = DifficultyCalc::new(&params);
         let mut header = Header::default();
@@ -542,6 +1002,7 @@ mod tests {
             block_time_lower_bound: 5u64,
             block_time_upper_bound: 15u64,
             minimum_difficulty: U256::from(16),
+            unity: None,
         };
         let calculator = DifficultyCalc::new(&params);
         let mut header = Header::default();
@@ -558,4 +1019,244 @@ mod tests {
         assert_eq!(difficulty, U256::from(2999));
     }
 
+    #[test]
+    fn test_calculate_seal_difficulty() {
+        let params = POWEquihashEngineParams {
+            rampup_upper_bound: U256::zero(),
+            rampup_lower_bound: U256::zero(),
+            rampup_start_value: U256::zero(),
+            rampup_end_value: U256::zero(),
+            lower_block_reward: U256::zero(),
+            upper_block_reward: U256::zero(),
+            difficulty_bound_divisor: U256::from(2048),
+            block_time_lower_bound: 5u64,
+            block_time_upper_bound: 15u64,
+            minimum_difficulty: U256::from(16),
+            unity: Some(UnityParams {
+                fork_block: 1,
+                stake_block_time_lower_bound: 8u64,
+                stake_block_time_upper_bound: 12u64,
+                stake_minimum_difficulty: U256::from(1),
+                stake_initial_difficulty: U256::from(4096),
+                staking_registry: None,
+                stakers: BTreeMap::new(),
+            }),
+        };
+        let calculator = DifficultyCalc::new(&params);
+        assert_eq!(
+            calculator.calculate_seal_difficulty(SealType::PoS, None, None),
+            U256::from(4096)
+        );
+
+        let mut seal_parent = Header::default();
+        seal_parent.set_timestamp(1524528010u64);
+        seal_parent.set_difficulty(U256::from(4096));
+        assert_eq!(
+            calculator.calculate_seal_difficulty(SealType::PoS, Some(&seal_parent), None),
+            U256::from(4096)
+        );
+
+        // 10s is in the target range of the pow track but too fast for the stake track.
+        let mut seal_grant_parent = Header::default();
+        seal_grant_parent.set_timestamp(1524528003u64);
+        assert_eq!(
+            calculator.calculate_seal_difficulty(
+                SealType::PoS,
+                Some(&seal_parent),
+                Some(&seal_grant_parent)
+            ),
+            U256::from(4098)
+        );
+        assert_eq!(
+            calculator.calculate_seal_difficulty(
+                SealType::PoW,
+                Some(&seal_parent),
+                Some(&seal_grant_parent)
+            ),
+            U256::from(4096)
+        );
+    }
+
+    fn accounts() -> (Arc<AccountProvider>, Address, Address) {
+        let tap = Arc::new(AccountProvider::transient_provider());
+        let staker = tap
+            .insert_account_ed25519(Ed25519Secret::from_str("7ea8af7d0982509cd815096d35bc3a295f57b2a078e4e25731e3ea977b9544626702b86f33072a55f46003b1e3e242eb18556be54c5ab12044c3c20829e0abb5").unwrap(), "1")
+            .unwrap();
+        let other = tap
+            .insert_account_ed25519(Ed25519Secret::from_str("5a90d8e67da5d1dfbf17916ae83bae04ef334f53ce8763932eba2c1116a62426fff4317ae351bda5e4fa24352904a9366d3a89e38d1ffa51498ba9acfbc65724").unwrap(), "2")
+            .unwrap();
+        tap.unlock_account_permanently(staker, "1".into()).unwrap();
+        tap.unlock_account_permanently(other, "2".into()).unwrap();
+        (tap, staker, other)
+    }
+
+    #[test]
+    fn seals_both_seal_types_after_fork() {
+        let (tap, staker, other) = accounts();
+        let spec = Spec::new_unity_test();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let db = spec
+            .ensure_db_good(get_temp_state_db(), &Default::default())
+            .unwrap();
+        let mut open_block = OpenBlock::new(
+            engine,
+            Default::default(),
+            db,
+            &genesis_header,
+            None,
+            Arc::new(vec![genesis_header.hash()]),
+            Address::default(),
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            Arc::new(MemoryDBRepository::new()),
+        )
+        .unwrap();
+
+        // only accounts with a stake may seal.
+        engine.set_signer(tap.clone(), other);
+        assert!(!engine.prepare_staking_block(
+            open_block.block_mut().header_mut(),
+            &genesis_header,
+            None
+        ));
+
+        engine.set_signer(tap.clone(), staker);
+        assert!(engine.prepare_staking_block(
+            open_block.block_mut().header_mut(),
+            &genesis_header,
+            None
+        ));
+        let locked = open_block.close_and_lock();
+        let seal = match engine.generate_seal(locked.block(), &genesis_header) {
+            Seal::Regular(seal) => seal,
+            _ => panic!("expected a regular seal"),
+        };
+        let sealed = locked.seal(engine, seal).ok().unwrap();
+        let staked = sealed.header();
+        assert_eq!(staked.seal_type(), SealType::PoS);
+        assert_eq!(*staked.author(), staker);
+        assert!(engine.verify_block_basic(staked).is_ok());
+        assert!(engine.verify_block_unordered(staked).is_ok());
+        assert!(
+            engine
+                .verify_block_family(staked, &genesis_header, None)
+                .is_ok()
+        );
+
+        // the pow track carries on from genesis.
+        let mut mined = Header::default();
+        mined.set_number(2);
+        mined.set_parent_hash(staked.hash());
+        mined.set_timestamp(staked.timestamp() + 1);
+        mined.set_difficulty(*genesis_header.difficulty());
+        assert!(
+            engine
+                .verify_block_family(&mined, staked, Some(&genesis_header))
+                .is_ok()
+        );
+
+        // no staking block on top of a staking block.
+        mined.set_version(V2);
+        match engine.verify_block_family(&mined, staked, Some(&genesis_header)) {
+            Err(Error::Block(BlockError::InvalidHeaderVersion(_))) => {}
+            _ => panic!("should be rejected as a second staking block in a row"),
+        }
+    }
+
+    #[test]
+    fn rejects_staking_header_before_unity_fork() {
+        let engine = Spec::new_unity_test().engine;
+        let mut header = Header::default();
+        header.set_seal_type(SealType::PoS);
+        header.set_seal(vec![vec![0u8; 96], vec![0u8; 96]]);
+        match engine.verify_block_basic(&header) {
+            Err(Error::Block(BlockError::InvalidHeaderVersion(_))) => {}
+            _ => panic!("should be rejected as a staking header before the fork"),
+        }
+
+        header.set_number(1);
+        assert!(engine.verify_block_basic(&header).is_ok());
+    }
+
+    #[test]
+    fn rejects_staking_block_of_wrong_author() {
+        let (tap, staker, other) = accounts();
+        let engine = Spec::new_unity_test().engine;
+
+        let mut header = Header::default();
+        header.set_number(1);
+        header.set_seal_type(SealType::PoS);
+        header.set_author(other);
+        let seed = tap.sign(staker, None, super::staking::seed_message(&[])).unwrap();
+        let signature = tap.sign(staker, None, header.bare_hash()).unwrap();
+        header.set_seal(vec![seed.to_vec(), signature.to_vec()]);
+        match engine.verify_block_unordered(&header) {
+            Err(Error::Engine(EngineError::NotAuthorized(_))) => {}
+            _ => panic!("should be rejected as not authorized"),
+        }
+    }
+
+    fn staking_header(
+        tap: &AccountProvider,
+        staker: Address,
+        parent: &Header,
+        difficulty: U256,
+    ) -> Header
+    {
+        let mut header = Header::default();
+        header.set_number(parent.number() + 1);
+        header.set_parent_hash(parent.hash());
+        header.set_seal_type(SealType::PoS);
+        header.set_author(staker);
+        header.set_difficulty(difficulty);
+        let seed = tap.sign(staker, None, super::staking::seed_message(&[])).unwrap();
+        let delay = super::staking::staking_delay(&difficulty, &seed[..], &U256::one());
+        header.set_timestamp(parent.timestamp() + delay);
+        let signature = tap.sign(staker, None, header.bare_hash()).unwrap();
+        header.set_seal(vec![seed.to_vec(), signature.to_vec()]);
+        header
+    }
+
+    #[test]
+    fn verifies_detached_staking_header_against_its_ancestors() {
+        let (tap, staker, other) = accounts();
+        let spec = Spec::new_unity_test();
+        let engine = &*spec.engine;
+        let genesis_header = spec.genesis_header();
+        let ancestor = |hash: &H256| {
+            if *hash == genesis_header.hash() {
+                Some(genesis_header.clone())
+            } else {
+                None
+            }
+        };
+
+        let staked = staking_header(&tap, staker, &genesis_header, U256::one());
+        assert!(engine.verify_block_detached(&staked, &ancestor).is_ok());
+
+        // well signed, but by an account without any stake.
+        let forged = staking_header(&tap, other, &genesis_header, U256::one());
+        assert!(engine.verify_block_basic(&forged).is_ok());
+        assert!(engine.verify_block_unordered(&forged).is_ok());
+        match engine.verify_block_detached(&forged, &ancestor) {
+            Err(Error::Engine(EngineError::NotAuthorized(_))) => {}
+            _ => panic!("should be rejected as not authorized"),
+        }
+
+        // a staker claiming more difficulty than the chain allows.
+        let heavy = staking_header(&tap, staker, &genesis_header, U256::from(1000));
+        match engine.verify_block_detached(&heavy, &ancestor) {
+            Err(Error::Block(BlockError::InvalidDifficulty(_))) => {}
+            _ => panic!("should be rejected for its difficulty"),
+        }
+
+        // nothing to check against without the parent.
+        let orphan = staking_header(&tap, staker, &staked, U256::one());
+        match engine.verify_block_detached(&orphan, &ancestor) {
+            Err(Error::Block(BlockError::UnknownParent(_))) => {}
+            _ => panic!("should be rejected without its parent"),
+        }
+    }
 }
@@ -0,0 +1,294 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Staking side of the unity hybrid consensus.
+//!
+//! After the unity fork a block may be sealed by a staker instead of an equihash solution.
+//! The seal of such a block holds two ed25519 signatures of the staker: the seed, signing the
+//! seed of the previous staking block, and the signature of the block itself. A staker may seal
+//! once `staking_delay` seconds have passed since the parent; the delay shrinks with the stake
+//! and is randomized by the seed.
+
+use std::collections::BTreeMap;
+use std::sync::Weak;
+
+use aion_types::{H256, U256, U512, Address};
+use blake2b::blake2b;
+use bytes::Bytes;
+use client::{BlockId, EngineClient};
+use engines::EngineError;
+use error::{BlockError, Error};
+use header::Header;
+use key::{public_to_address_ed25519, verify_signature_ed25519, Ed25519Signature};
+use parking_lot::RwLock;
+use unexpected::{Mismatch, OutOfBounds};
+
+use_contract!(staking_registry, "StakingRegistry", "res/contracts/staking_registry.json");
+
+/// Upper bound of the staking delay, in seconds.
+const MAX_STAKING_DELAY: u64 = 0xffff_ffff;
+
+/// Fractional bits of the fixed point numbers the staking delay is computed with.
+const FRACTION_BITS: usize = 64;
+
+/// `ln(2)` in fixed point, rounded down.
+const LN_2: u64 = 0xb172_17f7_d1cf_79ab;
+
+/// Source of the stakes.
+pub enum Stakes {
+    /// Fixed stakes taken from the spec.
+    Fixed(BTreeMap<Address, U256>),
+    /// Stakes registered in a contract.
+    Registry(StakingRegistry),
+}
+
+impl Stakes {
+    /// Register the client used to call the registry.
+    pub fn register_client(&self, client: Weak<EngineClient>) {
+        if let Stakes::Registry(ref registry) = *self {
+            *registry.client.write() = Some(client);
+        }
+    }
+
+    /// Stake of `staker` in the state of the block `parent_hash`.
+    pub fn stake_of(&self, staker: &Address, parent_hash: &H256) -> Result<U256, Error> {
+        match *self {
+            Stakes::Fixed(ref stakes) => Ok(stakes.get(staker).cloned().unwrap_or_else(U256::zero)),
+            Stakes::Registry(ref registry) => registry.stake_of(staker, parent_hash),
+        }
+    }
+}
+
+/// Stakes read from a staking registry contract.
+pub struct StakingRegistry {
+    address: Address,
+    contract: staking_registry::StakingRegistry,
+    client: RwLock<Option<Weak<EngineClient>>>,
+}
+
+impl StakingRegistry {
+    /// Create a new staking registry at the given address.
+    pub fn new(address: Address) -> Self {
+        StakingRegistry {
+            address: address,
+            contract: staking_registry::StakingRegistry::default(),
+            client: RwLock::new(None),
+        }
+    }
+
+    fn stake_of(&self, staker: &Address, parent_hash: &H256) -> Result<U256, Error> {
+        let client = self
+            .client
+            .read()
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .ok_or(EngineError::RequiresClient)?;
+        let full_client = client.as_full_client().ok_or(EngineError::RequiresClient)?;
+
+        let address = self.address;
+        self.contract
+            .functions()
+            .get_stake()
+            .call(*staker, &|data| {
+                full_client.call_contract(BlockId::Hash(*parent_hash), address, data)
+            })
+            .map_err(|e| {
+                EngineError::InsufficientProof(format!("Unable to read stake of {}: {}", staker, e))
+                    .into()
+            })
+    }
+}
+
+/// Message signed by the seed of a staking block, given the seed of the previous one.
+pub fn seed_message(parent_seed: &[u8]) -> H256 { blake2b(parent_seed) }
+
+/// Seed of a staking block.
+pub fn seed(header: &Header) -> &[u8] {
+    header.seal().get(0).map_or(&[][..], |seed| seed.as_slice())
+}
+
+/// Seconds a staker has to wait after the parent block before sealing.
+///
+/// The delay is `difficulty * ln(2^256 / hash(seed)) / stake`, rounded up and at least one
+/// second, so it is exponentially distributed with a mean of `difficulty / stake`. It decides
+/// the validity of blocks, so it is computed in fixed point rather than floating point, whose
+/// `ln` may round differently from one platform to another.
+pub fn staking_delay(difficulty: &U256, seed: &[u8], stake: &U256) -> u64 {
+    if stake.is_zero() {
+        return MAX_STAKING_DELAY;
+    }
+    let hash = at_least_one(U256::from(&*blake2b(seed)));
+    // ln(2^256 / hash) = (256 - log2(hash)) * ln(2)
+    let log = (U256::from(256) << FRACTION_BITS) - log2_fixed(&hash);
+    let ln = (log * U256::from(LN_2)) >> FRACTION_BITS;
+
+    let numerator = difficulty.full_mul(ln);
+    let denominator = U512::from(*stake) << FRACTION_BITS;
+    let delay = (numerator + denominator - U512::one()) / denominator;
+    if delay >= U512::from(MAX_STAKING_DELAY) {
+        MAX_STAKING_DELAY
+    } else if delay.is_zero() {
+        1
+    } else {
+        delay.low_u64()
+    }
+}
+
+/// Binary logarithm of a non-zero value, rounded down, in fixed point with `FRACTION_BITS`
+/// fractional bits.
+fn log2_fixed(value: &U256) -> U256 {
+    let integer = value.bits() - 1;
+    // the value scaled into [1, 2).
+    let mut mantissa = if integer > FRACTION_BITS {
+        *value >> (integer - FRACTION_BITS)
+    } else {
+        *value << (FRACTION_BITS - integer)
+    };
+    let two = U256::one() << (FRACTION_BITS + 1);
+
+    // each squaring of the mantissa yields the next fractional bit.
+    let mut log = U256::from(integer) << FRACTION_BITS;
+    for bit in (0..FRACTION_BITS).rev() {
+        mantissa = (mantissa * mantissa) >> FRACTION_BITS;
+        if mantissa >= two {
+            mantissa = mantissa >> 1;
+            log = log | (U256::one() << bit);
+        }
+    }
+    log
+}
+
+fn at_least_one(value: U256) -> U256 {
+    if value.is_zero() {
+        U256::one()
+    } else {
+        value
+    }
+}
+
+fn seal_signature(field: &Bytes) -> Result<Ed25519Signature, Error> {
+    if field.len() != 96 {
+        return Err(EngineError::BadSealFieldSize(OutOfBounds {
+            min: Some(96),
+            max: Some(96),
+            found: field.len(),
+        })
+        .into());
+    }
+    Ok(Ed25519Signature::from(field.clone()))
+}
+
+/// Check the arity and the field sizes of a staking seal.
+pub fn verify_seal_fields(header: &Header) -> Result<(), Error> {
+    let seal = header.seal();
+    if seal.len() != 2 {
+        return Err(BlockError::InvalidSealArity(Mismatch {
+            expected: 2,
+            found: seal.len(),
+        })
+        .into());
+    }
+    seal_signature(&seal[0])?;
+    seal_signature(&seal[1])?;
+    Ok(())
+}
+
+/// Check the block signature of a staking seal and return the staker.
+pub fn verify_block_signature(header: &Header) -> Result<Address, Error> {
+    verify_seal_fields(header)?;
+    let seed = seal_signature(&header.seal()[0])?;
+    let signature = seal_signature(&header.seal()[1])?;
+
+    let public = signature.get_public();
+    if seed.get_public() != public {
+        trace!(target: "equihash", "block {}: seed and seal signed by different keys", header.number());
+        return Err(BlockError::InvalidSeal.into());
+    }
+    let staker = public_to_address_ed25519(&public);
+    if *header.author() != staker {
+        return Err(EngineError::NotAuthorized(*header.author()).into());
+    }
+    if !verify_signature_ed25519(public, signature, &header.bare_hash()) {
+        return Err(BlockError::InvalidSeal.into());
+    }
+    Ok(staker)
+}
+
+/// Check the seed of a staking seal against the seed of the previous staking block.
+pub fn verify_seed(header: &Header, parent_seed: &[u8]) -> Result<(), Error> {
+    let seed = seal_signature(header.seal().get(0).ok_or(BlockError::InvalidSeal)?)?;
+    let public = seed.get_public();
+    if !verify_signature_ed25519(public, seed, &seed_message(parent_seed)) {
+        trace!(target: "equihash", "block {}: invalid seed", header.number());
+        return Err(BlockError::InvalidSeal.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use aion_types::U256;
+    use super::{log2_fixed, staking_delay};
+
+    #[test]
+    fn staking_delay_shrinks_with_stake() {
+        let difficulty = U256::from(1000);
+        let seed = [7u8; 96];
+        let small = staking_delay(&difficulty, &seed, &U256::from(10));
+        let large = staking_delay(&difficulty, &seed, &U256::from(1000));
+        assert!(small > large);
+        assert!(large >= 1);
+        assert_eq!(
+            staking_delay(&difficulty, &seed, &U256::zero()),
+            super::MAX_STAKING_DELAY
+        );
+    }
+
+    #[test]
+    fn log2_fixed_vectors() {
+        let log2 = |value: U256| log2_fixed(&value);
+        assert_eq!(log2(1.into()), U256::zero());
+        assert_eq!(log2(2.into()), U256::one() << 64);
+        assert_eq!(log2(3.into()), "195c01a39fbd6879f".into());
+        assert_eq!(log2(1000.into()), "9f73da38d9d4a83eb".into());
+        assert_eq!(log2(U256::one() << 255), U256::from(255) << 64);
+        assert_eq!(log2(U256::max_value()), "ffffffffffffffffff".into());
+    }
+
+    #[test]
+    fn staking_delay_vectors() {
+        let delay = |difficulty: U256, seed: &[u8], stake: U256| {
+            staking_delay(&difficulty, seed, &stake)
+        };
+        assert_eq!(delay(1000.into(), &[7u8; 96], 10.into()), 115);
+        assert_eq!(delay(1000.into(), &[7u8; 96], 1000.into()), 2);
+        assert_eq!(
+            delay(U256::one() << 64, &[1u8; 96], 3_000_000_000_000_000_000u64.into()),
+            4
+        );
+        assert_eq!(delay(16.into(), &[0u8; 96], 1.into()), 24);
+        assert_eq!(
+            delay(1_000_000_000_000u64.into(), &[], 1.into()),
+            super::MAX_STAKING_DELAY
+        );
+    }
+}
@@ -145,8 +145,22 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
     ) -> Result<Executed, ExecutionError>
     {
         let sender = t.sender();
+        let gas_payer = t.gas_payer();
+        let gas_cost = t.gas.saturating_mul(t.gas_price);
+        if gas_payer != sender {
+            // give the fee payer a sufficient balance for the energy
+            let balance = self.state.balance(&gas_payer)?;
+            if balance < gas_cost {
+                self.state
+                    .add_balance(&gas_payer, &(gas_cost - balance), CleanupMode::NoEmpty)?;
+            }
+        }
         let balance = self.state.balance(&sender)?;
-        let needed_balance = t.value.saturating_add(t.gas.saturating_mul(t.gas_price));
+        let needed_balance = if gas_payer == sender {
+            t.value.saturating_add(gas_cost)
+        } else {
+            t.value
+        };
         if balance < needed_balance {
             // give the sender a sufficient balance
             self.state
@@ -176,6 +190,13 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             }));
         }
 
+        // 1.1 Fee-delegated transactions are only valid once fee delegation is active
+        if t.is_fee_delegated() && !self.machine.fee_delegation_active(self.info.number) {
+            return Err(From::from(ExecutionError::TransactionMalformed(
+                "fee delegation is not active".into(),
+            )));
+        }
+
         // 2. Check gas limit
         // 2.1 Gas limit should not be less than the basic gas requirement
         let base_gas_required: U256 = t.gas_required();
@@ -213,15 +234,31 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
 
         // 3. Check balance, avoid unaffordable transactions
         // TODO: we might need bigints here, or at least check overflows.
+        // The energy of a fee-delegated transaction is charged to the fee payer,
+        // the sender then only needs to afford the value.
+        let gas_payer = t.gas_payer();
         let balance: U512 = U512::from(self.state.balance(&sender)?);
         let gas_cost: U512 = t.gas.full_mul(t.gas_price);
-        let total_cost: U512 = U512::from(t.value) + gas_cost;
+        let total_cost: U512 = if gas_payer == sender {
+            U512::from(t.value) + gas_cost
+        } else {
+            U512::from(t.value)
+        };
         if balance < total_cost {
             return Err(From::from(ExecutionError::NotEnoughCash {
                 required: total_cost,
                 got: balance,
             }));
         }
+        if gas_payer != sender {
+            let gas_payer_balance: U512 = U512::from(self.state.balance(&gas_payer)?);
+            if gas_payer_balance < gas_cost {
+                return Err(From::from(ExecutionError::NotEnoughCash {
+                    required: gas_cost,
+                    got: gas_payer_balance,
+                }));
+            }
+        }
 
         // Deduct the basic gas requirement and pass the remaining gas limit to VM
         let init_gas = t.gas - base_gas_required;
@@ -231,11 +268,11 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         // Transactions filtered above are rejected and not included in the current block.
 
         // Increment nonce of the sender and deduct the cost of the entire gas limit from
-        // the sender's account. After VM execution, gas left (not used) shall be refunded
-        // (if applicable) to the sender's account.
+        // the gas payer's account. After VM execution, gas left (not used) shall be refunded
+        // (if applicable) to the gas payer's account.
         self.state.inc_nonce(&sender)?;
         self.state.sub_balance(
-            &sender,
+            &gas_payer,
             &U256::from(gas_cost),
             &mut substate.to_cleanup_mode(),
         )?;
@@ -582,16 +619,16 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         );
 
         // Transfer refund and transaction fee.
-        let sender = t.sender();
+        let gas_payer = t.gas_payer();
         trace!(
             target: "executive",
-            "exec::finalize: Refunding refund_value={}, sender={}\n",
+            "exec::finalize: Refunding refund_value={}, gas_payer={}\n",
             refund_value,
-            sender
+            gas_payer
         );
-        // Below: NoEmpty is safe since the sender must already be non-null to have sent this transaction
+        // Below: NoEmpty is safe since the gas payer must already be non-null to have paid for it
         self.state
-            .add_balance(&sender, &refund_value, CleanupMode::NoEmpty)?;
+            .add_balance(&gas_payer, &refund_value, CleanupMode::NoEmpty)?;
         trace!(
             target: "executive",
             "exec::finalize: Compensating author: fees_value={}, author={}\n",
@@ -1588,6 +1625,69 @@ Address};
         );
     }
 
+    #[test]
+    fn fee_delegated_transfer() {
+        use key::generate_keypair;
+        use transaction::{FEE_DELEGATED_TRANSACTION_TYPE, UnverifiedTransaction};
+
+        let sender = generate_keypair();
+        let fee_payer = generate_keypair();
+        let receiver = Address::from(0x10);
+        let mut params = make_frontier_machine().params().clone();
+        params.fee_delegation_transition = Some(0);
+        let machine = EthereumMachine::regular(params, ::std::collections::BTreeMap::new());
+        let mut info = EnvInfo::default();
+        info.gas_limit = U256::from(3_000_000);
+        info.author = Address::from(1);
+        let mut state = get_temp_state();
+        state
+            .add_balance(&sender.address(), &U256::from(100), CleanupMode::NoEmpty)
+            .unwrap();
+        state
+            .add_balance(&fee_payer.address(), &U256::from(100_000), CleanupMode::NoEmpty)
+            .unwrap();
+
+        let mut transaction: Transaction = Transaction::new(
+            U256::zero(),
+            U256::from(1),
+            U256::from(50_000),
+            Action::Call(receiver),
+            100.into(),
+            Bytes::new(),
+        );
+        transaction.transaction_type = FEE_DELEGATED_TRANSACTION_TYPE;
+        let unverified: UnverifiedTransaction = transaction.sign(&sender.secret(), None).into();
+        let signed_transaction = unverified.sign_fee_payer(&fee_payer.secret());
+        assert_eq!(signed_transaction.sender(), sender.address());
+        assert_eq!(signed_transaction.gas_payer(), fee_payer.address());
+
+        // rejected while fee delegation is not active.
+        let inactive = make_frontier_machine();
+        let error = {
+            let mut ex = Executive::new(&mut state, &info, &inactive);
+            ex.transact(&signed_transaction, true, false).unwrap_err()
+        };
+        assert_eq!(
+            error,
+            ExecutionError::TransactionMalformed("fee delegation is not active".into())
+        );
+
+        let executed = {
+            let mut ex = Executive::new(&mut state, &info, &machine);
+            ex.transact(&signed_transaction, true, false).unwrap()
+        };
+
+        // the sender only pays the value, the fee payer pays for the energy used.
+        assert_eq!(state.balance(&sender.address()).unwrap(), U256::zero());
+        assert_eq!(state.balance(&receiver).unwrap(), U256::from(100));
+        assert_eq!(
+            state.balance(&fee_payer.address()).unwrap(),
+            U256::from(100_000) - executed.gas_used
+        );
+        assert_eq!(state.nonce(&sender.address()).unwrap(), U256::from(1));
+        assert_eq!(state.nonce(&fee_payer.address()).unwrap(), U256::zero());
+    }
+
     #[test]
     fn error_cases_revert() {
         let code = "605060405234156100105760006000fd5b610015565b610199806100246000396000f30060506040526000356c01000000000000000000000000900463ffffffff1680632d7df21a146100335761002d565b60006000fd5b341561003f5760006000fd5b6100666004808080601001359035909160200190919290803590601001909190505061007c565b6040518082815260100191505060405180910390f35b6000600060007f66fa32225b641331dff20698cd66d310b3149e86d875926af7ea2f2a9079e80b856040518082815260100191505060405180910390a18585915091506001841115156100d55783925061016456610163565b60018282632d7df21a898960018a036000604051601001526040518463ffffffff166c010000000000000000000000000281526004018084848252816010015260200182815260100193505050506010604051808303816000888881813b151561013f5760006000fd5b5af1151561014d5760006000fd5b5050505060405180519060100150019250610164565b5b505093925050505600a165627a7a72305820c4755a8b960e01280a2c8d85fae255d08e1be318b2c2685a948e7b42660c2f5c0029".from_hex().unwrap();
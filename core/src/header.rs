@@ -65,6 +65,30 @@ pub enum Seal {
 // define more versions here.
 /// header version v1
 pub const V1: HeaderVersion = 1;
+/// header version v2, staking-sealed blocks of the unity hybrid consensus
+pub const V2: HeaderVersion = 2;
+
+/// Seal type of a block.
+///
+/// From the unity fork on the version field tells how a block is sealed; older blocks are all
+/// sealed by proof of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SealType {
+    /// Sealed by an equihash solution.
+    PoW,
+    /// Sealed by the signature of a staker.
+    PoS,
+}
+
+impl SealType {
+    /// Header version carrying this seal type.
+    pub fn version(&self) -> HeaderVersion {
+        match *self {
+            SealType::PoW => V1,
+            SealType::PoS => V2,
+        }
+    }
+}
 
 /// A block header.
 ///
@@ -173,6 +197,13 @@ impl Header {
     pub fn new() -> Self { Self::default() }
     /// Get version field of the header
     pub fn version(&self) -> HeaderVersion { self.version }
+    /// Get the seal type of the header, as given by its version.
+    pub fn seal_type(&self) -> SealType {
+        match self.version {
+            V2 => SealType::PoS,
+            _ => SealType::PoW,
+        }
+    }
 
     /// Get the parent_hash field of the header.
     pub fn parent_hash(&self) -> &H256 { &self.parent_hash }
@@ -232,6 +263,9 @@ impl Header {
         self.note_dirty();
     }
 
+    /// Set the seal type of the header.
+    pub fn set_seal_type(&mut self, seal_type: SealType) { self.set_version(seal_type.version()); }
+
     /// Set the number field of the header.
     pub fn set_parent_hash(&mut self, a: H256) {
         self.parent_hash = a;
@@ -106,6 +106,7 @@ extern crate tokio;
 
 pub mod account_provider;
 pub mod block;
+pub mod cht;
 pub mod client;
 pub mod transaction;
 pub mod db;
@@ -118,6 +119,7 @@ pub mod header;
 pub mod machine;
 pub mod miner;
 pub mod pod_state;
+pub mod proof;
 pub mod service;
 pub mod spec;
 pub mod state;
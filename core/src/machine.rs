@@ -194,9 +194,39 @@ impl EthereumMachine {
         Ok(SignedTransaction::new(t)?)
     }
 
+    /// The chain id transactions included at the given block must be signed for, if any.
+    pub fn signing_chain_id(&self, block_number: BlockNumber) -> Option<u64> {
+        match self.params().chain_id_transition {
+            Some(transition) if block_number >= transition => Some(self.params().chain_id),
+            _ => None,
+        }
+    }
+
+    /// Whether fee-delegated transactions are accepted in the block with the given number.
+    pub fn fee_delegation_active(&self, block_number: BlockNumber) -> bool {
+        match self.params().fee_delegation_transition {
+            Some(transition) => block_number >= transition,
+            None => false,
+        }
+    }
+
     /// Does basic verification of the transaction.
-    pub fn verify_transaction_basic(&self, t: &UnverifiedTransaction) -> Result<(), Error> {
-        t.verify_basic(None)?;
+    /// Once the chain id transition is reached, transactions must be bound to the spec chain id.
+    /// Fee-delegated transactions are rejected before the fee delegation transition.
+    pub fn verify_transaction_basic(
+        &self,
+        t: &UnverifiedTransaction,
+        header: &Header,
+    ) -> Result<(), Error>
+    {
+        let chain_id = self.signing_chain_id(header.number());
+        if chain_id.is_some() && t.chain_id().is_none() {
+            return Err(transaction::Error::InvalidChainId.into());
+        }
+        if t.is_fee_delegated() && !self.fee_delegation_active(header.number()) {
+            return Err(transaction::Error::FeeDelegationNotActive.into());
+        }
+        t.verify_basic(chain_id)?;
 
         Ok(())
     }
@@ -36,7 +36,7 @@ use parking_lot::{Mutex, RwLock};
 use transaction::banning_queue::{BanningTransactionQueue, Threshold};
 use transaction::local_transactions::{Status as LocalTransactionStatus, TxIoMessage};
 use transaction::transaction_queue::{
-    AccountDetails, PrioritizationStrategy, RemovalReason,
+    AccountDetails, PrioritizationStrategy, QueueListener, RemovalReason,
     TransactionDetailsProvider as TransactionQueueDetailsProvider, TransactionOrigin,
     TransactionQueue,
 };
@@ -46,7 +46,7 @@ use transaction::{
     UnverifiedTransaction,
 };
 use using_queue::{GetAction, UsingQueue};
-use block::{ClosedBlock, IsBlock, Block};
+use block::{ClosedBlock, IsBlock, Block, OpenBlock};
 use client::{MiningBlockChainClient, BlockId, TransactionId};
 use executive::contract_address;
 use header::{Header, BlockNumber};
@@ -167,6 +167,7 @@ pub struct Miner {
     options: MinerOptions,
     gas_range_target: RwLock<(U256, U256)>,
     author: RwLock<Address>,
+    staker: RwLock<Option<Address>>,
     extra_data: RwLock<Bytes>,
     engine: Arc<EthEngine>,
     accounts: Option<Arc<AccountProvider>>,
@@ -234,6 +235,7 @@ impl Miner {
             }),
             gas_range_target: RwLock::new((U256::zero(), U256::zero())),
             author: RwLock::new(Address::default()),
+            staker: RwLock::new(None),
             extra_data: RwLock::new(Vec::new()),
             options: options,
             accounts: accounts,
@@ -246,6 +248,17 @@ impl Miner {
     /// get the interval to prepare a new / update an existing block
     pub fn prepare_block_interval(&self) -> Duration { self.options.prepare_block_interval.clone() }
 
+    /// Set the account sealing blocks with its stake, once the engine allows it.
+    pub fn set_staker(&self, staker: Option<Address>) {
+        if let Some(staker) = staker {
+            match self.accounts {
+                Some(ref accounts) => self.engine.set_signer(accounts.clone(), staker),
+                None => warn!(target: "block", "No account provider, staker {} can't sign blocks.", staker),
+            }
+        }
+        *self.staker.write() = staker;
+    }
+
     /// Replace tx message channel. Useful for testing.
     pub fn set_tx_message_channel(&self, tx_message: IoChannel<TxIoMessage>) {
         *self.tx_message.lock() = tx_message;
@@ -297,6 +310,16 @@ impl Miner {
         self.transaction_listener.write().push(f);
     }
 
+    /// Set a callback to be told about every transaction entering or leaving the queue.
+    pub fn add_queue_listener(&self, listener: QueueListener) {
+        self.transaction_queue.write().add_listener(listener);
+    }
+
+    /// Transaction waiting in the queue, whether pending or not.
+    pub fn queued_transaction(&self, hash: &H256) -> Option<PendingTransaction> {
+        self.transaction_queue.read().find(hash)
+    }
+
     fn map_pending_block<F, T>(&self, f: F, latest_block_number: BlockNumber) -> Option<T>
     where F: FnOnce(&ClosedBlock) -> T {
         self.from_pending_block(latest_block_number, || None, |block| Some(f(block)))
@@ -353,6 +376,19 @@ impl Miner {
             (transactions, open_block, last_work_hash)
         };
 
+        self.push_transactions(client, &mut open_block, transactions);
+        let block = open_block.close();
+        (block, original_work_hash)
+    }
+
+    /// Pushes transactions into an open block, dropping or penalizing the bad ones in the queue.
+    fn push_transactions(
+        &self,
+        client: &MiningBlockChainClient,
+        open_block: &mut OpenBlock,
+        transactions: Vec<SignedTransaction>,
+    )
+    {
         let mut invalid_transactions = HashSet::new();
         let mut non_allowed_transactions = HashSet::new();
         let mut transactions_to_penalize = HashSet::new();
@@ -378,7 +414,12 @@ impl Miner {
             //     }
             //     _ => open_block.push_transaction(tx, None),
             // };
-            let result = open_block.push_transaction(tx, None);
+            // Queued transactions may predate the chain id or fee delegation transition.
+            let verified = self
+                .engine
+                .verify_transaction_basic(&tx, open_block.block().header());
+            let result =
+                verified.and_then(|_| open_block.push_transaction(tx, None).map(|_| ()));
             let took = start.elapsed();
 
             // Check for heavy transactions
@@ -450,8 +491,6 @@ impl Miner {
         }
         trace!(target: "block", "Pushed {}/{} transactions", tx_count, tx_total);
 
-        let block = open_block.close();
-
         let fetch_nonce = |a: &Address| client.latest_nonce(a);
 
         {
@@ -466,7 +505,66 @@ impl Miner {
                 queue.penalize(&hash);
             }
         }
-        (block, original_work_hash)
+    }
+
+    /// Seals a block with the stake of the configured staker and imports it, if the engine lets
+    /// the staker seal on top of the best block now.
+    fn seal_staking_block(&self, client: &MiningBlockChainClient) -> bool {
+        let staker = match *self.staker.read() {
+            Some(staker) => staker,
+            None => return false,
+        };
+
+        let mut open_block = client.prepare_open_block(
+            staker,
+            (self.gas_floor_target(), self.gas_ceil_target()),
+            self.extra_data(),
+        );
+        let parent = match client.block_header(BlockId::Hash(*open_block.header().parent_hash())) {
+            Some(header) => header.decode(),
+            None => return false,
+        };
+        let grant_parent = match parent.number() {
+            0 => None,
+            _ => {
+                client
+                    .block_header(BlockId::Hash(*parent.parent_hash()))
+                    .map(|header| header.decode())
+            }
+        };
+        if !self.engine.prepare_staking_block(
+            open_block.block_mut().header_mut(),
+            &parent,
+            grant_parent.as_ref(),
+        ) {
+            return false;
+        }
+
+        trace!(target: "block", "seal_staking_block: sealing block {} as {}", parent.number() + 1, staker);
+        let transactions = self
+            .transaction_queue
+            .read()
+            .top_transactions_at(parent.number(), parent.timestamp());
+        self.push_transactions(client, &mut open_block, transactions);
+        let block = open_block.close();
+
+        match self.engine.generate_seal(block.block(), &parent) {
+            Seal::Regular(seal) => {
+                block
+                    .lock()
+                    .seal(&*self.engine, seal)
+                    .map(|sealed| client.import_sealed_block(sealed).is_ok())
+                    .unwrap_or_else(|e| {
+                        warn!(
+                            target: "block",
+                            "ERROR: seal failed when given internally generated seal: {}",
+                            e
+                        );
+                        false
+                    })
+            }
+            _ => false,
+        }
     }
 
     /// Check is reseal is allowed and necessary.
@@ -657,6 +755,9 @@ impl Miner {
     /// configurations and the current conditions.
     pub fn try_prepare_block(&self, client: &MiningBlockChainClient) {
         // self.prepare_work_sealing()
+        if self.seal_staking_block(client) {
+            trace!(target: "block", "try_prepare_block: imported a staking block");
+        }
         self.update_sealing(client);
     }
 
@@ -696,8 +797,13 @@ impl Miner {
             }));
         }
 
-        // Verify balance
-        let cost: U256 = transaction.value + transaction.gas_price * transaction.gas;
+        // Verify balance, the energy of a fee-delegated transaction is paid by the fee payer. The
+        // queue checks the fee payer, against all the energy it already pays for.
+        let gas_cost: U256 = transaction.gas_price * transaction.gas;
+        let cost: U256 = match transaction.fee_payer() {
+            Some(_) => transaction.value,
+            None => transaction.value + gas_cost,
+        };
         if client.latest_balance(&transaction.sender()) < cost {
             return Err(Error::Transaction(TransactionError::InsufficientBalance {
                 cost: cost,
@@ -726,7 +832,7 @@ impl Miner {
         }
         match self
             .engine
-            .verify_transaction_basic(&transaction)
+            .verify_transaction_basic(&transaction, &best_block_header)
             .and_then(|_| {
                 self.engine
                     .verify_transaction_signature(transaction, &best_block_header)
@@ -888,6 +994,9 @@ impl MinerService for Miner {
 
     fn set_author(&self, author: Address) {
         if self.engine.seals_internally().is_some() {
+            if let Some(ref accounts) = self.accounts {
+                self.engine.set_signer(accounts.clone(), author);
+            }
             let mut sealing_work = self.sealing_work.lock();
             sealing_work.enabled = true;
         }
@@ -0,0 +1,158 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Merkle proofs of accounts, storage and receipts, as served to light clients.
+//!
+//! A proof is the list of raw trie nodes visited looking a key up from the root, which is
+//! enough for a light client to repeat the lookup against a root taken from a header it trusts.
+
+use aion_types::{H256, U256};
+use bytes::Bytes;
+use kvdb::{DBValue, HashStore, MemoryDB};
+use receipt::SimpleReceipt;
+use rlp::{self, UntrustedRlp};
+use trie::recorder::Recorder;
+use trie::{Trie, TrieDB, TrieDBMut, TrieMut};
+use types::basic_account::BasicAccount;
+
+/// Look `key` up in the trie under `root` with only the nodes of `proof` at hand.
+/// Returns `None` if the proof is incomplete or invalid, and `Some(None)` if it proves the key
+/// absent.
+pub fn check_trie_proof(proof: &[Bytes], root: &H256, key: &[u8]) -> Option<Option<DBValue>> {
+    let mut db = MemoryDB::new();
+    for node in proof {
+        db.insert(&node[..]);
+    }
+
+    let res = match TrieDB::new(&db, root) {
+        Ok(trie) => trie.get(key),
+        Err(_) => return None,
+    };
+    res.ok()
+}
+
+/// Prove the item at `index` of an ordered trie, such as the transactions or receipts trie of
+/// a block. Returns the trie nodes in order from the root, or `None` if there is no such item.
+pub fn prove_ordered_trie<I, A>(input: I, index: usize) -> Option<Vec<Bytes>>
+where
+    I: IntoIterator<Item = A>,
+    A: AsRef<[u8]>,
+{
+    let mut db = MemoryDB::new();
+    let mut root = H256::default();
+    let mut count = 0;
+    {
+        let mut trie = TrieDBMut::new(&mut db, &mut root);
+        for (i, item) in input.into_iter().enumerate() {
+            if trie.insert(&rlp::encode(&i), item.as_ref()).is_err() {
+                return None;
+            }
+            count += 1;
+        }
+    }
+    if index >= count {
+        return None;
+    }
+
+    let mut recorder = Recorder::new();
+    let trie = TrieDB::new(&db, &root).ok()?;
+    trie.get_with(&rlp::encode(&index), &mut recorder).ok()?;
+
+    Some(recorder.drain().into_iter().map(|r| r.data).collect())
+}
+
+/// Check the proof of an account against a state root.
+/// `account_key` == blake2b(address). Returns `Some(None)` for an account proved absent.
+pub fn check_account_proof(
+    proof: &[Bytes],
+    state_root: &H256,
+    account_key: &H256,
+) -> Option<Option<BasicAccount>>
+{
+    match check_trie_proof(proof, state_root, account_key)? {
+        Some(value) => UntrustedRlp::new(&value).as_val().ok().map(Some),
+        None => Some(None),
+    }
+}
+
+/// Check the proof of a storage value against the storage root of its account.
+/// `storage_key` == blake2b(key). Storage proved absent holds zero.
+pub fn check_storage_proof(
+    proof: &[Bytes],
+    storage_root: &H256,
+    storage_key: &H256,
+) -> Option<H256>
+{
+    match check_trie_proof(proof, storage_root, storage_key)? {
+        Some(value) => {
+            UntrustedRlp::new(&value)
+                .as_val::<U256>()
+                .ok()
+                .map(Into::into)
+        }
+        None => Some(H256::zero()),
+    }
+}
+
+/// Check the proof of the receipt at `index` against the receipts root of its block.
+pub fn check_receipt_proof(
+    proof: &[Bytes],
+    receipts_root: &H256,
+    index: usize,
+) -> Option<SimpleReceipt>
+{
+    match check_trie_proof(proof, receipts_root, &rlp::encode(&index))? {
+        Some(value) => UntrustedRlp::new(&value).as_val().ok(),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use triehash::ordered_trie_root;
+
+    #[test]
+    fn prove_and_check_ordered_trie() {
+        let items: Vec<Vec<u8>> = (0..100u8).map(|i| vec![i; 40]).collect();
+        let root = ordered_trie_root(items.iter());
+
+        let proof = prove_ordered_trie(items.iter(), 42).unwrap();
+        let value = check_trie_proof(&proof, &root, &rlp::encode(&42usize));
+        assert_eq!(value.unwrap().map(|v| v.to_vec()), Some(items[42].clone()));
+        // nodes left out of the proof make it invalid.
+        let partial = &proof[..proof.len() - 1];
+        assert!(check_trie_proof(partial, &root, &rlp::encode(&42usize)).is_none());
+        assert!(prove_ordered_trie(items.iter(), 100).is_none());
+    }
+
+    #[test]
+    fn empty_storage_proves_zero() {
+        use blake2b::BLAKE2B_NULL_RLP;
+
+        let key = H256::from(5);
+        assert_eq!(
+            check_storage_proof(&[], &BLAKE2B_NULL_RLP, &key),
+            Some(H256::zero())
+        );
+    }
+}
@@ -38,7 +38,7 @@ use rlp::{Rlp, RlpStream};
 use vms::{CallType, ActionValue, ActionParams, ParamsType, EnvInfo};
 
 use precompiled::builtin::{BuiltinContract, builtin_contract};
-use engines::{POWEquihashEngine, EthEngine, NullEngine, InstantSeal};
+use engines::{POWEquihashEngine, EthEngine, NullEngine, InstantSeal, AuthorityRound};
 use error::Error;
 use executive::Executive;
 use factory::Factories;
@@ -67,6 +67,12 @@ pub struct CommonParams {
     pub registrar: Address,
     /// Transaction permission managing contract address.
     pub transaction_permission_contract: Option<Address>,
+    /// Chain id bound into transaction signatures.
+    pub chain_id: u64,
+    /// Block from which transactions must be signed for `chain_id`. `None` if never.
+    pub chain_id_transition: Option<u64>,
+    /// Block from which fee-delegated transactions are accepted. `None` if never.
+    pub fee_delegation_transition: Option<u64>,
 }
 
 impl From<ajson::spec::Params> for CommonParams {
@@ -78,6 +84,9 @@ impl From<ajson::spec::Params> for CommonParams {
             gas_limit_bound_divisor: p.gas_limit_bound_divisor.into(),
             registrar: p.registrar.map_or_else(Address::new, Into::into),
             transaction_permission_contract: p.transaction_permission_contract.map(Into::into),
+            chain_id: p.chain_id.map_or(0, Into::into),
+            chain_id_transition: p.chain_id_transition.map(Into::into),
+            fee_delegation_transition: p.fee_delegation_transition.map(Into::into),
         }
     }
 }
@@ -174,6 +183,14 @@ impl Clone for Spec {
     }
 }
 
+/// Check the parameters agree with each other before loading them.
+fn check_params(p: &ajson::spec::Params) -> Result<(), String> {
+    if p.chain_id_transition.is_some() && p.chain_id.is_none() {
+        return Err("chainIdTransition is set without chainId".into());
+    }
+    Ok(())
+}
+
 fn load_machine_from(s: ajson::spec::Spec) -> EthereumMachine {
     let builtins = s
         .accounts
@@ -283,6 +300,10 @@ impl Spec {
                 Arc::new(NullEngine::new(null.params.into(), machine))
             }
             ajson::spec::Engine::InstantSeal => Arc::new(InstantSeal::new(machine)),
+            ajson::spec::Engine::AuthorityRound(authority_round) => {
+                AuthorityRound::new(authority_round.params.into(), machine)
+                    .expect("Failed to start AuthorityRound consensus engine.")
+            }
         }
     }
 
@@ -469,18 +490,18 @@ impl Spec {
 
     /// Loads just the state machine from a json file.
     pub fn load_machine<R: Read>(reader: R) -> Result<EthereumMachine, String> {
-        ajson::spec::Spec::load(reader)
-            .map_err(fmt_err)
-            .map(load_machine_from)
+        let s = ajson::spec::Spec::load(reader).map_err(fmt_err)?;
+        check_params(&s.params).map_err(fmt_err)?;
+        Ok(load_machine_from(s))
     }
 
     /// Loads spec from json file. Provide factories for executing contracts and ensuring
     /// storage goes to the right place.
     pub fn load<'a, T: Into<SpecParams<'a>>, R>(params: T, reader: R) -> Result<Self, String>
     where R: Read {
-        ajson::spec::Spec::load(reader)
-            .map_err(fmt_err)
-            .and_then(|x| load_from(params.into(), x).map_err(fmt_err))
+        let s = ajson::spec::Spec::load(reader).map_err(fmt_err)?;
+        check_params(&s.params).map_err(fmt_err)?;
+        load_from(params.into(), s).map_err(fmt_err)
     }
 
     /// initialize genesis epoch data, using in-memory database for
@@ -557,6 +578,14 @@ impl Spec {
     /// Create a new Spec with InstantSeal consensus which does internal sealing (not requiring
     /// work).
     pub fn new_instant() -> Spec { load_bundled!("instant_seal") }
+
+    /// Create a new Spec with AuthorityRound consensus which does internal sealing (not
+    /// requiring work), with two validators and the step fixed at 2.
+    pub fn new_test_round() -> Spec { load_bundled!("authority_round") }
+
+    /// Create a new Spec with the equihash engine where the unity fork is active from block 1,
+    /// so blocks may be sealed by proof of work or by the single staker.
+    pub fn new_unity_test() -> Spec { load_bundled!("unity_test") }
 }
 
 #[cfg(test)]
@@ -568,6 +597,26 @@ mod tests {
         assert!(Spec::load(&::std::env::temp_dir(), &[] as &[u8]).is_err());
     }
 
+    #[test]
+    fn test_load_chain_id_transition_without_chain_id() {
+        let null = include_str!("../../res/null.json");
+        let params = "\"minGasLimit\": \"0x1388\"";
+        let without_id = null.replace(
+            params,
+            &format!("{}, \"chainIdTransition\": \"0x0\"", params),
+        );
+        let with_id = null.replace(
+            params,
+            &format!("{}, \"chainId\": \"0x20\", \"chainIdTransition\": \"0x0\"", params),
+        );
+
+        assert!(Spec::load(&::std::env::temp_dir(), without_id.as_bytes()).is_err());
+        assert!(Spec::load_machine(without_id.as_bytes()).is_err());
+        let spec = Spec::load(&::std::env::temp_dir(), with_id.as_bytes()).unwrap();
+        assert_eq!(spec.params().chain_id, 0x20);
+        assert_eq!(spec.params().chain_id_transition, Some(0));
+    }
+
     #[test]
     fn test_chain() {
         let test_spec = Spec::new_test();
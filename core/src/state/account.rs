@@ -214,6 +214,21 @@ impl Account {
 
     pub fn set_empty_but_commit(&mut self) { self.empty_but_commit = true; }
 
+    /// Set this account's balance.
+    pub fn set_balance(&mut self, balance: U256) { self.balance = balance; }
+
+    /// Set this account's nonce.
+    pub fn set_nonce(&mut self, nonce: U256) { self.nonce = nonce; }
+
+    /// Forget all the storage of this account, committed or not.
+    pub fn reset_storage(&mut self) {
+        self.storage_root = BLAKE2B_NULL_RLP;
+        self.storage_cache = Self::empty_storage_cache();
+        self.storage_changes = HashMap::new();
+        self.storage_cache_dword = Self::empty_storage_cache_dword();
+        self.storage_changes_dword = HashMap::new();
+    }
+
     pub fn get_empty_but_commit(&mut self) -> bool { return self.empty_but_commit; }
 
     /// Reset this account's code to the given code.
@@ -781,6 +781,27 @@ impl<B: Backend> State<B> {
         self.require(a, false).map(|mut x| x.inc_nonce())
     }
 
+    /// Set the balance of account `a`.
+    /// NOTE: Only meant for simulations on a state which is never committed.
+    pub fn set_balance(&mut self, a: &Address, balance: U256) -> trie::Result<()> {
+        self.require(a, false)?.set_balance(balance);
+        Ok(())
+    }
+
+    /// Set the nonce of account `a`.
+    /// NOTE: Only meant for simulations on a state which is never committed.
+    pub fn set_nonce(&mut self, a: &Address, nonce: U256) -> trie::Result<()> {
+        self.require(a, false)?.set_nonce(nonce);
+        Ok(())
+    }
+
+    /// Drop all the storage of account `a`.
+    /// NOTE: Only meant for simulations on a state which is never committed.
+    pub fn reset_storage(&mut self, a: &Address) -> trie::Result<()> {
+        self.require(a, false)?.reset_storage();
+        Ok(())
+    }
+
     /// Mutate storage of account `a` so that it is `value` for `key`.
     pub fn set_storage(&mut self, a: &Address, key: H128, value: H128) -> trie::Result<()> {
         trace!(target: "state", "set_storage({}:{:x} to {:x})", a, key, value);
@@ -1648,6 +1669,29 @@ mod tests {
         assert_eq!(state.nonce(&a).unwrap(), U256::from(3u64));
     }
 
+    #[test]
+    fn override_account() {
+        let mut state = get_temp_state();
+        let a = Address::zero();
+        let (k1, k2) = (H128::from(U128::from(1u64)), H128::from(U128::from(2u64)));
+        state
+            .add_balance(&a, &U256::from(69u64), CleanupMode::NoEmpty)
+            .unwrap();
+        state.set_storage(&a, k1, H128::from(U128::from(10u64))).unwrap();
+        state.commit().unwrap();
+
+        state.set_balance(&a, U256::from(5u64)).unwrap();
+        state.set_nonce(&a, U256::from(7u64)).unwrap();
+        assert_eq!(state.balance(&a).unwrap(), U256::from(5u64));
+        assert_eq!(state.nonce(&a).unwrap(), U256::from(7u64));
+
+        state.set_storage(&a, k2, H128::from(U128::from(20u64))).unwrap();
+        state.reset_storage(&a).unwrap();
+        assert_eq!(state.storage_at(&a, &k1).unwrap(), H128::zero());
+        assert_eq!(state.storage_at(&a, &k2).unwrap(), H128::zero());
+        assert_eq!(state.balance(&a).unwrap(), U256::from(5u64));
+    }
+
     #[test]
     fn balance_nonce() {
         let mut state = get_temp_state();
@@ -399,6 +399,9 @@ impl StateDB {
     /// Returns underlying `JournalDB`.
     pub fn journal_db(&self) -> &JournalDB { &*self.db }
 
+    /// Returns mutable reference to underlying `JournalDB`.
+    pub fn journal_db_mut(&mut self) -> &mut JournalDB { &mut *self.db }
+
     /// Query how much memory is set aside for the accounts cache (in bytes).
     pub fn cache_size(&self) -> usize { self.cache_size }
 
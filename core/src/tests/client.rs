@@ -369,6 +369,58 @@ fn does_not_propagate_delayed_transactions() {
     assert_eq!(2, client.miner().pending_transactions().len());
 }
 
+#[test]
+fn executes_call_bundle() {
+    use client::{BlockOverride, CallAnalytics};
+    use executive::contract_address;
+
+    let client = generate_dummy_client(1);
+    let sender = Address::from(0x10);
+    let transaction = |nonce: u64, action: Action, data: Vec<u8>| {
+        Transaction {
+            nonce: nonce.into(),
+            gas_price: 0.into(),
+            gas: 1_000_000.into(),
+            action: action,
+            value: 0.into(),
+            data: data,
+            nonce_bytes: Vec::new(),
+            gas_price_bytes: Vec::new(),
+            gas_bytes: Vec::new(),
+            value_bytes: Vec::new(),
+            transaction_type: 0x01,
+        }
+        .fake_sign(sender)
+    };
+    // PUSH1 0, PUSH1 0, LOG0, STOP: a creation emitting one empty log
+    let create = transaction(0, Action::Create, vec![0x60, 0x00, 0x60, 0x00, 0xa0, 0x00]);
+    let transfer = transaction(1, Action::Call(Address::from(0x20)), Vec::new());
+    let analytics = CallAnalytics::default();
+
+    let executed = client
+        .call_many(
+            &[(create, analytics), (transfer, analytics)],
+            BlockId::Latest,
+            &BlockOverride::default(),
+        )
+        .unwrap();
+
+    assert_eq!(executed.len(), 2);
+    assert_eq!(executed[0].logs.len(), 1);
+    assert_eq!(
+        executed[0].logs[0].address,
+        contract_address(&sender, &U256::zero()).0
+    );
+    assert!(executed[1].logs.is_empty());
+    assert_eq!(executed[1].gas_used, U256::from(21000));
+    assert!(executed[0].gas_used > executed[1].gas_used);
+    // the second transaction runs on top of the first one
+    assert_eq!(
+        executed[1].cumulative_gas_used,
+        executed[0].gas_used + executed[1].gas_used
+    );
+}
+
 #[test]
 fn transaction_proof() {
     use client::ProvingBlockChainClient;
@@ -436,3 +488,168 @@ fn transaction_proof() {
     assert_eq!(state.balance(&Address::default()).unwrap(), 5.into());
     assert_eq!(state.balance(&address).unwrap(), 95.into());
 }
+
+#[test]
+fn account_proof() {
+    use blake2b::blake2b;
+    use client::ProvingBlockChainClient;
+    use proof::check_account_proof;
+
+    let client = generate_dummy_client(0);
+    let address = Address::random();
+    let test_spec = Spec::new_test();
+    let mut b = client.prepare_open_block(
+        Address::default(),
+        (3141562.into(), 31415620.into()),
+        vec![],
+    );
+    b.block_mut()
+        .state_mut()
+        .add_balance(&address, &5.into(), CleanupMode::NoEmpty)
+        .unwrap();
+    b.set_difficulty(U256::from(1));
+    b.block_mut().state_mut().commit().unwrap();
+    let b = b.close_and_lock().seal(&*test_spec.engine, vec![]).unwrap();
+    client.import_sealed_block(b).unwrap();
+
+    let state_root = client.best_block_header().state_root();
+    let (proof, account) = client
+        .prove_account(blake2b(&address), BlockId::Latest)
+        .unwrap();
+    assert_eq!(account.balance, 5.into());
+    assert_eq!(
+        check_account_proof(&proof, &state_root, &blake2b(&address)),
+        Some(Some(account))
+    );
+
+    // an account never touched is proved absent.
+    let untouched = blake2b(&Address::random());
+    let (proof, _) = client
+        .prove_account(untouched, BlockId::Latest)
+        .unwrap();
+    assert_eq!(
+        check_account_proof(&proof, &state_root, &untouched),
+        Some(None)
+    );
+}
+
+fn address_index_client(test_spec: &Spec, db: Arc<::kvdb::KeyValueDB>) -> Arc<Client> {
+    let mut config = ClientConfig::default();
+    config.tx_address_index = true;
+    Client::new(
+        config,
+        test_spec,
+        db,
+        Arc::new(Miner::with_spec(test_spec)),
+        IoChannel::disconnected(),
+    )
+    .unwrap()
+}
+
+fn transfer(
+    keypair: &::key::Ed25519KeyPair,
+    nonce: u64,
+    to: Address,
+) -> ::transaction::SignedTransaction
+{
+    Transaction {
+        nonce: nonce.into(),
+        gas_price: 0.into(),
+        gas: 100_000.into(),
+        action: Action::Call(to),
+        value: 0.into(),
+        data: Vec::new(),
+        nonce_bytes: Vec::new(),
+        gas_price_bytes: Vec::new(),
+        gas_bytes: Vec::new(),
+        value_bytes: Vec::new(),
+        transaction_type: ::transaction::DEFAULT_TRANSACTION_TYPE,
+    }
+    .sign(&keypair.secret().0, None)
+}
+
+fn import_blocks(client: &Client, blocks: &[::bytes::Bytes]) {
+    for block in blocks {
+        client.import_block(block.clone()).unwrap();
+    }
+    client.flush_queue();
+    client.import_verified_blocks();
+}
+
+fn transactions_by_address(client: &Client, address: &Address) -> Vec<::aion_types::H256> {
+    client
+        .transactions_by_address(address, 0, 10)
+        .unwrap()
+        .0
+        .into_iter()
+        .map(|t| t.hash())
+        .collect()
+}
+
+#[test]
+fn address_index_follows_reorgs() {
+    use key::generate_keypair;
+
+    let test_spec = Spec::new_null();
+    let keypair = generate_keypair();
+    let sender = keypair.address();
+    let (first, second) = (Address::from(0x10), Address::from(0x20));
+    let t1 = transfer(&keypair, 0, first);
+    let t2 = transfer(&keypair, 0, second);
+    let (hash1, hash2) = (t1.hash(), t2.hash());
+    let chain = generate_dummy_blocks_with_transactions(&test_spec, vec![vec![t1]], 0);
+    // a longer fork spending the same nonce in its second block.
+    let fork = generate_dummy_blocks_with_transactions(&test_spec, vec![vec![], vec![t2]], 5);
+
+    let client = address_index_client(&test_spec, new_db());
+    import_blocks(&client, &chain);
+    assert_eq!(transactions_by_address(&client, &sender), vec![hash1]);
+    assert_eq!(transactions_by_address(&client, &first), vec![hash1]);
+
+    import_blocks(&client, &fork);
+    assert_eq!(client.chain_info().best_block_number, 2);
+    assert_eq!(transactions_by_address(&client, &sender), vec![hash2]);
+    assert_eq!(transactions_by_address(&client, &first), vec![]);
+    assert_eq!(transactions_by_address(&client, &second), vec![hash2]);
+}
+
+#[test]
+fn address_index_backfills_blocks_imported_before_it() {
+    use key::generate_keypair;
+
+    let test_spec = Spec::new_null();
+    let keypair = generate_keypair();
+    let sender = keypair.address();
+    let recipient = Address::from(0x10);
+    let transactions = (0..3)
+        .map(|nonce| vec![transfer(&keypair, nonce, recipient)])
+        .collect::<Vec<_>>();
+    let mut hashes = transactions
+        .iter()
+        .map(|block| block[0].hash())
+        .collect::<Vec<_>>();
+    hashes.reverse();
+    let blocks = generate_dummy_blocks_with_transactions(&test_spec, transactions, 0);
+
+    let db = new_db();
+    {
+        let client = Client::new(
+            ClientConfig::default(),
+            &test_spec,
+            db.clone(),
+            Arc::new(Miner::with_spec(&test_spec)),
+            IoChannel::disconnected(),
+        )
+        .unwrap();
+        import_blocks(&client, &blocks);
+    }
+
+    // the blocks imported without the index are only found once backfilled.
+    let client = address_index_client(&test_spec, db);
+    assert_eq!(transactions_by_address(&client, &sender), vec![]);
+    assert_eq!(client.transactions_by_address(&sender, 0, 10).unwrap().1, (4, 3));
+    client.tick();
+    assert_eq!(client.transactions_by_address(&sender, 0, 10).unwrap().1, (0, 3));
+    assert_eq!(transactions_by_address(&client, &sender), hashes);
+    assert_eq!(transactions_by_address(&client, &recipient), hashes);
+}
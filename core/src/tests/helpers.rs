@@ -20,7 +20,7 @@
  *
  ******************************************************************************/
 
-use aion_types::{H256, U256};
+use aion_types::{Address, H256, U256};
 use account_provider::AccountProvider;
 use block::{OpenBlock, Drain};
 use blockchain::{BlockChain, Config as BlockChainConfig};
@@ -243,6 +243,54 @@ where
     client
 }
 
+/// Seal blocks on top of the genesis, block `i + 1` holding `transactions[i]`. Forks get
+/// different blocks for a different `timestamp_salt`.
+pub fn generate_dummy_blocks_with_transactions(
+    test_spec: &Spec,
+    transactions: Vec<Vec<SignedTransaction>>,
+    timestamp_salt: u64,
+) -> Vec<Bytes>
+{
+    let test_engine = &*test_spec.engine;
+    let mut db = test_spec
+        .ensure_db_good(get_temp_state_db(), &Default::default())
+        .unwrap();
+    let mut rolling_timestamp = 40 + timestamp_salt;
+    let mut last_hashes = vec![];
+    let mut last_header = test_spec.genesis_header();
+    let mut blocks = Vec::new();
+    for block_transactions in transactions {
+        last_hashes.push(last_header.hash());
+
+        let mut b = OpenBlock::new(
+            test_engine,
+            Default::default(),
+            db,
+            &last_header,
+            None,
+            Arc::new(last_hashes.clone()),
+            Address::default(),
+            (3141562.into(), 31415620.into()),
+            vec![],
+            false,
+            Arc::new(MemoryDBRepository::new()),
+        )
+        .unwrap();
+        b.set_difficulty(U256::from(0x20000));
+        rolling_timestamp += 10;
+        b.set_timestamp(rolling_timestamp);
+        for transaction in block_transactions {
+            b.push_transaction(transaction, None).unwrap();
+        }
+
+        let b = b.close_and_lock().seal(test_engine, vec![]).unwrap();
+        last_header = BlockView::new(&b.rlp_bytes()).header();
+        blocks.push(b.rlp_bytes());
+        db = b.drain();
+    }
+    blocks
+}
+
 pub fn push_blocks_to_client(
     client: &Arc<Client>,
     timestamp_salt: u64,
@@ -304,7 +352,7 @@ pub fn get_test_client_with_blocks(blocks: Vec<Bytes>) -> Arc<Client> {
     client
 }
 
-fn new_db() -> Arc<KeyValueDB> {
+pub fn new_db() -> Arc<KeyValueDB> {
     let mut db_configs = Vec::new();
     for db_name in db::DB_NAMES.to_vec() {
         db_configs.push(db_name.into());
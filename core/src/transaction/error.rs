@@ -80,6 +80,8 @@ pub enum Error {
     CodeBanned,
     /// Invalid chain ID given.
     InvalidChainId,
+    /// Fee-delegated transaction given before fee delegation is active.
+    FeeDelegationNotActive,
     /// Not enough permissions given by permission contract.
     NotAllowed,
     /// Signature error
@@ -160,6 +162,9 @@ impl fmt::Display for Error {
             RecipientBanned => "Recipient is temporarily banned.".into(),
             CodeBanned => "Contract code is temporarily banned.".into(),
             InvalidChainId => "Transaction of this chain ID is not allowed on this chain.".into(),
+            FeeDelegationNotActive => {
+                "Fee-delegated transactions are not allowed on this chain yet.".into()
+            }
             InvalidSignature(ref err) => format!("Transaction has invalid signature: {}.", err),
             NotAllowed => {
                 "Sender does not have permissions to execute this type of transction".into()
@@ -49,6 +49,9 @@ pub const SYSTEM_ADDRESS: Address = H256([
 
 pub const DEFAULT_TRANSACTION_TYPE: u8 = 0x01;
 
+/// Transaction type whose energy is paid by a fee payer countersigning the transaction.
+pub const FEE_DELEGATED_TRANSACTION_TYPE: u8 = 0x03;
+
 struct TransactionEnergyRule;
 impl TransactionEnergyRule {
     pub fn is_valid_gas_create(gas: U256) -> bool {
@@ -149,6 +152,11 @@ impl Transaction {
         }
     }
 
+    /// Whether the energy of this transaction is paid by a fee payer rather than the sender.
+    pub fn is_fee_delegated(&self) -> bool {
+        self.transaction_type == FEE_DELEGATED_TRANSACTION_TYPE
+    }
+
     /// Append object with a without signature into RLP stream
     pub fn rlp_append_unsigned_transaction(
         &self,
@@ -216,6 +224,9 @@ impl From<ajson::transaction::Transaction> for UnverifiedTransaction {
             },
             timestamp: t.timestamp.into(),
             sig: t.sig.into(),
+            chain_id: None,
+            fee_delegated: false,
+            fee_payer_sig: Vec::new(),
             hash: 0.into(),
         }
         .compute_hash()
@@ -245,14 +256,17 @@ impl Transaction {
     pub fn with_signature(
         self,
         sig: Ed25519Signature,
-        _chain_id: Option<u64>,
+        chain_id: Option<u64>,
         timestamp: Bytes,
     ) -> UnverifiedTransaction
     {
         UnverifiedTransaction {
+            fee_delegated: self.is_fee_delegated(),
             unsigned: self,
             timestamp,
             sig: sig.to_vec(),
+            chain_id,
+            fee_payer_sig: Vec::new(),
             hash: 0.into(),
         }
         .compute_hash()
@@ -262,9 +276,12 @@ impl Transaction {
     #[cfg(test)]
     pub fn invalid_sign(self) -> UnverifiedTransaction {
         UnverifiedTransaction {
+            fee_delegated: self.is_fee_delegated(),
             unsigned: self,
             timestamp: vec![0x00; 8],
             sig: vec![0u8; 96],
+            chain_id: None,
+            fee_payer_sig: Vec::new(),
             hash: 0.into(),
         }
         .compute_hash()
@@ -274,29 +291,37 @@ impl Transaction {
     pub fn fake_sign(self, from: Address) -> SignedTransaction {
         SignedTransaction {
             transaction: UnverifiedTransaction {
+                fee_delegated: self.is_fee_delegated(),
                 unsigned: self,
                 timestamp: vec![0x00; 8],
                 sig: vec![1u8; 96],
+                chain_id: None,
+                fee_payer_sig: Vec::new(),
                 hash: 0.into(),
             }
             .compute_hash(),
             sender: from,
             public: None,
+            fee_payer: None,
         }
     }
 
     /// Add EIP-86 compatible empty signature.
-    pub fn null_sign(self, _chain_id: u64) -> SignedTransaction {
+    pub fn null_sign(self, chain_id: u64) -> SignedTransaction {
         SignedTransaction {
             transaction: UnverifiedTransaction {
+                fee_delegated: self.is_fee_delegated(),
                 unsigned: self,
                 timestamp: vec![0x00; 8],
                 sig: vec![0u8; 96],
+                chain_id: Some(chain_id),
+                fee_payer_sig: Vec::new(),
                 hash: 0.into(),
             }
             .compute_hash(),
             sender: UNSIGNED_SENDER,
             public: None,
+            fee_payer: None,
         }
     }
 
@@ -324,6 +349,14 @@ pub struct UnverifiedTransaction {
     unsigned: Transaction,
     /// Signature
     sig: Bytes,
+    /// Chain the transaction is bound to, part of the signed message. `None` for transactions
+    /// signed without replay protection.
+    chain_id: Option<u64>,
+    /// Whether the transaction uses the fee-delegated layout. Type 3 transactions encoded before
+    /// fee delegation carry no fee payer item and are decoded as regular transactions.
+    fee_delegated: bool,
+    /// Fee payer signature over `fee_payer_hash`, empty unless fee-delegated and countersigned.
+    fee_payer_sig: Bytes,
     /// Hash of the transaction
     hash: H256,
     /// Timestamp.
@@ -339,7 +372,21 @@ impl Deref for UnverifiedTransaction {
 
 impl rlp::Decodable for UnverifiedTransaction {
     fn decode(d: &UntrustedRlp) -> Result<Self, DecoderError> {
-        if d.item_count()? != 9 {
+        let item_count = d.item_count()?;
+        if item_count < 9 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        let transaction_type = match d.val_at::<Vec<u8>>(7)?.first() {
+            Some(transaction_type) => *transaction_type,
+            None => 0u8,
+        };
+        // fee-delegated transactions carry the fee payer signature as a list right after the
+        // signature; older type 3 transactions have a plain chain id or nothing there.
+        let fee_delegated = transaction_type == FEE_DELEGATED_TRANSACTION_TYPE
+            && item_count > 9
+            && d.at(9)?.is_list();
+        let sealed_count = if fee_delegated { 10 } else { 9 };
+        if item_count != sealed_count && item_count != sealed_count + 1 {
             return Err(DecoderError::RlpIncorrectListLen);
         }
         let hash = blake2b(d.as_raw());
@@ -369,17 +416,26 @@ impl rlp::Decodable for UnverifiedTransaction {
                 gas_bytes: d.val_at(5)?,
                 gas_price: to_u256(d.val_at::<Vec<u8>>(6)?, 8),
                 gas_price_bytes: d.val_at(6)?,
-                transaction_type: {
-                    let transaction_type_vec = d.val_at::<Vec<u8>>(7)?;
-                    if transaction_type_vec.len() == 0 {
-                        0u8
-                    } else {
-                        transaction_type_vec[0]
-                    }
-                },
+                transaction_type: transaction_type,
             },
             timestamp: d.val_at(4)?,
             sig: d.val_at(8)?,
+            chain_id: if item_count > sealed_count {
+                Some(d.val_at(sealed_count)?)
+            } else {
+                None
+            },
+            fee_delegated: fee_delegated,
+            fee_payer_sig: if fee_delegated {
+                let fee_payer = d.at(9)?;
+                match fee_payer.item_count()? {
+                    0 => Vec::new(),
+                    1 => fee_payer.val_at(0)?,
+                    _ => return Err(DecoderError::RlpIncorrectListLen),
+                }
+            } else {
+                Vec::new()
+            },
             hash: hash,
         })
     }
@@ -408,8 +464,18 @@ impl UnverifiedTransaction {
     }
 
     /// Append object with a signature into RLP stream
+    /// Fee-delegated transactions carry the fee payer signature after the signature, wrapped in a
+    /// list that is empty until countersigned, and transactions bound to a chain carry the chain
+    /// id as the last item.
     fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
-        s.begin_list(9);
+        let mut item_count = 9;
+        if self.is_fee_delegated() {
+            item_count += 1;
+        }
+        if self.chain_id.is_some() {
+            item_count += 1;
+        }
+        s.begin_list(item_count);
         if self.nonce_bytes.is_empty() {
             s.append(&self.nonce);
         } else {
@@ -435,15 +501,29 @@ impl UnverifiedTransaction {
         }
         s.append(&self.transaction_type);
         s.append(&self.sig);
+        if self.is_fee_delegated() {
+            if self.fee_payer_sig.is_empty() {
+                s.begin_list(0);
+            } else {
+                s.begin_list(1).append(&self.fee_payer_sig);
+            }
+        }
+        if let Some(n) = self.chain_id {
+            s.append(&n);
+        }
     }
 
     ///    Reference to unsigned part of this transaction.
     pub fn as_unsigned(&self) -> &Transaction { &self.unsigned }
 
+    /// Whether the energy of this transaction is paid by a fee payer rather than the sender.
+    /// Unlike `Transaction::is_fee_delegated` this follows the encoded layout.
+    pub fn is_fee_delegated(&self) -> bool { self.fee_delegated }
+
     pub fn standard_v(&self) -> u8 { 0 }
 
     /// The chain ID, or `None` if this is a global transaction.
-    pub fn chain_id(&self) -> Option<u64> { None }
+    pub fn chain_id(&self) -> Option<u64> { self.chain_id }
 
     /// Construct a signature object from the sig.
     pub fn signature(&self) -> Ed25519Signature { Ed25519Signature::from(self.sig.clone()) }
@@ -462,6 +542,48 @@ impl UnverifiedTransaction {
         )
     }
 
+    /// The message the fee payer signs: the sender's message together with the sender's
+    /// signature, so a fee payer only ever pays for the exact transaction it has seen.
+    pub fn fee_payer_hash(&self) -> H256 {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&self.unsigned.hash(self.chain_id(), &self.timestamp));
+        stream.append(&self.sig);
+        blake2b(stream.as_raw())
+    }
+
+    /// Construct the fee payer signature object, if the transaction has been countersigned.
+    pub fn fee_payer_signature(&self) -> Option<Ed25519Signature> {
+        if !self.fee_delegated || self.fee_payer_sig.is_empty() {
+            None
+        } else {
+            Some(Ed25519Signature::from(self.fee_payer_sig.clone()))
+        }
+    }
+
+    /// Recovers the public key of the fee payer, if the transaction has been countersigned.
+    pub fn recover_fee_payer_public(&self) -> Result<Option<Ed25519Public>, key::Error> {
+        match self.fee_payer_signature() {
+            Some(sig) => Ok(Some(recover_ed25519(&sig, &self.fee_payer_hash())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Attach the fee payer signature to a fee-delegated transaction.
+    pub fn with_fee_payer_signature(mut self, sig: Ed25519Signature) -> UnverifiedTransaction {
+        self.fee_payer_sig = sig.to_vec();
+        self.compute_hash()
+    }
+
+    /// Countersign a fee-delegated transaction as its fee payer.
+    pub fn sign_fee_payer(self, key: &[u8]) -> SignedTransaction {
+        let key = Ed25519Secret::from_slice(key)
+            .expect("key is valid and context has signing capabilities; qed");
+        let sig = sign_ed25519(&key, &self.fee_payer_hash())
+            .expect("data is valid and context has signing capabilities; qed");
+        SignedTransaction::new(self.with_fee_payer_signature(sig))
+            .expect("secret is valid so it's recoverable")
+    }
+
     /// Do basic validation, checking for valid signature and minimum gas,
     // TODO: consider use in block validation.
     // TODO-aion: add other validation as java version does.
@@ -489,7 +611,14 @@ impl UnverifiedTransaction {
     }
 
     /// Verify basic signature params. Does not attempt sender recovery.
-    pub fn verify_basic(&self, _chain_id: Option<u64>) -> Result<(), error::Error> {
+    /// A transaction bound to a chain is only accepted if `chain_id` is that chain.
+    pub fn verify_basic(&self, chain_id: Option<u64>) -> Result<(), error::Error> {
+        match (self.chain_id, chain_id) {
+            (None, _) => {}
+            (Some(n), Some(m)) if n == m => {}
+            _ => return Err(error::Error::InvalidChainId),
+        }
+
         // verify nonce length
         if self.unsigned.nonce.leading_zeros() / 8 < 16 {
             return Err(error::Error::InvalidNonceLength);
@@ -540,6 +669,14 @@ impl UnverifiedTransaction {
             )));
         }
 
+        // verify fee payer sig length
+        if self.is_fee_delegated() && self.fee_payer_sig.len() != 96 {
+            return Err(error::Error::InvalidSignature(format!(
+                "fee payer signature length is invalid: {}",
+                self.fee_payer_sig.len()
+            )));
+        }
+
         // signature is verified in SignedTransaction.
 
         Ok(())
@@ -552,6 +689,7 @@ pub struct SignedTransaction {
     transaction: UnverifiedTransaction,
     sender: Address,
     public: Option<Ed25519Public>,
+    fee_payer: Option<Address>,
 }
 
 impl HeapSizeOf for SignedTransaction {
@@ -579,14 +717,19 @@ impl SignedTransaction {
                 transaction: transaction,
                 sender: UNSIGNED_SENDER,
                 public: None,
+                fee_payer: None,
             })
         } else {
             let public = transaction.recover_public()?;
             let sender = public_to_address_ed25519(&public);
+            let fee_payer = transaction
+                .recover_fee_payer_public()?
+                .map(|public| public_to_address_ed25519(&public));
             Ok(SignedTransaction {
                 transaction: transaction,
                 sender: sender,
                 public: Some(H256::from_slice(&public.0)),
+                fee_payer: fee_payer,
             })
         }
     }
@@ -594,6 +737,12 @@ impl SignedTransaction {
     /// Returns transaction sender.
     pub fn sender(&self) -> Address { self.sender }
 
+    /// Returns the fee payer of a countersigned fee-delegated transaction.
+    pub fn fee_payer(&self) -> Option<Address> { self.fee_payer }
+
+    /// Returns the account charged for energy: the fee payer if any, else the sender.
+    pub fn gas_payer(&self) -> Address { self.fee_payer.unwrap_or(self.sender) }
+
     /// Returns a public key of the sender.
     pub fn public_key(&self) -> Option<Ed25519Public> { self.public }
 
@@ -638,6 +787,17 @@ impl LocalizedTransaction {
         self.cached_sender = Some(sender);
         sender
     }
+
+    /// Returns the fee payer of a fee-delegated transaction.
+    /// Panics if `LocalizedTransaction` is constructed using invalid `UnverifiedTransaction`.
+    pub fn fee_payer(&self) -> Option<Address> {
+        self.recover_fee_payer_public()
+            .expect(
+                "LocalizedTransaction is always constructed from transaction from blockchain; \
+                 Blockchain only stores verified transactions; qed",
+            )
+            .map(|public| public_to_address_ed25519(&public))
+    }
 }
 
 impl Deref for LocalizedTransaction {
@@ -1095,6 +1255,106 @@ mod tests {
         assert_eq!(t.chain_id(), None);
     }
 
+    #[test]
+    fn signing_with_chain_id() {
+        use key::generate_keypair;
+
+        let key = generate_keypair();
+        let t = Transaction {
+            action: Action::Create,
+            nonce: U256::from(42),
+            nonce_bytes: Vec::new(),
+            gas_price: U256::from(3000),
+            gas_bytes: Vec::new(),
+            gas: U256::from(50_000),
+            gas_price_bytes: Vec::new(),
+            value: U256::from(1),
+            value_bytes: Vec::new(),
+            data: b"Hello!".to_vec(),
+            transaction_type: 1,
+        }
+        .sign(&key.secret(), Some(32));
+        assert_eq!(t.chain_id(), Some(32));
+
+        let decoded: UnverifiedTransaction = rlp::decode(&t.rlp_bytes());
+        assert_eq!(decoded.chain_id(), Some(32));
+        let decoded = SignedTransaction::new(decoded).unwrap();
+        assert_eq!(decoded.sender(), t.sender());
+        assert_eq!(decoded.hash(), t.hash());
+
+        assert!(t.verify_basic(Some(32)).is_ok());
+        assert_eq!(t.verify_basic(Some(256)), Err(error::Error::InvalidChainId));
+        assert_eq!(t.verify_basic(None), Err(error::Error::InvalidChainId));
+    }
+
+    #[test]
+    fn fee_delegated_signing() {
+        use key::generate_keypair;
+
+        let sender = generate_keypair();
+        let fee_payer = generate_keypair();
+        let mut t = Transaction::new(
+            U256::from(42),
+            U256::from(3000),
+            U256::from(50_000),
+            Action::Call(Address::from(0x69)),
+            U256::from(1),
+            b"Hello!".to_vec(),
+        );
+        t.transaction_type = FEE_DELEGATED_TRANSACTION_TYPE;
+        let t: UnverifiedTransaction = t.sign(&sender.secret(), Some(32)).into();
+
+        // not countersigned yet: the sender pays and the transaction is not valid.
+        let decoded: UnverifiedTransaction = rlp::decode(&t.rlp_bytes());
+        assert_eq!(decoded, t);
+        assert_eq!(SignedTransaction::new(decoded).unwrap().fee_payer(), None);
+        assert!(t.verify_basic(Some(32)).is_err());
+
+        let t = t.sign_fee_payer(&fee_payer.secret());
+        assert_eq!(t.sender(), sender.address());
+        assert_eq!(t.fee_payer(), Some(fee_payer.address()));
+        assert_eq!(t.gas_payer(), fee_payer.address());
+        assert!(t.verify_basic(Some(32)).is_ok());
+
+        let decoded: UnverifiedTransaction = rlp::decode(&t.rlp_bytes());
+        assert_eq!(decoded.chain_id(), Some(32));
+        let decoded = SignedTransaction::new(decoded).unwrap();
+        assert_eq!(decoded.sender(), sender.address());
+        assert_eq!(decoded.fee_payer(), Some(fee_payer.address()));
+        assert_eq!(decoded.hash(), t.hash());
+    }
+
+    #[test]
+    fn decodes_type_3_transactions_without_fee_payer_item() {
+        // type 3 transactions sealed before fee delegation have no fee payer item.
+        let legacy = |chain_id: Option<u64>| {
+            let mut s = RlpStream::new_list(if chain_id.is_some() { 10 } else { 9 });
+            s.append(&U256::from(1));
+            s.append(&Action::Call(Address::from(0x69)));
+            s.append(&U256::zero());
+            s.append(&Vec::<u8>::new());
+            s.append(&vec![0u8; 8]);
+            encode_long(&U256::from(21_000), &mut s);
+            encode_long(&U256::from(10_000_000_000u64), &mut s);
+            s.append(&FEE_DELEGATED_TRANSACTION_TYPE);
+            s.append(&vec![1u8; 96]);
+            if let Some(n) = chain_id {
+                s.append(&n);
+            }
+            s.out()
+        };
+
+        for chain_id in vec![None, Some(32)] {
+            let raw = legacy(chain_id);
+            let decoded: UnverifiedTransaction = rlp::decode(&raw);
+            assert!(!decoded.is_fee_delegated());
+            assert_eq!(decoded.chain_id(), chain_id);
+            assert!(decoded.fee_payer_signature().is_none());
+            assert_eq!(decoded.hash(), blake2b(&raw));
+            assert_eq!(&*decoded.rlp_bytes(), &raw[..]);
+        }
+    }
+
     #[test]
     fn fake_signing() {
         let t = Transaction {
@@ -317,11 +317,109 @@ impl VerifiedTransaction {
 
     fn sender(&self) -> Address { self.transaction.sender() }
 
+    fn fee_payer(&self) -> Option<Address> { self.transaction.fee_payer() }
+
+    /// Balance the sender needs, the energy of a fee-delegated transaction is paid by the fee payer.
     fn cost(&self) -> U256 {
-        self.transaction.value + self.transaction.gas_price * self.transaction.gas
+        match self.fee_payer() {
+            Some(_) => self.transaction.value,
+            None => self.transaction.value + self.gas_cost(),
+        }
+    }
+
+    fn gas_cost(&self) -> U256 { self.transaction.gas_price * self.transaction.gas }
+}
+
+/// A change of the transactions kept in the queue, told to the queue listeners.
+pub enum QueueEvent<'a> {
+    /// A transaction entered the queue, with the condition it is held back until.
+    Added(&'a SignedTransaction, Option<&'a transaction::Condition>),
+    /// A transaction left the queue, mined, replaced, dropped or invalid.
+    Removed(&'a H256),
+}
+
+/// Callback told about every transaction entering or leaving the queue. It is called with the
+/// queue locked, so it must not call back into the queue.
+pub type QueueListener = Box<Fn(QueueEvent) + Send + Sync>;
+
+/// All transactions of the queue by hash, telling the listeners about every insertion and
+/// removal.
+#[derive(Default)]
+struct TransactionsByHash {
+    transactions: HashMap<H256, VerifiedTransaction>,
+    /// Energy costs of the queued fee-delegated transactions by the fee payer paying them.
+    fee_payer_costs: HashMap<Address, U256>,
+    listeners: Vec<QueueListener>,
+}
+
+impl TransactionsByHash {
+    fn insert(&mut self, hash: H256, tx: VerifiedTransaction) -> Option<VerifiedTransaction> {
+        for listener in self.listeners.iter() {
+            listener(QueueEvent::Added(&tx.transaction, tx.condition.as_ref()));
+        }
+        if let Some(fee_payer) = tx.fee_payer() {
+            *self.fee_payer_costs.entry(fee_payer).or_insert_with(U256::zero) += tx.gas_cost();
+        }
+        let old = self.transactions.insert(hash, tx);
+        if let Some(ref old) = old {
+            self.release_fee_payer_cost(old);
+        }
+        old
+    }
+
+    fn remove(&mut self, hash: &H256) -> Option<VerifiedTransaction> {
+        let removed = self.transactions.remove(hash);
+        if let Some(ref tx) = removed {
+            self.release_fee_payer_cost(tx);
+            for listener in self.listeners.iter() {
+                listener(QueueEvent::Removed(hash));
+            }
+        }
+        removed
+    }
+
+    fn clear(&mut self) {
+        for hash in self.transactions.keys() {
+            for listener in self.listeners.iter() {
+                listener(QueueEvent::Removed(hash));
+            }
+        }
+        self.transactions.clear();
+        self.fee_payer_costs.clear();
+    }
+
+    /// Energy cost of all queued transactions paid by the given fee payer.
+    fn fee_payer_cost(&self, fee_payer: &Address) -> U256 {
+        self.fee_payer_costs
+            .get(fee_payer)
+            .cloned()
+            .unwrap_or_else(U256::zero)
+    }
+
+    fn release_fee_payer_cost(&mut self, tx: &VerifiedTransaction) {
+        let fee_payer = match tx.fee_payer() {
+            Some(fee_payer) => fee_payer,
+            None => return,
+        };
+        let left = match self.fee_payer_costs.get_mut(&fee_payer) {
+            Some(cost) => {
+                *cost = *cost - tx.gas_cost();
+                *cost
+            }
+            None => return,
+        };
+        if left.is_zero() {
+            self.fee_payer_costs.remove(&fee_payer);
+        }
     }
 }
 
+impl Deref for TransactionsByHash {
+    type Target = HashMap<H256, VerifiedTransaction>;
+
+    fn deref(&self) -> &Self::Target { &self.transactions }
+}
+
 #[derive(Debug, Default)]
 struct GasPriceQueue {
     backing: BTreeMap<U256, HashSet<H256>>,
@@ -436,7 +534,7 @@ impl TransactionSet {
     /// Returns addresses and lowest nonces of transactions removed because of limit.
     fn enforce_limit(
         &mut self,
-        by_hash: &mut HashMap<H256, VerifiedTransaction>,
+        by_hash: &mut TransactionsByHash,
         local: &mut LocalTransactionsList,
     ) -> Option<HashMap<Address, U256>>
     {
@@ -617,7 +715,7 @@ pub struct TransactionQueue {
     /// Priority queue for transactions that has been received but are not yet valid to go to block
     future: TransactionSet,
     /// All transactions managed by queue indexed by hash
-    by_hash: HashMap<H256, VerifiedTransaction>,
+    by_hash: TransactionsByHash,
     /// Last nonce of transaction in current (to quickly check next expected transaction)
     last_nonces: HashMap<Address, U256>,
     /// List of local transactions and their statuses.
@@ -673,13 +771,18 @@ impl TransactionQueue {
             max_time_in_queue: DEFAULT_QUEUING_PERIOD,
             current,
             future,
-            by_hash: HashMap::new(),
+            by_hash: TransactionsByHash::default(),
             last_nonces: HashMap::new(),
             local_transactions: LocalTransactionsList::new_default(io_channel),
             next_transaction_id: 0,
         }
     }
 
+    /// Register a callback told about every transaction entering or leaving the queue.
+    pub fn add_listener(&mut self, listener: QueueListener) {
+        self.by_hash.listeners.push(listener);
+    }
+
     /// Returns current status for this queue
     pub fn status(&self) -> TransactionQueueStatus {
         TransactionQueueStatus {
@@ -750,6 +853,34 @@ impl TransactionQueue {
             origin == TransactionOrigin::Local);
         let client_account = details_provider.fetch_account(&tx.sender());
 
+        // The fee payer has to cover the energy of everything it already pays for in the queue,
+        // less the transaction this one would replace.
+        if let Some(fee_payer) = tx.fee_payer() {
+            let replaced = self
+                .current
+                .by_address
+                .get(&tx.sender(), &tx.nonce)
+                .or_else(|| self.future.by_address.get(&tx.sender(), &tx.nonce))
+                .and_then(|order| self.by_hash.get(&order.hash))
+                .filter(|old| old.fee_payer() == Some(fee_payer))
+                .map_or_else(U256::zero, |old| old.gas_cost());
+            let queued = self.by_hash.fee_payer_cost(&fee_payer) - replaced;
+            let cost = queued + tx.gas_price * tx.gas;
+            let balance = details_provider.fetch_account(&fee_payer).balance;
+            if cost > balance {
+                trace!(target: "txqueue",
+                    "Rejecting transaction whose fee payer can't cover the queued energy (hash: {:?}, fee payer: {:?}, cost: {:?}, balance: {:?})",
+                    tx.hash(),
+                    fee_payer,
+                    cost,
+                    balance);
+                return Err(transaction::Error::InsufficientBalance {
+                    cost,
+                    balance,
+                });
+            }
+        }
+
         // Construct VerifiedTransaction
         let id = self.next_transaction_id;
         self.next_transaction_id += 1;
@@ -825,6 +956,52 @@ impl TransactionQueue {
             self.cull(*sender, details.nonce);
         }
 
+        let fee_payers = self
+            .by_hash
+            .values()
+            .filter_map(|tx| tx.fee_payer())
+            .filter(|fee_payer| !senders.contains_key(fee_payer))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|fee_payer| (fee_payer, fetch_account(&fee_payer)))
+            .collect::<HashMap<_, _>>();
+
+        // Keep the fee-delegated transactions each fee payer can pay for, oldest first, since the
+        // balance of a fee payer is shared by every transaction it pays the energy of.
+        let unpaid = {
+            let mut by_fee_payer = HashMap::new();
+            for tx in self.by_hash.values() {
+                if let Some(fee_payer) = tx.fee_payer() {
+                    by_fee_payer
+                        .entry(fee_payer)
+                        .or_insert_with(Vec::new)
+                        .push(tx);
+                }
+            }
+
+            let mut unpaid = HashSet::new();
+            for (fee_payer, mut txs) in by_fee_payer {
+                let balance = match senders
+                    .get(&fee_payer)
+                    .or_else(|| fee_payers.get(&fee_payer))
+                {
+                    Some(details) => details.balance,
+                    None => continue,
+                };
+                txs.sort_by_key(|tx| tx.insertion_id);
+                let mut paid = U256::zero();
+                for tx in txs {
+                    match paid.overflowing_add(tx.gas_cost()) {
+                        (total, false) if total <= balance => paid = total,
+                        _ => {
+                            unpaid.insert(tx.hash());
+                        }
+                    }
+                }
+            }
+            unpaid
+        };
+
         let max_time = self.max_time_in_queue;
         let balance_check = max_time >> 3;
         // Clear transactions occupying the queue too long
@@ -839,8 +1016,10 @@ impl TransactionQueue {
                 }
 
                 if time_diff > balance_check {
+                    let fee_payer_short = unpaid.contains(hash);
                     return match senders.get(&tx.sender()) {
                         Some(details) if tx.cost() > details.balance => Some(*hash),
+                        Some(_) if fee_payer_short => Some(*hash),
                         _ => None,
                     };
                 }
@@ -1391,7 +1570,7 @@ impl TransactionQueue {
         base_nonce: U256,
         strategy: PrioritizationStrategy,
         set: &mut TransactionSet,
-        by_hash: &mut HashMap<H256, VerifiedTransaction>,
+        by_hash: &mut TransactionsByHash,
         local: &mut LocalTransactionsList,
     ) -> bool
     {
@@ -1423,7 +1602,7 @@ impl TransactionQueue {
         old: TransactionOrder,
         order: TransactionOrder,
         set: &mut TransactionSet,
-        by_hash: &mut HashMap<H256, VerifiedTransaction>,
+        by_hash: &mut TransactionsByHash,
         local: &mut LocalTransactionsList,
     ) -> bool
     {
@@ -1490,7 +1669,7 @@ fn check_if_removed(
 pub mod test {
     use aion_types::{U256, Address};
     use super::*;
-    use key::{generate_keypair};
+    use key::{generate_keypair, Ed25519KeyPair};
     use rustc_hex::FromHex;
     use transaction::Transaction;
     use io::IoService;
@@ -1719,7 +1898,7 @@ pub mod test {
         let tx1 = VerifiedTransaction::new(tx1, TransactionOrigin::External, None, 0, 0);
         let tx2 = VerifiedTransaction::new(tx2, TransactionOrigin::External, None, 0, 1);
         let mut by_hash = {
-            let mut x = HashMap::new();
+            let mut x = TransactionsByHash::default();
             let tx1 = VerifiedTransaction::new(
                 tx1.transaction.clone(),
                 TransactionOrigin::External,
@@ -2725,6 +2904,53 @@ pub mod test {
         assert_eq!(stats.pending, 0);
     }
 
+    #[test]
+    fn should_tell_listeners_about_queue_changes() {
+        // given
+        let mut txq = TransactionQueue::default();
+        let events = ::std::sync::Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        txq.add_listener(Box::new(move |event: QueueEvent| {
+            let event = match event {
+                QueueEvent::Added(transaction, _) => (true, transaction.hash()),
+                QueueEvent::Removed(hash) => (false, *hash),
+            };
+            recorded.lock().push(event);
+        }));
+        let (tx, tx2) = new_tx_pair_default(1.into(), 0.into());
+
+        // when
+        txq.add(
+            tx.clone(),
+            TransactionOrigin::External,
+            0,
+            None,
+            &default_tx_provider(),
+        )
+        .unwrap();
+        txq.add(
+            tx2.clone(),
+            TransactionOrigin::External,
+            0,
+            None,
+            &default_tx_provider(),
+        )
+        .unwrap();
+        txq.remove(&tx.hash(), &|_| 0.into(), RemovalReason::Invalid);
+        txq.clear();
+
+        // then
+        assert_eq!(
+            *events.lock(),
+            vec![
+                (true, tx.hash()),
+                (true, tx2.hash()),
+                (false, tx.hash()),
+                (false, tx2.hash()),
+            ]
+        );
+    }
+
     #[test]
     fn should_drop_old_transactions_when_hitting_the_limit() {
         // given
@@ -3531,6 +3757,77 @@ pub mod test {
         assert_eq!(txq.top_transactions(), vec![tx1, tx3]);
     }
 
+    fn new_fee_delegated_tx(fee_payer: &Ed25519KeyPair) -> SignedTransaction {
+        let mut tx = new_unsigned_tx(default_nonce(), default_gas_val(), default_gas_price());
+        tx.transaction_type = transaction::FEE_DELEGATED_TRANSACTION_TYPE;
+        let tx: transaction::UnverifiedTransaction =
+            tx.sign(generate_keypair().secret(), None).into();
+        tx.sign_fee_payer(fee_payer.secret())
+    }
+
+    #[test]
+    fn should_reject_transactions_the_fee_payer_cannot_pay_for_together() {
+        // given
+        let fee_payer = generate_keypair();
+        let tx1 = new_fee_delegated_tx(&fee_payer);
+        let tx2 = new_fee_delegated_tx(&fee_payer);
+        let mut txq = TransactionQueue::default();
+        let provider = default_tx_provider().with_account(AccountDetails {
+            nonce: default_nonce(),
+            balance: 150_000.into(),
+        });
+
+        // when
+        txq.add(tx1.clone(), TransactionOrigin::External, 0, None, &provider).unwrap();
+        let res = txq.add(tx2, TransactionOrigin::External, 0, None, &provider);
+
+        // then
+        assert_eq!(
+            unwrap_tx_err(res),
+            transaction::Error::InsufficientBalance {
+                balance: 150_000.into(),
+                cost: 200_000.into(),
+            }
+        );
+        assert_eq!(txq.top_transactions(), vec![tx1]);
+    }
+
+    #[test]
+    fn should_remove_transactions_the_fee_payer_cannot_pay_for_together() {
+        // given
+        let fee_payer = generate_keypair();
+        let tx1 = new_fee_delegated_tx(&fee_payer);
+        let tx2 = new_fee_delegated_tx(&fee_payer);
+        let mut txq = TransactionQueue::default();
+        txq.add(
+            tx1.clone(),
+            TransactionOrigin::External,
+            0,
+            None,
+            &default_tx_provider(),
+        )
+        .unwrap();
+        txq.add(
+            tx2,
+            TransactionOrigin::External,
+            0,
+            None,
+            &default_tx_provider(),
+        )
+        .unwrap();
+        assert_eq!(txq.top_transactions().len(), 2);
+
+        // when
+        let fetch_account = |_: &Address| AccountDetails {
+            nonce: default_nonce(),
+            balance: 150_000.into(),
+        };
+        txq.remove_old(&fetch_account, super::DEFAULT_QUEUING_PERIOD >> 2);
+
+        // then
+        assert_eq!(txq.top_transactions(), vec![tx1]);
+    }
+
     #[test]
     fn should_not_order_transactions_by_hash() {
         // given
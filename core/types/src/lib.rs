@@ -48,6 +48,7 @@ pub mod receipt;
 pub mod restoration_status;
 pub mod security_level;
 pub mod state_diff;
+pub mod state_override;
 pub mod trace_filter;
 pub mod tree_route;
 pub mod verification_queue_info;
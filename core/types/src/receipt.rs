@@ -222,6 +222,8 @@ pub struct LocalizedReceipt {
     pub from: Option<Address>,
     /// to address
     pub to: Option<Address>,
+    /// fee payer address of a fee-delegated transaction
+    pub fee_payer: Option<Address>,
     /// output
     pub output: Bytes,
     /// error message
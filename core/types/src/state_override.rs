@@ -0,0 +1,64 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! State override related types
+
+use std::collections::BTreeMap;
+use aion_types::{Address, H128, H256, U256};
+use bytes::Bytes;
+
+/// Value of a storage slot: a word for FastVM contracts, a double word for AVM contracts.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum StorageValue {
+    /// 16 bytes value.
+    Word(H128),
+    /// 32 bytes value.
+    DWord(H256),
+}
+
+/// Replacements for the fields of an account in the state a call is executed on.
+#[derive(Eq, PartialEq, Default, Clone, Debug)]
+pub struct AccountOverride {
+    /// Balance.
+    pub balance: Option<U256>,
+    /// Nonce.
+    pub nonce: Option<U256>,
+    /// Code.
+    pub code: Option<Bytes>,
+    /// Storage replacing the whole storage of the account.
+    pub state: Option<BTreeMap<H128, StorageValue>>,
+    /// Storage slots replaced one by one, the other slots are kept.
+    pub state_diff: Option<BTreeMap<H128, StorageValue>>,
+}
+
+/// Account overrides by address.
+pub type StateOverride = BTreeMap<Address, AccountOverride>;
+
+/// Replacements for the environment of the block a call is executed in.
+#[derive(Eq, PartialEq, Default, Clone, Debug)]
+pub struct BlockOverride {
+    /// Block number.
+    pub number: Option<u64>,
+    /// Block timestamp.
+    pub timestamp: Option<u64>,
+    /// Block author.
+    pub author: Option<Address>,
+}
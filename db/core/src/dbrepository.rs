@@ -66,6 +66,8 @@ impl MemoryDBRepository {
     }
     fn flush(&self) -> Result<()> { Ok(()) }
 
+    fn flush_db(&self, _db_name: &str) -> Result<()> { Ok(()) }
+
     #[cfg(test)]
     fn close_all(&mut self) {}
 
@@ -104,19 +106,24 @@ impl DbRepository {
     }
     /// flush overlay to disk
     fn flush(&self) -> Result<()> {
-        for db_name in self.db_priority.clone() {
-            match self.dbs.get(&*db_name) {
-                Some(db) => {
-                    let mut db = db.write();
-                    db.flush().map_err(|e| {
-                        Error::FlushError {
-                            name: db_name,
-                            desc: e,
-                        }
-                    })?;
-                }
-                _ => error!(target: "db","db:{} not found",db_name),
+        for db_name in self.db_priority.iter() {
+            self.flush_db(db_name)?;
+        }
+        Ok(())
+    }
+    /// flush the overlay of one db to disk
+    fn flush_db(&self, db_name: &str) -> Result<()> {
+        match self.dbs.get(db_name) {
+            Some(db) => {
+                let mut db = db.write();
+                db.flush().map_err(|e| {
+                    Error::FlushError {
+                        name: db_name.into(),
+                        desc: e,
+                    }
+                })?;
             }
+            _ => error!(target: "db","db:{} not found",db_name),
         }
         Ok(())
     }
@@ -162,6 +169,8 @@ impl MockDbRepository {
     }
     /// flush all db
     fn flush(&self) -> Result<()> { Ok(()) }
+    /// flush one db
+    fn flush_db(&self, _db_name: &str) -> Result<()> { Ok(()) }
     /// close all dbs
     fn close_all(&mut self) { self.dbs.clear(); }
     /// reopen all dbs
@@ -260,7 +269,7 @@ macro_rules! impl_keyvaluedb {
             fn iter_from_prefix<'a>(
                 &'a self,
                 db_name: &str,
-                prefix: &'static [u8],
+                prefix: &[u8],
             ) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>
             {
                 match self.dbs.get(db_name) {
@@ -278,6 +287,8 @@ macro_rules! impl_keyvaluedb {
 
             fn flush(&self) -> Result<()> { $name::flush(self) }
 
+            fn flush_db(&self, db_name: &str) -> Result<()> { $name::flush_db(self, db_name) }
+
             #[cfg(test)]
             fn close_all(&mut self) { $name::close_all(self); }
             #[cfg(test)]
@@ -75,16 +75,13 @@ impl KeyValueDAO for Mockkvdb {
             .map(|(_, v)| v.clone().into_vec().into_boxed_slice())
     }
 
-    fn iter_from_prefix(
-        &self,
-        prefix: &'static [u8],
-    ) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)>>
-    {
+    fn iter_from_prefix(&self, prefix: &[u8]) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)>> {
+        let prefix = prefix.to_vec();
         Box::new(
             self.db
                 .clone()
                 .into_iter()
-                .skip_while(move |(k, _)| !k.starts_with(prefix))
+                .skip_while(move |(k, _)| !k.starts_with(&prefix))
                 .map(|(k, v)| {
                     (
                         k.into_vec().into_boxed_slice(),
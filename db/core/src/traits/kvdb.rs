@@ -34,10 +34,7 @@ pub trait KeyValueDAO: Sync + Send {
     /// Get value by partial key. Prefix size should match configured prefix size. Only searches flushed values.
     fn get_by_prefix(&self, prefix: &[u8]) -> Option<Box<[u8]>>;
     /// Return an iterator, from the beginning the key that prefix size matching the configured prefix size
-    fn iter_from_prefix(
-        &self,
-        prefix: &'static [u8],
-    ) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)>>;
+    fn iter_from_prefix(&self, prefix: &[u8]) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)>>;
 }
 /// db repository operation.
 pub trait KeyValueDB: Sync + Send {
@@ -53,6 +50,8 @@ pub trait KeyValueDB: Sync + Send {
     fn write_buffered(&self, transaction: DBTransaction);
     /// Flush db
     fn flush(&self) -> Result<()> { Ok(()) }
+    /// Flush the specified db only, leaving the writes buffered in the others
+    fn flush_db(&self, db_name: &str) -> Result<()>;
     /// Return a specified db' iterator
     fn iter(&self, db_name: &'static str) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)>>;
     /// Get value by partial key. Prefix size should match configured prefix size. Only searches flushed values.
@@ -61,7 +60,7 @@ pub trait KeyValueDB: Sync + Send {
     fn iter_from_prefix<'a>(
         &'a self,
         db_name: &'static str,
-        prefix: &'static [u8],
+        prefix: &[u8],
     ) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
     /// Close all dbs
     #[cfg(test)]
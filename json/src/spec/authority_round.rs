@@ -0,0 +1,115 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Authority round engine params deserialization.
+
+use hash::Address;
+use uint::Uint;
+
+/// Validator set deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub enum ValidatorSet {
+    /// A simple list of authorities.
+    #[serde(rename = "list")]
+    List(Vec<Address>),
+    /// Address of a contract that indicates the list of authorities.
+    #[serde(rename = "contract")]
+    Contract(Address),
+}
+
+/// Authority round params deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct AuthorityRoundParams {
+    /// Duration of a step in seconds.
+    #[serde(rename = "stepDuration")]
+    pub step_duration: Uint,
+    /// Valid authorities.
+    pub validators: ValidatorSet,
+    /// Starting step. Determined automatically if not specified.
+    #[serde(rename = "startStep")]
+    pub start_step: Option<Uint>,
+    /// Block reward.
+    #[serde(rename = "blockReward")]
+    pub block_reward: Option<Uint>,
+}
+
+/// Authority round engine deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct AuthorityRound {
+    /// Authority round engine params.
+    pub params: AuthorityRoundParams,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use uint::Uint;
+    use aion_types::{U256, H256};
+    use hash::Address;
+    use spec::authority_round::{AuthorityRound, ValidatorSet};
+
+    #[test]
+    fn authority_round_deserialization() {
+        let s = r#"{
+            "params": {
+                "stepDuration": "0x02",
+                "validators": {
+                    "list" : ["0xa00a2d0d10ce8a2ea47a76fbb935405df2a12b0e2bc932f188f84b5f16da9c2c"]
+                },
+                "startStep" : 24,
+                "blockReward": "0x0d"
+            }
+        }"#;
+
+        let deserialized: AuthorityRound = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.params.step_duration, Uint(U256::from(0x02)));
+        assert_eq!(
+            deserialized.params.validators,
+            ValidatorSet::List(vec![Address(H256::from(
+                "0xa00a2d0d10ce8a2ea47a76fbb935405df2a12b0e2bc932f188f84b5f16da9c2c"
+            ))])
+        );
+        assert_eq!(deserialized.params.start_step, Some(Uint(U256::from(24))));
+        assert_eq!(deserialized.params.block_reward, Some(Uint(U256::from(0x0d))));
+    }
+
+    #[test]
+    fn authority_round_contract_deserialization() {
+        let s = r#"{
+            "params": {
+                "stepDuration": "0x0a",
+                "validators": {
+                    "contract" : "0x0000000000000000000000000000000000000000000000000000000000000300"
+                }
+            }
+        }"#;
+
+        let deserialized: AuthorityRound = serde_json::from_str(s).unwrap();
+        assert_eq!(
+            deserialized.params.validators,
+            ValidatorSet::Contract(Address(H256::from(
+                "0x0000000000000000000000000000000000000000000000000000000000000300"
+            )))
+        );
+        assert_eq!(deserialized.params.start_step, None);
+    }
+}
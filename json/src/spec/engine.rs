@@ -22,7 +22,7 @@
 
 //! Engine deserialization.
 
-use super::{POWEquihashEngine, NullEngine};
+use super::{POWEquihashEngine, NullEngine, AuthorityRound};
 
 /// Engine deserialization.
 #[derive(Debug, PartialEq, Deserialize)]
@@ -35,6 +35,9 @@ pub enum Engine {
     /// Instantly sealing engine.
     #[serde(rename = "instantSeal")]
     InstantSeal,
+    /// Authority round engine.
+    #[serde(rename = "authorityRound")]
+    AuthorityRound(AuthorityRound),
 }
 
 #[cfg(test)]
@@ -67,5 +70,22 @@ mod tests {
             Engine::InstantSeal => {} // instant seal is unit tested in its own file.
             _ => panic!(),
         };
+
+        let s = r#"{
+            "authorityRound": {
+                "params": {
+                    "stepDuration": "0x05",
+                    "validators": {
+                        "list" : ["0xa00a2d0d10ce8a2ea47a76fbb935405df2a12b0e2bc932f188f84b5f16da9c2c"]
+                    }
+                }
+            }
+        }"#;
+
+        let deserialized: Engine = serde_json::from_str(s).unwrap();
+        match deserialized {
+            Engine::AuthorityRound(_) => {} // authority round is unit tested in its own file.
+            _ => panic!(),
+        };
     }
 }
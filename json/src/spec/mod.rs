@@ -32,6 +32,7 @@ pub mod engine;
 pub mod state;
 pub mod pow_equihash_engine;
 pub mod null_engine;
+pub mod authority_round;
 
 pub use self::account::Account;
 pub use self::builtin::Builtin;
@@ -43,3 +44,4 @@ pub use self::engine::Engine;
 pub use self::state::State;
 pub use self::pow_equihash_engine::{POWEquihashEngineParams, POWEquihashEngine};
 pub use self::null_engine::{NullEngine, NullEngineParams};
+pub use self::authority_round::{AuthorityRound, AuthorityRoundParams, ValidatorSet};
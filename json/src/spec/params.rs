@@ -43,6 +43,15 @@ pub struct Params {
     /// Transaction permission contract address.
     #[serde(rename = "transactionPermissionContract")]
     pub transaction_permission_contract: Option<Address>,
+    /// See `CommonParams` docs.
+    #[serde(rename = "chainID")]
+    pub chain_id: Option<Uint>,
+    /// See `CommonParams` docs.
+    #[serde(rename = "chainIdTransition")]
+    pub chain_id_transition: Option<Uint>,
+    /// See `CommonParams` docs.
+    #[serde(rename = "feeDelegationTransition")]
+    pub fee_delegation_transition: Option<Uint>,
 }
 
 #[cfg(test)]
@@ -64,6 +73,37 @@ mod tests {
         assert_eq!(deserialized.maximum_extra_data_size, Uint(U256::from(0x20)));
         assert_eq!(deserialized.min_gas_limit, Uint(U256::from(0x1388)));
         assert_eq!(deserialized.gas_limit_bound_divisor, Uint(U256::from(0x20)));
+        assert_eq!(deserialized.chain_id, None);
+        assert_eq!(deserialized.chain_id_transition, None);
+        assert_eq!(deserialized.fee_delegation_transition, None);
+    }
+
+    #[test]
+    fn params_chain_id_deserialization() {
+        let s = r#"{
+            "maximumExtraDataSize": "0x20",
+            "minGasLimit": "0x1388",
+            "gasLimitBoundDivisor": "0x20",
+            "chainID": "0x20",
+            "chainIdTransition": "0x3e8"
+        }"#;
+
+        let deserialized: Params = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.chain_id, Some(Uint(U256::from(0x20))));
+        assert_eq!(deserialized.chain_id_transition, Some(Uint(U256::from(0x3e8))));
+    }
+
+    #[test]
+    fn params_fee_delegation_deserialization() {
+        let s = r#"{
+            "maximumExtraDataSize": "0x20",
+            "minGasLimit": "0x1388",
+            "gasLimitBoundDivisor": "0x20",
+            "feeDelegationTransition": "0x7d0"
+        }"#;
+
+        let deserialized: Params = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.fee_delegation_transition, Some(Uint(U256::from(0x7d0))));
     }
 
     #[test]
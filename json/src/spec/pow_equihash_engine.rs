@@ -19,6 +19,9 @@
  *
  ******************************************************************************/
 
+use std::collections::BTreeMap;
+
+use hash::Address;
 use uint::Uint;
 
 #[derive(Clone, Debug, PartialEq, Deserialize)]
@@ -43,6 +46,22 @@ pub struct POWEquihashEngineParams {
     pub block_time_upper_bound: Option<u64>,
     #[serde(rename = "minimumDifficulty")]
     pub minimum_difficulty: Option<Uint>,
+    /// Block from which on blocks may also be sealed by stakers.
+    #[serde(rename = "unityForkBlock")]
+    pub unity_fork_block: Option<u64>,
+    #[serde(rename = "stakeBlockTimeLowerBound")]
+    pub stake_block_time_lower_bound: Option<u64>,
+    #[serde(rename = "stakeBlockTimeUpperBound")]
+    pub stake_block_time_upper_bound: Option<u64>,
+    #[serde(rename = "stakeMinimumDifficulty")]
+    pub stake_minimum_difficulty: Option<Uint>,
+    #[serde(rename = "stakeInitialDifficulty")]
+    pub stake_initial_difficulty: Option<Uint>,
+    /// Address of the staking registry contract.
+    #[serde(rename = "stakingRegistry")]
+    pub staking_registry: Option<Address>,
+    /// Fixed stakes, used instead of a registry on test chains.
+    pub stakers: Option<BTreeMap<Address, Uint>>,
 }
 
 /// pow equihash engine deserialization
@@ -51,3 +70,27 @@ pub struct POWEquihashEngine {
     /// pow equihash engine params.
     pub params: POWEquihashEngineParams,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use spec::pow_equihash_engine::POWEquihashEngine;
+
+    #[test]
+    fn pow_equihash_engine_deserialization() {
+        let s = r#"{
+            "params": {
+                "unityForkBlock": 10,
+                "stakeInitialDifficulty": "0x10",
+                "stakers": {
+                    "0xa07bfd7baa8497fd43258a5442a26f277206f62a98668ae2212ab3f4c71a10c8": "0x05"
+                }
+            }
+        }"#;
+
+        let deserialized: POWEquihashEngine = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.params.unity_fork_block, Some(10));
+        assert_eq!(deserialized.params.staking_registry, None);
+        assert_eq!(deserialized.params.stakers.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,457 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Append-only journal of the whole transaction queue.
+//!
+//! Every transaction entering the queue is recorded as an `Insert` and every transaction
+//! leaving it as a `Remove`, as the queue tells its listeners, keyed by an increasing
+//! sequence number in a column of its own. Replaying the records in key order gives back
+//! the queue as it was before the node went down.
+//!
+//! The queue only hands the events over to a writer thread, which encodes and appends the
+//! records, flushes the journal column every `FLUSH_INTERVAL_MS` and rewrites the journal
+//! as a snapshot of the queue once it has grown past twice the queue.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use acore::transaction::transaction_queue::{QueueEvent, QueueListener};
+use acore::transaction::PendingTransaction;
+use aion_types::H256;
+use db::{DBTransaction, KeyValueDB};
+use parking_lot::Mutex;
+
+use super::{Error, NodeInfo, TransactionEntry};
+
+const FLUSH_INTERVAL_MS: u64 = 2 * 1000; // once every 2 seconds.
+/// Records appended before the journal is worth compacting, however small the queue.
+const MIN_COMPACT_RECORDS: u64 = 4096;
+
+#[derive(Serialize, Deserialize)]
+enum JournalRecord {
+    Insert(H256, TransactionEntry),
+    Remove(H256),
+}
+
+enum Message {
+    Added(PendingTransaction),
+    Removed(H256),
+    /// Flush what was sent before and reply.
+    Flush(Sender<()>),
+    Shutdown,
+}
+
+/// Create a new transaction queue journal, given a database, a column of its own to write to,
+/// and a node.
+pub fn create<T: NodeInfo + 'static>(
+    db: Arc<KeyValueDB>,
+    db_name: &'static str,
+    node: T,
+) -> TransactionJournal<T>
+{
+    let store = Arc::new(JournalStore {
+        db: db,
+        db_name: db_name,
+        node: node,
+        next_seq: Mutex::new(0),
+    });
+    let (sender, receiver) = mpsc::channel();
+    let writer = {
+        let store = store.clone();
+        thread::Builder::new()
+            .name("tx-journal".into())
+            .spawn(move || store.write_records(receiver))
+            .expect("Failed to spawn transaction journal writer")
+    };
+
+    TransactionJournal {
+        store: store,
+        sender: Mutex::new(sender),
+        writer: Some(writer),
+    }
+}
+
+/// Drop every journal record in the given column, without creating a journal.
+pub fn clear(db: &KeyValueDB, db_name: &'static str) -> Result<(), Error> {
+    trace!(target: "local_store", "Clearing transaction journal.");
+
+    let mut batch = DBTransaction::new();
+    for (key, _) in db.iter(db_name) {
+        batch.delete(db_name, &key);
+    }
+    db.write_buffered(batch);
+    db.flush_db(db_name).map_err(Error::Database)
+}
+
+/// Journals every transaction of the queue, not only the local ones.
+pub struct TransactionJournal<T: NodeInfo> {
+    store: Arc<JournalStore<T>>,
+    sender: Mutex<Sender<Message>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl<T: NodeInfo> TransactionJournal<T> {
+    /// Replay the journal and return the transactions left queued, in insertion order.
+    ///
+    /// Nothing is validated here apart from the signature; callers are expected to
+    /// re-import the transactions against the current state.
+    pub fn transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
+        self.store.transactions()
+    }
+
+    /// Rewrite the journal as a snapshot of the current queue. Meant to be called after the
+    /// replayed transactions are queued again, before listening to the queue.
+    pub fn compact(&self) -> Result<(), Error> { self.store.compact().map(|_| ()) }
+
+    /// Listener to register with the queue. It only passes the events on to the writer, as
+    /// it is called with the queue lock held; events after the journal is gone are dropped.
+    pub fn listener(&self) -> QueueListener {
+        let sender = Mutex::new(self.sender.lock().clone());
+        Box::new(move |event: QueueEvent| {
+            let message = match event {
+                QueueEvent::Added(transaction, condition) => {
+                    Message::Added(PendingTransaction::new(
+                        transaction.clone(),
+                        condition.cloned(),
+                    ))
+                }
+                QueueEvent::Removed(hash) => Message::Removed(*hash),
+            };
+            let _ = sender.lock().send(message);
+        })
+    }
+
+    /// Wait for the events received so far to be written and flushed to disk.
+    pub fn flush(&self) {
+        let (sender, receiver) = mpsc::channel();
+        if self.sender.lock().send(Message::Flush(sender)).is_ok() {
+            let _ = receiver.recv();
+        }
+    }
+}
+
+impl<T: NodeInfo> Drop for TransactionJournal<T> {
+    fn drop(&mut self) {
+        debug!(target: "local_store", "Flushing transaction journal on shutdown.");
+
+        let _ = self.sender.lock().send(Message::Shutdown);
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+struct JournalStore<T: NodeInfo> {
+    db: Arc<KeyValueDB>,
+    db_name: &'static str,
+    node: T,
+    /// Sequence number of the next record.
+    next_seq: Mutex<u64>,
+}
+
+impl<T: NodeInfo> JournalStore<T> {
+    fn transactions(&self) -> Result<Vec<PendingTransaction>, Error> {
+        let mut queued: HashMap<H256, (u64, TransactionEntry)> = HashMap::new();
+        let mut last_seq = None;
+
+        for (seq, value) in self.records() {
+            let record = ::serde_json::from_slice::<JournalRecord>(&value).map_err(Error::Json)?;
+            match record {
+                JournalRecord::Insert(hash, entry) => {
+                    queued.entry(hash).or_insert((seq, entry));
+                }
+                JournalRecord::Remove(hash) => {
+                    queued.remove(&hash);
+                }
+            }
+            last_seq = Some(seq);
+        }
+
+        *self.next_seq.lock() = last_seq.map_or(0, |seq| seq + 1);
+
+        let mut entries: Vec<_> = queued.into_iter().map(|(_, entry)| entry).collect();
+        entries.sort_by_key(|&(seq, _)| seq);
+
+        Ok(entries
+            .into_iter()
+            .filter_map(|(_, entry)| entry.into_pending())
+            .collect())
+    }
+
+    // rewrites the journal as a snapshot of the queue, returns the records written. The
+    // events still waiting for the writer are applied over the snapshot afterwards, which
+    // leaves every transaction as its last event says.
+    fn compact(&self) -> Result<u64, Error> {
+        trace!(target: "local_store", "Compacting transaction journal.");
+
+        let queued = self.node.queued_transactions();
+
+        let mut next_seq = self.next_seq.lock();
+        let mut batch = DBTransaction::new();
+        for (seq, _) in self.records() {
+            batch.delete(self.db_name, &journal_key(seq));
+        }
+
+        let len = queued.len() as u64;
+        for (seq, pending) in queued.into_iter().enumerate() {
+            let record = JournalRecord::Insert(pending.hash(), pending.into());
+            self.put_record(&mut batch, seq as u64, &record)?;
+        }
+
+        self.db.write_buffered(batch);
+        self.db.flush_db(self.db_name).map_err(Error::Database)?;
+        *next_seq = len;
+        Ok(len)
+    }
+
+    fn append(&self, record: &JournalRecord) -> Result<(), Error> {
+        let mut next_seq = self.next_seq.lock();
+        let mut batch = DBTransaction::new();
+        self.put_record(&mut batch, *next_seq, record)?;
+        self.db.write_buffered(batch);
+        *next_seq += 1;
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        trace!(target: "local_store", "Flushing transaction journal records.");
+        self.db.flush_db(self.db_name).map_err(Error::Database)
+    }
+
+    // the writer thread, until the journal is dropped.
+    fn write_records(&self, receiver: Receiver<Message>) {
+        let interval = Duration::from_millis(FLUSH_INTERVAL_MS);
+        let mut last_flush = Instant::now();
+        let mut dirty = false;
+        // records of the last snapshot written by this thread, and records appended since.
+        let mut snapshot_len = 0;
+        let mut appended = 0;
+
+        loop {
+            let timeout = interval
+                .checked_sub(last_flush.elapsed())
+                .unwrap_or_else(|| Duration::from_millis(0));
+            let (record, done) = match receiver.recv_timeout(timeout) {
+                Ok(Message::Added(pending)) => {
+                    (Some(JournalRecord::Insert(pending.hash(), pending.into())), None)
+                }
+                Ok(Message::Removed(hash)) => (Some(JournalRecord::Remove(hash)), None),
+                Ok(Message::Flush(done)) => (None, Some(done)),
+                Err(RecvTimeoutError::Timeout) => (None, None),
+                Ok(Message::Shutdown) | Err(RecvTimeoutError::Disconnected) => {
+                    if dirty {
+                        if let Err(e) = self.flush() {
+                            debug!(target: "local_store", "Error flushing transaction journal: {}", e);
+                        }
+                    }
+                    return;
+                }
+            };
+
+            if let Some(record) = record {
+                match self.append(&record) {
+                    Ok(()) => {
+                        dirty = true;
+                        appended += 1;
+                    }
+                    Err(e) => debug!(target: "local_store", "Error appending to transaction journal: {}", e),
+                }
+            }
+
+            if appended > cmp::max(MIN_COMPACT_RECORDS, snapshot_len) {
+                match self.compact() {
+                    Ok(len) => {
+                        snapshot_len = len;
+                        appended = 0;
+                        dirty = false;
+                    }
+                    Err(e) => debug!(target: "local_store", "Error compacting transaction journal: {}", e),
+                }
+            }
+
+            if done.is_some() || last_flush.elapsed() >= interval {
+                if dirty {
+                    match self.flush() {
+                        Ok(()) => dirty = false,
+                        Err(e) => debug!(target: "local_store", "Error flushing transaction journal: {}", e),
+                    }
+                }
+                last_flush = Instant::now();
+            }
+            if let Some(done) = done {
+                let _ = done.send(());
+            }
+        }
+    }
+
+    // all journal records, ordered by sequence number.
+    fn records(&self) -> Vec<(u64, Box<[u8]>)> {
+        let mut records: Vec<_> = self
+            .db
+            .iter(self.db_name)
+            .filter_map(|(key, value)| journal_seq(&key).map(|seq| (seq, value)))
+            .collect();
+        records.sort_by_key(|&(seq, _)| seq);
+        records
+    }
+
+    fn put_record(
+        &self,
+        batch: &mut DBTransaction,
+        seq: u64,
+        record: &JournalRecord,
+    ) -> Result<(), Error>
+    {
+        let json = ::serde_json::to_vec(record).map_err(Error::Json)?;
+        batch.put_vec(self.db_name, &journal_key(seq), json);
+        Ok(())
+    }
+}
+
+fn journal_key(seq: u64) -> Vec<u8> { (0..8).map(|i| (seq >> (56 - 8 * i)) as u8).collect() }
+
+fn journal_seq(key: &[u8]) -> Option<u64> {
+    if key.len() != 8 {
+        return None;
+    }
+    Some(key.iter().fold(0u64, |seq, byte| (seq << 8) | *byte as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use NodeInfo;
+
+    use std::sync::Arc;
+    use acore::transaction::{Condition, Transaction, PendingTransaction};
+    use acore::transaction::transaction_queue::QueueEvent;
+    use aion_types::H256;
+    use key::generate_keypair;
+    use parking_lot::Mutex;
+
+    struct Queue(Arc<Mutex<Vec<PendingTransaction>>>);
+    impl NodeInfo for Queue {
+        fn pending_transactions(&self) -> Vec<PendingTransaction> { Vec::new() }
+        fn queued_transactions(&self) -> Vec<PendingTransaction> { self.0.lock().clone() }
+    }
+
+    fn transactions(count: u64) -> Vec<PendingTransaction> {
+        let keypair = generate_keypair();
+        (0..count)
+            .map(|nonce| {
+                let mut tx = Transaction::default();
+                tx.nonce = nonce.into();
+                tx.nonce_bytes = vec![nonce as u8];
+                PendingTransaction::new(tx.sign(keypair.secret(), None), None)
+            })
+            .collect()
+    }
+
+    fn added(pending: &PendingTransaction) -> QueueEvent {
+        QueueEvent::Added(&pending.transaction, pending.condition.as_ref())
+    }
+
+    #[test]
+    fn replays_inserts_and_removes() {
+        let db = Arc::new(::db::MockDbRepository::init(vec!["test".into()]));
+        let mut txs = transactions(5);
+        txs[4].condition = Some(Condition::Number(10));
+
+        {
+            let journal = super::create(db.clone(), "test", Queue(Arc::new(Mutex::new(vec![]))));
+            let listener = journal.listener();
+            for pending in &txs[..3] {
+                listener(added(pending));
+            }
+
+            // one transaction mined, two more received.
+            listener(QueueEvent::Removed(&txs[0].hash()));
+            listener(added(&txs[3]));
+            listener(added(&txs[4]));
+            journal.flush();
+
+            assert_eq!(journal.store.records().len(), 6);
+            assert_eq!(journal.transactions().unwrap(), txs[1..].to_vec());
+            ::std::mem::forget(journal);
+        }
+
+        let journal = super::create(db.clone(), "test", Queue(Arc::new(Mutex::new(vec![]))));
+        assert_eq!(journal.transactions().unwrap(), txs[1..].to_vec());
+
+        // appending goes on after the replayed records.
+        journal.listener()(QueueEvent::Removed(&txs[1].hash()));
+        journal.flush();
+        assert_eq!(journal.store.records().len(), 7);
+        assert_eq!(journal.transactions().unwrap(), txs[2..].to_vec());
+    }
+
+    #[test]
+    fn compacts_after_loading() {
+        let db = Arc::new(::db::MockDbRepository::init(vec!["test".into()]));
+        let txs = transactions(4);
+
+        {
+            let journal = super::create(db.clone(), "test", Queue(Arc::new(Mutex::new(vec![]))));
+            let listener = journal.listener();
+            for pending in &txs {
+                listener(added(pending));
+            }
+            for pending in &txs[2..] {
+                listener(QueueEvent::Removed(&pending.hash()));
+            }
+        }
+
+        // the replayed transactions are queued again before compacting.
+        let queue = Arc::new(Mutex::new(vec![]));
+        let journal = super::create(db.clone(), "test", Queue(queue.clone()));
+        assert_eq!(journal.store.records().len(), 6);
+        queue.lock().extend(journal.transactions().unwrap());
+        journal.compact().unwrap();
+        assert_eq!(journal.store.records().len(), 2);
+        assert_eq!(journal.transactions().unwrap(), txs[..2].to_vec());
+
+        super::clear(&*db, "test").unwrap();
+        assert_eq!(journal.transactions().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn compacts_once_grown_past_the_queue() {
+        let db = Arc::new(::db::MockDbRepository::init(vec!["test".into()]));
+        let txs = transactions(2);
+        let journal = super::create(db.clone(), "test", Queue(Arc::new(Mutex::new(txs.clone()))));
+        let listener = journal.listener();
+
+        for i in 0..super::MIN_COMPACT_RECORDS {
+            listener(QueueEvent::Removed(&H256::from(i)));
+        }
+        journal.flush();
+        assert_eq!(journal.store.records().len() as u64, super::MIN_COMPACT_RECORDS);
+
+        // one more record and the journal is just the queue again.
+        listener(QueueEvent::Removed(&H256::from(0)));
+        journal.flush();
+        assert_eq!(journal.store.records().len(), 2);
+        assert_eq!(journal.transactions().unwrap(), txs);
+    }
+}
@@ -20,7 +20,8 @@
  *
  ******************************************************************************/
 
-//! Manages local node data: pending local transactions, sync security level
+//! Manages local node data: pending local transactions, sync security level,
+//! and optionally a journal of the whole transaction queue.
 
 use std::sync::Arc;
 use std::fmt;
@@ -35,6 +36,8 @@ use rlp::UntrustedRlp;
 
 extern crate acore;
 extern crate acore_io as io;
+extern crate aion_types;
+extern crate parking_lot;
 extern crate rlp;
 extern crate serde_json;
 extern crate serde;
@@ -49,6 +52,10 @@ extern crate log;
 #[cfg(test)]
 extern crate key;
 
+pub mod journal;
+
+pub use journal::TransactionJournal;
+
 use db::KeyValueDB;
 const LOCAL_TRANSACTIONS_KEY: &'static [u8] = &*b"LOCAL_TXS";
 
@@ -137,6 +144,9 @@ impl From<PendingTransaction> for TransactionEntry {
 pub trait NodeInfo: Send + Sync {
     /// Get all pending transactions of local origin.
     fn pending_transactions(&self) -> Vec<PendingTransaction>;
+
+    /// Get all transactions in the queue (current and future), regardless of origin.
+    fn queued_transactions(&self) -> Vec<PendingTransaction>;
 }
 
 /// Create a new local data store, given a database, a column to write to, and a node.
@@ -255,6 +265,7 @@ mod tests {
     struct Dummy(Vec<PendingTransaction>);
     impl NodeInfo for Dummy {
         fn pending_transactions(&self) -> Vec<PendingTransaction> { self.0.clone() }
+        fn queued_transactions(&self) -> Vec<PendingTransaction> { self.0.clone() }
     }
 
     #[test]
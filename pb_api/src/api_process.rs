@@ -21,13 +21,18 @@
 
 #![allow(dead_code)]
 #![allow(non_snake_case)]
+use aion_rpc::informant::{RpcStats, Transport};
 use aion_rpc::traits::Pb;
 use std::sync::Arc;
+use std::time::Instant;
 use pb_api_util::*;
 use message::*;
 use protobuf::{ Message, ProtobufEnum};
 use aion_types::{U256, H256};
-use aion_rpc::types::{Transaction, Block, BlockTransactions, U256 as RpcU256, H256 as RpcH256, SimpleReceipt};
+use aion_rpc::types::{
+    Transaction, Block, BlockNumber, BlockNumberOrHash, BlockTransactions, Receipt,
+    U256 as RpcU256, H256 as RpcH256, SimpleReceipt,
+};
 use rustc_hex::{ToHex};
 use acore::transaction::local_transactions::TxIoMessage;
 use io::{IoService, IoHandler};
@@ -40,6 +45,7 @@ use super::LOG_TARGET;
 const API_VER: u8 = 2;
 const API_REQHEADER_LEN: usize = 4;
 const TX_HASH_LEN: usize = 32;
+const BLOCK_HASH_LEN: usize = 32;
 const ACCOUNT_CREATE_LIMIT: usize = 100;
 
 macro_rules! api_try {
@@ -67,10 +73,16 @@ pub struct ApiProcess {
     msg_id_mapping: Arc<RwLock<HashMap<H256, SimpleEntry>>>,
     pending_status: Arc<MsQueue<TxPendingStatus>>,
     io_service: Arc<IoService<TxIoMessage>>,
+    stats: Arc<RpcStats>,
 }
 
 impl ApiProcess {
-    pub fn new(ethclient: Arc<Pb>, io_service: IoService<TxIoMessage>) -> Self {
+    pub fn new(
+        ethclient: Arc<Pb>,
+        io_service: IoService<TxIoMessage>,
+        stats: Arc<RpcStats>,
+    ) -> Self
+    {
         let pending_status = Arc::new(MsQueue::new());
         let msg_id_mapping = Arc::new(RwLock::new(HashMap::new()));
         let io_service = Arc::new(io_service);
@@ -84,10 +96,24 @@ impl ApiProcess {
             msg_id_mapping,
             pending_status,
             io_service,
+            stats,
         }
     }
 
-    pub fn process(&self, request: &Vec<u8>, _socketId: &Vec<u8>) -> Vec<u8> {
+    pub fn process(&self, request: &Vec<u8>, socket_id: &Vec<u8>) -> Vec<u8> {
+        let start = Instant::now();
+        let response = self.process_message(request, socket_id);
+        let method = match request.get(2).and_then(|message| Funcs::from_i32(*message as i32)) {
+            Some(func) => format!("{:?}", func),
+            None => "unknown".into(),
+        };
+        // the return code, second byte of the header, is negative or r_fail on failure
+        let success = response.get(1).map_or(false, |code| *code as i8 > 0);
+        self.stats.add_call(Transport::Pb, &method, start.elapsed(), success, request);
+        response
+    }
+
+    fn process_message(&self, request: &Vec<u8>, _socketId: &Vec<u8>) -> Vec<u8> {
         if request.is_empty() || request.len() < get_api_header_len() {
             return to_return_header(get_api_version(), Retcode::r_fail_header_len.value());
         }
@@ -213,6 +239,48 @@ impl ApiProcess {
                 let block = self.client.block_by_number(num, false);
                 create_block_msg(block)
             }
+            Some(Funcs::f_getBlockReceipts) => {
+                debug!(target: LOG_TARGET, "process message: f_getBlockReceipts");
+                if service != Servs::s_chain.value() {
+                    return to_return_header(
+                        get_api_version(),
+                        Retcode::r_fail_service_call.value(),
+                    );
+                }
+                let data = parse_msg_req(request, &msghash);
+                let mut req = req_getBlockReceipts::new();
+                api_try!(req.merge_from_bytes(&data));
+                // a block hash, when given, takes precedence over the block number
+                let blockhash = req.get_blockHash().to_vec();
+                let id = if blockhash.is_empty() {
+                    BlockNumberOrHash::Number(BlockNumber::Num(req.get_blockNumber()))
+                } else if blockhash.len() == BLOCK_HASH_LEN {
+                    BlockNumberOrHash::Hash(H256::from(blockhash.as_slice()).into())
+                } else {
+                    return to_return_header(
+                        get_api_version(),
+                        Retcode::r_fail_function_arguments.value(),
+                    );
+                };
+                let receipts = match self.client.block_receipts(id) {
+                    Some(receipts) => receipts,
+                    None => {
+                        return to_return_header(
+                            get_api_version(),
+                            Retcode::r_fail_function_arguments.value(),
+                        );
+                    }
+                };
+                let receipts = receipts
+                    .into_iter()
+                    .map(get_rsp_getTransactionReceipt)
+                    .collect::<Vec<_>>();
+                let mut rsp = rsp_getBlockReceipts::new();
+                rsp.set_receipts(receipts.into());
+                let retheader = to_return_header(get_api_version(), Retcode::r_success.value());
+                let retbody = api_try!(rsp.write_to_bytes());
+                combine_ret_msg(retheader, retbody)
+            }
             Some(Funcs::f_getBlockDetailsByNumber) => {
                 //TODO:
                 debug!(
@@ -303,6 +371,7 @@ impl ApiProcess {
                         node.set_blockNumber(n.highest_block_number);
                         node.set_nodeId(n.id);
                         node.set_remote_p2p_ip(n.ip);
+                        node.set_reputation(n.reputation);
                         node
                     })
                     .collect::<Vec<_>>();
@@ -436,6 +505,48 @@ fn get_rsp_getTransaction(tx: Transaction) -> rsp_getTransaction {
     rsp
 }
 
+fn get_rsp_getTransactionReceipt(re: Receipt) -> rsp_getTransactionReceipt {
+    let mut rsp = rsp_getTransactionReceipt::new();
+    let txhash = re.transaction_hash.map_or_else(|| H256::from(0), |a| a.into());
+    let txindex = re
+        .transaction_index
+        .map_or_else(|| U256::from(0), |a| a.into());
+    let blockhash = re.block_hash.map_or_else(|| H256::from(0), |a| a.into());
+    let blocknumber = re.block_number.map_or_else(|| U256::from(0), |a| a.into());
+    let gas_used = re.gas_used.map_or_else(|| U256::from(0), |a| a.into());
+    let contract_address = re
+        .contract_address
+        .map_or_else(|| H256::from(0), |a| a.into());
+
+    rsp.set_txIndex(txindex.low_u32());
+    rsp.set_blockNumber(blocknumber.into());
+    rsp.set_nrgConsumed(gas_used.into());
+    rsp.set_cumulativeNrgUsed(to_u256(re.cumulative_gas_used).into());
+    rsp.set_blockHash(blockhash.0.to_vec());
+    rsp.set_txHash(txhash.0.to_vec());
+    rsp.set_from(re.from.unwrap_or_default().0.to_vec());
+    rsp.set_to(re.to.unwrap_or_default().0.to_vec());
+    rsp.set_contractAddress(contract_address.0.to_vec());
+    let tles = re
+        .logs
+        .into_iter()
+        .map(|log| {
+            let mut tle = t_LgEle::new();
+            tle.set_data(log.data.into_vec());
+            tle.set_address(log.address.0.to_vec());
+            let topics: Vec<String> = log
+                .topics
+                .into_iter()
+                .map(|t| t.0.to_vec().to_hex())
+                .collect();
+            tle.set_topics(topics.into());
+            tle
+        })
+        .collect::<Vec<_>>();
+    rsp.set_logs(tles.into());
+    rsp
+}
+
 fn create_t_block_detail(block: Block, br: Vec<SimpleReceipt>) -> t_BlockDetail {
     let blockhash = block.hash.map_or_else(|| H256::from(0), |a| a.into());
     let blocknumber = block.number.unwrap_or(0u64);
@@ -929,6 +929,7 @@ pub struct t_Node {
     pub remote_p2p_ip: ::std::string::String,
     pub remote_p2p_port: u32,
     pub latency: u32,
+    pub reputation: i32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     cached_size: ::protobuf::CachedSize,
@@ -1035,6 +1036,21 @@ impl t_Node {
     pub fn get_latency(&self) -> u32 {
         self.latency
     }
+
+    // int32 reputation = 6;
+
+    pub fn clear_reputation(&mut self) {
+        self.reputation = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_reputation(&mut self, v: i32) {
+        self.reputation = v;
+    }
+
+    pub fn get_reputation(&self) -> i32 {
+        self.reputation
+    }
 }
 
 impl ::protobuf::Message for t_Node {
@@ -1073,6 +1089,13 @@ impl ::protobuf::Message for t_Node {
                     let tmp = is.read_uint32()?;
                     self.latency = tmp;
                 },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.reputation = tmp;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -1100,6 +1123,9 @@ impl ::protobuf::Message for t_Node {
         if self.latency != 0 {
             my_size += ::protobuf::rt::value_size(5, self.latency, ::protobuf::wire_format::WireTypeVarint);
         }
+        if self.reputation != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.reputation, ::protobuf::wire_format::WireTypeVarint);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -1121,6 +1147,9 @@ impl ::protobuf::Message for t_Node {
         if self.latency != 0 {
             os.write_uint32(5, self.latency)?;
         }
+        if self.reputation != 0 {
+            os.write_int32(6, self.reputation)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1188,6 +1217,11 @@ impl ::protobuf::Message for t_Node {
                     |m: &t_Node| { &m.latency },
                     |m: &mut t_Node| { &mut m.latency },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
+                    "reputation",
+                    |m: &t_Node| { &m.reputation },
+                    |m: &mut t_Node| { &mut m.reputation },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<t_Node>(
                     "t_Node",
                     fields,
@@ -1215,6 +1249,7 @@ impl ::protobuf::Clear for t_Node {
         self.clear_remote_p2p_ip();
         self.clear_remote_p2p_port();
         self.clear_latency();
+        self.clear_reputation();
         self.unknown_fields.clear();
     }
 }
@@ -14611,6 +14646,374 @@ impl ::protobuf::reflect::ProtobufValue for rsp_getTransactionReceipt {
     }
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct req_getBlockReceipts {
+    // message fields
+    pub blockNumber: u64,
+    pub blockHash: ::std::vec::Vec<u8>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl req_getBlockReceipts {
+    pub fn new() -> req_getBlockReceipts {
+        ::std::default::Default::default()
+    }
+
+    // uint64 blockNumber = 1;
+
+    pub fn clear_blockNumber(&mut self) {
+        self.blockNumber = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_blockNumber(&mut self, v: u64) {
+        self.blockNumber = v;
+    }
+
+    pub fn get_blockNumber(&self) -> u64 {
+        self.blockNumber
+    }
+
+    // bytes blockHash = 2;
+
+    pub fn clear_blockHash(&mut self) {
+        self.blockHash.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_blockHash(&mut self, v: ::std::vec::Vec<u8>) {
+        self.blockHash = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_blockHash(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.blockHash
+    }
+
+    // Take field
+    pub fn take_blockHash(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.blockHash, ::std::vec::Vec::new())
+    }
+
+    pub fn get_blockHash(&self) -> &[u8] {
+        &self.blockHash
+    }
+}
+
+impl ::protobuf::Message for req_getBlockReceipts {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.blockNumber = tmp;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.blockHash)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.blockNumber != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.blockNumber, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.blockHash.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(2, &self.blockHash);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if self.blockNumber != 0 {
+            os.write_uint64(1, self.blockNumber)?;
+        }
+        if !self.blockHash.is_empty() {
+            os.write_bytes(2, &self.blockHash)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> req_getBlockReceipts {
+        req_getBlockReceipts::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                    "blockNumber",
+                    |m: &req_getBlockReceipts| { &m.blockNumber },
+                    |m: &mut req_getBlockReceipts| { &mut m.blockNumber },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "blockHash",
+                    |m: &req_getBlockReceipts| { &m.blockHash },
+                    |m: &mut req_getBlockReceipts| { &mut m.blockHash },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<req_getBlockReceipts>(
+                    "req_getBlockReceipts",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static req_getBlockReceipts {
+        static mut instance: ::protobuf::lazy::Lazy<req_getBlockReceipts> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const req_getBlockReceipts,
+        };
+        unsafe {
+            instance.get(req_getBlockReceipts::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for req_getBlockReceipts {
+    fn clear(&mut self) {
+        self.clear_blockNumber();
+        self.clear_blockHash();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for req_getBlockReceipts {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for req_getBlockReceipts {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct rsp_getBlockReceipts {
+    // message fields
+    pub receipts: ::protobuf::RepeatedField<rsp_getTransactionReceipt>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl rsp_getBlockReceipts {
+    pub fn new() -> rsp_getBlockReceipts {
+        ::std::default::Default::default()
+    }
+
+    // repeated .bp_api.rsp_getTransactionReceipt receipts = 1;
+
+    pub fn clear_receipts(&mut self) {
+        self.receipts.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_receipts(&mut self, v: ::protobuf::RepeatedField<rsp_getTransactionReceipt>) {
+        self.receipts = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_receipts(&mut self) -> &mut ::protobuf::RepeatedField<rsp_getTransactionReceipt> {
+        &mut self.receipts
+    }
+
+    // Take field
+    pub fn take_receipts(&mut self) -> ::protobuf::RepeatedField<rsp_getTransactionReceipt> {
+        ::std::mem::replace(&mut self.receipts, ::protobuf::RepeatedField::new())
+    }
+
+    pub fn get_receipts(&self) -> &[rsp_getTransactionReceipt] {
+        &self.receipts
+    }
+}
+
+impl ::protobuf::Message for rsp_getBlockReceipts {
+    fn is_initialized(&self) -> bool {
+        for v in &self.receipts {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.receipts)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.receipts {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.receipts {
+            os.write_tag(1, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> rsp_getBlockReceipts {
+        rsp_getBlockReceipts::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<rsp_getTransactionReceipt>>(
+                    "receipts",
+                    |m: &rsp_getBlockReceipts| { &m.receipts },
+                    |m: &mut rsp_getBlockReceipts| { &mut m.receipts },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<rsp_getBlockReceipts>(
+                    "rsp_getBlockReceipts",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static rsp_getBlockReceipts {
+        static mut instance: ::protobuf::lazy::Lazy<rsp_getBlockReceipts> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const rsp_getBlockReceipts,
+        };
+        unsafe {
+            instance.get(rsp_getBlockReceipts::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for rsp_getBlockReceipts {
+    fn clear(&mut self) {
+        self.clear_receipts();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for rsp_getBlockReceipts {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for rsp_getBlockReceipts {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
 #[derive(PartialEq,Clone,Default)]
 pub struct req_getUncleByBlockHashAndIndex {
     // message fields
@@ -25234,6 +25637,7 @@ pub enum Funcs {
     f_getBlockDetailsByHash = 58,
     f_listening = 59,
     f_peerCount = 60,
+    f_getBlockReceipts = 61,
 }
 
 impl ::protobuf::ProtobufEnum for Funcs {
@@ -25304,6 +25708,7 @@ impl ::protobuf::ProtobufEnum for Funcs {
             58 => ::std::option::Option::Some(Funcs::f_getBlockDetailsByHash),
             59 => ::std::option::Option::Some(Funcs::f_listening),
             60 => ::std::option::Option::Some(Funcs::f_peerCount),
+            61 => ::std::option::Option::Some(Funcs::f_getBlockReceipts),
             _ => ::std::option::Option::None
         }
     }
@@ -25371,6 +25776,7 @@ impl ::protobuf::ProtobufEnum for Funcs {
             Funcs::f_getBlockDetailsByHash,
             Funcs::f_listening,
             Funcs::f_peerCount,
+            Funcs::f_getBlockReceipts,
         ];
         values
     }
@@ -25561,12 +25967,13 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     (\x0cR\x02to\x12\x14\n\x05value\x18\x04\x20\x01(\x0cR\x05value\x12\x12\n\
     \x04data\x18\x05\x20\x01(\x0cR\x04data\x12\x14\n\x05nonce\x18\x06\x20\
     \x01(\x0cR\x05nonce\x12\x20\n\x0bnrgConsumed\x18\x07\x20\x01(\x04R\x0bnr\
-    gConsumed\x12\x1a\n\x08nrgPrice\x18\x08\x20\x01(\x04R\x08nrgPrice\"\xa8\
+    gConsumed\x12\x1a\n\x08nrgPrice\x18\x08\x20\x01(\x04R\x08nrgPrice\"\xc8\
     \x01\n\x06t_Node\x12\x20\n\x0bblockNumber\x18\x01\x20\x01(\x04R\x0bblock\
     Number\x12\x16\n\x06nodeId\x18\x02\x20\x01(\tR\x06nodeId\x12\"\n\rremote\
     _p2p_ip\x18\x03\x20\x01(\tR\x0bremoteP2pIp\x12&\n\x0fremote_p2p_port\x18\
     \x04\x20\x01(\rR\rremoteP2pPort\x12\x18\n\x07latency\x18\x05\x20\x01(\rR\
-    \x07latency\"O\n\x07t_LgEle\x12\x18\n\x07address\x18\x01\x20\x01(\x0cR\
+    \x07latency\x12\x1e\n\nreputation\x18\x06\x20\x01(\x05R\nreputation\
+    \"O\n\x07t_LgEle\x12\x18\n\x07address\x18\x01\x20\x01(\x0cR\
     \x07address\x12\x12\n\x04data\x18\x02\x20\x01(\x0cR\x04data\x12\x16\n\
     \x06topics\x18\x03\x20\x03(\tR\x06topics\"\xaa\x01\n\nt_FilterCt\x12\x12\
     \n\x04from\x18\x01\x20\x01(\tR\x04from\x12\x0e\n\x02to\x18\x02\x20\x01(\
@@ -25735,125 +26142,129 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     txHash\x18\x06\x20\x01(\x0cR\x06txHash\x12\x12\n\x04from\x18\x07\x20\x01\
     (\x0cR\x04from\x12\x0e\n\x02to\x18\x08\x20\x01(\x0cR\x02to\x12(\n\x0fcon\
     tractAddress\x18\t\x20\x01(\x0cR\x0fcontractAddress\x12#\n\x04logs\x18\n\
-    \x20\x03(\x0b2\x0f.bp_api.t_LgEleR\x04logs\"U\n\x1freq_getUncleByBlockHa\
-    shAndIndex\x12\x1c\n\tblockHash\x18\x01\x20\x01(\x0cR\tblockHash\x12\x14\
-    \n\x05index\x18\x02\x20\x01(\rR\x05index\".\n\x10rsp_getCompilers\x12\
-    \x1a\n\x08compiler\x18\x01\x20\x03(\tR\x08compiler\"-\n\x13req_compileSo\
-    lidity\x12\x16\n\x06source\x18\x01\x20\x01(\tR\x06source\"Q\n\x13rsp_com\
-    pileSolidity\x12\x12\n\x04code\x18\x01\x20\x01(\tR\x04code\x12&\n\x04inf\
-    o\x18\x02\x20\x01(\x0b2\x12.bp_api.t_ContractR\x04info\"!\n\x0brsp_getWo\
-    rk\x12\x12\n\x04work\x18\x01\x20\x03(\tR\x04work\"Z\n\x0ereq_submitWork\
-    \x12\x14\n\x05nonce\x18\x01\x20\x01(\x0cR\x05nonce\x12\x1a\n\x08solution\
-    \x18\x02\x20\x01(\x0cR\x08solution\x12\x16\n\x06digest\x18\x03\x20\x01(\
-    \x0cR\x06digest\"4\n\x0ersp_submitWork\x12\"\n\x0cworkAccepted\x18\x01\
-    \x20\x01(\x08R\x0cworkAccepted\"?\n\x1brsp_fetchQueuedTransactions\x12\
-    \x20\n\x02tx\x18\x01\x20\x03(\x0b2\x10.bp_api.t_AionTxR\x02tx\"2\n\x12re\
-    q_rawTransaction\x12\x1c\n\tencodedTx\x18\x01\x20\x01(\x0cR\tencodedTx\"\
-    \x8d\x01\n\x0freq_estimateNrg\x12\x12\n\x04from\x18\x01\x20\x01(\x0cR\
-    \x04from\x12\x0e\n\x02to\x18\x02\x20\x01(\x0cR\x02to\x12\x14\n\x05value\
-    \x18\x03\x20\x01(\x0cR\x05value\x12\x12\n\x04data\x18\x04\x20\x01(\x0cR\
-    \x04data\x12\x10\n\x03nrg\x18\x05\x20\x01(\x04R\x03nrg\x12\x1a\n\x08nrgP\
-    rice\x18\x06\x20\x01(\x04R\x08nrgPrice\"#\n\x0frsp_estimateNrg\x12\x10\n\
-    \x03nrg\x18\x01\x20\x01(\x04R\x03nrg\"$\n\nrsp_mining\x12\x16\n\x06minin\
-    g\x18\x01\x20\x01(\x08R\x06mining\"*\n\x0crsp_hashrate\x12\x1a\n\x08hash\
-    rate\x18\x01\x20\x01(\x04R\x08hashrate\"8\n\x12rsp_getActiveNodes\x12\"\
-    \n\x04node\x18\x01\x20\x03(\x0b2\x0e.bp_api.t_NodeR\x04node\"8\n\x12rsp_\
-    getStaticNodes\x12\"\n\x04node\x18\x01\x20\x03(\x0b2\x0e.bp_api.t_NodeR\
-    \x04node\"&\n\x12rsp_getSolcVersion\x12\x10\n\x03ver\x18\x01\x20\x01(\tR\
-    \x03ver\")\n\rrsp_isSyncing\x12\x18\n\x07syncing\x18\x01\x20\x01(\x08R\
-    \x07syncing\"\xcc\x01\n\x0crsp_syncInfo\x12\x18\n\x07syncing\x18\x01\x20\
-    \x01(\x08R\x07syncing\x12*\n\x10networkBestBlock\x18\x02\x20\x01(\x04R\
-    \x10networkBestBlock\x12&\n\x0echainBestBlock\x18\x03\x20\x01(\x04R\x0ec\
-    hainBestBlock\x12(\n\x0fmaxImportBlocks\x18\x04\x20\x01(\rR\x0fmaxImport\
-    Blocks\x12$\n\rstartingBlock\x18\x05\x20\x01(\x04R\rstartingBlock\"f\n\
-    \x0ersp_systemInfo\x12\x1a\n\x08cpuUsage\x18\x01\x20\x01(\x02R\x08cpuUsa\
-    ge\x12\x20\n\x0bmemoryUsage\x18\x02\x20\x01(\x04R\x0bmemoryUsage\x12\x16\
-    \n\x06DBSize\x18\x03\x20\x01(\x04R\x06DBSize\"W\n\x11req_eventRegister\
-    \x12\x16\n\x06events\x18\x01\x20\x03(\tR\x06events\x12*\n\x06filter\x18\
-    \x02\x20\x01(\x0b2\x12.bp_api.t_FilterCtR\x06filter\"+\n\x11rsp_eventReg\
-    ister\x12\x16\n\x06result\x18\x01\x20\x01(\x08R\x06result\"Q\n\x13req_ev\
-    entDeregister\x12\x16\n\x06events\x18\x01\x20\x03(\tR\x06events\x12\"\n\
-    \x0ccontractAddr\x18\x02\x20\x01(\x0cR\x0ccontractAddr\"-\n\x13rsp_event\
-    Deregister\x12\x16\n\x06result\x18\x01\x20\x01(\x08R\x06result\"8\n\x13r\
-    sp_EventCtCallback\x12!\n\x02ec\x18\x01\x20\x03(\x0b2\x11.bp_api.t_Event\
-    CtR\x02ec\"O\n\x11req_accountCreate\x12\x1a\n\x08password\x18\x01\x20\
-    \x03(\tR\x08password\x12\x1e\n\nprivateKey\x18\x02\x20\x01(\x08R\nprivat\
-    eKey\"M\n\x11rsp_accountCreate\x12\x18\n\x07address\x18\x01\x20\x03(\x0c\
-    R\x07address\x12\x1e\n\nprivateKey\x18\x02\x20\x03(\x0cR\nprivateKey\"G\
-    \n\x0freq_accountlock\x12\x18\n\x07account\x18\x01\x20\x01(\x0cR\x07acco\
-    unt\x12\x1a\n\x08password\x18\x02\x20\x01(\tR\x08password\")\n\x0frsp_ac\
-    countlock\x12\x16\n\x06locked\x18\x01\x20\x01(\x08R\x06locked\"K\n\x11re\
-    q_userPrivilege\x12\x1a\n\x08username\x18\x01\x20\x01(\tR\x08username\
-    \x12\x1a\n\x08password\x18\x02\x20\x01(\tR\x08password\"1\n\x11rsp_userP\
-    rivilege\x12\x1c\n\tprivilege\x18\x01\x20\x03(\tR\tprivilege\"?\n\x11req\
-    _queryCtEvents\x12*\n\x06filter\x18\x01\x20\x01(\x0b2\x12.bp_api.t_Filte\
-    rCtR\x06filter\"6\n\x11rsp_queryCtEvents\x12!\n\x02ec\x18\x01\x20\x03(\
-    \x0b2\x11.bp_api.t_EventCtR\x02ec\"J\n\x0ct_PrivateKey\x12\x1e\n\nprivat\
-    eKey\x18\x01\x20\x01(\tR\nprivateKey\x12\x1a\n\x08password\x18\x02\x20\
-    \x01(\tR\x08password\"J\n\x12req_importAccounts\x124\n\nprivateKey\x18\
-    \x01\x20\x03(\x0b2\x14.bp_api.t_PrivateKeyR\nprivateKey\"4\n\x12rsp_impo\
-    rtAccounts\x12\x1e\n\ninvalidKey\x18\x01\x20\x03(\tR\ninvalidKey\"=\n\
-    \x05t_Key\x12\x18\n\x07address\x18\x01\x20\x01(\x0cR\x07address\x12\x1a\
-    \n\x08password\x18\x02\x20\x01(\tR\x08password\"=\n\x12req_exportAccount\
-    s\x12'\n\x07keyFile\x18\x01\x20\x03(\x0b2\r.bp_api.t_KeyR\x07keyFile\"L\
-    \n\x12rsp_exportAccounts\x12\x18\n\x07keyFile\x18\x01\x20\x03(\x0cR\x07k\
-    eyFile\x12\x1c\n\tfailedKey\x18\x02\x20\x03(\x0cR\tfailedKey\"=\n\x1drsp\
-    _getCurrentTotalDifficulty\x12\x1c\n\ttotalDiff\x18\x01\x20\x01(\x0cR\tt\
-    otalDiff\"=\n\x1breq_getBlockDetailsByNumber\x12\x1e\n\nblkNumbers\x18\
-    \x01\x20\x03(\x04R\nblkNumbers\"T\n\x1brsp_getBlockDetailsByNumber\x125\
-    \n\nblkDetails\x18\x01\x20\x03(\x0b2\x15.bp_api.t_BlockDetailR\nblkDetai\
-    ls\"3\n\x1breq_getBlockDetailsByLatest\x12\x14\n\x05count\x18\x01\x20\
-    \x01(\x04R\x05count\"T\n\x1brsp_getBlockDetailsByLatest\x125\n\nblkDetai\
-    ls\x18\x01\x20\x03(\x0b2\x15.bp_api.t_BlockDetailR\nblkDetails\"-\n\x15r\
-    eq_getBlocksByLatest\x12\x14\n\x05count\x18\x01\x20\x01(\x04R\x05count\"\
-    <\n\x15rsp_getBlocksByLatest\x12#\n\x04blks\x18\x01\x20\x03(\x0b2\x0f.bp\
-    _api.t_BlockR\x04blks\"B\n\"req_getAccountDetailsByAddressList\x12\x1c\n\
-    \taddresses\x18\x01\x20\x03(\x0cR\taddresses\"Y\n\"rsp_getAccountDetails\
-    ByAddressList\x123\n\x08accounts\x18\x01\x20\x03(\x0b2\x17.bp_api.t_Acco\
-    untDetailR\x08accounts\"d\n\x16req_getBlockSqlByRange\x12&\n\x0eblkNumbe\
-    rStart\x18\x01\x20\x01(\x04R\x0eblkNumberStart\x12\"\n\x0cblkNumberEnd\
-    \x18\x02\x20\x01(\x04R\x0cblkNumberEnd\"D\n\x16rsp_getBlockSqlByRange\
-    \x12*\n\x06blkSql\x18\x01\x20\x03(\x0b2\x12.bp_api.t_BlockSqlR\x06blkSql\
-    \"h\n\x1areq_getBlockDetailsByRange\x12&\n\x0eblkNumberStart\x18\x01\x20\
+    \x20\x03(\x0b2\x0f.bp_api.t_LgEleR\x04logs\"V\n\x14req_getBlockReceipts\
+    \x12\x20\n\x0bblockNumber\x18\x01\x20\x01(\x04R\x0bblockNumber\x12\x1c\n\
+    \tblockHash\x18\x02\x20\x01(\x0cR\tblockHash\"U\n\x14rsp_getBlockReceipt\
+    s\x12=\n\x08receipts\x18\x01\x20\x03(\x0b2!.bp_api.rsp_getTransactionRec\
+    eiptR\x08receipts\"U\n\x1freq_getUncleByBlockHashAndIndex\x12\x1c\n\tblo\
+    ckHash\x18\x01\x20\x01(\x0cR\tblockHash\x12\x14\n\x05index\x18\x02\x20\
+    \x01(\rR\x05index\".\n\x10rsp_getCompilers\x12\x1a\n\x08compiler\x18\x01\
+    \x20\x03(\tR\x08compiler\"-\n\x13req_compileSolidity\x12\x16\n\x06source\
+    \x18\x01\x20\x01(\tR\x06source\"Q\n\x13rsp_compileSolidity\x12\x12\n\x04\
+    code\x18\x01\x20\x01(\tR\x04code\x12&\n\x04info\x18\x02\x20\x01(\x0b2\
+    \x12.bp_api.t_ContractR\x04info\"!\n\x0brsp_getWork\x12\x12\n\x04work\
+    \x18\x01\x20\x03(\tR\x04work\"Z\n\x0ereq_submitWork\x12\x14\n\x05nonce\
+    \x18\x01\x20\x01(\x0cR\x05nonce\x12\x1a\n\x08solution\x18\x02\x20\x01(\
+    \x0cR\x08solution\x12\x16\n\x06digest\x18\x03\x20\x01(\x0cR\x06digest\"4\
+    \n\x0ersp_submitWork\x12\"\n\x0cworkAccepted\x18\x01\x20\x01(\x08R\x0cwo\
+    rkAccepted\"?\n\x1brsp_fetchQueuedTransactions\x12\x20\n\x02tx\x18\x01\
+    \x20\x03(\x0b2\x10.bp_api.t_AionTxR\x02tx\"2\n\x12req_rawTransaction\x12\
+    \x1c\n\tencodedTx\x18\x01\x20\x01(\x0cR\tencodedTx\"\x8d\x01\n\x0freq_es\
+    timateNrg\x12\x12\n\x04from\x18\x01\x20\x01(\x0cR\x04from\x12\x0e\n\x02t\
+    o\x18\x02\x20\x01(\x0cR\x02to\x12\x14\n\x05value\x18\x03\x20\x01(\x0cR\
+    \x05value\x12\x12\n\x04data\x18\x04\x20\x01(\x0cR\x04data\x12\x10\n\x03n\
+    rg\x18\x05\x20\x01(\x04R\x03nrg\x12\x1a\n\x08nrgPrice\x18\x06\x20\x01(\
+    \x04R\x08nrgPrice\"#\n\x0frsp_estimateNrg\x12\x10\n\x03nrg\x18\x01\x20\
+    \x01(\x04R\x03nrg\"$\n\nrsp_mining\x12\x16\n\x06mining\x18\x01\x20\x01(\
+    \x08R\x06mining\"*\n\x0crsp_hashrate\x12\x1a\n\x08hashrate\x18\x01\x20\
+    \x01(\x04R\x08hashrate\"8\n\x12rsp_getActiveNodes\x12\"\n\x04node\x18\
+    \x01\x20\x03(\x0b2\x0e.bp_api.t_NodeR\x04node\"8\n\x12rsp_getStaticNodes\
+    \x12\"\n\x04node\x18\x01\x20\x03(\x0b2\x0e.bp_api.t_NodeR\x04node\"&\n\
+    \x12rsp_getSolcVersion\x12\x10\n\x03ver\x18\x01\x20\x01(\tR\x03ver\")\n\
+    \rrsp_isSyncing\x12\x18\n\x07syncing\x18\x01\x20\x01(\x08R\x07syncing\"\
+    \xcc\x01\n\x0crsp_syncInfo\x12\x18\n\x07syncing\x18\x01\x20\x01(\x08R\
+    \x07syncing\x12*\n\x10networkBestBlock\x18\x02\x20\x01(\x04R\x10networkB\
+    estBlock\x12&\n\x0echainBestBlock\x18\x03\x20\x01(\x04R\x0echainBestBloc\
+    k\x12(\n\x0fmaxImportBlocks\x18\x04\x20\x01(\rR\x0fmaxImportBlocks\x12$\
+    \n\rstartingBlock\x18\x05\x20\x01(\x04R\rstartingBlock\"f\n\x0ersp_syste\
+    mInfo\x12\x1a\n\x08cpuUsage\x18\x01\x20\x01(\x02R\x08cpuUsage\x12\x20\n\
+    \x0bmemoryUsage\x18\x02\x20\x01(\x04R\x0bmemoryUsage\x12\x16\n\x06DBSize\
+    \x18\x03\x20\x01(\x04R\x06DBSize\"W\n\x11req_eventRegister\x12\x16\n\x06\
+    events\x18\x01\x20\x03(\tR\x06events\x12*\n\x06filter\x18\x02\x20\x01(\
+    \x0b2\x12.bp_api.t_FilterCtR\x06filter\"+\n\x11rsp_eventRegister\x12\x16\
+    \n\x06result\x18\x01\x20\x01(\x08R\x06result\"Q\n\x13req_eventDeregister\
+    \x12\x16\n\x06events\x18\x01\x20\x03(\tR\x06events\x12\"\n\x0ccontractAd\
+    dr\x18\x02\x20\x01(\x0cR\x0ccontractAddr\"-\n\x13rsp_eventDeregister\x12\
+    \x16\n\x06result\x18\x01\x20\x01(\x08R\x06result\"8\n\x13rsp_EventCtCall\
+    back\x12!\n\x02ec\x18\x01\x20\x03(\x0b2\x11.bp_api.t_EventCtR\x02ec\"O\n\
+    \x11req_accountCreate\x12\x1a\n\x08password\x18\x01\x20\x03(\tR\x08passw\
+    ord\x12\x1e\n\nprivateKey\x18\x02\x20\x01(\x08R\nprivateKey\"M\n\x11rsp_\
+    accountCreate\x12\x18\n\x07address\x18\x01\x20\x03(\x0cR\x07address\x12\
+    \x1e\n\nprivateKey\x18\x02\x20\x03(\x0cR\nprivateKey\"G\n\x0freq_account\
+    lock\x12\x18\n\x07account\x18\x01\x20\x01(\x0cR\x07account\x12\x1a\n\x08\
+    password\x18\x02\x20\x01(\tR\x08password\")\n\x0frsp_accountlock\x12\x16\
+    \n\x06locked\x18\x01\x20\x01(\x08R\x06locked\"K\n\x11req_userPrivilege\
+    \x12\x1a\n\x08username\x18\x01\x20\x01(\tR\x08username\x12\x1a\n\x08pass\
+    word\x18\x02\x20\x01(\tR\x08password\"1\n\x11rsp_userPrivilege\x12\x1c\n\
+    \tprivilege\x18\x01\x20\x03(\tR\tprivilege\"?\n\x11req_queryCtEvents\x12\
+    *\n\x06filter\x18\x01\x20\x01(\x0b2\x12.bp_api.t_FilterCtR\x06filter\"6\
+    \n\x11rsp_queryCtEvents\x12!\n\x02ec\x18\x01\x20\x03(\x0b2\x11.bp_api.t_\
+    EventCtR\x02ec\"J\n\x0ct_PrivateKey\x12\x1e\n\nprivateKey\x18\x01\x20\
+    \x01(\tR\nprivateKey\x12\x1a\n\x08password\x18\x02\x20\x01(\tR\x08passwo\
+    rd\"J\n\x12req_importAccounts\x124\n\nprivateKey\x18\x01\x20\x03(\x0b2\
+    \x14.bp_api.t_PrivateKeyR\nprivateKey\"4\n\x12rsp_importAccounts\x12\x1e\
+    \n\ninvalidKey\x18\x01\x20\x03(\tR\ninvalidKey\"=\n\x05t_Key\x12\x18\n\
+    \x07address\x18\x01\x20\x01(\x0cR\x07address\x12\x1a\n\x08password\x18\
+    \x02\x20\x01(\tR\x08password\"=\n\x12req_exportAccounts\x12\'\n\x07keyFi\
+    le\x18\x01\x20\x03(\x0b2\r.bp_api.t_KeyR\x07keyFile\"L\n\x12rsp_exportAc\
+    counts\x12\x18\n\x07keyFile\x18\x01\x20\x03(\x0cR\x07keyFile\x12\x1c\n\t\
+    failedKey\x18\x02\x20\x03(\x0cR\tfailedKey\"=\n\x1drsp_getCurrentTotalDi\
+    fficulty\x12\x1c\n\ttotalDiff\x18\x01\x20\x01(\x0cR\ttotalDiff\"=\n\x1br\
+    eq_getBlockDetailsByNumber\x12\x1e\n\nblkNumbers\x18\x01\x20\x03(\x04R\n\
+    blkNumbers\"T\n\x1brsp_getBlockDetailsByNumber\x125\n\nblkDetails\x18\
+    \x01\x20\x03(\x0b2\x15.bp_api.t_BlockDetailR\nblkDetails\"3\n\x1breq_get\
+    BlockDetailsByLatest\x12\x14\n\x05count\x18\x01\x20\x01(\x04R\x05count\"\
+    T\n\x1brsp_getBlockDetailsByLatest\x125\n\nblkDetails\x18\x01\x20\x03(\
+    \x0b2\x15.bp_api.t_BlockDetailR\nblkDetails\"-\n\x15req_getBlocksByLates\
+    t\x12\x14\n\x05count\x18\x01\x20\x01(\x04R\x05count\"<\n\x15rsp_getBlock\
+    sByLatest\x12#\n\x04blks\x18\x01\x20\x03(\x0b2\x0f.bp_api.t_BlockR\x04bl\
+    ks\"B\n\"req_getAccountDetailsByAddressList\x12\x1c\n\taddresses\x18\x01\
+    \x20\x03(\x0cR\taddresses\"Y\n\"rsp_getAccountDetailsByAddressList\x123\
+    \n\x08accounts\x18\x01\x20\x03(\x0b2\x17.bp_api.t_AccountDetailR\x08acco\
+    unts\"d\n\x16req_getBlockSqlByRange\x12&\n\x0eblkNumberStart\x18\x01\x20\
     \x01(\x04R\x0eblkNumberStart\x12\"\n\x0cblkNumberEnd\x18\x02\x20\x01(\
-    \x04R\x0cblkNumberEnd\"S\n\x1arsp_getBlockDetailsByRange\x125\n\nblkDeta\
-    ils\x18\x01\x20\x03(\x0b2\x15.bp_api.t_BlockDetailR\nblkDetails\"(\n\x0c\
-    req_getNonce\x12\x18\n\x07address\x18\x01\x20\x01(\x0cR\x07address\"$\n\
-    \x0crsp_getNonce\x12\x14\n\x05nonce\x18\x01\x20\x01(\x0cR\x05nonce\"-\n\
-    \x0frsp_getNrgPrice\x12\x1a\n\x08nrgPrice\x18\x01\x20\x01(\x04R\x08nrgPr\
-    ice\"9\n\x19req_getBlockDetailsByHash\x12\x1c\n\tblockHash\x18\x01\x20\
-    \x01(\x0cR\tblockHash\"R\n\x19rsp_getBlockDetailsByHash\x125\n\nblkDetai\
-    ls\x18\x01\x20\x01(\x0b2\x15.bp_api.t_BlockDetailR\nblkDetails\"1\n\rrsp\
-    _listening\x12\x20\n\x0bisListening\x18\x01\x20\x01(\x08R\x0bisListening\
-    \"%\n\rrsp_peerCount\x12\x14\n\x05peers\x18\x01\x20\x01(\rR\x05peers*\
-    \x84\x01\n\x05Servs\x12\x08\n\x04s_hb\x10\0\x12\x0b\n\x07s_admin\x10\x01\
-    \x12\x0b\n\x07s_chain\x10\x02\x12\r\n\ts_account\x10\x03\x12\x08\n\x04s_\
-    tx\x10\x04\x12\t\n\x05s_net\x10\x05\x12\n\n\x06s_mine\x10\x06\x12\x0f\n\
-    \x0bs_privilege\x10\x07\x12\x0c\n\x08s_wallet\x10\x08\x12\x08\n\x04s_NA\
-    \x10\t*\x87\x0b\n\x05Funcs\x12\x15\n\x11f_protocolVersion\x10\0\x12\x12\
-    \n\x0ef_minerAddress\x10\x01\x12\x0e\n\nf_accounts\x10\x02\x12\x11\n\rf_\
-    blockNumber\x10\x03\x12\x10\n\x0cf_getBalance\x10\x04\x12\x12\n\x0ef_get\
-    StorageAt\x10\x05\x12\x13\n\x0ff_unlockAccount\x10\x06\x12\x15\n\x11f_se\
-    ndTransaction\x10\x07\x12\x1a\n\x16f_getTransactionByHash\x10\x08\x12\r\
-    \n\tf_compile\x10\t\x12\x14\n\x10f_contractDeploy\x10\n\x12\x19\n\x15f_g\
-    etTransactionCount\x10\x0b\x12$\n\x20f_getBlockTransactionCountByHash\
-    \x10\x0c\x12&\n\"f_getBlockTransactionCountByNumber\x10\r\x12\r\n\tf_get\
-    Code\x10\x0e\x12\n\n\x06f_call\x10\x0f\x12\x14\n\x10f_getBlockByHash\x10\
-    \x10\x12\x16\n\x12f_getBlockByNumber\x10\x11\x12'\n#f_getTransactionByBl\
-    ockHashAndIndex\x10\x12\x12)\n%f_getTransactionByBlockNumberAndIndex\x10\
-    \x13\x12\x1b\n\x17f_getTransactionReceipt\x10\x14\x12\x12\n\x0ef_getComp\
-    ilers\x10\x15\x12\x15\n\x11f_compileSolidity\x10\x16\x12\r\n\tf_getWork\
-    \x10\x17\x12\x10\n\x0cf_submitWork\x10\x18\x12\x1d\n\x19f_fetchQueuedTra\
-    nsactions\x10\x19\x12\x17\n\x13f_signedTransaction\x10\x1a\x12\x14\n\x10\
-    f_rawTransaction\x10\x1b\x12\x11\n\rf_estimateNrg\x10\x1c\x12\x0c\n\x08f\
-    _mining\x10\x1d\x12\x0e\n\nf_hashrate\x10\x1e\x12\x14\n\x10f_getActiveNo\
-    des\x10\x1f\x12\x14\n\x10f_getSolcVersion\x10\x20\x12\x0f\n\x0bf_isSynci\
-    ng\x10!\x12\x0e\n\nf_syncInfo\x10\"\x12\x13\n\x0ff_getSystemInfo\x10#\
-    \x12\x13\n\x0ff_eventRegister\x10$\x12\x15\n\x11f_eventDeregister\x10%\
-    \x12\x13\n\x0ff_accountCreate\x10&\x12\x11\n\rf_accountLock\x10'\x12\x13\
-    \n\x0ff_userPrivilege\x10(\x12\x10\n\x0cf_eventQuery\x10)\x12\x14\n\x10f\
-    _importAccounts\x10*\x12\x14\n\x10f_exportAccounts\x10+\x12\x1c\n\x18f_g\
-    etBlockHeaderByNumber\x10,\x12\x1a\n\x16f_getBlockHeaderByHash\x10-\x12\
+    \x04R\x0cblkNumberEnd\"D\n\x16rsp_getBlockSqlByRange\x12*\n\x06blkSql\
+    \x18\x01\x20\x03(\x0b2\x12.bp_api.t_BlockSqlR\x06blkSql\"h\n\x1areq_getB\
+    lockDetailsByRange\x12&\n\x0eblkNumberStart\x18\x01\x20\x01(\x04R\x0eblk\
+    NumberStart\x12\"\n\x0cblkNumberEnd\x18\x02\x20\x01(\x04R\x0cblkNumberEn\
+    d\"S\n\x1arsp_getBlockDetailsByRange\x125\n\nblkDetails\x18\x01\x20\x03(\
+    \x0b2\x15.bp_api.t_BlockDetailR\nblkDetails\"(\n\x0creq_getNonce\x12\x18\
+    \n\x07address\x18\x01\x20\x01(\x0cR\x07address\"$\n\x0crsp_getNonce\x12\
+    \x14\n\x05nonce\x18\x01\x20\x01(\x0cR\x05nonce\"-\n\x0frsp_getNrgPrice\
+    \x12\x1a\n\x08nrgPrice\x18\x01\x20\x01(\x04R\x08nrgPrice\"9\n\x19req_get\
+    BlockDetailsByHash\x12\x1c\n\tblockHash\x18\x01\x20\x01(\x0cR\tblockHash\
+    \"R\n\x19rsp_getBlockDetailsByHash\x125\n\nblkDetails\x18\x01\x20\x01(\
+    \x0b2\x15.bp_api.t_BlockDetailR\nblkDetails\"1\n\rrsp_listening\x12\x20\
+    \n\x0bisListening\x18\x01\x20\x01(\x08R\x0bisListening\"%\n\rrsp_peerCou\
+    nt\x12\x14\n\x05peers\x18\x01\x20\x01(\rR\x05peers*\x84\x01\n\x05Servs\
+    \x12\x08\n\x04s_hb\x10\x00\x12\x0b\n\x07s_admin\x10\x01\x12\x0b\n\x07s_c\
+    hain\x10\x02\x12\r\n\ts_account\x10\x03\x12\x08\n\x04s_tx\x10\x04\x12\t\
+    \n\x05s_net\x10\x05\x12\n\n\x06s_mine\x10\x06\x12\x0f\n\x0bs_privilege\
+    \x10\x07\x12\x0c\n\x08s_wallet\x10\x08\x12\x08\n\x04s_NA\x10\t*\x9f\x0b\
+    \n\x05Funcs\x12\x15\n\x11f_protocolVersion\x10\x00\x12\x12\n\x0ef_minerA\
+    ddress\x10\x01\x12\x0e\n\nf_accounts\x10\x02\x12\x11\n\rf_blockNumber\
+    \x10\x03\x12\x10\n\x0cf_getBalance\x10\x04\x12\x12\n\x0ef_getStorageAt\
+    \x10\x05\x12\x13\n\x0ff_unlockAccount\x10\x06\x12\x15\n\x11f_sendTransac\
+    tion\x10\x07\x12\x1a\n\x16f_getTransactionByHash\x10\x08\x12\r\n\tf_comp\
+    ile\x10\t\x12\x14\n\x10f_contractDeploy\x10\n\x12\x19\n\x15f_getTransact\
+    ionCount\x10\x0b\x12$\n\x20f_getBlockTransactionCountByHash\x10\x0c\x12&\
+    \n\"f_getBlockTransactionCountByNumber\x10\r\x12\r\n\tf_getCode\x10\x0e\
+    \x12\n\n\x06f_call\x10\x0f\x12\x14\n\x10f_getBlockByHash\x10\x10\x12\x16\
+    \n\x12f_getBlockByNumber\x10\x11\x12\'\n#f_getTransactionByBlockHashAndI\
+    ndex\x10\x12\x12)\n%f_getTransactionByBlockNumberAndIndex\x10\x13\x12\
+    \x1b\n\x17f_getTransactionReceipt\x10\x14\x12\x12\n\x0ef_getCompilers\
+    \x10\x15\x12\x15\n\x11f_compileSolidity\x10\x16\x12\r\n\tf_getWork\x10\
+    \x17\x12\x10\n\x0cf_submitWork\x10\x18\x12\x1d\n\x19f_fetchQueuedTransac\
+    tions\x10\x19\x12\x17\n\x13f_signedTransaction\x10\x1a\x12\x14\n\x10f_ra\
+    wTransaction\x10\x1b\x12\x11\n\rf_estimateNrg\x10\x1c\x12\x0c\n\x08f_min\
+    ing\x10\x1d\x12\x0e\n\nf_hashrate\x10\x1e\x12\x14\n\x10f_getActiveNodes\
+    \x10\x1f\x12\x14\n\x10f_getSolcVersion\x10\x20\x12\x0f\n\x0bf_isSyncing\
+    \x10!\x12\x0e\n\nf_syncInfo\x10\"\x12\x13\n\x0ff_getSystemInfo\x10#\x12\
+    \x13\n\x0ff_eventRegister\x10$\x12\x15\n\x11f_eventDeregister\x10%\x12\
+    \x13\n\x0ff_accountCreate\x10&\x12\x11\n\rf_accountLock\x10\'\x12\x13\n\
+    \x0ff_userPrivilege\x10(\x12\x10\n\x0cf_eventQuery\x10)\x12\x14\n\x10f_i\
+    mportAccounts\x10*\x12\x14\n\x10f_exportAccounts\x10+\x12\x1c\n\x18f_get\
+    BlockHeaderByNumber\x10,\x12\x1a\n\x16f_getBlockHeaderByHash\x10-\x12\
     \x1f\n\x1bf_getCurrentTotalDifficulty\x10.\x12\x14\n\x10f_getStaticNodes\
     \x10/\x12\x1d\n\x19f_getBlockDetailsByNumber\x100\x12\x1d\n\x19f_getBloc\
     kDetailsByLatest\x101\x12\x17\n\x13f_getBlocksByLatest\x102\x12$\n\x20f_\
@@ -25861,34 +26272,34 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     \x12\x08\n\x04f_NA\x105\x12\x18\n\x14f_getBlockSqlByRange\x106\x12\x1c\n\
     \x18f_getBlockDetailsByRange\x107\x12\x0e\n\nf_getNonce\x108\x12\x11\n\r\
     f_getNrgPrice\x109\x12\x1b\n\x17f_getBlockDetailsByHash\x10:\x12\x0f\n\
-    \x0bf_listening\x10;\x12\x0f\n\x0bf_peerCount\x10<*\xa1\x07\n\x07Retcode\
-    \x12\n\n\x06r_fail\x10\0\x12\r\n\tr_success\x10\x01\x12\x13\n\x0fr_walle\
-    t_nullcb\x10\x02\x12\x15\n\x11r_heartbeatReturn\x10\x03\x12\x15\n\x11r_p\
-    rivilegeReturn\x10\x04\x12\r\n\tr_tx_Init\x10d\x12\x0f\n\x0br_tx_Recved\
-    \x10e\x12\x10\n\x0cr_tx_Dropped\x10f\x12\x13\n\x0fr_tx_NewPending\x10g\
-    \x12\x10\n\x0cr_tx_Pending\x10h\x12\x11\n\rr_tx_Included\x10i\x12\x10\n\
-    \x0cr_tx_eventCb\x10j\x12\x08\n\x04r_NA\x10k\x12\x1e\n\x11r_fail_header_\
-    len\x10\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\x20\n\x13r_fail_serv\
-    ice_call\x10\xfe\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12!\n\x14r_fail_fu\
-    nction_call\x10\xfd\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12&\n\x19r_fail\
-    _function_exception\x10\xfc\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\x1f\
-    \n\x12r_fail_api_version\x10\xfb\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\
-    \x1f\n\x12r_fail_ct_bytecode\x10\xfa\xff\xff\xff\xff\xff\xff\xff\xff\x01\
-    \x12\x1c\n\x0fr_fail_null_rsp\x10\xf9\xff\xff\xff\xff\xff\xff\xff\xff\
-    \x01\x12\x20\n\x13r_fail_invalid_addr\x10\xf8\xff\xff\xff\xff\xff\xff\
-    \xff\xff\x01\x12'\n\x1ar_fail_null_compile_source\x10\xf7\xff\xff\xff\
-    \xff\xff\xff\xff\xff\x01\x12$\n\x17r_fail_compile_contract\x10\xf6\xff\
-    \xff\xff\xff\xff\xff\xff\xff\x01\x12#\n\x16r_fail_sendTx_null_rep\x10\
-    \xf5\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\x1e\n\x11r_fail_getcode_to\
-    \x10\xf4\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12*\n\x1dr_fail_getTxRecei\
-    pt_null_recp\x10\xf3\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12(\n\x1br_fai\
-    l_zmqHandler_exception\x10\xf2\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12(\
-    \n\x1br_fail_hit_pending_tx_limit\x10\xf1\xff\xff\xff\xff\xff\xff\xff\
-    \xff\x01\x12%\n\x18r_fail_txqueue_exception\x10\xf0\xff\xff\xff\xff\xff\
-    \xff\xff\xff\x01\x12&\n\x19r_fail_function_arguments\x10\xef\xff\xff\xff\
-    \xff\xff\xff\xff\xff\x01\x12!\n\x14r_fail_unsupport_api\x10\xee\xff\xff\
-    \xff\xff\xff\xff\xff\xff\x01\x12\x1b\n\x0er_fail_unknown\x10\xed\xff\xff\
-    \xff\xff\xff\xff\xff\xff\x01b\x06proto3\
+    \x0bf_listening\x10;\x12\x0f\n\x0bf_peerCount\x10<\x12\x16\n\x12f_getBlo\
+    ckReceipts\x10=*\xa1\x07\n\x07Retcode\x12\n\n\x06r_fail\x10\x00\x12\r\n\
+    \tr_success\x10\x01\x12\x13\n\x0fr_wallet_nullcb\x10\x02\x12\x15\n\x11r_\
+    heartbeatReturn\x10\x03\x12\x15\n\x11r_privilegeReturn\x10\x04\x12\r\n\t\
+    r_tx_Init\x10d\x12\x0f\n\x0br_tx_Recved\x10e\x12\x10\n\x0cr_tx_Dropped\
+    \x10f\x12\x13\n\x0fr_tx_NewPending\x10g\x12\x10\n\x0cr_tx_Pending\x10h\
+    \x12\x11\n\rr_tx_Included\x10i\x12\x10\n\x0cr_tx_eventCb\x10j\x12\x08\n\
+    \x04r_NA\x10k\x12\x1e\n\x11r_fail_header_len\x10\xff\xff\xff\xff\xff\xff\
+    \xff\xff\xff\x01\x12\x20\n\x13r_fail_service_call\x10\xfe\xff\xff\xff\
+    \xff\xff\xff\xff\xff\x01\x12!\n\x14r_fail_function_call\x10\xfd\xff\xff\
+    \xff\xff\xff\xff\xff\xff\x01\x12&\n\x19r_fail_function_exception\x10\xfc\
+    \xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\x1f\n\x12r_fail_api_version\x10\
+    \xfb\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\x1f\n\x12r_fail_ct_bytecode\
+    \x10\xfa\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\x1c\n\x0fr_fail_null_rs\
+    p\x10\xf9\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\x20\n\x13r_fail_invali\
+    d_addr\x10\xf8\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\'\n\x1ar_fail_nul\
+    l_compile_source\x10\xf7\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12$\n\x17r\
+    _fail_compile_contract\x10\xf6\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12#\
+    \n\x16r_fail_sendTx_null_rep\x10\xf5\xff\xff\xff\xff\xff\xff\xff\xff\x01\
+    \x12\x1e\n\x11r_fail_getcode_to\x10\xf4\xff\xff\xff\xff\xff\xff\xff\xff\
+    \x01\x12*\n\x1dr_fail_getTxReceipt_null_recp\x10\xf3\xff\xff\xff\xff\xff\
+    \xff\xff\xff\x01\x12(\n\x1br_fail_zmqHandler_exception\x10\xf2\xff\xff\
+    \xff\xff\xff\xff\xff\xff\x01\x12(\n\x1br_fail_hit_pending_tx_limit\x10\
+    \xf1\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12%\n\x18r_fail_txqueue_except\
+    ion\x10\xf0\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12&\n\x19r_fail_functio\
+    n_arguments\x10\xef\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12!\n\x14r_fail\
+    _unsupport_api\x10\xee\xff\xff\xff\xff\xff\xff\xff\xff\x01\x12\x1b\n\x0e\
+    r_fail_unknown\x10\xed\xff\xff\xff\xff\xff\xff\xff\xff\x01b\x06proto3\
 ";
 
 static mut file_descriptor_proto_lazy: ::protobuf::lazy::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::lazy::Lazy {
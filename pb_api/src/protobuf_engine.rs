@@ -29,6 +29,7 @@ use message::{Servs, Retcode};
 use pb_api_util;
 use ::protobuf::ProtobufEnum;
 use api_process::{ApiProcess, to_rsp_msg, to_rsp_msg_with_result};
+use aion_rpc::informant::RpcStats;
 use aion_rpc::traits::Pb;
 use std::net::SocketAddr;
 use acore::transaction::local_transactions::TxIoMessage;
@@ -186,11 +187,12 @@ impl Default for WalletApiConfiguration {
     }
 }
 
-/// initialize protobuf engine and start.
+/// initialize protobuf engine and start. Calls are counted in `stats`.
 pub fn new_pb(
     conf: WalletApiConfiguration,
     client: Arc<Pb>,
     io_service: IoService<TxIoMessage>,
+    stats: Arc<RpcStats>,
 ) -> Result<Option<PBEngine>, String>
 {
     if !conf.enabled {
@@ -202,7 +204,7 @@ pub fn new_pb(
             .parse()
             .map_err(|_| format!("Invalid Wallet api server listen host/port given: {}", url))?;
 
-        let mut pb = PBEngine::new(client, io_service, url);
+        let mut pb = PBEngine::new(client, io_service, url, stats);
         match pb.run(conf) {
             Ok(()) => Ok(Some(pb)),
             Err(e) => Err(format!("wallet server start failed: {}", e)),
@@ -218,8 +220,14 @@ pub struct PBEngine {
 }
 
 impl PBEngine {
-    fn new(client: Arc<Pb>, io_service: IoService<TxIoMessage>, url: String) -> PBEngine {
-        let apis = Arc::new(ApiProcess::new(client, io_service));
+    fn new(
+        client: Arc<Pb>,
+        io_service: IoService<TxIoMessage>,
+        url: String,
+        stats: Arc<RpcStats>,
+    ) -> PBEngine
+    {
+        let apis = Arc::new(ApiProcess::new(client, io_service, stats));
         let shutdown = Arc::new(AtomicBool::new(true));
         let bind_url = format!("tcp://{}", url);
         info!(target: LOG_TARGET, "bind to address: {}", bind_url.as_str());
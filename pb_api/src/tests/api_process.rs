@@ -208,6 +208,47 @@ fn test_process_get_block_by_number() {
     assert_eq!(Retcode::r_fail_service_call.value() as u8, rsp[1]);
 }
 
+#[test]
+fn test_process_get_block_receipts() {
+    let mut req = req_getBlockReceipts::new();
+    req.set_blockNumber(1);
+    let rsp = send_request(
+        Servs::s_chain.value(),
+        Funcs::f_getBlockReceipts.value(),
+        req.write_to_bytes().unwrap(),
+    );
+    assert_eq!(Retcode::r_success.value() as u8, rsp[1]);
+    let by_number = parse_from_bytes::<rsp_getBlockReceipts>(&strip_header(rsp)).unwrap();
+
+    let blockhash: H256 = "d008ecacaa4c7a38ce6081b4b1dff2891e028c7eaaa5cce70865c5251fcd4212"
+        .parse()
+        .unwrap();
+    let mut req = req_getBlockReceipts::new();
+    req.set_blockHash(blockhash.0.to_vec());
+    let rsp = send_request(
+        Servs::s_chain.value(),
+        Funcs::f_getBlockReceipts.value(),
+        req.write_to_bytes().unwrap(),
+    );
+    assert_eq!(Retcode::r_success.value() as u8, rsp[1]);
+    let by_hash = parse_from_bytes::<rsp_getBlockReceipts>(&strip_header(rsp)).unwrap();
+    assert_eq!(by_number.get_receipts(), by_hash.get_receipts());
+
+    req.set_blockHash(vec![1, 2, 3]);
+    let rsp = send_request(
+        Servs::s_chain.value(),
+        Funcs::f_getBlockReceipts.value(),
+        req.write_to_bytes().unwrap(),
+    );
+    assert_eq!(Retcode::r_fail_function_arguments.value() as u8, rsp[1]);
+    let rsp = send_request(
+        Servs::s_hb.value(),
+        Funcs::f_getBlockReceipts.value(),
+        req.write_to_bytes().unwrap(),
+    );
+    assert_eq!(Retcode::r_fail_service_call.value() as u8, rsp[1]);
+}
+
 #[test]
 fn test_process_block_details() {
     let mut req = req_getBlockDetailsByNumber::new();
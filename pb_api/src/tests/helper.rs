@@ -21,6 +21,7 @@
 
 use acore::client::{Client, ClientConfig, BlockChainClient};
 use aion_rpc::impls::EthClient;
+use aion_rpc::informant::RpcStats;
 use acore::spec::Spec;
 use kvdb::{MockDbRepository, KeyValueDB};
 use std::sync::Arc;
@@ -101,8 +102,10 @@ pub fn api_process_instance() -> ApiProcess {
         &miner.clone(),
         &Arc::new(ExternalMiner::default()),
         Default::default(),
+        Default::default(),
+        None,
     ));
-    ApiProcess::new(ethclient.clone(), io_service)
+    ApiProcess::new(ethclient.clone(), io_service, Arc::new(RpcStats::default()))
 }
 
 static ENCODEBLOCK: &[u8] = &[
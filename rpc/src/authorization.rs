@@ -0,0 +1,335 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Secret-based authorization of RPC requests.
+
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jsonrpc_core::{Call, Error, Output, Request, Response};
+use rcrypto::hmac::Hmac;
+use rcrypto::mac::{Mac, MacResult};
+use rcrypto::sha2::Sha256;
+use rcrypto::util::fixed_time_eq;
+use rustc_serialize::base64::FromBase64;
+use serde_json::{self, Value};
+
+use helpers::errors;
+
+/// Method IPC connections authenticate with, as they carry no token of their own.
+pub const AUTHENTICATE_METHOD: &'static str = "rpc_authenticate";
+
+/// A secret an interface accepts requests with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Secret {
+    /// Static API key, sent as is.
+    ApiKey(String),
+    /// Key of HS256 JSON web tokens, which must carry an `exp` claim.
+    Jwt(Vec<u8>),
+}
+
+#[derive(Debug, PartialEq)]
+enum JwtError {
+    Malformed,
+    BadSignature,
+    Expired,
+}
+
+/// Secrets accepted by an interface, each with the modules (as listed by `rpc_modules`)
+/// whose methods it may call.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Authorization {
+    secrets: Vec<(Secret, HashSet<String>)>,
+}
+
+impl Authorization {
+    /// Create an authorization accepting no secret.
+    pub fn new() -> Self { Authorization::default() }
+
+    /// Accept `secret` for calls to methods of `modules`.
+    pub fn add(&mut self, secret: Secret, modules: HashSet<String>) {
+        self.secrets.push((secret, modules));
+    }
+
+    /// Check the calls of a request sent with `token`. On failure returns the response to send
+    /// instead of processing the request, none if it only holds notifications.
+    pub fn check(&self, request: &Request, token: Option<&str>) -> Result<(), Option<Response>> {
+        let allowed = self.modules(token, now());
+        let check_call = |call: &Call| -> Result<(), Error> {
+            let method = match *call {
+                Call::MethodCall(ref call) => &call.method,
+                Call::Notification(ref notification) => &notification.method,
+                // left to the handler to reject
+                _ => return Ok(()),
+            };
+            let modules = allowed.as_ref().map_err(Clone::clone)?;
+            let module = method_module(method);
+            if modules.contains(module) {
+                Ok(())
+            } else {
+                Err(errors::unauthorized(format!(
+                    "Not allowed to call {} methods.",
+                    module
+                )))
+            }
+        };
+
+        match *request {
+            Request::Single(ref call) => {
+                check_call(call).map_err(|error| failure(call, error).map(Response::Single))
+            }
+            Request::Batch(ref calls) => {
+                let results = calls.iter().map(|call| check_call(call)).collect::<Vec<_>>();
+                if results.iter().all(Result::is_ok) {
+                    return Ok(());
+                }
+                // the batch is rejected as a whole
+                let outputs = calls
+                    .iter()
+                    .zip(results.into_iter())
+                    .filter_map(|(call, result)| {
+                        let error = result.err().unwrap_or_else(|| {
+                            errors::unauthorized("Batch holds unauthorized calls.")
+                        });
+                        failure(call, error)
+                    })
+                    .collect::<Vec<_>>();
+                Err(if outputs.is_empty() {
+                    None
+                } else {
+                    Some(Response::Batch(outputs))
+                })
+            }
+        }
+    }
+
+    /// Check that `token` is accepted, whatever the modules it gives access to.
+    pub fn verify(&self, token: &str) -> Result<(), Error> {
+        self.modules(Some(token), now()).map(|_| ())
+    }
+
+    /// Modules the given token may call at unix time `now`.
+    fn modules(&self, token: Option<&str>, now: u64) -> Result<&HashSet<String>, Error> {
+        let token = match token {
+            Some(token) => token,
+            None => return Err(errors::unauthorized("Missing API key or token.")),
+        };
+        let mut expired = false;
+        for &(ref secret, ref modules) in &self.secrets {
+            match *secret {
+                Secret::ApiKey(ref key) => {
+                    if fixed_time_eq(key.as_bytes(), token.as_bytes()) {
+                        return Ok(modules);
+                    }
+                }
+                Secret::Jwt(ref key) => {
+                    match verify_jwt(key, token, now) {
+                        Ok(()) => return Ok(modules),
+                        Err(JwtError::Expired) => expired = true,
+                        Err(_) => {}
+                    }
+                }
+            }
+        }
+        Err(errors::unauthorized(if expired {
+            "Token has expired."
+        } else {
+            "Invalid API key or token."
+        }))
+    }
+}
+
+/// Name of the module serving the given method.
+pub fn method_module(method: &str) -> &str {
+    match method {
+        "eth_subscribe" | "eth_unsubscribe" => "pubsub",
+        "modules" => "rpc",
+        "ping" => "ping",
+        _ => {
+            match method.find('_') {
+                Some(index) => &method[..index],
+                // stratum methods are not namespaced
+                None => "stratum",
+            }
+        }
+    }
+}
+
+pub(crate) fn failure(call: &Call, error: Error) -> Option<Output> {
+    match *call {
+        Call::MethodCall(ref call) => Some(Output::from(Err(error), call.id.clone(), call.jsonrpc)),
+        _ => None,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Verify the HS256 signature and the expiry of a JSON web token.
+fn verify_jwt(key: &[u8], token: &str, now: u64) -> Result<(), JwtError> {
+    let parts = token.split('.').collect::<Vec<_>>();
+    if parts.len() != 3 {
+        return Err(JwtError::Malformed);
+    }
+
+    let header = decode_json(parts[0])?;
+    if header.get("alg").and_then(Value::as_str) != Some("HS256") {
+        return Err(JwtError::Malformed);
+    }
+    let signature = parts[2]
+        .from_base64()
+        .map_err(|_| JwtError::Malformed)?;
+    let mut mac = Hmac::new(Sha256::new(), key);
+    mac.input(token[..parts[0].len() + 1 + parts[1].len()].as_bytes());
+    if mac.result() != MacResult::new(&signature) {
+        return Err(JwtError::BadSignature);
+    }
+
+    let claims = decode_json(parts[1])?;
+    match claims.get("exp").and_then(Value::as_u64) {
+        Some(exp) if exp > now => Ok(()),
+        Some(_) => Err(JwtError::Expired),
+        None => Err(JwtError::Malformed),
+    }
+}
+
+fn decode_json(part: &str) -> Result<Value, JwtError> {
+    let bytes = part.from_base64().map_err(|_| JwtError::Malformed)?;
+    serde_json::from_slice(&bytes).map_err(|_| JwtError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::Id;
+    use rustc_serialize::base64::{ToBase64, URL_SAFE};
+
+    const NOW: u64 = 1_500_000_000;
+
+    fn jwt(key: &[u8], claims: &str) -> String {
+        let header = br#"{"alg":"HS256","typ":"JWT"}"#.to_base64(URL_SAFE);
+        let payload = format!("{}.{}", header, claims.as_bytes().to_base64(URL_SAFE));
+        let mut mac = Hmac::new(Sha256::new(), key);
+        mac.input(payload.as_bytes());
+        format!("{}.{}", payload, mac.result().code().to_base64(URL_SAFE))
+    }
+
+    fn modules(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn call(method: &str) -> String {
+        format!(
+            r#"{{"jsonrpc":"2.0","method":"{}","params":[],"id":1}}"#,
+            method
+        )
+    }
+
+    fn request(json: &str) -> Request { serde_json::from_str(json).unwrap() }
+
+    #[test]
+    fn should_map_methods_to_modules() {
+        assert_eq!(method_module("eth_getBalance"), "eth");
+        assert_eq!(method_module("eth_subscribe"), "pubsub");
+        assert_eq!(method_module("personal_unlockAccount"), "personal");
+        assert_eq!(method_module("rpc_modules"), "rpc");
+        assert_eq!(method_module("modules"), "rpc");
+        assert_eq!(method_module("getblocktemplate"), "stratum");
+        assert_eq!(method_module("ping"), "ping");
+    }
+
+    #[test]
+    fn should_verify_jwt() {
+        let key = b"secret";
+        let token = jwt(key, &format!(r#"{{"exp":{}}}"#, NOW + 60));
+        assert_eq!(verify_jwt(key, &token, NOW), Ok(()));
+        assert_eq!(verify_jwt(key, &token, NOW + 60), Err(JwtError::Expired));
+        assert_eq!(
+            verify_jwt(b"other", &token, NOW),
+            Err(JwtError::BadSignature)
+        );
+
+        let token = jwt(key, r#"{"iat":1}"#);
+        assert_eq!(verify_jwt(key, &token, NOW), Err(JwtError::Malformed));
+        assert_eq!(verify_jwt(key, "a.b", NOW), Err(JwtError::Malformed));
+    }
+
+    #[test]
+    fn should_resolve_modules_of_secrets() {
+        let mut authorization = Authorization::new();
+        authorization.add(
+            Secret::ApiKey("reader".into()),
+            modules(&["eth", "net"]),
+        );
+        authorization.add(Secret::Jwt(b"secret".to_vec()), modules(&["personal"]));
+
+        assert_eq!(
+            authorization.modules(Some("reader"), NOW),
+            Ok(&modules(&["eth", "net"]))
+        );
+        let token = jwt(b"secret", &format!(r#"{{"exp":{}}}"#, NOW + 1));
+        assert_eq!(
+            authorization.modules(Some(token.as_str()), NOW),
+            Ok(&modules(&["personal"]))
+        );
+
+        let expired = authorization.modules(Some(token.as_str()), NOW + 1).unwrap_err();
+        assert_eq!(expired.message, "Token has expired.");
+        assert!(authorization.modules(Some("writer"), NOW).is_err());
+        assert!(authorization.modules(None, NOW).is_err());
+    }
+
+    #[test]
+    fn should_reject_calls_to_other_modules() {
+        let mut authorization = Authorization::new();
+        authorization.add(Secret::ApiKey("reader".into()), modules(&["eth"]));
+
+        let single = request(&call("eth_blockNumber"));
+        assert_eq!(authorization.check(&single, Some("reader")), Ok(()));
+
+        let single = request(&call("personal_listAccounts"));
+        match authorization.check(&single, Some("reader")) {
+            Err(Some(Response::Single(Output::Failure(failure)))) => {
+                assert_eq!(
+                    failure.error,
+                    errors::unauthorized("Not allowed to call personal methods.")
+                );
+                assert_eq!(failure.id, Id::Num(1));
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        let batch = request(&format!(
+            "[{},{}]",
+            call("eth_blockNumber"),
+            call("personal_listAccounts")
+        ));
+        match authorization.check(&batch, Some("reader")) {
+            Err(Some(Response::Batch(outputs))) => assert_eq!(outputs.len(), 2),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
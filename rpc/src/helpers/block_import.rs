@@ -23,7 +23,12 @@
 //! Block import analysis functions.
 
 use acore::client::BlockQueueInfo;
-use sync::sync::storage::SyncState;
+use sync::sync::storage::{SyncState, SyncStatus};
+use types::SyncInfo;
+
+/// Number of blocks the chain may lag behind the network and still count as synced.
+/// Refer to java's impl: AionImpl.java isSyncComplete.
+const SYNC_LAG: u64 = 5;
 
 /// Check if client is during major sync or during block import.
 pub fn is_major_importing(sync_state: Option<SyncState>, queue_info: BlockQueueInfo) -> bool {
@@ -37,11 +42,36 @@ pub fn is_major_importing(sync_state: Option<SyncState>, queue_info: BlockQueueI
     is_verifying || is_syncing_state
 }
 
+/// Sync progress as reported by `eth_syncing` and the `syncing` subscription, `None` once
+/// the chain caught up with the network.
+pub fn sync_info(
+    status: &SyncStatus,
+    best_block: u64,
+    queue_info: &BlockQueueInfo,
+) -> Option<SyncInfo>
+{
+    let highest_block = status.highest_block_number.unwrap_or(0);
+    if best_block + SYNC_LAG >= highest_block {
+        return None;
+    }
+    Some(SyncInfo {
+        // to comply with java's impl, return hex string.
+        starting_block: format!("{:#x}", status.start_block_number),
+        current_block: format!("{:#x}", best_block),
+        highest_block: format!("{:#x}", highest_block),
+        sync_speed: status.sync_speed,
+        peer_count: status.num_peers,
+        unverified_queue_size: queue_info.unverified_queue_size,
+        verifying_queue_size: queue_info.verifying_queue_size,
+        verified_queue_size: queue_info.verified_queue_size,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use acore::client::BlockQueueInfo;
-    use sync::sync::storage::SyncState;
-    use super::is_major_importing;
+    use sync::sync::storage::{SyncState, SyncStatus};
+    use super::{is_major_importing, sync_info};
 
     fn queue_info(unverified: usize, verified: usize) -> BlockQueueInfo {
         BlockQueueInfo {
@@ -68,4 +98,32 @@ mod tests {
         ));
         assert!(!is_major_importing(Some(SyncState::Idle), queue_info(0, 0)));
     }
+
+    #[test]
+    fn reports_sync_progress() {
+        let status = SyncStatus {
+            state: SyncState::Idle,
+            protocol_version: 0,
+            network_id: 256,
+            start_block_number: 10,
+            last_imported_block_number: None,
+            highest_block_number: Some(100),
+            blocks_received: 0,
+            blocks_total: 0,
+            num_peers: 3,
+            num_active_peers: 0,
+            sync_speed: 20,
+        };
+
+        let info = sync_info(&status, 50, &queue_info(7, 2)).unwrap();
+        assert_eq!(info.starting_block, "0xa");
+        assert_eq!(info.current_block, "0x32");
+        assert_eq!(info.highest_block, "0x64");
+        assert_eq!(info.sync_speed, 20);
+        assert_eq!(info.peer_count, 3);
+        assert_eq!(info.unverified_queue_size, 7);
+        assert_eq!(info.verified_queue_size, 2);
+
+        assert!(sync_info(&status, 95, &queue_info(0, 0)).is_none());
+    }
 }
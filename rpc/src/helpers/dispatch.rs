@@ -184,9 +184,11 @@ impl<C: MiningBlockChainClient, M: MinerService> Dispatcher for FullDispatcher<C
         password: SignWith,
     ) -> BoxFuture<SignResult>
     {
+        let chain_id = self.client.signing_chain_id();
+
         if let Some(nonce) = filled.nonce {
             return Box::new(future::done(sign_transaction(
-                &*accounts, filled, None, nonce, password,
+                &*accounts, filled, chain_id, nonce, password,
             )));
         }
 
@@ -194,7 +196,7 @@ impl<C: MiningBlockChainClient, M: MinerService> Dispatcher for FullDispatcher<C
         let reserved = self.nonces.lock().reserve(filled.from, state);
 
         Box::new(ProspectiveSigner::new(
-            accounts, filled, None, reserved, password,
+            accounts, filled, chain_id, reserved, password,
         ))
     }
 
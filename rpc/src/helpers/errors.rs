@@ -29,6 +29,7 @@ use acore::account_provider::{SignError as AccountError};
 use acore::error::{Error as EthcoreError, CallError};
 use jsonrpc_core::{futures, Error, ErrorCode, Value};
 use rlp::DecoderError;
+use serde_json::Map;
 use acore::transaction::Error as TransactionError;
 
 mod codes {
@@ -38,6 +39,7 @@ mod codes {
     pub const NO_AUTHOR: i64 = -32002;
     pub const NO_NEW_WORK: i64 = -32003;
     pub const NO_WORK_REQUIRED: i64 = -32004;
+    pub const LIMIT_EXCEEDED: i64 = -32005;
     pub const UNKNOWN_ERROR: i64 = -32009;
     pub const TRANSACTION_ERROR: i64 = -32010;
     pub const EXECUTION_ERROR: i64 = -32015;
@@ -50,6 +52,7 @@ mod codes {
     pub const REQUEST_REJECTED: i64 = -32040;
     pub const REQUEST_REJECTED_LIMIT: i64 = -32041;
     pub const REQUEST_NOT_FOUND: i64 = -32042;
+    pub const UNAUTHORIZED: i64 = -32050;
     pub const ENCRYPTION_ERROR: i64 = -32055;
     //    pub const ENCODING_ERROR: i64 = -32058;
     pub const FETCH_ERROR: i64 = -32060;
@@ -115,6 +118,27 @@ pub fn request_rejected_limit() -> Error {
     }
 }
 
+/// The request went over one of the limits configured on the node. `limit` names it and `max`
+/// is its value, both are also given as error data.
+pub fn limit_exceeded(limit: &str, max: u64) -> Error {
+    let mut data = Map::new();
+    data.insert("limit".into(), Value::String(limit.into()));
+    data.insert("max".into(), Value::from(max));
+    Error {
+        code: ErrorCode::ServerError(codes::LIMIT_EXCEEDED),
+        message: format!("Request exceeds the {} limit of {}.", limit, max),
+        data: Some(Value::Object(data)),
+    }
+}
+
+pub fn unauthorized<T: Into<String>>(reason: T) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::UNAUTHORIZED),
+        message: reason.into(),
+        data: None,
+    }
+}
+
 pub fn account<T: fmt::Debug>(error: &str, details: T) -> Error {
     Error {
         code: ErrorCode::ServerError(codes::ACCOUNT_ERROR),
@@ -242,6 +266,26 @@ pub fn ws_disabled() -> Error {
     }
 }
 
+pub fn address_index_disabled() -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+        message: "Address index is disabled. Start the node with --tx-address-index to enable it."
+            .into(),
+        data: None,
+    }
+}
+
+pub fn address_index_incomplete(lowest: u64, highest: u64) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+        message: format!(
+            "Address index only covers blocks #{} to #{} yet. Retry once it is backfilled.",
+            lowest, highest
+        ),
+        data: None,
+    }
+}
+
 pub fn network_disabled() -> Error {
     Error {
         code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
@@ -369,6 +413,7 @@ pub fn transaction_message(error: TransactionError) -> String {
         }
         InvalidSignature(sig) => format!("Invalid signature: {}", sig),
         InvalidChainId => "Invalid chain id.".into(),
+        FeeDelegationNotActive => "Fee-delegated transactions are not active yet.".into(),
         InvalidGasLimit(_) => "Supplied gas is beyond limit.".into(),
         SenderBanned => "Sender is banned in local queue.".into(),
         RecipientBanned => "Recipient is banned in local queue.".into(),
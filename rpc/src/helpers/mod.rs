@@ -33,6 +33,8 @@ mod poll_filter;
 mod poll_manager;
 mod requests;
 mod subscribers;
+#[cfg(test)]
+pub mod test_sync;
 
 pub use self::dispatch::{Dispatcher, FullDispatcher};
 pub use self::poll_manager::PollManager;
@@ -38,8 +38,9 @@ pub enum PollFilter {
     Block(BlockNumber),
     /// Hashes of all transactions which client was notified about.
     PendingTransaction(Vec<H256>),
-    /// Number of From block number, pending logs and log filter itself.
-    Logs(BlockNumber, HashSet<Log>, Filter),
+    /// Number of From block number, hash of the last block client was notified about,
+    /// pending logs and log filter itself.
+    Logs(BlockNumber, Option<H256>, HashSet<Log>, Filter),
 }
 
 /// Returns only last `n` logs
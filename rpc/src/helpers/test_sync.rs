@@ -0,0 +1,70 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Sync provider for tests, with a status which can be changed at will.
+
+use std::collections::BTreeMap;
+
+use aion_types::H256;
+use parking_lot::RwLock;
+use sync::sync::SyncProvider;
+use sync::sync::storage::{ActivePeerInfo, PeerInfo, SyncState, SyncStatus, TransactionStats};
+
+pub struct TestSyncProvider {
+    status: RwLock<SyncStatus>,
+}
+
+impl TestSyncProvider {
+    /// Creates an idle sync provider without peers.
+    pub fn new() -> Self {
+        TestSyncProvider {
+            status: RwLock::new(SyncStatus {
+                state: SyncState::Idle,
+                protocol_version: 0,
+                network_id: 256,
+                start_block_number: 0,
+                last_imported_block_number: None,
+                highest_block_number: None,
+                blocks_total: 0,
+                blocks_received: 0,
+                num_peers: 0,
+                num_active_peers: 0,
+                sync_speed: 0,
+            }),
+        }
+    }
+
+    /// Changes the reported sync status.
+    pub fn set_status(&self, status: SyncStatus) { *self.status.write() = status; }
+}
+
+impl SyncProvider for TestSyncProvider {
+    fn status(&self) -> SyncStatus { *self.status.read() }
+
+    fn peers(&self) -> Vec<PeerInfo> { Vec::new() }
+
+    fn enode(&self) -> Option<String> { None }
+
+    fn transactions_stats(&self) -> BTreeMap<H256, TransactionStats> { BTreeMap::new() }
+
+    fn active(&self) -> Vec<ActivePeerInfo> { Vec::new() }
+}
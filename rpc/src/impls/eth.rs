@@ -22,44 +22,66 @@
 
 //! Eth rpc implementationethcore/src/state/mod.rs.
 
+use std::cmp;
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::thread;
 use std::time;
 
 use rlp::UntrustedRlp;
-use aion_types::{H256, H128, U128, Address};
+use aion_types::{H256, H128, U128, U256, Address};
 use serde_json::{self, Value};
 use serde_json::map::Map;
 use dispatch::DynamicGasPrice;
 
-use sync::sync::SyncProvider;
+use sync::sync::{LightProvider, SyncProvider};
+use sync::sync::light::{LightRequest, LightResponse};
 use acore::account_provider::AccountProvider;
-use acore::client::{MiningBlockChainClient, BlockId, TransactionId};
+use acore::basic_account::BasicAccount;
+use acore::client::{
+    MiningBlockChainClient, BlockId, TransactionId, CallAnalytics,
+    StateOverride as EthStateOverride, BlockOverride as EthBlockOverride,
+};
 use acore::filter::Filter as EthcoreFilter;
 use acore::header::{BlockNumber as EthBlockNumber};
 use acore::log_entry::LogEntry;
 use acore::miner::MinerService;
 use acore::miner::external::ExternalMinerService;
 use acore::transaction::SignedTransaction;
-use acore::blockchain::BlockReceipts;
+use acore::blockchain::{BlockReceipts, MAX_SKIPPED_TRANSACTIONS};
 use solidity::compile;
+use blake2b::blake2b;
 
-use jsonrpc_core::{BoxFuture, Result};
-use jsonrpc_core::futures::future;
-use jsonrpc_macros::Trailing;
+use jsonrpc_core::{BoxFuture, Result, Params, Metadata};
+use jsonrpc_core::futures::{future, Future};
+use jsonrpc_macros::{Trailing, IoDelegate};
+use serde::Serialize;
 
 use helpers::{errors, limit_logs, fake_sign};
 use helpers::dispatch::{FullDispatcher, default_gas_price};
 use helpers::accounts::unwrap_provider;
+use helpers::block_import::sync_info;
+use limits::CallLimits;
 use traits::{Eth, Pb};
 use types::{
-    Block, BlockTransactions, BlockNumber, Bytes, SyncStatus,
+    Block, BlockTransactions, BlockNumber, BlockNumberOrHash, Bytes, SyncStatus,
     Transaction, CallRequest, Index, Filter, Log, Receipt, Work,
     H64 as RpcH64, H256 as RpcH256, U256 as RpcU256, U128 as RpcU128, H128 as RpcH128,
-    Contract, ContractInfo, Abi, AbiIO, SyncInfo, AcitvePeerInfo, PbSyncInfo, SimpleReceipt, SimpleReceiptLog
+    Contract, ContractInfo, Abi, AbiIO, AcitvePeerInfo, PbSyncInfo, SimpleReceipt, SimpleReceiptLog,
+    FeeHistory, Histogram, StateOverride, StateDiff, BundleTransaction, BlockOverride,
+    CallBundleResult,
 };
 
+/// Maximum number of blocks `eth_feeHistory` reports on.
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// Number of buckets of `eth_gasPriceHistogram`.
+const GAS_PRICE_HISTOGRAM_BUCKETS: usize = 10;
+
+/// Maximum number of transactions `eth_getTransactionsByAddress` returns at once.
+const MAX_TRANSACTIONS_BY_ADDRESS: usize = 1000;
+
 // const EXTRA_INFO_PROOF: &'static str = "Object exists in in blockchain (fetched earlier), extra_info is always available if object exists; qed";
 
 /// Eth rpc implementation.
@@ -76,6 +98,8 @@ where
     miner: Arc<M>,
     external_miner: Arc<EM>,
     dynamic_gas_price: Option<DynamicGasPrice>,
+    limits: CallLimits,
+    light: Option<Arc<LightProvider>>,
 }
 
 impl<C, S: ?Sized, M, EM> EthClient<C, S, M, EM>
@@ -85,7 +109,8 @@ where
     M: MinerService,
     EM: ExternalMinerService,
 {
-    /// Creates new EthClient.
+    /// Creates new EthClient. In light mode, `light` serves the accounts the local state
+    /// doesn't hold.
     pub fn new(
         client: &Arc<C>,
         sync: &Arc<S>,
@@ -93,6 +118,8 @@ where
         miner: &Arc<M>,
         em: &Arc<EM>,
         dynamic_gas_price: Option<DynamicGasPrice>,
+        limits: CallLimits,
+        light: Option<Arc<LightProvider>>,
     ) -> Self
     {
         EthClient {
@@ -102,6 +129,8 @@ where
             accounts: accounts.clone(),
             external_miner: em.clone(),
             dynamic_gas_price: dynamic_gas_price.clone(),
+            limits,
+            light,
         }
     }
 
@@ -109,6 +138,49 @@ where
     /// set.
     fn account_provider(&self) -> Result<Arc<AccountProvider>> { unwrap_provider(&self.accounts) }
 
+    /// Fails in light mode, where the local state is only the genesis one and nothing can be
+    /// executed against it.
+    fn check_full_state(&self) -> Result<()> {
+        match self.light {
+            Some(_) => Err(errors::light_unimplemented(None)),
+            None => Ok(()),
+        }
+    }
+
+    /// Account at the given block, as proved by a full node against the headers synced in
+    /// light mode. `None` if the account is proved not to exist.
+    fn light_account(
+        light: &LightProvider,
+        address: &Address,
+        num: BlockNumber,
+    ) -> BoxFuture<Option<BasicAccount>>
+    {
+        let header = match num {
+            BlockNumber::Latest | BlockNumber::Pending => light.best_header(),
+            BlockNumber::Earliest => light.header(0),
+            BlockNumber::Num(number) => light.header(number),
+        };
+        let block_hash = match header {
+            Some(header) => header.hash(),
+            None => return Box::new(future::err(errors::unknown_block())),
+        };
+        let request = LightRequest::Account {
+            block_hash,
+            address_hash: blake2b(address),
+        };
+        Box::new(
+            light
+                .fetch(request)
+                .map_err(errors::on_demand_cancel)
+                .and_then(|response| {
+                    match response {
+                        LightResponse::Account(account) => Ok(account),
+                        _ => Err(errors::internal("Unexpected light response", "")),
+                    }
+                }),
+        )
+    }
+
     fn block(&self, id: BlockId, include_txs: bool) -> Result<Option<Block>> {
         let client = &self.client;
         match (client.block(id.clone()), client.block_total_difficulty(id)) {
@@ -184,6 +256,162 @@ where
     }
 }
 
+impl<C, S: ?Sized, M, EM> EthClient<C, S, M, EM>
+where
+    C: MiningBlockChainClient + 'static,
+    S: SyncProvider + 'static,
+    M: MinerService + 'static,
+    EM: ExternalMinerService + 'static,
+{
+    /// Creates the `Eth` delegate, with `eth_call` and `eth_estimateGas` accepting a state
+    /// override as optional third parameter. `Trailing` only covers the last parameter, so
+    /// these two methods parse their params themselves.
+    pub fn to_delegate_with_state_override<Meta: Metadata>(self) -> IoDelegate<Self, Meta> {
+        let mut delegate = Eth::to_delegate(self);
+        delegate.add_method("eth_call", |client, params| {
+            let (request, num, state_override) = call_params(params)?;
+            client
+                .call_with_state_override(request, num, state_override.into())
+                .and_then(to_value)
+        });
+        delegate.add_method("eth_estimateGas", |client, params| {
+            let (request, num, state_override) = call_params(params)?;
+            client
+                .estimate_gas_with_state_override(request, num, state_override.into())
+                .and_then(to_value)
+        });
+        delegate
+    }
+
+    fn call_with_state_override(
+        &self,
+        request: CallRequest,
+        num: BlockNumber,
+        state_override: EthStateOverride,
+    ) -> Result<Bytes>
+    {
+        self.check_full_state()?;
+        let request = CallRequest::into(request);
+        let signed = fake_sign::sign_call(request)?;
+        self.with_call_timeout(move |client| {
+            client
+                .call(&signed, Default::default(), num.into(), &state_override)
+                .map(|b| b.output.into())
+                .map_err(errors::call)
+        })
+    }
+
+    fn estimate_gas_with_state_override(
+        &self,
+        request: CallRequest,
+        num: BlockNumber,
+        state_override: EthStateOverride,
+    ) -> Result<RpcU256>
+    {
+        self.check_full_state()?;
+        let request = CallRequest::into(request);
+        let signed = fake_sign::sign_call(request)?;
+        self.with_call_timeout(move |client| {
+            client
+                .estimate_gas(&signed, num.into(), &state_override)
+                .map(Into::into)
+                .map_err(errors::call)
+        })
+    }
+
+    /// Runs `f` against the client in one of the call slots, giving up once the call timeout
+    /// has elapsed. Calls are rejected while every slot is taken.
+    ///
+    /// The timeout only answers the request: the EVM is not interrupted, so the execution goes
+    /// on in its call thread, holding its slot, until it completes. The slots thus bound the
+    /// executions left running as well as the threads running them.
+    fn with_call_timeout<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&C) -> Result<T> + Send + 'static,
+    {
+        let slot = self.limits.call_slots.acquire()?;
+        let timeout = match self.limits.call_timeout {
+            Some(timeout) => timeout,
+            None => {
+                let result = f(&*self.client);
+                drop(slot);
+                return result;
+            }
+        };
+        let client = self.client.clone();
+        let (tx, rx) = mpsc::channel();
+        self.limits.call_slots.spawn(slot, move || {
+            let _ = tx.send(f(&*client));
+        });
+        match rx.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                let millis = timeout.as_secs() * 1_000 + u64::from(timeout.subsec_millis());
+                Err(errors::limit_exceeded("callTimeout", millis))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(errors::internal("call failed", "execution aborted"))
+            }
+        }
+    }
+}
+
+/// Converts the block override of a bundle call, rejecting a number or timestamp which does not
+/// fit a block header.
+fn block_override_params(block_override: BlockOverride) -> Result<EthBlockOverride> {
+    let to_u64 = |name: &str, value: Option<RpcU256>| -> Result<Option<u64>> {
+        match value {
+            Some(value) => {
+                let value: U256 = value.into();
+                if value > U256::from(u64::max_value()) {
+                    Err(errors::invalid_params(name, "exceeds u64"))
+                } else {
+                    Ok(Some(value.low_u64()))
+                }
+            }
+            None => Ok(None),
+        }
+    };
+    Ok(EthBlockOverride {
+        number: to_u64("number", block_override.number)?,
+        timestamp: to_u64("timestamp", block_override.timestamp)?,
+        author: block_override.coinbase.map(Into::into),
+    })
+}
+
+/// Parses the params of `eth_call` and `eth_estimateGas`: a call request, optionally followed
+/// by a block number and a state override.
+fn call_params(params: Params) -> Result<(CallRequest, BlockNumber, StateOverride)> {
+    let len = match params {
+        Params::Array(ref values) => values.len(),
+        _ => 0,
+    };
+    match len {
+        1 => {
+            params
+                .parse::<(CallRequest,)>()
+                .map(|(request,)| (request, Default::default(), Default::default()))
+        }
+        2 => {
+            params
+                .parse::<(CallRequest, BlockNumber)>()
+                .map(|(request, num)| (request, num, Default::default()))
+        }
+        3 => params.parse(),
+        _ => {
+            Err(errors::invalid_params(
+                "params",
+                format!("Expected 1 to 3 parameters, got {}", len),
+            ))
+        }
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| errors::internal("serialization failed", e))
+}
+
 pub fn pending_logs<M>(miner: &M, best_block: EthBlockNumber, filter: &EthcoreFilter) -> Vec<Log>
 where M: MinerService {
     let receipts = miner.pending_receipts(best_block);
@@ -236,25 +464,10 @@ where
     }
 
     fn syncing(&self) -> Result<SyncStatus> {
-        let status = self.sync.status();
-        let client = &self.client;
-
-        let chain_info = client.chain_info();
-        let current_block = chain_info.best_block_number;
-        let highest_block = status.highest_block_number.unwrap_or(0u64);
-
-        // refer to java's impl: AionImpl.java isSyncComplete.
-        if (current_block + 5) < highest_block {
-            let info = SyncInfo {
-                // to comply with java's impl, return hex string.
-                starting_block: format!("{:#x}", status.start_block_number),
-                current_block: format!("{:#x}", current_block),
-                highest_block: format!("{:#x}", highest_block),
-            };
-            Ok(SyncStatus::Info(info))
-        } else {
-            Ok(SyncStatus::None)
-        }
+        let best_block = self.client.chain_info().best_block_number;
+        let queue_info = self.client.queue_info();
+        let info = sync_info(&self.sync.status(), best_block, &queue_info);
+        Ok(info.map_or(SyncStatus::None, SyncStatus::Info))
     }
 
     fn author(&self) -> Result<RpcH256> { Ok(RpcH256::from(self.miner.author())) }
@@ -271,6 +484,86 @@ where
         )))
     }
 
+    fn fee_history(
+        &self,
+        block_count: RpcU256,
+        newest_block: BlockNumber,
+        percentiles: Trailing<Vec<f64>>,
+    ) -> Result<FeeHistory>
+    {
+        let percentiles: Option<Vec<f64>> = percentiles.into();
+        if let Some(ref percentiles) = percentiles {
+            let in_range = percentiles.iter().all(|p| *p >= 0.0 && *p <= 100.0);
+            let increasing = percentiles.windows(2).all(|w| w[0] <= w[1]);
+            if !in_range || !increasing {
+                return Err(errors::invalid_params(
+                    "percentiles",
+                    "Expected increasing values between 0 and 100.",
+                ));
+            }
+        }
+
+        let newest = match newest_block {
+            BlockNumber::Pending => BlockId::Latest,
+            number => number.into(),
+        };
+        let newest = self
+            .client
+            .block_number(newest)
+            .ok_or_else(|| errors::invalid_params("newestBlock", "Unknown block."))?;
+        let block_count: U256 = block_count.into();
+        let block_count = cmp::min(block_count, U256::from(MAX_FEE_HISTORY_BLOCKS)).low_u64();
+        let block_count = cmp::min(block_count, newest + 1);
+        let oldest = newest + 1 - block_count;
+
+        let mut gas_used_ratio = Vec::new();
+        let mut gas_price = Vec::new();
+        for number in oldest..newest + 1 {
+            let block = self
+                .client
+                .block(BlockId::Number(number))
+                .ok_or_else(errors::not_enough_data)?;
+            let gas_limit = block.gas_limit().low_u64();
+            gas_used_ratio.push(if gas_limit == 0 {
+                0.0
+            } else {
+                block.gas_used().low_u64() as f64 / gas_limit as f64
+            });
+
+            if let Some(ref percentiles) = percentiles {
+                let corpus: ::stats::Corpus<U256> = block
+                    .transaction_views()
+                    .iter()
+                    .map(|t| t.gas_price())
+                    .collect();
+                gas_price.push(
+                    percentiles
+                        .iter()
+                        .map(|p| corpus.nearest_rank(*p).cloned().unwrap_or_default().into())
+                        .collect(),
+                );
+            }
+        }
+
+        Ok(FeeHistory {
+            oldest_block: oldest.into(),
+            gas_used_ratio: gas_used_ratio,
+            gas_price: percentiles.map(|_| gas_price),
+        })
+    }
+
+    fn gas_price_histogram(&self) -> Result<Histogram> {
+        let dynamic_gas_price = self.dynamic_gas_price.clone().unwrap_or_default();
+        self.client
+            .gas_price_corpus(
+                dynamic_gas_price.blk_price_window,
+                dynamic_gas_price.max_blk_traverse,
+            )
+            .histogram(GAS_PRICE_HISTOGRAM_BUCKETS)
+            .ok_or_else(errors::not_enough_data)
+            .map(Into::into)
+    }
+
     fn accounts(&self) -> Result<Vec<RpcH256>> {
         let store = self.account_provider()?;
         let accounts = store
@@ -289,6 +582,13 @@ where
 
         let id = num.unwrap_or_default();
 
+        if let Some(ref light) = self.light {
+            return Box::new(
+                Self::light_account(&**light, &address, id)
+                    .map(|account| account.map_or_else(U256::zero, |a| a.balance).into()),
+            );
+        }
+
         try_bf!(check_known(&*self.client, id.clone()));
         let res = match self.client.balance(&address, id.into()) {
             Some(balance) => Ok(balance.into()),
@@ -310,6 +610,7 @@ where
 
         let id = num.unwrap_or_default();
 
+        try_bf!(self.check_full_state());
         try_bf!(check_known(&*self.client, id.clone()));
         let res = match self
             .client
@@ -330,6 +631,13 @@ where
     {
         let address: Address = RpcH256::into(address);
 
+        if let Some(ref light) = self.light {
+            return Box::new(
+                Self::light_account(&**light, &address, num.unwrap_or_default())
+                    .map(|account| account.map_or_else(U256::zero, |a| a.nonce).into()),
+            );
+        }
+
         let res = match num.unwrap_or_default() {
             BlockNumber::Pending => {
                 let nonce = self
@@ -377,6 +685,7 @@ where
         let address: Address = RpcH256::into(address);
 
         let id = num.unwrap_or_default();
+        try_bf!(self.check_full_state());
         try_bf!(check_known(&*self.client, id.clone()));
 
         let res = match self.client.code(&address, id.into()) {
@@ -409,6 +718,54 @@ where
         Box::new(future::ok(tx))
     }
 
+    fn transactions_by_address(
+        &self,
+        address: RpcH256,
+        offset: Index,
+        count: Index,
+    ) -> BoxFuture<Vec<Transaction>>
+    {
+        if count.value() > MAX_TRANSACTIONS_BY_ADDRESS {
+            return Box::new(future::err(errors::invalid_params(
+                "count",
+                format!("At most {} transactions per request", MAX_TRANSACTIONS_BY_ADDRESS),
+            )));
+        }
+        if offset.value() > MAX_SKIPPED_TRANSACTIONS {
+            return Box::new(future::err(errors::invalid_params(
+                "offset",
+                format!("At most {} transactions can be skipped", MAX_SKIPPED_TRANSACTIONS),
+            )));
+        }
+
+        let best_block = self.client.chain_info().best_block_number;
+        let (transactions, (lowest, highest)) = match self.client.transactions_by_address(
+            &address.into(),
+            offset.value(),
+            count.value(),
+        ) {
+            Some(result) => result,
+            None => return Box::new(future::err(errors::address_index_disabled())),
+        };
+        // the newest transactions are missing while the index lags the chain, the oldest ones
+        // unless the page was filled before reaching the lowest indexed block.
+        if highest < best_block || (lowest > 0 && transactions.len() < count.value()) {
+            return Box::new(future::err(errors::address_index_incomplete(lowest, highest)));
+        }
+
+        let client = &self.client;
+        let transactions = transactions
+            .into_iter()
+            .map(|t| {
+                let timestamp = client
+                    .block_header(BlockId::Hash(t.block_hash))
+                    .map_or(0, |header| header.timestamp());
+                Transaction::from_localized(t, timestamp)
+            })
+            .collect();
+        Box::new(future::ok(transactions))
+    }
+
     fn transaction_by_block_hash_and_index(
         &self,
         hash: RpcH256,
@@ -438,14 +795,25 @@ where
         Box::new(future::ok(receipt.map(Into::into)))
     }
 
+    fn block_receipts(&self, id: BlockNumberOrHash) -> BoxFuture<Option<Vec<Receipt>>> {
+        let receipts: Option<Vec<Receipt>> = self
+            .client
+            .localized_block_receipts(id.into())
+            .map(|receipts| receipts.into_iter().map(Into::into).collect());
+        Box::new(future::ok(receipts))
+    }
+
     fn compilers(&self) -> Result<Vec<String>> { Ok(vec![String::from("solidity")]) }
 
     fn logs(&self, filter: Filter) -> BoxFuture<Vec<Log>> {
         let include_pending = filter.to_block == Some(BlockNumber::Pending);
         let filter: EthcoreFilter = filter.into();
+        try_bf!(self.limits.check_logs_filter(&*self.client, &filter));
+        let mut query = filter.clone();
+        self.limits.cap_logs_filter(&mut query);
         let mut logs = self
             .client
-            .logs(filter.clone())
+            .logs(query)
             .into_iter()
             .map(From::from)
             .collect::<Vec<Log>>();
@@ -457,6 +825,7 @@ where
         }
 
         let logs = limit_logs(logs, filter.limit);
+        try_bf!(self.limits.check_logs_count(logs.len()));
 
         Box::new(future::ok(logs))
     }
@@ -497,26 +866,112 @@ where
     fn submit_transaction(&self, raw: Bytes) -> Result<RpcH256> { self.send_raw_transaction(raw) }
 
     fn call(&self, request: CallRequest, num: Trailing<BlockNumber>) -> BoxFuture<Bytes> {
-        let request = CallRequest::into(request);
-        let signed = try_bf!(fake_sign::sign_call(request));
+        Box::new(future::done(self.call_with_state_override(
+            request,
+            num.unwrap_or_default(),
+            EthStateOverride::new(),
+        )))
+    }
 
-        let num = num.unwrap_or_default();
-        let result = self.client.call(&signed, Default::default(), num.into());
+    fn estimate_gas(&self, request: CallRequest, num: Trailing<BlockNumber>) -> BoxFuture<RpcU256> {
+        Box::new(future::done(self.estimate_gas_with_state_override(
+            request,
+            num.unwrap_or_default(),
+            EthStateOverride::new(),
+        )))
+    }
 
-        Box::new(future::done(
-            result.map(|b| b.output.into()).map_err(errors::call),
-        ))
+    fn call_bundle(
+        &self,
+        transactions: Vec<BundleTransaction>,
+        num: BlockNumber,
+        block_override: Trailing<BlockOverride>,
+    ) -> BoxFuture<Vec<CallBundleResult>>
+    {
+        try_bf!(self.check_full_state());
+        try_bf!(self.limits.check_bundle_size(transactions.len()));
+        let block_override = try_bf!(block_override_params(block_override.unwrap_or_default()));
+        let analytics = CallAnalytics {
+            state_diffing: true,
+            ..Default::default()
+        };
+        let mut signed = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            let signed_transaction = match transaction {
+                BundleTransaction::Raw(raw) => {
+                    try_bf!(
+                        UntrustedRlp::new(&raw.into_vec())
+                            .as_val()
+                            .map_err(errors::rlp)
+                            .and_then(|tx| SignedTransaction::new(tx).map_err(errors::transaction))
+                    )
+                }
+                BundleTransaction::Call(request) => {
+                    if request.from.is_none() {
+                        return Box::new(future::err(errors::invalid_params(
+                            "from",
+                            "required for unsigned transactions",
+                        )));
+                    }
+                    try_bf!(fake_sign::sign_call(request.into()))
+                }
+            };
+            signed.push((signed_transaction, analytics));
+        }
+
+        let (signed, executed) = try_bf!(self.with_call_timeout(move |client| {
+            client
+                .call_many(&signed, num.into(), &block_override)
+                .map(|executed| (signed, executed))
+                .map_err(errors::call)
+        }));
+
+        let results = signed
+            .iter()
+            .zip(executed)
+            .enumerate()
+            .map(|(index, (&(ref t, _), e))| {
+                let hash = t.hash();
+                let logs = e
+                    .logs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(log_index, entry)| {
+                        let mut log = Log::from(entry);
+                        log.transaction_hash = Some(hash.into());
+                        log.transaction_index = Some(index.into());
+                        log.transaction_log_index = Some(log_index.into());
+                        log
+                    })
+                    .collect();
+                CallBundleResult {
+                    transaction_hash: hash.into(),
+                    gas_used: e.gas_used.into(),
+                    output: e.output.into(),
+                    error: if e.exception.is_empty() {
+                        None
+                    } else {
+                        Some(e.exception)
+                    },
+                    logs: logs,
+                    state_diff: e
+                        .state_diff
+                        .map(Into::into)
+                        .unwrap_or_else(|| StateDiff(Default::default())),
+                }
+            })
+            .collect();
+        Box::new(future::ok(results))
     }
 
-    fn estimate_gas(&self, request: CallRequest, num: Trailing<BlockNumber>) -> BoxFuture<RpcU256> {
-        let request = CallRequest::into(request);
-        let signed = try_bf!(fake_sign::sign_call(request));
-        Box::new(future::done(
-            self.client
-                .estimate_gas(&signed, num.unwrap_or_default().into())
-                .map(Into::into)
-                .map_err(errors::call),
-        ))
+    fn call_many(
+        &self,
+        transactions: Vec<BundleTransaction>,
+        num: BlockNumber,
+        block_override: Trailing<BlockOverride>,
+    ) -> BoxFuture<Vec<CallBundleResult>>
+    {
+        self.call_bundle(transactions, num, block_override)
     }
 
     fn compile_lll(&self, _: String) -> Result<Bytes> {
@@ -796,6 +1251,7 @@ where
                     highest_block_number: node.highest_block_number,
                     id: node.id,
                     ip: node.ip,
+                    reputation: node.reputation,
                 }
             })
             .collect()
@@ -821,6 +1277,12 @@ where
             .map(|r| r.into())
     }
 
+    fn block_receipts(&self, id: BlockNumberOrHash) -> Option<Vec<Receipt>> {
+        self.client
+            .localized_block_receipts(id.into())
+            .map(|receipts| receipts.into_iter().map(Into::into).collect())
+    }
+
     fn block_receipt(&self, number: i64) -> Vec<SimpleReceipt> {
         let decode = |br: BlockReceipts| {
             br.receipts
@@ -885,3 +1347,120 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use acore::client::TestBlockChainClient;
+    use acore::miner::external::ExternalMiner;
+    use aion_types::U256;
+    use jsonrpc_core::IoHandler;
+    use serde_json::{self, Value};
+
+    use helpers::test_sync::TestSyncProvider;
+    use limits::CallLimits;
+    use super::EthClient;
+
+    fn io() -> IoHandler { io_with(Arc::new(TestBlockChainClient::new())) }
+
+    fn io_with(client: Arc<TestBlockChainClient>) -> IoHandler {
+        let eth = EthClient::new(
+            &client,
+            &Arc::new(TestSyncProvider::new()),
+            &None,
+            &client.miner,
+            &Arc::new(ExternalMiner::default()),
+            None,
+            CallLimits::default(),
+            None,
+        );
+        let mut io = IoHandler::new();
+        io.extend_with(eth.to_delegate_with_state_override());
+        io
+    }
+
+    fn request(method: &str, params: &str) -> String {
+        format!(
+            r#"{{"jsonrpc":"2.0","method":"{}","params":{},"id":1}}"#,
+            method, params
+        )
+    }
+
+    #[test]
+    fn estimates_gas_of_a_call_request_alone() {
+        let response = io().handle_request_sync(&request("eth_estimateGas", r#"[{}]"#));
+        assert_eq!(
+            response,
+            Some(r#"{"jsonrpc":"2.0","result":"0x5208","id":1}"#.to_owned())
+        );
+    }
+
+    #[test]
+    fn estimates_gas_with_block_and_state_override() {
+        let io = io();
+        for params in &[r#"[{}, "latest"]"#, r#"[{}, "latest", {}]"#] {
+            let response = io.handle_request_sync(&request("eth_estimateGas", params));
+            assert_eq!(
+                response,
+                Some(r#"{"jsonrpc":"2.0","result":"0x5208","id":1}"#.to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_calls_without_request_or_with_extra_params() {
+        let io = io();
+        for params in &["[]", r#"[{}, "latest", {}, {}]"#] {
+            let response = io.handle_request_sync(&request("eth_call", params)).unwrap();
+            assert!(response.contains(r#""code":-32602"#), response);
+        }
+    }
+
+    #[test]
+    fn reports_fee_history_of_a_block_range() {
+        let client = Arc::new(TestBlockChainClient::new());
+        fn prices(prices: &[u64]) -> Vec<U256> { prices.iter().map(|p| U256::from(*p)).collect() }
+        client.add_block_with_gas_prices(500_000.into(), &prices(&[30, 10, 20]));
+        client.add_block_with_gas_prices(250_000.into(), &[]);
+        client.add_block_with_gas_prices(1_000_000.into(), &prices(&[40]));
+        let io = io_with(client);
+        let result = |params: &str| -> Value {
+            let response = io
+                .handle_request_sync(&request("eth_feeHistory", params))
+                .unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()["result"].clone()
+        };
+        let expected = |json: &str| serde_json::from_str::<Value>(json).unwrap();
+
+        assert_eq!(
+            result(r#"["0x3", "latest", [0, 50, 100]]"#),
+            expected(
+                r#"{
+                    "oldestBlock": "0x1",
+                    "gasUsedRatio": [0.5, 0.25, 1.0],
+                    "gasPrice": [
+                        ["0xa", "0x14", "0x1e"],
+                        ["0x0", "0x0", "0x0"],
+                        ["0x28", "0x28", "0x28"]
+                    ]
+                }"#
+            )
+        );
+        assert_eq!(
+            result(r#"["0x2", "0x2"]"#),
+            expected(r#"{"oldestBlock": "0x1", "gasUsedRatio": [0.5, 0.25]}"#)
+        );
+        // the range stops at the genesis block.
+        assert_eq!(
+            result(r#"["0x10", "latest", [25]]"#),
+            expected(
+                r#"{
+                    "oldestBlock": "0x0",
+                    "gasUsedRatio": [0.0, 0.5, 0.25, 1.0],
+                    "gasPrice": [["0x0"], ["0xa"], ["0x0"], ["0x28"]]
+                }"#
+            )
+        );
+    }
+}
@@ -22,6 +22,7 @@
 
 //! Eth Filter RPC implementation
 
+use std::cmp;
 use std::sync::Arc;
 use std::collections::HashSet;
 
@@ -38,6 +39,7 @@ use traits::EthFilter;
 use types::{BlockNumber, Index, Filter, FilterChanges, Log, H256 as RpcH256, U256 as RpcU256};
 use helpers::{errors, PollFilter, PollManager, limit_logs};
 use impls::eth::pending_logs;
+use limits::CallLimits;
 
 /// Something which provides data that can be filtered over.
 pub trait Filterable {
@@ -56,6 +58,12 @@ pub trait Filterable {
     /// Get logs that match the given filter.
     fn logs(&self, filter: EthcoreFilter) -> BoxFuture<Vec<Log>>;
 
+    /// Get logs of the block with given hash, even if it is not canonical any more.
+    fn block_logs(&self, hash: H256, filter: &EthcoreFilter) -> Vec<Log>;
+
+    /// Get the parent hash of the block with given hash.
+    fn parent_hash(&self, hash: H256) -> Option<H256>;
+
     /// Get logs from the pending block.
     fn pending_logs(&self, block_number: u64, filter: &EthcoreFilter) -> Vec<Log>;
 
@@ -72,6 +80,7 @@ where
     client: Arc<C>,
     miner: Arc<M>,
     polls: Mutex<PollManager<PollFilter>>,
+    limits: CallLimits,
 }
 
 impl<C, M> EthFilterClient<C, M>
@@ -80,11 +89,12 @@ where
     M: MinerService,
 {
     /// Creates new Eth filter client.
-    pub fn new(client: Arc<C>, miner: Arc<M>) -> Self {
+    pub fn new(client: Arc<C>, miner: Arc<M>, limits: CallLimits) -> Self {
         EthFilterClient {
             client: client,
             miner: miner,
             polls: Mutex::new(PollManager::new()),
+            limits: limits,
         }
     }
 }
@@ -106,14 +116,31 @@ where
         self.miner.pending_transactions_hashes(best)
     }
 
-    fn logs(&self, filter: EthcoreFilter) -> BoxFuture<Vec<Log>> {
-        Box::new(future::ok(
-            self.client
-                .logs(filter)
-                .into_iter()
-                .map(Into::into)
-                .collect(),
-        ))
+    fn logs(&self, mut filter: EthcoreFilter) -> BoxFuture<Vec<Log>> {
+        try_bf!(self.limits.check_logs_filter(&*self.client, &filter));
+        self.limits.cap_logs_filter(&mut filter);
+        let logs = self
+            .client
+            .logs(filter)
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<Log>>();
+        try_bf!(self.limits.check_logs_count(logs.len()));
+        Box::new(future::ok(logs))
+    }
+
+    fn block_logs(&self, hash: H256, filter: &EthcoreFilter) -> Vec<Log> {
+        self.client
+            .block_logs(&hash, filter)
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    fn parent_hash(&self, hash: H256) -> Option<H256> {
+        self.client
+            .block_header(BlockId::Hash(hash))
+            .map(|header| header.parent_hash())
     }
 
     fn pending_logs(&self, block_number: u64, filter: &EthcoreFilter) -> Vec<Log> {
@@ -123,6 +150,24 @@ where
     fn polls(&self) -> &Mutex<PollManager<PollFilter>> { &self.polls }
 }
 
+/// Walks back from the last block a filter was notified about (`number`, `hash`) until the
+/// canon chain is met. Returns the number of the common ancestor and the hashes of retracted
+/// blocks, newest first.
+fn retracted_blocks<T: Filterable>(filterable: &T, number: u64, hash: H256) -> (u64, Vec<H256>) {
+    let mut number = number;
+    let mut hash = hash;
+    let mut retracted = Vec::new();
+    while number > 0 && filterable.block_hash(BlockId::Number(number)) != Some(hash.into()) {
+        retracted.push(hash);
+        hash = match filterable.parent_hash(hash) {
+            Some(parent) => parent,
+            None => break,
+        };
+        number -= 1;
+    }
+    (number, retracted)
+}
+
 impl<T: Filterable + Send + Sync + 'static> EthFilter for T {
     fn new_filter(&self, filter: Filter) -> Result<RpcU256> {
         let mut polls = self.polls().lock();
@@ -133,8 +178,12 @@ impl<T: Filterable + Send + Sync + 'static> EthFilter for T {
                 .map_or_else(|| BlockId::Latest, Into::into),
         ) {
             Some(block_number) => {
-                let id =
-                    polls.create_poll(PollFilter::Logs(block_number, Default::default(), filter));
+                let id = polls.create_poll(PollFilter::Logs(
+                    block_number,
+                    None,
+                    Default::default(),
+                    filter,
+                ));
                 Ok(id.into())
             }
             None => Err(errors::filter("Get block number from input value failed.")),
@@ -199,16 +248,51 @@ impl<T: Filterable + Send + Sync + 'static> EthFilter for T {
                         // return new hashes
                         Either::A(future::ok(FilterChanges::Hashes(new_hashes)))
                     }
-                    PollFilter::Logs(ref mut block_number, ref mut previous_logs, ref filter) => {
-                        // retrive the current block number
-                        let current_number = self.best_block_number();
+                    PollFilter::Logs(
+                        ref mut block_number,
+                        ref mut last_hash,
+                        ref mut previous_logs,
+                        ref filter,
+                    ) => {
+                        // retrive the current block number, leaving out unconfirmed blocks
+                        let confirmations = filter.confirmations.unwrap_or(0);
+                        let current_number = self
+                            .best_block_number()
+                            .saturating_sub(confirmations);
 
                         // check if we need to check pending hashes
                         let include_pending = filter.to_block == Some(BlockNumber::Pending);
 
                         // build appropriate filter
                         let mut filter: EthcoreFilter = filter.clone().into();
+
+                        // blocks the client was notified about which are no longer canonical
+                        let mut removed = Vec::new();
+                        if let Some(hash) = *last_hash {
+                            let (ancestor, retracted) =
+                                retracted_blocks(self, *block_number - 1, hash);
+                            for hash in retracted {
+                                let mut logs = self.block_logs(hash, &filter);
+                                logs.reverse();
+                                removed.extend(logs.into_iter().map(|mut log| {
+                                    log.log_type = "removed".into();
+                                    log
+                                }));
+                            }
+                            if ancestor + 1 < *block_number {
+                                *block_number = ancestor + 1;
+                                *last_hash = self
+                                    .block_hash(BlockId::Number(ancestor))
+                                    .map(Into::into);
+                            }
+                        }
                         filter.from_block = BlockId::Number(*block_number);
+                        if !include_pending {
+                            let to_block = self
+                                .block_number(filter.to_block.clone())
+                                .map_or(current_number, |n| cmp::min(n, current_number));
+                            filter.to_block = BlockId::Number(to_block);
+                        }
 
                         // retrieve pending logs
                         let pending = if include_pending {
@@ -229,21 +313,34 @@ impl<T: Filterable + Send + Sync + 'static> EthFilter for T {
                             Vec::new()
                         };
 
-                        // save the number of the next block as a first block from which
-                        // we want to get logs
-                        *block_number = current_number + 1;
-
-                        // retrieve logs in range from_block..min(BlockId::Latest..to_block)
-                        let limit = filter.limit;
-                        Either::B(
-                            self.logs(filter)
-                                .map(move |mut logs| {
-                                    logs.extend(pending);
-                                    logs
-                                }) // append fetched pending logs
-                                .map(move |logs| limit_logs(logs, limit)) // limit the logs
-                                .map(FilterChanges::Logs),
-                        )
+                        // nothing new got confirmed since the last poll
+                        if current_number < *block_number {
+                            removed.extend(pending);
+                            Either::A(future::ok(FilterChanges::Logs(removed)))
+                        } else {
+                            // save the number of the next block as a first block from which
+                            // we want to get logs
+                            *block_number = current_number + 1;
+                            *last_hash = self
+                                .block_hash(BlockId::Number(current_number))
+                                .map(Into::into);
+
+                            // retrieve logs in range from_block..min(BlockId::Latest..to_block)
+                            let limit = filter.limit;
+                            Either::B(
+                                self.logs(filter)
+                                    .map(move |mut logs| {
+                                        logs.extend(pending);
+                                        logs
+                                    }) // append fetched pending logs
+                                    .map(move |logs| limit_logs(logs, limit)) // limit the logs
+                                    .map(move |logs| {
+                                        removed.extend(logs);
+                                        removed
+                                    }) // removed logs go first
+                                    .map(FilterChanges::Logs),
+                            )
+                        }
                     }
                 }
             }
@@ -255,7 +352,7 @@ impl<T: Filterable + Send + Sync + 'static> EthFilter for T {
             let mut polls = self.polls().lock();
 
             match polls.poll(&index.value()) {
-                Some(&PollFilter::Logs(ref _block_number, ref _previous_log, ref filter)) => {
+                Some(&PollFilter::Logs(_, _, ref _previous_log, ref filter)) => {
                     filter.clone()
                 }
                 // just empty array
@@ -291,3 +388,97 @@ impl<T: Filterable + Send + Sync + 'static> EthFilter for T {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use acore::client::{BlockChainClient, BlockId, EachBlockWith, TestBlockChainClient};
+    use acore::log_entry::LocalizedLogEntry;
+    use acore::miner::Miner;
+    use jsonrpc_core::futures::Future;
+    use serde_json;
+
+    use limits::CallLimits;
+    use traits::EthFilter;
+    use types::{BlockNumber, Filter, FilterChanges, Index, Log, U256 as RpcU256};
+    use super::EthFilterClient;
+
+    type Filters = EthFilterClient<TestBlockChainClient, Miner>;
+
+    fn filters(client: &Arc<TestBlockChainClient>) -> Filters {
+        EthFilterClient::new(client.clone(), client.miner.clone(), CallLimits::default())
+    }
+
+    fn filter(confirmations: Option<u64>) -> Filter {
+        Filter {
+            from_block: Some(BlockNumber::Num(1)),
+            to_block: None,
+            address: None,
+            topics: None,
+            limit: None,
+            confirmations: confirmations,
+        }
+    }
+
+    // a log of the canon block at `number`.
+    fn log(client: &TestBlockChainClient, number: u64) -> LocalizedLogEntry {
+        LocalizedLogEntry {
+            block_hash: client.block_hash(BlockId::Number(number)).unwrap(),
+            block_number: number,
+            ..Default::default()
+        }
+    }
+
+    fn mined(entry: &LocalizedLogEntry) -> Log { Log::from(entry.clone()) }
+
+    fn removed(entry: &LocalizedLogEntry) -> Log {
+        let mut log = Log::from(entry.clone());
+        log.log_type = "removed".into();
+        log
+    }
+
+    fn changes(filters: &Filters, id: &RpcU256) -> Vec<Log> {
+        let index: Index = serde_json::from_value(serde_json::to_value(id).unwrap()).unwrap();
+        match filters.filter_changes(index).wait().unwrap() {
+            FilterChanges::Logs(logs) => logs,
+            changes => panic!("Unexpected filter changes {:?}", changes),
+        }
+    }
+
+    #[test]
+    fn returns_logs_of_retracted_blocks_as_removed() {
+        let client = Arc::new(TestBlockChainClient::new());
+        client.add_blocks(3, EachBlockWith::Nothing);
+        let (second, third) = (log(&client, 2), log(&client, 3));
+        client.set_logs(vec![second.clone(), third.clone()]);
+        let filters = filters(&client);
+        let id = filters.new_filter(filter(None)).unwrap();
+        assert_eq!(changes(&filters, &id), vec![mined(&second), mined(&third)]);
+
+        // blocks 2 and 3 get replaced by a longer fork.
+        client.add_fork(1, 3);
+        let forked = log(&client, 3);
+        client.set_logs(vec![second.clone(), third.clone(), forked.clone()]);
+        assert_eq!(
+            changes(&filters, &id),
+            vec![removed(&third), removed(&second), mined(&forked)]
+        );
+        assert_eq!(changes(&filters, &id), vec![]);
+    }
+
+    #[test]
+    fn holds_back_logs_until_confirmed() {
+        let client = Arc::new(TestBlockChainClient::new());
+        client.add_blocks(3, EachBlockWith::Nothing);
+        let logs = (1..4).map(|number| log(&client, number)).collect::<Vec<_>>();
+        client.set_logs(logs.clone());
+        let filters = filters(&client);
+        let id = filters.new_filter(filter(Some(2))).unwrap();
+
+        assert_eq!(changes(&filters, &id), vec![mined(&logs[0])]);
+        assert_eq!(changes(&filters, &id), vec![]);
+        client.add_blocks(1, EachBlockWith::Nothing);
+        assert_eq!(changes(&filters, &id), vec![mined(&logs[1])]);
+    }
+}
@@ -22,16 +22,20 @@
 
 //! Eth PUB-SUB rpc implementation.
 
+use std::cmp;
+use std::mem;
 use std::sync::{Arc, Weak};
 use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
 
-use jsonrpc_core::{Result, Error};
-use jsonrpc_core::futures::{self, Future, IntoFuture};
+use jsonrpc_core::Result;
+use jsonrpc_core::futures::{Future, Stream};
 use jsonrpc_macros::Trailing;
 use jsonrpc_macros::pubsub::{Sink, Subscriber};
 use jsonrpc_pubsub::SubscriptionId;
 
 use helpers::{errors, limit_logs, Subscribers};
+use helpers::block_import::sync_info;
 use metadata::Metadata;
 use traits::EthPubSub;
 use types::{pubsub, Log};
@@ -39,49 +43,64 @@ use types::{pubsub, Log};
 use acore::encoded;
 use acore::filter::Filter as EthFilter;
 use acore::client::{BlockChainClient, ChainNotify, BlockId};
+use sync::sync::SyncProvider;
 use tokio::runtime::TaskExecutor;
+use tokio::timer::Interval;
 use aion_types::H256;
 use bytes::Bytes;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
 type Client = Sink<pubsub::Result>;
 
+/// Interval of progress notifications to `syncing` subscribers while syncing, in seconds.
+const SYNCING_NOTIFY_INTERVAL: u64 = 5;
+
 /// Eth PubSub implementation.
 pub struct EthPubSubClient<C> {
     handler: Arc<ChainNotificationHandler<C>>,
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
-    logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
+    logs_subscribers: Arc<RwLock<Subscribers<(Client, (EthFilter, u64))>>>,
     transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    syncing_subscribers: Arc<RwLock<Subscribers<Client>>>,
 }
 
-impl<C> EthPubSubClient<C> {
+impl<C: BlockChainClient + 'static> EthPubSubClient<C> {
     /// Creates new `EthPubSubClient`.
-    pub fn new(client: Arc<C>, executor: TaskExecutor) -> Self {
+    pub fn new(client: Arc<C>, sync: Arc<SyncProvider>, executor: TaskExecutor) -> Self {
         let heads_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let logs_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let transactions_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let syncing_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+
+        let handler = Arc::new(ChainNotificationHandler {
+            client,
+            sync,
+            executor,
+            heads_subscribers: heads_subscribers.clone(),
+            logs_subscribers: logs_subscribers.clone(),
+            transactions_subscribers: transactions_subscribers.clone(),
+            syncing_subscribers: syncing_subscribers.clone(),
+            syncing: Mutex::new(false),
+            syncing_task: Mutex::new(false),
+        });
 
         EthPubSubClient {
-            handler: Arc::new(ChainNotificationHandler {
-                client,
-                executor,
-                heads_subscribers: heads_subscribers.clone(),
-                logs_subscribers: logs_subscribers.clone(),
-                transactions_subscribers: transactions_subscribers.clone(),
-            }),
+            handler,
             heads_subscribers,
             logs_subscribers,
             transactions_subscribers,
+            syncing_subscribers,
         }
     }
 
     /// Creates new `EthPubSubCient` with deterministic subscription ids.
     #[cfg(test)]
-    pub fn new_test(client: Arc<C>, executor: TaskExecutor) -> Self {
-        let client = Self::new(client, executor);
+    pub fn new_test(client: Arc<C>, sync: Arc<SyncProvider>, executor: TaskExecutor) -> Self {
+        let client = Self::new(client, sync, executor);
         *client.heads_subscribers.write() = Subscribers::new_test();
         *client.logs_subscribers.write() = Subscribers::new_test();
         *client.transactions_subscribers.write() = Subscribers::new_test();
+        *client.syncing_subscribers.write() = Subscribers::new_test();
         client
     }
 
@@ -92,10 +111,16 @@ impl<C> EthPubSubClient<C> {
 /// PubSub Notification handler.
 pub struct ChainNotificationHandler<C> {
     client: Arc<C>,
+    sync: Arc<SyncProvider>,
     executor: TaskExecutor,
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
-    logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
+    logs_subscribers: Arc<RwLock<Subscribers<(Client, (EthFilter, u64))>>>,
     transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    syncing_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    /// Whether `syncing` subscribers were last told the node is syncing.
+    syncing: Mutex<bool>,
+    /// Whether the task telling `syncing` subscribers about the progress runs.
+    syncing_task: Mutex<bool>,
 }
 
 impl<C> ChainNotificationHandler<C> {
@@ -121,40 +146,6 @@ impl<C> ChainNotificationHandler<C> {
         }
     }
 
-    fn notify_logs<F, T>(&self, enacted: &[H256], logs: F)
-    where
-        F: Fn(EthFilter) -> T,
-        T: IntoFuture<Item = Vec<Log>, Error = Error>,
-        T::Future: Send + 'static,
-    {
-        for &(ref subscriber, ref filter) in self.logs_subscribers.read().values() {
-            let logs = futures::future::join_all(
-                enacted
-                    .iter()
-                    .map(|hash| {
-                        let mut filter = filter.clone();
-                        filter.from_block = BlockId::Hash(*hash);
-                        filter.to_block = filter.from_block.clone();
-                        logs(filter).into_future()
-                    })
-                    .collect::<Vec<_>>(),
-            );
-            let limit = filter.limit;
-            let executor = self.executor.clone();
-            let subscriber = subscriber.clone();
-            self.executor.spawn(
-                logs.map(move |logs| {
-                    let logs = logs.into_iter().flat_map(|log| log).collect();
-
-                    for log in limit_logs(logs, limit) {
-                        Self::notify(&executor, &subscriber, pubsub::Result::Log(log))
-                    }
-                })
-                .map_err(|e| warn!(target:"rpc","Unable to fetch latest logs: {:?}", e)),
-            );
-        }
-    }
-
     /// Notify all subscribers about new transaction hashes.
     pub fn new_transactions(&self, hashes: &[H256]) {
         for subscriber in self.transactions_subscribers.read().values() {
@@ -169,6 +160,148 @@ impl<C> ChainNotificationHandler<C> {
     }
 }
 
+impl<C: BlockChainClient + 'static> ChainNotificationHandler<C> {
+    /// Start telling `syncing` subscribers about the progress while syncing, unless already
+    /// done. The task ends once the last of them leaves, or together with the handler.
+    fn start_syncing_task(handler: &Arc<Self>) {
+        {
+            let mut running = handler.syncing_task.lock();
+            if *running {
+                return;
+            }
+            *running = true;
+        }
+
+        let weak = Arc::downgrade(handler);
+        let syncing_task = Interval::new(
+            Instant::now(),
+            Duration::from_secs(SYNCING_NOTIFY_INTERVAL),
+        )
+        .map_err(|e| error!(target: "rpc", "interval errored; err={:?}", e))
+        .for_each(move |_| {
+            let handler = weak.upgrade().ok_or(())?;
+            {
+                // checked with the flag held, so a subscriber coming meanwhile starts a new task
+                let mut running = handler.syncing_task.lock();
+                if handler.syncing_subscribers.read().is_empty() {
+                    *running = false;
+                    return Err(());
+                }
+            }
+            handler.notify_syncing(true);
+            Ok(())
+        });
+        handler.executor.spawn(syncing_task);
+    }
+}
+
+impl<C: BlockChainClient> ChainNotificationHandler<C> {
+    /// Pairs known blocks with their numbers, lowest number first.
+    fn numbered(&self, hashes: &[H256]) -> Vec<(u64, H256)> {
+        let mut blocks = hashes
+            .iter()
+            .filter_map(|hash| {
+                self.client
+                    .block_number(BlockId::Hash(*hash))
+                    .map(|number| (number, *hash))
+            })
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|&(number, _)| number);
+        blocks
+    }
+
+    /// Blocks whose logs a subscriber waiting for `confirmations` blocks has to be notified
+    /// about, flagged with `true` when the logs were removed. Blocks the subscriber has seen
+    /// and which got retracted come first, newest first, followed by newly confirmed blocks.
+    fn confirmed_blocks(
+        &self,
+        enacted: &[(u64, H256)],
+        retracted: &[(u64, H256)],
+        confirmations: u64,
+    ) -> Vec<(H256, bool)>
+    {
+        let ancestor = match (enacted.first(), retracted.first()) {
+            (Some(&(e, _)), Some(&(r, _))) => cmp::min(e, r) - 1,
+            (Some(&(n, _)), None) | (None, Some(&(n, _))) => n - 1,
+            (None, None) => return Vec::new(),
+        };
+        let old_best = retracted.last().map_or(ancestor, |&(number, _)| number);
+        let new_best = enacted.last().map_or(ancestor, |&(number, _)| number);
+
+        // a block at `number` was seen once `number + confirmations <= old_best`
+        let mut blocks = retracted
+            .iter()
+            .rev()
+            .filter(|&&(number, _)| number + confirmations <= old_best)
+            .map(|&(_, hash)| (hash, true))
+            .collect::<Vec<_>>();
+
+        let first = cmp::min(ancestor + 1, (old_best + 1).saturating_sub(confirmations));
+        if new_best >= confirmations {
+            for number in first..=new_best - confirmations {
+                let hash = if number > ancestor {
+                    enacted
+                        .iter()
+                        .find(|&&(n, _)| n == number)
+                        .map(|&(_, hash)| hash)
+                } else {
+                    self.client.block_hash(BlockId::Number(number))
+                };
+                blocks.extend(hash.map(|hash| (hash, false)));
+            }
+        }
+        blocks
+    }
+
+    /// Notify `syncing` subscribers when syncing starts or stops and, with `progress`, about
+    /// the progress made while syncing.
+    fn notify_syncing(&self, progress: bool) {
+        let best_block = self.client.chain_info().best_block_number;
+        let queue_info = self.client.queue_info();
+        let status = sync_info(&self.sync.status(), best_block, &queue_info);
+        let syncing = status.is_some();
+        let changed = mem::replace(&mut *self.syncing.lock(), syncing) != syncing;
+        if !changed && !(progress && syncing) {
+            return;
+        }
+
+        let result = pubsub::Result::SyncState(pubsub::PubSubSyncStatus {
+            syncing,
+            status,
+        });
+        for subscriber in self.syncing_subscribers.read().values() {
+            Self::notify(&self.executor, subscriber, result.clone());
+        }
+    }
+
+    fn notify_logs(&self, enacted: &[H256], retracted: &[H256]) {
+        let enacted = self.numbered(enacted);
+        let retracted = self.numbered(retracted);
+
+        for &(ref subscriber, (ref filter, confirmations)) in self.logs_subscribers.read().values()
+        {
+            let mut removed = Vec::new();
+            let mut logs = Vec::new();
+            for (hash, is_removed) in self.confirmed_blocks(&enacted, &retracted, confirmations) {
+                let block_logs = self.client.block_logs(&hash, filter).into_iter();
+                if is_removed {
+                    removed.extend(block_logs.rev().map(|entry| {
+                        let mut log = Log::from(entry);
+                        log.log_type = "removed".into();
+                        log
+                    }));
+                } else {
+                    logs.extend(block_logs.map(Log::from));
+                }
+            }
+
+            for log in removed.into_iter().chain(limit_logs(logs, filter.limit)) {
+                Self::notify(&self.executor, subscriber, pubsub::Result::Log(log));
+            }
+        }
+    }
+}
+
 impl<C: BlockChainClient> ChainNotify for ChainNotificationHandler<C> {
     fn new_blocks(
         &self,
@@ -202,33 +335,15 @@ impl<C: BlockChainClient> ChainNotify for ChainNotificationHandler<C> {
         // Headers
         self.notify_heads(&headers);
 
-        // Enacted logs
-        self.notify_logs(&enacted, |filter| {
-            Ok(self
-                .client
-                .logs(filter)
-                .into_iter()
-                .map(Into::into)
-                .collect())
-        });
+        // Logs
+        self.notify_logs(&enacted, &retracted);
 
-        // Retracted logs
-        self.notify_logs(&retracted, |filter| {
-            Ok(self
-                .client
-                .logs(filter)
-                .into_iter()
-                .map(Into::into)
-                .map(|mut log: Log| {
-                    log.log_type = "removed".into();
-                    log
-                })
-                .collect())
-        });
+        // Sync state transitions
+        self.notify_syncing(false);
     }
 }
 
-impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
+impl<C: BlockChainClient + 'static> EthPubSub for EthPubSubClient<C> {
     type Metadata = Metadata;
 
     fn subscribe(
@@ -248,9 +363,10 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
                 errors::invalid_params("newHeads", "Expected no parameters.")
             }
             (pubsub::Kind::Logs, Some(pubsub::Params::Logs(filter))) => {
+                let confirmations = filter.confirmations.unwrap_or(0);
                 self.logs_subscribers
                     .write()
-                    .push(subscriber, filter.into());
+                    .push(subscriber, (filter.into(), confirmations));
                 return;
             }
             (pubsub::Kind::Logs, _) => errors::invalid_params("logs", "Expected a filter object."),
@@ -261,7 +377,14 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
             (pubsub::Kind::NewPendingTransactions, _) => {
                 errors::invalid_params("newPendingTransactions", "Expected no parameters.")
             }
-            _ => errors::unimplemented(None),
+            (pubsub::Kind::Syncing, None) => {
+                self.syncing_subscribers.write().push(subscriber);
+                ChainNotificationHandler::start_syncing_task(&self.handler);
+                return;
+            }
+            (pubsub::Kind::Syncing, _) => {
+                errors::invalid_params("syncing", "Expected no parameters.")
+            }
         };
 
         let _ = subscriber.reject(error);
@@ -271,7 +394,180 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
         let res = self.heads_subscribers.write().remove(&id).is_some();
         let res2 = self.logs_subscribers.write().remove(&id).is_some();
         let res3 = self.transactions_subscribers.write().remove(&id).is_some();
+        let res4 = self.syncing_subscribers.write().remove(&id).is_some();
+
+        Ok(res || res2 || res3 || res4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
 
-        Ok(res || res2 || res3)
+    use acore::client::{
+        BlockChainClient, BlockId, ChainNotify, EachBlockWith, TestBlockChainClient,
+    };
+    use acore::log_entry::LocalizedLogEntry;
+    use aion_types::H256;
+    use jsonrpc_core::MetaIoHandler;
+    use jsonrpc_core::futures::Stream;
+    use jsonrpc_core::futures::stream::Wait;
+    use jsonrpc_core::futures::sync::mpsc;
+    use jsonrpc_pubsub::Session;
+    use serde_json::{self, Value};
+    use sync::sync::SyncProvider;
+    use sync::sync::storage::SyncState;
+    use tokio::runtime::{self, Runtime};
+
+    use helpers::test_sync::TestSyncProvider;
+    use metadata::Metadata;
+    use traits::EthPubSub;
+    use types::Log;
+    use super::{ChainNotificationHandler, EthPubSubClient};
+
+    /// A subscription over an in-memory session.
+    struct Subscription {
+        handler: Arc<ChainNotificationHandler<TestBlockChainClient>>,
+        notifications: Wait<mpsc::Receiver<String>>,
+        // dropping the session ends the subscription.
+        _metadata: Metadata,
+        _io: MetaIoHandler<Metadata>,
+        _runtime: Runtime,
+    }
+
+    impl Subscription {
+        /// Subscribe with the given `eth_subscribe` params.
+        fn new(
+            client: Arc<TestBlockChainClient>,
+            sync: Arc<TestSyncProvider>,
+            params: &str,
+        ) -> Self
+        {
+            let runtime = runtime::Builder::new()
+                .core_threads(1)
+                .build()
+                .expect("runtime build error");
+            let pubsub = EthPubSubClient::new_test(client, sync, runtime.executor());
+            let handler = pubsub.handler().upgrade().unwrap();
+            let mut io = MetaIoHandler::default();
+            io.extend_with(pubsub.to_delegate());
+
+            let mut metadata = Metadata::default();
+            let (sender, receiver) = mpsc::channel(8);
+            metadata.session = Some(Arc::new(Session::new(sender)));
+            let request = format!(
+                r#"{{"jsonrpc":"2.0","method":"eth_subscribe","params":{},"id":1}}"#,
+                params
+            );
+            let response = io.handle_request_sync(&request, metadata.clone()).unwrap();
+            assert!(response.contains("result"), response);
+
+            Subscription {
+                handler: handler,
+                notifications: receiver.wait(),
+                _metadata: metadata,
+                _io: io,
+                _runtime: runtime,
+            }
+        }
+
+        fn logs(client: Arc<TestBlockChainClient>, confirmations: u64) -> Self {
+            let params = format!(r#"["logs",{{"confirmations":{}}}]"#, confirmations);
+            Self::new(client, Arc::new(TestSyncProvider::new()), &params)
+        }
+
+        fn new_blocks(&self, enacted: Vec<H256>, retracted: Vec<H256>) {
+            self.handler
+                .new_blocks(vec![], vec![], enacted, retracted, vec![], vec![], 0);
+        }
+
+        fn next_result(&mut self) -> Value {
+            let notification = self
+                .notifications
+                .next()
+                .expect("notification")
+                .expect("notification");
+            let notification: Value = serde_json::from_str(&notification).unwrap();
+            notification["params"]["result"].clone()
+        }
+    }
+
+    // a log of the canon block at `number`.
+    fn entry(client: &TestBlockChainClient, number: u64) -> LocalizedLogEntry {
+        LocalizedLogEntry {
+            block_hash: client.block_hash(BlockId::Number(number)).unwrap(),
+            block_number: number,
+            ..Default::default()
+        }
+    }
+
+    fn log(entry: &LocalizedLogEntry, removed: bool) -> Value {
+        let mut log = Log::from(entry.clone());
+        if removed {
+            log.log_type = "removed".into();
+        }
+        serde_json::to_value(log).unwrap()
+    }
+
+    #[test]
+    fn notifies_logs_of_retracted_blocks_as_removed() {
+        let client = Arc::new(TestBlockChainClient::new());
+        client.add_blocks(3, EachBlockWith::Nothing);
+        let (second, third) = (entry(&client, 2), entry(&client, 3));
+        let mut subscription = Subscription::logs(client.clone(), 0);
+
+        // blocks 2 and 3 get replaced by a longer fork.
+        let (enacted, retracted) = client.add_fork(1, 3);
+        let forked = entry(&client, 3);
+        client.set_logs(vec![second.clone(), third.clone(), forked.clone()]);
+        subscription.new_blocks(enacted, retracted);
+
+        assert_eq!(subscription.next_result(), log(&third, true));
+        assert_eq!(subscription.next_result(), log(&second, true));
+        assert_eq!(subscription.next_result(), log(&forked, false));
+    }
+
+    #[test]
+    fn notifies_logs_once_confirmed() {
+        let client = Arc::new(TestBlockChainClient::new());
+        client.add_blocks(3, EachBlockWith::Nothing);
+        let mut subscription = Subscription::logs(client.clone(), 2);
+        client.add_blocks(2, EachBlockWith::Nothing);
+        let entries = (1..6).map(|number| entry(&client, number)).collect::<Vec<_>>();
+        client.set_logs(entries.clone());
+
+        // block 4 confirms block 2 and block 5 confirms block 3.
+        subscription.new_blocks(vec![entries[3].block_hash], vec![]);
+        subscription.new_blocks(vec![entries[4].block_hash], vec![]);
+        assert_eq!(subscription.next_result(), log(&entries[1], false));
+        assert_eq!(subscription.next_result(), log(&entries[2], false));
+    }
+
+    #[test]
+    fn notifies_syncing_when_it_starts_and_stops() {
+        let client = Arc::new(TestBlockChainClient::new());
+        let sync = Arc::new(TestSyncProvider::new());
+        let logs = Subscription::logs(client.clone(), 0);
+        assert!(!*logs.handler.syncing_task.lock());
+        let mut subscription = Subscription::new(client, sync.clone(), r#"["syncing"]"#);
+        assert!(*subscription.handler.syncing_task.lock());
+
+        let mut status = sync.status();
+        status.state = SyncState::Blocks;
+        status.highest_block_number = Some(100);
+        sync.set_status(status);
+        subscription.new_blocks(vec![], vec![]);
+        let result = subscription.next_result();
+        assert_eq!(result["syncing"], Value::Bool(true));
+        assert_eq!(result["status"]["highestBlock"], Value::String("0x64".into()));
+
+        status.state = SyncState::Idle;
+        status.highest_block_number = None;
+        sync.set_status(status);
+        subscription.new_blocks(vec![], vec![]);
+        assert_eq!(
+            subscription.next_result(),
+            serde_json::from_str::<Value>(r#"{"syncing":false}"#).unwrap()
+        );
     }
 }
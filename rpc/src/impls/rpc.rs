@@ -22,24 +22,29 @@
 
 //! RPC generic methods implementation.
 use std::collections::BTreeMap;
+use std::sync::Arc;
 use jsonrpc_core::Result;
+use informant::RpcStats;
 use traits::Rpc;
+use types::RpcStatsReport;
 
 /// RPC generic methods implementation.
 pub struct RpcClient {
     modules: BTreeMap<String, String>,
     valid_apis: Vec<String>,
+    stats: Arc<RpcStats>,
 }
 
 impl RpcClient {
     /// Creates new `RpcClient`.
-    pub fn new(modules: BTreeMap<String, String>) -> Self {
+    pub fn new(modules: BTreeMap<String, String>, stats: Arc<RpcStats>) -> Self {
         // geth 1.3.6 fails upon receiving unknown api
         let valid_apis = vec!["web3", "eth", "net", "personal", "rpc", "stratum"];
 
         RpcClient {
             modules: modules,
             valid_apis: valid_apis.into_iter().map(|x| x.to_owned()).collect(),
+            stats: stats,
         }
     }
 }
@@ -69,4 +74,6 @@ impl Rpc for RpcClient {
 
         Ok(modules)
     }
+
+    fn stats(&self) -> Result<RpcStatsReport> { Ok(self.stats.report()) }
 }
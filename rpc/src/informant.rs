@@ -22,17 +22,29 @@
 
 //! RPC Requests Statistics
 
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::sync::Arc;
 use std::sync::atomic::{self, AtomicUsize};
 use std::time;
+use blake2b::blake2b;
 use jsonrpc_core as rpc;
-use jsonrpc_core::futures::future::Either;
+use jsonrpc_core::futures::future::{self, Either};
 use order_stat;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use serde_json;
+
+use authorization::{Authorization, AUTHENTICATE_METHOD};
+use limits::{RequestLimiter, RequestLimits};
+use types::{Latency, MethodStats, Origin, RpcStatsReport, SlowCall};
+use Metadata;
 
 const RATE_SECONDS: usize = 10;
 const STATS_SAMPLES: usize = 60;
+const LATENCY_BUCKETS: usize = 32;
+const MAX_SLOW_CALLS: usize = 100;
 
 struct RateCalculator {
     era: time::Instant,
@@ -134,15 +146,189 @@ impl<T: Default + Copy + Ord> StatsCalculator<T> {
     }
 }
 
+/// Histogram of latencies in microseconds, each bucket twice as wide as the previous one.
+#[derive(Default, Debug)]
+struct LatencyHistogram {
+    buckets: [u64; LATENCY_BUCKETS],
+    count: u64,
+}
+
+impl LatencyHistogram {
+    pub fn add(&mut self, micros: u64) {
+        let bucket = (64 - micros.leading_zeros()) as usize;
+        self.buckets[cmp::min(bucket, LATENCY_BUCKETS - 1)] += 1;
+        self.count += 1;
+    }
+
+    /// Returns the upper bound of the bucket holding the given percentile.
+    pub fn percentile(&self, percent: u64) -> u64 {
+        let rank = (self.count * percent + 99) / 100;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank && seen > 0 {
+                return (1u64 << bucket) - 1;
+            }
+        }
+        0
+    }
+}
+
+/// Transport a call was received over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transport {
+    /// HTTP server
+    Http,
+    /// WebSockets server
+    Ws,
+    /// IPC server
+    Ipc,
+    /// Wallet (protobuf) API
+    Pb,
+    /// Unknown
+    Unknown,
+}
+
+impl<'a> From<&'a Origin> for Transport {
+    fn from(origin: &'a Origin) -> Self {
+        match *origin {
+            Origin::Rpc(_) => Transport::Http,
+            Origin::Ws { .. } => Transport::Ws,
+            Origin::Ipc(_) => Transport::Ipc,
+            Origin::Unknown => Transport::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Transport::Http => "http",
+            Transport::Ws => "ws",
+            Transport::Ipc => "ipc",
+            Transport::Pb => "pb",
+            Transport::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Default, Debug)]
+struct MethodCounters {
+    calls: u64,
+    errors: u64,
+    latency: LatencyHistogram,
+}
+
 /// RPC Statistics
 #[derive(Default, Debug)]
 pub struct RpcStats {
     requests: RwLock<RateCalculator>,
     roundtrips: RwLock<StatsCalculator<u32>>,
     active_sessions: AtomicUsize,
+    methods: Mutex<HashMap<(Transport, String), MethodCounters>>,
+    slow_calls: Mutex<VecDeque<SlowCall>>,
+    slow_call_threshold: Option<time::Duration>,
 }
 
 impl RpcStats {
+    /// Create stats logging calls which take at least `slow_call_threshold`, if given.
+    pub fn new(slow_call_threshold: Option<time::Duration>) -> Self {
+        RpcStats {
+            slow_call_threshold,
+            ..Default::default()
+        }
+    }
+
+    /// Whether slow calls are logged, and so their params should be given to `add_call`.
+    pub fn logs_slow_calls(&self) -> bool { self.slow_call_threshold.is_some() }
+
+    /// Count a call of `method` over `transport` which took `duration`. Slow calls are logged
+    /// with a digest of `params`.
+    pub fn add_call(
+        &self,
+        transport: Transport,
+        method: &str,
+        duration: time::Duration,
+        success: bool,
+        params: &[u8],
+    )
+    {
+        let micros = duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros());
+        {
+            let mut methods = self.methods.lock();
+            let counters = methods
+                .entry((transport, method.to_owned()))
+                .or_insert_with(Default::default);
+            counters.calls += 1;
+            if !success {
+                counters.errors += 1;
+            }
+            counters.latency.add(micros);
+        }
+
+        match self.slow_call_threshold {
+            Some(threshold) if duration >= threshold => {
+                let digest = blake2b(params);
+                warn!(
+                    target: "rpc",
+                    "Slow call {} over {} took {}ms, params digest: {:?}",
+                    method,
+                    transport,
+                    micros / 1_000,
+                    digest
+                );
+                let timestamp = time::SystemTime::now()
+                    .duration_since(time::UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or(0);
+                let mut slow_calls = self.slow_calls.lock();
+                if slow_calls.len() >= MAX_SLOW_CALLS {
+                    slow_calls.pop_front();
+                }
+                slow_calls.push_back(SlowCall {
+                    method: method.to_owned(),
+                    transport: transport.to_string(),
+                    params_digest: digest.into(),
+                    duration: micros,
+                    timestamp,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the statistics of all methods and the recent slow calls.
+    pub fn report(&self) -> RpcStatsReport {
+        let mut methods = self
+            .methods
+            .lock()
+            .iter()
+            .map(|(&(transport, ref method), counters)| {
+                MethodStats {
+                    method: method.clone(),
+                    transport: transport.to_string(),
+                    calls: counters.calls,
+                    errors: counters.errors,
+                    latency: Latency {
+                        p50: counters.latency.percentile(50),
+                        p90: counters.latency.percentile(90),
+                        p99: counters.latency.percentile(99),
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+        methods.sort_by(|a, b| (&a.method, &a.transport).cmp(&(&b.method, &b.transport)));
+
+        RpcStatsReport {
+            sessions: self.sessions(),
+            requests_rate: self.requests_rate(),
+            approximated_roundtrip: self.approximated_roundtrip(),
+            methods,
+            slow_calls: self.slow_calls.lock().iter().cloned().collect(),
+        }
+    }
+
     /// Count session opened
     pub fn open_session(&self) { self.active_sessions.fetch_add(1, atomic::Ordering::SeqCst); }
 
@@ -176,34 +362,103 @@ pub trait ActivityNotifier: Send + Sync + 'static {
 pub struct Middleware<T: ActivityNotifier = ClientNotifier> {
     stats: Arc<RpcStats>,
     notifier: T,
+    authorization: Option<Arc<Authorization>>,
+    /// Tokens the open IPC sessions authenticated with.
+    sessions: Arc<Mutex<HashMap<Origin, String>>>,
+    limiter: RequestLimiter,
 }
 
 impl<T: ActivityNotifier> Middleware<T> {
-    /// Create new Middleware with stats counter and activity notifier.
-    pub fn new(stats: Arc<RpcStats>, notifier: T) -> Self {
+    /// Create new Middleware with stats counter and activity notifier. Requests are only
+    /// processed if authorized when `authorization` is given, and within `limits`.
+    pub fn new(
+        stats: Arc<RpcStats>,
+        notifier: T,
+        authorization: Option<Arc<Authorization>>,
+        limits: RequestLimits,
+    ) -> Self
+    {
         Middleware {
             stats,
             notifier,
+            authorization,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            limiter: RequestLimiter::new(limits),
         }
     }
 
+    /// Answer a call to `rpc_authenticate` on an IPC session, keeping the token for the
+    /// following requests of the session until it is closed. `None` for other requests.
+    fn authenticate(
+        &self,
+        authorization: &Authorization,
+        request: &rpc::Request,
+        meta: &Metadata,
+    ) -> Option<rpc::Response>
+    {
+        let call = match *request {
+            rpc::Request::Single(rpc::Call::MethodCall(ref call))
+                if call.method == AUTHENTICATE_METHOD =>
+            {
+                call
+            }
+            _ => return None,
+        };
+        let session = match (&meta.origin, meta.session.as_ref()) {
+            (&Origin::Ipc(_), Some(session)) => session,
+            _ => return None,
+        };
+
+        let token = serde_json::to_value(&call.params)
+            .ok()
+            .and_then(|params| params.get(0).and_then(|token| token.as_str().map(Into::into)));
+        let result = match token {
+            Some(token) => authorization.verify(&token).map(|_| token),
+            None => Err(rpc::Error::invalid_params(
+                "Expected the API key or token to authenticate with.",
+            )),
+        };
+        let output = match result {
+            Ok(token) => {
+                let sessions = self.sessions.clone();
+                let origin = meta.origin.clone();
+                session.on_drop(move || {
+                    sessions.lock().remove(&origin);
+                });
+                self.sessions.lock().insert(meta.origin.clone(), token);
+                rpc::Output::from(Ok(rpc::Value::Bool(true)), call.id.clone(), call.jsonrpc)
+            }
+            Err(error) => rpc::Output::from(Err(error), call.id.clone(), call.jsonrpc),
+        };
+        Some(rpc::Response::Single(output))
+    }
+
     fn as_micro(dur: time::Duration) -> u32 {
         (dur.as_secs() * 1_000_000) as u32 + dur.subsec_nanos() / 1_000
     }
+
+    /// Serialized params of a call, only needed when slow calls are logged.
+    fn params<P: Serialize>(&self, params: &P) -> Vec<u8> {
+        if self.stats.logs_slow_calls() {
+            serde_json::to_vec(params).unwrap_or_default()
+        } else {
+            Vec::new()
+        }
+    }
 }
 
-impl<M: rpc::Metadata, T: ActivityNotifier> rpc::Middleware<M> for Middleware<T> {
+impl<T: ActivityNotifier> rpc::Middleware<Metadata> for Middleware<T> {
     type Future = rpc::FutureResponse;
-    type CallFuture = rpc::middleware::NoopCallFuture;
+    type CallFuture = Box<rpc::futures::Future<Item = Option<rpc::Output>, Error = ()> + Send>;
 
     fn on_request<F, X>(
         &self,
         request: rpc::Request,
-        meta: M,
+        meta: Metadata,
         process: F,
     ) -> Either<Self::Future, X>
     where
-        F: FnOnce(rpc::Request, M) -> X,
+        F: FnOnce(rpc::Request, Metadata) -> X,
         X: rpc::futures::Future<Item = Option<rpc::Response>, Error = ()> + Send + 'static,
     {
         let start = time::Instant::now();
@@ -211,6 +466,27 @@ impl<M: rpc::Metadata, T: ActivityNotifier> rpc::Middleware<M> for Middleware<T>
         self.notifier.active();
         self.stats.count_request();
 
+        if let Some(ref authorization) = self.authorization {
+            if let Some(response) = self.authenticate(authorization, &request, &meta) {
+                return Either::A(Box::new(future::ok(Some(response))));
+            }
+            let session_token = self.sessions.lock().get(&meta.origin).cloned();
+            let token = meta
+                .auth_token
+                .as_ref()
+                .or(session_token.as_ref())
+                .map(|token| token.as_str());
+            if let Err(response) = authorization.check(&request, token) {
+                debug!(target: "rpc", "Rejected unauthorized request from {}", meta.origin);
+                return Either::A(Box::new(future::ok(response)));
+            }
+        }
+
+        if let Err(response) = self.limiter.check(&request, &meta) {
+            debug!(target: "rpc", "Rejected request over limits from {}", meta.origin);
+            return Either::A(Box::new(future::ok(response)));
+        }
+
         let id = match request {
             rpc::Request::Single(rpc::Call::MethodCall(ref call)) => Some(call.id.clone()),
             _ => None,
@@ -227,6 +503,47 @@ impl<M: rpc::Metadata, T: ActivityNotifier> rpc::Middleware<M> for Middleware<T>
 
         Either::A(Box::new(future))
     }
+
+    fn on_call<F, X>(&self, call: rpc::Call, meta: Metadata, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: FnOnce(rpc::Call, Metadata) -> X,
+        X: rpc::futures::Future<Item = Option<rpc::Output>, Error = ()> + Send + 'static,
+    {
+        let counted = match call {
+            rpc::Call::MethodCall(ref call) => {
+                Some((call.method.clone(), self.params(&call.params)))
+            }
+            rpc::Call::Notification(ref notification) => {
+                Some((notification.method.clone(), self.params(&notification.params)))
+            }
+            _ => None,
+        };
+        let (method, params) = match counted {
+            Some(counted) => counted,
+            None => return Either::B(next(call, meta)),
+        };
+
+        let start = time::Instant::now();
+        let transport = Transport::from(&meta.origin);
+        let stats = self.stats.clone();
+        let future = next(call, meta).map(move |output| {
+            let (success, method) = match output {
+                Some(rpc::Output::Failure(ref failure)) => {
+                    // calls to methods which do not exist are counted together
+                    if failure.error.code == rpc::ErrorCode::MethodNotFound {
+                        (false, "unknown")
+                    } else {
+                        (false, method.as_str())
+                    }
+                }
+                _ => (true, method.as_str()),
+            };
+            stats.add_call(transport, method, start.elapsed(), success, &params);
+            output
+        });
+
+        Either::A(Box::new(future))
+    }
 }
 
 /// Client Notifier
@@ -242,7 +559,25 @@ impl ActivityNotifier for ClientNotifier {
 #[cfg(test)]
 mod tests {
 
-    use super::{RateCalculator, StatsCalculator, RpcStats};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use jsonrpc_core::{MetaIoHandler, Value};
+    use jsonrpc_core::futures::sync::mpsc;
+    use jsonrpc_pubsub::Session;
+    use authorization::{Authorization, Secret};
+    use limits::RequestLimits;
+    use types::Origin;
+    use Metadata;
+    use super::{
+        ActivityNotifier, LatencyHistogram, Middleware, RateCalculator, RpcStats, StatsCalculator,
+        Transport,
+    };
+
+    struct Idle;
+
+    impl ActivityNotifier for Idle {
+        fn active(&self) {}
+    }
 
     #[test]
     fn should_calculate_rate() {
@@ -299,6 +634,99 @@ mod tests {
         assert_eq!(stats.approximated_roundtrip(), 125);
     }
 
+    #[test]
+    fn should_approximate_latency_percentiles() {
+        // given
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.percentile(50), 0);
+
+        // when
+        for _ in 0..90 {
+            histogram.add(100);
+        }
+        for _ in 0..9 {
+            histogram.add(1_000);
+        }
+        histogram.add(1_000_000);
+
+        // then
+        assert_eq!(histogram.percentile(50), 127);
+        assert_eq!(histogram.percentile(90), 127);
+        assert_eq!(histogram.percentile(99), 1_023);
+        assert_eq!(histogram.percentile(100), 1_048_575);
+    }
+
+    #[test]
+    fn should_count_calls_per_method() {
+        // given
+        let stats = RpcStats::new(Some(Duration::from_millis(100)));
+
+        // when
+        stats.add_call(Transport::Http, "eth_call", Duration::from_millis(1), true, b"[]");
+        stats.add_call(Transport::Http, "eth_call", Duration::from_millis(200), false, b"[]");
+        stats.add_call(Transport::Ws, "eth_call", Duration::from_millis(1), true, b"[]");
+        let report = stats.report();
+
+        // then
+        assert_eq!(report.methods.len(), 2);
+        let http = &report.methods[0];
+        assert_eq!(http.transport, "http");
+        assert_eq!((http.calls, http.errors), (2, 1));
+        assert_eq!(http.latency.p99, 262_143);
+        assert_eq!(report.methods[1].transport, "ws");
+        assert_eq!(report.slow_calls.len(), 1);
+        assert_eq!(report.slow_calls[0].method, "eth_call");
+        assert_eq!(report.slow_calls[0].duration, 200_000);
+    }
+
+    #[test]
+    fn should_authenticate_ipc_sessions() {
+        // given
+        let mut authorization = Authorization::new();
+        authorization.add(
+            Secret::ApiKey("reader".into()),
+            vec!["rpc".to_owned()].into_iter().collect(),
+        );
+        let mut io = MetaIoHandler::with_middleware(Middleware::new(
+            Arc::new(RpcStats::default()),
+            Idle,
+            Some(Arc::new(authorization)),
+            RequestLimits::default(),
+        ));
+        io.add_method("rpc_modules", |_| Ok(Value::Bool(true)));
+        let (sender, _receiver) = mpsc::channel(8);
+        let meta = Metadata {
+            origin: Origin::Ipc(1.into()),
+            session: Some(Arc::new(Session::new(sender))),
+            ..Default::default()
+        };
+        let call = r#"{"jsonrpc":"2.0","method":"rpc_modules","params":[],"id":1}"#;
+        let authenticate = |token: &str| {
+            format!(
+                r#"{{"jsonrpc":"2.0","method":"rpc_authenticate","params":["{}"],"id":2}}"#,
+                token
+            )
+        };
+
+        // when
+        let before = io.handle_request_sync(call, meta.clone()).unwrap();
+        let rejected = io.handle_request_sync(&authenticate("writer"), meta.clone()).unwrap();
+        let accepted = io.handle_request_sync(&authenticate("reader"), meta.clone()).unwrap();
+        let after = io.handle_request_sync(call, meta.clone()).unwrap();
+        let other = Metadata {
+            origin: Origin::Ipc(2.into()),
+            ..meta.clone()
+        };
+        let other_session = io.handle_request_sync(call, other).unwrap();
+
+        // then
+        assert!(before.contains("Missing API key or token."));
+        assert!(rejected.contains("Invalid API key or token."));
+        assert_eq!(accepted, r#"{"jsonrpc":"2.0","result":true,"id":2}"#);
+        assert_eq!(after, r#"{"jsonrpc":"2.0","result":true,"id":1}"#);
+        assert!(other_session.contains("Missing API key or token."));
+    }
+
     #[test]
     fn should_be_sync_and_send() {
         let stats = RpcStats::default();
@@ -22,6 +22,7 @@
 
 #[macro_use]
 extern crate futures;
+extern crate futures_cpupool;
 
 extern crate ansi_term;
 extern crate cid;
@@ -31,11 +32,13 @@ extern crate order_stat;
 extern crate parking_lot;
 extern crate rand;
 extern crate rustc_hex;
+extern crate rustc_serialize;
 extern crate semver;
 extern crate serde;
 extern crate serde_json;
 extern crate time;
 extern crate blake2b;
+extern crate crypto as rcrypto;
 extern crate trace_time;
 
 extern crate tokio_timer;
@@ -85,7 +88,9 @@ extern crate macros;
 mod helpers;
 
 pub mod types;
+pub mod authorization;
 pub mod informant;
+pub mod limits;
 pub mod metadata;
 pub mod traits;
 pub mod impls;
@@ -93,7 +98,6 @@ pub mod impls;
 pub use jsonrpc_pubsub::Session as PubSubSession;
 pub use ipc::{Server as IpcServer, MetaExtractor as IpcMetaExtractor, RequestContext as IpcRequestContext};
 pub use http::{
-    Server as HttpServer,
     hyper,
     RequestMiddleware, RequestMiddlewareAction,
     AccessControlAllowOrigin, Host, DomainsValidation
@@ -101,12 +105,14 @@ pub use http::{
 pub use ws::{Server as WsServer,Error as WsError, ErrorKind as WsErrorKind};
 
 pub use helpers::{block_import::is_major_importing,dispatch};
+pub use authorization::{Authorization, Secret};
+pub use limits::{CallLimits, CallSlots, RequestLimits};
 pub use metadata::Metadata;
 pub use types::Origin;
 
 mod server_http;
 mod server_ipc;
 mod server_ws;
-pub use server_http::{RpcExtractor, start_http};
+pub use server_http::{HttpExtractor, HttpServer, RpcExtractor, start_http};
 pub use server_ipc::start_ipc;
 pub use server_ws::start_ws;
@@ -0,0 +1,499 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Limits protecting the node from excessive or expensive RPC requests.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use acore::client::BlockChainClient;
+use acore::filter::Filter;
+use futures::Future;
+use futures_cpupool::{Builder as CpuPoolBuilder, CpuPool};
+use jsonrpc_core::{Error, Request, Response};
+use parking_lot::Mutex;
+
+use authorization::failure;
+use helpers::errors;
+use types::Origin;
+use Metadata;
+
+/// Number of clients tracked for rate limiting above which idle ones are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+/// Threads running calls when their number is not bounded.
+const UNBOUNDED_CALL_THREADS: usize = 8;
+
+/// Limits on the requests an interface accepts.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestLimits {
+    /// Maximum number of calls in a batch request.
+    pub max_batch_size: Option<usize>,
+    /// Maximum number of calls a client may make per second.
+    pub max_requests_per_second: Option<usize>,
+}
+
+/// Budgets of methods which are expensive to serve.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallLimits {
+    /// Maximum number of blocks a logs query may span.
+    pub max_logs_block_range: Option<u64>,
+    /// Maximum number of logs a logs query may return.
+    pub max_logs: Option<usize>,
+    /// Time after which `eth_call`, `eth_estimateGas` and bundle calls give up.
+    pub call_timeout: Option<Duration>,
+    /// Maximum number of transactions a bundle call may simulate.
+    pub max_bundle_transactions: Option<usize>,
+    /// Executions of calls running at once, shared by the interfaces the limits are cloned to.
+    pub call_slots: CallSlots,
+}
+
+impl CallLimits {
+    /// Check the number of blocks the given filter spans.
+    pub fn check_logs_filter<C>(&self, client: &C, filter: &Filter) -> Result<(), Error>
+    where C: BlockChainClient + ?Sized {
+        if self.max_logs_block_range.is_none() {
+            return Ok(());
+        }
+        let best = client.chain_info().best_block_number;
+        let from = client.block_number(filter.from_block.clone()).unwrap_or(best);
+        let to = client.block_number(filter.to_block.clone()).unwrap_or(best);
+        self.check_logs_block_range(from, to)
+    }
+
+    /// Check the number of blocks between `from` and `to`, both included.
+    pub fn check_logs_block_range(&self, from: u64, to: u64) -> Result<(), Error> {
+        match self.max_logs_block_range {
+            Some(max) if to >= from && to - from >= max => {
+                Err(errors::limit_exceeded("maxLogsBlockRange", max))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Cap a logs query so that it stops as soon as it is known to return more logs than
+    /// allowed, instead of collecting all of them to be rejected.
+    pub fn cap_logs_filter(&self, filter: &mut Filter) {
+        if let Some(max) = self.max_logs {
+            let cap = max.saturating_add(1);
+            filter.limit = Some(filter.limit.map_or(cap, |limit| cmp::min(limit, cap)));
+        }
+    }
+
+    /// Check the number of transactions of a bundle call.
+    pub fn check_bundle_size(&self, count: usize) -> Result<(), Error> {
+        match self.max_bundle_transactions {
+            Some(max) if count > max => {
+                Err(errors::limit_exceeded("maxBundleTransactions", max as u64))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the number of logs about to be returned.
+    pub fn check_logs_count(&self, count: usize) -> Result<(), Error> {
+        match self.max_logs {
+            Some(max) if count > max => Err(errors::limit_exceeded("maxLogs", max as u64)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Bounds the number of calls executing at once. Clones share the same slots and the same
+/// threads to run calls on.
+#[derive(Clone, Default)]
+pub struct CallSlots {
+    max: Option<usize>,
+    busy: Arc<AtomicUsize>,
+    pool: Arc<Mutex<Option<CpuPool>>>,
+}
+
+impl CallSlots {
+    /// Slots for up to `max` calls at once.
+    pub fn new(max: usize) -> Self {
+        CallSlots {
+            max: Some(max),
+            busy: Default::default(),
+            pool: Default::default(),
+        }
+    }
+
+    /// Maximum number of calls executing at once, if bounded.
+    pub fn max(&self) -> Option<usize> { self.max }
+
+    /// Take a slot, held until the returned guard is dropped. Fails with an error when every
+    /// slot is taken.
+    pub fn acquire(&self) -> Result<CallSlot, Error> {
+        let mut busy = self.busy.load(Ordering::SeqCst);
+        loop {
+            if let Some(max) = self.max {
+                if busy >= max {
+                    return Err(errors::limit_exceeded("maxConcurrentCalls", max as u64));
+                }
+            }
+            let current = self.busy.compare_and_swap(busy, busy + 1, Ordering::SeqCst);
+            if current == busy {
+                return Ok(CallSlot {
+                    busy: self.busy.clone(),
+                });
+            }
+            busy = current;
+        }
+    }
+
+    /// Run `f` on one of the call threads, giving the slot back once it is over. There are as
+    /// many threads as slots, started with the first call, so a call taking a slot never waits
+    /// for a thread.
+    pub fn spawn<F>(&self, slot: CallSlot, f: F)
+    where F: FnOnce() + Send + 'static
+    {
+        let pool = self
+            .pool
+            .lock()
+            .get_or_insert_with(|| {
+                let size = self.max.unwrap_or(UNBOUNDED_CALL_THREADS);
+                CpuPoolBuilder::new()
+                    .pool_size(cmp::max(size, 1))
+                    .name_prefix("rpc-call-")
+                    .create()
+            })
+            .clone();
+        pool.spawn_fn(move || {
+            f();
+            drop(slot);
+            Ok::<(), ()>(())
+        })
+        .forget();
+    }
+}
+
+impl PartialEq for CallSlots {
+    fn eq(&self, other: &Self) -> bool { self.max == other.max }
+}
+
+impl fmt::Debug for CallSlots {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CallSlots")
+            .field("max", &self.max)
+            .field("busy", &self.busy.load(Ordering::SeqCst))
+            .finish()
+    }
+}
+
+/// A call slot taken, given back when dropped.
+pub struct CallSlot {
+    busy: Arc<AtomicUsize>,
+}
+
+impl Drop for CallSlot {
+    fn drop(&mut self) { self.busy.fetch_sub(1, Ordering::SeqCst); }
+}
+
+struct Window {
+    start: Instant,
+    calls: usize,
+}
+
+/// Enforces the request limits of an interface.
+pub struct RequestLimiter {
+    limits: RequestLimits,
+    clients: Mutex<HashMap<String, Window>>,
+}
+
+impl RequestLimiter {
+    /// Create a limiter enforcing `limits`.
+    pub fn new(limits: RequestLimits) -> Self {
+        RequestLimiter {
+            limits,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check a request sent with the given metadata. On failure returns the response to send
+    /// instead of processing the request, none if it only holds notifications.
+    pub fn check(&self, request: &Request, meta: &Metadata) -> Result<(), Option<Response>> {
+        self.check_at(request, &client_id(meta), Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        request: &Request,
+        client: &str,
+        now: Instant,
+    ) -> Result<(), Option<Response>>
+    {
+        let calls = match *request {
+            Request::Single(_) => 1,
+            Request::Batch(ref calls) => calls.len(),
+        };
+        if let Some(max) = self.limits.max_batch_size {
+            if calls > max {
+                let error = errors::limit_exceeded("maxBatchSize", max as u64);
+                return Err(reject(request, error));
+            }
+        }
+
+        if let Some(max) = self.limits.max_requests_per_second {
+            let second = Duration::from_secs(1);
+            let mut clients = self.clients.lock();
+            if clients.len() >= MAX_TRACKED_CLIENTS {
+                clients.retain(|_, window| now.duration_since(window.start) < second);
+            }
+            let window = clients.entry(client.to_owned()).or_insert(Window {
+                start: now,
+                calls: 0,
+            });
+            if now.duration_since(window.start) >= second {
+                window.start = now;
+                window.calls = 0;
+            }
+            // rejected calls are not counted, so the client recovers once it slows down
+            if window.calls + calls > max {
+                let error = errors::limit_exceeded("maxRequestsPerSecond", max as u64);
+                return Err(reject(request, error));
+            }
+            window.calls += calls;
+        }
+
+        Ok(())
+    }
+}
+
+/// Identifies the client a request is counted against: the API key or token it was sent with,
+/// else its address, as reported by a trusted proxy or by the relay in front of the HTTP server,
+/// else its connection. HTTP requests reaching the server around the relay, which only local
+/// processes can do, share a single budget.
+fn client_id(meta: &Metadata) -> String {
+    if let Some(ref token) = meta.auth_token {
+        return format!("token:{}", token);
+    }
+    if let Some(remote) = meta.remote {
+        return format!("ip:{}", remote);
+    }
+    match meta.origin {
+        Origin::Ws { ref session, .. } | Origin::Ipc(ref session) => {
+            format!("session:{}", session)
+        }
+        _ => "anonymous".into(),
+    }
+}
+
+/// The response rejecting every call of the request with `error`.
+fn reject(request: &Request, error: Error) -> Option<Response> {
+    match *request {
+        Request::Single(ref call) => failure(call, error).map(Response::Single),
+        Request::Batch(ref calls) => {
+            let outputs = calls
+                .iter()
+                .filter_map(|call| failure(call, error.clone()))
+                .collect::<Vec<_>>();
+            if outputs.is_empty() {
+                None
+            } else {
+                Some(Response::Batch(outputs))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use acore::client::BlockId;
+    use jsonrpc_core::Output;
+    use serde_json;
+
+    fn request(calls: usize) -> Request {
+        let call = r#"{"jsonrpc":"2.0","method":"eth_blockNumber","params":[],"id":1}"#;
+        let json = if calls == 1 {
+            call.to_owned()
+        } else {
+            format!("[{}]", vec![call; calls].join(","))
+        };
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn should_limit_batch_size() {
+        let limiter = RequestLimiter::new(RequestLimits {
+            max_batch_size: Some(2),
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_at(&request(2), "client", now), Ok(()));
+        match limiter.check_at(&request(3), "client", now) {
+            Err(Some(Response::Batch(outputs))) => {
+                assert_eq!(outputs.len(), 3);
+                match outputs[0] {
+                    Output::Failure(ref failure) => {
+                        assert_eq!(failure.error, errors::limit_exceeded("maxBatchSize", 2));
+                    }
+                    ref other => panic!("Unexpected output: {:?}", other),
+                }
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_limit_requests_per_client() {
+        let limiter = RequestLimiter::new(RequestLimits {
+            max_requests_per_second: Some(3),
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_at(&request(2), "a", now), Ok(()));
+        assert_eq!(limiter.check_at(&request(1), "a", now), Ok(()));
+        match limiter.check_at(&request(1), "a", now) {
+            Err(Some(Response::Single(Output::Failure(failure)))) => {
+                assert_eq!(
+                    failure.error,
+                    errors::limit_exceeded("maxRequestsPerSecond", 3)
+                );
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        // other clients have their own budget
+        assert_eq!(limiter.check_at(&request(3), "b", now), Ok(()));
+        // which is restored every second
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.check_at(&request(3), "a", later), Ok(()));
+    }
+
+    #[test]
+    fn should_identify_clients() {
+        let mut meta = Metadata::default();
+        assert_eq!(client_id(&meta), "anonymous");
+        meta.remote = Some("10.0.0.1".parse().unwrap());
+        assert_eq!(client_id(&meta), "ip:10.0.0.1");
+        meta.remote = None;
+        meta.origin = Origin::Ipc(Default::default());
+        assert!(client_id(&meta).starts_with("session:"));
+        meta.auth_token = Some("key".into());
+        assert_eq!(client_id(&meta), "token:key");
+    }
+
+    #[test]
+    fn should_bound_concurrent_calls() {
+        let slots = CallSlots::new(2);
+        let shared = slots.clone();
+        let first = slots.acquire().unwrap();
+        let _second = shared.acquire().unwrap();
+        match slots.acquire() {
+            Err(error) => assert_eq!(error, errors::limit_exceeded("maxConcurrentCalls", 2)),
+            Ok(_) => panic!("all slots should be taken"),
+        }
+        // slots are given back once the call is over
+        drop(first);
+        assert!(shared.acquire().is_ok());
+        // unbounded by default
+        let unbounded = CallSlots::default();
+        let _taken: Vec<_> = (0..100).map(|_| unbounded.acquire().unwrap()).collect();
+    }
+
+    #[test]
+    fn should_hold_slots_while_calls_run() {
+        use std::sync::mpsc;
+
+        let slots = CallSlots::new(1);
+        let (start, started) = mpsc::channel();
+        let (finish, finished) = mpsc::channel::<()>();
+        let (done, is_done) = mpsc::channel();
+        slots.spawn(slots.acquire().unwrap(), move || {
+            start.send(()).unwrap();
+            let _ = finished.recv();
+            done.send(()).unwrap();
+        });
+        started.recv().unwrap();
+        assert!(slots.acquire().is_err());
+
+        finish.send(()).unwrap();
+        is_done.recv().unwrap();
+        // the slot goes back right after the call returns.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while slots.acquire().is_err() {
+            assert!(Instant::now() < deadline);
+            ::std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn should_check_logs_budgets() {
+        let limits = CallLimits {
+            max_logs_block_range: Some(10),
+            max_logs: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(limits.check_logs_block_range(0, 9), Ok(()));
+        assert_eq!(
+            limits.check_logs_block_range(0, 10),
+            Err(errors::limit_exceeded("maxLogsBlockRange", 10))
+        );
+        assert_eq!(limits.check_logs_block_range(10, 0), Ok(()));
+        assert_eq!(limits.check_logs_count(100), Ok(()));
+        assert_eq!(
+            limits.check_logs_count(101),
+            Err(errors::limit_exceeded("maxLogs", 100))
+        );
+        assert_eq!(CallLimits::default().check_logs_count(1_000_000), Ok(()));
+
+        let filter = |limit| {
+            Filter {
+                from_block: BlockId::Earliest,
+                to_block: BlockId::Latest,
+                address: None,
+                topics: vec![None, None, None, None],
+                limit,
+            }
+        };
+        let capped = |mut filter: Filter| {
+            limits.cap_logs_filter(&mut filter);
+            filter.limit
+        };
+        // one more log than allowed is enough to reject the query
+        assert_eq!(capped(filter(None)), Some(101));
+        assert_eq!(capped(filter(Some(1000))), Some(101));
+        assert_eq!(capped(filter(Some(10))), Some(10));
+        let mut unlimited = filter(None);
+        CallLimits::default().cap_logs_filter(&mut unlimited);
+        assert_eq!(unlimited.limit, None);
+    }
+
+    #[test]
+    fn should_check_bundle_size() {
+        let limits = CallLimits {
+            max_bundle_transactions: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(limits.check_bundle_size(2), Ok(()));
+        assert_eq!(
+            limits.check_bundle_size(3),
+            Err(errors::limit_exceeded("maxBundleTransactions", 2))
+        );
+        assert_eq!(CallLimits::default().check_bundle_size(1_000), Ok(()));
+    }
+}
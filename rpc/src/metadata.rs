@@ -21,6 +21,7 @@
  ******************************************************************************/
 
 //! Aion RPC requests Metadata.
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use jsonrpc_core;
@@ -35,6 +36,10 @@ pub struct Metadata {
     pub origin: Origin,
     /// Request PubSub Session
     pub session: Option<Arc<Session>>,
+    /// API key or JSON web token presented with the request
+    pub auth_token: Option<String>,
+    /// Address of the client, as reported by a trusted reverse proxy
+    pub remote: Option<IpAddr>,
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
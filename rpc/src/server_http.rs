@@ -21,33 +21,102 @@
  ******************************************************************************/
 
 use jsonrpc_core;
+use futures::future::{self, Loop};
+use futures::sync::oneshot;
 use http;
 use hyper;
+use tokio;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::prelude::*;
 use tokio::runtime::TaskExecutor;
 use Metadata;
 use types::Origin;
-use std::net::SocketAddr;
+use std::io;
+use std::net::{self, IpAddr, SocketAddr};
+
+/// Header through which the relay in front of the server tells the address of the peer, which
+/// the server itself doesn't pass to the handler. It is inserted first, so it wins over any
+/// header of the same name sent by the peer.
+const PEER_HEADER: &'static str = "x-aion-peer";
+/// Longest request line the relay reads before giving up on the connection.
+const MAX_REQUEST_LINE: usize = 8 * 1024;
 
 /// Common HTTP & IPC & WebSocket metadata extractor.
 pub struct RpcExtractor;
 
-impl http::MetaExtractor<Metadata> for RpcExtractor {
+/// HTTP metadata extractor.
+pub struct HttpExtractor {
+    /// Whether the node is behind a reverse proxy reporting the address of the client in the
+    /// `X-Real-IP` or `X-Forwarded-For` header. Otherwise the address of the peer is used.
+    pub trust_proxy_headers: bool,
+}
+
+impl http::MetaExtractor<Metadata> for HttpExtractor {
     fn read_metadata(&self, req: &hyper::Request<hyper::Body>) -> Metadata {
         let as_string = |header: Option<&hyper::header::HeaderValue>| {
             header.and_then(|val| val.to_str().ok().map(|s| s.to_owned()))
         };
         let user_agent = as_string(req.headers().get("user-agent"));
+        let auth_token =
+            as_string(req.headers().get("authorization")).and_then(|value| bearer_token(&value));
+        // the proxy appends the address it got the request from to `X-Forwarded-For`, so the
+        // entries before the last one may be forged by the client.
+        let remote = if self.trust_proxy_headers {
+            as_string(req.headers().get("x-real-ip"))
+                .or_else(|| {
+                    as_string(req.headers().get("x-forwarded-for"))
+                        .and_then(|value| value.rsplit(',').next().map(|s| s.to_owned()))
+                })
+                .and_then(|address| address.trim().parse::<IpAddr>().ok())
+        } else {
+            None
+        };
+        let remote = remote.or_else(|| {
+            as_string(req.headers().get(PEER_HEADER))
+                .and_then(|address| address.trim().parse::<IpAddr>().ok())
+        });
         Metadata {
             origin: match user_agent {
                 Some(service) => Origin::Rpc(service.into()),
                 None => Origin::Rpc("unknown".into()),
             },
             session: None,
+            auth_token,
+            remote,
+        }
+    }
+}
+
+/// Token of an `Authorization: Bearer <token>` header value.
+pub(crate) fn bearer_token(value: &str) -> Option<String> {
+    let mut parts = value.splitn(2, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("bearer") => {
+            Some(token.trim().to_owned())
         }
+        _ => None,
+    }
+}
+
+/// A running HTTP server, with the relay accepting its connections.
+pub struct HttpServer {
+    server: http::Server,
+    relay: oneshot::Sender<()>,
+}
+
+impl HttpServer {
+    /// Stop accepting connections and close the server.
+    pub fn close(self) {
+        let _ = self.relay.send(());
+        self.server.close();
     }
 }
 
 /// Start http server asynchronously and returns result with `Server` handle on success or an error.
+/// Request bodies are limited to `max_payload` MB.
+///
+/// The server itself listens on a loopback port; connections to `addr` go through a relay
+/// which tells it the address of each peer.
 pub fn start_http<M, S, H, T>(
     addr: &SocketAddr,
     cors_domains: http::DomainsValidation<http::AccessControlAllowOrigin>,
@@ -55,20 +124,153 @@ pub fn start_http<M, S, H, T>(
     handler: H,
     extractor: T,
     threads: usize,
+    max_payload: usize,
     executor: TaskExecutor,
-) -> ::std::io::Result<http::Server>
+) -> io::Result<HttpServer>
 where
     M: jsonrpc_core::Metadata,
     S: jsonrpc_core::Middleware<M>,
     H: Into<jsonrpc_core::MetaIoHandler<M, S>>,
     T: http::MetaExtractor<M>,
 {
+    // bind the public address first, so that it being in use is reported as such.
+    let listener = TcpListener::bind(addr)?;
+    let inner = net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+
+    // clients send the public address as their host, not the one the server binds.
+    let allowed_hosts = match allowed_hosts {
+        http::DomainsValidation::AllowOnly(mut hosts) => {
+            let address = addr.to_string();
+            hosts.push(address.replace("127.0.0.1", "localhost").into());
+            hosts.push(address.into());
+            http::DomainsValidation::AllowOnly(hosts)
+        }
+        disabled => disabled,
+    };
+
     let builder = http::ServerBuilder::with_meta_extractor(handler, extractor)
         .threads(threads)
-        .event_loop_executor(executor)
+        .max_request_body_size(max_payload * 1024 * 1024)
+        .event_loop_executor(executor.clone())
         .cors(cors_domains.into())
         .allowed_hosts(allowed_hosts.into())
         .keep_alive(false);
+    let server = builder.start_http(&inner)?;
 
-    Ok(builder.start_http(addr)?)
+    Ok(HttpServer {
+        server: server,
+        relay: start_relay(listener, inner, executor),
+    })
+}
+
+// accepts connections on `listener` and relays them to the server at `inner`, until the
+// returned sender is used or dropped.
+fn start_relay(
+    listener: TcpListener,
+    inner: SocketAddr,
+    executor: TaskExecutor,
+) -> oneshot::Sender<()>
+{
+    let (close, closed) = oneshot::channel();
+    let relay_executor = executor.clone();
+    let accept = listener
+        .incoming()
+        .then(|client| {
+            if let Err(ref e) = client {
+                debug!(target: "rpc", "HTTP connection not accepted: {}", e);
+            }
+            Ok::<_, ()>(client.ok())
+        })
+        .filter_map(|client| client)
+        .for_each(move |client| {
+            relay_executor.spawn(relay(client, inner));
+            Ok(())
+        })
+        .select(closed.map_err(|_| ()))
+        .map(|_| ())
+        .map_err(|_| ());
+    executor.spawn(accept);
+    close
+}
+
+// relays one connection, adding the address of the peer right after the request line. The
+// server answers a single request per connection and then closes it, which ends the relay.
+fn relay(client: TcpStream, inner: SocketAddr) -> Box<Future<Item = (), Error = ()> + Send> {
+    let peer = match client.peer_addr() {
+        Ok(address) => address.ip(),
+        Err(_) => return Box::new(future::ok(())),
+    };
+    let forward = read_line(client)
+        .join(TcpStream::connect(&inner))
+        .and_then(move |((client, mut head), server)| {
+            head.extend_from_slice(format!("{}: {}\r\n", PEER_HEADER, peer).as_bytes());
+            tokio::io::write_all(server, head).map(move |(server, _)| (client, server))
+        })
+        .and_then(|(client, server)| {
+            let (client_reader, client_writer) = client.split();
+            let (server_reader, server_writer) = server.split();
+            let upstream = tokio::io::copy(client_reader, server_writer)
+                .and_then(|(_, _, server_writer)| tokio::io::shutdown(server_writer))
+                .then(|_| future::empty::<(), io::Error>());
+            let downstream = tokio::io::copy(server_reader, client_writer).map(|_| ());
+            upstream.select(downstream).map(|_| ()).map_err(|(e, _)| e)
+        });
+    Box::new(forward.map_err(|e| trace!(target: "rpc", "HTTP relay closed: {}", e)))
+}
+
+// reads up to the end of the request line, byte by byte so that nothing after it is consumed.
+fn read_line(
+    stream: TcpStream,
+) -> Box<Future<Item = (TcpStream, Vec<u8>), Error = io::Error> + Send>
+{
+    Box::new(future::loop_fn((stream, Vec::new()), |(stream, mut line)| {
+        tokio::io::read_exact(stream, [0u8; 1]).and_then(move |(stream, byte)| {
+            line.push(byte[0]);
+            if byte[0] == b'\n' {
+                Ok(Loop::Break((stream, line)))
+            } else if line.len() >= MAX_REQUEST_LINE {
+                Err(io::Error::new(io::ErrorKind::InvalidData, "request line too long"))
+            } else {
+                Ok(Loop::Continue((stream, line)))
+            }
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn relays_with_the_address_of_the_peer() {
+        let runtime = Runtime::new().unwrap();
+        let server = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let inner = server.local_addr().unwrap();
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _close = start_relay(listener, inner, runtime.executor());
+
+        let mut client = net::TcpStream::connect(&addr).unwrap();
+        client
+            .write_all(b"POST / HTTP/1.1\r\nX-Aion-Peer: 10.0.0.1\r\n\r\n")
+            .unwrap();
+
+        let (mut connection, _) = server.accept().unwrap();
+        let expected = concat!(
+            "POST / HTTP/1.1\r\n",
+            "x-aion-peer: 127.0.0.1\r\n",
+            "X-Aion-Peer: 10.0.0.1\r\n\r\n"
+        );
+        let mut request = vec![0u8; expected.len()];
+        connection.read_exact(&mut request).unwrap();
+        assert_eq!(String::from_utf8(request).unwrap(), expected);
+
+        connection.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+        drop(connection);
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(response, "HTTP/1.1 200 OK\r\n\r\n");
+    }
 }
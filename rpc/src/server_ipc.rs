@@ -33,6 +33,8 @@ impl ipc::MetaExtractor<Metadata> for RpcExtractor {
         Metadata {
             origin: Origin::Ipc(req.session_id.into()),
             session: Some(Arc::new(Session::new(req.sender.clone()))),
+            auth_token: None,
+            remote: None,
         }
     }
 }
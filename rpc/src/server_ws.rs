@@ -20,6 +20,8 @@
  *
  ******************************************************************************/
 
+use std::cell::RefCell;
+use std::str;
 use std::sync::Arc;
 use ws;
 use jsonrpc_core as core;
@@ -29,7 +31,28 @@ use tokio::runtime::TaskExecutor;
 use Metadata;
 use types::Origin;
 
-use server_http::RpcExtractor;
+use server_http::{bearer_token, RpcExtractor};
+
+thread_local! {
+    /// Token of the `Authorization` header of the handshake being processed. The middleware
+    /// reads it right before the extractor runs on the same thread, which only sees the
+    /// subprotocols of the handshake.
+    static HANDSHAKE_TOKEN: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Middleware keeping the bearer token of each handshake for the extractor.
+struct AuthorizationHeader;
+
+impl ws::RequestMiddleware for AuthorizationHeader {
+    fn process(&self, req: &ws::ws::Request) -> ws::MiddlewareAction {
+        let token = req
+            .header("authorization")
+            .and_then(|value| str::from_utf8(value).ok())
+            .and_then(bearer_token);
+        HANDSHAKE_TOKEN.with(|handshake| *handshake.borrow_mut() = token);
+        ws::MiddlewareAction::Proceed
+    }
+}
 
 impl ws::MetaExtractor<Metadata> for RpcExtractor {
     fn extract(&self, req: &ws::RequestContext) -> Metadata {
@@ -42,14 +65,20 @@ impl ws::MetaExtractor<Metadata> for RpcExtractor {
             session: req.session_id.into(),
         };
         let session = Some(Arc::new(Session::new(req.sender())));
+        // browsers cannot set headers on WebSockets, so they send the token as the subprotocol
+        let auth_token = HANDSHAKE_TOKEN
+            .with(|handshake| handshake.borrow_mut().take())
+            .or_else(|| req.protocols.get(0).map(|protocol| protocol.to_string()));
         Metadata {
             origin,
             session,
+            auth_token,
+            remote: None,
         }
     }
 }
 
-/// Start WS server and return `Server` handle.
+/// Start WS server and return `Server` handle. Messages are limited to `max_payload` MB.
 pub fn start_ws<M, S, H, T>(
     addr: &SocketAddr,
     handler: H,
@@ -58,6 +87,7 @@ pub fn start_ws<M, S, H, T>(
     extractor: T,
     executor: TaskExecutor,
     max_connections: usize,
+    max_payload: usize,
 ) -> Result<ws::Server, ws::Error>
 where
     M: core::Metadata,
@@ -66,8 +96,10 @@ where
     T: ws::MetaExtractor<M>,
 {
     ws::ServerBuilder::with_meta_extractor(handler, extractor)
+        .request_middleware(AuthorizationHeader)
         .event_loop_executor(executor)
         .max_connections(max_connections)
+        .max_payload(max_payload * 1024 * 1024)
         .allowed_origins(allowed_origins)
         .allowed_hosts(allowed_hosts)
         .start(addr)
@@ -26,9 +26,10 @@ use std::collections::HashMap;
 use jsonrpc_core::{Result, BoxFuture};
 use jsonrpc_macros::Trailing;
 
-use types::{Block, BlockNumber, Bytes, CallRequest, Filter, FilterChanges, Index};
-use types::{Log, Receipt, SyncStatus, Transaction, Work, Contract};
+use types::{Block, BlockNumber, BlockNumberOrHash, Bytes, CallRequest, Filter, FilterChanges, Index};
+use types::{Log, Receipt, SyncStatus, Transaction, Work, Contract, FeeHistory, Histogram};
 use types::{H64, H256, U256, U128, H128};
+use types::{BundleTransaction, BlockOverride, CallBundleResult};
 
 build_rpc_trait! {
     /// Eth rpc interface.
@@ -58,6 +59,15 @@ build_rpc_trait! {
         #[rpc(name = "eth_gasPrice")]
         fn gas_price(&self) -> Result<U256>;
 
+        /// Returns gas used ratios and gas price percentiles of the given number of blocks
+        /// up to the given newest block.
+        #[rpc(name = "eth_feeHistory")]
+        fn fee_history(&self, U256, BlockNumber, Trailing<Vec<f64>>) -> Result<FeeHistory>;
+
+        /// Returns a histogram of the gas prices in recent blocks.
+        #[rpc(name = "eth_gasPriceHistogram")]
+        fn gas_price_histogram(&self) -> Result<Histogram>;
+
         /// Returns accounts list.
         #[rpc(name = "eth_accounts")]
         fn accounts(&self) -> Result<Vec<H256>>;
@@ -106,18 +116,35 @@ build_rpc_trait! {
         #[rpc(name = "eth_submitTransaction")]
         fn submit_transaction(&self, Bytes) -> Result<H256>;
 
-        /// Call contract, returning the output data.
+        /// Call contract, returning the output data. Also takes a state override as optional
+        /// third parameter, see `EthClient::to_delegate_with_state_override`.
         #[rpc(name = "eth_call")]
         fn call(&self, CallRequest, Trailing<BlockNumber>) -> BoxFuture<Bytes>;
 
-        /// Estimate gas needed for execution of given contract.
+        /// Estimate gas needed for execution of given contract. Also takes a state override as
+        /// optional third parameter.
         #[rpc(name = "eth_estimateGas")]
         fn estimate_gas(&self, CallRequest, Trailing<BlockNumber>) -> BoxFuture<U256>;
 
+        /// Executes the transactions one after another on the state of the given block,
+        /// returning the result, logs and state diff of each.
+        #[rpc(name = "eth_callBundle")]
+        fn call_bundle(&self, Vec<BundleTransaction>, BlockNumber, Trailing<BlockOverride>) -> BoxFuture<Vec<CallBundleResult>>;
+
+        /// @alias of `eth_callBundle`.
+        #[rpc(name = "eth_callMany")]
+        fn call_many(&self, Vec<BundleTransaction>, BlockNumber, Trailing<BlockOverride>) -> BoxFuture<Vec<CallBundleResult>>;
+
         /// Get transaction by its hash.
         #[rpc(name = "eth_getTransactionByHash")]
         fn transaction_by_hash(&self, H256) -> BoxFuture<Option<Transaction>>;
 
+        /// Returns at most `count` of the transactions sent from, sent to or creating the given
+        /// address, newest first, skipping the first `offset` ones. Requires the address index,
+        /// and fails while the blocks the page reaches are not indexed yet.
+        #[rpc(name = "eth_getTransactionsByAddress")]
+        fn transactions_by_address(&self, H256, Index, Index) -> BoxFuture<Vec<Transaction>>;
+
         /// Returns transaction at given block hash and index.
         #[rpc(name = "eth_getTransactionByBlockHashAndIndex")]
         fn transaction_by_block_hash_and_index(&self, H256, Index) -> BoxFuture<Option<Transaction>>;
@@ -130,6 +157,10 @@ build_rpc_trait! {
         #[rpc(name = "eth_getTransactionReceipt")]
         fn transaction_receipt(&self, H256) -> BoxFuture<Option<Receipt>>;
 
+        /// Returns the receipts of all transactions in the block with given number or hash.
+        #[rpc(name = "eth_getBlockReceipts")]
+        fn block_receipts(&self, BlockNumberOrHash) -> BoxFuture<Option<Vec<Receipt>>>;
+
         /// Returns available compilers.
         /// @deprecated
         #[rpc(name = "eth_getCompilers")]
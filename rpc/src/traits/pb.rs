@@ -20,7 +20,10 @@
  *
  ******************************************************************************/
 
-use types::{U256, H256, Transaction, Block, AcitvePeerInfo, PbSyncInfo, Receipt, Bytes, SimpleReceipt};
+use types::{
+    U256, H256, Transaction, Block, BlockNumberOrHash, AcitvePeerInfo, PbSyncInfo, Receipt, Bytes,
+    SimpleReceipt,
+};
 
 pub trait Pb: Sync + Send {
     fn balance(&self, address: H256) -> U256;
@@ -41,5 +44,7 @@ pub trait Pb: Sync + Send {
 
     fn transaction_receipt(&self, txhash: H256) -> Option<Receipt>;
 
+    fn block_receipts(&self, id: BlockNumberOrHash) -> Option<Vec<Receipt>>;
+
     fn pb_send_transaction(&self, raw: Bytes) -> Option<H256>;
 }
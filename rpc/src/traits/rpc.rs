@@ -26,6 +26,8 @@ use std::collections::BTreeMap;
 
 use jsonrpc_core::Result;
 
+use types::RpcStatsReport;
+
 build_rpc_trait! {
     /// RPC Interface.
     pub trait Rpc {
@@ -38,5 +40,9 @@ build_rpc_trait! {
         /// @ignore
         #[rpc(name = "rpc_modules")]
         fn rpc_modules(&self) -> Result<BTreeMap<String, String>>;
+
+        /// Returns call counts, error counts and latencies of each method, and recent slow calls.
+        #[rpc(name = "rpc_stats")]
+        fn stats(&self) -> Result<RpcStatsReport>;
     }
 }
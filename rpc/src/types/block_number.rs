@@ -24,6 +24,7 @@ use std::fmt;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::{Error, Visitor};
 use acore::client::BlockId;
+use types::H256;
 
 /// Represents rpc api block number param.
 #[derive(Debug, PartialEq, Clone, Hash, Eq)]
@@ -126,6 +127,25 @@ impl Into<BlockId> for BlockNumber {
     }
 }
 
+/// Represents rpc api block number or block hash param.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum BlockNumberOrHash {
+    /// Block hash
+    Hash(H256),
+    /// Block number or tag
+    Number(BlockNumber),
+}
+
+impl Into<BlockId> for BlockNumberOrHash {
+    fn into(self) -> BlockId {
+        match self {
+            BlockNumberOrHash::Hash(hash) => BlockId::Hash(hash.into()),
+            BlockNumberOrHash::Number(number) => number.into(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use acore::client::BlockId;
@@ -154,6 +174,23 @@ mod tests {
         assert!(serde_json::from_str::<BlockNumber>(s).is_ok());
     }
 
+    #[test]
+    fn block_number_or_hash_deserialization() {
+        let s = r#"["0xa", "latest",
+            "0x0000000000000000000000000000000000000000000000000000000000000001"]"#;
+        let deserialized: Vec<BlockNumberOrHash> = serde_json::from_str(s).unwrap();
+        assert_eq!(
+            deserialized,
+            vec![
+                BlockNumberOrHash::Number(BlockNumber::Num(10)),
+                BlockNumberOrHash::Number(BlockNumber::Latest),
+                BlockNumberOrHash::Hash(H256::from(1)),
+            ]
+        );
+        let id: BlockId = deserialized[2].clone().into();
+        assert_eq!(id, BlockId::Hash(1.into()));
+    }
+
     #[test]
     fn block_number_into() {
         assert_eq!(BlockId::Number(100), BlockNumber::Num(100).into());
@@ -0,0 +1,108 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+use types::{Bytes, CallRequest, H256, Log, StateDiff, U256};
+
+/// Transaction of a call bundle
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum BundleTransaction {
+    /// Signed raw transaction
+    Raw(Bytes),
+    /// Unsigned call, `from` is required
+    Call(CallRequest),
+}
+
+/// Block environment override
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockOverride {
+    /// Number
+    pub number: Option<U256>,
+    /// Timestamp
+    pub timestamp: Option<U256>,
+    /// Coinbase
+    pub coinbase: Option<H256>,
+}
+
+/// Result of a transaction of a call bundle
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CallBundleResult {
+    /// Transaction hash
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: H256,
+    /// Gas used
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U256,
+    /// Output
+    pub output: Bytes,
+    /// Exception, if the transaction failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Logs
+    pub logs: Vec<Log>,
+    /// State diff
+    #[serde(rename = "stateDiff")]
+    pub state_diff: StateDiff,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use types::{CallRequest, H256, U256};
+    use super::{BlockOverride, BundleTransaction};
+
+    #[test]
+    fn bundle_transaction_deserialize() {
+        let s = r#"[
+            "0x1234",
+            {"from":"0x0000000000000000000000000000000000000000000000000000000000000001"}
+        ]"#;
+        let deserialized: Vec<BundleTransaction> = serde_json::from_str(s).unwrap();
+
+        assert_eq!(
+            deserialized,
+            vec![
+                BundleTransaction::Raw(vec![0x12, 0x34].into()),
+                BundleTransaction::Call(CallRequest {
+                    from: Some(H256::from(1)),
+                    ..Default::default()
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn block_override_deserialize() {
+        let s = r#"{"number":"0x10","coinbase":"0x0000000000000000000000000000000000000000000000000000000000000002"}"#;
+        let deserialized: BlockOverride = serde_json::from_str(s).unwrap();
+
+        assert_eq!(
+            deserialized,
+            BlockOverride {
+                number: Some(U256::from(0x10)),
+                timestamp: None,
+                coinbase: Some(H256::from(2)),
+            }
+        );
+    }
+}
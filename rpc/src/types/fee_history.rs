@@ -0,0 +1,67 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Fee history.
+
+use types::U256;
+
+/// Gas usage and gas prices of a range of blocks.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct FeeHistory {
+    /// Number of the oldest block in the range.
+    #[serde(rename = "oldestBlock")]
+    pub oldest_block: U256,
+    /// Gas used divided by gas limit, for each block.
+    #[serde(rename = "gasUsedRatio")]
+    pub gas_used_ratio: Vec<f64>,
+    /// Requested percentiles of the transaction gas prices, for each block.
+    #[serde(rename = "gasPrice", skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<Vec<Vec<U256>>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use super::FeeHistory;
+
+    #[test]
+    fn should_serialize_fee_history() {
+        let history = FeeHistory {
+            oldest_block: 5.into(),
+            gas_used_ratio: vec![0.5, 0.0],
+            gas_price: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&history).unwrap(),
+            r#"{"oldestBlock":"0x5","gasUsedRatio":[0.5,0.0]}"#
+        );
+
+        let history = FeeHistory {
+            gas_price: Some(vec![vec![10.into(), 20.into()], vec![0.into(), 0.into()]]),
+            ..history
+        };
+        assert_eq!(
+            serde_json::to_string(&history).unwrap(),
+            r#"{"oldestBlock":"0x5","gasUsedRatio":[0.5,0.0],"gasPrice":[["0xa","0x14"],["0x0","0x0"]]}"#
+        );
+    }
+}
@@ -79,6 +79,9 @@ pub struct Filter {
     pub topics: Option<Vec<Topic>>,
     /// Limit
     pub limit: Option<usize>,
+    /// Number of blocks a log's block must be buried under before the log is delivered.
+    /// Only used by filter polling and log subscriptions.
+    pub confirmations: Option<u64>,
 }
 
 impl Into<EthFilter> for Filter {
@@ -200,8 +203,13 @@ mod tests {
                 address: None,
                 topics: None,
                 limit: None,
+                confirmations: None,
             }
         );
+
+        let s = r#"{"toBlock":"latest","confirmations":12}"#;
+        let deserialized: Filter = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.confirmations, Some(12));
     }
 
     #[test]
@@ -218,6 +226,7 @@ mod tests {
                 VariadicValue::Null,
             ]),
             limit: None,
+            confirmations: None,
         };
 
         let eth_filter: EthFilter = filter.into();
@@ -58,7 +58,7 @@ impl Serialize for Log {
         S: Serializer,
     {
         let mut log = serializer.serialize_struct("Log", 9)?;
-        log.serialize_field("removed", &false)?;
+        log.serialize_field("removed", &(self.log_type == "removed"))?;
         log.serialize_field("logIndex", &self.log_index)?;
         log.serialize_field("transactionIndex", &self.transaction_index)?;
         log.serialize_field("transactionHash", &self.transaction_hash)?;
@@ -142,5 +142,12 @@ mod tests {
 
         let serialized = serde_json::to_string(&log).unwrap();
         assert_eq!(serialized, s);
+
+        let removed = Log {
+            log_type: "removed".to_owned(),
+            ..log
+        };
+        let serialized = serde_json::to_string(&removed).unwrap();
+        assert_eq!(serialized, s.replace(r#""removed":false"#, r#""removed":true"#));
     }
 }
@@ -27,9 +27,11 @@ mod template_param;
 mod block;
 mod block_number;
 mod bytes;
+mod call_bundle;
 mod call_request;
 mod confirmations;
 mod contract;
+mod fee_history;
 mod filter;
 mod hash;
 mod histogram;
@@ -39,7 +41,10 @@ mod node_kind;
 mod provenance;
 mod receipt;
 mod rpc_settings;
+mod rpc_stats;
 mod secretstore;
+mod state_diff;
+mod state_override;
 mod sync;
 mod transaction;
 mod transaction_request;
@@ -51,15 +56,17 @@ pub mod pubsub;
 
 pub use self::bytes::Bytes;
 pub use self::block::{Block, BlockTransactions, Header};
-pub use self::block_number::BlockNumber;
+pub use self::block_number::{BlockNumber, BlockNumberOrHash};
 pub use self::template_param::TemplateParam;
 pub use self::stratum_header::{SimpleHeader, StratumHeader};
+pub use self::call_bundle::{BundleTransaction, BlockOverride, CallBundleResult};
 pub use self::call_request::CallRequest;
 pub use self::confirmations::{
     ConfirmationPayload, ConfirmationRequest, ConfirmationResponse, ConfirmationResponseWithToken,
     TransactionModification, SignRequest, DecryptRequest
 };
 pub use self::contract::{Contract, ContractInfo, Abi, AbiIO};
+pub use self::fee_history::FeeHistory;
 pub use self::filter::{Filter, FilterChanges};
 pub use self::hash::{H64, H128, H160, H256, H512, H520, H768, H2048};
 pub use self::histogram::Histogram;
@@ -69,7 +76,10 @@ pub use self::node_kind::{NodeKind, Availability, Capability};
 pub use self::provenance::Origin;
 pub use self::receipt::{Receipt, SimpleReceipt, SimpleReceiptLog};
 pub use self::rpc_settings::RpcSettings;
+pub use self::rpc_stats::{RpcStatsReport, MethodStats, Latency, SlowCall};
 pub use self::secretstore::EncryptedDocumentKey;
+pub use self::state_diff::{StateDiff, AccountDiff, Diff, ChangedType};
+pub use self::state_override::{AccountOverride, StateOverride, StorageValue};
 pub use self::sync::{
     SyncStatus, SyncInfo, Peers, PeerInfo, PeerNetworkInfo, TransactionStats, ChainStatus, AcitvePeerInfo, PbSyncInfo
 };
@@ -25,7 +25,7 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::Error;
 use serde_json::{Value, from_value};
-use types::{Header, Filter, Log, H256};
+use types::{Header, Filter, Log, H256, SyncInfo};
 
 /// Subscription result.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -36,6 +36,18 @@ pub enum Result {
     Log(Log),
     /// Transaction hash
     TransactionHash(H256),
+    /// Sync status
+    SyncState(PubSubSyncStatus),
+}
+
+/// Sync status pushed to `syncing` subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PubSubSyncStatus {
+    /// Whether the node is syncing
+    pub syncing: bool,
+    /// Sync progress, present while syncing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<SyncInfo>,
 }
 
 impl Serialize for Result {
@@ -45,6 +57,7 @@ impl Serialize for Result {
             Result::Header(ref header) => header.serialize(serializer),
             Result::Log(ref log) => log.serialize(serializer),
             Result::TransactionHash(ref hash) => hash.serialize(serializer),
+            Result::SyncState(ref status) => status.serialize(serializer),
         }
     }
 }
@@ -98,8 +111,8 @@ impl<'a> Deserialize<'a> for Params {
 #[cfg(test)]
 mod tests {
     use serde_json;
-    use super::{Result, Kind, Params};
-    use types::{Header, Filter};
+    use super::{Result, Kind, Params, PubSubSyncStatus};
+    use types::{Header, Filter, SyncInfo};
     use types::filter::VariadicValue;
 
     #[test]
@@ -141,6 +154,7 @@ mod tests {
                 address: None,
                 topics: None,
                 limit: None,
+                confirmations: None,
             })
         );
         assert_eq!(
@@ -151,6 +165,7 @@ mod tests {
                 address: None,
                 topics: None,
                 limit: Some(10),
+                confirmations: None,
             })
         );
         assert_eq!(
@@ -165,6 +180,7 @@ mod tests {
                         .unwrap()
                 )]),
                 limit: None,
+                confirmations: None,
             })
         );
     }
@@ -192,4 +208,32 @@ mod tests {
         let expected = r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","parentHash":"0x0000000000000000000000000000000000000000000000000000000000000000","miner":"0x0000000000000000000000000000000000000000000000000000000000000000","stateRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","transactionsRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","receiptsRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","number":"0x0","gasUsed":"0x0","gasLimit":"0x0","extraData":"0x","logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","timestamp":"0x0","difficulty":"0x0","nonce":"0x","solution":"0x","size":"0x45"}"#;
         assert_eq!(serde_json::to_string(&header).unwrap(), expected);
     }
+
+    #[test]
+    fn should_serialize_sync_state() {
+        let stopped = Result::SyncState(PubSubSyncStatus {
+            syncing: false,
+            status: None,
+        });
+        assert_eq!(
+            serde_json::to_string(&stopped).unwrap(),
+            r#"{"syncing":false}"#
+        );
+
+        let syncing = Result::SyncState(PubSubSyncStatus {
+            syncing: true,
+            status: Some(SyncInfo {
+                starting_block: "0x0".into(),
+                current_block: "0x10".into(),
+                highest_block: "0x20".into(),
+                sync_speed: 4,
+                peer_count: 2,
+                unverified_queue_size: 1,
+                verifying_queue_size: 0,
+                verified_queue_size: 3,
+            }),
+        });
+        let expected = r#"{"syncing":true,"status":{"startingBlock":"0x0","currentBlock":"0x10","highestBlock":"0x20","syncSpeed":4,"peerCount":2,"unverifiedQueueSize":1,"verifyingQueueSize":0,"verifiedQueueSize":3}}"#;
+        assert_eq!(serde_json::to_string(&syncing).unwrap(), expected);
+    }
 }
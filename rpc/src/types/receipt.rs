@@ -89,6 +89,8 @@ pub struct Receipt {
     pub from: Option<Address>,
     /// to address
     pub to: Option<Address>,
+    /// fee payer address
+    pub fee_payer: Option<Address>,
     /// output
     pub output: Option<Bytes>,
     /// status
@@ -118,6 +120,9 @@ impl Serialize for Receipt {
         receipt.serialize_field("contractAddress", &self.contract_address)?;
         receipt.serialize_field("from", &self.from)?;
         receipt.serialize_field("to", &self.to)?;
+        if self.fee_payer.is_some() {
+            receipt.serialize_field("feePayer", &self.fee_payer)?;
+        }
         receipt.serialize_field("logsBloom", &self.logs_bloom.0.to_hex())?;
         receipt.serialize_field("root", &self.state_root.clone().map(|x| x.0.to_hex()))?;
         receipt.serialize_field("status", &self.status)?;
@@ -157,6 +162,7 @@ impl From<LocalizedReceipt> for Receipt {
             gas_limit: Some(r.gas_limit.into()),
             from: r.from.into(),
             to: r.to.into(),
+            fee_payer: r.fee_payer,
             output: Some(r.output.into()),
             status: match r.error_message.as_str() {
                 "" => Some(String::from("0x1")),
@@ -183,6 +189,7 @@ impl From<RichReceipt> for Receipt {
             gas_limit: None,
             from: None,
             to: None,
+            fee_payer: None,
             output: None,
             status: None,
         }
@@ -206,6 +213,7 @@ impl From<EthReceipt> for Receipt {
             gas_limit: None,
             from: None,
             to: None,
+            fee_payer: None,
             output: None,
             status: None,
         }
@@ -272,6 +280,7 @@ mod tests {
             ),
             gas_limit: Some(0x10.into()),
             gas_price: Some(0x10.into()),
+            fee_payer: None,
             output: Some(Bytes::new(vec![])),
             status: None,
         };
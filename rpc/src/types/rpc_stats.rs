@@ -0,0 +1,110 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! RPC statistics.
+
+use types::H256;
+
+/// Statistics of the RPC servers, as returned by `rpc_stats`. Durations are in microseconds.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct RpcStatsReport {
+    /// Number of open sessions.
+    pub sessions: usize,
+    /// Requests per second.
+    #[serde(rename = "requestsRate")]
+    pub requests_rate: usize,
+    /// Approximated median roundtrip of requests.
+    #[serde(rename = "approximatedRoundtrip")]
+    pub approximated_roundtrip: u32,
+    /// Statistics of each method, per transport.
+    pub methods: Vec<MethodStats>,
+    /// Most recent calls which took longer than the slow call threshold.
+    #[serde(rename = "slowCalls")]
+    pub slow_calls: Vec<SlowCall>,
+}
+
+/// Statistics of the calls to a method over a transport.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct MethodStats {
+    /// Method name.
+    pub method: String,
+    /// Transport the method was called over: `http`, `ws`, `ipc` or `pb`.
+    pub transport: String,
+    /// Number of calls.
+    pub calls: u64,
+    /// Number of calls which failed.
+    pub errors: u64,
+    /// Latency percentiles.
+    pub latency: Latency,
+}
+
+/// Latency percentiles, in microseconds. These are upper bounds, exact within a factor of two.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Latency {
+    /// Median.
+    pub p50: u64,
+    /// 90th percentile.
+    pub p90: u64,
+    /// 99th percentile.
+    pub p99: u64,
+}
+
+/// A call which took longer than the slow call threshold.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct SlowCall {
+    /// Method name.
+    pub method: String,
+    /// Transport the method was called over.
+    pub transport: String,
+    /// Blake2b digest of the params of the call.
+    #[serde(rename = "paramsDigest")]
+    pub params_digest: H256,
+    /// Duration of the call.
+    pub duration: u64,
+    /// Unix time the call completed at.
+    pub timestamp: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use super::{Latency, MethodStats};
+
+    #[test]
+    fn should_serialize_method_stats() {
+        let stats = MethodStats {
+            method: "eth_call".into(),
+            transport: "http".into(),
+            calls: 3,
+            errors: 1,
+            latency: Latency {
+                p50: 127,
+                p90: 255,
+                p99: 255,
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&stats).unwrap(),
+            r#"{"method":"eth_call","transport":"http","calls":3,"errors":1,"latency":{"p50":127,"p90":255,"p99":255}}"#
+        );
+    }
+}
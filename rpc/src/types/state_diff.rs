@@ -0,0 +1,174 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+use std::collections::BTreeMap;
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+use acore::account_diff;
+use acore::state_diff;
+use types::{Bytes, H128, H256, U256};
+
+/// Change of a value between two states
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diff<T> {
+    /// Unchanged
+    Same,
+    /// Created
+    Born(T),
+    /// Removed
+    Died(T),
+    /// Changed
+    Changed(ChangedType<T>),
+}
+
+/// Previous and next value of a change
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedType<T> {
+    /// Previous value
+    pub from: T,
+    /// Next value
+    pub to: T,
+}
+
+impl<T, U> From<account_diff::Diff<U>> for Diff<T>
+where
+    T: From<U>,
+    U: Eq,
+{
+    fn from(c: account_diff::Diff<U>) -> Self {
+        match c {
+            account_diff::Diff::Same => Diff::Same,
+            account_diff::Diff::Born(t) => Diff::Born(t.into()),
+            account_diff::Diff::Died(t) => Diff::Died(t.into()),
+            account_diff::Diff::Changed(from, to) => {
+                Diff::Changed(ChangedType {
+                    from: from.into(),
+                    to: to.into(),
+                })
+            }
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Diff<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        match *self {
+            Diff::Same => serializer.serialize_str("="),
+            Diff::Born(ref t) => {
+                let mut diff = serializer.serialize_struct("Diff", 1)?;
+                diff.serialize_field("+", t)?;
+                diff.end()
+            }
+            Diff::Died(ref t) => {
+                let mut diff = serializer.serialize_struct("Diff", 1)?;
+                diff.serialize_field("-", t)?;
+                diff.end()
+            }
+            Diff::Changed(ref t) => {
+                let mut diff = serializer.serialize_struct("Diff", 1)?;
+                diff.serialize_field("*", t)?;
+                diff.end()
+            }
+        }
+    }
+}
+
+/// Account diff
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountDiff {
+    /// Balance
+    pub balance: Diff<U256>,
+    /// Nonce
+    pub nonce: Diff<U256>,
+    /// Code
+    pub code: Diff<Bytes>,
+    /// Storage
+    pub storage: BTreeMap<H128, Diff<H128>>,
+    /// Double word storage
+    #[serde(rename = "storageDword", skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage_dword: BTreeMap<H128, Diff<H256>>,
+}
+
+impl From<account_diff::AccountDiff> for AccountDiff {
+    fn from(c: account_diff::AccountDiff) -> Self {
+        AccountDiff {
+            balance: c.balance.into(),
+            nonce: c.nonce.into(),
+            code: c.code.into(),
+            storage: c
+                .storage
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+            storage_dword: c
+                .storage_dword
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+/// State diff, account diffs by address
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StateDiff(pub BTreeMap<H256, AccountDiff>);
+
+impl From<state_diff::StateDiff> for StateDiff {
+    fn from(c: state_diff::StateDiff) -> Self {
+        StateDiff(
+            c.raw
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde_json;
+    use types::{H128, U256};
+    use super::{AccountDiff, ChangedType, Diff};
+
+    #[test]
+    fn account_diff_serialize() {
+        let mut storage = BTreeMap::new();
+        storage.insert(H128::from(1), Diff::Born(H128::from(2)));
+        let diff = AccountDiff {
+            balance: Diff::Changed(ChangedType {
+                from: U256::from(1),
+                to: U256::from(2),
+            }),
+            nonce: Diff::Same,
+            code: Diff::Died(vec![0x60].into()),
+            storage: storage,
+            storage_dword: BTreeMap::new(),
+        };
+        let serialized = serde_json::to_string(&diff).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"balance":{"*":{"from":"0x1","to":"0x2"}},"nonce":"=","code":{"-":"0x60"},"storage":{"0x00000000000000000000000000000001":{"+":"0x00000000000000000000000000000002"}}}"#
+        );
+    }
+}
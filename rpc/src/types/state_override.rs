@@ -0,0 +1,163 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+use std::collections::BTreeMap;
+use aion_types::H128 as EthH128;
+use acore::client::{
+    AccountOverride as EthAccountOverride, StateOverride as EthStateOverride,
+    StorageValue as EthStorageValue,
+};
+use types::{Bytes, H128, H256, U256};
+
+/// Storage slot value, 16 bytes for FastVM contracts or 32 bytes for AVM contracts
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum StorageValue {
+    /// Word
+    Word(H128),
+    /// Double word
+    DWord(H256),
+}
+
+impl Into<EthStorageValue> for StorageValue {
+    fn into(self) -> EthStorageValue {
+        match self {
+            StorageValue::Word(value) => EthStorageValue::Word(value.into()),
+            StorageValue::DWord(value) => EthStorageValue::DWord(value.into()),
+        }
+    }
+}
+
+/// Account override
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountOverride {
+    /// Balance
+    pub balance: Option<U256>,
+    /// Nonce
+    pub nonce: Option<U256>,
+    /// Code
+    pub code: Option<Bytes>,
+    /// Full storage, replacing all slots of the account
+    pub state: Option<BTreeMap<H128, StorageValue>>,
+    /// Storage slots to replace, keeping the others
+    #[serde(rename = "stateDiff")]
+    pub state_diff: Option<BTreeMap<H128, StorageValue>>,
+}
+
+impl Into<EthAccountOverride> for AccountOverride {
+    fn into(self) -> EthAccountOverride {
+        fn storage(slots: BTreeMap<H128, StorageValue>) -> BTreeMap<EthH128, EthStorageValue> {
+            slots.into_iter().map(|(k, v)| (k.into(), v.into())).collect()
+        }
+
+        EthAccountOverride {
+            balance: self.balance.map(Into::into),
+            nonce: self.nonce.map(Into::into),
+            code: self.code.map(Into::into),
+            state: self.state.map(storage),
+            state_diff: self.state_diff.map(storage),
+        }
+    }
+}
+
+/// State override, account overrides by address
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct StateOverride(pub BTreeMap<H256, AccountOverride>);
+
+impl Into<EthStateOverride> for StateOverride {
+    fn into(self) -> EthStateOverride {
+        self.0
+            .into_iter()
+            .map(|(address, account)| (address.into(), account.into()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde_json;
+    use types::{H128, H256, U256};
+    use super::{AccountOverride, StateOverride, StorageValue};
+
+    #[test]
+    fn state_override_deserialize() {
+        let s = r#"{
+            "0x0000000000000000000000000000000000000000000000000000000000000001": {
+                "balance": "0x10",
+                "nonce": "0x2",
+                "code": "0x6000"
+            },
+            "0x0000000000000000000000000000000000000000000000000000000000000002": {
+                "stateDiff": {
+                    "0x00000000000000000000000000000001": "0x00000000000000000000000000000005",
+                    "0x00000000000000000000000000000002": "0x0000000000000000000000000000000000000000000000000000000000000006"
+                }
+            }
+        }"#;
+        let deserialized: StateOverride = serde_json::from_str(s).unwrap();
+
+        let mut state_diff = BTreeMap::new();
+        state_diff.insert(H128::from(1), StorageValue::Word(H128::from(5)));
+        state_diff.insert(H128::from(2), StorageValue::DWord(H256::from(6)));
+        let mut expected = BTreeMap::new();
+        expected.insert(
+            H256::from(1),
+            AccountOverride {
+                balance: Some(U256::from(0x10)),
+                nonce: Some(U256::from(2)),
+                code: Some(vec![0x60, 0x00].into()),
+                state: None,
+                state_diff: None,
+            },
+        );
+        expected.insert(
+            H256::from(2),
+            AccountOverride {
+                state_diff: Some(state_diff),
+                ..Default::default()
+            },
+        );
+        assert_eq!(deserialized, StateOverride(expected));
+    }
+
+    #[test]
+    fn state_override_rejects_other_storage_value_sizes() {
+        let s = r#"{
+            "0x0000000000000000000000000000000000000000000000000000000000000001": {
+                "state": {"0x00000000000000000000000000000001": "0x0005"}
+            }
+        }"#;
+        let deserialized: Result<StateOverride, _> = serde_json::from_str(s);
+        assert!(deserialized.is_err());
+    }
+
+    #[test]
+    fn state_override_rejects_unknown_fields() {
+        let s = r#"{
+            "0x0000000000000000000000000000000000000000000000000000000000000001": {"storage": {}}
+        }"#;
+        let deserialized: Result<StateOverride, _> = serde_json::from_str(s);
+        assert!(deserialized.is_err());
+    }
+}
@@ -25,7 +25,7 @@ use serde::{Serialize, Serializer};
 use types::{U256, H512};
 
 /// Sync info
-#[derive(Default, Debug, Serialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, PartialEq, Eq)]
 pub struct SyncInfo {
     /// Starting block, hex representation
     #[serde(rename = "startingBlock")]
@@ -36,6 +36,21 @@ pub struct SyncInfo {
     /// Highest block seen so far, hex representation
     #[serde(rename = "highestBlock")]
     pub highest_block: String,
+    /// Blocks imported per second
+    #[serde(rename = "syncSpeed")]
+    pub sync_speed: u16,
+    /// Number of connected peers
+    #[serde(rename = "peerCount")]
+    pub peer_count: usize,
+    /// Blocks waiting for verification
+    #[serde(rename = "unverifiedQueueSize")]
+    pub unverified_queue_size: usize,
+    /// Blocks being verified
+    #[serde(rename = "verifyingQueueSize")]
+    pub verifying_queue_size: usize,
+    /// Verified blocks waiting for import
+    #[serde(rename = "verifiedQueueSize")]
+    pub verified_queue_size: usize,
 }
 
 /// Peers info
@@ -93,6 +108,8 @@ pub struct AcitvePeerInfo {
     pub id: String,
     /// remote ip
     pub ip: String,
+    /// reputation score
+    pub reputation: i32,
 }
 
 ///sync info use by pb
@@ -150,7 +167,7 @@ mod tests {
         let serialized = serde_json::to_string(&t).unwrap();
         assert_eq!(
             serialized,
-            r#"{"startingBlock":"","currentBlock":"","highestBlock":""}"#
+            r#"{"startingBlock":"","currentBlock":"","highestBlock":"","syncSpeed":0,"peerCount":0,"unverifiedQueueSize":0,"verifyingQueueSize":0,"verifiedQueueSize":0}"#
         );
     }
 
@@ -174,7 +191,7 @@ mod tests {
         let serialized = serde_json::to_string(&t).unwrap();
         assert_eq!(
             serialized,
-            r#"{"startingBlock":"","currentBlock":"","highestBlock":""}"#
+            r#"{"startingBlock":"","currentBlock":"","highestBlock":"","syncSpeed":0,"peerCount":0,"unverifiedQueueSize":0,"verifyingQueueSize":0,"verifiedQueueSize":0}"#
         );
     }
 
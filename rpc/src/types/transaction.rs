@@ -42,6 +42,8 @@ pub struct Transaction {
     pub transaction_index: Option<U256>,
     /// Sender
     pub from: H256,
+    /// Fee payer of a fee-delegated transaction
+    pub fee_payer: Option<H256>,
     /// Recipient
     pub to: Option<H256>,
     /// Transfered value
@@ -93,6 +95,9 @@ impl Serialize for Transaction {
         }
         transaction.serialize_field("to", &self.to)?;
         transaction.serialize_field("from", &self.from)?;
+        if self.fee_payer.is_some() {
+            transaction.serialize_field("feePayer", &self.fee_payer)?;
+        }
         transaction.serialize_field("value", &self.value)?;
         transaction.serialize_field("gasPrice", &self.gas_price)?;
         transaction.serialize_field("gas", &to_u256(self.gas).low_u64())?;
@@ -151,6 +156,7 @@ impl Transaction {
             block_number: Some(t.block_number.into()),
             transaction_index: Some(t.transaction_index.into()),
             from: t.sender().into(),
+            fee_payer: t.fee_payer().map(Into::into),
             to: match t.action {
                 Action::Create => None,
                 Action::Call(ref address) => Some(address.clone().into()),
@@ -188,6 +194,7 @@ impl Transaction {
             block_number: None,
             transaction_index: None,
             from: t.sender().into(),
+            fee_payer: t.fee_payer().map(Into::into),
             to: match t.action {
                 Action::Create => None,
                 Action::Call(ref address) => Some(address.clone().into()),
@@ -231,6 +238,16 @@ mod tests {
         assert_eq!(serialized, r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","nonce":0,"blockHash":null,"blockNumber":null,"transactionIndex":null,"to":null,"from":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x0","gasPrice":"0x0","gas":0,"nrgPrice":"0x0","nrg":0,"input":"0x","contractAddress":null,"timestamp":0}"#);
     }
 
+    #[test]
+    fn test_fee_delegated_transaction_serialize() {
+        let mut t = Transaction::default();
+        t.fee_payer = Some(::aion_types::H256::from(0x10).into());
+        let serialized = serde_json::to_string(&t).unwrap();
+        assert!(serialized.contains(
+            r#""feePayer":"0x0000000000000000000000000000000000000000000000000000000000000010""#
+        ));
+    }
+
     #[test]
     fn test_transaction_serialize2() {
         let mut t = Transaction::default();
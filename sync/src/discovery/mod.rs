@@ -0,0 +1,526 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Kademlia style peer discovery over udp.
+//!
+//! Every node runs a discovery service on the udp port matching its tcp port. Nodes ping each
+//! other to prove they own their address and the key they sign with; a node which answered is
+//! added to the routing table and may ask for the neighbours of any key. Lookups repeatedly ask
+//! the closest known nodes for nodes even closer to a target, first the local key and then
+//! random ones, filling the table with nodes spread over the whole key space rather than only
+//! those around the boot nodes.
+//!
+//! Packets from a node id already in the table under another key are dropped. A node restarted
+//! with a new key gets back into the tables of others once its old entry fails a liveness check.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use aion_types::H256;
+use blake2b::blake2b;
+use key::Ed25519KeyPair;
+use p2p::unix_now;
+use rand::random;
+
+pub mod packet;
+pub mod table;
+
+pub use self::packet::{Body, Packet, Received, MAX_PACKET_SIZE};
+pub use self::table::{node_key, Insert, NodeEntry, NodeTable, BUCKET_SIZE};
+
+/// Nodes asked for neighbours at once during a lookup.
+pub const ALPHA: usize = 3;
+/// Time a ping or a find node is waited for.
+pub const REQUEST_TIMEOUT_MS: u64 = 2000;
+/// Time a node which answered a ping may ask for neighbours.
+pub const BOND_EXPIRATION_SECS: u64 = 12 * 60 * 60;
+/// Pause between two lookups.
+pub const LOOKUP_INTERVAL_SECS: u64 = 30;
+/// Pause between two liveness checks of the least recently seen node of a bucket.
+pub const REVALIDATE_INTERVAL_SECS: u64 = 10;
+/// Period of the discovery timer.
+pub const TICK_INTERVAL_MS: u64 = 500;
+
+/// A packet to send, with its destination.
+pub type Outgoing = (SocketAddr, Vec<u8>);
+
+struct PendingPing {
+    node_id: String,
+    addr: SocketAddr,
+    deadline: Instant,
+    /// Key of the table node being checked, dropped from the table if it does not answer.
+    revalidating: Option<H256>,
+}
+
+struct Lookup {
+    target: H256,
+    asked: HashSet<H256>,
+}
+
+/// Discovery protocol state, free of any io: packets received and timer ticks go in, packets
+/// to send come out.
+pub struct Discovery {
+    node_id: String,
+    key: H256,
+    keypair: Ed25519KeyPair,
+    table: NodeTable,
+    seeds: Vec<(String, SocketAddr)>,
+    /// Pings waiting for a pong, by packet hash.
+    pings: HashMap<H256, PendingPing>,
+    /// Nodes asked for neighbours, with the time their answer is due.
+    find_nodes: HashMap<SocketAddr, Instant>,
+    /// Nodes which answered a ping, with the time they did.
+    bonds: HashMap<H256, Instant>,
+    lookup: Option<Lookup>,
+    lookups: u64,
+    next_lookup: Instant,
+    next_revalidation: Instant,
+    outgoing: Vec<Outgoing>,
+}
+
+impl Discovery {
+    pub fn new(node_id: &str, keypair: Ed25519KeyPair, now: Instant) -> Self {
+        let key = node_key(node_id);
+        Discovery {
+            node_id: node_id.to_owned(),
+            key: key,
+            keypair: keypair,
+            table: NodeTable::new(key),
+            seeds: Vec::new(),
+            pings: HashMap::new(),
+            find_nodes: HashMap::new(),
+            bonds: HashMap::new(),
+            lookup: None,
+            lookups: 0,
+            next_lookup: now,
+            next_revalidation: now + Duration::from_secs(REVALIDATE_INTERVAL_SECS),
+            outgoing: Vec::new(),
+        }
+    }
+
+    /// Add a node to start from, pinged whenever the table is empty.
+    pub fn add_seed(&mut self, node_id: &str, addr: SocketAddr) {
+        if node_id != self.node_id {
+            self.seeds.push((node_id.to_owned(), addr));
+        }
+    }
+
+    pub fn len(&self) -> usize { self.table.len() }
+
+    /// Nodes in the routing table.
+    pub fn nodes(&self) -> Vec<NodeEntry> { self.table.entries() }
+
+    /// Handle a received packet.
+    pub fn on_packet(&mut self, from: SocketAddr, data: &[u8], now: Instant) -> Vec<Outgoing> {
+        let received = match Packet::decode(data) {
+            Some(received) => received,
+            None => {
+                trace!(target: "net", "Invalid discovery packet from {}", from);
+                return Vec::new();
+            }
+        };
+        let Received {
+            packet,
+            public,
+            hash,
+        } = received;
+        if packet.expiration < unix_now() || packet.node_id == self.node_id {
+            return Vec::new();
+        }
+        let key = node_key(&packet.node_id);
+        let known_public = self.table.get(&key).map(|entry| entry.public);
+        if known_public.map_or(false, |known_public| known_public != public) {
+            debug!(target: "net", "Discovery packet from {}@{} signed with an unknown key", packet.node_id, from);
+            return Vec::new();
+        }
+
+        match packet.body {
+            Body::Ping => {
+                self.send(
+                    from,
+                    Body::Pong {
+                        ping_hash: hash,
+                    },
+                );
+                if known_public.is_some() {
+                    self.add_node(NodeEntry::new(&packet.node_id, public, from), now);
+                } else {
+                    self.ping(&packet.node_id, from, None, now);
+                }
+            }
+            Body::Pong {
+                ping_hash,
+            } => {
+                let expected = match self.pings.get(&ping_hash) {
+                    Some(ping) => ping.addr == from && ping.node_id == packet.node_id,
+                    None => false,
+                };
+                if expected {
+                    self.pings.remove(&ping_hash);
+                    self.bonds.insert(key, now);
+                    self.add_node(NodeEntry::new(&packet.node_id, public, from), now);
+                }
+            }
+            Body::FindNode {
+                target,
+            } => {
+                if self.is_bonded(&key, now) {
+                    let nodes = self
+                        .table
+                        .closest(&target, BUCKET_SIZE + 1)
+                        .into_iter()
+                        .filter(|entry| entry.key != key)
+                        .take(BUCKET_SIZE)
+                        .map(|entry| (entry.node_id, entry.addr))
+                        .collect();
+                    self.send(
+                        from,
+                        Body::Neighbours {
+                            nodes: nodes,
+                        },
+                    );
+                }
+            }
+            Body::Neighbours {
+                nodes,
+            } => {
+                let asked = self
+                    .find_nodes
+                    .get(&from)
+                    .map_or(false, |deadline| *deadline >= now);
+                if asked {
+                    for (node_id, addr) in nodes.into_iter().take(BUCKET_SIZE) {
+                        let known = self.table.get(&node_key(&node_id)).is_some();
+                        if node_id != self.node_id && !known {
+                            self.ping(&node_id, addr, None, now);
+                        }
+                    }
+                }
+            }
+        }
+        self.outgoing.drain(..).collect()
+    }
+
+    /// Drive timeouts, liveness checks and lookups.
+    pub fn tick(&mut self, now: Instant) -> Vec<Outgoing> {
+        let expired: Vec<H256> = self
+            .pings
+            .iter()
+            .filter(|&(_, ping)| ping.deadline < now)
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in expired {
+            if let Some(ping) = self.pings.remove(&hash) {
+                if let Some(key) = ping.revalidating {
+                    debug!(target: "net", "Discovery node {}@{} dropped, not answering", ping.node_id, ping.addr);
+                    self.table.remove(&key);
+                    self.bonds.remove(&key);
+                }
+            }
+        }
+        self.find_nodes.retain(|_, deadline| *deadline >= now);
+
+        if now >= self.next_revalidation {
+            self.next_revalidation = now + Duration::from_secs(REVALIDATE_INTERVAL_SECS);
+            let buckets = self.table.filled_buckets();
+            if !buckets.is_empty() {
+                let index = buckets[random::<usize>() % buckets.len()];
+                let oldest = self.table.oldest(index).cloned();
+                if let Some(oldest) = oldest {
+                    self.ping(&oldest.node_id, oldest.addr, Some(oldest.key), now);
+                }
+            }
+        }
+
+        if self.lookup.is_none() && now >= self.next_lookup {
+            if self.table.len() == 0 {
+                for (node_id, addr) in self.seeds.clone() {
+                    self.ping(&node_id, addr, None, now);
+                }
+                self.next_lookup = now + Duration::from_millis(REQUEST_TIMEOUT_MS);
+            } else {
+                // the first lookup is for the local key, filling the nearby buckets.
+                let target = if self.lookups == 0 {
+                    self.key
+                } else {
+                    H256::random()
+                };
+                self.lookups += 1;
+                self.lookup = Some(Lookup {
+                    target: target,
+                    asked: HashSet::new(),
+                });
+            }
+        }
+        self.continue_lookup(now);
+
+        self.outgoing.drain(..).collect()
+    }
+
+    // ask the closest nodes not asked yet, ending the lookup once all were asked and answered.
+    fn continue_lookup(&mut self, now: Instant) {
+        let candidates: Vec<NodeEntry> = match self.lookup {
+            Some(ref lookup) => {
+                self.table
+                    .closest(&lookup.target, BUCKET_SIZE)
+                    .into_iter()
+                    .filter(|entry| !lookup.asked.contains(&entry.key))
+                    .take(ALPHA.saturating_sub(self.find_nodes.len()))
+                    .collect()
+            }
+            None => return,
+        };
+
+        if candidates.is_empty() {
+            if self.find_nodes.is_empty() {
+                self.lookup = None;
+                self.next_lookup = now + Duration::from_secs(LOOKUP_INTERVAL_SECS);
+            }
+            return;
+        }
+
+        let target = match self.lookup {
+            Some(ref mut lookup) => {
+                for entry in candidates.iter() {
+                    lookup.asked.insert(entry.key);
+                }
+                lookup.target
+            }
+            None => return,
+        };
+        for entry in candidates {
+            self.find_nodes
+                .insert(entry.addr, now + Duration::from_millis(REQUEST_TIMEOUT_MS));
+            self.send(
+                entry.addr,
+                Body::FindNode {
+                    target: target,
+                },
+            );
+        }
+    }
+
+    fn add_node(&mut self, entry: NodeEntry, now: Instant) {
+        match self.table.insert(entry) {
+            Insert::Full(oldest) => self.ping(&oldest.node_id, oldest.addr, Some(oldest.key), now),
+            Insert::Added | Insert::Updated | Insert::Conflict | Insert::Local => {}
+        }
+    }
+
+    fn is_bonded(&self, key: &H256, now: Instant) -> bool {
+        self.bonds.get(key).map_or(false, |bonded| {
+            now.duration_since(*bonded) < Duration::from_secs(BOND_EXPIRATION_SECS)
+        })
+    }
+
+    fn ping(&mut self, node_id: &str, addr: SocketAddr, revalidating: Option<H256>, now: Instant) {
+        if self.pings.values().any(|ping| ping.addr == addr) {
+            return;
+        }
+        let ping = Packet::new(&self.node_id, Body::Ping, unix_now());
+        if let Some(data) = ping.encode(self.keypair.secret()) {
+            self.pings.insert(
+                blake2b(&data),
+                PendingPing {
+                    node_id: node_id.to_owned(),
+                    addr: addr,
+                    deadline: now + Duration::from_millis(REQUEST_TIMEOUT_MS),
+                    revalidating: revalidating,
+                },
+            );
+            self.outgoing.push((addr, data));
+        }
+    }
+
+    fn send(&mut self, addr: SocketAddr, body: Body) {
+        let packet = Packet::new(&self.node_id, body, unix_now());
+        if let Some(data) = packet.encode(self.keypair.secret()) {
+            self.outgoing.push((addr, data));
+        }
+    }
+}
+
+/// A running discovery service.
+pub struct DiscoveryService {
+    local_addr: SocketAddr,
+    running: Arc<AtomicBool>,
+}
+
+impl DiscoveryService {
+    /// Address the service is bound to.
+    pub fn local_addr(&self) -> SocketAddr { self.local_addr }
+
+    /// Stop the service, its socket is closed within a timer period.
+    pub fn stop(&self) { self.running.store(false, Ordering::SeqCst); }
+}
+
+/// Bind a udp socket and run discovery on it in a thread of its own.
+pub fn start(discovery: Arc<Mutex<Discovery>>, addr: &SocketAddr) -> io::Result<DiscoveryService> {
+    let socket = UdpSocket::bind(addr)?;
+    socket.set_read_timeout(Some(Duration::from_millis(TICK_INTERVAL_MS)))?;
+    let local_addr = socket.local_addr()?;
+    let running = Arc::new(AtomicBool::new(true));
+
+    let thread_running = running.clone();
+    thread::Builder::new()
+        .name("discovery".into())
+        .spawn(move || {
+            let mut buf = [0u8; MAX_PACKET_SIZE];
+            let mut next_tick = Instant::now();
+            while thread_running.load(Ordering::SeqCst) {
+                match socket.recv_from(&mut buf) {
+                    Ok((len, from)) => {
+                        let outgoing = match discovery.lock() {
+                            Ok(mut discovery) => {
+                                discovery.on_packet(from, &buf[..len], Instant::now())
+                            }
+                            Err(_) => Vec::new(),
+                        };
+                        send(&socket, outgoing);
+                    }
+                    Err(ref e)
+                        if e.kind() == io::ErrorKind::WouldBlock
+                            || e.kind() == io::ErrorKind::TimedOut => {}
+                    Err(e) => trace!(target: "net", "Discovery receive failed: {}", e),
+                }
+
+                let now = Instant::now();
+                if now >= next_tick {
+                    next_tick = now + Duration::from_millis(TICK_INTERVAL_MS);
+                    let outgoing = match discovery.lock() {
+                        Ok(mut discovery) => discovery.tick(now),
+                        Err(_) => Vec::new(),
+                    };
+                    send(&socket, outgoing);
+                }
+            }
+        })?;
+
+    Ok(DiscoveryService {
+        local_addr: local_addr,
+        running: running,
+    })
+}
+
+fn send(socket: &UdpSocket, outgoing: Vec<Outgoing>) {
+    for (addr, data) in outgoing {
+        if let Err(e) = socket.send_to(&data, &addr) {
+            trace!(target: "net", "Discovery send to {} failed: {}", addr, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use key::generate_keypair;
+
+    fn discovery(i: usize, now: Instant) -> (Discovery, SocketAddr) {
+        let node_id = format!("c33d1066-8c7e-496c-9c4e-{:012}", i);
+        let addr = format!("127.0.0.1:{}", 30000 + i).parse().unwrap();
+        (Discovery::new(&node_id, generate_keypair(), now), addr)
+    }
+
+    // deliver packets between the nodes until none is left.
+    fn deliver(
+        nodes: &mut Vec<(Discovery, SocketAddr)>,
+        mut queue: Vec<(SocketAddr, Outgoing)>,
+        now: Instant,
+    )
+    {
+        while !queue.is_empty() {
+            let (from, (to, data)) = queue.remove(0);
+            if let Some(node) = nodes.iter_mut().find(|node| node.1 == to) {
+                let outgoing = node.0.on_packet(from, &data, now);
+                queue.extend(outgoing.into_iter().map(|packet| (to, packet)));
+            }
+        }
+    }
+
+    #[test]
+    fn nodes_find_each_other_through_a_seed() {
+        let now = Instant::now();
+        let mut nodes: Vec<(Discovery, SocketAddr)> = (0..6).map(|i| discovery(i, now)).collect();
+        let seed_id = nodes[0].0.node_id.clone();
+        let seed_addr = nodes[0].1;
+        for node in nodes.iter_mut().skip(1) {
+            node.0.add_seed(&seed_id, seed_addr);
+        }
+
+        for round in 0..10 {
+            let now = now + Duration::from_millis(TICK_INTERVAL_MS * round);
+            let mut queue = Vec::new();
+            for node in nodes.iter_mut() {
+                let from = node.1;
+                queue.extend(node.0.tick(now).into_iter().map(|packet| (from, packet)));
+            }
+            deliver(&mut nodes, queue, now);
+        }
+
+        for node in nodes.iter() {
+            assert_eq!(node.0.len(), 5);
+        }
+    }
+
+    #[test]
+    fn unanswered_nodes_are_dropped() {
+        let now = Instant::now();
+        let (mut a, a_addr) = discovery(0, now);
+        let (mut b, b_addr) = discovery(1, now);
+        b.add_seed(&a.node_id.clone(), a_addr);
+
+        let mut nodes = vec![(a, a_addr), (b, b_addr)];
+        let queue = nodes[1].0.tick(now).into_iter().map(|packet| (b_addr, packet)).collect();
+        deliver(&mut nodes, queue, now);
+        assert_eq!(nodes[0].0.len(), 1);
+        assert_eq!(nodes[1].0.len(), 1);
+
+        // b goes away, a checks it and drops it once the ping timed out.
+        a = nodes.remove(0).0;
+        let later = now + Duration::from_secs(REVALIDATE_INTERVAL_SECS);
+        let outgoing = a.tick(later);
+        assert!(outgoing.iter().any(|&(to, _)| to == b_addr));
+        a.tick(later + Duration::from_millis(REQUEST_TIMEOUT_MS + 1));
+        assert_eq!(a.len(), 0);
+    }
+
+    #[test]
+    fn find_node_needs_a_bond() {
+        let now = Instant::now();
+        let (mut a, _) = discovery(0, now);
+        let (b, b_addr) = discovery(1, now);
+        let find_node = Packet::new(
+            &b.node_id,
+            Body::FindNode {
+                target: H256::random(),
+            },
+            unix_now(),
+        );
+        let data = find_node.encode(b.keypair.secret()).unwrap();
+        assert!(a.on_packet(b_addr, &data, now).is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Discovery packets.
+//!
+//! A packet is the signature of the blake2b hash of its payload, 96 bytes starting with the
+//! sender's public key, followed by the payload: the RLP list `[type, node_id, expiration, ..]`.
+//! Packets past their expiration, in unix seconds, are dropped so they cannot be replayed.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use aion_types::{Ed25519Public, H256};
+use blake2b::blake2b;
+use key::{recover_ed25519, sign_ed25519, Ed25519Secret, Ed25519Signature};
+use p2p::NODE_ID_LENGTH;
+use rlp::{DecoderError, RlpStream, UntrustedRlp};
+
+pub const SIGNATURE_LENGTH: usize = 96;
+/// Largest packet sent or accepted.
+pub const MAX_PACKET_SIZE: usize = 1280;
+/// Lifetime of a packet.
+pub const EXPIRATION_SECS: u64 = 20;
+
+const PING: u8 = 1;
+const PONG: u8 = 2;
+const FIND_NODE: u8 = 3;
+const NEIGHBOURS: u8 = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Body {
+    /// Liveness check, answered with a `Pong`.
+    Ping,
+    /// Answer to the ping with the given packet hash.
+    Pong {
+        ping_hash: H256,
+    },
+    /// Ask for the nodes closest to a key.
+    FindNode {
+        target: H256,
+    },
+    /// Nodes closest to the key asked for, by node id and address.
+    Neighbours {
+        nodes: Vec<(String, SocketAddr)>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    /// Node id of the sender.
+    pub node_id: String,
+    pub expiration: u64,
+    pub body: Body,
+}
+
+/// A packet with a valid signature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Received {
+    pub packet: Packet,
+    /// Key the packet was signed with.
+    pub public: Ed25519Public,
+    /// Hash of the whole packet, by which a `Pong` refers to its `Ping`.
+    pub hash: H256,
+}
+
+impl Packet {
+    pub fn new(node_id: &str, body: Body, now: u64) -> Self {
+        Packet {
+            node_id: node_id.to_owned(),
+            expiration: now + EXPIRATION_SECS,
+            body: body,
+        }
+    }
+
+    /// Sign the packet. Neighbours with an address other than ipv4 are left out.
+    pub fn encode(&self, secret: &Ed25519Secret) -> Option<Vec<u8>> {
+        let mut stream = RlpStream::new();
+        match self.body {
+            Body::Ping => {
+                stream.begin_list(3);
+                self.append_header(&mut stream, PING);
+            }
+            Body::Pong {
+                ref ping_hash,
+            } => {
+                stream.begin_list(4);
+                self.append_header(&mut stream, PONG);
+                stream.append(ping_hash);
+            }
+            Body::FindNode {
+                ref target,
+            } => {
+                stream.begin_list(4);
+                self.append_header(&mut stream, FIND_NODE);
+                stream.append(target);
+            }
+            Body::Neighbours {
+                ref nodes,
+            } => {
+                let nodes: Vec<(&String, Ipv4Addr, u16)> = nodes
+                    .iter()
+                    .filter_map(|&(ref node_id, ref addr)| {
+                        match addr.ip() {
+                            IpAddr::V4(ip) => Some((node_id, ip, addr.port())),
+                            IpAddr::V6(_) => None,
+                        }
+                    })
+                    .collect();
+                stream.begin_list(4);
+                self.append_header(&mut stream, NEIGHBOURS);
+                stream.begin_list(nodes.len());
+                for &(node_id, ip, port) in nodes.iter() {
+                    stream.begin_list(3);
+                    stream.append(node_id);
+                    stream.append(&ip.octets().to_vec());
+                    stream.append(&port);
+                }
+            }
+        }
+
+        let payload = stream.out();
+        let signature = sign_ed25519(secret, &blake2b(&payload)).ok()?;
+        let mut data = signature.to_vec();
+        data.extend_from_slice(&payload);
+        if data.len() > MAX_PACKET_SIZE {
+            return None;
+        }
+        Some(data)
+    }
+
+    /// Check the signature of a packet and decode it. Expiration is left to the caller.
+    pub fn decode(data: &[u8]) -> Option<Received> {
+        if data.len() <= SIGNATURE_LENGTH || data.len() > MAX_PACKET_SIZE {
+            return None;
+        }
+        let (signature, payload) = data.split_at(SIGNATURE_LENGTH);
+        let signature = Ed25519Signature::from(signature.to_vec());
+        let public = recover_ed25519(&signature, &blake2b(payload)).ok()?;
+        let packet = Self::decode_payload(&UntrustedRlp::new(payload)).ok()?;
+        Some(Received {
+            packet: packet,
+            public: public,
+            hash: blake2b(data),
+        })
+    }
+
+    fn append_header(&self, stream: &mut RlpStream, packet_type: u8) {
+        stream.append(&packet_type);
+        stream.append(&self.node_id);
+        stream.append(&self.expiration);
+    }
+
+    fn decode_payload(rlp: &UntrustedRlp) -> Result<Packet, DecoderError> {
+        let packet_type: u8 = rlp.val_at(0)?;
+        let node_id = decode_node_id(&rlp.at(1)?)?;
+        let expiration: u64 = rlp.val_at(2)?;
+        let body = match packet_type {
+            PING => Body::Ping,
+            PONG => {
+                Body::Pong {
+                    ping_hash: rlp.val_at(3)?,
+                }
+            }
+            FIND_NODE => {
+                Body::FindNode {
+                    target: rlp.val_at(3)?,
+                }
+            }
+            NEIGHBOURS => {
+                let mut nodes = Vec::new();
+                for node in rlp.at(3)?.iter() {
+                    let node_id = decode_node_id(&node.at(0)?)?;
+                    let ip: Vec<u8> = node.val_at(1)?;
+                    let port: u16 = node.val_at(2)?;
+                    if ip.len() != 4 {
+                        return Err(DecoderError::Custom("invalid neighbour ip"));
+                    }
+                    let ip = Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]);
+                    nodes.push((node_id, SocketAddr::new(IpAddr::V4(ip), port)));
+                }
+                Body::Neighbours {
+                    nodes: nodes,
+                }
+            }
+            _ => return Err(DecoderError::Custom("unknown discovery packet type")),
+        };
+        Ok(Packet {
+            node_id: node_id,
+            expiration: expiration,
+            body: body,
+        })
+    }
+}
+
+fn decode_node_id(rlp: &UntrustedRlp) -> Result<String, DecoderError> {
+    let node_id: String = rlp.as_val()?;
+    if node_id.len() != NODE_ID_LENGTH {
+        return Err(DecoderError::Custom("invalid node id"));
+    }
+    Ok(node_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use discovery::table::BUCKET_SIZE;
+    use key::generate_keypair;
+
+    const NODE_ID: &'static str = "c33d1066-8c7e-496c-9c4e-c89318280274";
+
+    #[test]
+    fn signed_packets_round_trip() {
+        let keypair = generate_keypair();
+        let nodes: Vec<(String, SocketAddr)> = (0..BUCKET_SIZE)
+            .map(|i| {
+                (
+                    format!("c33d1066-8c7e-496c-9c4e-{:012}", i),
+                    format!("13.92.155.{}:30303", i).parse().unwrap(),
+                )
+            })
+            .collect();
+        let bodies = vec![
+            Body::Ping,
+            Body::Pong {
+                ping_hash: H256::random(),
+            },
+            Body::FindNode {
+                target: H256::random(),
+            },
+            Body::Neighbours {
+                nodes: nodes,
+            },
+        ];
+
+        for body in bodies {
+            let packet = Packet::new(NODE_ID, body, 1_000_000);
+            let data = packet.encode(keypair.secret()).unwrap();
+            let received = Packet::decode(&data).unwrap();
+            assert_eq!(received.packet, packet);
+            assert_eq!(received.public, *keypair.public());
+            assert_eq!(received.hash, blake2b(&data));
+        }
+    }
+
+    #[test]
+    fn tampered_packets_are_dropped() {
+        let keypair = generate_keypair();
+        let packet = Packet::new(NODE_ID, Body::Ping, 1_000_000);
+        let mut data = packet.encode(keypair.secret()).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert_eq!(Packet::decode(&data), None);
+
+        let short_id = Packet::new("c33d1066", Body::Ping, 1_000_000);
+        let data = short_id.encode(keypair.secret()).unwrap();
+        assert_eq!(Packet::decode(&data), None);
+    }
+}
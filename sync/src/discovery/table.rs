@@ -0,0 +1,268 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Kademlia routing table.
+//!
+//! Nodes are keyed by the blake2b hash of their node id and sorted into one bucket per
+//! logarithmic distance from the local key. A bucket holds at most `BUCKET_SIZE` nodes, the
+//! least recently seen first. Nodes showing up while their bucket is full wait as replacements
+//! until a node of the bucket stops answering.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+
+use aion_types::{Ed25519Public, H256};
+use blake2b::blake2b;
+
+/// Most nodes of a bucket, and most replacements waiting for it.
+pub const BUCKET_SIZE: usize = 16;
+/// Number of buckets, one per bit of the node key.
+pub const BUCKETS: usize = 256;
+
+/// A node found by discovery.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeEntry {
+    /// Routing key, the blake2b hash of the node id.
+    pub key: H256,
+    pub node_id: String,
+    /// Key the node signs its packets with.
+    pub public: Ed25519Public,
+    /// Address the node listens on, for discovery and for connections alike.
+    pub addr: SocketAddr,
+}
+
+impl NodeEntry {
+    pub fn new(node_id: &str, public: Ed25519Public, addr: SocketAddr) -> Self {
+        NodeEntry {
+            key: node_key(node_id),
+            node_id: node_id.to_owned(),
+            public: public,
+            addr: addr,
+        }
+    }
+}
+
+/// Outcome of adding a node to the table.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Insert {
+    /// The node was added to its bucket.
+    Added,
+    /// The node was known already and is now the most recently seen one.
+    Updated,
+    /// The bucket is full. The node waits as a replacement and the least recently seen node,
+    /// returned here, should be checked.
+    Full(NodeEntry),
+    /// The node id is known with another public key, the node was not added.
+    Conflict,
+    /// The node is the local one.
+    Local,
+}
+
+/// Routing key of a node id.
+pub fn node_key(node_id: &str) -> H256 { blake2b(node_id.as_bytes()) }
+
+/// Logarithmic distance between two keys: the index of the highest bit they differ in, `None`
+/// for equal keys.
+pub fn log_distance(a: &H256, b: &H256) -> Option<usize> {
+    for i in 0..32 {
+        let xor = a[i] ^ b[i];
+        if xor != 0 {
+            return Some((31 - i) * 8 + 7 - xor.leading_zeros() as usize);
+        }
+    }
+    None
+}
+
+struct Bucket {
+    entries: VecDeque<NodeEntry>,
+    replacements: VecDeque<NodeEntry>,
+}
+
+pub struct NodeTable {
+    local: H256,
+    buckets: Vec<Bucket>,
+}
+
+impl NodeTable {
+    pub fn new(local: H256) -> Self {
+        NodeTable {
+            local: local,
+            buckets: (0..BUCKETS)
+                .map(|_| {
+                    Bucket {
+                        entries: VecDeque::new(),
+                        replacements: VecDeque::new(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize { self.buckets.iter().map(|bucket| bucket.entries.len()).sum() }
+
+    pub fn get(&self, key: &H256) -> Option<&NodeEntry> {
+        let index = log_distance(&self.local, key)?;
+        self.buckets[index]
+            .entries
+            .iter()
+            .find(|entry| entry.key == *key)
+    }
+
+    /// Add a node which answered, or refresh it if known.
+    pub fn insert(&mut self, entry: NodeEntry) -> Insert {
+        let index = match log_distance(&self.local, &entry.key) {
+            Some(index) => index,
+            None => return Insert::Local,
+        };
+        let bucket = &mut self.buckets[index];
+
+        if let Some(position) = bucket.entries.iter().position(|e| e.key == entry.key) {
+            if bucket.entries[position].public != entry.public {
+                return Insert::Conflict;
+            }
+            bucket.entries.remove(position);
+            bucket.entries.push_back(entry);
+            return Insert::Updated;
+        }
+
+        if bucket.entries.len() < BUCKET_SIZE {
+            bucket.entries.push_back(entry);
+            return Insert::Added;
+        }
+
+        bucket.replacements.retain(|e| e.key != entry.key);
+        if bucket.replacements.len() >= BUCKET_SIZE {
+            bucket.replacements.pop_front();
+        }
+        bucket.replacements.push_back(entry);
+        Insert::Full(bucket.entries[0].clone())
+    }
+
+    /// Drop a node which stopped answering, the latest replacement of its bucket takes its place.
+    pub fn remove(&mut self, key: &H256) -> Option<NodeEntry> {
+        let index = log_distance(&self.local, key)?;
+        let bucket = &mut self.buckets[index];
+        let position = bucket.entries.iter().position(|e| e.key == *key)?;
+        let removed = bucket.entries.remove(position);
+        if let Some(replacement) = bucket.replacements.pop_back() {
+            bucket.entries.push_back(replacement);
+        }
+        removed
+    }
+
+    /// Up to `count` nodes, the closest to `target` first.
+    pub fn closest(&self, target: &H256, count: usize) -> Vec<NodeEntry> {
+        let mut entries = self.entries();
+        entries.sort_by_key(|entry| entry.key ^ *target);
+        entries.truncate(count);
+        entries
+    }
+
+    /// The least recently seen node of the bucket at `index`, if it has any.
+    pub fn oldest(&self, index: usize) -> Option<&NodeEntry> {
+        self.buckets.get(index)?.entries.front()
+    }
+
+    /// Indexes of the buckets holding nodes.
+    pub fn filled_buckets(&self) -> Vec<usize> {
+        (0..BUCKETS)
+            .filter(|index| !self.buckets[*index].entries.is_empty())
+            .collect()
+    }
+
+    pub fn entries(&self) -> Vec<NodeEntry> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.entries.iter().cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(i: usize) -> NodeEntry {
+        NodeEntry::new(
+            &format!("00000000-0000-0000-0000-{:012}", i),
+            H256::from(i as u64),
+            format!("127.0.0.1:{}", 30000 + i).parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn log_distance_is_highest_differing_bit() {
+        let zero = H256::zero();
+        assert_eq!(log_distance(&zero, &zero), None);
+        assert_eq!(log_distance(&zero, &H256::from(1)), Some(0));
+        assert_eq!(log_distance(&zero, &H256::from(0x80)), Some(7));
+        let mut high = H256::zero();
+        high[0] = 0x40;
+        assert_eq!(log_distance(&high, &zero), Some(254));
+    }
+
+    #[test]
+    fn full_buckets_keep_replacements() {
+        let mut table = NodeTable::new(H256::zero());
+        let entries: Vec<NodeEntry> = (0..200).map(entry).collect();
+        // most random keys fall into the top bucket.
+        let top: Vec<NodeEntry> = entries
+            .iter()
+            .filter(|e| log_distance(&H256::zero(), &e.key) == Some(255))
+            .cloned()
+            .collect();
+        assert!(top.len() > BUCKET_SIZE);
+
+        for e in top[..BUCKET_SIZE].iter() {
+            assert_eq!(table.insert(e.clone()), Insert::Added);
+        }
+        assert_eq!(table.insert(top[BUCKET_SIZE].clone()), Insert::Full(top[0].clone()));
+        assert_eq!(table.insert(top[1].clone()), Insert::Updated);
+
+        let mut conflicting = top[2].clone();
+        conflicting.public = H256::from(0xffff);
+        assert_eq!(table.insert(conflicting), Insert::Conflict);
+
+        // the oldest node stops answering and the replacement takes its place.
+        assert_eq!(table.oldest(255), Some(&top[0]));
+        assert_eq!(table.remove(&top[0].key), Some(top[0].clone()));
+        assert_eq!(table.get(&top[BUCKET_SIZE].key), Some(&top[BUCKET_SIZE]));
+        assert_eq!(table.len(), BUCKET_SIZE);
+        assert_eq!(table.oldest(255), Some(&top[2]));
+    }
+
+    #[test]
+    fn closest_sorts_by_xor_distance() {
+        // fewer nodes than a bucket holds, so none of them waits as a replacement.
+        let mut table = NodeTable::new(H256::zero());
+        for i in 0..BUCKET_SIZE {
+            assert_eq!(table.insert(entry(i)), Insert::Added);
+        }
+        let target = entry(7).key;
+        let closest = table.closest(&target, 5);
+        assert_eq!(closest.len(), 5);
+        assert_eq!(closest[0], entry(7));
+        for pair in closest.windows(2) {
+            assert!(pair[0].key ^ target < pair[1].key ^ target);
+        }
+    }
+}
@@ -24,8 +24,6 @@ extern crate byteorder;
 extern crate bytes;
 extern crate futures;
 #[macro_use]
-extern crate lazy_static;
-#[macro_use]
 extern crate log;
 extern crate lru_cache;
 extern crate rand;
@@ -33,7 +31,6 @@ extern crate rustc_hex;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde;
-extern crate state;
 extern crate tokio;
 extern crate tokio_codec;
 extern crate tokio_threadpool;
@@ -43,10 +40,13 @@ extern crate acore;
 extern crate acore_bytes;
 extern crate acore_io;
 extern crate aion_types;
+extern crate key;
 extern crate rlp;
+extern crate triehash;
 extern crate uuid;
 extern crate aion_version as version;
 
+pub mod discovery;
 pub mod net;
 pub mod p2p;
 pub mod sync;
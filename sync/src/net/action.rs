@@ -62,6 +62,14 @@ impl NetAction {
             _ => NetAction::UNKNOWN,
         }
     }
+
+    /// Whether the action asks the peer for data, limited in rate.
+    pub fn is_request(&self) -> bool {
+        match *self {
+            NetAction::HANDSHAKEREQ | NetAction::PING | NetAction::ACTIVENODESREQ => true,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for NetAction {
@@ -31,7 +31,7 @@ use super::super::event::{NetEvent, HANDSHAKE_DONE};
 pub struct ActiveNodesHandler;
 
 impl ActiveNodesHandler {
-    pub fn send_activenodes_req() {
+    pub fn send_activenodes_req(p2p: &P2pMgr) {
         let mut req = ChannelBuffer::new();
         req.head.ver = Version::V0.value();
         req.head.ctrl = Control::NET.value();
@@ -39,19 +39,19 @@ impl ActiveNodesHandler {
 
         req.head.len = 0;
 
-        let handshaked_nodes = P2pMgr::get_nodes(HANDSHAKE_DONE);
+        let handshaked_nodes = p2p.get_nodes(HANDSHAKE_DONE);
         let handshaked_nodes_count = handshaked_nodes.len();
         if handshaked_nodes_count > 0 {
             let random_index = random::<usize>() % handshaked_nodes_count;
             let node = &handshaked_nodes[random_index];
-            P2pMgr::send(node.node_hash, req.clone());
+            p2p.send(node.node_hash, req.clone());
             trace!(target: "net", "Net activenodes req sent...");
         } else {
             trace!(target: "net", "Net no active node...");
         }
     }
 
-    pub fn handle_active_nodes_req(peer_node: &mut Node) {
+    pub fn handle_active_nodes_req(p2p: &P2pMgr, peer_node: &mut Node) {
         trace!(target: "net", "ACTIVENODESREQ received.");
 
         let mut res = ChannelBuffer::new();
@@ -61,7 +61,7 @@ impl ActiveNodesHandler {
         res.head.set_control(Control::NET);
         res.head.action = NetAction::ACTIVENODESRES.value();
 
-        let active_nodes = P2pMgr::get_nodes(HANDSHAKE_DONE);
+        let active_nodes = p2p.get_nodes(HANDSHAKE_DONE);
         let mut res_body = Vec::new();
         let active_nodes_count = active_nodes.len();
 
@@ -92,11 +92,11 @@ impl ActiveNodesHandler {
         res.head.set_length(res.body.len() as u32);
 
         NetEvent::update_node_state(peer_node, NetEvent::OnActiveNodesReq);
-        P2pMgr::update_node(peer_node_hash, peer_node);
-        P2pMgr::send(peer_node_hash, res);
+        p2p.update_node(peer_node_hash, peer_node);
+        p2p.send(peer_node_hash, res);
     }
 
-    pub fn handle_active_nodes_res(peer_node: &mut Node, req: ChannelBuffer) {
+    pub fn handle_active_nodes_res(p2p: &P2pMgr, peer_node: &mut Node, req: ChannelBuffer) {
         trace!(target: "net", "ACTIVENODESRES received.");
 
         let peer_node_hash = peer_node.node_hash;
@@ -117,7 +117,7 @@ impl ActiveNodesHandler {
                 node.state_code = DISCONNECTED;
                 node.node_hash = P2pMgr::calculate_hash(&node.get_node_id());
 
-                let local_node_ip = P2pMgr::get_local_node().ip_addr.ip;
+                let local_node_ip = p2p.get_local_node().ip_addr.ip;
                 let local_node_ip_hash = P2pMgr::calculate_hash(&local_node_ip);
                 let peer_node_ip_hash = P2pMgr::calculate_hash(&peer_node.ip_addr.ip);
                 let node_ip_hash = P2pMgr::calculate_hash(&node.ip_addr.ip);
@@ -131,14 +131,14 @@ impl ActiveNodesHandler {
         }
 
         for n in node_list.iter() {
-            match P2pMgr::get_node(n.node_hash) {
+            match p2p.get_node(n.node_hash) {
                 Some(_) => {}
                 None => {
-                    P2pMgr::add_node(n.clone());
+                    p2p.add_node(n.clone());
                 }
             }
         }
         NetEvent::update_node_state(peer_node, NetEvent::OnActiveNodesRes);
-        P2pMgr::update_node(peer_node_hash, peer_node);
+        p2p.update_node(peer_node_hash, peer_node);
     }
 }
@@ -21,9 +21,9 @@
 
 pub use p2p::*;
 
-pub type Callback = fn(node: &mut Node, cb: ChannelBuffer);
+pub type Callback = Handle;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct DefaultHandler {
     pub callback: Callback,
 }
@@ -33,5 +33,3 @@ impl DefaultHandler {
 
     pub fn handle(&self, node: &mut Node, cb: ChannelBuffer) { (self.callback)(node, cb); }
 }
-
-unsafe impl Send for DefaultHandler {}
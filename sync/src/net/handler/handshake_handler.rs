@@ -34,8 +34,8 @@ const REVISION_PREFIX: &str = "r-";
 pub struct HandshakeHandler;
 
 impl HandshakeHandler {
-    pub fn send_handshake_req(node: &mut Node) {
-        let local_node = P2pMgr::get_local_node();
+    pub fn send_handshake_req(p2p: &P2pMgr, node: &mut Node) {
+        let local_node = p2p.get_local_node();
         let mut req = ChannelBuffer::new();
         req.head.ver = Version::V0.value();
         req.head.ctrl = Control::NET.value();
@@ -63,24 +63,31 @@ impl HandshakeHandler {
 
         // handshake req
         trace!(target: "net", "Net handshake req sent...");
-        P2pMgr::send(node.node_hash, req.clone());
+        p2p.send(node.node_hash, req.clone());
         node.inc_repeated();
 
-        P2pMgr::update_node(node.node_hash, node);
+        p2p.update_node(node.node_hash, node);
     }
 
-    pub fn handle_handshake_req(node: &mut Node, req: ChannelBuffer) {
+    pub fn handle_handshake_req(p2p: &P2pMgr, node: &mut Node, req: ChannelBuffer) {
         trace!(target: "net", "HANDSHAKEREQ received.");
 
         let (node_id, req_body_rest) = req.body.split_at(NODE_ID_LENGTH);
         let (mut net_id, req_body_rest) = req_body_rest.split_at(mem::size_of::<i32>());
         let peer_net_id = net_id.read_u32::<BigEndian>().unwrap_or(0);
-        let local_net_id = P2pMgr::get_network_config().net_id;
+        let local_net_id = p2p.get_network_config().net_id;
         if peer_net_id != local_net_id {
             warn!(target: "net", "Invalid net id {}, should be {}.", peer_net_id, local_net_id);
             return;
         }
 
+        let node_id_str = String::from_utf8_lossy(node_id).into_owned();
+        if p2p.is_node_banned(&node_id_str) {
+            debug!(target: "net", "Banned node {}@{} rejected.", node_id_str, node.get_ip_addr());
+            p2p.remove_peer(node.node_hash);
+            return;
+        }
+
         let (_ip, req_body_rest) = req_body_rest.split_at(IP_LENGTH);
         let (mut port, revision_version) = req_body_rest.split_at(mem::size_of::<i32>());
         let (revision_len, rest) = revision_version.split_at(1);
@@ -115,20 +122,20 @@ impl HandshakeHandler {
         let old_node_hash = node.node_hash;
         let node_id_hash = P2pMgr::calculate_hash(&node.get_node_id());
         node.node_hash = node_id_hash;
-        if P2pMgr::is_connected(node_id_hash) {
+        if p2p.is_connected(node_id_hash) {
             trace!(target: "net", "known node {}@{} ...", node.get_node_id(), node.get_ip_addr());
         } else {
             NetEvent::update_node_state(node, NetEvent::OnHandshakeReq);
-            if let Some(socket) = P2pMgr::get_peer(old_node_hash) {
-                P2pMgr::add_peer(node.clone(), &socket);
+            if let Some(link) = p2p.get_peer(old_node_hash) {
+                p2p.add_peer(node.clone(), link);
             }
         }
 
-        P2pMgr::send(node.node_hash, res);
-        P2pMgr::remove_peer(old_node_hash);
+        p2p.send(node.node_hash, res);
+        p2p.remove_peer(old_node_hash);
     }
 
-    pub fn handle_handshake_res(node: &mut Node, req: ChannelBuffer) {
+    pub fn handle_handshake_res(p2p: &P2pMgr, node: &mut Node, req: ChannelBuffer) {
         trace!(target: "net", "HANDSHAKERES received.");
 
         let (_, revision) = req.body.split_at(1);
@@ -142,6 +149,7 @@ impl HandshakeHandler {
         }
 
         NetEvent::update_node_state(node, NetEvent::OnHandshakeRes);
-        P2pMgr::update_node(node.node_hash, node);
+        p2p.update_node(node.node_hash, node);
+        p2p.record_connected(node);
     }
 }
@@ -28,7 +28,7 @@ use p2p::*;
 pub struct PingPongHandler;
 
 impl PingPongHandler {
-    pub fn handle_ping(node: &mut Node, _req: ChannelBuffer) {
+    pub fn handle_ping(p2p: &P2pMgr, node: &mut Node, _req: ChannelBuffer) {
         trace!(target: "net", "PING received.");
 
         let mut res = ChannelBuffer::new();
@@ -41,16 +41,16 @@ impl PingPongHandler {
             .put_slice("POWEquihash pong".to_string().as_bytes());
         res.head.set_length(res.body.len() as u32);
         NetEvent::update_node_state(node, NetEvent::OnPing);
-        P2pMgr::update_node(node_hash, node);
-        P2pMgr::send(node_hash, res);
+        p2p.update_node(node_hash, node);
+        p2p.send(node_hash, res);
     }
 
-    pub fn handle_pong(node: &mut Node, _req: ChannelBuffer) {
+    pub fn handle_pong(p2p: &P2pMgr, node: &mut Node, _req: ChannelBuffer) {
         trace!(target: "net", "PONG received.");
 
         let node_hash = node.node_hash;
 
         NetEvent::update_node_state(node, NetEvent::OnPong);
-        P2pMgr::update_node(node_hash, node);
+        p2p.update_node(node_hash, node);
     }
 }
@@ -19,9 +19,12 @@
  *
  ******************************************************************************/
 
+use discovery::{self, Discovery};
 use futures::{Future, Stream};
+use key::generate_keypair;
 use p2p::*;
-use state::Storage;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tokio::runtime::TaskExecutor;
@@ -39,44 +42,69 @@ use self::handler::default_handler::DefaultHandler;
 use self::handler::handshake_handler::HandshakeHandler;
 use self::handler::pingpong_handler::PingPongHandler;
 
-lazy_static! {
-    static ref DEFAULT_HANDLER: Storage<DefaultHandler> = Storage::new();
-}
-
 const RECONNECT_BOOT_NOEDS_INTERVAL: u64 = 10;
 const RECONNECT_NORMAL_NOEDS_INTERVAL: u64 = 1;
 const NODE_ACTIVE_REQ_INTERVAL: u64 = 10;
+const SAVE_KNOWN_PEERS_INTERVAL: u64 = 300;
 
-#[derive(Clone, Copy)]
-pub struct NetManager;
+/// Network layer of a node, passing the messages it does not handle itself on to the
+/// default handler.
+#[derive(Clone)]
+pub struct NetManager {
+    p2p: P2pMgr,
+    handler: DefaultHandler,
+}
 
 impl NetManager {
-    pub fn enable(executor: &TaskExecutor, handler: DefaultHandler) {
-        DEFAULT_HANDLER.set(handler);
+    pub fn new(p2p: P2pMgr, handler: DefaultHandler) -> NetManager {
+        NetManager {
+            p2p: p2p,
+            handler: handler,
+        }
+    }
+
+    pub fn enable(&self, executor: &TaskExecutor) {
+        self.enable_p2p_server(executor);
+        self.enable_p2p_clients(executor);
+        self.enable_discovery(executor);
+
+        self.enable_activenodes_req_task(executor);
+        self.enable_save_known_peers_task(executor);
+    }
+
+    /// Enable the periodic tasks only, for nodes connected with `connect_in_memory`.
+    pub fn enable_in_memory(&self, executor: &TaskExecutor) {
+        self.enable_activenodes_req_task(executor);
+    }
 
-        Self::enable_p2p_server(executor);
-        Self::enable_p2p_clients(executor);
+    /// Connect to the network of another node in the same process, without sockets.
+    pub fn connect_in_memory(&self, server: &NetManager) {
+        self.p2p.connect_in_memory(&server.p2p, self.as_handle(), server.as_handle());
+    }
 
-        Self::enable_activenodes_req_task(executor);
+    /// Callback for the messages of the peers of this node.
+    pub fn as_handle(&self) -> Handle {
+        let net = self.clone();
+        Arc::new(move |node: &mut Node, req: ChannelBuffer| net.handle(node, req))
     }
 
-    fn enable_p2p_server(executor: &TaskExecutor) {
+    fn enable_p2p_server(&self, executor: &TaskExecutor) {
         thread::sleep(Duration::from_secs(5));
-        let local_addr = P2pMgr::get_local_node().get_ip_addr();
-        P2pMgr::create_server(&executor, &local_addr, Self::handle);
+        let local_addr = self.p2p.get_local_node().get_ip_addr();
+        self.p2p.create_server(&executor, &local_addr, self.as_handle());
     }
 
-    fn enable_p2p_clients(executor: &TaskExecutor) {
-        let local_node = P2pMgr::get_local_node();
+    fn enable_p2p_clients(&self, executor: &TaskExecutor) {
+        let local_node = self.p2p.get_local_node();
         let local_node_id_hash = P2pMgr::calculate_hash(&local_node.get_node_id());
-        let network_config = P2pMgr::get_network_config();
+        let network_config = self.p2p.get_network_config();
         let boot_nodes = P2pMgr::load_boot_nodes(network_config.boot_nodes.clone());
         let max_peers_num = network_config.max_peers as usize;
         let client_ip_black_list = network_config.ip_black_list.clone();
         let sync_from_boot_nodes_only = network_config.sync_from_boot_nodes_only;
 
-        Self::enable_clients_for_boot_nodes(executor, boot_nodes);
-        Self::enable_clients_for_normal_nodes(
+        self.enable_clients_for_boot_nodes(executor, boot_nodes);
+        self.enable_clients_for_normal_nodes(
             executor,
             local_node_id_hash,
             max_peers_num,
@@ -85,21 +113,22 @@ impl NetManager {
         );
     }
 
-    fn enable_clients_for_boot_nodes(executor: &TaskExecutor, boot_nodes: Vec<Node>) {
+    fn enable_clients_for_boot_nodes(&self, executor: &TaskExecutor, boot_nodes: Vec<Node>) {
+        let net = self.clone();
         let connect_boot_nodes_task = Interval::new(
             Instant::now(),
             Duration::from_secs(RECONNECT_BOOT_NOEDS_INTERVAL),
         ).for_each(move |_| {
             for boot_node in boot_nodes.iter() {
                 let node_hash = P2pMgr::calculate_hash(&boot_node.get_node_id());
-                if let Some(node) = P2pMgr::get_node(node_hash) {
+                if let Some(node) = net.p2p.get_node(node_hash) {
                     if node.state_code == DISCONNECTED {
                         trace!(target: "net", "boot node reconnected: {}@{}", boot_node.get_node_id(), boot_node.get_ip_addr());
-                        Self::connet_peer(boot_node.clone());
+                        net.connet_peer(boot_node.clone());
                     }
                 } else {
                     trace!(target: "net", "boot node loaded: {}@{}", boot_node.get_node_id(), boot_node.get_ip_addr());
-                    Self::connet_peer(boot_node.clone());
+                    net.connet_peer(boot_node.clone());
                 }
             }
 
@@ -110,6 +139,7 @@ impl NetManager {
     }
 
     fn enable_clients_for_normal_nodes(
+        &self,
         executor: &TaskExecutor,
         local_node_id_hash: u64,
         max_peers_num: usize,
@@ -117,19 +147,33 @@ impl NetManager {
         sync_from_boot_nodes_only: bool,
     )
     {
+        // seed the nodes to connect to with the peers known from earlier runs.
+        if !sync_from_boot_nodes_only {
+            for node in self.p2p.load_known_peers(max_peers_num) {
+                if node.node_hash != local_node_id_hash
+                    && !client_ip_black_list.contains(&node.ip_addr.get_ip())
+                    && self.p2p.get_node(node.node_hash).is_none()
+                {
+                    trace!(target: "net", "known node loaded: {}@{}", node.get_node_id(), node.get_ip_addr());
+                    self.p2p.add_node(node);
+                }
+            }
+        }
+
+        let net = self.clone();
         let connect_normal_nodes_task = Interval::new(
             Instant::now(),
             Duration::from_secs(RECONNECT_NORMAL_NOEDS_INTERVAL),
         )
         .for_each(move |_| {
-            let active_nodes_count = P2pMgr::get_nodes_count(ALIVE);
+            let active_nodes_count = net.p2p.get_nodes_count(ALIVE);
             if !sync_from_boot_nodes_only && active_nodes_count < max_peers_num {
-                if let Some(peer_node) = P2pMgr::get_an_inactive_node() {
+                if let Some(peer_node) = net.p2p.get_an_inactive_node() {
                     let peer_node_id_hash = P2pMgr::calculate_hash(&peer_node.get_node_id());
                     if peer_node_id_hash != local_node_id_hash {
                         let peer_ip = peer_node.ip_addr.get_ip();
                         if !client_ip_black_list.contains(&peer_ip) {
-                            Self::connet_peer(peer_node);
+                            net.connet_peer(peer_node);
                         }
                     }
                 };
@@ -141,20 +185,93 @@ impl NetManager {
         executor.spawn(connect_normal_nodes_task);
     }
 
-    fn connet_peer(peer_node: Node) {
+    fn enable_discovery(&self, executor: &TaskExecutor) {
+        let network_config = self.p2p.get_network_config();
+        if !network_config.discovery || network_config.sync_from_boot_nodes_only {
+            return;
+        }
+        let local_node = self.p2p.get_local_node();
+        let local_addr: SocketAddr = match local_node.get_ip_addr().parse() {
+            Ok(addr) => addr,
+            Err(_) => {
+                error!(target: "net", "Invalid discovery address: {}", local_node.get_ip_addr());
+                return;
+            }
+        };
+
+        let mut discovery = Discovery::new(
+            &local_node.get_node_id(),
+            generate_keypair(),
+            Instant::now(),
+        );
+        for boot_node in P2pMgr::load_boot_nodes(network_config.boot_nodes.clone()) {
+            if let Ok(addr) = boot_node.get_ip_addr().parse() {
+                discovery.add_seed(&boot_node.get_node_id(), addr);
+            }
+        }
+        let discovery = Arc::new(Mutex::new(discovery));
+        match discovery::start(discovery.clone(), &local_addr) {
+            Ok(service) => {
+                info!(target: "net", "Discovery listening on udp {}", service.local_addr());
+            }
+            Err(e) => {
+                error!(target: "net", "Failed to start discovery on {}: {}", local_addr, e);
+                return;
+            }
+        }
+
+        let local_node_id_hash = P2pMgr::calculate_hash(&local_node.get_node_id());
+        let client_ip_black_list = network_config.ip_black_list.clone();
+        let p2p = self.p2p.clone();
+        let discovered_nodes_task = Interval::new(
+            Instant::now(),
+            Duration::from_secs(RECONNECT_NORMAL_NOEDS_INTERVAL),
+        )
+        .for_each(move |_| {
+            let entries = match discovery.lock() {
+                Ok(discovery) => discovery.nodes(),
+                Err(_) => Vec::new(),
+            };
+            for entry in entries {
+                let node_hash = P2pMgr::calculate_hash(&entry.node_id);
+                if node_hash == local_node_id_hash
+                    || p2p.get_node(node_hash).is_some()
+                    || client_ip_black_list.contains(&entry.addr.ip().to_string())
+                {
+                    continue;
+                }
+                trace!(target: "net", "discovered node loaded: {}@{}", entry.node_id, entry.addr);
+                let node = P2pMgr::new_inactive_node(&entry.node_id, &entry.addr.to_string());
+                p2p.add_node(node);
+            }
+
+            Ok(())
+        })
+        .map_err(|e| error!("interval errored; err={:?}", e));
+        executor.spawn(discovered_nodes_task);
+    }
+
+    fn connet_peer(&self, peer_node: Node) {
+        if self.p2p.is_node_banned(&peer_node.get_node_id())
+            || self.p2p.is_ip_banned(&peer_node.ip_addr.get_ip())
+        {
+            trace!(target: "net", "Skip banned node {}", peer_node.get_ip_addr());
+            return;
+        }
         trace!(target: "net", "Try to connect to node {}", peer_node.get_ip_addr());
         let node_hash = P2pMgr::calculate_hash(&peer_node.get_node_id());
-        P2pMgr::remove_peer(node_hash);
-        P2pMgr::create_client(peer_node, Self::handle);
+        self.p2p.remove_peer(node_hash);
+        self.p2p.create_client(peer_node, self.as_handle());
     }
 
-    fn enable_activenodes_req_task(executor: &TaskExecutor) {
+    fn enable_activenodes_req_task(&self, executor: &TaskExecutor) {
+        let p2p = self.p2p.clone();
         let activenodes_req_task = Interval::new(
             Instant::now(),
             Duration::from_secs(NODE_ACTIVE_REQ_INTERVAL),
         )
         .for_each(move |_| {
-            ActiveNodesHandler::send_activenodes_req();
+            ActiveNodesHandler::send_activenodes_req(&p2p);
 
             Ok(())
         })
@@ -162,7 +279,23 @@ impl NetManager {
         executor.spawn(activenodes_req_task);
     }
 
-    fn handle(node: &mut Node, req: ChannelBuffer) {
+    fn enable_save_known_peers_task(&self, executor: &TaskExecutor) {
+        let p2p = self.p2p.clone();
+        let save_known_peers_task = Interval::new(
+            Instant::now() + Duration::from_secs(SAVE_KNOWN_PEERS_INTERVAL),
+            Duration::from_secs(SAVE_KNOWN_PEERS_INTERVAL),
+        )
+        .for_each(move |_| {
+            p2p.save_known_peers();
+
+            Ok(())
+        })
+        .map_err(|e| error!("interval errored; err={:?}", e));
+        executor.spawn(save_known_peers_task);
+    }
+
+    fn handle(&self, node: &mut Node, req: ChannelBuffer) {
+        let p2p = &self.p2p;
         match Version::from(req.head.ver) {
             Version::V0 => {
                 trace!(target: "net", "Ver 0 package received.");
@@ -171,38 +304,42 @@ impl NetManager {
                     Control::NET => {
                         trace!(target: "net", "P2P NET message received.");
 
-                        match NetAction::from(req.head.action) {
+                        let action = NetAction::from(req.head.action);
+                        if !p2p.check_limits(node, &req, action.is_request()) {
+                            return;
+                        }
+
+                        match action {
                             NetAction::DISCONNECT => {
                                 trace!(target: "net", "DISCONNECT received.");
                             }
                             NetAction::HANDSHAKEREQ => {
-                                HandshakeHandler::handle_handshake_req(node, req);
+                                HandshakeHandler::handle_handshake_req(p2p, node, req);
                             }
                             NetAction::HANDSHAKERES => {
-                                HandshakeHandler::handle_handshake_res(node, req);
+                                HandshakeHandler::handle_handshake_res(p2p, node, req);
                             }
                             NetAction::PING => {
-                                PingPongHandler::handle_ping(node, req);
+                                PingPongHandler::handle_ping(p2p, node, req);
                             }
                             NetAction::PONG => {
-                                PingPongHandler::handle_pong(node, req);
+                                PingPongHandler::handle_pong(p2p, node, req);
                             }
                             NetAction::ACTIVENODESREQ => {
-                                ActiveNodesHandler::handle_active_nodes_req(node);
+                                ActiveNodesHandler::handle_active_nodes_req(p2p, node);
                             }
                             NetAction::ACTIVENODESRES => {
-                                ActiveNodesHandler::handle_active_nodes_res(node, req);
+                                ActiveNodesHandler::handle_active_nodes_res(p2p, node, req);
                             }
                             _ => {
                                 error!(target: "net", "Invalid received.");
                             }
                         };
                     }
-                    Control::SYNC => {
-                        trace!(target: "net", "P2P SYNC message received.");
+                    Control::SYNC | Control::LIGHT => {
+                        trace!(target: "net", "P2P {} message received.", Control::from(req.head.ctrl));
 
-                        let handler = DEFAULT_HANDLER.get();
-                        handler.handle(node, req);
+                        self.handler.handle(node, req);
                     }
                     _ => {
                         error!(target: "net", "Invalid message received: {}", req.head);
@@ -211,7 +348,7 @@ impl NetManager {
             }
             Version::V1 => {
                 trace!(target: "net", "Ver 1 package received.");
-                HandshakeHandler::send_handshake_req(node);
+                HandshakeHandler::send_handshake_req(p2p, node);
             }
             _ => {
                 error!(target: "net", "Invalid Version.");
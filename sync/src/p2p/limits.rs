@@ -0,0 +1,137 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Limits on the requests of peers.
+//!
+//! Each peer gets a token bucket per request action, holding up to `request_burst` tokens and
+//! refilled at `request_rate` tokens a second. A request takes a token, and a peer sending
+//! requests while its bucket is empty is over the limit.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Token bucket for the requests of one action from one peer.
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    tokens: f64,
+    capacity: f64,
+    rate: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket of `capacity` tokens, refilled at `rate` tokens a second.
+    pub fn new(capacity: u32, rate: u32, now: Instant) -> Self {
+        TokenBucket {
+            tokens: capacity as f64,
+            capacity: capacity as f64,
+            rate: rate as f64,
+            updated: now,
+        }
+    }
+
+    /// Take a token, false if the bucket is empty.
+    pub fn take(&mut self, now: Instant) -> bool {
+        if now > self.updated {
+            let elapsed = now.duration_since(self.updated);
+            let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9;
+            self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+            self.updated = now;
+        }
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Request rate limits of the peers of a node, per peer and action.
+pub struct RateLimits {
+    burst: u32,
+    rate: u32,
+    buckets: HashMap<(u64, u8, u8), TokenBucket>,
+}
+
+impl RateLimits {
+    pub fn new(burst: u32, rate: u32) -> Self {
+        RateLimits {
+            burst: burst,
+            rate: rate,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Count a request of `action` under `ctrl` from a peer, false if the peer is over the limit.
+    pub fn admit(&mut self, node_hash: u64, ctrl: u8, action: u8, now: Instant) -> bool {
+        let (burst, rate) = (self.burst, self.rate);
+        self.buckets
+            .entry((node_hash, ctrl, action))
+            .or_insert_with(|| TokenBucket::new(burst, rate, now))
+            .take(now)
+    }
+
+    /// Forget the buckets of a peer.
+    pub fn remove(&mut self, node_hash: u64) {
+        self.buckets.retain(|&(hash, _, _), _| hash != node_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn bucket_refills_up_to_capacity() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, 10, now);
+        assert!(bucket.take(now));
+        assert!(bucket.take(now));
+        assert!(!bucket.take(now));
+
+        let later = now + Duration::from_millis(150);
+        assert!(bucket.take(later));
+        assert!(!bucket.take(later));
+
+        let much_later = later + Duration::from_secs(60);
+        assert!(bucket.take(much_later));
+        assert!(bucket.take(much_later));
+        assert!(!bucket.take(much_later));
+    }
+
+    #[test]
+    fn limits_are_per_peer_and_action() {
+        let now = Instant::now();
+        let mut limits = RateLimits::new(1, 1);
+        assert!(limits.admit(1, 1, 2, now));
+        assert!(!limits.admit(1, 1, 2, now));
+        assert!(limits.admit(1, 1, 4, now));
+        assert!(limits.admit(2, 1, 2, now));
+
+        limits.remove(1);
+        assert!(limits.admit(1, 1, 2, now));
+        assert!(!limits.admit(2, 1, 2, now));
+    }
+}
@@ -23,15 +23,17 @@ use bincode::config;
 use bytes::{BufMut, BytesMut};
 use futures::sync::mpsc;
 use futures::{Future, Stream};
+use kvdb::KeyValueDB;
 use rand::prelude::*;
-use state::Storage;
+use std::cmp;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use std::io;
 use std::net::Shutdown;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Instant, SystemTime};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::prelude::*;
 use tokio::runtime::TaskExecutor;
@@ -40,34 +42,66 @@ use tokio_threadpool::{Builder, ThreadPool};
 
 mod error;
 mod event;
+mod limits;
 mod msg;
 mod node;
+mod peer_store;
+mod reputation;
 
 pub use self::error::*;
 pub use self::event::*;
+pub use self::limits::*;
 pub use self::msg::*;
 pub use self::node::*;
+pub use self::peer_store::*;
+pub use self::reputation::*;
 
-lazy_static! {
-    static ref LOCAL_NODE: Storage<Node> = Storage::new();
-    static ref NETWORK_CONFIG: Storage<NetworkConfig> = Storage::new();
-    static ref SOCKETS_MAP: Storage<Mutex<HashMap<u64, TcpStream>>> = Storage::new();
-    static ref GLOBAL_NODES_MAP: Storage<RwLock<HashMap<u64, Node>>> = Storage::new();
-    static ref ENABLED: Storage<AtomicBool> = Storage::new();
-    static ref TP: Storage<ThreadPool> = Storage::new();
+const CHANNEL_CAPACITY: usize = 409600;
+
+/// Callback the messages received from a peer are passed to.
+pub type Handle = Arc<Fn(&mut Node, ChannelBuffer) + Send + Sync>;
+
+/// Connection to a peer.
+pub enum Link {
+    /// Tcp connection.
+    Tcp(TcpStream),
+    /// Channels to a node in the same process, closed once the flag is set.
+    Memory(Arc<AtomicBool>),
 }
 
-#[derive(Clone, Copy)]
-pub struct P2pMgr;
+impl Link {
+    pub fn shutdown(&self) -> io::Result<()> {
+        match *self {
+            Link::Tcp(ref socket) => socket.shutdown(Shutdown::Both),
+            Link::Memory(ref closed) => {
+                closed.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+    }
+}
 
-impl P2pMgr {
-    pub fn enable(cfg: NetworkConfig) {
-        let sockets_map: HashMap<u64, TcpStream> = HashMap::new();
-        SOCKETS_MAP.set(Mutex::new(sockets_map));
+struct P2pState {
+    local_node: Node,
+    network_config: NetworkConfig,
+    sockets_map: Mutex<HashMap<u64, Link>>,
+    nodes_map: RwLock<HashMap<u64, Node>>,
+    enabled: AtomicBool,
+    thread_pool: ThreadPool,
+    ban_list: Mutex<BanList>,
+    rate_limits: Mutex<RateLimits>,
+    peer_store: Mutex<PeerStore>,
+}
 
-        let node_map: HashMap<u64, Node> = HashMap::new();
-        GLOBAL_NODES_MAP.set(RwLock::new(node_map));
+/// Peer to peer network of a node. Clones share the same peers, each node of a process has its
+/// own.
+#[derive(Clone)]
+pub struct P2pMgr {
+    state: Arc<P2pState>,
+}
 
+impl P2pMgr {
+    pub fn new(cfg: NetworkConfig) -> P2pMgr {
         let local_node_str = cfg.local_node.clone();
         let mut local_node = Node::new_with_node_str(local_node_str);
 
@@ -75,33 +109,36 @@ impl P2pMgr {
 
         info!(target:"net","local node loaded: {}@{}", local_node.get_node_id(), local_node.get_ip_addr());
 
-        LOCAL_NODE.set(local_node.clone());
-
-        ENABLED.set(AtomicBool::new(true));
-
-        TP.set(
-            Builder::new()
-                .pool_size((cfg.max_peers * 3) as usize)
-                .build(),
-        );
-
-        NETWORK_CONFIG.set(cfg);
+        let rate_limits = RateLimits::new(cfg.request_burst, cfg.request_rate);
+        let thread_pool = Builder::new()
+            .pool_size((cfg.max_peers * 3) as usize)
+            .build();
+
+        P2pMgr {
+            state: Arc::new(P2pState {
+                local_node: local_node,
+                network_config: cfg,
+                sockets_map: Mutex::new(HashMap::new()),
+                nodes_map: RwLock::new(HashMap::new()),
+                enabled: AtomicBool::new(true),
+                thread_pool: thread_pool,
+                ban_list: Mutex::new(BanList::new()),
+                rate_limits: Mutex::new(rate_limits),
+                peer_store: Mutex::new(PeerStore::new()),
+            }),
+        }
     }
 
-    pub fn create_server(
-        executor: &TaskExecutor,
-        local_addr: &String,
-        handle: fn(node: &mut Node, req: ChannelBuffer),
-    )
-    {
+    pub fn create_server(&self, executor: &TaskExecutor, local_addr: &String, handle: Handle) {
         if let Ok(addr) = local_addr.parse() {
             let listener = TcpListener::bind(&addr).expect("Failed to bind");
             info!(target: "net", "Listening on: {}", local_addr);
+            let p2p = self.clone();
             let server = listener
                 .incoming()
                 .map_err(|e| error!(target: "net", "Failed to accept socket; error = {:?}", e))
                 .for_each(move |socket| {
-                    Self::process_inbounds(socket, handle);
+                    p2p.process_inbounds(socket, handle.clone());
 
                     Ok(())
                 });
@@ -111,25 +148,61 @@ impl P2pMgr {
         }
     }
 
-    pub fn create_client(peer_node: Node, handle: fn(node: &mut Node, req: ChannelBuffer)) {
+    pub fn create_client(&self, peer_node: Node, handle: Handle) {
         let node_ip_addr = peer_node.get_ip_addr();
         if let Ok(addr) = node_ip_addr.parse() {
-            let thread_pool = Self::get_thread_pool();
             let node_id = peer_node.get_node_id();
+            let p2p = self.clone();
+            let p2p_failed = self.clone();
             let connect = TcpStream::connect(&addr)
                 .map(move |socket| {
-                    Self::process_outbounds(socket, peer_node, handle);
+                    p2p.process_outbounds(socket, peer_node, handle);
                 })
-                .map_err(
-                    move |e| error!(target: "net", "Node: {}@{}, {}", node_ip_addr, node_id, e),
-                );
-            thread_pool.spawn(connect);
+                .map_err(move |e| {
+                    error!(target: "net", "Node: {}@{}, {}", node_ip_addr, node_id, e);
+                    p2p_failed.record_failed(&node_id);
+                });
+            self.get_thread_pool().spawn(connect);
         }
     }
 
-    pub fn get_thread_pool() -> &'static ThreadPool { TP.get() }
+    /// Connect to `server`, the network of another node in the same process, over channels
+    /// instead of a socket. `handle` gets the messages from the server, `server_handle` the
+    /// ones from this node.
+    pub fn connect_in_memory(&self, server: &P2pMgr, handle: Handle, server_handle: Handle) {
+        let (server_tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (tx, server_rx) = mpsc::channel(CHANNEL_CAPACITY);
+
+        let mut client_node = Node::new();
+        client_node.ip_addr = self.get_local_node().ip_addr;
+        client_node.state_code = CONNECTED;
+        let server_closed = Arc::new(AtomicBool::new(false));
+        let server_link = Link::Memory(server_closed.clone());
+        match server.add_inbound(client_node, server_tx, server_link) {
+            Some(node_hash) => {
+                let stream = Self::until_closed(server_rx, server_closed);
+                server.spawn_read(node_hash, stream, server_handle);
+            }
+            None => return,
+        }
+
+        let closed = Arc::new(AtomicBool::new(false));
+        let server_node = server.get_local_node().clone();
+        let link = Link::Memory(closed.clone());
+        if let Some(mut peer_node) = self.add_outbound(server_node, tx, link) {
+            // OnConnect
+            let mut req = ChannelBuffer::new();
+            req.head.set_version(Version::V1);
+            handle(&mut peer_node, req);
+
+            let stream = Self::until_closed(rx, closed);
+            self.spawn_read(peer_node.node_hash, stream, handle);
+        }
+    }
+
+    pub fn get_thread_pool(&self) -> &ThreadPool { &self.state.thread_pool }
 
-    pub fn get_network_config() -> &'static NetworkConfig { NETWORK_CONFIG.get() }
+    pub fn get_network_config(&self) -> &NetworkConfig { &self.state.network_config }
 
     pub fn load_boot_nodes(boot_nodes_str: Vec<String>) -> Vec<Node> {
         let mut boot_nodes = Vec::new();
@@ -143,55 +216,123 @@ impl P2pMgr {
         boot_nodes
     }
 
-    pub fn get_local_node() -> &'static Node { LOCAL_NODE.get() }
+    /// Back the known peers with a database, loading the ones saved by an earlier run.
+    pub fn open_peer_store(&self, db: Arc<KeyValueDB>) {
+        if let Ok(mut peer_store) = self.state.peer_store.lock() {
+            *peer_store = PeerStore::open(db, unix_now());
+            info!(target: "net", "{} known peers loaded.", peer_store.len());
+        }
+    }
+
+    /// Up to `count` known peers to connect to, the most reliable first.
+    pub fn load_known_peers(&self, count: usize) -> Vec<Node> {
+        let mut known_peers = Vec::new();
+        if let Ok(peer_store) = self.state.peer_store.lock() {
+            for record in peer_store.best(count) {
+                if record.node_id.len() != NODE_ID_LENGTH || !record.ip_addr.contains(':') {
+                    continue;
+                }
+                let mut node = Self::new_inactive_node(&record.node_id, &record.ip_addr);
+                node.best_block_num = record.best_block_num;
+                known_peers.push(node);
+            }
+        }
+        known_peers
+    }
 
-    pub fn disable() {
-        ENABLED.get().store(false, Ordering::SeqCst);
-        Self::reset();
+    /// A node to connect to, known by its id and its `ip:port`.
+    pub fn new_inactive_node(node_id: &str, ip_addr: &str) -> Node {
+        let mut node = Node::new_with_node_str(format!("p2p://{}@{}", node_id, ip_addr));
+        node.state_code = DISCONNECTED;
+        node.node_hash = Self::calculate_hash(&node.get_node_id());
+        node
     }
 
-    pub fn reset() {
-        if let Ok(mut sockets_map) = SOCKETS_MAP.get().lock() {
-            for (_, socket) in sockets_map.iter_mut() {
-                if let Err(e) = socket.shutdown() {
+    /// Record a successful outbound connection to a peer.
+    pub fn record_connected(&self, node: &Node) {
+        if node.is_from_boot_list {
+            return;
+        }
+        if let Ok(mut peer_store) = self.state.peer_store.lock() {
+            peer_store.on_connected(
+                &node.get_node_id(),
+                &node.get_ip_addr(),
+                node.best_block_num,
+                unix_now(),
+            );
+        }
+    }
+
+    /// Record a failed outbound connection to a peer.
+    pub fn record_failed(&self, node_id: &str) {
+        if let Ok(mut peer_store) = self.state.peer_store.lock() {
+            peer_store.on_failed(node_id);
+        }
+    }
+
+    /// Refresh the known peers still connected, evict the stale ones and save the rest.
+    pub fn save_known_peers(&self) {
+        let now = unix_now();
+        let alive_nodes = self.get_nodes(ALIVE);
+        if let Ok(mut peer_store) = self.state.peer_store.lock() {
+            for node in alive_nodes.iter() {
+                peer_store.on_alive(&node.get_node_id(), node.best_block_num, now);
+            }
+            peer_store.evict(now);
+            if let Err(e) = peer_store.save() {
+                warn!(target: "net", "Error saving known peers: {}", e);
+            }
+        }
+    }
+
+    pub fn get_local_node(&self) -> &Node { &self.state.local_node }
+
+    pub fn disable(&self) {
+        self.state.enabled.store(false, Ordering::SeqCst);
+        self.save_known_peers();
+        self.reset();
+    }
+
+    pub fn reset(&self) {
+        if let Ok(sockets_map) = self.state.sockets_map.lock() {
+            for (_, link) in sockets_map.iter() {
+                if let Err(e) = link.shutdown() {
                     error!(target: "net", "Invalid socket， {}", e);
                 }
             }
         }
-        if let Ok(mut nodes_map) = GLOBAL_NODES_MAP.get().write() {
+        if let Ok(mut nodes_map) = self.state.nodes_map.write() {
             nodes_map.clear();
         }
     }
 
-    pub fn get_peer(node_hash: u64) -> Option<TcpStream> {
-        if let Ok(mut socktes_map) = SOCKETS_MAP.get().lock() {
+    pub fn get_peer(&self, node_hash: u64) -> Option<Link> {
+        if let Ok(mut socktes_map) = self.state.sockets_map.lock() {
             return socktes_map.remove(&node_hash);
         }
 
         None
     }
 
-    pub fn add_peer(node: Node, ref_socket: &TcpStream) {
-        if let Ok(socket) = ref_socket.try_clone() {
-            if let Ok(mut sockets_map) = SOCKETS_MAP.get().lock() {
-                match sockets_map.get(&node.node_hash) {
-                    Some(_) => {
-                        warn!(target: "net", "Known node, ...");
-                    }
-                    None => {
-                        if let Ok(mut peer_nodes) = GLOBAL_NODES_MAP.get().write() {
-                            let max_peers_num = NETWORK_CONFIG.get().max_peers as usize;
-                            if peer_nodes.len() < max_peers_num {
-                                match peer_nodes.get(&node.node_hash) {
-                                    Some(_) => {
-                                        warn!(target: "net", "Known node...");
-                                    }
-                                    None => {
-                                        // info!(target: "p2p", "Node {}@{} added.", node.get_node_id(), node.get_ip_addr());
-                                        sockets_map.insert(node.node_hash, socket);
-                                        peer_nodes.insert(node.node_hash, node);
-                                        return;
-                                    }
+    pub fn add_peer(&self, node: Node, link: Link) {
+        if let Ok(mut sockets_map) = self.state.sockets_map.lock() {
+            match sockets_map.get(&node.node_hash) {
+                Some(_) => {
+                    warn!(target: "net", "Known node, ...");
+                }
+                None => {
+                    if let Ok(mut peer_nodes) = self.state.nodes_map.write() {
+                        let max_peers_num = self.state.network_config.max_peers as usize;
+                        if peer_nodes.len() < max_peers_num {
+                            match peer_nodes.get(&node.node_hash) {
+                                Some(_) => {
+                                    warn!(target: "net", "Known node...");
+                                }
+                                None => {
+                                    // info!(target: "p2p", "Node {}@{} added.", node.get_node_id(), node.get_ip_addr());
+                                    sockets_map.insert(node.node_hash, link);
+                                    peer_nodes.insert(node.node_hash, node);
+                                    return;
                                 }
                             }
                         }
@@ -200,20 +341,23 @@ impl P2pMgr {
             }
         }
 
-        if let Err(e) = ref_socket.shutdown(Shutdown::Both) {
+        if let Err(e) = link.shutdown() {
             error!(target: "net", "{}", e);
         }
     }
 
-    pub fn remove_peer(node_hash: u64) -> Option<Node> {
-        if let Ok(mut sockets_map) = SOCKETS_MAP.get().lock() {
-            if let Some(socket) = sockets_map.remove(&node_hash) {
-                if let Err(e) = socket.shutdown(Shutdown::Both) {
+    pub fn remove_peer(&self, node_hash: u64) -> Option<Node> {
+        if let Ok(mut sockets_map) = self.state.sockets_map.lock() {
+            if let Some(link) = sockets_map.remove(&node_hash) {
+                if let Err(e) = link.shutdown() {
                     trace!(target: "net", "remove_peer， invalid socket， {}", e);
                 }
             }
         }
-        if let Ok(mut peer_nodes) = GLOBAL_NODES_MAP.get().write() {
+        if let Ok(mut rate_limits) = self.state.rate_limits.lock() {
+            rate_limits.remove(node_hash);
+        }
+        if let Ok(mut peer_nodes) = self.state.nodes_map.write() {
             // if let Some(node) = peer_nodes.remove(&node_hash) {
             //     info!(target: "p2p", "Node {}@{} removed.", node.get_node_id(), node.get_ip_addr());
             //     return Some(node);
@@ -225,9 +369,9 @@ impl P2pMgr {
         None
     }
 
-    pub fn add_node(node: Node) {
-        let max_peers_num = NETWORK_CONFIG.get().max_peers as usize;
-        if let Ok(mut nodes_map) = GLOBAL_NODES_MAP.get().write() {
+    pub fn add_node(&self, node: Node) {
+        let max_peers_num = self.state.network_config.max_peers as usize;
+        if let Ok(mut nodes_map) = self.state.nodes_map.write() {
             if nodes_map.len() < max_peers_num {
                 match nodes_map.get(&node.node_hash) {
                     Some(_) => {
@@ -242,8 +386,8 @@ impl P2pMgr {
         }
     }
 
-    fn get_tx(node_hash: u64) -> Option<Tx> {
-        if let Ok(nodes_map) = GLOBAL_NODES_MAP.get().read() {
+    fn get_tx(&self, node_hash: u64) -> Option<Tx> {
+        if let Ok(nodes_map) = self.state.nodes_map.read() {
             if let Some(node) = nodes_map.get(&node_hash) {
                 return node.tx.clone();
             }
@@ -251,8 +395,8 @@ impl P2pMgr {
         None
     }
 
-    pub fn is_connected(node_id_hash: u64) -> bool {
-        let all_nodes = P2pMgr::get_all_nodes();
+    pub fn is_connected(&self, node_id_hash: u64) -> bool {
+        let all_nodes = self.get_all_nodes();
         for node in all_nodes.iter() {
             if node_id_hash == Self::calculate_hash(&node.get_node_id()) {
                 return true;
@@ -261,9 +405,9 @@ impl P2pMgr {
         false
     }
 
-    pub fn get_nodes_count(state_code: u32) -> usize {
+    pub fn get_nodes_count(&self, state_code: u32) -> usize {
         let mut nodes_count = 0;
-        if let Ok(nodes_map) = GLOBAL_NODES_MAP.get().read() {
+        if let Ok(nodes_map) = self.state.nodes_map.read() {
             for val in nodes_map.values() {
                 if val.state_code & state_code == state_code {
                     nodes_count += 1;
@@ -273,21 +417,9 @@ impl P2pMgr {
         nodes_count
     }
 
-    pub fn get_nodes_count_with_mode(mode: Mode) -> usize {
-        let mut nodes_count = 0;
-        if let Ok(nodes_map) = GLOBAL_NODES_MAP.get().read() {
-            for val in nodes_map.values() {
-                if val.mode == mode {
-                    nodes_count += 1;
-                }
-            }
-        }
-        nodes_count
-    }
-
-    pub fn get_all_nodes_count() -> u16 {
+    pub fn get_all_nodes_count(&self) -> u16 {
         let mut count = 0;
-        if let Ok(nodes_map) = GLOBAL_NODES_MAP.get().read() {
+        if let Ok(nodes_map) = self.state.nodes_map.read() {
             for _ in nodes_map.values() {
                 count += 1;
             }
@@ -295,9 +427,9 @@ impl P2pMgr {
         count
     }
 
-    pub fn get_all_nodes() -> Vec<Node> {
+    pub fn get_all_nodes(&self) -> Vec<Node> {
         let mut nodes = Vec::new();
-        if let Ok(nodes_map) = GLOBAL_NODES_MAP.get().read() {
+        if let Ok(nodes_map) = self.state.nodes_map.read() {
             for val in nodes_map.values() {
                 let node = val.clone();
                 nodes.push(node);
@@ -306,9 +438,9 @@ impl P2pMgr {
         nodes
     }
 
-    pub fn get_nodes(state_code_mask: u32) -> Vec<Node> {
+    pub fn get_nodes(&self, state_code_mask: u32) -> Vec<Node> {
         let mut nodes = Vec::new();
-        if let Ok(nodes_map) = GLOBAL_NODES_MAP.get().read() {
+        if let Ok(nodes_map) = self.state.nodes_map.read() {
             for val in nodes_map.values() {
                 let node = val.clone();
                 if node.state_code & state_code_mask == state_code_mask {
@@ -319,8 +451,8 @@ impl P2pMgr {
         nodes
     }
 
-    pub fn get_an_inactive_node() -> Option<Node> {
-        let nodes = Self::get_nodes(DISCONNECTED);
+    pub fn get_an_inactive_node(&self) -> Option<Node> {
+        let nodes = self.get_nodes(DISCONNECTED);
         let mut normal_nodes = Vec::new();
         for node in nodes.iter() {
             if node.is_from_boot_list {
@@ -335,30 +467,30 @@ impl P2pMgr {
         let random_index = random::<usize>() % normal_nodes.len();
         let node = &normal_nodes[random_index];
 
-        Self::remove_peer(node.node_hash)
+        self.remove_peer(node.node_hash)
     }
 
-    pub fn get_an_active_node() -> Option<Node> {
-        let nodes = Self::get_nodes(ALIVE);
+    pub fn get_an_active_node(&self) -> Option<Node> {
+        let nodes = self.get_nodes(ALIVE);
         if nodes.len() != 0 {
             let random_index = random::<usize>() % nodes.len();
-            return Self::get_node(nodes[random_index].node_hash);
+            return self.get_node(nodes[random_index].node_hash);
         } else {
             None
         }
     }
 
-    pub fn get_an_node_with_state_code(state_code_mask: u32) -> Option<Node> {
-        let nodes = Self::get_nodes(state_code_mask);
+    pub fn get_an_node_with_state_code(&self, state_code_mask: u32) -> Option<Node> {
+        let nodes = self.get_nodes(state_code_mask);
         if nodes.len() == 0 {
             return None;
         }
         let random_index = random::<usize>() % nodes.len();
-        Self::get_node(nodes[random_index].node_hash)
+        self.get_node(nodes[random_index].node_hash)
     }
 
-    pub fn get_node(node_hash: u64) -> Option<Node> {
-        if let Ok(nodes_map) = GLOBAL_NODES_MAP.get().read() {
+    pub fn get_node(&self, node_hash: u64) -> Option<Node> {
+        if let Ok(nodes_map) = self.state.nodes_map.read() {
             if let Some(node) = nodes_map.get(&node_hash) {
                 return Some(node.clone());
             }
@@ -366,67 +498,128 @@ impl P2pMgr {
         None
     }
 
-    pub fn update_node_with_mode(node_hash: u64, node: &Node) {
-        if let Ok(mut nodes_map) = GLOBAL_NODES_MAP.get().write() {
+    pub fn update_node(&self, node_hash: u64, node: &mut Node) {
+        if let Ok(mut nodes_map) = self.state.nodes_map.write() {
             if let Some(n) = nodes_map.get_mut(&node_hash) {
                 n.update(node);
             }
         }
     }
 
-    pub fn update_node(node_hash: u64, node: &mut Node) {
-        if let Ok(mut nodes_map) = GLOBAL_NODES_MAP.get().write() {
-            if let Some(n) = nodes_map.get_mut(&node_hash) {
-                node.mode = n.mode.clone();
-                n.update(node);
-            }
+    /// Apply a behaviour to the peer's reputation. A peer falling to `BAN_REPUTATION` is banned
+    /// and disconnected, returns false in that case.
+    pub fn rate_node(&self, node: &mut Node, behaviour: Behaviour) -> bool {
+        node.reputation = rate(node.reputation, behaviour);
+        if node.reputation > BAN_REPUTATION {
+            self.update_node(node.node_hash, node);
+            return true;
+        }
+
+        if let Ok(mut ban_list) = self.state.ban_list.lock() {
+            let ban = ban_list.ban(
+                &node.get_node_id(),
+                &node.ip_addr.get_ip(),
+                SystemTime::now(),
+            );
+            warn!(target: "net", "Node {}@{} banned: {:?}, last offence: {:?}", node.get_node_id(), node.get_ip_addr(), ban, behaviour);
         }
+        if let Ok(mut peer_store) = self.state.peer_store.lock() {
+            peer_store.remove(&node.get_node_id());
+        }
+        self.remove_peer(node.node_hash);
+        false
     }
 
-    pub fn process_inbounds(socket: TcpStream, handle: fn(node: &mut Node, req: ChannelBuffer)) {
-        if let Ok(peer_addr) = socket.peer_addr() {
-            let mut peer_node = Node::new_with_addr(peer_addr);
-            let peer_ip = peer_node.ip_addr.get_ip();
-            let local_ip = P2pMgr::get_local_node().ip_addr.get_ip();
-            let network_config = P2pMgr::get_network_config();
-            if P2pMgr::get_nodes_count(ALIVE) < network_config.max_peers as usize
-                && !network_config.ip_black_list.contains(&peer_ip)
-            {
-                let mut value = peer_node.ip_addr.get_addr();
-                value.push_str(&local_ip);
-                peer_node.node_hash = P2pMgr::calculate_hash(&value);
-                peer_node.state_code = CONNECTED;
-                trace!(target: "net", "New incoming connection: {}", peer_addr);
+    /// Same as `rate_node`, for a peer known by its hash only.
+    pub fn rate_peer(&self, node_hash: u64, behaviour: Behaviour) -> bool {
+        match self.get_node(node_hash) {
+            Some(mut node) => self.rate_node(&mut node, behaviour),
+            None => false,
+        }
+    }
 
-                let (tx, rx) = mpsc::channel(409600);
-                let thread_pool = P2pMgr::get_thread_pool();
+    pub fn is_node_banned(&self, node_id: &str) -> bool {
+        if let Ok(ban_list) = self.state.ban_list.lock() {
+            return ban_list.is_node_banned(node_id, SystemTime::now());
+        }
+        false
+    }
 
-                peer_node.tx = Some(tx);
-                peer_node.state_code = CONNECTED;
-                peer_node.ip_addr.is_server = false;
+    pub fn is_ip_banned(&self, ip: &str) -> bool {
+        if let Ok(ban_list) = self.state.ban_list.lock() {
+            return ban_list.is_ip_banned(ip, SystemTime::now());
+        }
+        false
+    }
 
-                trace!(target: "net", "A new peer added: {}", peer_node);
+    /// Disconnect a peer for `reason`.
+    pub fn disconnect(&self, node_hash: u64, reason: &str) {
+        if let Some(node) = self.remove_peer(node_hash) {
+            info!(target: "net", "Peer {}@{} disconnected: {}.", node.get_node_id(), node.get_ip_addr(), reason);
+        }
+    }
 
-                let mut node_hash = peer_node.node_hash;
-                P2pMgr::add_peer(peer_node, &socket);
-                // process request from the incoming stream
-                let (sink, stream) = P2pMgr::split_frame(socket);
-                let read = stream.for_each(move |msg| {
-                    if let Some(mut peer_node) = P2pMgr::get_node(node_hash) {
-                        handle(&mut peer_node, msg.clone());
-                        node_hash = P2pMgr::calculate_hash(&peer_node.get_node_id());
-                    }
+    /// Check a message from a peer against the frame size limit of its kind and, for requests,
+    /// against the rate limit of its action. The peer is disconnected if it is over a limit.
+    pub fn check_limits(&self, node: &Node, msg: &ChannelBuffer, request: bool) -> bool {
+        let network_config = self.get_network_config();
+        let max_size = if request {
+            network_config.max_request_frame_size
+        } else {
+            network_config.max_frame_size
+        };
+        let size = cmp::max(msg.head.len as usize, msg.body.len());
+        if size > max_size as usize {
+            let reason = format!(
+                "frame of {} bytes for action {} of control {}, over the limit of {}",
+                size, msg.head.action, msg.head.ctrl, max_size
+            );
+            self.disconnect(node.node_hash, &reason);
+            return false;
+        }
 
-                    Ok(())
-                });
+        if request {
+            let (ctrl, action) = (msg.head.ctrl, msg.head.action);
+            let now = Instant::now();
+            let admitted = match self.state.rate_limits.lock() {
+                Ok(mut rate_limits) => rate_limits.admit(node.node_hash, ctrl, action, now),
+                Err(_) => true,
+            };
+            if !admitted {
+                let reason = format!(
+                    "requests for action {} of control {} over the limit of {} a second",
+                    msg.head.action, msg.head.ctrl, network_config.request_rate
+                );
+                self.disconnect(node.node_hash, &reason);
+                return false;
+            }
+        }
 
-                thread_pool.spawn(read.then(|_| Ok(())));
+        true
+    }
+
+    pub fn process_inbounds(&self, socket: TcpStream, handle: Handle) {
+        if let Ok(peer_addr) = socket.peer_addr() {
+            let link = match socket.try_clone() {
+                Ok(socket) => Link::Tcp(socket),
+                Err(e) => {
+                    error!(target: "net", "{}", e);
+                    return;
+                }
+            };
+            let peer_node = Node::new_with_addr(peer_addr);
+            let peer_ip = peer_node.ip_addr.get_ip();
+            let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+            if let Some(node_hash) = self.add_inbound(peer_node, tx, link) {
+                // process request from the incoming stream
+                let (sink, stream) = self.split_frame(socket);
+                self.spawn_read(node_hash, stream, handle);
 
                 // send everything in rx to sink
                 let write = sink.send_all(rx.map_err(|()| {
                     io::Error::new(io::ErrorKind::Other, "rx shouldn't have an error")
                 }));
-                thread_pool.spawn(write.then(move |_| {
+                self.get_thread_pool().spawn(write.then(move |_| {
                     trace!(target:"net", "Connection with {:?} closed.", peer_ip);
                     Ok(())
                 }));
@@ -436,75 +629,139 @@ impl P2pMgr {
         }
     }
 
-    fn process_outbounds(
-        socket: TcpStream,
-        peer_node: Node,
-        handle: fn(node: &mut Node, req: ChannelBuffer),
-    )
-    {
-        let mut peer_node = peer_node.clone();
-        peer_node.node_hash = P2pMgr::calculate_hash(&peer_node.get_node_id());
+    fn process_outbounds(&self, socket: TcpStream, peer_node: Node, handle: Handle) {
+        let link = match socket.try_clone() {
+            Ok(socket) => Link::Tcp(socket),
+            Err(e) => {
+                error!(target: "net", "{}", e);
+                return;
+            }
+        };
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        if let Some(mut peer_node) = self.add_outbound(peer_node, tx, link) {
+            let peer_ip = peer_node.get_ip_addr().clone();
+
+            // process request from the outcoming stream
+            let (sink, stream) = self.split_frame(socket);
+
+            // OnConnect
+            let mut req = ChannelBuffer::new();
+            req.head.set_version(Version::V1);
+            handle(&mut peer_node, req);
+
+            self.spawn_read(peer_node.node_hash, stream, handle);
+
+            // send everything in rx to sink
+            let write = sink.send_all(
+                rx.map_err(|()| io::Error::new(io::ErrorKind::Other, "rx shouldn't have an error")),
+            );
+            self.get_thread_pool().spawn(write.then(move |_| {
+                trace!(target:"net", "Connection with {:?} closed.", peer_ip);
+                Ok(())
+            }));
+        }
+    }
+
+    // add a peer which connected to this node, known by its address until the handshake.
+    // returns the hash it is added with, none if not accepted.
+    fn add_inbound(&self, peer_node: Node, tx: Tx, link: Link) -> Option<u64> {
+        let mut peer_node = peer_node;
+        let peer_ip = peer_node.ip_addr.get_ip();
+        let local_ip = self.get_local_node().ip_addr.get_ip();
+        let network_config = self.get_network_config();
+        if self.get_nodes_count(ALIVE) >= network_config.max_peers as usize
+            || network_config.ip_black_list.contains(&peer_ip)
+            || self.is_ip_banned(&peer_ip)
+        {
+            return None;
+        }
+
+        let mut value = peer_node.ip_addr.get_addr();
+        value.push_str(&local_ip);
+        peer_node.node_hash = Self::calculate_hash(&value);
+        trace!(target: "net", "New incoming connection: {}", peer_node.get_ip_addr());
+
+        peer_node.tx = Some(tx);
+        peer_node.state_code = CONNECTED;
+        peer_node.ip_addr.is_server = false;
+
+        trace!(target: "net", "A new peer added: {}", peer_node);
+
+        let node_hash = peer_node.node_hash;
+        self.add_peer(peer_node, link);
+        Some(node_hash)
+    }
+
+    // add a peer this node connected to, none if it is already connected.
+    fn add_outbound(&self, peer_node: Node, tx: Tx, link: Link) -> Option<Node> {
+        let mut peer_node = peer_node;
+        peer_node.node_hash = Self::calculate_hash(&peer_node.get_node_id());
         let node_hash = peer_node.node_hash;
 
-        if let Some(node) = P2pMgr::get_node(node_hash) {
+        if let Some(node) = self.get_node(node_hash) {
             if node.state_code == DISCONNECTED {
                 trace!(target: "net", "update known peer node {}@{}...", node.get_node_id(), node.get_ip_addr());
-                P2pMgr::remove_peer(node_hash);
+                self.remove_peer(node_hash);
             } else {
-                return;
+                return None;
             }
         }
 
-        let (tx, rx) = mpsc::channel(409600);
         peer_node.tx = Some(tx);
         peer_node.state_code = CONNECTED | IS_SERVER;
         peer_node.ip_addr.is_server = true;
-        let peer_ip = peer_node.get_ip_addr().clone();
         trace!(target: "net", "A new peer added: {}@{}", peer_node.get_node_id(), peer_node.get_ip_addr());
 
-        P2pMgr::add_peer(peer_node.clone(), &socket);
-
-        // process request from the outcoming stream
-        let (sink, stream) = P2pMgr::split_frame(socket);
-
-        // OnConnect
-        let mut req = ChannelBuffer::new();
-        req.head.set_version(Version::V1);
-        handle(&mut peer_node, req);
+        self.add_peer(peer_node.clone(), link);
+        Some(peer_node)
+    }
 
-        let read = stream.for_each(move |msg| {
-            if let Some(mut peer_node) = P2pMgr::get_node(node_hash) {
-                handle(&mut peer_node, msg);
+    // pass the messages of a peer to the handle. The peer is looked up by its node id once
+    // the handshake sets it, and disconnected on a frame which cannot be read.
+    fn spawn_read<S>(&self, node_hash: u64, stream: S, handle: Handle)
+    where S: Stream<Item = ChannelBuffer, Error = io::Error> + Send + 'static {
+        let p2p = self.clone();
+        let mut node_hash = node_hash;
+        let read = stream.then(Ok::<_, ()>).for_each(move |msg| {
+            match msg {
+                Ok(msg) => {
+                    if let Some(mut peer_node) = p2p.get_node(node_hash) {
+                        handle(&mut peer_node, msg);
+                        node_hash = Self::calculate_hash(&peer_node.get_node_id());
+                    }
+                    Ok(())
+                }
+                Err(e) => {
+                    p2p.disconnect(node_hash, &format!("{}", e));
+                    Err(())
+                }
             }
-
-            Ok(())
         });
-        let thread_pool = P2pMgr::get_thread_pool();
-        thread_pool.spawn(read.then(|_| Ok(())));
+        self.get_thread_pool().spawn(read.then(|_| Ok(())));
+    }
 
-        // send everything in rx to sink
-        let write = sink.send_all(
-            rx.map_err(|()| io::Error::new(io::ErrorKind::Other, "rx shouldn't have an error")),
-        );
-        thread_pool.spawn(write.then(move |_| {
-            trace!(target:"net", "Connection with {:?} closed.", peer_ip);
-            Ok(())
-        }));
+    fn until_closed(
+        rx: mpsc::Receiver<ChannelBuffer>,
+        closed: Arc<AtomicBool>,
+    ) -> impl Stream<Item = ChannelBuffer, Error = io::Error> + Send
+    {
+        rx.take_while(move |_| Ok(!closed.load(Ordering::SeqCst)))
+            .map_err(|()| io::Error::new(io::ErrorKind::Other, "rx shouldn't have an error"))
     }
 
-    pub fn send(node_hash: u64, msg: ChannelBuffer) {
-        match Self::get_tx(node_hash) {
+    pub fn send(&self, node_hash: u64, msg: ChannelBuffer) {
+        match self.get_tx(node_hash) {
             Some(mut tx) => {
                 match tx.try_send(msg) {
                     Ok(()) => {}
                     Err(e) => {
-                        Self::remove_peer(node_hash);
+                        self.remove_peer(node_hash);
                         trace!(target: "net", "Failed to send the msg, Err: {}", e);
                     }
                 }
             }
             None => {
-                Self::remove_peer(node_hash);
+                self.remove_peer(node_hash);
                 trace!(target: "net", "Invalid peer !, node_hash: {}", node_hash);
             }
         }
@@ -517,16 +774,28 @@ impl P2pMgr {
     }
 
     pub fn split_frame(
+        &self,
         socket: TcpStream,
     ) -> (
         stream::SplitSink<Framed<TcpStream, P2pCodec>>,
         stream::SplitStream<Framed<TcpStream, P2pCodec>>,
     ) {
-        P2pCodec.framed(socket).split()
+        P2pCodec::new(self.get_network_config().max_frame_size).framed(socket).split()
     }
 }
 
-pub struct P2pCodec;
+/// Codec of the frames exchanged with peers, refusing the ones over `max_frame_size` bytes.
+pub struct P2pCodec {
+    max_frame_size: u32,
+}
+
+impl P2pCodec {
+    pub fn new(max_frame_size: u32) -> P2pCodec {
+        P2pCodec {
+            max_frame_size: max_frame_size,
+        }
+    }
+}
 
 impl Encoder for P2pCodec {
     type Item = ChannelBuffer;
@@ -558,6 +827,15 @@ impl Decoder for P2pCodec {
                 let (head_raw, _) = src.split_at(HEADER_LENGTH);
                 if let Ok(head) = decoder.deserialize(head_raw) {
                     decoded.head = head;
+                    if decoded.head.len > self.max_frame_size {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "frame of {} bytes over the limit of {}",
+                                decoded.head.len, self.max_frame_size
+                            ),
+                        ));
+                    }
                     if decoded.head.len as usize + HEADER_LENGTH > len {
                         return Ok(None);
                     }
@@ -587,8 +865,26 @@ pub struct NetworkConfig {
     pub local_node: String,
     /// if only sync from bootnodes
     pub sync_from_boot_nodes_only: bool,
+    /// if download the state at a recent block instead of executing the chain from genesis
+    pub fast_sync: bool,
+    /// if sync headers only and verify state with proofs instead of executing blocks
+    pub light: bool,
+    /// if discover peers over udp on the port of the local node
+    pub discovery: bool,
     /// IP black list
     pub ip_black_list: Vec<String>,
+    /// Largest frame a peer may send, in bytes
+    pub max_frame_size: u32,
+    /// Largest request a peer may send, in bytes
+    pub max_request_frame_size: u32,
+    /// Most headers served for a request
+    pub max_headers_per_request: u32,
+    /// Most block bodies served for a request
+    pub max_bodies_per_request: u32,
+    /// Requests of an action a peer may send a second
+    pub request_rate: u32,
+    /// Requests of an action a peer may send at once
+    pub request_burst: u32,
 }
 
 impl Default for NetworkConfig {
@@ -604,7 +900,16 @@ impl NetworkConfig {
             local_node: String::from("p2p://00000000-0000-0000-0000-000000000000@0.0.0.0:30303"),
             net_id: 0,
             sync_from_boot_nodes_only: false,
+            fast_sync: false,
+            light: false,
+            discovery: false,
             ip_black_list: Vec::new(),
+            max_frame_size: 64 * 1024 * 1024,
+            max_request_frame_size: 64 * 1024,
+            max_headers_per_request: 192,
+            max_bodies_per_request: 128,
+            request_rate: 100,
+            request_burst: 200,
         }
     }
 }
@@ -65,6 +65,7 @@ impl fmt::Display for Version {
 pub enum Control {
     NET = 0,
     SYNC = 1,
+    LIGHT = 2,
     UNKNOWN = 0xFF,
 }
 
@@ -73,6 +74,7 @@ impl Control {
         match *self {
             Control::NET => 0 as u8,
             Control::SYNC => 1 as u8,
+            Control::LIGHT => 2 as u8,
             Control::UNKNOWN => 0xFF as u8,
         }
     }
@@ -81,6 +83,7 @@ impl Control {
         match value {
             0 => Control::NET,
             1 => Control::SYNC,
+            2 => Control::LIGHT,
             _ => Control::UNKNOWN,
         }
     }
@@ -91,6 +94,7 @@ impl fmt::Display for Control {
         let printable = match *self {
             Control::NET => "NET",
             Control::SYNC => "SYNC",
+            Control::LIGHT => "LIGHT",
             Control::UNKNOWN => "UNKNOWN",
         };
         write!(f, "{}", printable)
@@ -45,28 +45,6 @@ pub const IS_SERVER: u32 = 1 << 1;
 pub const ALIVE: u32 = 1 << 3;
 pub const DISCONNECTED: u32 = 1 << 10;
 
-#[derive(Clone, PartialEq)]
-pub enum Mode {
-    NORMAL,
-    BACKWARD,
-    FORWARD,
-    LIGHTNING,
-    THUNDER,
-}
-
-impl fmt::Display for Mode {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let printable = match *self {
-            Mode::NORMAL => "NORMAL",
-            Mode::BACKWARD => "BACKWARD",
-            Mode::FORWARD => "FORWARD",
-            Mode::LIGHTNING => "LIGHTNING",
-            Mode::THUNDER => "THUNDER",
-        };
-        write!(f, "{}", printable)
-    }
-}
-
 #[derive(Clone, Copy, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct IpAddr {
     pub ip: [u8; 8],
@@ -132,7 +110,6 @@ pub struct Node {
     pub genesis_hash: H256,
     pub target_total_difficulty: U256,
     pub current_total_difficulty: U256,
-    pub mode: Mode,
     pub last_request_timestamp: SystemTime,
     pub last_request_num: u64,
     pub last_broadcast_timestamp: SystemTime,
@@ -140,6 +117,8 @@ pub struct Node {
     pub tx: Option<Tx>,
     pub is_from_boot_list: bool,
     pub repeated: u8,
+    pub reputation: i32,
+    pub capabilities: u8,
     pub revision: [u8; MAX_REVISION_LENGTH],
 }
 
@@ -157,7 +136,6 @@ impl Node {
             genesis_hash: H256::default(),
             target_total_difficulty: U256::default(),
             current_total_difficulty: U256::default(),
-            mode: Mode::NORMAL,
             last_request_timestamp: SystemTime::now(),
             last_request_num: 0,
             last_broadcast_timestamp: SystemTime::now(),
@@ -165,6 +143,8 @@ impl Node {
             tx: None,
             is_from_boot_list: false,
             repeated: 0,
+            reputation: 0,
+            capabilities: 0,
             revision: [b' '; MAX_REVISION_LENGTH],
         }
     }
@@ -233,13 +213,14 @@ impl Node {
         self.genesis_hash = node_new.genesis_hash;
         self.target_total_difficulty = node_new.target_total_difficulty;
         self.current_total_difficulty = node_new.current_total_difficulty;
-        self.mode = node_new.mode.clone();
         self.last_request_timestamp = node_new.last_request_timestamp;
         self.last_request_num = node_new.last_request_num;
         self.last_broadcast_timestamp = node_new.last_broadcast_timestamp;
         self.is_from_boot_list = node_new.is_from_boot_list;
         self.tx = node_new.tx.clone();
         self.repeated = node_new.repeated;
+        self.reputation = node_new.reputation;
+        self.capabilities = node_new.capabilities;
         self.revision = node_new.revision;
     }
 
@@ -282,7 +263,6 @@ impl fmt::Display for Node {
         write!(f, "    best block number: {}\n", self.best_block_num)?;
         write!(f, "    synced block number: {}\n", self.synced_block_num)?;
         write!(f, "    boot node: {}\n", self.is_from_boot_list)?;
-        write!(f, "    mode: {}\n", self.mode)?;
         write!(
             f,
             "    last request timestamp: {:?}\n",
@@ -297,6 +277,8 @@ impl fmt::Display for Node {
         write!(f, "    best hash: {:?}\n", self.best_hash)?;
         write!(f, "    genesis hash: {:?}\n", self.genesis_hash)?;
         write!(f, "    repeated: {:?}\n", self.repeated)?;
+        write!(f, "    reputation: {}\n", self.reputation)?;
+        write!(f, "    capabilities: {:08b}\n", self.capabilities)?;
         write!(
             f,
             "    total difficulty: {}\n",
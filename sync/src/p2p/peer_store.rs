@@ -0,0 +1,312 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Known peers kept across restarts.
+//!
+//! Every peer we managed to connect to is recorded with the last time it was seen alive, how
+//! often connecting to it worked or failed and its best block. The records are saved in
+//! `COL_NODE_INFO` and the most reliable of them seed the outbound connections on the next
+//! start, so a node does not depend on its boot nodes only. Peers not seen for
+//! `MAX_PEER_AGE_SECS`, or failing too often, are evicted and at most `MAX_STORED_PEERS` are kept.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use acore::db::COL_NODE_INFO;
+use kvdb::{self, DBTransaction, KeyValueDB};
+use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
+
+const KNOWN_PEERS_KEY: &'static [u8] = &*b"KNOWN_PEERS";
+
+/// Most peers kept in the store.
+pub const MAX_STORED_PEERS: usize = 512;
+/// Time after which a peer not seen alive is forgotten.
+pub const MAX_PEER_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+/// Failed connections, beyond the successful ones, after which a peer is forgotten.
+pub const MAX_PEER_FAILURES: u32 = 5;
+
+/// What we know about a peer from earlier connections.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerRecord {
+    pub node_id: String,
+    /// Address the peer accepts connections on, as `ip:port`.
+    pub ip_addr: String,
+    /// Seconds since the unix epoch the peer was last seen alive.
+    pub last_seen: u64,
+    pub successes: u32,
+    pub failures: u32,
+    pub best_block_num: u64,
+}
+
+impl PeerRecord {
+    fn score(&self) -> i64 { self.successes as i64 - self.failures as i64 }
+
+    fn is_stale(&self, now: u64) -> bool {
+        now.saturating_sub(self.last_seen) > MAX_PEER_AGE_SECS
+            || self.failures >= self.successes.saturating_add(MAX_PEER_FAILURES)
+    }
+
+    // the most reliable peers first, the most recently seen among equals.
+    fn compare(&self, other: &PeerRecord) -> Ordering {
+        other
+            .score()
+            .cmp(&self.score())
+            .then(other.last_seen.cmp(&self.last_seen))
+    }
+}
+
+impl Encodable for PeerRecord {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(6);
+        s.append(&self.node_id);
+        s.append(&self.ip_addr);
+        s.append(&self.last_seen);
+        s.append(&self.successes);
+        s.append(&self.failures);
+        s.append(&self.best_block_num);
+    }
+}
+
+impl Decodable for PeerRecord {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        Ok(PeerRecord {
+            node_id: rlp.val_at(0)?,
+            ip_addr: rlp.val_at(1)?,
+            last_seen: rlp.val_at(2)?,
+            successes: rlp.val_at(3)?,
+            failures: rlp.val_at(4)?,
+            best_block_num: rlp.val_at(5)?,
+        })
+    }
+}
+
+/// Seconds since the unix epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Known peers, backed by the node info column of a database if one is given.
+pub struct PeerStore {
+    db: Option<Arc<KeyValueDB>>,
+    records: HashMap<String, PeerRecord>,
+}
+
+impl PeerStore {
+    /// A store kept in memory only.
+    pub fn new() -> Self {
+        PeerStore {
+            db: None,
+            records: HashMap::new(),
+        }
+    }
+
+    /// Open the store on a database, loading the peers saved by an earlier run.
+    pub fn open(db: Arc<KeyValueDB>, now: u64) -> Self {
+        let records = match db.get(COL_NODE_INFO, KNOWN_PEERS_KEY) {
+            Ok(Some(value)) => {
+                UntrustedRlp::new(&value)
+                    .as_list::<PeerRecord>()
+                    .unwrap_or_else(|e| {
+                        warn!(target: "net", "Invalid known peers, starting afresh: {}", e);
+                        Vec::new()
+                    })
+            }
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                warn!(target: "net", "Error loading known peers: {}", e);
+                Vec::new()
+            }
+        };
+
+        let mut store = PeerStore {
+            db: Some(db),
+            records: records
+                .into_iter()
+                .map(|record| (record.node_id.clone(), record))
+                .collect(),
+        };
+        store.evict(now);
+        store
+    }
+
+    pub fn len(&self) -> usize { self.records.len() }
+
+    pub fn get(&self, node_id: &str) -> Option<&PeerRecord> { self.records.get(node_id) }
+
+    /// Record a successful connection to a peer, adding it if unknown.
+    pub fn on_connected(&mut self, node_id: &str, ip_addr: &str, best_block_num: u64, now: u64) {
+        let record = self
+            .records
+            .entry(node_id.to_owned())
+            .or_insert_with(|| {
+                PeerRecord {
+                    node_id: node_id.to_owned(),
+                    ip_addr: ip_addr.to_owned(),
+                    last_seen: now,
+                    successes: 0,
+                    failures: 0,
+                    best_block_num: best_block_num,
+                }
+            });
+        record.ip_addr = ip_addr.to_owned();
+        record.last_seen = now;
+        record.successes = record.successes.saturating_add(1);
+        if best_block_num > record.best_block_num {
+            record.best_block_num = best_block_num;
+        }
+    }
+
+    /// Refresh a known peer still connected.
+    pub fn on_alive(&mut self, node_id: &str, best_block_num: u64, now: u64) {
+        if let Some(record) = self.records.get_mut(node_id) {
+            record.last_seen = now;
+            if best_block_num > record.best_block_num {
+                record.best_block_num = best_block_num;
+            }
+        }
+    }
+
+    /// Record a failed connection to a known peer.
+    pub fn on_failed(&mut self, node_id: &str) {
+        if let Some(record) = self.records.get_mut(node_id) {
+            record.failures = record.failures.saturating_add(1);
+        }
+    }
+
+    /// Forget a peer, e.g. a banned one.
+    pub fn remove(&mut self, node_id: &str) -> Option<PeerRecord> { self.records.remove(node_id) }
+
+    /// Drop the stale peers and the least reliable ones over `MAX_STORED_PEERS`.
+    pub fn evict(&mut self, now: u64) {
+        self.records.retain(|_, record| !record.is_stale(now));
+        if self.records.len() > MAX_STORED_PEERS {
+            let evicted: Vec<String> = self
+                .sorted()
+                .into_iter()
+                .skip(MAX_STORED_PEERS)
+                .map(|record| record.node_id)
+                .collect();
+            for node_id in evicted {
+                self.records.remove(&node_id);
+            }
+        }
+    }
+
+    /// Up to `count` peers to connect to, the most reliable first.
+    pub fn best(&self, count: usize) -> Vec<PeerRecord> {
+        let mut records = self.sorted();
+        records.truncate(count);
+        records
+    }
+
+    /// Write the peers to the database, if the store has one.
+    pub fn save(&self) -> kvdb::Result<()> {
+        let db = match self.db {
+            Some(ref db) => db,
+            None => return Ok(()),
+        };
+        let records = self.sorted();
+        let mut stream = RlpStream::new_list(records.len());
+        for record in records.iter() {
+            stream.append(record);
+        }
+        let mut batch = DBTransaction::new();
+        batch.put_vec(COL_NODE_INFO, KNOWN_PEERS_KEY, stream.out());
+        db.write(batch)
+    }
+
+    fn sorted(&self) -> Vec<PeerRecord> {
+        let mut records: Vec<PeerRecord> = self.records.values().cloned().collect();
+        records.sort_by(|a, b| a.compare(b));
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use acore::db::COL_NODE_INFO;
+    use kvdb::MockDbRepository;
+
+    const NODE_ID: &'static str = "c33d1066-8c7e-496c-9c4e-c89318280274";
+    const IP_ADDR: &'static str = "13.92.155.115:30303";
+
+    #[test]
+    fn saves_and_loads_peers() {
+        let db = Arc::new(MockDbRepository::init(vec![COL_NODE_INFO.into()]));
+        let now = 1_000_000;
+        {
+            let mut store = PeerStore::open(db.clone(), now);
+            assert_eq!(store.len(), 0);
+            store.on_connected(NODE_ID, IP_ADDR, 10, now);
+            store.on_alive(NODE_ID, 20, now + 60);
+            store.on_failed(NODE_ID);
+            store.on_alive("c33d2207-729a-4584-86f1-e19ab97cf9ce", 30, now);
+            store.save().unwrap();
+        }
+
+        let store = PeerStore::open(db, now + 120);
+        assert_eq!(store.len(), 1);
+        assert_eq!(
+            store.get(NODE_ID),
+            Some(&PeerRecord {
+                node_id: NODE_ID.into(),
+                ip_addr: IP_ADDR.into(),
+                last_seen: now + 60,
+                successes: 1,
+                failures: 1,
+                best_block_num: 20,
+            })
+        );
+    }
+
+    #[test]
+    fn evicts_stale_and_failing_peers() {
+        let mut store = PeerStore::new();
+        let now = 1_000_000;
+        store.on_connected(NODE_ID, IP_ADDR, 0, now);
+        store.on_connected("old", "13.92.155.116:30303", 0, now - MAX_PEER_AGE_SECS - 1);
+        store.on_connected("failing", "13.92.155.117:30303", 0, now);
+        for _ in 0..MAX_PEER_FAILURES + 1 {
+            store.on_failed("failing");
+        }
+        store.evict(now);
+        assert_eq!(store.len(), 1);
+        assert!(store.get(NODE_ID).is_some());
+
+        for i in 0..MAX_STORED_PEERS + 10 {
+            store.on_connected(&format!("peer-{}", i), IP_ADDR, 0, now + i as u64);
+        }
+        store.on_connected(NODE_ID, IP_ADDR, 0, now);
+        store.evict(now);
+        assert_eq!(store.len(), MAX_STORED_PEERS);
+        // the peer connected to twice ranks first, the oldest of the others are dropped.
+        assert_eq!(store.best(1)[0].node_id, NODE_ID);
+        assert!(store.get("peer-0").is_none());
+        assert!(store.get(&format!("peer-{}", MAX_STORED_PEERS + 9)).is_some());
+    }
+}
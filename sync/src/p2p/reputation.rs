@@ -0,0 +1,197 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Peer reputation and bans.
+//!
+//! Every peer starts at a neutral score which useful responses raise and misbehaviour lowers.
+//! A peer whose score drops to `BAN_REPUTATION` is banned by node id and by ip, first for
+//! `TEMPORARY_BAN_SECS` and for good once it has been banned `MAX_TEMPORARY_BANS` times.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Highest score a peer can build up.
+pub const MAX_REPUTATION: i32 = 100;
+/// Score at which a peer gets banned.
+pub const BAN_REPUTATION: i32 = -100;
+/// Length of a temporary ban.
+pub const TEMPORARY_BAN_SECS: u64 = 30 * 60;
+/// Temporary bans after which a peer is banned permanently.
+pub const MAX_TEMPORARY_BANS: u32 = 3;
+
+/// Peer behaviour affecting its reputation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Behaviour {
+    /// A response which answered an outstanding request.
+    UsefulResponse,
+    /// A response which answered part of an outstanding request, e.g. a peer missing some
+    /// of the bodies asked.
+    PartialResponse,
+    /// Headers, bodies or messages failing validation.
+    InvalidData,
+    /// A block which failed to import.
+    BadBlock,
+    /// A request left unanswered.
+    Timeout,
+    /// A response to nothing that was asked.
+    Unsolicited,
+}
+
+impl Behaviour {
+    /// Change to the peer's score.
+    pub fn score(&self) -> i32 {
+        match *self {
+            Behaviour::UsefulResponse => 1,
+            Behaviour::PartialResponse => 0,
+            Behaviour::InvalidData => -40,
+            Behaviour::BadBlock => -60,
+            Behaviour::Timeout => -10,
+            Behaviour::Unsolicited => -5,
+        }
+    }
+}
+
+/// Apply a behaviour to a score, returns the new score.
+pub fn rate(reputation: i32, behaviour: Behaviour) -> i32 {
+    let reputation = reputation.saturating_add(behaviour.score());
+    if reputation > MAX_REPUTATION {
+        MAX_REPUTATION
+    } else {
+        reputation
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ban {
+    /// Banned until the given time.
+    Temporary(SystemTime),
+    /// Banned for good.
+    Permanent,
+}
+
+impl Ban {
+    fn is_active(&self, now: SystemTime) -> bool {
+        match *self {
+            Ban::Temporary(until) => until > now,
+            Ban::Permanent => true,
+        }
+    }
+}
+
+/// Banned node ids and ips.
+pub struct BanList {
+    node_ids: HashMap<String, Ban>,
+    ips: HashMap<String, Ban>,
+    strikes: HashMap<String, u32>,
+}
+
+impl BanList {
+    pub fn new() -> Self {
+        BanList {
+            node_ids: HashMap::new(),
+            ips: HashMap::new(),
+            strikes: HashMap::new(),
+        }
+    }
+
+    /// Ban a node id together with its ip. Repeated offenders are banned permanently.
+    pub fn ban(&mut self, node_id: &str, ip: &str, now: SystemTime) -> Ban {
+        let strikes = {
+            let strikes = self.strikes.entry(node_id.to_owned()).or_insert(0);
+            *strikes += 1;
+            *strikes
+        };
+        let ban = if strikes >= MAX_TEMPORARY_BANS {
+            Ban::Permanent
+        } else {
+            Ban::Temporary(now + Duration::from_secs(TEMPORARY_BAN_SECS))
+        };
+
+        self.node_ids.insert(node_id.to_owned(), ban);
+        self.ips.insert(ip.to_owned(), ban);
+        self.purge(now);
+        ban
+    }
+
+    pub fn is_node_banned(&self, node_id: &str, now: SystemTime) -> bool {
+        self.node_ids
+            .get(node_id)
+            .map_or(false, |ban| ban.is_active(now))
+    }
+
+    pub fn is_ip_banned(&self, ip: &str, now: SystemTime) -> bool {
+        self.ips.get(ip).map_or(false, |ban| ban.is_active(now))
+    }
+
+    /// Ban of a node id, if any is in force.
+    pub fn node_ban(&self, node_id: &str, now: SystemTime) -> Option<Ban> {
+        self.node_ids
+            .get(node_id)
+            .cloned()
+            .and_then(|ban| if ban.is_active(now) { Some(ban) } else { None })
+    }
+
+    // drop the temporary bans which ran out.
+    fn purge(&mut self, now: SystemTime) {
+        self.node_ids.retain(|_, ban| ban.is_active(now));
+        self.ips.retain(|_, ban| ban.is_active(now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_is_capped() {
+        assert_eq!(rate(MAX_REPUTATION, Behaviour::UsefulResponse), MAX_REPUTATION);
+        assert_eq!(rate(0, Behaviour::Timeout), -10);
+        assert_eq!(rate(0, Behaviour::PartialResponse), 0);
+        assert_eq!(rate(::std::i32::MIN, Behaviour::BadBlock), ::std::i32::MIN);
+    }
+
+    #[test]
+    fn bans_expire_until_permanent() {
+        let mut ban_list = BanList::new();
+        let now = SystemTime::now();
+        let node_id = "c33d1066-8c7e-496c-9c4e-c89318280274";
+        let ip = "13.92.155.115";
+
+        assert_eq!(
+            ban_list.ban(node_id, ip, now),
+            Ban::Temporary(now + Duration::from_secs(TEMPORARY_BAN_SECS))
+        );
+        assert!(ban_list.is_node_banned(node_id, now));
+        assert!(ban_list.is_ip_banned(ip, now));
+        assert!(!ban_list.is_ip_banned("13.92.155.116", now));
+
+        let later = now + Duration::from_secs(TEMPORARY_BAN_SECS);
+        assert!(!ban_list.is_node_banned(node_id, later));
+        assert!(!ban_list.is_ip_banned(ip, later));
+
+        for _ in 1..MAX_TEMPORARY_BANS {
+            ban_list.ban(node_id, ip, later);
+        }
+        assert_eq!(ban_list.node_ban(node_id, later), Some(Ban::Permanent));
+        assert!(ban_list.is_ip_banned(ip, later + Duration::from_secs(TEMPORARY_BAN_SECS)));
+    }
+}
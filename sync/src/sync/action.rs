@@ -31,6 +31,14 @@ pub enum SyncAction {
     BLOCKSBODIESRES = 5,
     BROADCASTTX = 6,
     BROADCASTBLOCK = 7,
+    ANNOUNCETXHASHES = 8,
+    TRANSACTIONSREQ = 9,
+    TRANSACTIONSRES = 10,
+    COMPACTBLOCK = 11,
+    BLOCKTRANSACTIONSREQ = 12,
+    BLOCKTRANSACTIONSRES = 13,
+    STATEDATAREQ = 14,
+    STATEDATARES = 15,
     UNKNOWN = 0xFF,
 }
 
@@ -45,6 +53,14 @@ impl SyncAction {
             SyncAction::BLOCKSBODIESRES => 5 as u8,
             SyncAction::BROADCASTTX => 6 as u8,
             SyncAction::BROADCASTBLOCK => 7 as u8,
+            SyncAction::ANNOUNCETXHASHES => 8 as u8,
+            SyncAction::TRANSACTIONSREQ => 9 as u8,
+            SyncAction::TRANSACTIONSRES => 10 as u8,
+            SyncAction::COMPACTBLOCK => 11 as u8,
+            SyncAction::BLOCKTRANSACTIONSREQ => 12 as u8,
+            SyncAction::BLOCKTRANSACTIONSRES => 13 as u8,
+            SyncAction::STATEDATAREQ => 14 as u8,
+            SyncAction::STATEDATARES => 15 as u8,
             SyncAction::UNKNOWN => 0xFF as u8,
         }
     }
@@ -59,9 +75,30 @@ impl SyncAction {
             5 => SyncAction::BLOCKSBODIESRES,
             6 => SyncAction::BROADCASTTX,
             7 => SyncAction::BROADCASTBLOCK,
+            8 => SyncAction::ANNOUNCETXHASHES,
+            9 => SyncAction::TRANSACTIONSREQ,
+            10 => SyncAction::TRANSACTIONSRES,
+            11 => SyncAction::COMPACTBLOCK,
+            12 => SyncAction::BLOCKTRANSACTIONSREQ,
+            13 => SyncAction::BLOCKTRANSACTIONSRES,
+            14 => SyncAction::STATEDATAREQ,
+            15 => SyncAction::STATEDATARES,
             _ => SyncAction::UNKNOWN,
         }
     }
+
+    /// Whether the action asks the peer for data, limited in size and rate.
+    pub fn is_request(&self) -> bool {
+        match *self {
+            SyncAction::STATUSREQ
+            | SyncAction::BLOCKSHEADERSREQ
+            | SyncAction::BLOCKSBODIESREQ
+            | SyncAction::TRANSACTIONSREQ
+            | SyncAction::BLOCKTRANSACTIONSREQ
+            | SyncAction::STATEDATAREQ => true,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for SyncAction {
@@ -75,8 +112,96 @@ impl fmt::Display for SyncAction {
             SyncAction::BLOCKSBODIESRES => "BLOCKSBODIESRES",
             SyncAction::BROADCASTTX => "BROADCASTTX",
             SyncAction::BROADCASTBLOCK => "BROADCASTBLOCK",
+            SyncAction::ANNOUNCETXHASHES => "ANNOUNCETXHASHES",
+            SyncAction::TRANSACTIONSREQ => "TRANSACTIONSREQ",
+            SyncAction::TRANSACTIONSRES => "TRANSACTIONSRES",
+            SyncAction::COMPACTBLOCK => "COMPACTBLOCK",
+            SyncAction::BLOCKTRANSACTIONSREQ => "BLOCKTRANSACTIONSREQ",
+            SyncAction::BLOCKTRANSACTIONSRES => "BLOCKTRANSACTIONSRES",
+            SyncAction::STATEDATAREQ => "STATEDATAREQ",
+            SyncAction::STATEDATARES => "STATEDATARES",
             SyncAction::UNKNOWN => "UNKNOWN",
         };
         write!(f, "{}", printable)
     }
 }
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub enum LightAction {
+    HEADERSREQ = 0,
+    HEADERSRES = 1,
+    HEADERPROOFREQ = 2,
+    HEADERPROOFRES = 3,
+    ACCOUNTPROOFREQ = 4,
+    ACCOUNTPROOFRES = 5,
+    STORAGEPROOFREQ = 6,
+    STORAGEPROOFRES = 7,
+    RECEIPTPROOFREQ = 8,
+    RECEIPTPROOFRES = 9,
+    UNKNOWN = 0xFF,
+}
+
+impl LightAction {
+    pub fn value(&self) -> u8 {
+        match *self {
+            LightAction::HEADERSREQ => 0 as u8,
+            LightAction::HEADERSRES => 1 as u8,
+            LightAction::HEADERPROOFREQ => 2 as u8,
+            LightAction::HEADERPROOFRES => 3 as u8,
+            LightAction::ACCOUNTPROOFREQ => 4 as u8,
+            LightAction::ACCOUNTPROOFRES => 5 as u8,
+            LightAction::STORAGEPROOFREQ => 6 as u8,
+            LightAction::STORAGEPROOFRES => 7 as u8,
+            LightAction::RECEIPTPROOFREQ => 8 as u8,
+            LightAction::RECEIPTPROOFRES => 9 as u8,
+            LightAction::UNKNOWN => 0xFF as u8,
+        }
+    }
+
+    pub fn from(value: u8) -> LightAction {
+        match value {
+            0 => LightAction::HEADERSREQ,
+            1 => LightAction::HEADERSRES,
+            2 => LightAction::HEADERPROOFREQ,
+            3 => LightAction::HEADERPROOFRES,
+            4 => LightAction::ACCOUNTPROOFREQ,
+            5 => LightAction::ACCOUNTPROOFRES,
+            6 => LightAction::STORAGEPROOFREQ,
+            7 => LightAction::STORAGEPROOFRES,
+            8 => LightAction::RECEIPTPROOFREQ,
+            9 => LightAction::RECEIPTPROOFRES,
+            _ => LightAction::UNKNOWN,
+        }
+    }
+
+    /// Whether the action asks the peer for data, limited in size and rate.
+    pub fn is_request(&self) -> bool {
+        match *self {
+            LightAction::HEADERSREQ
+            | LightAction::HEADERPROOFREQ
+            | LightAction::ACCOUNTPROOFREQ
+            | LightAction::STORAGEPROOFREQ
+            | LightAction::RECEIPTPROOFREQ => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for LightAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let printable = match *self {
+            LightAction::HEADERSREQ => "HEADERSREQ",
+            LightAction::HEADERSRES => "HEADERSRES",
+            LightAction::HEADERPROOFREQ => "HEADERPROOFREQ",
+            LightAction::HEADERPROOFRES => "HEADERPROOFRES",
+            LightAction::ACCOUNTPROOFREQ => "ACCOUNTPROOFREQ",
+            LightAction::ACCOUNTPROOFRES => "ACCOUNTPROOFRES",
+            LightAction::STORAGEPROOFREQ => "STORAGEPROOFREQ",
+            LightAction::STORAGEPROOFRES => "STORAGEPROOFRES",
+            LightAction::RECEIPTPROOFREQ => "RECEIPTPROOFREQ",
+            LightAction::RECEIPTPROOFRES => "RECEIPTPROOFRES",
+            LightAction::UNKNOWN => "UNKNOWN",
+        };
+        write!(f, "{}", printable)
+    }
+}
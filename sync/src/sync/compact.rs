@@ -0,0 +1,216 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Compact block relay.
+//!
+//! A compact block carries the sealed header and, for each transaction, a short id made of the
+//! first 8 bytes of the transaction hash. The receiver rebuilds the block from the transactions
+//! it already holds and asks the sender only for those it misses, by index. A rebuilt block is
+//! checked against the transactions root of the header before it is imported, so that short id
+//! collisions never reach the importer; on a mismatch, all transactions are asked for.
+
+use std::collections::HashMap;
+
+use acore::block::Block;
+use acore::header::{Header as BlockHeader, Seal};
+use acore::transaction::UnverifiedTransaction;
+use aion_types::H256;
+use rlp::{DecoderError, Encodable, RlpStream, UntrustedRlp};
+use triehash::ordered_trie_root;
+
+pub type ShortId = u64;
+
+/// Short id of a transaction.
+pub fn short_id(hash: &H256) -> ShortId {
+    hash.iter()
+        .take(8)
+        .fold(0u64, |id, byte| (id << 8) | *byte as u64)
+}
+
+/// A block being rebuilt from a compact block.
+pub struct CompactBlock {
+    header: BlockHeader,
+    short_ids: Vec<ShortId>,
+    transactions: Vec<Option<UnverifiedTransaction>>,
+}
+
+impl CompactBlock {
+    /// Encode a block as compact block message body.
+    pub fn encode(block: &Block) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(2);
+        block.header.stream_rlp(&mut stream, Seal::With);
+        stream.begin_list(block.transactions.len());
+        for transaction in block.transactions.iter() {
+            stream.append(&short_id(&transaction.hash()));
+        }
+        stream.out()
+    }
+
+    /// Decode a compact block message body.
+    pub fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let header: BlockHeader = rlp.val_at(0)?;
+        let short_ids: Vec<ShortId> = rlp.list_at(1)?;
+        let transactions = short_ids.iter().map(|_| None).collect();
+        Ok(CompactBlock {
+            header: header,
+            short_ids: short_ids,
+            transactions: transactions,
+        })
+    }
+
+    pub fn header(&self) -> &BlockHeader { &self.header }
+
+    pub fn short_ids(&self) -> &[ShortId] { &self.short_ids }
+
+    /// Fill in the transactions found among the known ones, returns the indexes still missing.
+    pub fn reconstruct<I>(&mut self, known: I) -> Vec<usize>
+    where I: IntoIterator<Item = UnverifiedTransaction> {
+        let mut known: HashMap<ShortId, UnverifiedTransaction> = known
+            .into_iter()
+            .map(|transaction| (short_id(&transaction.hash()), transaction))
+            .collect();
+        for (index, id) in self.short_ids.iter().enumerate() {
+            if self.transactions[index].is_none() {
+                self.transactions[index] = known.remove(id);
+            }
+        }
+        self.missing()
+    }
+
+    /// Indexes of the transactions not known yet.
+    pub fn missing(&self) -> Vec<usize> {
+        self.transactions
+            .iter()
+            .enumerate()
+            .filter(|&(_, transaction)| transaction.is_none())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Fill in the missing transactions sent back by a peer, in index order. Returns false if
+    /// they don't answer what is missing.
+    pub fn fill(&mut self, transactions: Vec<UnverifiedTransaction>) -> bool {
+        let missing = self.missing();
+        if missing.len() != transactions.len() {
+            return false;
+        }
+        for (index, transaction) in missing.into_iter().zip(transactions.into_iter()) {
+            if short_id(&transaction.hash()) != self.short_ids[index] {
+                return false;
+            }
+            self.transactions[index] = Some(transaction);
+        }
+        true
+    }
+
+    /// The rebuilt block, if complete and matching the transactions root of its header.
+    /// Otherwise the compact block is given back with all its transactions forgotten, so that
+    /// they get requested in full.
+    pub fn into_block(mut self) -> Result<Block, CompactBlock> {
+        let matches = self.transactions.iter().all(Option::is_some) && {
+            let transactions_root = ordered_trie_root(
+                self.transactions
+                    .iter()
+                    .filter_map(Option::as_ref)
+                    .map(|transaction| transaction.rlp_bytes()),
+            );
+            &transactions_root == self.header.transactions_root()
+        };
+        if !matches {
+            self.transactions = self.short_ids.iter().map(|_| None).collect();
+            return Err(self);
+        }
+
+        Ok(Block {
+            header: self.header,
+            transactions: self.transactions.into_iter().filter_map(|t| t).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use acore::transaction::Transaction;
+    use aion_types::Address;
+
+    fn block(count: u64) -> Block {
+        let transactions: Vec<UnverifiedTransaction> = (0..count)
+            .map(|nonce| {
+                let mut transaction = Transaction::default();
+                transaction.nonce = nonce.into();
+                transaction.fake_sign(Address::default()).into()
+            })
+            .collect();
+        let mut header = BlockHeader::new();
+        header.set_transactions_root(ordered_trie_root(
+            transactions
+                .iter()
+                .map(|transaction| transaction.rlp_bytes()),
+        ));
+        Block {
+            header: header,
+            transactions: transactions,
+        }
+    }
+
+    #[test]
+    fn rebuilds_block_from_known_and_requested_transactions() {
+        let block = block(4);
+        let encoded = CompactBlock::encode(&block);
+        let mut compact = CompactBlock::decode(&UntrustedRlp::new(&encoded)).unwrap();
+        assert_eq!(compact.header().hash(), block.header.hash());
+
+        let known = vec![block.transactions[2].clone(), block.transactions[0].clone()];
+        assert_eq!(compact.reconstruct(known), vec![1, 3]);
+
+        // wrong order is refused.
+        assert!(!compact.fill(vec![
+            block.transactions[3].clone(),
+            block.transactions[1].clone(),
+        ]));
+        let mut compact = CompactBlock::decode(&UntrustedRlp::new(&encoded)).unwrap();
+        compact.reconstruct(vec![block.transactions[0].clone(), block.transactions[2].clone()]);
+        assert!(compact.fill(vec![
+            block.transactions[1].clone(),
+            block.transactions[3].clone(),
+        ]));
+
+        let rebuilt = compact.into_block().unwrap();
+        assert_eq!(rebuilt.rlp_bytes(Seal::With), block.rlp_bytes(Seal::With));
+    }
+
+    #[test]
+    fn refuses_block_not_matching_transactions_root() {
+        let block = block(2);
+        let mut compact =
+            CompactBlock::decode(&UntrustedRlp::new(&CompactBlock::encode(&block))).unwrap();
+        compact.reconstruct(block.transactions.clone());
+        compact.transactions.swap(0, 1);
+        let compact = match compact.into_block() {
+            Ok(_) => panic!("block rebuilt with transactions in the wrong order"),
+            Err(compact) => compact,
+        };
+        // everything is asked for again.
+        assert_eq!(compact.missing(), vec![0, 1]);
+    }
+}
@@ -19,71 +19,50 @@
  *
  ******************************************************************************/
 
-use acore::block::Block;
 use acore::client::BlockId;
+use acore::transaction::UnverifiedTransaction;
 use aion_types::H256;
 use bytes::BufMut;
 use rlp::{RlpStream, UntrustedRlp};
-use std::time::SystemTime;
+use std::cmp;
+use std::time::Instant;
 
 use super::super::action::SyncAction;
 use super::super::event::SyncEvent;
-use super::super::storage::{BlocksWrapper, SyncStorage};
+use super::super::skeleton::Response;
+use super::super::storage::SyncStorage;
 use p2p::*;
 
-use super::blocks_headers_handler::BlockHeadersHandler;
+use super::skeleton_handler::SkeletonHandler;
+use super::state_sync_handler::StateSyncHandler;
 
 const HASH_LEN: usize = 32;
 
 pub struct BlockBodiesHandler;
 
 impl BlockBodiesHandler {
-    pub fn send_blocks_bodies_req() {
+    pub fn send_blocks_bodies_req(p2p: &P2pMgr, node_hash: u64, hashes: Vec<H256>) {
         let mut req = ChannelBuffer::new();
         req.head.ver = Version::V0.value();
         req.head.ctrl = Control::SYNC.value();
         req.head.action = SyncAction::BLOCKSBODIESREQ.value();
 
-        let mut hws = Vec::new();
-        if let Ok(mut downloaded_headers) = SyncStorage::get_downloaded_headers().try_lock() {
-            while let Some(hw) = downloaded_headers.pop_front() {
-                if !hw.headers.is_empty() {
-                    hws.push(hw);
-                }
-            }
+        for hash in hashes.iter() {
+            req.body.put_slice(hash);
         }
+        req.head.set_length(req.body.len() as u32);
 
-        for hw in hws.iter() {
-            let mut req = req.clone();
-            req.body.clear();
-
-            for header in hw.headers.iter() {
-                if !SyncStorage::is_imported_block_hash(&header.hash()) {
-                    req.body.put_slice(&header.hash());
-                }
-            }
-
-            let body_len = req.body.len();
-            if body_len > 0 {
-                if let Ok(ref mut headers_with_bodies_requested) =
-                    SyncStorage::get_headers_with_bodies_requested().lock()
-                {
-                    if !headers_with_bodies_requested.contains_key(&hw.node_hash) {
-                        req.head.set_length(body_len as u32);
-
-                        P2pMgr::send(hw.node_hash, req);
-
-                        trace!(target: "sync", "Sync blocks bodies req sent...");
-                        let mut hw = hw.clone();
-                        hw.timestamp = SystemTime::now();
-                        headers_with_bodies_requested.insert(hw.node_hash, hw);
-                    }
-                }
-            }
-        }
+        p2p.send(node_hash, req);
+        trace!(target: "sync", "Sync blocks bodies req sent...");
     }
 
-    pub fn handle_blocks_bodies_req(node: &mut Node, req: ChannelBuffer) {
+    pub fn handle_blocks_bodies_req(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
         trace!(target: "sync", "BLOCKSBODIESREQ received.");
 
         let mut res = ChannelBuffer::new();
@@ -94,11 +73,12 @@ impl BlockBodiesHandler {
         res.head.action = SyncAction::BLOCKSBODIESRES.value();
 
         let mut res_body = Vec::new();
-        let hash_count = req.body.len() / HASH_LEN;
+        let max_bodies = p2p.get_network_config().max_bodies_per_request as usize;
+        let hash_count = cmp::min(req.body.len() / HASH_LEN, max_bodies);
         let mut rest = req.body.as_slice();
         let mut data = Vec::new();
         let mut body_count = 0;
-        let client = SyncStorage::get_block_chain();
+        let client = storage.get_block_chain();
         for _i in 0..hash_count {
             let (hash, next) = rest.split_at(HASH_LEN);
 
@@ -123,73 +103,68 @@ impl BlockBodiesHandler {
         res.head.set_length(res.body.len() as u32);
 
         SyncEvent::update_node_state(node, SyncEvent::OnBlockBodiesReq);
-        P2pMgr::update_node(node_hash, node);
-        P2pMgr::send(node_hash, res);
+        p2p.update_node(node_hash, node);
+        p2p.send(node_hash, res);
     }
 
-    pub fn handle_blocks_bodies_res(node: &mut Node, req: ChannelBuffer) {
+    pub fn handle_blocks_bodies_res(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
         trace!(target: "sync", "BLOCKSBODIESRES received from: {}.", node.get_ip_addr());
 
         let node_hash = node.node_hash;
-        let mut blocks = Vec::new();
+        let mut bodies = Vec::new();
         if req.body.len() > 0 {
-            match SyncStorage::pick_headers_with_bodies_requested(&node_hash) {
-                Some(hw) => {
-                    let headers = hw.headers;
-                    if !headers.is_empty() {
-                        let rlp = UntrustedRlp::new(req.body.as_slice());
-
-                        let mut bodies = Vec::new();
-                        for block_bodies in rlp.iter() {
-                            for block_body in block_bodies.iter() {
-                                let mut transactions = Vec::new();
-                                if !block_body.is_empty() {
-                                    for transaction_rlp in block_body.iter() {
-                                        if !transaction_rlp.is_empty() {
-                                            if let Ok(transaction) = transaction_rlp.as_val() {
-                                                transactions.push(transaction);
-                                            }
-                                        }
-                                    }
+            let rlp = UntrustedRlp::new(req.body.as_slice());
+            for block_bodies in rlp.iter() {
+                for block_body in block_bodies.iter() {
+                    let mut transactions: Vec<UnverifiedTransaction> = Vec::new();
+                    if !block_body.is_empty() {
+                        for transaction_rlp in block_body.iter() {
+                            if !transaction_rlp.is_empty() {
+                                if let Ok(transaction) = transaction_rlp.as_val() {
+                                    transactions.push(transaction);
                                 }
-                                bodies.push(transactions);
-                            }
-                        }
-
-                        if headers.len() == bodies.len() {
-                            for i in 0..headers.len() {
-                                let block = Block {
-                                    header: headers[i].clone(),
-                                    transactions: bodies[i].clone(),
-                                };
-                                blocks.push(block);
                             }
-                        } else {
-                            debug!(
-                                target: "sync",
-                                "Count mismatch, headers count: {}, bodies count: {}, node id: {}",
-                                headers.len(),
-                                bodies.len(),
-                                node.get_node_id()
-                            );
-                            blocks.clear();
-                        }
-
-                        if !blocks.is_empty() {
-                            let mut bw = BlocksWrapper::new();
-                            bw.node_id_hash = node.node_hash;
-                            bw.blocks.extend(blocks);
-                            SyncStorage::insert_downloaded_blocks(bw);
                         }
                     }
+                    bodies.push(transactions);
                 }
-                None => {}
             }
         }
 
-        BlockHeadersHandler::get_headers_from_node(node);
+        let response = if StateSyncHandler::is_active(storage) {
+            StateSyncHandler::on_bodies(storage, node_hash, bodies)
+        } else if let Ok(mut skeleton) = storage.get_skeleton().lock() {
+            skeleton.on_bodies(node_hash, bodies, Instant::now())
+        } else {
+            warn!(target: "sync", "skeleton_mutex lock failed");
+            Response::Unexpected
+        };
+
+        let behaviour = match response {
+            Response::Accepted => Behaviour::UsefulResponse,
+            Response::Partial => {
+                debug!(target: "sync", "Bodies missing from the response of node: {}", node.get_node_id());
+                Behaviour::PartialResponse
+            }
+            Response::Rejected => {
+                debug!(target: "sync", "Bodies not matching their headers from node: {}", node.get_node_id());
+                Behaviour::InvalidData
+            }
+            Response::Unexpected => {
+                Behaviour::Unsolicited
+            }
+        };
 
         SyncEvent::update_node_state(node, SyncEvent::OnBlockBodiesRes);
-        P2pMgr::update_node(node_hash, node);
+        let answered = response == Response::Accepted || response == Response::Partial;
+        if p2p.rate_node(node, behaviour) && answered {
+            SkeletonHandler::schedule(p2p, storage);
+        }
     }
 }
@@ -19,43 +19,65 @@
  *
  ******************************************************************************/
 
+use acore::block::Block;
 use acore::client::{BlockChainClient, BlockId, BlockImportError};
 use acore::error::{BlockError, ImportError};
-use acore::header::Header as BlockHeader;
+use acore::header::{Header as BlockHeader, Seal};
 use acore::transaction::UnverifiedTransaction;
 use aion_types::H256;
 use bytes::BufMut;
-use rlp::{RlpStream, UntrustedRlp};
+use rand::{thread_rng, Rng};
+use rlp::{DecoderError, RlpStream, UntrustedRlp};
+use std::collections::HashSet;
 use std::sync::Arc;
-use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use super::super::action::SyncAction;
+use super::super::compact::{short_id, CompactBlock, ShortId};
 use super::super::event::SyncEvent;
 use super::super::storage::SyncStorage;
+use super::status_handler::{COMPACT_BLOCK_CAPABILITY, TX_ANNOUNCE_CAPABILITY};
 use p2p::*;
 
 const MAX_NEW_BLOCK_AGE: u64 = 20;
+const HASH_LEN: usize = 32;
+const MAX_ANNOUNCED_HASHES: usize = 4096;
+
+// value of the shared transaction hashes cache
+const TRANSACTION_SEEN: u8 = 0;
 
 pub struct BroadcastsHandler;
 
 impl BroadcastsHandler {
-    pub fn broad_new_transactions() {
+    pub fn broad_new_transactions(p2p: &P2pMgr, storage: &SyncStorage) {
         // broadcast new transactions
         let mut transactions = Vec::new();
-        let mut size = 0;
-        if let Ok(mut received_transactions) = SyncStorage::get_received_transactions().try_lock() {
-            while let Some(transaction) = received_transactions.pop_front() {
-                transactions.extend_from_slice(&transaction);
-                size += 1;
+        let mut hashes = Vec::new();
+        if let Ok(mut received_transactions) = storage.get_received_transactions().try_lock() {
+            if let Ok(mut announced_transactions) =
+                storage.get_announced_transactions().lock()
+            {
+                while let Some(transaction) = received_transactions.pop_front() {
+                    let hash = match UntrustedRlp::new(&transaction).as_val() {
+                        Ok(t) => {
+                            let tx: UnverifiedTransaction = t;
+                            tx.hash()
+                        }
+                        Err(_) => continue,
+                    };
+                    transactions.extend_from_slice(&transaction);
+                    hashes.push(hash);
+                    announced_transactions.insert(hash, transaction);
+                }
             }
         }
 
+        let size = hashes.len();
         if size < 1 {
             return;
         }
 
-        let active_nodes = P2pMgr::get_nodes(ALIVE);
+        let mut active_nodes = p2p.get_nodes(ALIVE);
 
         if active_nodes.len() > 0 {
             let mut req = ChannelBuffer::new();
@@ -69,48 +91,51 @@ impl BroadcastsHandler {
 
             req.head.len = req.body.len() as u32;
 
-            let mut node_count = 0;
+            let mut announcement = ChannelBuffer::new();
+            announcement.head.ver = Version::V0.value();
+            announcement.head.ctrl = Control::SYNC.value();
+            announcement.head.action = SyncAction::ANNOUNCETXHASHES.value();
+            for hash in hashes.iter() {
+                announcement.body.put_slice(hash);
+            }
+            announcement.head.len = announcement.body.len() as u32;
+
+            // full bodies go to sqrt(peers) random peers and to the peers which can't take
+            // announcements, the others only get the hashes and ask for what they miss.
+            thread_rng().shuffle(&mut active_nodes);
+            let full_push_count = (active_nodes.len() as f64).sqrt().ceil() as usize;
+            let mut pushed = 0;
             for node in active_nodes.iter() {
-                P2pMgr::send(node.node_hash, req.clone());
-                trace!(target: "sync", "Sync broadcast new transactions sent...");
-                node_count += 1;
-                if node_count > 10 {
-                    break;
+                if node.capabilities & TX_ANNOUNCE_CAPABILITY != 0 && pushed >= full_push_count {
+                    p2p.send(node.node_hash, announcement.clone());
+                    trace!(target: "sync", "Sync new transaction hashes announced...");
                 } else {
-                    thread::sleep(Duration::from_millis(50));
+                    p2p.send(node.node_hash, req.clone());
+                    trace!(target: "sync", "Sync broadcast new transactions sent...");
+                    pushed += 1;
                 }
             }
-            debug!(target: "sync", "Sync broadcasted {} new transactions...", size);
+            debug!(target: "sync", "Sync broadcasted {} new transactions to {} nodes, announced to {} nodes...", size, pushed, active_nodes.len() - pushed);
         }
     }
 
-    pub fn propagate_new_blocks(block_hash: &H256, client: Arc<BlockChainClient>) {
+    pub fn propagate_new_blocks(p2p: &P2pMgr, block_hash: &H256, client: Arc<BlockChainClient>) {
         // broadcast new blocks
-        let active_nodes = P2pMgr::get_nodes(ALIVE);
-
-        if active_nodes.len() > 0 {
-            let mut req = ChannelBuffer::new();
-            req.head.ver = Version::V0.value();
-            req.head.ctrl = Control::SYNC.value();
-            req.head.action = SyncAction::BROADCASTBLOCK.value();
-
-            if let Some(block_rlp) = client.block(BlockId::Hash(block_hash.clone())) {
-                req.body.put_slice(&block_rlp.into_inner());
-
-                req.head.len = req.body.len() as u32;
-
-                for node in active_nodes.iter() {
-                    P2pMgr::send(node.node_hash, req.clone());
-                    trace!(target: "sync", "Sync broadcast new block sent...");
-                }
-            }
+        if let Some(block_rlp) = client.block(BlockId::Hash(block_hash.clone())) {
+            Self::relay_block(p2p, &block_rlp.into_inner());
         }
     }
 
-    pub fn handle_broadcast_block(node: &mut Node, req: ChannelBuffer) {
+    pub fn handle_broadcast_block(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
         trace!(target: "sync", "BROADCASTBLOCK received.");
 
-        if SyncStorage::get_synced_block_number() + 4 < SyncStorage::get_network_best_block_number()
+        if storage.get_synced_block_number() + 4 < storage.get_network_best_block_number()
         {
             // Ignore BROADCASTBLOCK message until full synced
             trace!(target: "sync", "Syncing..., ignore BROADCASTBLOCK message.");
@@ -121,63 +146,361 @@ impl BroadcastsHandler {
         if let Ok(header_rlp) = block_rlp.at(0) {
             if let Ok(h) = header_rlp.as_val() {
                 let header: BlockHeader = h;
-                let last_imported_number = SyncStorage::get_synced_block_number();
-                let hash = header.hash();
+                if Self::is_new_block(storage, &header) {
+                    let block_rlp = block_rlp.as_raw().to_vec();
+                    Self::import_new_block(p2p, storage, node, &header, block_rlp);
+                }
+                SyncEvent::update_node_state(node, SyncEvent::OnBroadCastBlock);
+            }
+        }
+    }
 
-                if last_imported_number > header.number()
-                    && last_imported_number - header.number() > MAX_NEW_BLOCK_AGE
-                {
-                    trace!(target: "sync", "Ignored ancient new block {:?}", header.hash());
+    pub fn handle_compact_block(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        trace!(target: "sync", "COMPACTBLOCK received.");
+
+        if storage.get_synced_block_number() + 4 < storage.get_network_best_block_number()
+        {
+            // Ignore COMPACTBLOCK message until full synced
+            trace!(target: "sync", "Syncing..., ignore COMPACTBLOCK message.");
+            return;
+        }
+
+        let mut compact = match CompactBlock::decode(&UntrustedRlp::new(req.body.as_slice())) {
+            Ok(compact) => compact,
+            Err(e) => {
+                debug!(target: "sync", "Invalid compact block from node {}: {:?}", node.get_node_id(), e);
+                p2p.rate_node(node, Behaviour::InvalidData);
+                return;
+            }
+        };
+        // no transactions are asked for a block not even sealed right.
+        if let Err(e) = storage.get_block_chain().verify_header_basic(compact.header()) {
+            debug!(target: "sync", "Invalid compact block header from node {}: {:?}", node.get_node_id(), e);
+            p2p.rate_node(node, Behaviour::InvalidData);
+            return;
+        }
+        if !Self::is_new_block(storage, compact.header()) {
+            return;
+        }
+
+        let known = Self::known_transactions(storage, compact.short_ids());
+        let missing = compact.reconstruct(known);
+        if missing.is_empty() {
+            let from_peer = compact.short_ids().is_empty();
+            Self::import_compact_block(p2p, storage, node, compact, from_peer);
+        } else {
+            debug!(target: "sync", "Compact block {:?} misses {} transactions, requesting them from node {}.", compact.header().hash(), missing.len(), node.get_node_id());
+            Self::request_block_transactions(p2p, storage, node, compact);
+        }
+        SyncEvent::update_node_state(node, SyncEvent::OnBroadCastBlock);
+    }
+
+    pub fn handle_block_transactions_req(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        trace!(target: "sync", "BLOCKTRANSACTIONSREQ received.");
+
+        let request_rlp = UntrustedRlp::new(req.body.as_slice());
+        let request: Result<(H256, Vec<u64>), DecoderError> = request_rlp
+            .val_at(0)
+            .and_then(|hash| request_rlp.list_at(1).map(|indexes| (hash, indexes)));
+        let (hash, indexes) = match request {
+            Ok(request) => request,
+            Err(e) => {
+                debug!(target: "sync", "Invalid block transactions request from node {}: {:?}", node.get_node_id(), e);
+                p2p.rate_node(node, Behaviour::InvalidData);
+                return;
+            }
+        };
+
+        let client = storage.get_block_chain();
+        let transactions = match client.block(BlockId::Hash(hash)) {
+            Some(block) => block.decode().transactions,
+            None => return,
+        };
+
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&hash);
+        stream.begin_list(indexes.len());
+        for index in indexes {
+            match transactions.get(index as usize) {
+                Some(transaction) => {
+                    stream.append(transaction);
+                }
+                None => {
+                    p2p.rate_node(node, Behaviour::InvalidData);
                     return;
                 }
+            }
+        }
 
-                let parent_hash = header.parent_hash();
-                let client = SyncStorage::get_block_chain();
-                match client.block_header(BlockId::Hash(*parent_hash)) {
-                    Some(_) => {
-                        if let Ok(ref mut imported_block_hashes) =
-                            SyncStorage::get_imported_block_hashes().lock()
-                        {
-                            if !imported_block_hashes.contains_key(&hash) {
-                                let result = client.import_block(block_rlp.as_raw().to_vec());
-
-                                match result {
-                                    Ok(_) => {
-                                        trace!(target: "sync", "New broadcast block imported {:?} ({})", hash, header.number());
-                                        imported_block_hashes.insert(hash, 0);
-                                        let active_nodes = P2pMgr::get_nodes(ALIVE);
-                                        for n in active_nodes.iter() {
-                                            // broadcast new block
-                                            trace!(target: "sync", "Sync broadcast new block sent...");
-                                            P2pMgr::send(n.node_hash, req.clone());
-                                        }
-                                    }
-                                    Err(BlockImportError::Import(ImportError::AlreadyInChain)) => {
-                                        trace!(target: "sync", "New block already in chain {:?}", hash);
-                                    }
-                                    Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {
-                                        trace!(target: "sync", "New block already queued {:?}", hash);
-                                    }
-                                    Err(BlockImportError::Block(BlockError::UnknownParent(p))) => {
-                                        info!(target: "sync", "New block with unknown parent ({:?}) {:?}", p, hash);
-                                    }
-                                    Err(e) => {
-                                        error!(target: "sync", "Bad new block {:?} : {:?}", hash, e);
-                                    }
-                                };
-                            }
-                        } else {
-                            trace!(target: "sync", "imported_block_hashes_mutex lock failed");
-                        }
+        let mut res = ChannelBuffer::new();
+        res.head.ver = Version::V0.value();
+        res.head.ctrl = Control::SYNC.value();
+        res.head.action = SyncAction::BLOCKTRANSACTIONSRES.value();
+        res.body.put_slice(&stream.out());
+        res.head.len = res.body.len() as u32;
+
+        p2p.send(node.node_hash, res);
+        SyncEvent::update_node_state(node, SyncEvent::OnBroadCastBlock);
+    }
+
+    pub fn handle_block_transactions_res(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        trace!(target: "sync", "BLOCKTRANSACTIONSRES received.");
+
+        let response_rlp = UntrustedRlp::new(req.body.as_slice());
+        let response: Result<(H256, Vec<UnverifiedTransaction>), DecoderError> = response_rlp
+            .val_at(0)
+            .and_then(|hash| response_rlp.list_at(1).map(|transactions| (hash, transactions)));
+        let (hash, transactions) = match response {
+            Ok(response) => response,
+            Err(e) => {
+                debug!(target: "sync", "Invalid block transactions from node {}: {:?}", node.get_node_id(), e);
+                p2p.rate_node(node, Behaviour::InvalidData);
+                return;
+            }
+        };
+
+        let compact = match storage.get_pending_compact_blocks().lock() {
+            Ok(mut pending_compact_blocks) => pending_compact_blocks.remove(&hash),
+            Err(_) => None,
+        };
+        let mut compact = match compact {
+            Some(compact) => compact,
+            None => {
+                // the block may have come in full meanwhile, or the request was given up.
+                trace!(target: "sync", "Block transactions from node {} for no pending compact block {:?}, ignored.", node.get_node_id(), hash);
+                return;
+            }
+        };
+
+        let from_peer = compact.missing().len() == compact.short_ids().len();
+        if compact.fill(transactions) {
+            Self::import_compact_block(p2p, storage, node, compact, from_peer);
+        } else {
+            debug!(target: "sync", "Block transactions from node {} don't match compact block {:?}", node.get_node_id(), hash);
+            p2p.rate_node(node, Behaviour::InvalidData);
+        }
+    }
+
+    // recent blocks on top of a known parent, not imported yet.
+    fn is_new_block(storage: &SyncStorage, header: &BlockHeader) -> bool {
+        let last_imported_number = storage.get_synced_block_number();
+        if last_imported_number > header.number()
+            && last_imported_number - header.number() > MAX_NEW_BLOCK_AGE
+        {
+            trace!(target: "sync", "Ignored ancient new block {:?}", header.hash());
+            return false;
+        }
+
+        let client = storage.get_block_chain();
+        client
+            .block_header(BlockId::Hash(*header.parent_hash()))
+            .is_some()
+    }
+
+    // import a new block got from a peer and relay it to the others.
+    fn import_new_block(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        header: &BlockHeader,
+        block_rlp: Vec<u8>,
+    )
+    {
+        let hash = header.hash();
+        let client = storage.get_block_chain();
+        if let Ok(ref mut imported_block_hashes) = storage.get_imported_block_hashes().lock() {
+            if !imported_block_hashes.contains_key(&hash) {
+                let result = client.import_block(block_rlp.clone());
+
+                match result {
+                    Ok(_) => {
+                        trace!(target: "sync", "New broadcast block imported {:?} ({})", hash, header.number());
+                        imported_block_hashes.insert(hash, 0);
+                        Self::relay_block(p2p, &block_rlp);
+                    }
+                    Err(BlockImportError::Import(ImportError::AlreadyInChain)) => {
+                        trace!(target: "sync", "New block already in chain {:?}", hash);
+                    }
+                    Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {
+                        trace!(target: "sync", "New block already queued {:?}", hash);
+                    }
+                    Err(BlockImportError::Block(BlockError::UnknownParent(p))) => {
+                        info!(target: "sync", "New block with unknown parent ({:?}) {:?}", p, hash);
+                    }
+                    Err(e) => {
+                        error!(target: "sync", "Bad new block {:?} : {:?}", hash, e);
+                        p2p.rate_node(node, Behaviour::BadBlock);
                     }
-                    None => {}
                 };
-                SyncEvent::update_node_state(node, SyncEvent::OnBroadCastBlock);
             }
+        } else {
+            trace!(target: "sync", "imported_block_hashes_mutex lock failed");
         }
     }
 
-    pub fn handle_broadcast_tx(node: &mut Node, req: ChannelBuffer) {
+    // `from_peer` tells whether all the transactions were sent by the peer, in which case a
+    // block not matching its transactions root is the peer's fault.
+    fn import_compact_block(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        compact: CompactBlock,
+        from_peer: bool,
+    )
+    {
+        match compact.into_block() {
+            Ok(block) => {
+                let block_rlp = block.rlp_bytes(Seal::With);
+                Self::import_new_block(p2p, storage, node, &block.header, block_rlp);
+            }
+            Err(ref compact) if from_peer => {
+                debug!(target: "sync", "Block {:?} rebuilt from transactions of node {} doesn't match its transactions root.", compact.header().hash(), node.get_node_id());
+                p2p.rate_node(node, Behaviour::InvalidData);
+            }
+            Err(compact) => {
+                // most likely a short id collision, all transactions are asked for.
+                debug!(target: "sync", "Rebuilt block {:?} doesn't match its transactions root, requesting the full block from node {}.", compact.header().hash(), node.get_node_id());
+                Self::request_block_transactions(p2p, storage, node, compact);
+            }
+        }
+    }
+
+    // ask the sender of a compact block for the transactions missing, and keep the block until
+    // they come.
+    fn request_block_transactions(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &Node,
+        compact: CompactBlock,
+    )
+    {
+        let hash = compact.header().hash();
+        let missing = compact.missing();
+
+        let mut transactions_req = ChannelBuffer::new();
+        transactions_req.head.ver = Version::V0.value();
+        transactions_req.head.ctrl = Control::SYNC.value();
+        transactions_req.head.action = SyncAction::BLOCKTRANSACTIONSREQ.value();
+
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&hash);
+        stream.begin_list(missing.len());
+        for index in missing.iter() {
+            stream.append(&(*index as u64));
+        }
+        transactions_req.body.put_slice(&stream.out());
+        transactions_req.head.len = transactions_req.body.len() as u32;
+
+        if let Ok(mut pending_compact_blocks) = storage.get_pending_compact_blocks().lock() {
+            pending_compact_blocks.insert(hash, compact);
+        }
+        p2p.send(node.node_hash, transactions_req);
+    }
+
+    // queued and announced transactions with the given short ids, compact blocks are rebuilt
+    // from.
+    fn known_transactions(
+        storage: &SyncStorage,
+        short_ids: &[ShortId],
+    ) -> Vec<UnverifiedTransaction>
+    {
+        let wanted: HashSet<ShortId> = short_ids.iter().cloned().collect();
+        let queued: Vec<H256> = match storage.get_queued_short_ids().lock() {
+            Ok(queued_short_ids) => {
+                wanted
+                    .iter()
+                    .filter_map(|id| queued_short_ids.get(id).cloned())
+                    .collect()
+            }
+            Err(_) => Vec::new(),
+        };
+
+        let client = storage.get_block_chain();
+        let mut transactions: Vec<UnverifiedTransaction> = queued
+            .iter()
+            .filter_map(|hash| client.queued_transaction(hash))
+            .map(|pending| UnverifiedTransaction::from(pending.transaction))
+            .collect();
+        if let Ok(announced_transactions) = storage.get_announced_transactions().lock() {
+            for (hash, transaction_rlp) in announced_transactions.iter() {
+                if !wanted.contains(&short_id(hash)) {
+                    continue;
+                }
+                if let Ok(transaction) = UntrustedRlp::new(transaction_rlp).as_val() {
+                    transactions.push(transaction);
+                }
+            }
+        }
+        transactions
+    }
+
+    // send a block to all active peers, as compact block to those able to rebuild it.
+    fn relay_block(p2p: &P2pMgr, block_rlp: &[u8]) {
+        let active_nodes = p2p.get_nodes(ALIVE);
+        if active_nodes.len() == 0 {
+            return;
+        }
+
+        let mut req = ChannelBuffer::new();
+        req.head.ver = Version::V0.value();
+        req.head.ctrl = Control::SYNC.value();
+        req.head.action = SyncAction::BROADCASTBLOCK.value();
+        req.body.put_slice(block_rlp);
+        req.head.len = req.body.len() as u32;
+
+        let compact = match UntrustedRlp::new(block_rlp).as_val() {
+            Ok(b) => {
+                let block: Block = b;
+                let mut compact = ChannelBuffer::new();
+                compact.head.ver = Version::V0.value();
+                compact.head.ctrl = Control::SYNC.value();
+                compact.head.action = SyncAction::COMPACTBLOCK.value();
+                compact.body.put_slice(&CompactBlock::encode(&block));
+                compact.head.len = compact.body.len() as u32;
+                Some(compact)
+            }
+            Err(_) => None,
+        };
+
+        for node in active_nodes.iter() {
+            match compact {
+                Some(ref compact) if node.capabilities & COMPACT_BLOCK_CAPABILITY != 0 => {
+                    p2p.send(node.node_hash, compact.clone());
+                    trace!(target: "sync", "Sync compact block sent...");
+                }
+                _ => {
+                    p2p.send(node.node_hash, req.clone());
+                    trace!(target: "sync", "Sync broadcast new block sent...");
+                }
+            }
+        }
+    }
+
+    pub fn handle_broadcast_tx(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
         trace!(target: "sync", "BROADCASTTX received.");
 
         if node.last_broadcast_timestamp + Duration::from_millis(20) > SystemTime::now() {
@@ -185,7 +508,7 @@ impl BroadcastsHandler {
             return;
         }
 
-        if SyncStorage::get_synced_block_number() + 4 < SyncStorage::get_network_best_block_number()
+        if storage.get_synced_block_number() + 4 < storage.get_network_best_block_number()
         {
             // Ignore BROADCASTTX message until full synced
             trace!(target: "sync", "Syncing..., ignore BROADCASTTX message.");
@@ -194,17 +517,21 @@ impl BroadcastsHandler {
 
         let transactions_rlp = UntrustedRlp::new(req.body.as_slice());
         let mut transactions = Vec::new();
-        if let Ok(ref mut transaction_hashes) = SyncStorage::get_sent_transaction_hashes().lock() {
+        if let Ok(ref mut transaction_hashes) = storage.get_sent_transaction_hashes().lock() {
             for transaction_rlp in transactions_rlp.iter() {
                 if !transaction_rlp.is_empty() {
                     if let Ok(t) = transaction_rlp.as_val() {
                         let tx: UnverifiedTransaction = t;
                         let hash = tx.hash();
 
+                        // no need to ask the announcers any more.
+                        if let Ok(mut requests) = storage.get_transaction_requests().lock() {
+                            requests.on_received(&hash);
+                        }
                         if !transaction_hashes.contains_key(&hash) {
                             transactions.push(tx);
-                            transaction_hashes.insert(hash, 0);
-                            SyncStorage::insert_received_transaction(
+                            transaction_hashes.insert(hash, TRANSACTION_SEEN);
+                            storage.insert_received_transaction(
                                 transaction_rlp.as_raw().to_vec(),
                             );
                         }
@@ -213,13 +540,304 @@ impl BroadcastsHandler {
             }
 
             if transactions.len() > 0 {
-                let client = SyncStorage::get_block_chain();
+                let client = storage.get_block_chain();
                 client.import_queued_transactions(transactions);
             }
             node.last_broadcast_timestamp = SystemTime::now();
         }
 
         SyncEvent::update_node_state(node, SyncEvent::OnBroadCastTx);
-        P2pMgr::update_node(node.node_hash, node);
+        p2p.update_node(node.node_hash, node);
+    }
+
+    pub fn handle_announce_tx_hashes(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        trace!(target: "sync", "ANNOUNCETXHASHES received.");
+
+        if storage.get_synced_block_number() + 4 < storage.get_network_best_block_number()
+        {
+            // Ignore ANNOUNCETXHASHES message until full synced
+            trace!(target: "sync", "Syncing..., ignore ANNOUNCETXHASHES message.");
+            return;
+        }
+
+        let now = Instant::now();
+        let mut unknown_hashes = Vec::new();
+        if let Ok(ref mut transaction_hashes) = storage.get_sent_transaction_hashes().lock() {
+            if let Ok(mut requests) = storage.get_transaction_requests().lock() {
+                for hash in req
+                    .body
+                    .chunks(HASH_LEN)
+                    .filter(|hash| hash.len() == HASH_LEN)
+                    .take(MAX_ANNOUNCED_HASHES)
+                {
+                    let hash = H256::from(hash);
+                    if !transaction_hashes.contains_key(&hash)
+                        && requests.on_announced(node.node_hash, hash, now)
+                    {
+                        unknown_hashes.push(hash);
+                    }
+                }
+                trace!(target: "sync", "Announced transactions being requested: {}", requests.count());
+            }
+        }
+
+        if unknown_hashes.len() > 0 {
+            Self::send_transactions_req(p2p, node.node_hash, &unknown_hashes);
+        }
+
+        SyncEvent::update_node_state(node, SyncEvent::OnBroadCastTx);
+        p2p.update_node(node.node_hash, node);
+    }
+
+    /// Ask the next announcers for the announced transactions not sent in time.
+    pub fn request_timed_out_transactions(p2p: &P2pMgr, storage: &SyncStorage, now: Instant) {
+        let retries = match storage.get_transaction_requests().lock() {
+            Ok(mut requests) => requests.expire(now),
+            Err(_) => {
+                warn!(target: "sync", "transaction_requests_mutex lock failed");
+                return;
+            }
+        };
+        for (node_hash, hashes) in retries {
+            debug!(target: "sync", "Announced transactions not sent in time, requesting {} of them from node {}.", hashes.len(), node_hash);
+            Self::send_transactions_req(p2p, node_hash, &hashes);
+        }
+    }
+
+    fn send_transactions_req(p2p: &P2pMgr, node_hash: u64, hashes: &[H256]) {
+        let mut transactions_req = ChannelBuffer::new();
+        transactions_req.head.ver = Version::V0.value();
+        transactions_req.head.ctrl = Control::SYNC.value();
+        transactions_req.head.action = SyncAction::TRANSACTIONSREQ.value();
+        for hash in hashes.iter() {
+            transactions_req.body.put_slice(hash);
+        }
+        transactions_req.head.len = transactions_req.body.len() as u32;
+
+        p2p.send(node_hash, transactions_req);
+        trace!(target: "sync", "Sync transactions req sent, count: {}", hashes.len());
+    }
+
+    pub fn handle_transactions_req(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        trace!(target: "sync", "TRANSACTIONSREQ received.");
+
+        let mut transactions = Vec::new();
+        let mut size = 0;
+        if let Ok(mut announced_transactions) = storage.get_announced_transactions().lock() {
+            for hash in req
+                .body
+                .chunks(HASH_LEN)
+                .filter(|hash| hash.len() == HASH_LEN)
+                .take(MAX_ANNOUNCED_HASHES)
+            {
+                if let Some(transaction) = announced_transactions.get_mut(&H256::from(hash)) {
+                    transactions.extend_from_slice(transaction);
+                    size += 1;
+                }
+            }
+        }
+
+        if size > 0 {
+            let mut res = ChannelBuffer::new();
+            res.head.ver = Version::V0.value();
+            res.head.ctrl = Control::SYNC.value();
+            res.head.action = SyncAction::TRANSACTIONSRES.value();
+
+            let mut txs_rlp = RlpStream::new_list(size);
+            txs_rlp.append_raw(transactions.as_slice(), size);
+            res.body.put_slice(txs_rlp.as_raw());
+            res.head.len = res.body.len() as u32;
+
+            p2p.send(node.node_hash, res);
+        }
+
+        SyncEvent::update_node_state(node, SyncEvent::OnBroadCastTx);
+        p2p.update_node(node.node_hash, node);
+    }
+
+    pub fn handle_transactions_res(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        trace!(target: "sync", "TRANSACTIONSRES received.");
+
+        let transactions_rlp = UntrustedRlp::new(req.body.as_slice());
+        let mut transactions = Vec::new();
+        let mut unsolicited = false;
+        if let Ok(ref mut transaction_hashes) = storage.get_sent_transaction_hashes().lock() {
+            if let Ok(mut requests) = storage.get_transaction_requests().lock() {
+                for transaction_rlp in transactions_rlp.iter() {
+                    if let Ok(t) = transaction_rlp.as_val() {
+                        let tx: UnverifiedTransaction = t;
+                        let hash = tx.hash();
+
+                        let requested = requests.on_received(&hash);
+                        // hashes already seen were got meanwhile from a full broadcast.
+                        if transaction_hashes.contains_key(&hash) {
+                            continue;
+                        }
+                        if !requested {
+                            unsolicited = true;
+                            continue;
+                        }
+                        transaction_hashes.insert(hash, TRANSACTION_SEEN);
+                        transactions.push(tx);
+                        storage.insert_received_transaction(transaction_rlp.as_raw().to_vec());
+                    }
+                }
+            }
+        }
+
+        if transactions.len() > 0 {
+            let client = storage.get_block_chain();
+            client.import_queued_transactions(transactions);
+        }
+
+        SyncEvent::update_node_state(node, SyncEvent::OnBroadCastTx);
+        if unsolicited {
+            p2p.rate_node(node, Behaviour::Unsolicited);
+        } else {
+            p2p.update_node(node.node_hash, node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::transaction_requests::REQUEST_TIMEOUT;
+    use super::super::test_network::{message, TestNetwork};
+    use super::*;
+    use acore::transaction::Transaction;
+    use aion_types::Address;
+    use rlp;
+
+    fn transaction(nonce: u64) -> UnverifiedTransaction {
+        let mut transaction = Transaction::default();
+        transaction.nonce = nonce.into();
+        transaction.fake_sign(Address::default()).into()
+    }
+
+    fn announce(network: &TestNetwork, probe: usize, hashes: &[H256]) {
+        let mut body = Vec::new();
+        for hash in hashes {
+            body.extend_from_slice(hash);
+        }
+        let mut node = network.peer(probe);
+        BroadcastsHandler::handle_announce_tx_hashes(
+            &network.p2p,
+            &network.storage,
+            &mut node,
+            message(SyncAction::ANNOUNCETXHASHES.value(), body),
+        );
+    }
+
+    fn respond(network: &TestNetwork, probe: usize, transactions: &[UnverifiedTransaction]) {
+        let mut stream = RlpStream::new_list(transactions.len());
+        for transaction in transactions {
+            stream.append(transaction);
+        }
+        let mut node = network.peer(probe);
+        BroadcastsHandler::handle_transactions_res(
+            &network.p2p,
+            &network.storage,
+            &mut node,
+            message(SyncAction::TRANSACTIONSRES.value(), stream.out()),
+        );
+    }
+
+    fn requested_hashes(msg: ChannelBuffer) -> Vec<H256> {
+        assert_eq!(msg.head.action, SyncAction::TRANSACTIONSREQ.value());
+        msg.body.chunks(HASH_LEN).map(|hash| H256::from(hash)).collect()
+    }
+
+    #[test]
+    fn requests_announced_transactions_once() {
+        let network = TestNetwork::new(2);
+        let hash = transaction(0).hash();
+
+        announce(&network, 0, &[hash]);
+        let req = network.receive(0).expect("transactions request");
+        assert_eq!(requested_hashes(req), vec![hash]);
+
+        // already asked from the first announcer.
+        announce(&network, 1, &[hash]);
+        assert!(network.receive(1).is_none());
+    }
+
+    #[test]
+    fn requests_timed_out_transactions_from_other_announcer() {
+        let network = TestNetwork::new(2);
+        let hash = transaction(0).hash();
+        announce(&network, 0, &[hash]);
+        announce(&network, 1, &[hash]);
+        network.receive(0).expect("transactions request");
+
+        let later = Instant::now() + Duration::from_secs(REQUEST_TIMEOUT);
+        BroadcastsHandler::request_timed_out_transactions(&network.p2p, &network.storage, later);
+        let req = network.receive(1).expect("transactions request");
+        assert_eq!(requested_hashes(req), vec![hash]);
+        assert!(network.receive(0).is_none());
+    }
+
+    #[test]
+    fn serves_announced_transactions() {
+        let network = TestNetwork::new(1);
+        let transaction = transaction(0);
+        network
+            .storage
+            .get_announced_transactions()
+            .lock()
+            .unwrap()
+            .insert(transaction.hash(), rlp::encode(&transaction).into_vec());
+
+        // unknown hashes are skipped.
+        let mut body = transaction.hash().to_vec();
+        body.extend_from_slice(&H256::from(1));
+        let mut node = network.peer(0);
+        BroadcastsHandler::handle_transactions_req(
+            &network.p2p,
+            &network.storage,
+            &mut node,
+            message(SyncAction::TRANSACTIONSREQ.value(), body),
+        );
+
+        let res = network.receive(0).expect("transactions response");
+        assert_eq!(res.head.action, SyncAction::TRANSACTIONSRES.value());
+        let sent: Vec<UnverifiedTransaction> = UntrustedRlp::new(&res.body).as_list().unwrap();
+        assert_eq!(sent, vec![transaction]);
+    }
+
+    #[test]
+    fn imports_requested_transactions_only() {
+        let network = TestNetwork::new(1);
+        let (requested, unsolicited) = (transaction(0), transaction(1));
+        announce(&network, 0, &[requested.hash()]);
+        network.receive(0).expect("transactions request");
+
+        respond(&network, 0, &[requested.clone()]);
+        assert_eq!(network.storage.get_received_transactions_count(), 1);
+        let reputation = network.peer(0).reputation;
+
+        // sent again, or never asked for.
+        respond(&network, 0, &[requested]);
+        assert_eq!(network.peer(0).reputation, reputation);
+        respond(&network, 0, &[unsolicited]);
+        assert_eq!(network.storage.get_received_transactions_count(), 1);
+        assert!(network.peer(0).reputation < reputation);
     }
 }
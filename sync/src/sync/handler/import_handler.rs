@@ -22,358 +22,72 @@
 use acore::client::{BlockId, BlockImportError, BlockStatus};
 use acore::error::{BlockError, ImportError};
 use acore::header::Seal;
-use acore::views::BlockView;
-use aion_types::{H256, U256};
 
-use p2p::{Mode, P2pMgr};
+use p2p::{Behaviour, P2pMgr};
 
 use super::super::storage::SyncStorage;
+
 pub struct ImportHandler;
-use super::blocks_headers_handler::BlockHeadersHandler;
 
 impl ImportHandler {
-    pub fn import_staged_blocks(hash: &H256) {
-        let mut blocks_to_import = Vec::new();
-        if let Ok(mut staged_blocks) = SyncStorage::get_staged_blocks().lock() {
-            if staged_blocks.contains_key(&hash) {
-                if let Some(blocks_staged) = staged_blocks.remove(hash) {
-                    blocks_to_import.extend(blocks_staged);
-                }
-            }
-        }
-
-        if blocks_to_import.len() > 0 {
-            let client = SyncStorage::get_block_chain();
-            let mut enable_import = true;
-            for block in blocks_to_import.iter() {
-                let block_view = BlockView::new(block);
-                let header_view = block_view.header_view();
-                let number = header_view.number();
-                let hash = header_view.hash();
-
-                if enable_import && !SyncStorage::is_staged_block_hash(hash) {
-                    SyncStorage::insert_requested_time(hash);
-                    match client.import_block(block.clone()) {
-                        Ok(_)
-                        | Err(BlockImportError::Import(ImportError::AlreadyInChain))
-                        | Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {
-                            trace!(target: "sync", "Staged blocks #{} imported...", number);
-                        }
-                        Err(e) => {
-                            enable_import = false;
-                            warn!(target: "sync", "Failed to import staged blocks #{}, due to {:?}", number, e);
-                        }
-                    }
-                }
-                SyncStorage::remove_staged_block_hash(hash);
-            }
-        }
-    }
-
-    pub fn import_blocks() {
-        let mut blocks_to_import = Vec::new();
-        let client = SyncStorage::get_block_chain();
+    /// Import the downloaded blocks which follow the ones already imported, in block order.
+    pub fn import_blocks(p2p: &P2pMgr, storage: &SyncStorage) {
+        let client = storage.get_block_chain();
         let mut bws = Vec::new();
 
-        if let Ok(ref mut downloaded_blocks) = SyncStorage::get_downloaded_blocks().try_lock() {
-            while let Some(bw) = downloaded_blocks.pop_front() {
-                bws.push(bw);
-            }
+        if let Ok(mut skeleton) = storage.get_skeleton().try_lock() {
+            bws = skeleton.drain_ready();
         } else {
-            warn!(target: "sync", "import_block fail to get downloaded blocks.");
+            trace!(target: "sync", "import_block fail to get downloaded blocks.");
         }
 
         for bw in bws.iter() {
-            if let Some(mut node) = P2pMgr::get_node(bw.node_id_hash) {
-                blocks_to_import.clear();
-                let mut max_block_number = 1;
-                for block in bw.blocks.iter() {
-                    let status = client.block_status(BlockId::Hash(block.header.hash()));
-                    if status == BlockStatus::Unknown {
-                        blocks_to_import.push(block.rlp_bytes(Seal::With));
-                    } else if status == BlockStatus::Bad {
-                        warn!(target: "sync", "Bad block {}, {:?}, got from node: {}@{}, mode: {}", block.header.number(), block.header.hash(), node.get_node_id(), node.get_ip_addr(), node.mode);
-                        // node.mode = Mode::BACKWARD;
-                        // P2pMgr::update_node_with_mode(node.node_hash, &node);
-                        break;
-                    } else if max_block_number < block.header.number() {
-                        max_block_number = block.header.number();
-                    }
+            for block in bw.blocks.iter() {
+                let hash = block.header.hash();
+                let number = block.header.number();
+                let status = client.block_status(BlockId::Hash(hash));
+                if status == BlockStatus::InChain || status == BlockStatus::Queued {
+                    continue;
                 }
 
-                if let Some(mut node) = P2pMgr::get_node(bw.node_id_hash) {
-                    if blocks_to_import.is_empty() {
-                        match node.mode {
-                            Mode::BACKWARD => {
-                                info!(target: "sync", "Node: {}, the fork point #{} found, switched from BACKWARD mode to FORWARD mode", node.get_node_id(), max_block_number);
-                                node.mode = Mode::FORWARD;
-                                node.synced_block_num = max_block_number;
-                            }
-                            Mode::FORWARD => {
-                                if SyncStorage::get_synced_block_number_last_time()
-                                    == SyncStorage::get_synced_block_number()
-                                {
-                                    SyncStorage::set_synced_block_number_last_time(
-                                        max_block_number,
-                                    );
-                                    SyncStorage::set_synced_block_number(max_block_number);
-                                } else {
-                                    info!(target: "sync", "Node: {}, the best block #{} found, switched from FORWARD mode to NORMAL mode", node.get_node_id(), max_block_number);
-                                    node.mode = Mode::NORMAL;
-                                }
-                                node.synced_block_num = max_block_number;
-                            }
-                            _ => {
-                                if max_block_number > node.synced_block_num {
-                                    if node.synced_block_num + 32
-                                        > SyncStorage::get_network_best_block_number()
-                                    {
-                                        node.mode = Mode::NORMAL;
-                                    } else {
-                                        let lightning_mode_nodes_count =
-                                            P2pMgr::get_nodes_count_with_mode(Mode::LIGHTNING);
-                                        let thunder_mode_nodes_count =
-                                            P2pMgr::get_nodes_count_with_mode(Mode::THUNDER);
-                                        let normal_mode_nodes_count =
-                                            P2pMgr::get_nodes_count_with_mode(Mode::NORMAL);
-                                        if node.synced_block_num + 500
-                                            < SyncStorage::get_network_best_block_number()
-                                            && normal_mode_nodes_count >= 5
-                                            && lightning_mode_nodes_count
-                                                < (thunder_mode_nodes_count
-                                                    + normal_mode_nodes_count)
-                                                    / 3
-                                        {
-                                            // attempt to jump
-                                            node.mode = Mode::LIGHTNING;
-                                        } else if thunder_mode_nodes_count
-                                            < normal_mode_nodes_count * 10
-                                        {
-                                            node.mode = Mode::THUNDER;
-                                        } else {
-                                            node.mode = Mode::NORMAL;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        P2pMgr::update_node_with_mode(node.node_hash, &node);
-                        if node.mode == Mode::NORMAL || node.mode == Mode::THUNDER {
-                            if SyncStorage::get_synced_block_number() + 8
-                                < SyncStorage::get_network_best_block_number()
-                            {
-                                BlockHeadersHandler::get_headers_from_node(&mut node);
-                            }
-                        }
-                        continue;
+                let result = if status == BlockStatus::Bad {
+                    Err(BlockImportError::Import(ImportError::KnownBad))
+                } else {
+                    client.import_block(block.rlp_bytes(Seal::With))
+                };
+                storage.insert_requested_time(hash);
+                match result {
+                    Ok(_)
+                    | Err(BlockImportError::Import(ImportError::AlreadyInChain))
+                    | Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {
+                        debug!(target: "sync", "Block #{}, {:?} imported from node {}", number, hash, bw.node_id_hash);
                     }
-
-                    let mut offset = 0;
-                    for block in blocks_to_import.iter() {
-                        offset += 1;
-                        let block_view = BlockView::new(block);
-                        let (hash, number, parent, difficulty) = {
-                            let header_view = block_view.header_view();
-                            (
-                                header_view.hash(),
-                                header_view.number(),
-                                header_view.parent_hash(),
-                                header_view.difficulty(),
-                            )
-                        };
-
-                        debug!(target: "sync", "hash: {}, number: {}, parent: {}, node id: {}, mode: {}, synced_block_number: {}",
-                        hash, number, parent, node.get_node_id(), node.mode, node.synced_block_num);
-
-                        let result = client.import_block(block.clone());
-                        SyncStorage::insert_requested_time(hash);
-                        match result {
-                            Ok(_)
-                            | Err(BlockImportError::Import(ImportError::AlreadyInChain))
-                            | Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {
-                                let block_id = BlockId::Hash(hash);
-                                let status = client.block_status(block_id);
-                                // if status == BlockStatus::Unknown || status == BlockStatus::Bad {
-                                //     continue;
-                                // }
-
-                                node.current_total_difficulty =
-                                    node.current_total_difficulty + difficulty;
-
-                                node.synced_block_num = number;
-                                if result.is_err() {
-                                    if status == BlockStatus::InChain {
-                                        if let Some(current_total_difficulty) =
-                                            client.block_total_difficulty(block_id)
-                                        {
-                                            node.current_total_difficulty = current_total_difficulty
-                                        }
-                                    }
-                                    info!(target: "sync", "AlreadyStored block #{}, {:?} received from node {}", number, hash, node.get_node_id());
-                                } else {
-                                    debug!(target: "sync", "Best block #{}, {:?} imported from node {}", number, hash, node.get_node_id());
-                                }
-
-                                node.reset_repeated();
-
-                                match node.mode {
-                                    Mode::BACKWARD => {
-                                        info!(target: "sync", "Node: {}, found the fork point #{}, with status {:?}, switched to FORWARD mode", node.get_node_id(), number, status);
-                                        node.mode = Mode::FORWARD;
-                                        P2pMgr::update_node_with_mode(node.node_hash, &node);
-                                        // break;
-                                    }
-                                    Mode::FORWARD => {
-                                        info!(target: "sync", "Node: {}, found the best block #{} with status {:?}, switched to NORMAL mode", node.get_node_id(), number, status);
-                                        node.mode = Mode::NORMAL;
-                                        P2pMgr::update_node_with_mode(node.node_hash, &node);
-                                        // break;
-                                    }
-                                    _ => {
-                                        if node.synced_block_num + 32
-                                            > SyncStorage::get_network_best_block_number()
-                                        {
-                                            node.mode = Mode::NORMAL;
-                                        } else {
-                                            let lightning_mode_nodes_count =
-                                                P2pMgr::get_nodes_count_with_mode(Mode::LIGHTNING);
-                                            let thunder_mode_nodes_count =
-                                                P2pMgr::get_nodes_count_with_mode(Mode::THUNDER);
-                                            let normal_mode_nodes_count =
-                                                P2pMgr::get_nodes_count_with_mode(Mode::NORMAL);
-                                            if node.target_total_difficulty
-                                                >= SyncStorage::get_network_total_diff()
-                                                && node.synced_block_num + 500
-                                                    < SyncStorage::get_network_best_block_number()
-                                                && normal_mode_nodes_count >= 5
-                                                && lightning_mode_nodes_count
-                                                    < (thunder_mode_nodes_count
-                                                        + normal_mode_nodes_count)
-                                                        / 3
-                                            {
-                                                // attempt to jump
-                                                node.mode = Mode::LIGHTNING;
-                                            } else if thunder_mode_nodes_count
-                                                < normal_mode_nodes_count * 10
-                                            {
-                                                node.mode = Mode::THUNDER;
-                                            } else {
-                                                node.mode = Mode::NORMAL;
-                                            }
-                                        }
-                                        P2pMgr::update_node_with_mode(node.node_hash, &node);
-                                    }
-                                }
-                            }
-                            Err(BlockImportError::Block(BlockError::UnknownParent(_))) => {
-                                if number == 1 {
-                                    error!(target: "sync", "Invalid genesis !!!");
-
-                                    break;
-                                }
-
-                                if number > SyncStorage::get_synced_block_number() {
-                                    // put into staging...
-                                    if let Ok(mut staged_blocks) =
-                                        SyncStorage::get_staged_blocks().lock()
-                                    {
-                                        if staged_blocks.len() < 128
-                                            && !staged_blocks.contains_key(&parent)
-                                        {
-                                            let blocks_to_stage =
-                                                blocks_to_import.clone().split_off(offset);
-                                            let max_staged_block_number =
-                                                number + blocks_to_stage.len() as u64 - 1;
-                                            info!(target: "sync", "Staged blocks from {} to {} with parent: {}", number, max_staged_block_number, parent);
-                                            debug!(target: "sync", "cache size: {}", staged_blocks.len());
-
-                                            let mut staged_block_numbers = Vec::new();
-                                            let mut staged_block_hashes = Vec::new();
-                                            for block in blocks_to_import.iter() {
-                                                let block_view = BlockView::new(block);
-                                                let (number, hash) = {
-                                                    let header_view = block_view.header_view();
-                                                    (header_view.number(), header_view.hash())
-                                                };
-                                                staged_block_numbers.push(number);
-                                                staged_block_hashes.push(hash);
-                                            }
-
-                                            SyncStorage::insert_staged_block_hashes(
-                                                staged_block_hashes,
-                                            );
-
-                                            staged_blocks.insert(parent, blocks_to_stage);
-
-                                            if max_staged_block_number
-                                                > SyncStorage::get_max_staged_block_number()
-                                            {
-                                                SyncStorage::set_max_staged_block_number(
-                                                    max_staged_block_number,
-                                                );
-                                            }
-                                        } else {
-                                            node.synced_block_num =
-                                                client.chain_info().best_block_number;
-                                            node.mode = Mode::THUNDER;
-                                            P2pMgr::update_node_with_mode(node.node_hash, &node);
-                                        }
-                                        break;
-                                    }
-                                } else {
-                                    node.current_total_difficulty = U256::from(0);
-                                    node.synced_block_num = number;
-
-                                    if node.target_total_difficulty
-                                        < SyncStorage::get_network_total_diff()
-                                    {
-                                        P2pMgr::remove_peer(node.node_hash);
-                                    }
-                                    match node.mode {
-                                        Mode::LIGHTNING | Mode::THUNDER => {
-                                            warn!(target: "sync", "Unknown block: #{}, node {} run in NORMAL mode now.", number, node.get_node_id());
-                                            node.mode = Mode::NORMAL;
-                                        }
-                                        Mode::FORWARD | Mode::NORMAL => {
-                                            warn!(target: "sync", "Unknown block: #{}, node {} run in BACKWARD mode now.", number, node.get_node_id());
-                                            node.mode = Mode::BACKWARD;
-                                            node.last_request_num = 0;
-                                        }
-                                        Mode::BACKWARD => {
-                                            warn!(target: "sync", "Unknown block: #{}, node {} run in BACKWARD mode.", number, node.get_node_id());
-                                        }
-                                    }
-                                    P2pMgr::update_node_with_mode(node.node_hash, &node);
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                if !node.is_over_repeated_threshold() {
-                                    warn!(target: "sync", "Got bad block #{}, {:?}", number, hash);
-
-                                    node.mode = Mode::BACKWARD;
-                                    client.clear_bad();
-                                    node.inc_repeated();
-                                    P2pMgr::update_node_with_mode(node.node_hash, &node);
-                                } else {
-                                    warn!(target: "sync", "Bad block {:?} {:?}, remove peer node: {}@{}", hash, e, node.get_node_id(), node.get_ip_addr());
-                                    P2pMgr::remove_peer(node.node_hash);
-                                }
-                                break;
-                            }
-                        }
+                    Err(BlockImportError::Block(BlockError::UnknownParent(parent))) => {
+                        // the local chain forked off before the round started.
+                        warn!(target: "sync", "Unknown parent {:?} of block #{}, restarting sync further back.", parent, number);
+                        Self::abandon(storage, true);
+                        return;
                     }
-
-                    if node.mode == Mode::NORMAL || node.mode == Mode::THUNDER {
-                        node.synced_block_num = SyncStorage::get_synced_block_number();
-
-                        if node.synced_block_num + 8 < SyncStorage::get_network_best_block_number()
-                        {
-                            BlockHeadersHandler::get_headers_from_node(&mut node);
-                        }
+                    Err(e) => {
+                        warn!(target: "sync", "Bad block #{}, {:?}, {:?}, got from node: {}", number, hash, e, bw.node_id_hash);
+                        client.clear_bad();
+                        p2p.rate_peer(bw.node_id_hash, Behaviour::BadBlock);
+                        Self::abandon(storage, false);
+                        return;
                     }
                 }
             }
         }
     }
+
+    // drop the running round, what was downloaded after a failed block is of no use.
+    fn abandon(storage: &SyncStorage, rewind: bool) {
+        if let Ok(mut skeleton) = storage.get_skeleton().lock() {
+            if rewind {
+                skeleton.rewind();
+            } else {
+                skeleton.reset();
+            }
+        }
+    }
 }
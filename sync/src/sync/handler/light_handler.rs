@@ -0,0 +1,437 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+
+use acore::client::{BlockChainClient, BlockId};
+use acore::header::Header as BlockHeader;
+use aion_types::H256;
+use bytes::BufMut;
+use futures::sync::oneshot;
+use rlp::{RlpStream, UntrustedRlp};
+use std::cmp;
+use std::time::{Instant, SystemTime};
+
+use super::super::action::LightAction;
+use super::super::light::{
+    Kind, LightRequest, LightResponse, PeerHead, Request, Response, MAX_HEADERS,
+};
+use super::super::storage::SyncStorage;
+use super::status_handler::LIGHT_SERVE_CAPABILITY;
+
+use p2p::*;
+
+pub struct LightHandler;
+
+impl LightHandler {
+    /// Whether the node runs in light mode; blocks are neither downloaded nor executed.
+    pub fn is_active(storage: &SyncStorage) -> bool {
+        match storage.get_light_sync().lock() {
+            Ok(light_sync) => light_sync.is_active(),
+            Err(_) => false,
+        }
+    }
+
+    /// Fetch data proved by a full node. The receiver gets canceled if the data cannot be
+    /// proved against the headers synced, or if no peer served a valid proof.
+    pub fn fetch(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        request: LightRequest,
+    ) -> oneshot::Receiver<LightResponse>
+    {
+        let (sender, receiver) = oneshot::channel();
+        if let Ok(mut light_sync) = storage.get_light_sync().lock() {
+            if !light_sync.fetch(request, sender) {
+                debug!(target: "sync", "Light request not provable against the synced headers.");
+            }
+        } else {
+            warn!(target: "sync", "light_sync_mutex lock failed");
+        }
+
+        Self::schedule(p2p, storage);
+        receiver
+    }
+
+    /// Expire stale requests and hand out header and proof requests to idle serving peers.
+    pub fn schedule(p2p: &P2pMgr, storage: &SyncStorage) {
+        let peers = Self::peers(p2p);
+        let now = Instant::now();
+
+        let mut timed_out = Vec::new();
+        let mut requests = Vec::new();
+        if let Ok(mut light_sync) = storage.get_light_sync().lock() {
+            timed_out = light_sync.expire(&peers, now);
+            requests = light_sync.schedule(&peers, now);
+        } else {
+            warn!(target: "sync", "light_sync_mutex lock failed");
+        }
+
+        for node_hash in timed_out {
+            debug!(target: "sync", "Light request to node {} timed out, re-assigned.", node_hash);
+            p2p.rate_peer(node_hash, Behaviour::Timeout);
+        }
+
+        for (node_hash, request) in requests {
+            match request {
+                Request::Headers {
+                    req_id,
+                    from,
+                    count,
+                } => {
+                    debug!(target: "sync", "request light headers: from number: {}, count: {}, node: {}.", from, count, node_hash);
+                    let mut stream = RlpStream::new_list(3);
+                    stream.append(&req_id).append(&from).append(&count);
+                    Self::send(p2p, node_hash, LightAction::HEADERSREQ, stream.as_raw());
+                }
+                Request::Proof {
+                    req_id,
+                    request,
+                } => {
+                    trace!(target: "sync", "request light proof: {:?}, node: {}.", request, node_hash);
+                    Self::send_proof_req(p2p, node_hash, req_id, request);
+                }
+            }
+
+            if let Some(mut node) = p2p.get_node(node_hash) {
+                node.last_request_timestamp = SystemTime::now();
+                p2p.update_node(node_hash, &mut node);
+            }
+        }
+    }
+
+    /// Serve a light client request, charged to the credits of the peer. Requests beyond
+    /// the credits left are dropped.
+    pub fn handle_light_req(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        let action = LightAction::from(req.head.action);
+        trace!(target: "sync", "{} received.", action);
+
+        // light nodes have no state to prove.
+        if Self::is_active(storage) {
+            return;
+        }
+
+        let node_hash = node.node_hash;
+        let rlp = UntrustedRlp::new(req.body.as_slice());
+        let req_id: u64 = match rlp.val_at(0) {
+            Ok(req_id) => req_id,
+            Err(_) => {
+                p2p.rate_node(node, Behaviour::InvalidData);
+                return;
+            }
+        };
+
+        let (kind, count, res_action) = match action {
+            LightAction::HEADERSREQ => {
+                let max_headers = p2p.get_network_config().max_headers_per_request as u64;
+                let max_headers = cmp::min(max_headers, MAX_HEADERS);
+                let count = cmp::min(rlp.val_at::<u64>(2).unwrap_or(0), max_headers);
+                (Kind::Headers, count, LightAction::HEADERSRES)
+            }
+            LightAction::HEADERPROOFREQ => (Kind::HeaderProof, 1, LightAction::HEADERPROOFRES),
+            LightAction::ACCOUNTPROOFREQ => (Kind::AccountProof, 1, LightAction::ACCOUNTPROOFRES),
+            LightAction::STORAGEPROOFREQ => (Kind::StorageProof, 1, LightAction::STORAGEPROOFRES),
+            LightAction::RECEIPTPROOFREQ => (Kind::ReceiptProof, 1, LightAction::RECEIPTPROOFRES),
+            _ => return,
+        };
+
+        let credits = match storage.get_light_server().lock() {
+            Ok(mut light_server) => light_server.charge(node_hash, kind, count, Instant::now()),
+            Err(_) => {
+                warn!(target: "sync", "light_server_mutex lock failed");
+                None
+            }
+        };
+        let credits = match credits {
+            Some(credits) => credits,
+            None => {
+                debug!(target: "sync", "Light client {} out of credits, {} dropped.", node.get_node_id(), action);
+                return;
+            }
+        };
+
+        let payload = match kind {
+            Kind::Headers => Self::headers(storage, &rlp, count),
+            _ => Self::proof(storage, kind, &rlp),
+        };
+
+        let mut stream = RlpStream::new_list(2 + payload.len());
+        stream.append(&req_id).append(&credits);
+        for item in payload.iter() {
+            stream.append_raw(item, 1);
+        }
+
+        p2p.update_node(node_hash, node);
+        Self::send(p2p, node_hash, res_action, stream.as_raw());
+    }
+
+    pub fn handle_light_res(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        let action = LightAction::from(req.head.action);
+        trace!(target: "sync", "{} received.", action);
+
+        let node_hash = node.node_hash;
+        let rlp = UntrustedRlp::new(req.body.as_slice());
+        let (req_id, credits) = match (rlp.val_at::<u64>(0), rlp.val_at::<u64>(1)) {
+            (Ok(req_id), Ok(credits)) => (req_id, credits),
+            _ => {
+                p2p.rate_node(node, Behaviour::InvalidData);
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        let client = storage.get_block_chain();
+        let response = match storage.get_light_sync().lock() {
+            Ok(mut light_sync) => {
+                match action {
+                    LightAction::HEADERSRES => {
+                        match Self::decode_headers(&*client, &rlp) {
+                            Some(headers) => {
+                                light_sync.on_headers(
+                                    node_hash,
+                                    req_id,
+                                    credits,
+                                    headers,
+                                    &*client,
+                                    now,
+                                )
+                            }
+                            None => light_sync.reject(node_hash, req_id),
+                        }
+                    }
+                    _ => light_sync.on_proof(node_hash, req_id, credits, &rlp, now),
+                }
+            }
+            Err(_) => {
+                warn!(target: "sync", "light_sync_mutex lock failed");
+                Response::Unexpected
+            }
+        };
+
+        let behaviour = match response {
+            Response::Accepted => Behaviour::UsefulResponse,
+            Response::Partial => Behaviour::PartialResponse,
+            Response::Rejected => {
+                debug!(target: "sync", "Light {} failing validation from node: {}", action, node.get_node_id());
+                Behaviour::InvalidData
+            }
+            Response::Unexpected => Behaviour::Unsolicited,
+        };
+
+        if p2p.rate_node(node, behaviour) && response == Response::Accepted {
+            Self::schedule(p2p, storage);
+        }
+    }
+
+    // peers serving light clients.
+    fn peers(p2p: &P2pMgr) -> Vec<PeerHead> {
+        let sync_from_boot_nodes_only = p2p.get_network_config().sync_from_boot_nodes_only;
+        p2p.get_nodes(ALIVE)
+            .iter()
+            .filter(|node| !sync_from_boot_nodes_only || node.is_from_boot_list)
+            .filter(|node| node.capabilities & LIGHT_SERVE_CAPABILITY != 0)
+            .map(|node| {
+                PeerHead {
+                    node_hash: node.node_hash,
+                    best_block_num: node.best_block_num,
+                }
+            })
+            .collect()
+    }
+
+    fn send(p2p: &P2pMgr, node_hash: u64, action: LightAction, body: &[u8]) {
+        let mut req = ChannelBuffer::new();
+        req.head.ver = Version::V0.value();
+        req.head.ctrl = Control::LIGHT.value();
+        req.head.action = action.value();
+        req.body.put_slice(body);
+        req.head.set_length(req.body.len() as u32);
+
+        p2p.send(node_hash, req);
+    }
+
+    fn send_proof_req(p2p: &P2pMgr, node_hash: u64, req_id: u64, request: LightRequest) {
+        let mut stream = RlpStream::new();
+        let action = match request {
+            LightRequest::Header {
+                number,
+            } => {
+                stream.begin_list(2).append(&req_id).append(&number);
+                LightAction::HEADERPROOFREQ
+            }
+            LightRequest::Account {
+                block_hash,
+                address_hash,
+            } => {
+                stream
+                    .begin_list(3)
+                    .append(&req_id)
+                    .append(&block_hash)
+                    .append(&address_hash);
+                LightAction::ACCOUNTPROOFREQ
+            }
+            LightRequest::Storage {
+                block_hash,
+                address_hash,
+                key_hash,
+            } => {
+                stream
+                    .begin_list(4)
+                    .append(&req_id)
+                    .append(&block_hash)
+                    .append(&address_hash)
+                    .append(&key_hash);
+                LightAction::STORAGEPROOFREQ
+            }
+            LightRequest::Receipt {
+                block_hash,
+                index,
+            } => {
+                stream
+                    .begin_list(3)
+                    .append(&req_id)
+                    .append(&block_hash)
+                    .append(&index);
+                LightAction::RECEIPTPROOFREQ
+            }
+        };
+        Self::send(p2p, node_hash, action, stream.as_raw());
+    }
+
+    // `count` headers from the block asked for, as a single rlp list.
+    fn headers(storage: &SyncStorage, rlp: &UntrustedRlp, count: u64) -> Vec<Vec<u8>> {
+        let client = storage.get_block_chain();
+        let from: u64 = rlp.val_at(1).unwrap_or(1);
+        let headers: Vec<Vec<u8>> = (from..from.saturating_add(count))
+            .map(|number| client.block_header(BlockId::Number(number)))
+            .take_while(|header| header.is_some())
+            .filter_map(|header| header.map(|header| header.into_inner()))
+            .collect();
+
+        let mut stream = RlpStream::new_list(headers.len());
+        for header in headers.iter() {
+            stream.append_raw(header, 1);
+        }
+        vec![stream.out()]
+    }
+
+    // the proof asked for, each item encoded on its own. Nothing when this node cannot prove it.
+    fn proof(storage: &SyncStorage, kind: Kind, rlp: &UntrustedRlp) -> Vec<Vec<u8>> {
+        let client = storage.get_block_chain();
+        let prover = match client.as_proving_client() {
+            Some(prover) => prover,
+            None => return Vec::new(),
+        };
+
+        let proved = match kind {
+            Kind::HeaderProof => {
+                rlp.val_at::<u64>(1).ok().and_then(|number| {
+                    let header = client.block_header(BlockId::Number(number))?;
+                    let (header_proof, _) = prover.prove_header(number)?;
+                    Some(vec![header.into_inner(), Self::encode_nodes(&header_proof)])
+                })
+            }
+            Kind::AccountProof => {
+                match (rlp.val_at::<H256>(1), rlp.val_at::<H256>(2)) {
+                    (Ok(block_hash), Ok(address_hash)) => {
+                        prover
+                            .prove_account(address_hash, BlockId::Hash(block_hash))
+                            .map(|(account_proof, _)| vec![Self::encode_nodes(&account_proof)])
+                    }
+                    _ => None,
+                }
+            }
+            Kind::StorageProof => {
+                match (
+                    rlp.val_at::<H256>(1),
+                    rlp.val_at::<H256>(2),
+                    rlp.val_at::<H256>(3),
+                ) {
+                    (Ok(block_hash), Ok(address_hash), Ok(key_hash)) => {
+                        let id = BlockId::Hash(block_hash);
+                        prover.prove_account(address_hash, id).and_then(|(account_proof, _)| {
+                            prover
+                                .prove_storage(address_hash, key_hash, id)
+                                .map(|(storage_proof, _)| {
+                                    vec![
+                                        Self::encode_nodes(&account_proof),
+                                        Self::encode_nodes(&storage_proof),
+                                    ]
+                                })
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            Kind::ReceiptProof => {
+                match (rlp.val_at::<H256>(1), rlp.val_at::<usize>(2)) {
+                    (Ok(block_hash), Ok(index)) => {
+                        prover
+                            .prove_receipt(block_hash, index)
+                            .map(|receipt_proof| vec![Self::encode_nodes(&receipt_proof)])
+                    }
+                    _ => None,
+                }
+            }
+            Kind::Headers => None,
+        };
+        proved.unwrap_or_default()
+    }
+
+    fn encode_nodes(nodes: &[Vec<u8>]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(nodes.len());
+        for node in nodes {
+            stream.append(node);
+        }
+        stream.out()
+    }
+
+    // headers passing the cheap checks, `None` if any is malformed or fails them. Seals and
+    // difficulties are verified against the ancestors as the headers get inserted.
+    fn decode_headers(
+        client: &BlockChainClient,
+        rlp: &UntrustedRlp,
+    ) -> Option<Vec<BlockHeader>>
+    {
+        let mut headers = Vec::new();
+        for header_rlp in rlp.at(2).ok()?.iter() {
+            let header: BlockHeader = header_rlp.as_val().ok()?;
+            if let Err(e) = client.verify_header_basic(&header) {
+                debug!(target: "sync", "Invalid light header: {:?}, header: {}", e, header_rlp);
+                return None;
+            }
+            headers.push(header);
+        }
+        Some(headers)
+    }
+}
@@ -24,3 +24,9 @@ pub mod blocks_headers_handler;
 pub mod blocks_bodies_handler;
 pub mod broadcast_handler;
 pub mod import_handler;
+pub mod skeleton_handler;
+pub mod state_sync_handler;
+pub mod light_handler;
+
+#[cfg(test)]
+mod test_network;
@@ -0,0 +1,119 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+use std::time::{Instant, SystemTime};
+
+use super::super::skeleton::{PeerHead, Request};
+use super::super::storage::SyncStorage;
+use super::blocks_bodies_handler::BlockBodiesHandler;
+use super::blocks_headers_handler::BlockHeadersHandler;
+use super::light_handler::LightHandler;
+use super::state_sync_handler::StateSyncHandler;
+
+use p2p::*;
+
+pub struct SkeletonHandler;
+
+impl SkeletonHandler {
+    /// Expire stale requests and hand out skeleton, header and body requests to idle peers.
+    /// Blocks wait while the state of a pivot block is being downloaded, and are not synced at
+    /// all in light mode.
+    pub fn schedule(p2p: &P2pMgr, storage: &SyncStorage) {
+        if LightHandler::is_active(storage) {
+            LightHandler::schedule(p2p, storage);
+            return;
+        }
+        if StateSyncHandler::is_active(storage) {
+            StateSyncHandler::schedule(p2p, storage);
+            return;
+        }
+
+        let peers = Self::peers(p2p);
+        let local_best = storage.get_synced_block_number();
+        let now = Instant::now();
+
+        let mut timed_out = Vec::new();
+        let mut requests = Vec::new();
+        if let Ok(mut skeleton) = storage.get_skeleton().lock() {
+            timed_out = skeleton.expire(&peers, now);
+            requests = skeleton.schedule(local_best, &peers, now);
+        } else {
+            warn!(target: "sync", "skeleton_mutex lock failed");
+        }
+
+        for node_hash in timed_out {
+            debug!(target: "sync", "Request to node {} timed out, re-assigned.", node_hash);
+            p2p.rate_peer(node_hash, Behaviour::Timeout);
+        }
+
+        for (node_hash, request) in requests {
+            let from = match request {
+                Request::Headers {
+                    from,
+                    count,
+                    skip,
+                } => {
+                    debug!(target: "sync", "request headers: from number: {}, count: {}, skip: {}, node: {}.", from, count, skip, node_hash);
+                    BlockHeadersHandler::send_blocks_headers_req(p2p, node_hash, from, count, skip);
+                    from
+                }
+                Request::Bodies {
+                    from,
+                    hashes,
+                } => {
+                    debug!(target: "sync", "request bodies: from number: {}, count: {}, node: {}.", from, hashes.len(), node_hash);
+                    BlockBodiesHandler::send_blocks_bodies_req(p2p, node_hash, hashes);
+                    from
+                }
+            };
+
+            if let Some(mut node) = p2p.get_node(node_hash) {
+                node.last_request_timestamp = SystemTime::now();
+                node.last_request_num = from;
+                p2p.update_node(node_hash, &mut node);
+            }
+        }
+    }
+
+    /// Heads of the peers to sync from.
+    pub fn peers(p2p: &P2pMgr) -> Vec<PeerHead> {
+        let sync_from_boot_nodes_only = p2p.get_network_config().sync_from_boot_nodes_only;
+        p2p.get_nodes(ALIVE)
+            .iter()
+            .filter(|node| !sync_from_boot_nodes_only || node.is_from_boot_list)
+            .map(|node| {
+                PeerHead {
+                    node_hash: node.node_hash,
+                    best_block_num: node.best_block_num,
+                }
+            })
+            .collect()
+    }
+
+    /// Measured bodies throughput of a peer, in blocks per second.
+    pub fn throughput(storage: &SyncStorage, node_hash: u64) -> f64 {
+        match storage.get_skeleton().lock() {
+            Ok(skeleton) => skeleton.throughput(node_hash),
+            Err(_) => 0.0,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+use acore::header::Header as BlockHeader;
+use acore::transaction::UnverifiedTransaction;
+use aion_types::{H256, U256};
+use bytes::BufMut;
+use rlp::{RlpStream, UntrustedRlp};
+use std::time::{Instant, SystemTime};
+
+use super::super::action::SyncAction;
+use super::super::skeleton::Response;
+use super::super::state_sync::{Request, MAX_STATE_DATA};
+use super::super::storage::SyncStorage;
+use super::blocks_bodies_handler::BlockBodiesHandler;
+use super::blocks_headers_handler::BlockHeadersHandler;
+use super::skeleton_handler::SkeletonHandler;
+
+use p2p::*;
+
+const HASH_LEN: usize = 32;
+// state data served once a response gets this large are left for another request.
+const MAX_STATE_DATA_SIZE: usize = 2 * 1024 * 1024;
+
+pub struct StateSyncHandler;
+
+impl StateSyncHandler {
+    /// Whether the state of a pivot block is being downloaded; blocks are not synced meanwhile.
+    pub fn is_active(storage: &SyncStorage) -> bool {
+        match storage.get_state_sync().lock() {
+            Ok(state_sync) => state_sync.is_active(),
+            Err(_) => false,
+        }
+    }
+
+    /// Expire stale requests, hand out header, pivot and state data requests to idle peers, and
+    /// make the pivot the best block once its state is complete.
+    pub fn schedule(p2p: &P2pMgr, storage: &SyncStorage) {
+        let peers = SkeletonHandler::peers(p2p);
+        let now = Instant::now();
+
+        let mut timed_out = Vec::new();
+        let mut requests = Vec::new();
+        let mut pivot = None;
+        if let Ok(mut state_sync) = storage.get_state_sync().lock() {
+            timed_out = state_sync.expire(&peers, now);
+            requests = state_sync.schedule(&peers, now);
+            pivot = state_sync.complete();
+        } else {
+            warn!(target: "sync", "state_sync_mutex lock failed");
+        }
+
+        for node_hash in timed_out {
+            debug!(target: "sync", "State request to node {} timed out, re-assigned.", node_hash);
+            p2p.rate_peer(node_hash, Behaviour::Timeout);
+        }
+
+        for (node_hash, request) in requests {
+            match request {
+                Request::Headers {
+                    from,
+                    count,
+                } => {
+                    debug!(target: "sync", "request headers: from number: {}, count: {}, node: {}.", from, count, node_hash);
+                    BlockHeadersHandler::send_blocks_headers_req(
+                        p2p,
+                        node_hash,
+                        from,
+                        count as u32,
+                        0,
+                    );
+                }
+                Request::Bodies {
+                    hashes,
+                } => {
+                    debug!(target: "sync", "request pivot bodies: node: {}.", node_hash);
+                    BlockBodiesHandler::send_blocks_bodies_req(p2p, node_hash, hashes);
+                }
+                Request::StateData {
+                    hashes,
+                } => {
+                    trace!(target: "sync", "request state data: count: {}, node: {}.", hashes.len(), node_hash);
+                    Self::send_state_data_req(p2p, node_hash, hashes);
+                }
+            }
+
+            if let Some(mut node) = p2p.get_node(node_hash) {
+                node.last_request_timestamp = SystemTime::now();
+                p2p.update_node(node_hash, &mut node);
+            }
+        }
+
+        if let Some((header, parent, block, total_difficulty)) = pivot {
+            Self::import_pivot(storage, header, parent, block, total_difficulty);
+        }
+    }
+
+    /// Hand over headers received from a peer, to be verified onto the synced header chain.
+    pub fn on_headers(
+        storage: &SyncStorage,
+        node_hash: u64,
+        headers: Vec<BlockHeader>,
+    ) -> Response
+    {
+        let client = storage.get_block_chain();
+        match storage.get_state_sync().lock() {
+            Ok(mut state_sync) => state_sync.on_headers(node_hash, headers, &*client),
+            Err(_) => {
+                warn!(target: "sync", "state_sync_mutex lock failed");
+                Response::Unexpected
+            }
+        }
+    }
+
+    /// Hand over pivot bodies received from a peer.
+    pub fn on_bodies(
+        storage: &SyncStorage,
+        node_hash: u64,
+        bodies: Vec<Vec<UnverifiedTransaction>>,
+    ) -> Response
+    {
+        match storage.get_state_sync().lock() {
+            Ok(mut state_sync) => state_sync.on_bodies(node_hash, bodies),
+            Err(_) => {
+                warn!(target: "sync", "state_sync_mutex lock failed");
+                Response::Unexpected
+            }
+        }
+    }
+
+    pub fn send_state_data_req(p2p: &P2pMgr, node_hash: u64, hashes: Vec<H256>) {
+        let mut req = ChannelBuffer::new();
+        req.head.ver = Version::V0.value();
+        req.head.ctrl = Control::SYNC.value();
+        req.head.action = SyncAction::STATEDATAREQ.value();
+
+        for hash in hashes.iter() {
+            req.body.put_slice(hash);
+        }
+        req.head.set_length(req.body.len() as u32);
+
+        p2p.send(node_hash, req);
+    }
+
+    /// Serve state trie nodes and codes by hash, skipping those not known.
+    pub fn handle_state_data_req(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        trace!(target: "sync", "STATEDATAREQ received.");
+
+        let client = storage.get_block_chain();
+        let node_hash = node.node_hash;
+        let mut res = ChannelBuffer::new();
+        res.head.ver = Version::V0.value();
+        res.head.ctrl = Control::SYNC.value();
+        res.head.action = SyncAction::STATEDATARES.value();
+
+        let mut data = Vec::new();
+        let mut size = 0;
+        for hash in req.body.chunks(HASH_LEN).take(MAX_STATE_DATA) {
+            if hash.len() != HASH_LEN || size >= MAX_STATE_DATA_SIZE {
+                break;
+            }
+            if let Some(value) = client.state_data(&H256::from(hash)) {
+                size += value.len();
+                data.push(value);
+            }
+        }
+
+        let mut rlp = RlpStream::new_list(data.len());
+        for value in data.iter() {
+            rlp.append(value);
+        }
+        res.body.put_slice(rlp.as_raw());
+        res.head.set_length(res.body.len() as u32);
+
+        p2p.update_node(node_hash, node);
+        p2p.send(node_hash, res);
+    }
+
+    pub fn handle_state_data_res(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
+        trace!(target: "sync", "STATEDATARES received.");
+
+        let node_hash = node.node_hash;
+        let data: Vec<Vec<u8>> = UntrustedRlp::new(req.body.as_slice())
+            .iter()
+            .filter_map(|value| value.as_val().ok())
+            .collect();
+
+        let client = storage.get_block_chain();
+        let response = match storage.get_state_sync().lock() {
+            Ok(mut state_sync) => {
+                let (response, ready) = state_sync.on_state_data(
+                    node_hash,
+                    data,
+                    |address_hash: Option<&H256>, hash: &H256| {
+                        client.has_state_data(address_hash, hash)
+                    },
+                );
+                // written under the lock, so that no node gets written before the nodes it
+                // refers to.
+                if !ready.is_empty() {
+                    if let Err(e) = client.import_state_data(ready) {
+                        error!(target: "sync", "Failed to write state data: {:?}", e);
+                    }
+                }
+                response
+            }
+            Err(_) => {
+                warn!(target: "sync", "state_sync_mutex lock failed");
+                Response::Unexpected
+            }
+        };
+
+        let behaviour = match response {
+            Response::Accepted => Behaviour::UsefulResponse,
+            Response::Partial => Behaviour::PartialResponse,
+            Response::Rejected => {
+                debug!(target: "sync", "State data not matching the request from node: {}", node.get_node_id());
+                Behaviour::InvalidData
+            }
+            Response::Unexpected => Behaviour::Unsolicited,
+        };
+
+        if p2p.rate_node(node, behaviour) && response == Response::Accepted {
+            Self::schedule(p2p, storage);
+        }
+    }
+
+    // make the pivot the best block, block sync carries on from there.
+    fn import_pivot(
+        storage: &SyncStorage,
+        header: BlockHeader,
+        parent: Vec<u8>,
+        block: Vec<u8>,
+        total_difficulty: U256,
+    )
+    {
+        let number = header.number();
+        let client = storage.get_block_chain();
+        match client.import_state_pivot(parent, block, total_difficulty) {
+            Ok(hash) => {
+                info!(target: "sync", "State of block #{} ({}) downloaded, syncing blocks from there.", number, hash);
+                storage.set_synced_block_number(number);
+            }
+            Err(e) => {
+                error!(target: "sync", "Failed to import pivot #{}: {:?}, syncing from the local best block.", number, e);
+            }
+        }
+    }
+}
@@ -28,36 +28,55 @@ use std::time::SystemTime;
 use super::super::action::SyncAction;
 use super::super::event::SyncEvent;
 use super::super::storage::SyncStorage;
-use super::blocks_headers_handler::BlockHeadersHandler;
+use super::light_handler::LightHandler;
+use super::skeleton_handler::SkeletonHandler;
 use p2p::*;
 
 const BEST_HASH_LENGTH: usize = 32;
 const GENESIS_HASH_LENGTH: usize = 32;
 
+/// Capability flag for transaction hash announcements.
+pub const TX_ANNOUNCE_CAPABILITY: u8 = 1;
+/// Capability flag for compact block relay.
+pub const COMPACT_BLOCK_CAPABILITY: u8 = 1 << 1;
+/// Capability flag for serving headers and proofs to light clients.
+pub const LIGHT_SERVE_CAPABILITY: u8 = 1 << 2;
+const KNOWN_CAPABILITIES: u8 =
+    TX_ANNOUNCE_CAPABILITY | COMPACT_BLOCK_CAPABILITY | LIGHT_SERVE_CAPABILITY;
+
 pub struct StatusHandler;
 
 impl StatusHandler {
-    pub fn send_status_req_to_node(node_hash: u64) {
+    pub fn send_status_req_to_node(p2p: &P2pMgr, storage: &SyncStorage, node_hash: u64) {
         let mut req = ChannelBuffer::new();
         req.head.ver = Version::V0.value();
         req.head.ctrl = Control::SYNC.value();
         req.head.action = SyncAction::STATUSREQ.value();
-        req.head.len = 0;
+        req.body.push(Self::local_capabilities(storage));
+        req.head.len = req.body.len() as u32;
 
-        P2pMgr::send(node_hash, req);
+        p2p.send(node_hash, req);
     }
 
-    pub fn send_status_req() {
-        let active_nodes = P2pMgr::get_nodes(ALIVE);
+    pub fn send_status_req(p2p: &P2pMgr, storage: &SyncStorage) {
+        let active_nodes = p2p.get_nodes(ALIVE);
         for node in active_nodes.iter() {
             trace!(target: "sync","Sync status req sent...");
-            Self::send_status_req_to_node(node.node_hash);
+            Self::send_status_req_to_node(p2p, storage, node.node_hash);
         }
     }
 
-    pub fn handle_status_req(node: &mut Node) {
+    pub fn handle_status_req(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
         trace!(target: "sync", "STATUSREQ received.");
 
+        node.capabilities = Self::capabilities(&req.body);
+
         let mut res = ChannelBuffer::new();
         let node_hash = node.node_hash;
 
@@ -66,7 +85,7 @@ impl StatusHandler {
         res.head.action = SyncAction::STATUSRES.value();
 
         let mut res_body = Vec::new();
-        let chain_info = SyncStorage::get_chain_info();
+        let chain_info = storage.get_chain_info();
 
         let mut best_block_number = [0; 8];
         BigEndian::write_u64(&mut best_block_number, chain_info.best_block_number);
@@ -84,15 +103,22 @@ impl StatusHandler {
         res_body.put_slice(&total_difficulty_buf.to_vec());
         res_body.put_slice(&best_hash);
         res_body.put_slice(&genesis_hash);
+        res_body.push(Self::local_capabilities(storage));
 
         res.body.put_slice(res_body.as_slice());
         res.head.set_length(res.body.len() as u32);
         SyncEvent::update_node_state(node, SyncEvent::OnStatusReq);
-        P2pMgr::update_node(node_hash, node);
-        P2pMgr::send(node_hash, res);
+        p2p.update_node(node_hash, node);
+        p2p.send(node_hash, res);
     }
 
-    pub fn handle_status_res(node: &mut Node, req: ChannelBuffer) {
+    pub fn handle_status_res(
+        p2p: &P2pMgr,
+        storage: &SyncStorage,
+        node: &mut Node,
+        req: ChannelBuffer,
+    )
+    {
         trace!(target: "sync", "STATUSRES received.");
 
         let node_hash = node.node_hash;
@@ -103,37 +129,139 @@ impl StatusHandler {
         let total_difficulty_len = total_difficulty_len.read_u8().unwrap_or(0) as usize;
         let (total_difficulty, req_body_rest) = req_body_rest.split_at(total_difficulty_len);
         let (best_hash, req_body_rest) = req_body_rest.split_at(BEST_HASH_LENGTH);
-        let (_genesis_hash, _) = req_body_rest.split_at(GENESIS_HASH_LENGTH);
+        let (_genesis_hash, capabilities) = req_body_rest.split_at(GENESIS_HASH_LENGTH);
 
         node.best_hash = H256::from(best_hash);
         node.best_block_num = best_block_num;
-        if node.mode != Mode::BACKWARD && node.mode != Mode::FORWARD {
-            let chain_info = SyncStorage::get_chain_info();
-            node.synced_block_num = chain_info.best_block_number;
-            node.current_total_difficulty = chain_info.total_difficulty;
-        }
+        let chain_info = storage.get_chain_info();
+        node.synced_block_num = chain_info.best_block_number;
+        node.current_total_difficulty = chain_info.total_difficulty;
         node.target_total_difficulty = U256::from(total_difficulty);
+        node.capabilities = Self::capabilities(capabilities);
         SyncEvent::update_node_state(node, SyncEvent::OnStatusRes);
-        P2pMgr::update_node(node_hash, node);
+        p2p.update_node(node_hash, node);
 
-        SyncStorage::update_network_status(
+        storage.update_network_status(
             node.best_block_num,
             node.best_hash,
             node.target_total_difficulty,
         );
 
-        let sync_from_boot_nodes_only = P2pMgr::get_network_config().sync_from_boot_nodes_only;
+        let sync_from_boot_nodes_only = p2p.get_network_config().sync_from_boot_nodes_only;
         if sync_from_boot_nodes_only {
             if !node.is_from_boot_list {
                 return;
             }
         }
 
-        if SyncStorage::get_network_best_block_number() <= SyncStorage::get_synced_block_number() {
+        if storage.get_network_best_block_number() <= storage.get_synced_block_number() {
             node.last_request_timestamp = SystemTime::now();
-            P2pMgr::update_node(node.node_hash, node);
+            p2p.update_node(node.node_hash, node);
+        } else {
+            SkeletonHandler::schedule(p2p, storage);
+        }
+    }
+
+    // capabilities of this node, appended to status messages. Older peers ignore the trailing
+    // byte and keep receiving full transaction and block broadcasts. Light nodes have nothing
+    // to serve.
+    fn local_capabilities(storage: &SyncStorage) -> u8 {
+        if LightHandler::is_active(storage) {
+            TX_ANNOUNCE_CAPABILITY | COMPACT_BLOCK_CAPABILITY
         } else {
-            BlockHeadersHandler::get_headers_from_random_node();
+            KNOWN_CAPABILITIES
         }
     }
+
+    // status messages of older peers carry no capabilities.
+    fn capabilities(capabilities: &[u8]) -> u8 {
+        capabilities.first().map_or(0, |flags| flags & KNOWN_CAPABILITIES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_network::{message, TestNetwork};
+    use super::*;
+
+    fn status_res(capabilities: &[u8]) -> ChannelBuffer {
+        let mut body = vec![0u8; mem::size_of::<u64>()];
+        body.push(32);
+        body.extend_from_slice(&[0u8; 32]);
+        body.extend_from_slice(&H256::from(1));
+        body.extend_from_slice(&H256::from(2));
+        body.extend_from_slice(capabilities);
+        message(SyncAction::STATUSRES.value(), body)
+    }
+
+    #[test]
+    fn sends_capabilities_in_status_messages() {
+        let network = TestNetwork::new(1);
+        StatusHandler::send_status_req_to_node(
+            &network.p2p,
+            &network.storage,
+            network.peer(0).node_hash,
+        );
+        let req = network.receive(0).expect("status request");
+        assert_eq!(req.head.action, SyncAction::STATUSREQ.value());
+        assert_eq!(req.body, vec![KNOWN_CAPABILITIES]);
+
+        let mut node = network.peer(0);
+        StatusHandler::handle_status_req(
+            &network.p2p,
+            &network.storage,
+            &mut node,
+            message(SyncAction::STATUSREQ.value(), Vec::new()),
+        );
+        let res = network.receive(0).expect("status response");
+        assert_eq!(res.head.action, SyncAction::STATUSRES.value());
+        assert_eq!(res.body.last(), Some(&KNOWN_CAPABILITIES));
+    }
+
+    #[test]
+    fn reads_peer_capabilities() {
+        let network = TestNetwork::new(1);
+        let mut node = network.peer(0);
+
+        // unknown flags are dropped.
+        StatusHandler::handle_status_req(
+            &network.p2p,
+            &network.storage,
+            &mut node,
+            message(SyncAction::STATUSREQ.value(), vec![TX_ANNOUNCE_CAPABILITY | 1 << 7]),
+        );
+        assert_eq!(network.peer(0).capabilities, TX_ANNOUNCE_CAPABILITY);
+
+        StatusHandler::handle_status_res(
+            &network.p2p,
+            &network.storage,
+            &mut node,
+            status_res(&[COMPACT_BLOCK_CAPABILITY]),
+        );
+        assert_eq!(network.peer(0).capabilities, COMPACT_BLOCK_CAPABILITY);
+    }
+
+    #[test]
+    fn reads_status_of_older_peers_without_capabilities() {
+        let network = TestNetwork::new(1);
+        let mut node = network.peer(0);
+        node.capabilities = KNOWN_CAPABILITIES;
+
+        StatusHandler::handle_status_req(
+            &network.p2p,
+            &network.storage,
+            &mut node,
+            message(SyncAction::STATUSREQ.value(), Vec::new()),
+        );
+        assert_eq!(network.peer(0).capabilities, 0);
+
+        node.capabilities = KNOWN_CAPABILITIES;
+        StatusHandler::handle_status_res(
+            &network.p2p,
+            &network.storage,
+            &mut node,
+            status_res(&[]),
+        );
+        assert_eq!(network.peer(0).capabilities, 0);
+    }
 }
@@ -0,0 +1,1093 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+
+//! Light client scheduler and serving credits.
+//!
+//! In light mode no block is executed. Headers are synced on their own, linked by parent hash,
+//! checked by the engine against their ancestors and weighed by total difficulty; once the blocks of a CHT are buried `cht::SIZE` blocks deep
+//! its root is kept and its headers dropped. State and receipts are fetched on demand from full
+//! nodes as Merkle proofs, checked against the roots of a header still kept, and headers
+//! already dropped are fetched with a proof against the root of their CHT.
+//!
+//! Full nodes charge every request to a credit buffer per peer which recharges over time, and
+//! report the credits left with every response. Clients mirror the charges so as to only send
+//! requests a server is going to answer.
+//!
+//! The scheduler only keeps book; sending the requests is left to the handlers.
+
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use acore::basic_account::BasicAccount;
+use acore::cht;
+use acore::client::BlockChainClient;
+use acore::header::Header as BlockHeader;
+use acore::proof;
+use acore::receipt::SimpleReceipt;
+use aion_types::{H256, U256};
+use futures::sync::oneshot;
+use rlp::UntrustedRlp;
+
+use super::skeleton::REQUEST_TIMEOUT;
+
+pub use super::skeleton::{PeerHead, Response};
+
+/// Max headers served for one request.
+pub const MAX_HEADERS: u64 = 192;
+/// Credits a client starts with, and recharges up to.
+pub const CREDIT_LIMIT: u64 = 1_000_000;
+/// Credits recharged every second.
+pub const CREDIT_RECHARGE: u64 = 50_000;
+
+/// Headers re-requested below the local best block, so that short reorgs link up.
+pub const REORG_OVERLAP: u64 = 8;
+// peers asked for a proof before giving up on it.
+const MAX_ATTEMPTS: usize = 3;
+
+/// Kinds of requests served to light clients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Headers,
+    HeaderProof,
+    AccountProof,
+    StorageProof,
+    ReceiptProof,
+}
+
+impl Kind {
+    /// Credits charged for a request of `count` items.
+    pub fn cost(&self, count: u64) -> u64 {
+        match *self {
+            Kind::Headers => 1_000 + 200 * count,
+            // the CHT gets rebuilt from the chain for every proof.
+            Kind::HeaderProof => 50_000,
+            Kind::AccountProof => 5_000,
+            Kind::StorageProof => 8_000,
+            Kind::ReceiptProof => 10_000,
+        }
+    }
+}
+
+/// Request credits of a peer, recharged over time up to `CREDIT_LIMIT`.
+#[derive(Clone, Copy, Debug)]
+pub struct Credits {
+    value: u64,
+    updated: Instant,
+}
+
+impl Credits {
+    pub fn new(now: Instant) -> Self {
+        Credits {
+            value: CREDIT_LIMIT,
+            updated: now,
+        }
+    }
+
+    /// Credits available at `now`.
+    pub fn current(&self, now: Instant) -> u64 {
+        if now <= self.updated {
+            return self.value;
+        }
+        let elapsed = now.duration_since(self.updated);
+        let millis = elapsed
+            .as_secs()
+            .saturating_mul(1000)
+            .saturating_add(elapsed.subsec_millis() as u64);
+        let recharged = millis.saturating_mul(CREDIT_RECHARGE) / 1000;
+        cmp::min(CREDIT_LIMIT, self.value.saturating_add(recharged))
+    }
+
+    /// Take `cost` off the credits, if there are enough of them.
+    pub fn deduct(&mut self, cost: u64, now: Instant) -> bool {
+        let current = self.current(now);
+        if current < cost {
+            return false;
+        }
+        self.update(current - cost, now);
+        true
+    }
+
+    /// Take over the credits left as reported by a server.
+    pub fn update(&mut self, value: u64, now: Instant) {
+        self.value = cmp::min(CREDIT_LIMIT, value);
+        self.updated = cmp::max(self.updated, now);
+    }
+}
+
+/// Credits of the light clients served by this node.
+pub struct LightServer {
+    clients: HashMap<u64, Credits>,
+}
+
+impl LightServer {
+    pub fn new() -> Self {
+        LightServer {
+            clients: HashMap::new(),
+        }
+    }
+
+    /// Charge a request of `count` items to a client. Returns the credits left, or `None` if
+    /// the client cannot afford the request.
+    pub fn charge(&mut self, node_hash: u64, kind: Kind, count: u64, now: Instant) -> Option<u64> {
+        // clients fully recharged are no different from new ones.
+        self.clients
+            .retain(|_, credits| credits.current(now) < CREDIT_LIMIT);
+        let credits = self
+            .clients
+            .entry(node_hash)
+            .or_insert_with(|| Credits::new(now));
+        if credits.deduct(kind.cost(count), now) {
+            Some(credits.current(now))
+        } else {
+            None
+        }
+    }
+}
+
+/// Headers synced in light mode, along with their total difficulty. Headers of a CHT buried
+/// `cht::SIZE` blocks deep are dropped in favour of its root.
+pub struct HeaderChain {
+    headers: VecDeque<(BlockHeader, U256)>,
+    cht_roots: Vec<H256>,
+}
+
+impl HeaderChain {
+    pub fn new(genesis: BlockHeader, total_difficulty: U256) -> Self {
+        let mut headers = VecDeque::new();
+        headers.push_back((genesis, total_difficulty));
+        HeaderChain {
+            headers: headers,
+            cht_roots: Vec::new(),
+        }
+    }
+
+    /// Number of the best header.
+    pub fn best_number(&self) -> u64 { self.best().0.number() }
+
+    /// Hash of the best header.
+    pub fn best_hash(&self) -> H256 { self.best().0.hash() }
+
+    /// Total difficulty of the best header.
+    pub fn best_total_difficulty(&self) -> U256 { self.best().1 }
+
+    /// Number of the first header still kept.
+    pub fn first_number(&self) -> u64 {
+        self.headers
+            .front()
+            .expect("header chain is never empty; qed")
+            .0
+            .number()
+    }
+
+    /// Canonical header of a block, if still kept.
+    pub fn header(&self, number: u64) -> Option<&BlockHeader> {
+        self.entry(number).map(|&(ref header, _)| header)
+    }
+
+    /// Total difficulty of the canonical block with the given number, if still kept.
+    pub fn total_difficulty(&self, number: u64) -> Option<U256> {
+        self.entry(number).map(|&(_, total_difficulty)| total_difficulty)
+    }
+
+    /// Header with the given hash, if canonical and still kept.
+    pub fn header_by_hash(&self, hash: &H256) -> Option<&BlockHeader> {
+        self.headers
+            .iter()
+            .rev()
+            .map(|&(ref header, _)| header)
+            .find(|header| header.hash() == *hash)
+    }
+
+    /// Root of a CHT whose headers have been dropped.
+    pub fn cht_root(&self, cht_number: u64) -> Option<H256> {
+        self.cht_roots.get(cht_number as usize).cloned()
+    }
+
+    /// Insert consecutive headers, the first one being the child of a header still kept. Each
+    /// header gets its seal and difficulty verified by `client` against its ancestors. The
+    /// chain switches over to them if they make it heavier. Returns false if they do not link
+    /// or any fails verification.
+    pub fn insert(&mut self, headers: Vec<BlockHeader>, client: &BlockChainClient) -> bool {
+        let parent_number = match headers.first() {
+            Some(first) if first.number() > 0 => first.number() - 1,
+            Some(_) => return false,
+            None => return true,
+        };
+        let mut total_difficulty = match self.entry(parent_number) {
+            Some(&(ref parent, total_difficulty)) if parent.hash() == *headers[0].parent_hash() => {
+                total_difficulty
+            }
+            _ => return false,
+        };
+
+        let mut parent_hash = *headers[0].parent_hash();
+        let mut number = parent_number;
+        let mut entries = Vec::with_capacity(headers.len());
+        for header in headers {
+            if header.number() != number + 1 || *header.parent_hash() != parent_hash {
+                return false;
+            }
+            {
+                // ancestors are either among the headers inserted, or kept already.
+                let ancestor = |hash: &H256| {
+                    entries
+                        .iter()
+                        .rev()
+                        .map(|&(ref header, _)| header)
+                        .find(|header: &&BlockHeader| header.hash() == *hash)
+                        .or_else(|| self.header_by_hash(hash))
+                        .cloned()
+                };
+                if let Err(e) = client.verify_detached_header(&header, &ancestor) {
+                    debug!(target: "sync", "Light header #{} failing verification: {:?}", header.number(), e);
+                    return false;
+                }
+            }
+            number += 1;
+            parent_hash = header.hash();
+            total_difficulty = total_difficulty + *header.difficulty();
+            entries.push((header, total_difficulty));
+        }
+
+        if total_difficulty > self.best_total_difficulty() {
+            let keep = (parent_number + 1 - self.first_number()) as usize;
+            self.headers.truncate(keep);
+            self.headers.extend(entries);
+            self.compact();
+        }
+        true
+    }
+
+    fn best(&self) -> &(BlockHeader, U256) {
+        self.headers
+            .back()
+            .expect("header chain is never empty; qed")
+    }
+
+    fn entry(&self, number: u64) -> Option<&(BlockHeader, U256)> {
+        let first = self.first_number();
+        if number < first {
+            return None;
+        }
+        self.headers.get((number - first) as usize)
+    }
+
+    // keep the roots of the CHTs buried deep enough, instead of their headers. The last header
+    // of a CHT stays, as the parent of the next one.
+    fn compact(&mut self) {
+        loop {
+            let cht_number = self.cht_roots.len() as u64;
+            let end = cht::end_number(cht_number);
+            if self.best_number() < end + cht::SIZE {
+                break;
+            }
+
+            let first = self.first_number();
+            let root = {
+                let entries = self
+                    .headers
+                    .iter()
+                    .skip((cht::start_number(cht_number) - first) as usize)
+                    .map(|&(ref header, total_difficulty)| (header.hash(), total_difficulty));
+                cht::compute_root(cht_number, entries)
+            };
+            match root {
+                Some(root) => self.cht_roots.push(root),
+                None => break,
+            }
+            for _ in first..end {
+                self.headers.pop_front();
+            }
+        }
+    }
+}
+
+/// Data fetched from full nodes on demand.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LightRequest {
+    /// Canonical header of a block whose header has been dropped.
+    Header { number: u64 },
+    /// Account at a block, by the hash of its address.
+    Account {
+        block_hash: H256,
+        address_hash: H256,
+    },
+    /// Storage value of an account at a block, by the hashes of the address and of the key.
+    Storage {
+        block_hash: H256,
+        address_hash: H256,
+        key_hash: H256,
+    },
+    /// Receipt at `index` in a block.
+    Receipt { block_hash: H256, index: usize },
+}
+
+impl LightRequest {
+    pub fn kind(&self) -> Kind {
+        match *self {
+            LightRequest::Header {
+                ..
+            } => Kind::HeaderProof,
+            LightRequest::Account {
+                ..
+            } => Kind::AccountProof,
+            LightRequest::Storage {
+                ..
+            } => Kind::StorageProof,
+            LightRequest::Receipt {
+                ..
+            } => Kind::ReceiptProof,
+        }
+    }
+}
+
+/// Proved answer to a `LightRequest`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LightResponse {
+    /// The header with its total difficulty.
+    Header(BlockHeader, U256),
+    /// The account, `None` if proved absent.
+    Account(Option<BasicAccount>),
+    /// The storage value, zero if proved absent.
+    Storage(H256),
+    Receipt(SimpleReceipt),
+}
+
+/// A request the scheduler wants sent to a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    /// `count` headers starting at block `from`.
+    Headers { req_id: u64, from: u64, count: u64 },
+    /// Proof for an on-demand request.
+    Proof { req_id: u64, request: LightRequest },
+}
+
+struct Fetch {
+    request: LightRequest,
+    attempts: usize,
+    sender: oneshot::Sender<LightResponse>,
+}
+
+enum Task {
+    Headers { from: u64 },
+    Fetch(Fetch),
+}
+
+struct Pending {
+    node_hash: u64,
+    task: Task,
+    sent: Instant,
+}
+
+pub struct LightSync {
+    chain: Option<HeaderChain>,
+    next_id: u64,
+    pending: HashMap<u64, Pending>,
+    queue: VecDeque<Fetch>,
+    /// Credits left on each server, as far as known.
+    credits: HashMap<u64, Credits>,
+}
+
+impl LightSync {
+    pub fn new() -> Self {
+        LightSync {
+            chain: None,
+            next_id: 0,
+            pending: HashMap::new(),
+            queue: VecDeque::new(),
+            credits: HashMap::new(),
+        }
+    }
+
+    /// Start syncing headers on top of the genesis block.
+    pub fn start(&mut self, genesis: BlockHeader, total_difficulty: U256) {
+        self.chain = Some(HeaderChain::new(genesis, total_difficulty));
+    }
+
+    /// Whether the node runs in light mode.
+    pub fn is_active(&self) -> bool { self.chain.is_some() }
+
+    /// The headers synced so far.
+    pub fn chain(&self) -> Option<&HeaderChain> { self.chain.as_ref() }
+
+    /// Number of the best header synced, 0 when not in light mode.
+    pub fn best_number(&self) -> u64 {
+        self.chain.as_ref().map_or(0, |chain| chain.best_number())
+    }
+
+    /// Whether a request is outstanding on the peer.
+    pub fn is_busy(&self, node_hash: u64) -> bool {
+        self.pending
+            .values()
+            .any(|pending| pending.node_hash == node_hash)
+    }
+
+    /// Queue a request, whose proved answer goes to `sender`. Requests that could not be
+    /// checked against the local chain are dropped, which cancels the receiver.
+    pub fn fetch(&mut self, request: LightRequest, sender: oneshot::Sender<LightResponse>) -> bool {
+        if !self.can_verify(&request) {
+            return false;
+        }
+        self.queue.push_back(Fetch {
+            request: request,
+            attempts: 0,
+            sender: sender,
+        });
+        true
+    }
+
+    /// Drop requests which timed out or whose peer is gone. Proofs are asked from another peer.
+    /// Returns the peers which timed out.
+    pub fn expire(&mut self, peers: &[PeerHead], now: Instant) -> Vec<u64> {
+        let timeout = Duration::from_secs(REQUEST_TIMEOUT);
+        let expired: Vec<u64> = self
+            .pending
+            .iter()
+            .filter(|&(_, pending)| {
+                pending.sent + timeout <= now
+                    || !peers.iter().any(|peer| peer.node_hash == pending.node_hash)
+            })
+            .map(|(req_id, _)| *req_id)
+            .collect();
+
+        let mut timed_out = Vec::new();
+        for req_id in expired {
+            if let Some(pending) = self.pending.remove(&req_id) {
+                if peers.iter().any(|peer| peer.node_hash == pending.node_hash) {
+                    timed_out.push(pending.node_hash);
+                }
+                if let Task::Fetch(fetch) = pending.task {
+                    self.retry(fetch);
+                }
+            }
+        }
+        timed_out
+    }
+
+    /// Hand out header and proof requests to idle peers with credits left for them. Headers
+    /// are asked from the peer furthest ahead, one request at a time.
+    pub fn schedule(&mut self, peers: &[PeerHead], now: Instant) -> Vec<(u64, Request)> {
+        let mut requests = Vec::new();
+        let (best_number, first_number) = match self.chain {
+            Some(ref chain) => (chain.best_number(), chain.first_number()),
+            None => return requests,
+        };
+
+        let mut idle: Vec<PeerHead> = peers
+            .iter()
+            .filter(|peer| !self.is_busy(peer.node_hash))
+            .cloned()
+            .collect();
+        idle.sort_by(|a, b| b.best_block_num.cmp(&a.best_block_num));
+
+        let syncing = self.pending.values().any(|pending| {
+            match pending.task {
+                Task::Headers {
+                    ..
+                } => true,
+                Task::Fetch(_) => false,
+            }
+        });
+        let furthest = idle.first().cloned();
+        if let Some(peer) = furthest {
+            if !syncing && peer.best_block_num > best_number {
+                let from = cmp::max(
+                    first_number + 1,
+                    (best_number + 1).saturating_sub(REORG_OVERLAP),
+                );
+                let count = cmp::min(MAX_HEADERS, peer.best_block_num + 1 - from);
+                if self.charge(peer.node_hash, Kind::Headers, count, now) {
+                    let req_id = self.next_id();
+                    self.pending.insert(
+                        req_id,
+                        Pending {
+                            node_hash: peer.node_hash,
+                            task: Task::Headers {
+                                from: from,
+                            },
+                            sent: now,
+                        },
+                    );
+                    requests.push((
+                        peer.node_hash,
+                        Request::Headers {
+                            req_id: req_id,
+                            from: from,
+                            count: count,
+                        },
+                    ));
+                    idle.remove(0);
+                }
+            }
+        }
+
+        let mut waiting = VecDeque::new();
+        while let Some(fetch) = self.queue.pop_front() {
+            // nobody waits for the answer any more.
+            if fetch.sender.is_canceled() {
+                continue;
+            }
+
+            let kind = fetch.request.kind();
+            let position = idle
+                .iter()
+                .position(|peer| self.credits_at(peer.node_hash, now) >= kind.cost(1));
+            match position {
+                Some(position) => {
+                    let peer = idle.remove(position);
+                    self.charge(peer.node_hash, kind, 1, now);
+                    let req_id = self.next_id();
+                    requests.push((
+                        peer.node_hash,
+                        Request::Proof {
+                            req_id: req_id,
+                            request: fetch.request.clone(),
+                        },
+                    ));
+                    self.pending.insert(
+                        req_id,
+                        Pending {
+                            node_hash: peer.node_hash,
+                            task: Task::Fetch(fetch),
+                            sent: now,
+                        },
+                    );
+                }
+                None => waiting.push_back(fetch),
+            }
+        }
+        self.queue = waiting;
+
+        requests
+    }
+
+    /// Hand over headers received from a peer, along with the credits it has left for us.
+    /// `client` verifies them before they enter the chain.
+    pub fn on_headers(
+        &mut self,
+        node_hash: u64,
+        req_id: u64,
+        credits: u64,
+        headers: Vec<BlockHeader>,
+        client: &BlockChainClient,
+        now: Instant,
+    ) -> Response
+    {
+        let from = match self.pending.get(&req_id) {
+            Some(&Pending {
+                node_hash: assigned,
+                task: Task::Headers {
+                    from,
+                },
+                ..
+            }) if assigned == node_hash => from,
+            _ => return Response::Unexpected,
+        };
+        self.pending.remove(&req_id);
+        self.update_credits(node_hash, credits, now);
+
+        let chain = match self.chain {
+            Some(ref mut chain) => chain,
+            None => return Response::Unexpected,
+        };
+        match headers.first() {
+            Some(first) if first.number() != from => return Response::Rejected,
+            _ => {}
+        }
+        if chain.insert(headers, client) {
+            Response::Accepted
+        } else {
+            Response::Rejected
+        }
+    }
+
+    /// Hand over a proof received from a peer. `rlp` is the whole response: the request id and
+    /// the credits left, followed by the proof. Proofs that do not check out are asked from
+    /// another peer.
+    pub fn on_proof(
+        &mut self,
+        node_hash: u64,
+        req_id: u64,
+        credits: u64,
+        rlp: &UntrustedRlp,
+        now: Instant,
+    ) -> Response
+    {
+        let assigned = match self.pending.get(&req_id) {
+            Some(&Pending {
+                node_hash: assigned,
+                task: Task::Fetch(_),
+                ..
+            }) => assigned == node_hash,
+            _ => false,
+        };
+        if !assigned {
+            return Response::Unexpected;
+        }
+        let fetch = match self.pending.remove(&req_id) {
+            Some(Pending {
+                task: Task::Fetch(fetch),
+                ..
+            }) => fetch,
+            _ => return Response::Unexpected,
+        };
+        self.update_credits(node_hash, credits, now);
+
+        // servers answer with no proof at all when they do not have the data.
+        if rlp.item_count().unwrap_or(0) <= 2 {
+            self.retry(fetch);
+            return Response::Accepted;
+        }
+
+        match self.verify(&fetch.request, rlp) {
+            Some(response) => {
+                let _ = fetch.sender.send(response);
+                Response::Accepted
+            }
+            None => {
+                self.retry(fetch);
+                Response::Rejected
+            }
+        }
+    }
+
+    /// Drop a request answered with data failing validation, such as headers with a bad seal.
+    /// Proofs are asked from another peer.
+    pub fn reject(&mut self, node_hash: u64, req_id: u64) -> Response {
+        let assigned = match self.pending.get(&req_id) {
+            Some(pending) => pending.node_hash == node_hash,
+            None => false,
+        };
+        if !assigned {
+            return Response::Unexpected;
+        }
+        if let Some(pending) = self.pending.remove(&req_id) {
+            if let Task::Fetch(fetch) = pending.task {
+                self.retry(fetch);
+            }
+        }
+        Response::Rejected
+    }
+
+    fn can_verify(&self, request: &LightRequest) -> bool {
+        let chain = match self.chain {
+            Some(ref chain) => chain,
+            None => return false,
+        };
+        match *request {
+            LightRequest::Header {
+                number,
+            } => {
+                cht::block_to_cht_number(number)
+                    .and_then(|cht_number| chain.cht_root(cht_number))
+                    .is_some()
+            }
+            LightRequest::Account {
+                ref block_hash,
+                ..
+            }
+            | LightRequest::Storage {
+                ref block_hash,
+                ..
+            }
+            | LightRequest::Receipt {
+                ref block_hash,
+                ..
+            } => chain.header_by_hash(block_hash).is_some(),
+        }
+    }
+
+    fn verify(&self, request: &LightRequest, rlp: &UntrustedRlp) -> Option<LightResponse> {
+        let chain = self.chain.as_ref()?;
+        match *request {
+            LightRequest::Header {
+                number,
+            } => {
+                let header: BlockHeader = rlp.val_at(2).ok()?;
+                let header_proof: Vec<Vec<u8>> = rlp.list_at(3).ok()?;
+                let root = chain.cht_root(cht::block_to_cht_number(number)?)?;
+                let (hash, total_difficulty) = cht::check_proof(&header_proof, number, root)?;
+                if header.number() == number && header.hash() == hash {
+                    Some(LightResponse::Header(header, total_difficulty))
+                } else {
+                    None
+                }
+            }
+            LightRequest::Account {
+                ref block_hash,
+                ref address_hash,
+            } => {
+                let state_root = *chain.header_by_hash(block_hash)?.state_root();
+                let account_proof: Vec<Vec<u8>> = rlp.list_at(2).ok()?;
+                proof::check_account_proof(&account_proof, &state_root, address_hash)
+                    .map(LightResponse::Account)
+            }
+            LightRequest::Storage {
+                ref block_hash,
+                ref address_hash,
+                ref key_hash,
+            } => {
+                let state_root = *chain.header_by_hash(block_hash)?.state_root();
+                let account_proof: Vec<Vec<u8>> = rlp.list_at(2).ok()?;
+                let storage_proof: Vec<Vec<u8>> = rlp.list_at(3).ok()?;
+                match proof::check_account_proof(&account_proof, &state_root, address_hash)? {
+                    Some(account) => {
+                        proof::check_storage_proof(&storage_proof, &account.storage_root, key_hash)
+                            .map(LightResponse::Storage)
+                    }
+                    None => Some(LightResponse::Storage(H256::zero())),
+                }
+            }
+            LightRequest::Receipt {
+                ref block_hash,
+                index,
+            } => {
+                let receipts_root = *chain.header_by_hash(block_hash)?.receipts_root();
+                let receipt_proof: Vec<Vec<u8>> = rlp.list_at(2).ok()?;
+                proof::check_receipt_proof(&receipt_proof, &receipts_root, index)
+                    .map(LightResponse::Receipt)
+            }
+        }
+    }
+
+    // gives up on the request after `MAX_ATTEMPTS` peers, which cancels the receiver.
+    fn retry(&mut self, mut fetch: Fetch) {
+        fetch.attempts += 1;
+        if fetch.attempts < MAX_ATTEMPTS {
+            self.queue.push_back(fetch);
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn credits_at(&self, node_hash: u64, now: Instant) -> u64 {
+        self.credits
+            .get(&node_hash)
+            .map_or(CREDIT_LIMIT, |credits| credits.current(now))
+    }
+
+    fn charge(&mut self, node_hash: u64, kind: Kind, count: u64, now: Instant) -> bool {
+        self.credits
+            .entry(node_hash)
+            .or_insert_with(|| Credits::new(now))
+            .deduct(kind.cost(count), now)
+    }
+
+    fn update_credits(&mut self, node_hash: u64, value: u64, now: Instant) {
+        self.credits
+            .entry(node_hash)
+            .or_insert_with(|| Credits::new(now))
+            .update(value, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use acore::client::TestBlockChainClient;
+    use acore::header::SealType;
+    use acore::spec::Spec;
+    use blake2b::blake2b;
+    use futures::Future;
+    use key::{generate_keypair, sign_ed25519, Ed25519KeyPair, Ed25519Secret};
+    use rlp::RlpStream;
+    use std::str::FromStr;
+    use triehash::ordered_trie_root;
+
+    fn peer(node_hash: u64, best_block_num: u64) -> PeerHead {
+        PeerHead {
+            node_hash: node_hash,
+            best_block_num: best_block_num,
+        }
+    }
+
+    fn genesis() -> BlockHeader {
+        let mut header = BlockHeader::new();
+        header.set_difficulty(U256::from(1));
+        header
+    }
+
+    fn headers(parent: &BlockHeader, count: u64, difficulty: u64) -> Vec<BlockHeader> {
+        let mut headers: Vec<BlockHeader> = Vec::new();
+        for _ in 0..count {
+            let mut header = BlockHeader::new();
+            {
+                let parent = headers.last().unwrap_or(parent);
+                header.set_number(parent.number() + 1);
+                header.set_parent_hash(parent.hash());
+            }
+            header.set_difficulty(U256::from(difficulty));
+            headers.push(header);
+        }
+        headers
+    }
+
+    #[test]
+    fn credits_recharge_over_time() {
+        let now = Instant::now();
+        let mut credits = Credits::new(now);
+        assert!(credits.deduct(CREDIT_LIMIT, now));
+        assert!(!credits.deduct(1, now));
+        assert_eq!(credits.current(now + Duration::from_secs(1)), CREDIT_RECHARGE);
+        assert_eq!(credits.current(now + Duration::from_secs(3600)), CREDIT_LIMIT);
+
+        let mut server = LightServer::new();
+        let cost = Kind::HeaderProof.cost(1);
+        for _ in 0..CREDIT_LIMIT / cost {
+            assert!(server.charge(1, Kind::HeaderProof, 1, now).is_some());
+        }
+        assert_eq!(server.charge(1, Kind::HeaderProof, 1, now), None);
+        // other clients have credits of their own.
+        assert_eq!(
+            server.charge(2, Kind::HeaderProof, 1, now),
+            Some(CREDIT_LIMIT - cost)
+        );
+    }
+
+    #[test]
+    fn syncs_headers_and_switches_to_heavier_fork() {
+        let client = TestBlockChainClient::new();
+        let genesis = genesis();
+        let mut light = LightSync::new();
+        light.start(genesis.clone(), U256::from(1));
+        let now = Instant::now();
+
+        let canonical = headers(&genesis, 10, 2);
+        let requests = light.schedule(&[peer(1, 10)], now);
+        assert_eq!(
+            requests,
+            vec![(
+                1,
+                Request::Headers {
+                    req_id: 1,
+                    from: 1,
+                    count: 10,
+                },
+            )]
+        );
+        assert!(light.is_busy(1));
+        // nothing asked from peer 2.
+        assert_eq!(
+            light.on_headers(2, 1, CREDIT_LIMIT, canonical.clone(), &client, now),
+            Response::Unexpected
+        );
+        assert_eq!(
+            light.on_headers(1, 1, CREDIT_LIMIT, canonical.clone(), &client, now),
+            Response::Accepted
+        );
+        assert_eq!(light.best_number(), 10);
+
+        // a heavier fork off block 5 is picked up through the overlap.
+        let mut fork = canonical[2..5].to_vec();
+        let forked = headers(&canonical[4], 7, 3);
+        fork.extend(forked.iter().cloned());
+        let requests = light.schedule(&[peer(1, 12)], now);
+        match requests[0] {
+            (
+                1,
+                Request::Headers {
+                    req_id,
+                    from: 3,
+                    count: 10,
+                },
+            ) => {
+                assert_eq!(
+                    light.on_headers(1, req_id, CREDIT_LIMIT, fork, &client, now),
+                    Response::Accepted
+                );
+            }
+            ref request => panic!("unexpected request {:?}", request),
+        }
+        let chain = light.chain().unwrap();
+        assert_eq!(chain.best_number(), 12);
+        assert_eq!(chain.best_hash(), forked[6].hash());
+        assert_eq!(chain.header(6), Some(&forked[0]));
+
+        // headers which do not link are rejected.
+        let requests = light.schedule(&[peer(1, 20)], now);
+        let req_id = match requests[0].1 {
+            Request::Headers {
+                req_id,
+                ..
+            } => req_id,
+            ref request => panic!("unexpected request {:?}", request),
+        };
+        assert_eq!(
+            light.on_headers(1, req_id, CREDIT_LIMIT, headers(&genesis, 3, 2), &client, now),
+            Response::Rejected
+        );
+    }
+
+    fn staking_header(staker: &Ed25519KeyPair, parent: &BlockHeader) -> BlockHeader {
+        let mut header = BlockHeader::new();
+        header.set_number(parent.number() + 1);
+        header.set_parent_hash(parent.hash());
+        header.set_seal_type(SealType::PoS);
+        header.set_author(staker.address());
+        header.set_difficulty(U256::from(1));
+        // late enough for any staking delay.
+        header.set_timestamp(parent.timestamp() + 0xffff_ffff);
+        let empty: &[u8] = &[];
+        let seed = sign_ed25519(staker.secret(), &blake2b(empty)).unwrap();
+        let signature = sign_ed25519(staker.secret(), &header.bare_hash()).unwrap();
+        header.set_seal(vec![seed.to_vec(), signature.to_vec()]);
+        header
+    }
+
+    #[test]
+    fn rejects_forged_staking_headers() {
+        let spec = Spec::new_unity_test();
+        let genesis = spec.genesis_header();
+        let client = TestBlockChainClient::new_with_spec(spec);
+        let mut chain = HeaderChain::new(genesis.clone(), *genesis.difficulty());
+
+        // well signed, but by a key without any stake.
+        let forger = generate_keypair();
+        assert!(!chain.insert(vec![staking_header(&forger, &genesis)], &client));
+        assert_eq!(chain.best_number(), 0);
+
+        let staker = Ed25519KeyPair::from_secret(Ed25519Secret::from_str("7ea8af7d0982509cd815096d35bc3a295f57b2a078e4e25731e3ea977b9544626702b86f33072a55f46003b1e3e242eb18556be54c5ab12044c3c20829e0abb5").unwrap()).unwrap();
+        // claiming more difficulty than the chain allows, so as to outweigh it.
+        let mut heavy = staking_header(&staker, &genesis);
+        heavy.set_difficulty(U256::from(1_000_000));
+        let signature = sign_ed25519(staker.secret(), &heavy.bare_hash()).unwrap();
+        let seed = heavy.seal()[0].clone();
+        heavy.set_seal(vec![seed, signature.to_vec()]);
+        assert!(!chain.insert(vec![heavy], &client));
+        assert_eq!(chain.best_number(), 0);
+
+        let staked = staking_header(&staker, &genesis);
+        assert!(chain.insert(vec![staked.clone()], &client));
+        assert_eq!(chain.best_hash(), staked.hash());
+    }
+
+    #[test]
+    fn drops_headers_of_buried_chts() {
+        let client = TestBlockChainClient::new();
+        let genesis = genesis();
+        let mut chain = HeaderChain::new(genesis.clone(), U256::from(1));
+        let synced = headers(&genesis, 2 * cht::SIZE, 1);
+        assert!(chain.insert(synced.clone(), &client));
+
+        let entries = synced[..cht::SIZE as usize]
+            .iter()
+            .enumerate()
+            .map(|(i, header)| (header.hash(), U256::from(i as u64 + 2)));
+        assert_eq!(chain.cht_root(0), cht::compute_root(0, entries));
+        assert_eq!(chain.cht_root(1), None);
+        assert_eq!(chain.first_number(), cht::SIZE);
+        assert_eq!(chain.header(cht::SIZE - 1), None);
+        assert!(chain.header_by_hash(&synced[0].hash()).is_none());
+        assert_eq!(chain.best_number(), 2 * cht::SIZE);
+    }
+
+    #[test]
+    fn fetches_proved_receipts() {
+        let client = TestBlockChainClient::new();
+        let receipts: Vec<SimpleReceipt> = (0..4u64)
+            .map(|i| SimpleReceipt::new(H256::from(i), Vec::new()))
+            .collect();
+        let encoded: Vec<Vec<u8>> = receipts
+            .iter()
+            .map(|receipt| ::rlp::encode(receipt).into_vec())
+            .collect();
+        let genesis = genesis();
+        let mut block = headers(&genesis, 1, 1);
+        block[0].set_receipts_root(ordered_trie_root(encoded.iter()));
+        let block_hash = block[0].hash();
+
+        let mut light = LightSync::new();
+        light.start(genesis, U256::from(1));
+        assert!(light.chain.as_mut().unwrap().insert(block, &client));
+        let now = Instant::now();
+
+        let (sender, receiver) = oneshot::channel();
+        let request = LightRequest::Receipt {
+            block_hash: block_hash,
+            index: 2,
+        };
+        assert!(light.fetch(request.clone(), sender));
+        // blocks not synced cannot be proved against.
+        let (unknown, _) = oneshot::channel();
+        let unknown_request = LightRequest::Receipt {
+            block_hash: H256::from(7),
+            index: 0,
+        };
+        assert!(!light.fetch(unknown_request, unknown));
+
+        let respond = |req_id: u64, index: usize| {
+            let receipt_proof = proof::prove_ordered_trie(encoded.iter(), index).unwrap();
+            let mut stream = RlpStream::new_list(3);
+            stream.append(&req_id).append(&CREDIT_LIMIT);
+            stream.begin_list(receipt_proof.len());
+            for node in receipt_proof.iter() {
+                stream.append(node);
+            }
+            stream.out()
+        };
+
+        // a proof of another receipt is rejected, and the receipt asked from another peer.
+        let requests = light.schedule(&[peer(1, 1), peer(2, 1)], now);
+        assert_eq!(requests.len(), 1);
+        let (node_hash, req_id) = match requests[0] {
+            (
+                node_hash,
+                Request::Proof {
+                    req_id,
+                    request: ref asked,
+                },
+            ) => {
+                assert_eq!(*asked, request);
+                (node_hash, req_id)
+            }
+            ref request => panic!("unexpected request {:?}", request),
+        };
+        let wrong = respond(req_id, 1);
+        assert_eq!(
+            light.on_proof(node_hash, req_id, CREDIT_LIMIT, &UntrustedRlp::new(&wrong), now),
+            Response::Rejected
+        );
+
+        let requests = light.schedule(&[peer(1, 1), peer(2, 1)], now);
+        let req_id = match requests[0].1 {
+            Request::Proof {
+                req_id,
+                ..
+            } => req_id,
+            ref request => panic!("unexpected request {:?}", request),
+        };
+        let right = respond(req_id, 2);
+        assert_eq!(
+            light.on_proof(requests[0].0, req_id, CREDIT_LIMIT, &UntrustedRlp::new(&right), now),
+            Response::Accepted
+        );
+        assert_eq!(
+            receiver.wait(),
+            Ok(LightResponse::Receipt(receipts[2].clone()))
+        );
+    }
+}
@@ -20,9 +20,13 @@
  ******************************************************************************/
 
 use acore::client::{BlockChainClient, BlockId, BlockStatus, ChainNotify};
+use acore::header::Header as BlockHeader;
+use acore::transaction::transaction_queue::QueueEvent;
 use acore::transaction::UnverifiedTransaction;
 use aion_types::H256;
+use futures::sync::oneshot;
 use futures::{Future, Stream};
+use kvdb::KeyValueDB;
 use rlp::UntrustedRlp;
 use std::collections::BTreeMap;
 use std::ops::Index;
@@ -37,120 +41,137 @@ use p2p::*;
 
 use net::event::HANDSHAKE_DONE;
 
-use self::action::SyncAction;
+use self::action::{LightAction, SyncAction};
 // use self::error::*;
 use self::handler::blocks_bodies_handler::BlockBodiesHandler;
 use self::handler::blocks_headers_handler::BlockHeadersHandler;
 use self::handler::broadcast_handler::BroadcastsHandler;
 use self::handler::import_handler::ImportHandler;
+use self::handler::light_handler::LightHandler;
+use self::handler::skeleton_handler::SkeletonHandler;
+use self::handler::state_sync_handler::StateSyncHandler;
 use self::handler::status_handler::StatusHandler;
+use self::light::{LightRequest, LightResponse};
 use self::storage::{
     ActivePeerInfo, PeerInfo, SyncState, SyncStatus, SyncStorage, TransactionStats,
 };
 use rustc_hex::ToHex;
 
 pub mod action;
+mod compact;
 pub mod error;
 mod event;
 mod handler;
+pub mod light;
+mod skeleton;
+pub mod state_sync;
 pub mod storage;
+mod transaction_requests;
 
 const STATUS_REQ_INTERVAL: u64 = 2;
-const BLOCKS_BODIES_REQ_INTERVAL: u64 = 50;
-const BLOCKS_IMPORT_INTERVAL: u64 = 50;
+const SKELETON_SYNC_INTERVAL: u64 = 100;
 const STATICS_INTERVAL: u64 = 15;
 const BROADCAST_TRANSACTIONS_INTERVAL: u64 = 50;
 const SYNC_STATIC_CAPACITY: usize = 25;
 
 #[derive(Clone)]
-struct SyncMgr;
+struct SyncMgr {
+    p2p: P2pMgr,
+    storage: Arc<SyncStorage>,
+}
 
 impl SyncMgr {
-    fn enable(executor: &TaskExecutor) {
+    fn enable(&self, executor: &TaskExecutor) {
+        let (p2p, storage) = (self.p2p.clone(), self.storage.clone());
         let status_req_task =
             Interval::new(Instant::now(), Duration::from_secs(STATUS_REQ_INTERVAL))
                 .for_each(move |_| {
                     // status req
-                    StatusHandler::send_status_req();
+                    StatusHandler::send_status_req(&p2p, &storage);
 
                     Ok(())
                 })
                 .map_err(|e| error!("interval errored; err={:?}", e));
         executor.spawn(status_req_task);
 
-        let blocks_bodies_req_task = Interval::new(
-            Instant::now(),
-            Duration::from_millis(BLOCKS_BODIES_REQ_INTERVAL),
-        )
-        .for_each(move |_| {
-            // blocks bodies req
-            BlockBodiesHandler::send_blocks_bodies_req();
-
-            Ok(())
-        })
-        .map_err(|e| error!("interval errored; err={:?}", e));
-        executor.spawn(blocks_bodies_req_task);
-
-        let blocks_import_task = Interval::new(
+        // responses drive the skeleton sync, the timer only picks up timeouts, new peers and
+        // blocks ready for import.
+        let (p2p, storage) = (self.p2p.clone(), self.storage.clone());
+        let skeleton_sync_task = Interval::new(
             Instant::now(),
-            Duration::from_millis(BLOCKS_IMPORT_INTERVAL),
+            Duration::from_millis(SKELETON_SYNC_INTERVAL),
         )
         .for_each(move |_| {
-            ImportHandler::import_blocks();
+            SkeletonHandler::schedule(&p2p, &storage);
+            ImportHandler::import_blocks(&p2p, &storage);
 
             Ok(())
         })
         .map_err(|e| error!("interval errored; err={:?}", e));
-        executor.spawn(blocks_import_task);
+        executor.spawn(skeleton_sync_task);
 
+        let (p2p, storage) = (self.p2p.clone(), self.storage.clone());
         let broadcast_transactions_task = Interval::new(
             Instant::now(),
             Duration::from_millis(BROADCAST_TRANSACTIONS_INTERVAL),
         )
         .for_each(move |_| {
-            BroadcastsHandler::broad_new_transactions();
+            BroadcastsHandler::broad_new_transactions(&p2p, &storage);
+            BroadcastsHandler::request_timed_out_transactions(&p2p, &storage, Instant::now());
 
             Ok(())
         })
         .map_err(|e| error!("interval errored; err={:?}", e));
         executor.spawn(broadcast_transactions_task);
 
+        let (p2p, storage) = (self.p2p.clone(), self.storage.clone());
         let statics_task = Interval::new(Instant::now(), Duration::from_secs(STATICS_INTERVAL))
             .for_each(move |_| {
-                let connected_nodes = P2pMgr::get_nodes(CONNECTED);
+                let connected_nodes = p2p.get_nodes(CONNECTED);
                 for node in connected_nodes.iter() {
-                    if node.mode == Mode::BACKWARD || node.mode == Mode::FORWARD {
-                        if node.target_total_difficulty < SyncStorage::get_network_total_diff() {
-                            P2pMgr::remove_peer(node.node_hash);
-                        }
-                    } else if node.last_request_timestamp
+                    if node.last_request_timestamp
                         + Duration::from_secs(STATICS_INTERVAL * 4)
                         < SystemTime::now()
                     {
-                        P2pMgr::remove_peer(node.node_hash);
+                        p2p.remove_peer(node.node_hash);
                     }
                 }
 
-                let chain_info = SyncStorage::get_chain_info();
-                let block_number_last_time = SyncStorage::get_synced_block_number_last_time();
+                let chain_info = storage.get_chain_info();
+                let block_number_last_time = storage.get_synced_block_number_last_time();
                 let block_number_now = chain_info.best_block_number;
                 let sync_speed = (block_number_now - block_number_last_time) / STATICS_INTERVAL;
-                let mut active_nodes = P2pMgr::get_nodes(ALIVE);
+                let mut active_nodes = p2p.get_nodes(ALIVE);
                 let active_nodes_count = active_nodes.len();
 
                 info!(target: "sync", "{:=^127}", " Sync Statics ");
                 info!(target: "sync", "Best block number: {}, hash: {}", chain_info.best_block_number, chain_info.best_block_hash);
-                info!(target: "sync", "Network Best block number: {}, hash: {}", SyncStorage::get_network_best_block_number(), SyncStorage::get_network_best_block_hash());
-                info!(target: "sync", "Max staged block number: {}", SyncStorage::get_max_staged_block_number());
+                info!(target: "sync", "Network Best block number: {}, hash: {}", storage.get_network_best_block_number(), storage.get_network_best_block_hash());
+                let (skeleton_head, skeleton_target) = match storage.get_skeleton().lock() {
+                    Ok(skeleton) => (skeleton.head(), skeleton.target()),
+                    Err(_) => (0, 0),
+                };
+                info!(target: "sync", "Skeleton sync head: {}, target: {}", skeleton_head, skeleton_target);
+                if let Ok(state_sync) = storage.get_state_sync().lock() {
+                    if state_sync.is_active() {
+                        info!(target: "sync", "State sync pivot: {}, state nodes written: {}", state_sync.pivot_number(), state_sync.written());
+                    }
+                }
+                let light = LightHandler::is_active(&storage);
+                if light {
+                    if let Ok(light_sync) = storage.get_light_sync().lock() {
+                        info!(target: "sync", "Light mode, best header number: {}", light_sync.best_number());
+                    }
+                }
                 info!(target: "sync", "Sync speed: {} blks/sec", sync_speed);
                 info!(target: "sync",
                     "Total/Connected/Active peers: {}/{}/{}",
-                    P2pMgr::get_all_nodes_count(),
-                    P2pMgr::get_nodes_count(CONNECTED),
+                    p2p.get_all_nodes_count(),
+                    p2p.get_nodes_count(CONNECTED),
                     active_nodes_count,
                 );
                 info!(target: "sync", "{:-^127}","");
-                info!(target: "sync","      Total Diff    Blk No.    Blk Hash                 Address                 Revision      Conn  Seed  LstReq No.       Mode");
+                info!(target: "sync","      Total Diff    Blk No.    Blk Hash                 Address                 Revision      Conn  Seed  LstReq No.     Blks/s");
                 info!(target: "sync", "{:-^127}","");
                 active_nodes.sort_by(|a,b|{
                     if a.target_total_difficulty != b.target_total_difficulty{
@@ -179,7 +200,7 @@ impl SyncMgr {
                                 _ => ""
                             },
                             node.last_request_num,
-                            format!("{}",node.mode)
+                            format!("{:.1}", SkeletonHandler::throughput(&storage, node.node_hash))
                         );
                         count += 1;
                         if count == SYNC_STATIC_CAPACITY {
@@ -189,39 +210,27 @@ impl SyncMgr {
                 }
                 info!(target: "sync", "{:-^127}","");
 
-                if block_number_now + 8 < SyncStorage::get_network_best_block_number()
+                // no block is imported in light mode.
+                if !light
+                    && block_number_now + 8 < storage.get_network_best_block_number()
                     && block_number_now - block_number_last_time < 2
                 {
-                    SyncStorage::get_block_chain().clear_queue();
-                    SyncStorage::clear_downloaded_headers();
-                    SyncStorage::clear_downloaded_blocks();
-                    SyncStorage::clear_requested_blocks();
-                    SyncStorage::clear_headers_with_bodies_requested();
-                    SyncStorage::set_synced_block_number(SyncStorage::get_chain_info().best_block_number);
-                    let abnormal_mode_nodes_count =
-                        P2pMgr::get_nodes_count_with_mode(Mode::BACKWARD)
-                            + P2pMgr::get_nodes_count_with_mode(Mode::FORWARD);
-                    if abnormal_mode_nodes_count > (active_nodes_count / 5)
-                        || active_nodes_count == 0
-                    {
+                    storage.get_block_chain().clear_queue();
+                    if let Ok(mut skeleton) = storage.get_skeleton().lock() {
+                        skeleton.reset();
+                    }
+                    storage.clear_requested_blocks();
+                    storage.set_synced_block_number(storage.get_chain_info().best_block_number);
+                    if active_nodes_count == 0 {
                         info!(target: "sync", "Abnormal status, reseting network...");
-                        P2pMgr::reset();
+                        p2p.reset();
 
-                        SyncStorage::clear_imported_block_hashes();
-                        SyncStorage::clear_staged_blocks();
-                        SyncStorage::set_max_staged_block_number(0);
+                        storage.clear_imported_block_hashes();
                     }
                 }
 
-                SyncStorage::set_synced_block_number_last_time(block_number_now);
-                SyncStorage::set_sync_speed(sync_speed as u16);
-
-                if SyncStorage::get_network_best_block_number()
-                    <= SyncStorage::get_synced_block_number()
-                {
-                    // full synced
-                    SyncStorage::clear_staged_blocks();
-                }
+                storage.set_synced_block_number_last_time(block_number_now);
+                storage.set_sync_speed(sync_speed as u16);
 
                 Ok(())
             })
@@ -229,11 +238,14 @@ impl SyncMgr {
         executor.spawn(statics_task);
     }
 
-    fn handle(node: &mut Node, req: ChannelBuffer) {
+    fn handle(&self, node: &mut Node, req: ChannelBuffer) {
         if node.state_code & HANDSHAKE_DONE != HANDSHAKE_DONE {
             return;
         }
 
+        let p2p = &self.p2p;
+        let storage = &*self.storage;
+
         match Version::from(req.head.ver) {
             Version::V0 => {
                 trace!(target: "sync", "Ver 0 package received.");
@@ -243,36 +255,128 @@ impl SyncMgr {
                     Control::SYNC => {
                         trace!(target: "sync", "P2P message received.");
 
-                        match SyncAction::from(req.head.action) {
+                        let action = SyncAction::from(req.head.action);
+                        if !p2p.check_limits(node, &req, action.is_request()) {
+                            return;
+                        }
+
+                        match action {
                             SyncAction::STATUSREQ => {
-                                StatusHandler::handle_status_req(node);
+                                StatusHandler::handle_status_req(p2p, storage, node, req);
                             }
                             SyncAction::STATUSRES => {
-                                StatusHandler::handle_status_res(node, req);
+                                StatusHandler::handle_status_res(p2p, storage, node, req);
                             }
                             SyncAction::BLOCKSHEADERSREQ => {
-                                BlockHeadersHandler::handle_blocks_headers_req(node, req);
+                                BlockHeadersHandler::handle_blocks_headers_req(
+                                    p2p,
+                                    storage,
+                                    node,
+                                    req,
+                                );
                             }
                             SyncAction::BLOCKSHEADERSRES => {
-                                BlockHeadersHandler::handle_blocks_headers_res(node, req);
+                                BlockHeadersHandler::handle_blocks_headers_res(
+                                    p2p,
+                                    storage,
+                                    node,
+                                    req,
+                                );
                             }
                             SyncAction::BLOCKSBODIESREQ => {
-                                BlockBodiesHandler::handle_blocks_bodies_req(node, req);
+                                BlockBodiesHandler::handle_blocks_bodies_req(
+                                    p2p,
+                                    storage,
+                                    node,
+                                    req,
+                                );
                             }
                             SyncAction::BLOCKSBODIESRES => {
-                                BlockBodiesHandler::handle_blocks_bodies_res(node, req);
+                                BlockBodiesHandler::handle_blocks_bodies_res(
+                                    p2p,
+                                    storage,
+                                    node,
+                                    req,
+                                );
                             }
                             SyncAction::BROADCASTTX => {
-                                BroadcastsHandler::handle_broadcast_tx(node, req);
+                                BroadcastsHandler::handle_broadcast_tx(p2p, storage, node, req);
                             }
                             SyncAction::BROADCASTBLOCK => {
-                                BroadcastsHandler::handle_broadcast_block(node, req);
+                                BroadcastsHandler::handle_broadcast_block(p2p, storage, node, req);
+                            }
+                            SyncAction::ANNOUNCETXHASHES => {
+                                BroadcastsHandler::handle_announce_tx_hashes(
+                                    p2p,
+                                    storage,
+                                    node,
+                                    req,
+                                );
+                            }
+                            SyncAction::TRANSACTIONSREQ => {
+                                BroadcastsHandler::handle_transactions_req(p2p, storage, node, req);
+                            }
+                            SyncAction::TRANSACTIONSRES => {
+                                BroadcastsHandler::handle_transactions_res(p2p, storage, node, req);
+                            }
+                            SyncAction::COMPACTBLOCK => {
+                                BroadcastsHandler::handle_compact_block(p2p, storage, node, req);
+                            }
+                            SyncAction::BLOCKTRANSACTIONSREQ => {
+                                BroadcastsHandler::handle_block_transactions_req(
+                                    p2p,
+                                    storage,
+                                    node,
+                                    req,
+                                );
+                            }
+                            SyncAction::BLOCKTRANSACTIONSRES => {
+                                BroadcastsHandler::handle_block_transactions_res(
+                                    p2p,
+                                    storage,
+                                    node,
+                                    req,
+                                );
+                            }
+                            SyncAction::STATEDATAREQ => {
+                                StateSyncHandler::handle_state_data_req(p2p, storage, node, req);
+                            }
+                            SyncAction::STATEDATARES => {
+                                StateSyncHandler::handle_state_data_res(p2p, storage, node, req);
                             }
                             _ => {
                                 trace!(target: "sync", "UNKNOWN received.");
                             }
                         }
                     }
+                    Control::LIGHT => {
+                        trace!(target: "sync", "P2P light message received.");
+
+                        let action = LightAction::from(req.head.action);
+                        if !p2p.check_limits(node, &req, action.is_request()) {
+                            return;
+                        }
+
+                        match action {
+                            LightAction::HEADERSREQ
+                            | LightAction::HEADERPROOFREQ
+                            | LightAction::ACCOUNTPROOFREQ
+                            | LightAction::STORAGEPROOFREQ
+                            | LightAction::RECEIPTPROOFREQ => {
+                                LightHandler::handle_light_req(p2p, storage, node, req);
+                            }
+                            LightAction::HEADERSRES
+                            | LightAction::HEADERPROOFRES
+                            | LightAction::ACCOUNTPROOFRES
+                            | LightAction::STORAGEPROOFRES
+                            | LightAction::RECEIPTPROOFRES => {
+                                LightHandler::handle_light_res(p2p, storage, node, req);
+                            }
+                            LightAction::UNKNOWN => {
+                                trace!(target: "sync", "UNKNOWN received.");
+                            }
+                        }
+                    }
                     _ => {
                         error!(target: "sync", "Invalid message received: {}", req.head);
                     }
@@ -287,7 +391,7 @@ impl SyncMgr {
         };
     }
 
-    fn disable() { SyncStorage::reset(); }
+    fn disable(&self) { self.storage.reset(); }
 }
 
 /// Sync configuration
@@ -313,6 +417,8 @@ pub struct Params {
     pub client: Arc<BlockChainClient>,
     /// Network layer configuration.
     pub network_config: NetworkConfig,
+    /// Database the known peers are saved in.
+    pub db: Arc<KeyValueDB>,
 }
 
 pub struct NetworkService {
@@ -325,6 +431,14 @@ pub struct Sync {
     network: NetworkService,
     /// starting block number.
     starting_block_number: u64,
+    /// Database the known peers are saved in.
+    db: Arc<KeyValueDB>,
+    /// Peers of this node.
+    p2p: P2pMgr,
+    /// Sync state of this node.
+    storage: Arc<SyncStorage>,
+    /// Network layer, passing sync messages on to this node.
+    net: NetManager,
 }
 
 impl Sync {
@@ -334,7 +448,59 @@ impl Sync {
         // starting block number is the local best block number during kernel startup.
         let starting_block_number = chain_info.best_block_number;
 
-        SyncStorage::init(params.client);
+        let storage = Arc::new(SyncStorage::new(params.client));
+        {
+            // the storage keeps the client, which must not keep the storage alive in turn.
+            let client = storage.get_block_chain();
+            let queue_storage = Arc::downgrade(&storage);
+            client.add_transaction_queue_listener(Box::new(move |event: QueueEvent| {
+                if let Some(storage) = queue_storage.upgrade() {
+                    storage.on_transaction_queue_event(event);
+                }
+            }));
+            for pending in client.ready_transactions() {
+                storage.on_transaction_queue_event(QueueEvent::Added(
+                    &pending.transaction,
+                    pending.condition.as_ref(),
+                ));
+            }
+        }
+        let genesis = {
+            let client = storage.get_block_chain();
+            let genesis = client
+                .block_header(BlockId::Number(0))
+                .map(|header| header.decode());
+            let total_difficulty = client.block_total_difficulty(BlockId::Number(0));
+            match (genesis, total_difficulty) {
+                (Some(genesis), Some(total_difficulty)) => Some((genesis, total_difficulty)),
+                _ => None,
+            }
+        };
+        if let Some((genesis, total_difficulty)) = genesis {
+            if params.network_config.light {
+                if let Ok(mut light_sync) = storage.get_light_sync().lock() {
+                    light_sync.start(genesis, total_difficulty);
+                }
+            } else if params.network_config.fast_sync && starting_block_number == 0 {
+                // only a node without any block yet can skip executing the chain; the headers
+                // up to the pivot are still synced and verified from the genesis.
+                if let Ok(mut state_sync) = storage.get_state_sync().lock() {
+                    state_sync.start(genesis, total_difficulty);
+                }
+            }
+        }
+
+        let p2p = P2pMgr::new(params.network_config.clone());
+        let sync_mgr = SyncMgr {
+            p2p: p2p.clone(),
+            storage: storage.clone(),
+        };
+        let sync_handler = DefaultHandler {
+            callback: Arc::new(move |node: &mut Node, req: ChannelBuffer| {
+                sync_mgr.handle(node, req)
+            }),
+        };
+        let net = NetManager::new(p2p.clone(), sync_handler);
 
         let service = NetworkService {
             config: params.network_config.clone(),
@@ -342,8 +508,30 @@ impl Sync {
         Arc::new(Sync {
             network: service,
             starting_block_number: starting_block_number,
+            db: params.db,
+            p2p: p2p,
+            storage: storage,
+            net: net,
         })
     }
+
+    /// Start syncing with the nodes connected by `connect_in_memory` only, without opening any
+    /// socket.
+    pub fn start_in_memory(&self) {
+        let executor = self.storage.get_executor();
+        self.net.enable_in_memory(&executor);
+        self.sync_mgr().enable(&executor);
+    }
+
+    /// Connect to another node in the same process, as if it were a peer on the network.
+    pub fn connect_in_memory(&self, server: &Sync) { self.net.connect_in_memory(&server.net); }
+
+    fn sync_mgr(&self) -> SyncMgr {
+        SyncMgr {
+            p2p: self.p2p.clone(),
+            storage: self.storage.clone(),
+        }
+    }
 }
 
 pub trait SyncProvider: Send + ::std::marker::Sync {
@@ -373,18 +561,19 @@ impl SyncProvider for Sync {
             network_id: 256,
             start_block_number: self.starting_block_number,
             last_imported_block_number: None,
-            highest_block_number: { Some(SyncStorage::get_network_best_block_number()) },
+            highest_block_number: { Some(self.storage.get_network_best_block_number()) },
             blocks_received: 0,
             blocks_total: 0,
-            num_peers: { P2pMgr::get_nodes_count(ALIVE) },
+            num_peers: { self.p2p.get_nodes_count(ALIVE) },
             num_active_peers: 0,
+            sync_speed: self.storage.get_sync_speed(),
         }
     }
 
     /// Get sync peers
     fn peers(&self) -> Vec<PeerInfo> {
         let mut peer_info_list = Vec::new();
-        let peer_nodes = P2pMgr::get_all_nodes();
+        let peer_nodes = self.p2p.get_all_nodes();
         for peer in peer_nodes.iter() {
             let peer_info = PeerInfo {
                 id: Some(peer.get_node_id()),
@@ -394,12 +583,12 @@ impl SyncProvider for Sync {
         peer_info_list
     }
 
-    fn enode(&self) -> Option<String> { Some(P2pMgr::get_local_node().get_node_id()) }
+    fn enode(&self) -> Option<String> { Some(self.p2p.get_local_node().get_node_id()) }
 
     fn transactions_stats(&self) -> BTreeMap<H256, TransactionStats> { BTreeMap::new() }
 
     fn active(&self) -> Vec<ActivePeerInfo> {
-        let ac_nodes = P2pMgr::get_nodes(ALIVE);
+        let ac_nodes = self.p2p.get_nodes(ALIVE);
         ac_nodes
             .into_iter()
             .map(|node| {
@@ -407,12 +596,43 @@ impl SyncProvider for Sync {
                     highest_block_number: node.best_block_num,
                     id: node.node_id.to_hex(),
                     ip: node.ip_addr.ip.to_hex(),
+                    reputation: node.reputation,
                 }
             })
             .collect()
     }
 }
 
+/// On-demand access to chain data in light mode.
+pub trait LightProvider: Send + ::std::marker::Sync {
+    /// Get the best header synced, if in light mode.
+    fn best_header(&self) -> Option<BlockHeader>;
+
+    /// Get the canonical header synced at the given number, if still kept.
+    fn header(&self, number: u64) -> Option<BlockHeader>;
+
+    /// Fetch data proved by a full node against the headers synced. The receiver gets
+    /// canceled if that is not possible.
+    fn fetch(&self, request: LightRequest) -> oneshot::Receiver<LightResponse>;
+}
+
+impl LightProvider for Sync {
+    fn best_header(&self) -> Option<BlockHeader> {
+        let light_sync = self.storage.get_light_sync().lock().ok()?;
+        let chain = light_sync.chain()?;
+        chain.header(chain.best_number()).cloned()
+    }
+
+    fn header(&self, number: u64) -> Option<BlockHeader> {
+        let light_sync = self.storage.get_light_sync().lock().ok()?;
+        light_sync.chain()?.header(number).cloned()
+    }
+
+    fn fetch(&self, request: LightRequest) -> oneshot::Receiver<LightResponse> {
+        LightHandler::fetch(&self.p2p, &self.storage, request)
+    }
+}
+
 /// Trait for managing network
 pub trait NetworkManager: Send + ::std::marker::Sync {
     /// Set to allow unreserved peers to connect
@@ -425,6 +645,8 @@ pub trait NetworkManager: Send + ::std::marker::Sync {
     fn stop_network(&self);
     /// Query the current configuration of the network
     fn network_config(&self) -> NetworkConfig;
+    /// Get the local node
+    fn local_node(&self) -> Node;
 }
 
 impl NetworkManager for Sync {
@@ -433,27 +655,26 @@ impl NetworkManager for Sync {
     fn deny_unreserved_peers(&self) {}
 
     fn start_network(&self) {
-        let executor = SyncStorage::get_executor();
-        let sync_handler = DefaultHandler {
-            callback: SyncMgr::handle,
-        };
+        let executor = self.storage.get_executor();
 
-        P2pMgr::enable(self.network_config());
+        self.p2p.open_peer_store(self.db.clone());
         debug!(target: "sync", "###### P2P enabled... ######");
 
-        NetManager::enable(&executor, sync_handler);
+        self.net.enable(&executor);
         debug!(target: "sync", "###### network enabled... ######");
 
-        SyncMgr::enable(&executor);
+        self.sync_mgr().enable(&executor);
         debug!(target: "sync", "###### SYNC enabled... ######");
     }
 
     fn stop_network(&self) {
-        SyncMgr::disable();
-        P2pMgr::disable();
+        self.sync_mgr().disable();
+        self.p2p.disable();
     }
 
     fn network_config(&self) -> NetworkConfig { NetworkConfig::from(self.network.config.clone()) }
+
+    fn local_node(&self) -> Node { self.p2p.get_local_node().clone() }
 }
 
 impl ChainNotify for Sync {
@@ -461,7 +682,7 @@ impl ChainNotify for Sync {
         &self,
         imported: Vec<H256>,
         _invalid: Vec<H256>,
-        enacted: Vec<H256>,
+        _enacted: Vec<H256>,
         _retracted: Vec<H256>,
         sealed: Vec<H256>,
         _proposed: Vec<Vec<u8>>,
@@ -469,11 +690,10 @@ impl ChainNotify for Sync {
     )
     {
         if !imported.is_empty() {
-            let min_imported_block_number = SyncStorage::get_synced_block_number() + 1;
+            let min_imported_block_number = self.storage.get_synced_block_number() + 1;
             let mut max_imported_block_number = 0;
-            let client = SyncStorage::get_block_chain();
+            let client = self.storage.get_block_chain();
             for hash in imported.iter() {
-                ImportHandler::import_staged_blocks(&hash);
                 let block_id = BlockId::Hash(*hash);
                 if client.block_status(block_id) == BlockStatus::InChain {
                     if let Some(block_number) = client.block_number(block_id) {
@@ -489,7 +709,7 @@ impl ChainNotify for Sync {
                 return;
             }
 
-            let synced_block_number = SyncStorage::get_synced_block_number();
+            let synced_block_number = self.storage.get_synced_block_number();
             if max_imported_block_number <= synced_block_number {
                 let mut hashes = Vec::new();
                 for block_number in max_imported_block_number..synced_block_number + 1 {
@@ -499,18 +719,17 @@ impl ChainNotify for Sync {
                     }
                 }
                 if hashes.len() > 0 {
-                    SyncStorage::remove_imported_block_hashes(hashes);
+                    self.storage.remove_imported_block_hashes(hashes);
                 }
             }
 
-            SyncStorage::set_synced_block_number(max_imported_block_number);
+            self.storage.set_synced_block_number(max_imported_block_number);
 
             for block_number in min_imported_block_number..max_imported_block_number + 1 {
                 let block_id = BlockId::Number(block_number);
                 if let Some(blk) = client.block(block_id) {
                     let block_hash = blk.hash();
-                    ImportHandler::import_staged_blocks(&block_hash);
-                    if let Some(time) = SyncStorage::get_requested_time(&block_hash) {
+                    if let Some(time) = self.storage.get_requested_time(&block_hash) {
                         info!(target: "sync",
                             "New block #{} {}, with {} txs added in chain, time elapsed: {:?}.",
                             block_number, block_hash, blk.transactions_count(), SystemTime::now().duration_since(time).expect("importing duration"));
@@ -519,19 +738,13 @@ impl ChainNotify for Sync {
             }
         }
 
-        if enacted.is_empty() {
-            for hash in enacted.iter() {
-                debug!(target: "sync", "enacted hash: {:?}", hash);
-                ImportHandler::import_staged_blocks(&hash);
-            }
-        }
-
         if !sealed.is_empty() {
             debug!(target: "sync", "Propagating blocks...");
-            SyncStorage::insert_imported_block_hashes(sealed.clone());
+            self.storage.insert_imported_block_hashes(sealed.clone());
             BroadcastsHandler::propagate_new_blocks(
+                &self.p2p,
                 sealed.index(0),
-                SyncStorage::get_block_chain(),
+                self.storage.get_block_chain(),
             );
         }
     }
@@ -552,13 +765,13 @@ impl ChainNotify for Sync {
             if let Ok(tx) = UntrustedRlp::new(&transaction_rlp).as_val() {
                 let transaction: UnverifiedTransaction = tx;
                 let hash = transaction.hash();
-                let sent_transaction_hashes_mutex = SyncStorage::get_sent_transaction_hashes();
+                let sent_transaction_hashes_mutex = self.storage.get_sent_transaction_hashes();
                 let mut lock = sent_transaction_hashes_mutex.lock();
 
                 if let Ok(ref mut sent_transaction_hashes) = lock {
                     if !sent_transaction_hashes.contains_key(&hash) {
                         sent_transaction_hashes.insert(hash, 0);
-                        SyncStorage::insert_received_transaction(transaction_rlp);
+                        self.storage.insert_received_transaction(transaction_rlp);
                     }
                 }
             }
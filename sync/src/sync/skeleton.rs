@@ -0,0 +1,866 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Headers-first skeleton sync scheduler.
+//!
+//! A round starts by asking the best peer for a sparse skeleton: the last header of every
+//! `SEGMENT_SIZE` blocks ahead of the local chain. The gaps between skeleton headers are then
+//! filled in parallel by any peer far enough ahead, each segment checked against the skeleton
+//! on both ends. Filled headers are queued for body download and handed out by range to idle
+//! peers, sized by the throughput measured on each peer. Requests that time out or come back
+//! wrong are put back and re-assigned to another peer. Downloaded blocks are released to the
+//! importer strictly in block order.
+//!
+//! The scheduler only keeps book; sending the requests is left to the handlers.
+
+use std::cmp::{self, Ordering};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use acore::block::Block;
+use acore::header::Header as BlockHeader;
+use acore::transaction::UnverifiedTransaction;
+use aion_types::H256;
+use rlp::Encodable;
+use triehash::ordered_trie_root;
+
+use super::storage::BlocksWrapper;
+
+/// Number of blocks between two skeleton headers.
+pub const SEGMENT_SIZE: u64 = 128;
+/// Max skeleton headers requested in a round.
+pub const MAX_SKELETON_HEADERS: u64 = 32;
+/// Seconds a peer is given to answer a request before it is re-assigned.
+pub const REQUEST_TIMEOUT: u64 = 8;
+
+const MIN_BODIES_BATCH: usize = 8;
+const MAX_BODIES_BATCH: usize = 128;
+// blocks per second assumed for a peer until its first bodies response.
+const INITIAL_THROUGHPUT: f64 = 32.0;
+// seconds a bodies request is sized to take on the assigned peer.
+const BODIES_ROUND_TRIP: f64 = 1.0;
+// weight of the latest measurement in the moving average.
+const THROUGHPUT_WEIGHT: f64 = 0.2;
+const REWIND_STEP: u64 = 64;
+const MAX_REWIND: u64 = 4096;
+
+/// Head of a peer as far as scheduling is concerned.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeerHead {
+    pub node_hash: u64,
+    pub best_block_num: u64,
+}
+
+/// A request the scheduler wants sent to a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    /// `count` headers starting at block `from`, `skip` blocks apart.
+    Headers { from: u64, count: u32, skip: u32 },
+    /// Bodies of the given blocks, the first one being block `from`.
+    Bodies { from: u64, hashes: Vec<H256> },
+}
+
+/// Outcome of handing a response to the scheduler.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Response {
+    /// The response answered an outstanding request.
+    Accepted,
+    /// The response answered part of an outstanding request, the rest has been re-queued.
+    Partial,
+    /// The response did not match the outstanding request, which has been re-queued.
+    Rejected,
+    /// Nothing was asked from the peer, e.g. the request had already timed out.
+    Unexpected,
+}
+
+enum Task {
+    Skeleton { from: u64, count: u64 },
+    Segment(u64),
+    Bodies(Vec<BlockHeader>),
+}
+
+struct Assignment {
+    task: Task,
+    sent: Instant,
+}
+
+struct Segment {
+    last: u64,
+    anchor: Option<H256>,
+    requested: bool,
+}
+
+pub struct SkeletonSync {
+    /// Last block handed to the importer in the current round.
+    head: u64,
+    /// Last block of the current round, 0 when no round is running.
+    target: u64,
+    /// Blocks the next round starts behind the local best block.
+    rewind: u64,
+    anchors: HashMap<u64, H256>,
+    segments: BTreeMap<u64, Segment>,
+    bodies: VecDeque<BlockHeader>,
+    downloaded: BTreeMap<u64, BlocksWrapper>,
+    assignments: HashMap<u64, Assignment>,
+    throughputs: HashMap<u64, f64>,
+}
+
+impl SkeletonSync {
+    pub fn new() -> Self {
+        SkeletonSync {
+            head: 0,
+            target: 0,
+            rewind: 0,
+            anchors: HashMap::new(),
+            segments: BTreeMap::new(),
+            bodies: VecDeque::new(),
+            downloaded: BTreeMap::new(),
+            assignments: HashMap::new(),
+            throughputs: HashMap::new(),
+        }
+    }
+
+    /// Last block handed to the importer.
+    pub fn head(&self) -> u64 { self.head }
+
+    /// Last block of the running round, 0 if none.
+    pub fn target(&self) -> u64 { self.target }
+
+    /// Measured bodies throughput of a peer, in blocks per second.
+    pub fn throughput(&self, node_hash: u64) -> f64 {
+        self.throughputs
+            .get(&node_hash)
+            .cloned()
+            .unwrap_or(INITIAL_THROUGHPUT)
+    }
+
+    /// Whether a request to the peer is outstanding.
+    pub fn is_busy(&self, node_hash: u64) -> bool { self.assignments.contains_key(&node_hash) }
+
+    /// Drop every outstanding request for peers gone or too slow to answer, putting their work
+    /// back in the queue. Returns the connected peers whose requests timed out.
+    pub fn expire(&mut self, peers: &[PeerHead], now: Instant) -> Vec<u64> {
+        let timeout = Duration::from_secs(REQUEST_TIMEOUT);
+        let mut gone = Vec::new();
+        let mut timed_out = Vec::new();
+        for (node_hash, assignment) in self.assignments.iter() {
+            if !peers.iter().any(|peer| peer.node_hash == *node_hash) {
+                gone.push(*node_hash);
+            } else if assignment.sent + timeout <= now {
+                timed_out.push(*node_hash);
+            }
+        }
+
+        for node_hash in gone {
+            self.release(node_hash);
+            self.throughputs.remove(&node_hash);
+        }
+        for node_hash in timed_out.iter() {
+            self.release(*node_hash);
+            self.slow_down(*node_hash);
+        }
+        timed_out
+    }
+
+    /// Assign work to the idle peers. A new round is started from `local_best` when none is
+    /// running.
+    pub fn schedule(
+        &mut self,
+        local_best: u64,
+        peers: &[PeerHead],
+        now: Instant,
+    ) -> Vec<(u64, Request)>
+    {
+        let mut requests = Vec::new();
+        let mut idle: Vec<PeerHead> = peers
+            .iter()
+            .filter(|peer| !self.is_busy(peer.node_hash))
+            .cloned()
+            .collect();
+        // fastest peers get the work first.
+        idle.sort_by(|a, b| {
+            self.throughput(b.node_hash)
+                .partial_cmp(&self.throughput(a.node_hash))
+                .unwrap_or(Ordering::Equal)
+        });
+
+        if self.target == 0 && !self.is_skeleton_requested() {
+            if let Some(request) = self.start_round(local_best, &idle, now) {
+                requests.push(request);
+            }
+            idle.retain(|peer| !self.is_busy(peer.node_hash));
+        }
+
+        let mut remaining = Vec::new();
+        for peer in idle {
+            match self.next_segment(peer.best_block_num) {
+                Some((first, last)) => {
+                    self.assign(peer.node_hash, Task::Segment(first), now);
+                    requests.push((
+                        peer.node_hash,
+                        Request::Headers {
+                            from: first,
+                            count: (last - first + 1) as u32,
+                            skip: 0,
+                        },
+                    ));
+                }
+                None => remaining.push(peer),
+            }
+        }
+
+        for peer in remaining {
+            let batch_size = self.bodies_batch_size(peer.node_hash);
+            let mut headers = Vec::new();
+            while headers.len() < batch_size {
+                let available = self
+                    .bodies
+                    .front()
+                    .map_or(false, |header| header.number() <= peer.best_block_num);
+                if !available {
+                    break;
+                }
+                if let Some(header) = self.bodies.pop_front() {
+                    headers.push(header);
+                }
+            }
+            if headers.is_empty() {
+                continue;
+            }
+
+            let from = headers[0].number();
+            let hashes = headers.iter().map(|header| header.hash()).collect();
+            self.assign(peer.node_hash, Task::Bodies(headers), now);
+            requests.push((peer.node_hash, Request::Bodies { from, hashes }));
+        }
+
+        requests
+    }
+
+    /// Hand over headers received from a peer. The headers are expected to be valid on their
+    /// own; only how they fit the round is checked here.
+    pub fn on_headers(&mut self, node_hash: u64, headers: Vec<BlockHeader>) -> Response {
+        let assignment = match self.assignments.remove(&node_hash) {
+            Some(assignment) => assignment,
+            None => return Response::Unexpected,
+        };
+
+        match assignment.task {
+            Task::Skeleton {
+                from,
+                count,
+            } => {
+                if self.accept_skeleton(from, count, &headers) {
+                    Response::Accepted
+                } else {
+                    // the peer may not understand skeleton requests, carry on with a
+                    // plain contiguous segment instead.
+                    let last = self.head + SEGMENT_SIZE;
+                    self.target = last;
+                    self.segments.insert(
+                        self.head + 1,
+                        Segment {
+                            last: last,
+                            anchor: None,
+                            requested: false,
+                        },
+                    );
+                    Response::Rejected
+                }
+            }
+            Task::Segment(first) => {
+                if self.accept_segment(first, headers) {
+                    Response::Accepted
+                } else {
+                    if let Some(segment) = self.segments.get_mut(&first) {
+                        segment.requested = false;
+                    }
+                    Response::Rejected
+                }
+            }
+            task => {
+                self.assignments.insert(
+                    node_hash,
+                    Assignment {
+                        task: task,
+                        sent: assignment.sent,
+                    },
+                );
+                Response::Unexpected
+            }
+        }
+    }
+
+    /// Hand over block bodies received from a peer, in the order they were requested.
+    pub fn on_bodies(
+        &mut self,
+        node_hash: u64,
+        bodies: Vec<Vec<UnverifiedTransaction>>,
+        now: Instant,
+    ) -> Response
+    {
+        let assignment = match self.assignments.remove(&node_hash) {
+            Some(assignment) => assignment,
+            None => return Response::Unexpected,
+        };
+
+        match assignment.task {
+            Task::Bodies(headers) => {
+                // peers skip the bodies they don't have, so each body belongs to the next
+                // header whose transactions root it matches.
+                let mut indexes = Vec::with_capacity(bodies.len());
+                let mut next = 0;
+                for transactions in bodies.iter() {
+                    let transactions_root = ordered_trie_root(
+                        transactions
+                            .iter()
+                            .map(|transaction| transaction.rlp_bytes()),
+                    );
+                    match headers[next..]
+                        .iter()
+                        .position(|header| header.transactions_root() == &transactions_root)
+                    {
+                        Some(offset) => {
+                            indexes.push(next + offset);
+                            next += offset + 1;
+                        }
+                        None => {
+                            self.queue_bodies(headers);
+                            self.slow_down(node_hash);
+                            return Response::Rejected;
+                        }
+                    }
+                }
+
+                let mut matched = indexes.into_iter().zip(bodies.into_iter()).peekable();
+                let mut runs: Vec<Vec<Block>> = Vec::new();
+                let mut missing = Vec::new();
+                for (index, header) in headers.into_iter().enumerate() {
+                    if !matched.peek().map_or(false, |&(i, _)| i == index) {
+                        missing.push(header);
+                        continue;
+                    }
+                    let transactions = match matched.next() {
+                        Some((_, transactions)) => transactions,
+                        None => break,
+                    };
+                    let follows = runs
+                        .last()
+                        .and_then(|run| run.last())
+                        .map_or(false, |last| last.header.number() + 1 == header.number());
+                    let block = Block {
+                        header: header,
+                        transactions: transactions,
+                    };
+                    if !follows {
+                        runs.push(Vec::new());
+                    }
+                    if let Some(run) = runs.last_mut() {
+                        run.push(block);
+                    }
+                }
+
+                let received: usize = runs.iter().map(|run| run.len()).sum();
+                if received > 0 {
+                    self.measure(node_hash, received, assignment.sent, now);
+                }
+                for run in runs {
+                    let mut bw = BlocksWrapper::new();
+                    bw.node_id_hash = node_hash;
+                    let first = run[0].header.number();
+                    bw.blocks = run;
+                    self.downloaded.insert(first, bw);
+                }
+                if missing.is_empty() {
+                    return Response::Accepted;
+                }
+
+                // only what the peer skipped is asked again, from a peer trusted less.
+                self.queue_bodies(missing);
+                self.slow_down(node_hash);
+                Response::Partial
+            }
+            task => {
+                self.assignments.insert(
+                    node_hash,
+                    Assignment {
+                        task: task,
+                        sent: assignment.sent,
+                    },
+                );
+                Response::Unexpected
+            }
+        }
+    }
+
+    /// Take the downloaded blocks which directly follow the last ones handed over.
+    pub fn drain_ready(&mut self) -> Vec<BlocksWrapper> {
+        let mut ready = Vec::new();
+        loop {
+            let next = self.head + 1;
+            match self.downloaded.remove(&next) {
+                Some(bw) => {
+                    self.head += bw.blocks.len() as u64;
+                    ready.push(bw);
+                }
+                None => break,
+            }
+        }
+
+        if self.target != 0 && self.head >= self.target {
+            trace!(target: "sync", "Skeleton round up to #{} done.", self.target);
+            self.end_round();
+            self.rewind = 0;
+        }
+        ready
+    }
+
+    /// Abandon the running round, the next one starts from the local best block.
+    pub fn reset(&mut self) {
+        self.end_round();
+        self.bodies.clear();
+        self.downloaded.clear();
+        self.assignments.clear();
+        self.head = 0;
+        self.rewind = 0;
+    }
+
+    /// Abandon the running round because its first block did not connect to the local chain;
+    /// every further call starts the next round further back.
+    pub fn rewind(&mut self) {
+        let rewind = cmp::min(cmp::max(REWIND_STEP, self.rewind * 2), MAX_REWIND);
+        self.reset();
+        self.rewind = rewind;
+    }
+
+    fn end_round(&mut self) {
+        self.target = 0;
+        self.anchors.clear();
+        self.segments.clear();
+    }
+
+    fn start_round(
+        &mut self,
+        local_best: u64,
+        idle: &[PeerHead],
+        now: Instant,
+    ) -> Option<(u64, Request)>
+    {
+        let best = match idle.iter().max_by_key(|peer| peer.best_block_num) {
+            Some(peer) => *peer,
+            None => return None,
+        };
+        let head = cmp::max(local_best, self.head).saturating_sub(self.rewind);
+        if best.best_block_num <= head {
+            return None;
+        }
+        self.head = head;
+
+        let count = cmp::min(
+            (best.best_block_num - head) / SEGMENT_SIZE,
+            MAX_SKELETON_HEADERS,
+        );
+        if count == 0 {
+            // close to the tip, a single segment from the best peer is enough.
+            self.target = best.best_block_num;
+            self.segments.insert(
+                head + 1,
+                Segment {
+                    last: best.best_block_num,
+                    anchor: None,
+                    requested: false,
+                },
+            );
+            return None;
+        }
+
+        let from = head + SEGMENT_SIZE;
+        debug!(target: "sync", "Skeleton round from #{}, {} headers requested.", head + 1, count);
+        self.assign(
+            best.node_hash,
+            Task::Skeleton {
+                from: from,
+                count: count,
+            },
+            now,
+        );
+        Some((
+            best.node_hash,
+            Request::Headers {
+                from: from,
+                count: count as u32,
+                skip: (SEGMENT_SIZE - 1) as u32,
+            },
+        ))
+    }
+
+    fn accept_skeleton(&mut self, from: u64, count: u64, headers: &[BlockHeader]) -> bool {
+        if headers.is_empty() || headers.len() as u64 > count {
+            return false;
+        }
+        for (i, header) in headers.iter().enumerate() {
+            if header.number() != from + i as u64 * SEGMENT_SIZE {
+                return false;
+            }
+        }
+
+        let mut first = self.head + 1;
+        for header in headers.iter() {
+            let hash = header.hash();
+            self.anchors.insert(header.number(), hash);
+            self.segments.insert(
+                first,
+                Segment {
+                    last: header.number(),
+                    anchor: Some(hash),
+                    requested: false,
+                },
+            );
+            first = header.number() + 1;
+        }
+        self.target = first - 1;
+        true
+    }
+
+    fn accept_segment(&mut self, first: u64, headers: Vec<BlockHeader>) -> bool {
+        let (last, anchor) = match self.segments.get(&first) {
+            Some(segment) => (segment.last, segment.anchor),
+            None => return false,
+        };
+        if headers.len() as u64 != last - first + 1 {
+            return false;
+        }
+
+        let mut parent = self.anchors.get(&(first - 1)).cloned();
+        for (i, header) in headers.iter().enumerate() {
+            if header.number() != first + i as u64 {
+                return false;
+            }
+            if let Some(parent) = parent {
+                if *header.parent_hash() != parent {
+                    return false;
+                }
+            }
+            parent = Some(header.hash());
+        }
+        if anchor.is_some() && parent != anchor {
+            return false;
+        }
+
+        self.segments.remove(&first);
+        self.queue_bodies(headers);
+        true
+    }
+
+    fn is_skeleton_requested(&self) -> bool {
+        self.assignments.values().any(|assignment| {
+            match assignment.task {
+                Task::Skeleton {
+                    ..
+                } => true,
+                _ => false,
+            }
+        })
+    }
+
+    fn next_segment(&mut self, best_block_num: u64) -> Option<(u64, u64)> {
+        let next = self
+            .segments
+            .iter()
+            .find(|&(_, segment)| !segment.requested && segment.last <= best_block_num)
+            .map(|(first, segment)| (*first, segment.last));
+        if let Some((first, _)) = next {
+            if let Some(segment) = self.segments.get_mut(&first) {
+                segment.requested = true;
+            }
+        }
+        next
+    }
+
+    // queue headers for bodies download, keeping the queue in block order.
+    fn queue_bodies(&mut self, headers: Vec<BlockHeader>) {
+        let first = match headers.first() {
+            Some(header) => header.number(),
+            None => return,
+        };
+        let at = self
+            .bodies
+            .iter()
+            .position(|header| header.number() > first)
+            .unwrap_or(self.bodies.len());
+        for (i, header) in headers.into_iter().enumerate() {
+            self.bodies.insert(at + i, header);
+        }
+    }
+
+    fn assign(&mut self, node_hash: u64, task: Task, now: Instant) {
+        self.assignments.insert(
+            node_hash,
+            Assignment {
+                task: task,
+                sent: now,
+            },
+        );
+    }
+
+    // put the work of the peer's outstanding request back in the queue.
+    fn release(&mut self, node_hash: u64) {
+        if let Some(assignment) = self.assignments.remove(&node_hash) {
+            match assignment.task {
+                Task::Skeleton {
+                    ..
+                } => {}
+                Task::Segment(first) => {
+                    if let Some(segment) = self.segments.get_mut(&first) {
+                        segment.requested = false;
+                    }
+                }
+                Task::Bodies(headers) => self.queue_bodies(headers),
+            }
+        }
+    }
+
+    fn bodies_batch_size(&self, node_hash: u64) -> usize {
+        let size = (self.throughput(node_hash) * BODIES_ROUND_TRIP) as usize;
+        cmp::min(cmp::max(size, MIN_BODIES_BATCH), MAX_BODIES_BATCH)
+    }
+
+    fn measure(&mut self, node_hash: u64, blocks: usize, sent: Instant, now: Instant) {
+        let elapsed = now.duration_since(sent);
+        let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.0;
+        let measured = blocks as f64 / secs.max(0.001);
+        let throughput = self.throughput(node_hash);
+        self.throughputs.insert(
+            node_hash,
+            throughput * (1.0 - THROUGHPUT_WEIGHT) + measured * THROUGHPUT_WEIGHT,
+        );
+    }
+
+    fn slow_down(&mut self, node_hash: u64) {
+        let throughput = self.throughput(node_hash);
+        self.throughputs
+            .insert(node_hash, (throughput / 2.0).max(1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(from: u64, to: u64) -> Vec<BlockHeader> {
+        let mut headers: Vec<BlockHeader> = Vec::new();
+        for number in from..to + 1 {
+            let mut header = BlockHeader::new();
+            header.set_number(number);
+            if let Some(parent) = headers.last() {
+                header.set_parent_hash(parent.hash());
+            }
+            headers.push(header);
+        }
+        headers
+    }
+
+    fn peer(node_hash: u64, best_block_num: u64) -> PeerHead {
+        PeerHead {
+            node_hash: node_hash,
+            best_block_num: best_block_num,
+        }
+    }
+
+    #[test]
+    fn fills_skeleton_from_many_peers() {
+        let headers = chain(1, 3 * SEGMENT_SIZE);
+        let peers = vec![peer(1, 3 * SEGMENT_SIZE), peer(2, 3 * SEGMENT_SIZE)];
+        let now = Instant::now();
+        let mut skeleton = SkeletonSync::new();
+
+        let requests = skeleton.schedule(0, &peers, now);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].1,
+            Request::Headers {
+                from: SEGMENT_SIZE,
+                count: 3,
+                skip: (SEGMENT_SIZE - 1) as u32,
+            }
+        );
+        let anchors = headers
+            .iter()
+            .filter(|header| header.number() % SEGMENT_SIZE == 0)
+            .cloned()
+            .collect();
+        assert_eq!(skeleton.on_headers(requests[0].0, anchors), Response::Accepted);
+        assert_eq!(skeleton.target(), 3 * SEGMENT_SIZE);
+
+        // both peers fill a segment at once.
+        let requests = skeleton.schedule(0, &peers, now);
+        assert_eq!(requests.len(), 2);
+        for (node_hash, request) in requests {
+            match request {
+                Request::Headers {
+                    from,
+                    count,
+                    skip: 0,
+                } => {
+                    let segment = headers[(from - 1) as usize..(from - 1 + count as u64) as usize]
+                        .to_vec();
+                    assert_eq!(skeleton.on_headers(node_hash, segment), Response::Accepted);
+                }
+                request => panic!("unexpected request {:?}", request),
+            }
+        }
+
+        // a segment that does not end on its skeleton header is refused.
+        let requests = skeleton.schedule(0, &peers, now);
+        assert_eq!(requests.len(), 2);
+        let (filler, from) = requests
+            .iter()
+            .filter_map(|&(node_hash, ref request)| {
+                match *request {
+                    Request::Headers {
+                        from,
+                        ..
+                    } => Some((node_hash, from)),
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap();
+        assert_eq!(from, 2 * SEGMENT_SIZE + 1);
+        let segment = headers[(2 * SEGMENT_SIZE) as usize..].to_vec();
+        let mut forged = segment.clone();
+        forged.last_mut().unwrap().set_timestamp(1);
+        assert_eq!(skeleton.on_headers(filler, forged), Response::Rejected);
+        assert_eq!(skeleton.on_headers(filler, segment), Response::Unexpected);
+    }
+
+    #[test]
+    fn releases_blocks_in_order_and_reassigns_timeouts() {
+        let headers = chain(1, 16);
+        let peers = vec![peer(1, 16)];
+        let now = Instant::now();
+        let mut skeleton = SkeletonSync::new();
+
+        // close to the tip a single segment is requested directly.
+        let requests = skeleton.schedule(0, &peers, now);
+        assert_eq!(
+            requests,
+            vec![(
+                1,
+                Request::Headers {
+                    from: 1,
+                    count: 16,
+                    skip: 0,
+                },
+            )]
+        );
+        assert_eq!(skeleton.on_headers(1, headers.clone()), Response::Accepted);
+
+        let requests = skeleton.schedule(0, &peers, now);
+        assert_eq!(requests.len(), 1);
+        match requests[0].1 {
+            Request::Bodies {
+                from: 1,
+                ref hashes,
+            } => assert_eq!(hashes.len(), 16),
+            ref request => panic!("unexpected request {:?}", request),
+        }
+
+        // the peer never answers, the bodies go to the next peer.
+        let later = now + Duration::from_secs(REQUEST_TIMEOUT);
+        let peers = vec![peer(1, 16), peer(2, 16)];
+        assert_eq!(skeleton.expire(&peers, later), vec![1]);
+        assert!(skeleton.throughput(1) < INITIAL_THROUGHPUT);
+        let requests = skeleton.schedule(0, &peers, later);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0, 2);
+
+        assert_eq!(
+            skeleton.on_bodies(1, vec![Vec::new(); 16], later),
+            Response::Unexpected
+        );
+        assert_eq!(
+            skeleton.on_bodies(2, vec![Vec::new(); 16], later + Duration::from_millis(100)),
+            Response::Accepted
+        );
+        assert!(skeleton.throughput(2) > INITIAL_THROUGHPUT);
+
+        let ready = skeleton.drain_ready();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].blocks.len(), 16);
+        assert_eq!(skeleton.head(), 16);
+        assert_eq!(skeleton.target(), 0);
+    }
+
+    #[test]
+    fn requeues_bodies_missing_from_a_response() {
+        let mut headers: Vec<BlockHeader> = Vec::new();
+        for number in 1..17 {
+            let mut header = BlockHeader::new();
+            header.set_number(number);
+            if number == 5 {
+                header.set_transactions_root(H256::from(1));
+            }
+            if let Some(parent) = headers.last() {
+                header.set_parent_hash(parent.hash());
+            }
+            headers.push(header);
+        }
+        let peers = vec![peer(1, 16)];
+        let now = Instant::now();
+        let mut skeleton = SkeletonSync::new();
+
+        skeleton.schedule(0, &peers, now);
+        assert_eq!(skeleton.on_headers(1, headers.clone()), Response::Accepted);
+        skeleton.schedule(0, &peers, now);
+
+        // the peer skips the body of #5, which is the only one not empty.
+        assert_eq!(
+            skeleton.on_bodies(1, vec![Vec::new(); 15], now),
+            Response::Partial
+        );
+        let ready = skeleton.drain_ready();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(skeleton.head(), 4);
+
+        let requests = skeleton.schedule(0, &peers, now);
+        assert_eq!(
+            requests,
+            vec![(
+                1,
+                Request::Bodies {
+                    from: 5,
+                    hashes: vec![headers[4].hash()],
+                },
+            )]
+        );
+
+        // a body not matching the transactions root is refused.
+        assert_eq!(
+            skeleton.on_bodies(1, vec![Vec::new()], now),
+            Response::Rejected
+        );
+        let requests = skeleton.schedule(0, &peers, now);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(skeleton.drain_ready().len(), 0);
+    }
+}
@@ -0,0 +1,1108 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! State trie sync scheduler (fast sync).
+//!
+//! Instead of executing every block from genesis, a new node syncs the headers from genesis on
+//! their own, each checked by the engine against its ancestors, and picks among them a pivot block
+//! `PIVOT_DISTANCE` blocks behind its best peer. It downloads the pivot along with its parent, and
+//! fetches the state trie under the pivot's state root node by node: the account trie, then for
+//! every account its storage trie and code. Each node is checked against the hash it was asked by.
+//! A node is only handed over for writing once everything it refers to has been written, so a node
+//! found in the local database always comes with its whole subtree.
+//!
+//! Peers keep the state of recent blocks only. When the network moves too far past the pivot,
+//! a newer pivot is picked and its trie walked from the root again, fetching only the nodes not
+//! written yet; the state downloaded so far is healed into the one of the new pivot. Once the
+//! trie of the pivot is complete, the pivot becomes the best block, with the total difficulty
+//! summed up along the verified headers, and regular block import takes over.
+//!
+//! The scheduler only keeps book; sending the requests is left to the handlers.
+
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use acore::basic_account::BasicAccount;
+use acore::block::Block;
+use acore::client::BlockChainClient;
+use acore::header::{Header as BlockHeader, Seal};
+use acore::transaction::UnverifiedTransaction;
+use aion_types::{H256, U256};
+use blake2b::{blake2b, BLAKE2B_EMPTY, BLAKE2B_NULL_RLP};
+use rlp::{DecoderError, Encodable, UntrustedRlp};
+use triehash::ordered_trie_root;
+
+use super::light::{HeaderChain, MAX_HEADERS, REORG_OVERLAP};
+pub use super::skeleton::{PeerHead, Response};
+use super::skeleton::REQUEST_TIMEOUT;
+
+/// Blocks the pivot is picked behind the best peer.
+pub const PIVOT_DISTANCE: u64 = 16;
+/// Blocks the best peer may get past the pivot before a newer pivot is picked. Together with
+/// `PIVOT_DISTANCE` it has to stay below the state history kept by pruning peers.
+pub const PIVOT_STALENESS: u64 = 32;
+/// Max state trie nodes and codes asked from a peer at once.
+pub const MAX_STATE_DATA: usize = 384;
+
+/// A request the scheduler wants sent to a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    /// `count` consecutive headers on the way to the pivot, the first one being block `from`.
+    Headers { from: u64, count: u64 },
+    /// Bodies of the pivot parent and of the pivot.
+    Bodies { hashes: Vec<H256> },
+    /// State trie nodes and codes by hash.
+    StateData { hashes: Vec<H256> },
+}
+
+/// A state trie node or code, keyed by the hash of the address of the account owning it if
+/// any, and by its own hash.
+type Key = (Option<H256>, H256);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Account,
+    Storage,
+    Code,
+}
+
+#[derive(Clone, Debug)]
+struct Item {
+    kind: Kind,
+    address_hash: Option<H256>,
+    hash: H256,
+    // nibbles leading to an account trie node.
+    path: Vec<u8>,
+}
+
+impl Item {
+    fn key(&self) -> Key { (self.address_hash, self.hash) }
+}
+
+// a node waiting for the nodes it refers to before it can be written.
+struct Downloaded {
+    data: Vec<u8>,
+    pending: usize,
+    parents: Vec<Key>,
+}
+
+enum Task {
+    Headers { from: u64 },
+    Bodies(Vec<H256>),
+    StateData(Vec<Item>),
+}
+
+struct Assignment {
+    task: Task,
+    sent: Instant,
+}
+
+struct Pivot {
+    parent: BlockHeader,
+    header: BlockHeader,
+    total_difficulty: U256,
+    blocks: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+pub struct StateSync {
+    enabled: bool,
+    /// Headers synced from genesis, the pivot is picked among them.
+    chain: Option<HeaderChain>,
+    pivot: Option<Pivot>,
+    /// Nodes to request, the last one first so that subtrees complete early.
+    queue: Vec<Item>,
+    /// Nodes queued or requested, with the nodes waiting for them.
+    waiting: HashMap<Key, Vec<Key>>,
+    downloaded: HashMap<Key, Downloaded>,
+    assignments: HashMap<u64, Assignment>,
+    /// Peers which could not serve the state of the current pivot.
+    stale_peers: HashSet<u64>,
+    written: u64,
+}
+
+impl StateSync {
+    pub fn new() -> Self {
+        StateSync {
+            enabled: false,
+            chain: None,
+            pivot: None,
+            queue: Vec::new(),
+            waiting: HashMap::new(),
+            downloaded: HashMap::new(),
+            assignments: HashMap::new(),
+            stale_peers: HashSet::new(),
+            written: 0,
+        }
+    }
+
+    /// Start syncing headers from the genesis block up to a pivot block, and downloading the
+    /// state of the pivot.
+    pub fn start(&mut self, genesis: BlockHeader, total_difficulty: U256) {
+        self.enabled = true;
+        self.chain = Some(HeaderChain::new(genesis, total_difficulty));
+    }
+
+    /// Give up the state download, regular block sync takes over from the local best block.
+    pub fn stop(&mut self) {
+        self.enabled = false;
+        self.chain = None;
+        self.pivot = None;
+        self.clear();
+        self.assignments.clear();
+    }
+
+    /// Whether the state download is running; blocks are not imported meanwhile.
+    pub fn is_active(&self) -> bool { self.enabled }
+
+    /// Number of the best header synced, 0 if none.
+    pub fn best_header_number(&self) -> u64 {
+        self.chain.as_ref().map_or(0, |chain| chain.best_number())
+    }
+
+    /// Number of the current pivot block, 0 if none.
+    pub fn pivot_number(&self) -> u64 {
+        self.pivot
+            .as_ref()
+            .map_or(0, |pivot| pivot.header.number())
+    }
+
+    /// State trie nodes and codes handed over for writing so far.
+    pub fn written(&self) -> u64 { self.written }
+
+    /// Whether a request to the peer is outstanding.
+    pub fn is_busy(&self, node_hash: u64) -> bool { self.assignments.contains_key(&node_hash) }
+
+    /// Drop every outstanding request for peers gone or too slow to answer, putting their work
+    /// back in the queue. Returns the connected peers whose requests timed out.
+    pub fn expire(&mut self, peers: &[PeerHead], now: Instant) -> Vec<u64> {
+        let timeout = Duration::from_secs(REQUEST_TIMEOUT);
+        let mut gone = Vec::new();
+        let mut timed_out = Vec::new();
+        for (node_hash, assignment) in self.assignments.iter() {
+            if !peers.iter().any(|peer| peer.node_hash == *node_hash) {
+                gone.push(*node_hash);
+            } else if assignment.sent + timeout <= now {
+                timed_out.push(*node_hash);
+            }
+        }
+
+        for node_hash in gone {
+            self.release(node_hash);
+            self.stale_peers.remove(&node_hash);
+        }
+        for node_hash in timed_out.iter() {
+            self.release(*node_hash);
+        }
+        timed_out
+    }
+
+    /// Assign work to the idle peers, picking a newer pivot when the current one gets too old.
+    /// Headers are synced up to the pivot before it is picked. The download is given up when
+    /// the network is too short for a pivot.
+    pub fn schedule(&mut self, peers: &[PeerHead], now: Instant) -> Vec<(u64, Request)> {
+        let mut requests = Vec::new();
+        if !self.enabled {
+            return requests;
+        }
+        let best = match peers.iter().map(|peer| peer.best_block_num).max() {
+            Some(best) => best,
+            None => return requests,
+        };
+        let best_header = match self.chain {
+            Some(ref chain) => chain.best_number(),
+            None => return requests,
+        };
+        let mut idle: Vec<PeerHead> = peers
+            .iter()
+            .filter(|peer| !self.is_busy(peer.node_hash))
+            .cloned()
+            .collect();
+        idle.sort_by(|a, b| b.best_block_num.cmp(&a.best_block_num));
+
+        let needs_pivot = match self.pivot {
+            Some(ref pivot) => best >= pivot.header.number() + PIVOT_DISTANCE + PIVOT_STALENESS,
+            None => true,
+        };
+        if needs_pivot {
+            if self.pivot.is_none() && best <= PIVOT_DISTANCE + 1 {
+                debug!(target: "sync", "Network best block #{} too low for a state download.", best);
+                self.stop();
+                return requests;
+            }
+            let number = best - PIVOT_DISTANCE;
+            if best_header >= number {
+                self.pick_pivot(number);
+            } else if !self.is_headers_requested()
+                && !idle.is_empty()
+                && idle[0].best_block_num > best_header
+            {
+                let peer = idle.remove(0);
+                let from = best_header.saturating_sub(REORG_OVERLAP) + 1;
+                let count = cmp::min(MAX_HEADERS, number + 1 - from);
+                self.assign(
+                    peer.node_hash,
+                    Task::Headers {
+                        from: from,
+                    },
+                    now,
+                );
+                requests.push((
+                    peer.node_hash,
+                    Request::Headers {
+                        from: from,
+                        count: count,
+                    },
+                ));
+            }
+        }
+
+        let (number, hashes) = match self.pivot {
+            Some(ref pivot) => {
+                (
+                    pivot.header.number(),
+                    vec![pivot.parent.hash(), pivot.header.hash()],
+                )
+            }
+            None => return requests,
+        };
+        let has_blocks = self
+            .pivot
+            .as_ref()
+            .map_or(false, |pivot| pivot.blocks.is_some());
+        if !has_blocks && !self.is_bodies_requested() {
+            if let Some(index) = idle.iter().position(|peer| peer.best_block_num >= number) {
+                let peer = idle.remove(index);
+                self.assign(peer.node_hash, Task::Bodies(hashes.clone()), now);
+                requests.push((
+                    peer.node_hash,
+                    Request::Bodies {
+                        hashes: hashes,
+                    },
+                ));
+            }
+        }
+
+        for peer in idle {
+            if self.queue.is_empty() {
+                break;
+            }
+            if peer.best_block_num < number || self.stale_peers.contains(&peer.node_hash) {
+                continue;
+            }
+            let at = self.queue.len().saturating_sub(MAX_STATE_DATA);
+            let items = self.queue.split_off(at);
+            let hashes = items.iter().map(|item| item.hash).collect();
+            self.assign(peer.node_hash, Task::StateData(items), now);
+            requests.push((
+                peer.node_hash,
+                Request::StateData {
+                    hashes: hashes,
+                },
+            ));
+        }
+
+        requests
+    }
+
+    /// Hand over headers received from a peer. They have to link up to the headers synced so
+    /// far and pass the verification of `client`'s engine against their ancestors.
+    pub fn on_headers(
+        &mut self,
+        node_hash: u64,
+        headers: Vec<BlockHeader>,
+        client: &BlockChainClient,
+    ) -> Response
+    {
+        let assignment = match self.assignments.remove(&node_hash) {
+            Some(assignment) => assignment,
+            None => return Response::Unexpected,
+        };
+
+        match assignment.task {
+            Task::Headers {
+                from,
+            } => {
+                let fits = headers.first().map_or(false, |first| first.number() == from);
+                let inserted = fits && match self.chain {
+                    Some(ref mut chain) => chain.insert(headers, client),
+                    None => false,
+                };
+                if inserted {
+                    Response::Accepted
+                } else {
+                    Response::Rejected
+                }
+            }
+            task => {
+                self.assignments.insert(
+                    node_hash,
+                    Assignment {
+                        task: task,
+                        sent: assignment.sent,
+                    },
+                );
+                Response::Unexpected
+            }
+        }
+    }
+
+    /// Hand over the bodies of the pivot parent and of the pivot received from a peer.
+    pub fn on_bodies(
+        &mut self,
+        node_hash: u64,
+        bodies: Vec<Vec<UnverifiedTransaction>>,
+    ) -> Response
+    {
+        let assignment = match self.assignments.remove(&node_hash) {
+            Some(assignment) => assignment,
+            None => return Response::Unexpected,
+        };
+
+        match assignment.task {
+            Task::Bodies(hashes) => {
+                let pivot = match self.pivot {
+                    Some(ref mut pivot) => pivot,
+                    None => return Response::Accepted,
+                };
+                if hashes != vec![pivot.parent.hash(), pivot.header.hash()] {
+                    // the pivot moved on meanwhile.
+                    return Response::Accepted;
+                }
+                if bodies.len() != 2 {
+                    // the pivot bodies get asked again at the next schedule.
+                    return Response::Partial;
+                }
+
+                let mut blocks = Vec::new();
+                for (header, transactions) in vec![&pivot.parent, &pivot.header]
+                    .into_iter()
+                    .zip(bodies.into_iter())
+                {
+                    let transactions_root = ordered_trie_root(
+                        transactions
+                            .iter()
+                            .map(|transaction| transaction.rlp_bytes()),
+                    );
+                    if &transactions_root != header.transactions_root() {
+                        return Response::Rejected;
+                    }
+                    let block = Block {
+                        header: header.clone(),
+                        transactions: transactions,
+                    };
+                    blocks.push(block.rlp_bytes(Seal::With));
+                }
+                let block = blocks.pop();
+                let parent = blocks.pop();
+                if let (Some(parent), Some(block)) = (parent, block) {
+                    pivot.blocks = Some((parent, block));
+                }
+                Response::Accepted
+            }
+            task => {
+                self.assignments.insert(
+                    node_hash,
+                    Assignment {
+                        task: task,
+                        sent: assignment.sent,
+                    },
+                );
+                Response::Unexpected
+            }
+        }
+    }
+
+    /// Hand over state trie nodes and codes received from a peer, in any order. `has` tells
+    /// whether a node or code is written already, by the hash of its account's address and its
+    /// own hash. Returns the nodes and codes now ready to be written, along with the hash of
+    /// their account's address; nodes always come after everything they refer to.
+    pub fn on_state_data<F>(
+        &mut self,
+        node_hash: u64,
+        data: Vec<Vec<u8>>,
+        has: F,
+    ) -> (Response, Vec<(Option<H256>, Vec<u8>)>)
+    where F: Fn(Option<&H256>, &H256) -> bool {
+        let mut ready = Vec::new();
+        let assignment = match self.assignments.remove(&node_hash) {
+            Some(assignment) => assignment,
+            None => return (Response::Unexpected, ready),
+        };
+
+        let items = match assignment.task {
+            Task::StateData(items) => items,
+            task => {
+                self.assignments.insert(
+                    node_hash,
+                    Assignment {
+                        task: task,
+                        sent: assignment.sent,
+                    },
+                );
+                return (Response::Unexpected, ready);
+            }
+        };
+
+        let data: HashMap<H256, Vec<u8>> = data
+            .into_iter()
+            .filter(|value| !value.is_empty())
+            .map(|value| (blake2b(&value), value))
+            .collect();
+        let requested: HashSet<H256> = items.iter().map(|item| item.hash).collect();
+        let mut valid = data.keys().all(|hash| requested.contains(hash));
+        let mut answered = 0;
+        for item in items {
+            // requested for an older pivot.
+            if !self.waiting.contains_key(&item.key()) {
+                continue;
+            }
+            let value = match data.get(&item.hash) {
+                Some(value) => value.clone(),
+                None => {
+                    self.queue.push(item);
+                    continue;
+                }
+            };
+            let expanded = self.expand(&item, value, &has, &mut ready);
+            match expanded {
+                Ok(()) => answered += 1,
+                Err(e) => {
+                    debug!(target: "sync", "Invalid state data {}: {:?}", item.hash, e);
+                    valid = false;
+                    self.queue.push(item);
+                }
+            }
+        }
+
+        if !valid {
+            return (Response::Rejected, ready);
+        }
+        if answered == 0 {
+            // the peer has pruned the state of the pivot already.
+            self.stale_peers.insert(node_hash);
+        }
+        (Response::Accepted, ready)
+    }
+
+    /// Take the pivot header, the encoded pivot parent and pivot blocks, and the total
+    /// difficulty of the pivot, once the state of the pivot has been handed over entirely. The
+    /// state download is over from then on.
+    pub fn complete(&mut self) -> Option<(BlockHeader, Vec<u8>, Vec<u8>, U256)> {
+        if !self.enabled || !self.waiting.is_empty() {
+            return None;
+        }
+        let has_blocks = self
+            .pivot
+            .as_ref()
+            .map_or(false, |pivot| pivot.blocks.is_some());
+        if !has_blocks {
+            return None;
+        }
+
+        let pivot = self.pivot.take();
+        self.stop();
+        match pivot {
+            Some(Pivot {
+                header,
+                total_difficulty,
+                blocks: Some((parent, block)),
+                ..
+            }) => Some((header, parent, block, total_difficulty)),
+            _ => None,
+        }
+    }
+
+    fn is_headers_requested(&self) -> bool {
+        self.assignments.values().any(|assignment| {
+            match assignment.task {
+                Task::Headers {
+                    ..
+                } => true,
+                _ => false,
+            }
+        })
+    }
+
+    fn is_bodies_requested(&self) -> bool {
+        self.assignments.values().any(|assignment| {
+            match assignment.task {
+                Task::Bodies(_) => true,
+                _ => false,
+            }
+        })
+    }
+
+    // take the synced header with the given number, and its parent, as the new pivot.
+    fn pick_pivot(&mut self, number: u64) {
+        let pivot = self.chain.as_ref().and_then(|chain| {
+            match (
+                chain.header(number - 1),
+                chain.header(number),
+                chain.total_difficulty(number),
+            ) {
+                (Some(parent), Some(header), Some(total_difficulty)) => {
+                    Some((parent.clone(), header.clone(), total_difficulty))
+                }
+                _ => None,
+            }
+        });
+        match pivot {
+            Some((parent, header, total_difficulty)) => {
+                self.repivot(parent, header, total_difficulty)
+            }
+            None => warn!(target: "sync", "Header of pivot #{} not kept, pivot not moved.", number),
+        }
+    }
+
+    // walk the trie of a new pivot from its root, everything written already is kept.
+    fn repivot(&mut self, parent: BlockHeader, header: BlockHeader, total_difficulty: U256) {
+        debug!(target: "sync", "State sync pivot moved to #{} ({}).", header.number(), header.hash());
+        let root = *header.state_root();
+        self.clear();
+        self.waiting.insert((None, root), Vec::new());
+        self.queue.push(Item {
+            kind: Kind::Account,
+            address_hash: None,
+            hash: root,
+            path: Vec::new(),
+        });
+        self.pivot = Some(Pivot {
+            parent: parent,
+            header: header,
+            total_difficulty: total_difficulty,
+            blocks: None,
+        });
+    }
+
+    fn clear(&mut self) {
+        self.queue.clear();
+        self.waiting.clear();
+        self.downloaded.clear();
+        self.stale_peers.clear();
+    }
+
+    // queue the nodes a downloaded node refers to and are not written yet.
+    fn expand<F>(
+        &mut self,
+        item: &Item,
+        data: Vec<u8>,
+        has: &F,
+        ready: &mut Vec<(Option<H256>, Vec<u8>)>,
+    ) -> Result<(), DecoderError>
+    where F: Fn(Option<&H256>, &H256) -> bool {
+        let children = match item.kind {
+            Kind::Account => account_children(&item.path, &data)?,
+            Kind::Storage => {
+                trie_references(&data, &[])?
+                    .into_iter()
+                    .filter_map(|(_, reference)| {
+                        match reference {
+                            Reference::Node(hash) => {
+                                Some(Item {
+                                    kind: Kind::Storage,
+                                    address_hash: item.address_hash,
+                                    hash: hash,
+                                    path: Vec::new(),
+                                })
+                            }
+                            Reference::Value(_) => None,
+                        }
+                    })
+                    .collect()
+            }
+            Kind::Code => Vec::new(),
+        };
+
+        let key = item.key();
+        let parents = self.waiting.remove(&key).unwrap_or_else(Vec::new);
+        let mut pending = 0;
+        for child in children {
+            if has(child.address_hash.as_ref(), &child.hash) {
+                continue;
+            }
+            pending += 1;
+            let child_key = child.key();
+            if let Some(downloaded) = self.downloaded.get_mut(&child_key) {
+                downloaded.parents.push(key);
+                continue;
+            }
+            if let Some(waiting) = self.waiting.get_mut(&child_key) {
+                waiting.push(key);
+                continue;
+            }
+            self.waiting.insert(child_key, vec![key]);
+            self.queue.push(child);
+        }
+
+        self.downloaded.insert(
+            key,
+            Downloaded {
+                data: data,
+                pending: pending,
+                parents: parents,
+            },
+        );
+        if pending == 0 {
+            self.commit(key, ready);
+        }
+        Ok(())
+    }
+
+    // hand over a complete node, then every parent it completes in turn.
+    fn commit(&mut self, key: Key, ready: &mut Vec<(Option<H256>, Vec<u8>)>) {
+        let mut complete = vec![key];
+        while let Some(key) = complete.pop() {
+            let downloaded = match self.downloaded.remove(&key) {
+                Some(downloaded) => downloaded,
+                None => continue,
+            };
+            for parent in downloaded.parents.iter() {
+                if let Some(waiting) = self.downloaded.get_mut(parent) {
+                    waiting.pending -= 1;
+                    if waiting.pending == 0 {
+                        complete.push(*parent);
+                    }
+                }
+            }
+            ready.push((key.0, downloaded.data));
+            self.written += 1;
+        }
+    }
+
+    fn assign(&mut self, node_hash: u64, task: Task, now: Instant) {
+        self.assignments.insert(
+            node_hash,
+            Assignment {
+                task: task,
+                sent: now,
+            },
+        );
+    }
+
+    // put the work of the peer's outstanding request back in the queue.
+    fn release(&mut self, node_hash: u64) {
+        if let Some(assignment) = self.assignments.remove(&node_hash) {
+            if let Task::StateData(items) = assignment.task {
+                for item in items {
+                    if self.waiting.contains_key(&item.key()) {
+                        self.queue.push(item);
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum Reference {
+    Node(H256),
+    Value(Vec<u8>),
+}
+
+// nodes and code an account trie node refers to, including the storage root and code of the
+// accounts in its leaves.
+fn account_children(path: &[u8], data: &[u8]) -> Result<Vec<Item>, DecoderError> {
+    let mut children = Vec::new();
+    for (path, reference) in trie_references(data, path)? {
+        match reference {
+            Reference::Node(hash) => {
+                children.push(Item {
+                    kind: Kind::Account,
+                    address_hash: None,
+                    hash: hash,
+                    path: path,
+                })
+            }
+            Reference::Value(value) => {
+                // account trie keys are the hashes of the addresses.
+                if path.len() != 64 {
+                    return Err(DecoderError::Custom("account leaf not at full depth"));
+                }
+                let address_hash: Vec<u8> = path
+                    .chunks(2)
+                    .map(|pair| (pair[0] << 4) | pair[1])
+                    .collect();
+                let address_hash = H256::from(address_hash.as_slice());
+                let account: BasicAccount = UntrustedRlp::new(&value).as_val()?;
+                if account.storage_root != BLAKE2B_NULL_RLP {
+                    children.push(Item {
+                        kind: Kind::Storage,
+                        address_hash: Some(address_hash),
+                        hash: account.storage_root,
+                        path: Vec::new(),
+                    });
+                }
+                if account.code_hash != BLAKE2B_EMPTY {
+                    children.push(Item {
+                        kind: Kind::Code,
+                        address_hash: Some(address_hash),
+                        hash: account.code_hash,
+                        path: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(children)
+}
+
+// nodes and leaf values a trie node refers to, with their paths in nibbles. Nodes shorter than
+// a hash are inlined in their parent and walked right away.
+fn trie_references(data: &[u8], path: &[u8]) -> Result<Vec<(Vec<u8>, Reference)>, DecoderError> {
+    let mut references = Vec::new();
+    walk_node(&UntrustedRlp::new(data), path.to_vec(), &mut references)?;
+    Ok(references)
+}
+
+fn walk_node(
+    node: &UntrustedRlp,
+    path: Vec<u8>,
+    references: &mut Vec<(Vec<u8>, Reference)>,
+) -> Result<(), DecoderError>
+{
+    match node.item_count()? {
+        0 => {}
+        2 => {
+            // hex prefix encoded partial path: flags in the high nibble of the first byte, its
+            // low nibble is part of the path when odd.
+            let encoded = node.at(0)?.data()?;
+            if encoded.is_empty() {
+                return Err(DecoderError::RlpIsTooShort);
+            }
+            let mut path = path;
+            if encoded[0] & 0x10 == 0x10 {
+                path.push(encoded[0] & 0x0f);
+            }
+            for byte in encoded[1..].iter() {
+                path.push(byte >> 4);
+                path.push(byte & 0x0f);
+            }
+            if encoded[0] & 0x20 == 0x20 {
+                references.push((path, Reference::Value(node.at(1)?.data()?.to_vec())));
+            } else {
+                walk_reference(&node.at(1)?, path, references)?;
+            }
+        }
+        17 => {
+            for nibble in 0..16 {
+                let mut child_path = path.clone();
+                child_path.push(nibble as u8);
+                walk_reference(&node.at(nibble)?, child_path, references)?;
+            }
+        }
+        _ => return Err(DecoderError::RlpIncorrectListLen),
+    }
+    Ok(())
+}
+
+fn walk_reference(
+    reference: &UntrustedRlp,
+    path: Vec<u8>,
+    references: &mut Vec<(Vec<u8>, Reference)>,
+) -> Result<(), DecoderError>
+{
+    if reference.is_list() {
+        return walk_node(reference, path, references);
+    }
+    let hash = reference.data()?;
+    match hash.len() {
+        0 => {}
+        32 => references.push((path, Reference::Node(H256::from(hash)))),
+        _ => return Err(DecoderError::RlpInvalidLength),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use acore::client::TestBlockChainClient;
+    use rlp::RlpStream;
+
+    const BEST: u64 = 100;
+
+    fn peer(node_hash: u64, best_block_num: u64) -> PeerHead {
+        PeerHead {
+            node_hash: node_hash,
+            best_block_num: best_block_num,
+        }
+    }
+
+    fn nibbles(hash: &H256) -> Vec<u8> {
+        hash.iter()
+            .flat_map(|byte| vec![byte >> 4, byte & 0x0f])
+            .collect()
+    }
+
+    fn leaf(nibbles: &[u8], value: Vec<u8>) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        let even = if nibbles.len() % 2 == 1 {
+            encoded.push(0x30 | nibbles[0]);
+            &nibbles[1..]
+        } else {
+            encoded.push(0x20);
+            nibbles
+        };
+        for pair in even.chunks(2) {
+            encoded.push((pair[0] << 4) | pair[1]);
+        }
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&encoded);
+        stream.append(&value);
+        stream.out()
+    }
+
+    fn branch(children: &[(usize, H256)]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(17);
+        for nibble in 0..17 {
+            match children.iter().find(|&&(at, _)| at == nibble) {
+                Some(&(_, ref hash)) => {
+                    stream.append(hash);
+                }
+                None => {
+                    stream.append_empty_data();
+                }
+            }
+        }
+        stream.out()
+    }
+
+    fn account(balance: u64, storage_root: H256, code_hash: H256) -> Vec<u8> {
+        ::rlp::encode(&BasicAccount {
+            nonce: U256::zero(),
+            balance: U256::from(balance),
+            storage_root: storage_root,
+            code_hash: code_hash,
+        })
+        .into_vec()
+    }
+
+    // a state with a contract holding storage and code, and a plain account holding `balance`.
+    fn state(balance: u64) -> (H256, HashMap<H256, Vec<u8>>) {
+        let mut nodes = Vec::new();
+        let storage = leaf(&nibbles(&H256::from(7)), vec![1, 2, 3]);
+        let code = b"contract code".to_vec();
+        let contract = H256::from_slice(&[0x11; 32]);
+        let contract_leaf = leaf(
+            &nibbles(&contract)[1..],
+            account(0, blake2b(&storage), blake2b(&code)),
+        );
+        let plain = H256::from_slice(&[0x22; 32]);
+        let plain_leaf = leaf(
+            &nibbles(&plain)[1..],
+            account(balance, BLAKE2B_NULL_RLP, BLAKE2B_EMPTY),
+        );
+        let root = branch(&[(1, blake2b(&contract_leaf)), (2, blake2b(&plain_leaf))]);
+        let root_hash = blake2b(&root);
+        nodes.push(storage);
+        nodes.push(code);
+        nodes.push(contract_leaf);
+        nodes.push(plain_leaf);
+        nodes.push(root);
+        (
+            root_hash,
+            nodes
+                .into_iter()
+                .map(|node| (blake2b(&node), node))
+                .collect(),
+        )
+    }
+
+    fn genesis() -> BlockHeader {
+        let mut header = BlockHeader::new();
+        header.set_difficulty(U256::from(1));
+        header
+    }
+
+    // headers 1 to `count` on top of the genesis, with the given state roots.
+    fn chain(count: u64, state_roots: &[(u64, H256)]) -> Vec<BlockHeader> {
+        let mut headers: Vec<BlockHeader> = Vec::new();
+        for number in 1..count + 1 {
+            let mut header = BlockHeader::new();
+            header.set_number(number);
+            header.set_parent_hash(headers.last().map_or(genesis().hash(), |parent| parent.hash()));
+            header.set_difficulty(U256::from(1));
+            if let Some(&(_, root)) = state_roots.iter().find(|&&(at, _)| at == number) {
+                header.set_state_root(root);
+            }
+            headers.push(header);
+        }
+        headers
+    }
+
+    // serve every bodies and state data request until the scheduler stops asking. Returns the
+    // state data requested and the hashes written, in order.
+    fn serve(
+        state_sync: &mut StateSync,
+        peers: &[PeerHead],
+        server: &HashMap<H256, Vec<u8>>,
+        local: &mut HashMap<Key, Vec<u8>>,
+    ) -> (Vec<H256>, Vec<H256>)
+    {
+        let mut requested = Vec::new();
+        let mut written = Vec::new();
+        loop {
+            let requests = state_sync.schedule(peers, Instant::now());
+            if requests.is_empty() {
+                break;
+            }
+            for (node_hash, request) in requests {
+                match request {
+                    Request::Bodies {
+                        ..
+                    } => {
+                        assert_eq!(
+                            state_sync.on_bodies(node_hash, vec![Vec::new(), Vec::new()]),
+                            Response::Accepted
+                        );
+                    }
+                    Request::StateData {
+                        hashes,
+                    } => {
+                        let data = hashes
+                            .iter()
+                            .filter_map(|hash| server.get(hash).cloned())
+                            .collect();
+                        requested.extend(hashes);
+                        let (response, ready) = state_sync.on_state_data(
+                            node_hash,
+                            data,
+                            |address_hash: Option<&H256>, hash: &H256| {
+                                local.contains_key(&(address_hash.cloned(), *hash))
+                            },
+                        );
+                        assert_eq!(response, Response::Accepted);
+                        for (address_hash, value) in ready {
+                            let hash = blake2b(&value);
+                            written.push(hash);
+                            local.insert((address_hash, hash), value);
+                        }
+                    }
+                    request => panic!("unexpected request {:?}", request),
+                }
+            }
+        }
+        (requested, written)
+    }
+
+    #[test]
+    fn downloads_state_of_pivot_bottom_up() {
+        let (root, server) = state(1);
+        let client = TestBlockChainClient::new();
+        let peers = vec![peer(1, BEST), peer(2, BEST)];
+        let mut local = HashMap::new();
+        let mut state_sync = StateSync::new();
+        state_sync.start(genesis(), U256::from(1));
+
+        // headers are synced up to the pivot first.
+        let pivot = BEST - PIVOT_DISTANCE;
+        let requests = state_sync.schedule(&peers, Instant::now());
+        assert_eq!(
+            requests,
+            vec![(
+                1,
+                Request::Headers {
+                    from: 1,
+                    count: pivot,
+                },
+            )]
+        );
+        let headers = chain(pivot, &[(pivot, root)]);
+        let mut forged = headers.clone();
+        forged[10].set_parent_hash(H256::from(1));
+        assert_eq!(state_sync.on_headers(1, forged, &client), Response::Rejected);
+        assert_eq!(state_sync.best_header_number(), 0);
+        let requests = state_sync.schedule(&peers, Instant::now());
+        assert_eq!(
+            state_sync.on_headers(requests[0].0, headers, &client),
+            Response::Accepted
+        );
+        assert_eq!(state_sync.best_header_number(), pivot);
+
+        let (_, written) = serve(&mut state_sync, &peers, &server, &mut local);
+        assert_eq!(state_sync.pivot_number(), pivot);
+        assert_eq!(local.len(), server.len());
+        assert_eq!(state_sync.written(), server.len() as u64);
+        // a node is written only after everything it refers to.
+        assert_eq!(written.last(), Some(&root));
+
+        let (header, _, _, total_difficulty) = state_sync.complete().unwrap();
+        assert_eq!(*header.state_root(), root);
+        // summed up from the genesis along the synced headers.
+        assert_eq!(total_difficulty, U256::from(pivot + 1));
+        assert!(!state_sync.is_active());
+    }
+
+    #[test]
+    fn heals_state_on_newer_pivot() {
+        let (old_root, old_server) = state(1);
+        let (new_root, new_server) = state(2);
+        let client = TestBlockChainClient::new();
+        let mut local = HashMap::new();
+        let mut state_sync = StateSync::new();
+        state_sync.start(genesis(), U256::from(1));
+
+        let best = BEST + PIVOT_DISTANCE + PIVOT_STALENESS;
+        let old_pivot = BEST - PIVOT_DISTANCE;
+        let new_pivot = best - PIVOT_DISTANCE;
+        let headers = chain(new_pivot, &[(old_pivot, old_root), (new_pivot, new_root)]);
+
+        let peers = vec![peer(1, BEST)];
+        state_sync.schedule(&peers, Instant::now());
+        let first = headers[..old_pivot as usize].to_vec();
+        assert_eq!(state_sync.on_headers(1, first, &client), Response::Accepted);
+        serve(&mut state_sync, &peers, &old_server, &mut local);
+        assert_eq!(state_sync.pivot_number(), old_pivot);
+
+        // the network moves on before the pivot is handed over; headers are synced on from a
+        // few blocks below the best one.
+        let peers = vec![peer(1, best)];
+        let from = old_pivot - REORG_OVERLAP + 1;
+        let requests = state_sync.schedule(&peers, Instant::now());
+        assert_eq!(
+            requests,
+            vec![(
+                1,
+                Request::Headers {
+                    from: from,
+                    count: new_pivot + 1 - from,
+                },
+            )]
+        );
+        let next = headers[from as usize - 1..].to_vec();
+        assert_eq!(state_sync.on_headers(1, next, &client), Response::Accepted);
+
+        // only the nodes changed on the way to the plain account are fetched again.
+        let (requested, _) = serve(&mut state_sync, &peers, &new_server, &mut local);
+        let changed: HashSet<H256> = new_server
+            .keys()
+            .filter(|hash| !old_server.contains_key(hash))
+            .cloned()
+            .collect();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(requested.into_iter().collect::<HashSet<H256>>(), changed);
+
+        assert_eq!(state_sync.pivot_number(), new_pivot);
+        let (header, _, _, _) = state_sync.complete().unwrap();
+        assert_eq!(*header.state_root(), new_root);
+    }
+}
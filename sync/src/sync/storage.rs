@@ -21,37 +21,26 @@
 
 use acore::block::Block;
 use acore::client::{BlockChainClient, BlockChainInfo, BlockQueueInfo};
-use acore::header::Header as BlockHeader;
+use acore::transaction::transaction_queue::QueueEvent;
 use aion_types::{H256, U256};
 use lru_cache::LruCache;
-use state::Storage;
 use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::SystemTime;
 use tokio::runtime::{Runtime, TaskExecutor};
 
-lazy_static! {
-    static ref BLOCK_CHAIN: Storage<RwLock<BlockChain>> = Storage::new();
-    static ref SYNC_EXECUTORS: Storage<RwLock<SyncExecutor>> = Storage::new();
-    static ref LOCAL_STATUS: Storage<RwLock<LocalStatus>> = Storage::new();
-    static ref NETWORK_STATUS: Storage<RwLock<NetworkStatus>> = Storage::new();
-    static ref DOWNLOADED_HEADERS: Storage<Mutex<VecDeque<HeadersWrapper>>> = Storage::new();
-    static ref HEADERS_WITH_BODIES_REQUESTED: Storage<Mutex<HashMap<u64, HeadersWrapper>>> =
-        Storage::new();
-    static ref DOWNLOADED_BLOCKS: Storage<Mutex<VecDeque<BlocksWrapper>>> = Storage::new();
-    static ref REQUESTED_BLOCK_HASHES: Storage<Mutex<LruCache<H256, SystemTime>>> = Storage::new();
-    static ref IMPORTED_BLOCK_HASHES: Storage<Mutex<LruCache<H256, u8>>> = Storage::new();
-    static ref SENT_TRANSACTION_HASHES: Storage<Mutex<LruCache<H256, u8>>> = Storage::new();
-    static ref RECEIVED_TRANSACTIONS: Storage<Mutex<VecDeque<Vec<u8>>>> = Storage::new();
-    static ref STAGED_BLOCKS: Storage<Mutex<LruCache<H256, Vec<Vec<u8>>>>> = Storage::new();
-    static ref STAGED_BLOCK_HASHES: Storage<Mutex<LruCache<H256, u8>>> = Storage::new();
-}
+use super::compact::{short_id, CompactBlock, ShortId};
+use super::light::{LightServer, LightSync};
+use super::skeleton::SkeletonSync;
+use super::state_sync::StateSync;
+use super::transaction_requests::TransactionRequests;
 
-pub const MAX_DOWNLOADED_HEADERS_COUNT: usize = 4096;
 const MAX_CACHED_BLOCK_HASHES: usize = 128;
 const MAX_CACHED_TRANSACTION_HASHES: usize = 20480;
 const MAX_RECEIVED_TRANSACTIONS_COUNT: usize = 20480;
+const MAX_ANNOUNCED_TRANSACTIONS_COUNT: usize = 4096;
+const MAX_PENDING_COMPACT_BLOCKS: usize = 16;
 
 #[derive(Clone)]
 struct BlockChain {
@@ -63,68 +52,64 @@ struct SyncExecutor {
     inner: Option<Arc<Runtime>>,
 }
 
-pub struct SyncStorage;
+/// Sync state of a node, shared by the handlers of its messages.
+pub struct SyncStorage {
+    block_chain: RwLock<BlockChain>,
+    sync_executor: RwLock<SyncExecutor>,
+    local_status: RwLock<LocalStatus>,
+    network_status: RwLock<NetworkStatus>,
+    skeleton: Mutex<SkeletonSync>,
+    state_sync: Mutex<StateSync>,
+    light_sync: Mutex<LightSync>,
+    light_server: Mutex<LightServer>,
+    requested_block_hashes: Mutex<LruCache<H256, SystemTime>>,
+    imported_block_hashes: Mutex<LruCache<H256, u8>>,
+    sent_transaction_hashes: Mutex<LruCache<H256, u8>>,
+    received_transactions: Mutex<VecDeque<Vec<u8>>>,
+    announced_transactions: Mutex<LruCache<H256, Vec<u8>>>,
+    pending_compact_blocks: Mutex<LruCache<H256, CompactBlock>>,
+    queued_short_ids: Mutex<HashMap<ShortId, H256>>,
+    transaction_requests: Mutex<TransactionRequests>,
+}
 
 impl SyncStorage {
-    pub fn init(client: Arc<BlockChainClient>) {
-        if let Some(_) = BLOCK_CHAIN.try_get() {
-            if let Ok(mut block_chain) = BLOCK_CHAIN.get().write() {
-                if let Some(_) = block_chain.inner {
-                } else {
-                    block_chain.inner = Some(client);
-                }
-            }
-            if let Ok(mut sync_executor) = SYNC_EXECUTORS.get().write() {
-                if let Some(_) = sync_executor.inner {
-                } else {
-                    sync_executor.inner = Some(Arc::new(Runtime::new().expect("Tokio Runtime")));
-                }
-            }
-        } else {
-            let synced_block_number = client.chain_info().best_block_number;
-
-            let block_chain = BlockChain {
-                inner: Some(client),
-            };
-
-            let sync_executor = SyncExecutor {
-                inner: Some(Arc::new(Runtime::new().expect("Tokio Runtime"))),
-            };
-
-            let mut local_status = LocalStatus::new();
-            let mut network_status = NetworkStatus::new();
-            let mut downloaded_headers = VecDeque::new();
-            let mut headers_with_bodies_requested = HashMap::new();
-            let mut downloaded_blocks = VecDeque::new();
-            let mut requested_block_hashes = LruCache::new(MAX_CACHED_BLOCK_HASHES);
-            let mut imported_block_hashes = LruCache::new(MAX_CACHED_BLOCK_HASHES);
-            let mut sent_transaction_hases = LruCache::new(MAX_CACHED_TRANSACTION_HASHES);
-            let mut received_transactions = VecDeque::new();
-            let mut staged_blocks = LruCache::new(MAX_CACHED_BLOCK_HASHES);
-            let mut staged_block_hashes = LruCache::new(MAX_CACHED_BLOCK_HASHES);
+    pub fn new(client: Arc<BlockChainClient>) -> SyncStorage {
+        let synced_block_number = client.chain_info().best_block_number;
 
-            local_status.synced_block_number = synced_block_number;
-            local_status.synced_block_number_last_time = synced_block_number;
-            LOCAL_STATUS.set(RwLock::new(local_status));
-            NETWORK_STATUS.set(RwLock::new(network_status));
-            DOWNLOADED_HEADERS.set(Mutex::new(downloaded_headers));
-            HEADERS_WITH_BODIES_REQUESTED.set(Mutex::new(headers_with_bodies_requested));
-            DOWNLOADED_BLOCKS.set(Mutex::new(downloaded_blocks));
-            REQUESTED_BLOCK_HASHES.set(Mutex::new(requested_block_hashes));
-            IMPORTED_BLOCK_HASHES.set(Mutex::new(imported_block_hashes));
-            SENT_TRANSACTION_HASHES.set(Mutex::new(sent_transaction_hases));
-            RECEIVED_TRANSACTIONS.set(Mutex::new(received_transactions));
-            STAGED_BLOCKS.set(Mutex::new(staged_blocks));
-            STAGED_BLOCK_HASHES.set(Mutex::new(staged_block_hashes));
-
-            BLOCK_CHAIN.set(RwLock::new(block_chain));
-            SYNC_EXECUTORS.set(RwLock::new(sync_executor));
-        }
-    }
+        let block_chain = BlockChain {
+            inner: Some(client),
+        };
+
+        let sync_executor = SyncExecutor {
+            inner: Some(Arc::new(Runtime::new().expect("Tokio Runtime"))),
+        };
 
-    pub fn get_block_chain() -> Arc<BlockChainClient> {
-        BLOCK_CHAIN
-            .get()
+        let mut local_status = LocalStatus::new();
+        local_status.synced_block_number = synced_block_number;
+        local_status.synced_block_number_last_time = synced_block_number;
+
+        SyncStorage {
+            block_chain: RwLock::new(block_chain),
+            sync_executor: RwLock::new(sync_executor),
+            local_status: RwLock::new(local_status),
+            network_status: RwLock::new(NetworkStatus::new()),
+            skeleton: Mutex::new(SkeletonSync::new()),
+            state_sync: Mutex::new(StateSync::new()),
+            light_sync: Mutex::new(LightSync::new()),
+            light_server: Mutex::new(LightServer::new()),
+            requested_block_hashes: Mutex::new(LruCache::new(MAX_CACHED_BLOCK_HASHES)),
+            imported_block_hashes: Mutex::new(LruCache::new(MAX_CACHED_BLOCK_HASHES)),
+            sent_transaction_hashes: Mutex::new(LruCache::new(MAX_CACHED_TRANSACTION_HASHES)),
+            received_transactions: Mutex::new(VecDeque::new()),
+            announced_transactions: Mutex::new(LruCache::new(MAX_ANNOUNCED_TRANSACTIONS_COUNT)),
+            pending_compact_blocks: Mutex::new(LruCache::new(MAX_PENDING_COMPACT_BLOCKS)),
+            queued_short_ids: Mutex::new(HashMap::new()),
+            transaction_requests: Mutex::new(TransactionRequests::new()),
+        }
+    }
+
+    pub fn get_block_chain(&self) -> Arc<BlockChainClient> {
+        self.block_chain
             .read()
             .expect("get_block_chain")
             .clone()
@@ -132,9 +117,8 @@ impl SyncStorage {
             .expect("get_client")
     }
 
-    pub fn get_chain_info() -> BlockChainInfo {
-        let client = BLOCK_CHAIN
-            .get()
+    pub fn get_chain_info(&self) -> BlockChainInfo {
+        let client = self.block_chain
             .read()
             .expect("get_chain_info")
             .clone()
@@ -143,9 +127,8 @@ impl SyncStorage {
         client.chain_info()
     }
 
-    pub fn get_executor() -> TaskExecutor {
-        let rt = SYNC_EXECUTORS
-            .get()
+    pub fn get_executor(&self) -> TaskExecutor {
+        let rt = self.sync_executor
             .read()
             .expect("get_executor")
             .clone()
@@ -154,176 +137,84 @@ impl SyncStorage {
         rt.executor()
     }
 
-    pub fn set_synced_block_number(synced_block_number: u64) {
-        if let Ok(mut local_status) = LOCAL_STATUS.get().write() {
+    pub fn set_synced_block_number(&self, synced_block_number: u64) {
+        if let Ok(mut local_status) = self.local_status.write() {
             local_status.synced_block_number = synced_block_number;
         }
     }
 
-    pub fn get_synced_block_number() -> u64 {
-        if let Ok(local_status) = LOCAL_STATUS.get().read() {
+    pub fn get_synced_block_number(&self) -> u64 {
+        if let Ok(local_status) = self.local_status.read() {
             return local_status.synced_block_number;
         }
         0
     }
 
-    pub fn set_synced_block_number_last_time(synced_block_number_last_time: u64) {
-        if let Ok(mut local_status) = LOCAL_STATUS.get().write() {
+    pub fn set_synced_block_number_last_time(&self, synced_block_number_last_time: u64) {
+        if let Ok(mut local_status) = self.local_status.write() {
             local_status.synced_block_number_last_time = synced_block_number_last_time;
         }
     }
 
-    pub fn get_synced_block_number_last_time() -> u64 {
-        if let Ok(local_status) = LOCAL_STATUS.get().read() {
+    pub fn get_synced_block_number_last_time(&self) -> u64 {
+        if let Ok(local_status) = self.local_status.read() {
             return local_status.synced_block_number_last_time;
         }
         0
     }
 
-    pub fn set_sync_speed(sync_speed: u16) {
-        if let Ok(mut local_status) = LOCAL_STATUS.get().write() {
+    pub fn set_sync_speed(&self, sync_speed: u16) {
+        if let Ok(mut local_status) = self.local_status.write() {
             local_status.sync_speed = sync_speed;
         }
     }
 
-    pub fn get_sync_speed() -> u16 {
-        if let Ok(local_status) = LOCAL_STATUS.get().read() {
+    pub fn get_sync_speed(&self) -> u16 {
+        if let Ok(local_status) = self.local_status.read() {
             return local_status.sync_speed;
         }
         0
     }
 
-    pub fn set_max_staged_block_number(max_staged_block_number: u64) {
-        if let Ok(mut local_status) = LOCAL_STATUS.get().write() {
-            local_status.max_staged_block_number = max_staged_block_number;
-        }
-    }
-
-    pub fn get_max_staged_block_number() -> u64 {
-        if let Ok(local_status) = LOCAL_STATUS.get().read() {
-            return local_status.max_staged_block_number;
-        }
-        0
-    }
-
-    pub fn get_downloaded_headers() -> &'static Mutex<VecDeque<HeadersWrapper>> {
-        DOWNLOADED_HEADERS.get()
-    }
-
-    pub fn clear_downloaded_headers() {
-        if let Ok(ref mut downloaded_headers) = DOWNLOADED_HEADERS.get().lock() {
-            downloaded_headers.clear();
-        }
-    }
-
-    pub fn get_headers_with_bodies_requested() -> &'static Mutex<HashMap<u64, HeadersWrapper>> {
-        HEADERS_WITH_BODIES_REQUESTED.get()
-    }
-
-    pub fn insert_headers_with_bodies_requested(hw: HeadersWrapper) {
-        if let Ok(ref mut headers_with_bodies_requested) =
-            HEADERS_WITH_BODIES_REQUESTED.get().lock()
-        {
-            headers_with_bodies_requested.insert(hw.node_hash, hw);
-        } else {
-            warn!(target: "sync", "headers_with_bodies_requested_mutex lock failed");
-        }
-    }
-
-    pub fn pick_headers_with_bodies_requested(node_hash: &u64) -> Option<HeadersWrapper> {
-        if let Ok(ref mut headers_with_bodies_requested) =
-            HEADERS_WITH_BODIES_REQUESTED.get().lock()
-        {
-            headers_with_bodies_requested.remove(node_hash)
-        } else {
-            warn!(target: "sync", "headers_with_bodies_requested_mutex lock failed");
-            None
-        }
-    }
-
-    pub fn clear_headers_with_bodies_requested() {
-        if let Ok(ref mut headers_with_bodies_requested) =
-            HEADERS_WITH_BODIES_REQUESTED.get().lock()
-        {
-            headers_with_bodies_requested.clear();
-        }
-    }
-
-    pub fn get_downloaded_blocks() -> &'static Mutex<VecDeque<BlocksWrapper>> {
-        DOWNLOADED_BLOCKS.get()
-    }
+    pub fn get_skeleton(&self) -> &Mutex<SkeletonSync> { &self.skeleton }
 
-    pub fn get_downloaded_blocks_count() -> usize {
-        if let Ok(downloaded_blocks) = DOWNLOADED_BLOCKS.get().lock() {
-            return downloaded_blocks.len();
-        } else {
-            0
-        }
-    }
+    pub fn get_state_sync(&self) -> &Mutex<StateSync> { &self.state_sync }
 
-    pub fn insert_downloaded_headers(hw: HeadersWrapper) {
-        let downloaded_headers_mutex = DOWNLOADED_HEADERS.get();
-        {
-            let mut lock = downloaded_headers_mutex.lock();
-            if let Ok(ref mut downloaded_headers) = lock {
-                if downloaded_headers.len() <= MAX_DOWNLOADED_HEADERS_COUNT {
-                    downloaded_headers.push_back(hw);
-                }
-            } else {
-                warn!(target: "sync", "downloaded_headers_mutex lock failed");
-            }
-        }
-    }
+    pub fn get_light_sync(&self) -> &Mutex<LightSync> { &self.light_sync }
 
-    pub fn insert_downloaded_blocks(bw: BlocksWrapper) {
-        let downloaded_blocks_mutex = DOWNLOADED_BLOCKS.get();
-        {
-            let mut lock = downloaded_blocks_mutex.lock();
-            if let Ok(ref mut downloaded_blocks) = lock {
-                downloaded_blocks.push_back(bw);
-            } else {
-                warn!(target: "sync", "downloaded_blocks_mutex lock failed");
-            }
-        }
-    }
-
-    pub fn clear_downloaded_blocks() {
-        if let Ok(ref mut downloaded_blocks) = DOWNLOADED_BLOCKS.get().lock() {
-            downloaded_blocks.clear();
-        }
-    }
+    pub fn get_light_server(&self) -> &Mutex<LightServer> { &self.light_server }
 
-    pub fn get_network_best_block_number() -> u64 {
-        if let Ok(network_status) = NETWORK_STATUS.get().read() {
+    pub fn get_network_best_block_number(&self) -> u64 {
+        if let Ok(network_status) = self.network_status.read() {
             return network_status.best_block_num;
         }
         0
     }
 
-    pub fn get_network_best_block_hash() -> H256 {
-        if let Ok(network_status) = NETWORK_STATUS.get().read() {
+    pub fn get_network_best_block_hash(&self) -> H256 {
+        if let Ok(network_status) = self.network_status.read() {
             return network_status.best_hash;
         }
         H256::from(0)
     }
 
-    pub fn get_network_total_diff() -> U256 {
-        if let Ok(network_status) = NETWORK_STATUS.get().read() {
+    pub fn get_network_total_diff(&self) -> U256 {
+        if let Ok(network_status) = self.network_status.read() {
             return network_status.total_diff;
         }
         U256::from(0)
     }
 
-    pub fn insert_requested_time(hash: H256) {
-        if let Ok(ref mut requested_block_hashes) = REQUESTED_BLOCK_HASHES.get().lock() {
+    pub fn insert_requested_time(&self, hash: H256) {
+        if let Ok(ref mut requested_block_hashes) = self.requested_block_hashes.lock() {
             if !requested_block_hashes.contains_key(&hash) {
                 requested_block_hashes.insert(hash, SystemTime::now());
             }
         }
     }
 
-    pub fn get_requested_time(hash: &H256) -> Option<SystemTime> {
-        if let Ok(ref mut requested_block_hashes) = REQUESTED_BLOCK_HASHES.get().lock() {
+    pub fn get_requested_time(&self, hash: &H256) -> Option<SystemTime> {
+        if let Ok(ref mut requested_block_hashes) = self.requested_block_hashes.lock() {
             if let Some(time) = requested_block_hashes.get_mut(hash) {
                 return Some(time.clone());
             }
@@ -331,18 +222,18 @@ impl SyncStorage {
         None
     }
 
-    pub fn clear_requested_blocks() {
-        if let Ok(ref mut requested_block_hashes) = REQUESTED_BLOCK_HASHES.get().lock() {
+    pub fn clear_requested_blocks(&self) {
+        if let Ok(ref mut requested_block_hashes) = self.requested_block_hashes.lock() {
             requested_block_hashes.clear();
         }
     }
 
-    pub fn get_imported_block_hashes() -> &'static Mutex<LruCache<H256, u8>> {
-        return IMPORTED_BLOCK_HASHES.get();
+    pub fn get_imported_block_hashes(&self) -> &Mutex<LruCache<H256, u8>> {
+        return &self.imported_block_hashes;
     }
 
-    pub fn remove_imported_block_hashes(hashes: Vec<H256>) {
-        if let Ok(ref mut imported_block_hashes) = IMPORTED_BLOCK_HASHES.get().lock() {
+    pub fn remove_imported_block_hashes(&self, hashes: Vec<H256>) {
+        if let Ok(ref mut imported_block_hashes) = self.imported_block_hashes.lock() {
             for hash in hashes.iter() {
                 imported_block_hashes.remove(&hash);
             }
@@ -351,14 +242,14 @@ impl SyncStorage {
         }
     }
 
-    pub fn clear_imported_block_hashes() {
-        if let Ok(ref mut imported_block_hashes) = IMPORTED_BLOCK_HASHES.get().lock() {
+    pub fn clear_imported_block_hashes(&self) {
+        if let Ok(ref mut imported_block_hashes) = self.imported_block_hashes.lock() {
             imported_block_hashes.clear();
         }
     }
 
-    pub fn insert_imported_block_hashes(imported: Vec<H256>) {
-        if let Ok(ref mut imported_block_hashes) = IMPORTED_BLOCK_HASHES.get().lock() {
+    pub fn insert_imported_block_hashes(&self, imported: Vec<H256>) {
+        if let Ok(ref mut imported_block_hashes) = self.imported_block_hashes.lock() {
             for hash in imported.iter() {
                 imported_block_hashes.insert(*hash, 0);
             }
@@ -367,8 +258,8 @@ impl SyncStorage {
         }
     }
 
-    pub fn is_imported_block_hash(hash: &H256) -> bool {
-        if let Ok(ref mut imported_block_hashes) = IMPORTED_BLOCK_HASHES.get().lock() {
+    pub fn is_imported_block_hash(&self, hash: &H256) -> bool {
+        if let Ok(ref mut imported_block_hashes) = self.imported_block_hashes.lock() {
             imported_block_hashes.contains_key(hash)
         } else {
             warn!(target: "sync", "imported_block_hashes_mutex lock failed");
@@ -376,17 +267,18 @@ impl SyncStorage {
         }
     }
 
-    pub fn get_sent_transaction_hashes() -> &'static Mutex<LruCache<H256, u8>> {
-        SENT_TRANSACTION_HASHES.get()
+    pub fn get_sent_transaction_hashes(&self) -> &Mutex<LruCache<H256, u8>> {
+        &self.sent_transaction_hashes
     }
 
     pub fn update_network_status(
+        &self,
         best_block_num: u64,
         best_hash: H256,
         target_total_difficulty: U256,
     )
     {
-        if let Ok(mut network_status) = NETWORK_STATUS.get().write() {
+        if let Ok(mut network_status) = self.network_status.write() {
             if target_total_difficulty > network_status.total_diff {
                 network_status.best_block_num = best_block_num;
                 network_status.best_hash = best_hash;
@@ -395,20 +287,20 @@ impl SyncStorage {
         }
     }
 
-    pub fn get_received_transactions() -> &'static Mutex<VecDeque<Vec<u8>>> {
-        RECEIVED_TRANSACTIONS.get()
+    pub fn get_received_transactions(&self) -> &Mutex<VecDeque<Vec<u8>>> {
+        &self.received_transactions
     }
 
-    pub fn get_received_transactions_count() -> usize {
-        if let Ok(received_transactions) = RECEIVED_TRANSACTIONS.get().lock() {
+    pub fn get_received_transactions_count(&self) -> usize {
+        if let Ok(received_transactions) = self.received_transactions.lock() {
             return received_transactions.len();
         } else {
             0
         }
     }
 
-    pub fn insert_received_transaction(transaction: Vec<u8>) {
-        let mut lock = RECEIVED_TRANSACTIONS.get().lock();
+    pub fn insert_received_transaction(&self, transaction: Vec<u8>) {
+        let mut lock = self.received_transactions.lock();
         if let Ok(ref mut received_transactions) = lock {
             if received_transactions.len() <= MAX_RECEIVED_TRANSACTIONS_COUNT {
                 received_transactions.push_back(transaction);
@@ -418,44 +310,50 @@ impl SyncStorage {
         }
     }
 
-    pub fn get_staged_blocks() -> &'static Mutex<LruCache<H256, Vec<Vec<u8>>>> {
-        STAGED_BLOCKS.get()
+    /// Transactions recently broadcasted or announced, kept to answer transaction requests.
+    pub fn get_announced_transactions(&self) -> &Mutex<LruCache<H256, Vec<u8>>> {
+        &self.announced_transactions
     }
 
-    pub fn insert_staged_block_hashes(hashes: Vec<H256>) {
-        if let Ok(mut staged_block_hashes) = STAGED_BLOCK_HASHES.get().lock() {
-            for hash in hashes.iter() {
-                staged_block_hashes.insert(*hash, 0);
-            }
-        }
+    /// Announced transactions being requested from the announcers.
+    pub fn get_transaction_requests(&self) -> &Mutex<TransactionRequests> {
+        &self.transaction_requests
     }
 
-    pub fn is_staged_block_hash(hash: H256) -> bool {
-        if let Ok(mut staged_block_hashes) = STAGED_BLOCK_HASHES.get().lock() {
-            return staged_block_hashes.contains_key(&hash);
-        }
-        return false;
+    /// Compact blocks waiting for the missing transactions requested from the sender.
+    pub fn get_pending_compact_blocks(&self) -> &Mutex<LruCache<H256, CompactBlock>> {
+        &self.pending_compact_blocks
     }
 
-    pub fn remove_staged_block_hash(hash: H256) {
-        if let Ok(mut staged_block_hashes) = STAGED_BLOCK_HASHES.get().lock() {
-            staged_block_hashes.remove(&hash);
-        }
+    /// Hashes of the queued transactions by short id, compact blocks are rebuilt from.
+    pub fn get_queued_short_ids(&self) -> &Mutex<HashMap<ShortId, H256>> {
+        &self.queued_short_ids
     }
 
-    pub fn clear_staged_blocks() {
-        if let Ok(mut staged_blocks) = STAGED_BLOCKS.get().lock() {
-            staged_blocks.clear();
-        }
-
-        if let Ok(mut staged_block_hashes) = STAGED_BLOCK_HASHES.get().lock() {
-            staged_block_hashes.clear();
+    /// Keep the short ids of the queued transactions in step with the transaction queue.
+    pub fn on_transaction_queue_event(&self, event: QueueEvent) {
+        if let Ok(mut queued_short_ids) = self.queued_short_ids.lock() {
+            match event {
+                QueueEvent::Added(transaction, _) => {
+                    let hash = transaction.hash();
+                    queued_short_ids.insert(short_id(&hash), hash);
+                }
+                QueueEvent::Removed(hash) => {
+                    let id = short_id(hash);
+                    // another transaction may have taken over the short id.
+                    if queued_short_ids.get(&id) == Some(hash) {
+                        queued_short_ids.remove(&id);
+                    }
+                }
+            }
+        } else {
+            warn!(target: "sync", "queued_short_ids_mutex lock failed");
         }
     }
 
-    pub fn reset() {
-        SYNC_EXECUTORS.get().write().expect("get_executor").inner = None;
-        BLOCK_CHAIN.get().write().expect("get_block_chain").inner = None;
+    pub fn reset(&self) {
+        self.sync_executor.write().expect("get_executor").inner = None;
+        self.block_chain.write().expect("get_block_chain").inner = None;
     }
 }
 
@@ -481,6 +379,8 @@ pub struct SyncStatus {
     pub num_peers: usize,
     /// Total number of active peers.
     pub num_active_peers: usize,
+    /// Blocks imported per second, averaged over the last sync statistics interval.
+    pub sync_speed: u16,
 }
 
 impl SyncStatus {
@@ -508,6 +408,8 @@ pub struct ActivePeerInfo {
     pub id: String,
     /// remote p2p ip
     pub ip: String,
+    /// reputation score
+    pub reputation: i32,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -524,7 +426,6 @@ pub struct LocalStatus {
     pub synced_block_number: u64,
     pub synced_block_number_last_time: u64,
     pub sync_speed: u16,
-    pub max_staged_block_number: u64,
 }
 
 impl LocalStatus {
@@ -533,7 +434,6 @@ impl LocalStatus {
             synced_block_number: 0,
             synced_block_number_last_time: 0,
             sync_speed: 48,
-            max_staged_block_number: 0,
         }
     }
 }
@@ -551,11 +451,6 @@ impl fmt::Display for LocalStatus {
             "    synced block number last time: {}\n",
             self.synced_block_number_last_time
         ));
-        try!(write!(
-            f,
-            "    max staged block number: {}\n",
-            self.max_staged_block_number
-        ));
         write!(f, "\n")
     }
 }
@@ -594,23 +489,6 @@ impl fmt::Display for NetworkStatus {
     }
 }
 
-#[derive(Clone, PartialEq)]
-pub struct HeadersWrapper {
-    pub node_hash: u64,
-    pub timestamp: SystemTime,
-    pub headers: Vec<BlockHeader>,
-}
-
-impl HeadersWrapper {
-    pub fn new() -> Self {
-        HeadersWrapper {
-            node_hash: 0,
-            timestamp: SystemTime::now(),
-            headers: Vec::new(),
-        }
-    }
-}
-
 #[derive(Clone, PartialEq)]
 pub struct BlocksWrapper {
     pub node_id_hash: u64,
@@ -0,0 +1,185 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+//! Fetching of announced transactions.
+//!
+//! Peers able to take announcements get the hashes of new transactions instead of their bodies.
+//! A hash not seen yet is asked from the first peer announcing it, the peers announcing it
+//! meanwhile are kept as fallbacks. A request not answered within `REQUEST_TIMEOUT` is sent
+//! again to the next of them, and given up once none is left, so that the hash gets asked for
+//! again on its next announcement.
+//!
+//! The scheduler only keeps book; sending the requests is left to the handlers.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use aion_types::H256;
+
+/// Seconds a peer is given to send the transactions asked before another announcer is asked.
+pub const REQUEST_TIMEOUT: u64 = 4;
+/// Max transactions being requested at once, further announcements are ignored.
+pub const MAX_REQUESTED: usize = 8192;
+// announcers kept as fallbacks for a transaction.
+const MAX_ANNOUNCERS: usize = 8;
+
+struct Requested {
+    /// Peer the transaction was asked from.
+    node_hash: u64,
+    sent: Instant,
+    /// Other peers which announced the transaction, asked in turn on timeout.
+    announcers: VecDeque<u64>,
+}
+
+pub struct TransactionRequests {
+    requested: HashMap<H256, Requested>,
+}
+
+impl TransactionRequests {
+    pub fn new() -> Self {
+        TransactionRequests {
+            requested: HashMap::new(),
+        }
+    }
+
+    /// Number of transactions being requested.
+    pub fn count(&self) -> usize { self.requested.len() }
+
+    /// Record the announcement of a transaction not seen yet. Returns true if it is to be
+    /// asked from the announcer now, false if another peer was asked already.
+    pub fn on_announced(&mut self, node_hash: u64, hash: H256, now: Instant) -> bool {
+        if let Some(requested) = self.requested.get_mut(&hash) {
+            if requested.node_hash != node_hash
+                && !requested.announcers.contains(&node_hash)
+                && requested.announcers.len() < MAX_ANNOUNCERS
+            {
+                requested.announcers.push_back(node_hash);
+            }
+            return false;
+        }
+        if self.requested.len() >= MAX_REQUESTED {
+            return false;
+        }
+        self.requested.insert(
+            hash,
+            Requested {
+                node_hash: node_hash,
+                sent: now,
+                announcers: VecDeque::new(),
+            },
+        );
+        true
+    }
+
+    /// Record a transaction received, by request or not. Returns whether it was requested.
+    pub fn on_received(&mut self, hash: &H256) -> bool { self.requested.remove(hash).is_some() }
+
+    /// Move the requests timed out to the next announcer, returns the hashes to ask for by
+    /// peer. Requests without any announcer left are given up.
+    pub fn expire(&mut self, now: Instant) -> Vec<(u64, Vec<H256>)> {
+        let timeout = Duration::from_secs(REQUEST_TIMEOUT);
+        let mut retries: BTreeMap<u64, Vec<H256>> = BTreeMap::new();
+        let mut given_up = Vec::new();
+        for (hash, requested) in self.requested.iter_mut() {
+            if now < requested.sent + timeout {
+                continue;
+            }
+            match requested.announcers.pop_front() {
+                Some(node_hash) => {
+                    requested.node_hash = node_hash;
+                    requested.sent = now;
+                    retries.entry(node_hash).or_insert_with(Vec::new).push(*hash);
+                }
+                None => given_up.push(*hash),
+            }
+        }
+        for hash in given_up {
+            trace!(target: "sync", "No announcer left for transaction {:?}, request given up.", hash);
+            self.requested.remove(&hash);
+        }
+        retries
+            .into_iter()
+            .map(|(node_hash, mut hashes)| {
+                hashes.sort();
+                (node_hash, hashes)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asks_first_announcer_only() {
+        let now = Instant::now();
+        let mut requests = TransactionRequests::new();
+        let hash = H256::from(1);
+        assert!(requests.on_announced(1, hash, now));
+        assert!(!requests.on_announced(2, hash, now));
+        assert!(!requests.on_announced(1, hash, now));
+        assert_eq!(requests.count(), 1);
+
+        assert!(requests.on_received(&hash));
+        assert!(!requests.on_received(&hash));
+        assert_eq!(requests.count(), 0);
+    }
+
+    #[test]
+    fn retries_timed_out_requests_with_other_announcers() {
+        let now = Instant::now();
+        let mut requests = TransactionRequests::new();
+        let (first, second) = (H256::from(1), H256::from(2));
+        requests.on_announced(1, first, now);
+        requests.on_announced(1, second, now);
+        requests.on_announced(2, first, now);
+        requests.on_announced(3, first, now);
+        requests.on_announced(3, second, now);
+
+        assert!(requests.expire(now).is_empty());
+        let later = now + Duration::from_secs(REQUEST_TIMEOUT);
+        assert_eq!(requests.expire(later), vec![(2, vec![first]), (3, vec![second])]);
+
+        // the next announcer in turn, then given up.
+        let later = later + Duration::from_secs(REQUEST_TIMEOUT);
+        assert_eq!(requests.expire(later), vec![(3, vec![first])]);
+        assert_eq!(requests.count(), 1);
+        let later = later + Duration::from_secs(REQUEST_TIMEOUT);
+        assert!(requests.expire(later).is_empty());
+        assert_eq!(requests.count(), 0);
+
+        // asked for again on the next announcement.
+        assert!(requests.on_announced(2, first, later));
+    }
+
+    #[test]
+    fn ignores_announcements_beyond_limit() {
+        let now = Instant::now();
+        let mut requests = TransactionRequests::new();
+        for i in 0..MAX_REQUESTED {
+            assert!(requests.on_announced(1, H256::from(i as u64 + 1), now));
+        }
+        assert!(!requests.on_announced(1, H256::from(0), now));
+        assert_eq!(requests.count(), MAX_REQUESTED);
+    }
+}
@@ -45,7 +45,7 @@ pub fn new_spec() -> Spec {
     )
 }
 
-fn new_db() -> Arc<KeyValueDB> {
+pub fn new_db() -> Arc<KeyValueDB> {
     let mut db_configs = Vec::new();
     for db_name in db::DB_NAMES.to_vec() {
         db_configs.push(db_name.into());
@@ -81,10 +81,10 @@ pub fn get_network_config() -> NetworkConfig {
     net_config
 }
 
-pub fn init_sync_storage() {
+pub fn init_sync_storage() -> SyncStorage {
     let spec = new_spec();
     let client = get_client(&spec);
-    SyncStorage::init(client.clone() as Arc<BlockChainClient>);
+    SyncStorage::new(client.clone() as Arc<BlockChainClient>)
 }
 
 pub fn get_client(spec: &Spec) -> Arc<Client> {
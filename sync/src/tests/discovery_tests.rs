@@ -0,0 +1,73 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+
+use discovery::{self, Discovery, DiscoveryService};
+use key::generate_keypair;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_discovery_on_localhost() {
+    let count = 4;
+    let mut nodes: Vec<(String, Arc<Mutex<Discovery>>, DiscoveryService)> = Vec::new();
+    for i in 0..count {
+        let node_id = format!("c33d1066-8c7e-496c-9c4e-{:012}", i);
+        let discovery = Arc::new(Mutex::new(Discovery::new(
+            &node_id,
+            generate_keypair(),
+            Instant::now(),
+        )));
+        if let Some(&(ref seed_id, _, ref seed_service)) = nodes.first() {
+            discovery
+                .lock()
+                .unwrap()
+                .add_seed(seed_id, seed_service.local_addr());
+        }
+        let service = discovery::start(discovery.clone(), &"127.0.0.1:0".parse().unwrap())
+            .expect("discovery started");
+        nodes.push((node_id, discovery, service));
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let all_found = || {
+        nodes
+            .iter()
+            .all(|&(_, ref discovery, _)| discovery.lock().unwrap().len() == count - 1)
+    };
+    while !all_found() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    for &(ref node_id, ref discovery, ref service) in nodes.iter() {
+        service.stop();
+        let found: Vec<String> = discovery
+            .lock()
+            .unwrap()
+            .nodes()
+            .into_iter()
+            .map(|entry| entry.node_id)
+            .collect();
+        assert_eq!(found.len(), count - 1, "node {} found {:?}", node_id, found);
+        assert!(!found.contains(node_id));
+    }
+}
@@ -20,5 +20,7 @@
  ******************************************************************************/
 
 mod common;
+mod discovery_tests;
+mod network_tests;
 mod p2p_tests;
 mod sync_tests;
@@ -19,9 +19,12 @@
  *
  ******************************************************************************/
 
+use bytes::BytesMut;
 use p2p::*;
+use std::sync::Arc;
 use std::{thread, time};
 use tokio::runtime::Runtime;
+use tokio_codec::{Decoder, Encoder};
 
 use super::common::*;
 
@@ -35,19 +38,20 @@ fn test_create_server() {
     let executor_handle = rt.executor();
     let net_config = get_network_config();
 
-    P2pMgr::enable(net_config);
+    let p2p = P2pMgr::new(net_config);
+    let handle: Handle = Arc::new(handle);
     let server_addr = String::from("127.0.0.1:30000");
-    P2pMgr::create_server(&executor_handle, &server_addr, handle);
+    p2p.create_server(&executor_handle, &server_addr, handle.clone());
     let peer_node = Node::new_with_addr(server_addr.parse().unwrap());
-    P2pMgr::create_client(peer_node, handle);
+    p2p.create_client(peer_node, handle);
     let mut value = server_addr;
-    let local_ip = P2pMgr::get_local_node().ip_addr.get_ip();
+    let local_ip = p2p.get_local_node().ip_addr.get_ip();
     value.push_str(&local_ip);
     let node_hash = P2pMgr::calculate_hash(&value);
 
-    if let Some(peer_node) = P2pMgr::get_node(node_hash) {
+    if let Some(peer_node) = p2p.get_node(node_hash) {
         let msg = ChannelBuffer::new();
-        P2pMgr::send(peer_node.node_hash, msg);
+        p2p.send(peer_node.node_hash, msg);
     }
     thread::sleep(time::Duration::from_millis(2000));
     rt.shutdown_now();
@@ -57,18 +61,18 @@ fn test_create_server() {
 fn test_connection() {
     let net_config = get_network_config();
 
-    P2pMgr::enable(net_config);
+    P2pMgr::new(net_config);
 }
 
 #[test]
 fn test_load_boot_nodes() {
     let net_config = get_network_config();
 
-    P2pMgr::enable(net_config);
+    let p2p = P2pMgr::new(net_config);
 
     let node_hash = 666;
     let address = "66.66.66.66:8888";
-    let mut node = P2pMgr::get_local_node().clone();
+    let mut node = p2p.get_local_node().clone();
     for _ in 0..10 {
         node.inc_repeated();
     }
@@ -76,10 +80,10 @@ fn test_load_boot_nodes() {
     assert_eq!(node.is_over_repeated_threshold(), true);
 
     node.node_hash = node_hash;
-    P2pMgr::add_node(node.clone());
+    p2p.add_node(node.clone());
     println!("node: {}", node);
 
-    let mut peer_node = P2pMgr::get_node(node_hash).unwrap();
+    let mut peer_node = p2p.get_node(node_hash).unwrap();
     peer_node.set_ip_addr(address.parse().unwrap());
     assert_eq!(peer_node.get_ip_addr(), "66.66.66.66:8888".to_string());
     assert_eq!(peer_node.ip_addr.port, 8888);
@@ -87,32 +91,101 @@ fn test_load_boot_nodes() {
     assert_eq!(peer_node.is_over_repeated_threshold(), true);
 
     node.reset_repeated();
-    P2pMgr::update_node(node_hash, &mut node);
-    peer_node = P2pMgr::get_node(node_hash).unwrap();
+    p2p.update_node(node_hash, &mut node);
+    peer_node = p2p.get_node(node_hash).unwrap();
     assert_eq!(peer_node.ip_addr.port, 30303);
     assert_eq!(peer_node.is_over_repeated_threshold(), false);
 }
 
+#[test]
+fn test_ban_by_reputation() {
+    let net_config = get_network_config();
+
+    let p2p = P2pMgr::new(net_config);
+
+    let node_hash = 777;
+    let mut node = Node::new_with_node_str(
+        "p2p://c33d2207-729a-4584-86f1-e19ab97cf9ce@77.77.77.77:30303".to_string(),
+    );
+    node.node_hash = node_hash;
+    p2p.add_node(node.clone());
+
+    assert!(p2p.rate_peer(node_hash, Behaviour::UsefulResponse));
+    assert!(p2p.rate_peer(node_hash, Behaviour::Timeout));
+    assert_eq!(p2p.get_node(node_hash).unwrap().reputation, -9);
+    assert!(p2p.rate_peer(node_hash, Behaviour::InvalidData));
+    assert!(!p2p.is_node_banned(&node.get_node_id()));
+
+    assert!(!p2p.rate_peer(node_hash, Behaviour::BadBlock));
+    assert!(p2p.get_node(node_hash).is_none());
+    assert!(p2p.is_node_banned(&node.get_node_id()));
+    assert!(p2p.is_ip_banned(&node.ip_addr.get_ip()));
+}
+
 #[test]
 fn test_nodes_tablet() {
     let net_config = get_network_config();
 
-    P2pMgr::enable(net_config);
+    let p2p = P2pMgr::new(net_config);
     for i in 0..66 {
         let mut peer_node = Node::new_with_addr(format!("10.1.1.{}:30303", i).parse().unwrap());
         peer_node.node_hash = P2pMgr::calculate_hash(&peer_node.get_ip_addr());
-        P2pMgr::add_node(peer_node);
+        p2p.add_node(peer_node);
     }
 
-    let peer_node_count = P2pMgr::get_all_nodes_count();
+    let peer_node_count = p2p.get_all_nodes_count();
     assert_eq!(peer_node_count, 64);
 
     let ip_addr = "10.1.1.22:30303".to_string();
     let node_hash = P2pMgr::calculate_hash(&ip_addr);
-    let peer_node = P2pMgr::remove_peer(node_hash).unwrap();
+    let peer_node = p2p.remove_peer(node_hash).unwrap();
 
-    let peer_node_count = P2pMgr::get_all_nodes_count();
+    let peer_node_count = p2p.get_all_nodes_count();
     assert_eq!(peer_node_count, 63);
     assert_eq!(peer_node.get_ip_addr(), ip_addr);
-    P2pMgr::reset();
+    p2p.reset();
+}
+
+#[test]
+fn test_codec_refuses_large_frames() {
+    let mut codec = P2pCodec::new(16);
+    let mut buf = BytesMut::new();
+    for size in [16, 17].iter() {
+        let mut msg = ChannelBuffer::new();
+        msg.body = vec![0; *size];
+        msg.head.len = *size as u32;
+        codec.encode(msg, &mut buf).unwrap();
+    }
+
+    assert_eq!(codec.decode(&mut buf).unwrap().unwrap().body.len(), 16);
+    assert!(codec.decode(&mut buf).is_err());
+}
+
+#[test]
+fn test_disconnect_over_limits() {
+    let mut net_config = get_network_config();
+    net_config.max_request_frame_size = 8;
+    net_config.request_burst = 2;
+    let p2p = P2pMgr::new(net_config);
+
+    let mut node = Node::new_with_node_str(
+        "p2p://c33d302f-216b-47d4-ac44-5d8181b56e7e@88.88.88.88:30303".to_string(),
+    );
+    node.node_hash = 888;
+    p2p.add_node(node.clone());
+
+    let mut req = ChannelBuffer::new();
+    req.head.set_control(Control::SYNC);
+    req.head.action = 2;
+    assert!(p2p.check_limits(&node, &req, true));
+    assert!(p2p.check_limits(&node, &req, true));
+    assert!(!p2p.check_limits(&node, &req, true));
+    assert!(p2p.get_node(node.node_hash).is_none());
+
+    p2p.add_node(node.clone());
+    req.body = vec![0; 16];
+    req.head.len = 16;
+    assert!(p2p.check_limits(&node, &req, false));
+    assert!(!p2p.check_limits(&node, &req, true));
+    assert!(p2p.get_node(node.node_hash).is_none());
 }
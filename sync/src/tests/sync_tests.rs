@@ -19,14 +19,23 @@
  *
  ******************************************************************************/
 
-use acore::client::{BlockChainClient, BlockId, ChainNotify};
-use aion_types::H256;
-use p2p::P2pMgr;
+use acore::client::{BlockChainClient, BlockId, ChainNotify, ProvingBlockChainClient};
+use acore::header::Header as BlockHeader;
+use acore::transaction::transaction_queue::QueueEvent;
+use acore::transaction::Transaction;
+use acore::spec::Spec;
+use aion_types::{Address, H256, U256};
+use blake2b::blake2b;
+use futures::sync::oneshot;
+use futures::Future;
+use rlp::{RlpStream, UntrustedRlp};
 use std::sync::Arc;
 use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use super::common::*;
+use sync::light::{self, LightRequest, LightResponse, LightSync};
+use sync::state_sync::{PeerHead, Request, Response, StateSync, PIVOT_DISTANCE};
 use sync::storage::SyncStorage;
 use sync::*;
 
@@ -42,13 +51,15 @@ fn benchtest_sync_mainnet() {
         config: sync_config,
         client: client.clone() as Arc<BlockChainClient>,
         network_config: net_config,
+        db: new_db(),
     });
 
-    let (sync_provider, network_manager, _chain_notify) = (
+    let (sync_provider, network_manager, chain_notify) = (
         sync.clone() as Arc<SyncProvider>,
         sync.clone() as Arc<NetworkManager>,
         sync.clone() as Arc<ChainNotify>,
     );
+    client.add_notify(chain_notify);
 
     let start_time = SystemTime::now();
 
@@ -58,43 +69,32 @@ fn benchtest_sync_mainnet() {
     sync_provider.status();
     sync_provider.peers();
 
-    SyncStorage::set_synced_block_number(0);
     println!(
         "synced_block_number: {}",
-        SyncStorage::get_synced_block_number()
+        client.chain_info().best_block_number
     );
 
-    while SyncStorage::get_synced_block_number() < 999 {
+    while client.chain_info().best_block_number < 999 {
         thread::sleep(Duration::from_secs(1));
         // client.import_verified_blocks();
         client.flush_queue();
-        let active_nodes = P2pMgr::get_nodes(1 << 3);
-        let synced_block_number = client.chain_info().best_block_number;
+        let active_nodes = sync_provider.active();
 
-        SyncStorage::set_synced_block_number(synced_block_number);
         println!("==================== Sync Statics ====================");
         println!(
             "Best block number: {}",
-            SyncStorage::get_synced_block_number()
+            client.chain_info().best_block_number
         );
         println!(
-            "Total/Connected/Active peers: {}/{}/{}",
-            P2pMgr::get_all_nodes_count(),
-            P2pMgr::get_nodes_count(1),
+            "Total/Active peers: {}/{}",
+            sync_provider.peers().len(),
             active_nodes.len()
         );
-        println!("Address\t\t\tSeed\tBlock No.\tSynced No.\tMode\tLQN\tLQT");
+        println!("Address\t\t\tBlock No.\tReputation");
         for node in active_nodes.iter() {
-            let duration = node.last_request_timestamp.elapsed().unwrap();
             println!(
-                "{}\t{}\t{}\t\t{}\t\t{}\t{}\t{:#?}",
-                node.get_ip_addr(),
-                node.is_from_boot_list,
-                node.best_block_num,
-                node.synced_block_num,
-                node.mode,
-                node.last_request_num,
-                duration
+                "{}\t{}\t\t{}",
+                node.ip, node.highest_block_number, node.reputation
             );
         }
     }
@@ -120,14 +120,15 @@ fn benchtest_sync_mainnet() {
 
 #[test]
 fn benchtest_sync_storage_get_client() {
-    init_sync_storage();
+    let storage = Arc::new(init_sync_storage());
     let start_time = SystemTime::now();
 
     let mut threads = Vec::new();
     for _ in 0..100 {
-        let t = thread::spawn(|| {
+        let storage = storage.clone();
+        let t = thread::spawn(move || {
             for _ in 0..1000 {
-                SyncStorage::get_block_chain();
+                storage.get_block_chain();
             }
         });
         threads.push(t);
@@ -147,14 +148,15 @@ fn benchtest_sync_storage_get_client() {
 
 #[test]
 fn benchtest_sync_storage_get_block_chain() {
-    init_sync_storage();
+    let storage = Arc::new(init_sync_storage());
     let start_time = SystemTime::now();
 
     let mut threads = Vec::new();
     for _ in 0..100 {
-        let t = thread::spawn(|| {
+        let storage = storage.clone();
+        let t = thread::spawn(move || {
             for _ in 0..1000 {
-                SyncStorage::get_block_chain();
+                storage.get_block_chain();
             }
         });
         threads.push(t);
@@ -173,18 +175,18 @@ fn benchtest_sync_storage_get_block_chain() {
 
 #[test]
 fn benchtest_sync_storage_get_chain_info() {
-    init_sync_storage();
     let test_spec = new_spec();
     let client = get_client(&test_spec);
-    SyncStorage::init(client.clone() as Arc<BlockChainClient>);
+    let storage = Arc::new(SyncStorage::new(client.clone() as Arc<BlockChainClient>));
 
     let start_time = SystemTime::now();
 
     let mut threads = Vec::new();
     for _ in 0..100 {
-        let t = thread::spawn(|| {
+        let storage = storage.clone();
+        let t = thread::spawn(move || {
             for _ in 0..1000 {
-                SyncStorage::get_chain_info();
+                storage.get_chain_info();
             }
         });
         threads.push(t);
@@ -203,15 +205,16 @@ fn benchtest_sync_storage_get_chain_info() {
 
 #[test]
 fn benchtest_sync_storage_synced_block_number() {
-    init_sync_storage();
+    let storage = Arc::new(init_sync_storage());
     let start_time = SystemTime::now();
 
     let mut threads = Vec::new();
     for i in 0..500 {
+        let storage = storage.clone();
         let t = thread::spawn(move || {
             for j in 0..1000 {
-                SyncStorage::set_synced_block_number(i * j as u64);
-                SyncStorage::get_synced_block_number();
+                storage.set_synced_block_number(i * j as u64);
+                storage.get_synced_block_number();
             }
         });
         threads.push(t);
@@ -227,3 +230,203 @@ fn benchtest_sync_storage_synced_block_number() {
     );
     assert!(duration < Duration::from_secs(1));
 }
+
+#[test]
+fn test_queued_short_ids_follow_transaction_queue() {
+    let storage = init_sync_storage();
+    let queued = |storage: &SyncStorage| -> Vec<H256> {
+        storage
+            .get_queued_short_ids()
+            .lock()
+            .expect("queued short ids")
+            .values()
+            .cloned()
+            .collect()
+    };
+    let transaction = Transaction::default().fake_sign(Address::default());
+    let hash = transaction.hash();
+
+    storage.on_transaction_queue_event(QueueEvent::Added(&transaction, None));
+    assert_eq!(queued(&storage), vec![hash]);
+
+    // another transaction with the same short id leaving the queue keeps the one queued.
+    let mut other = hash;
+    other[31] ^= 1;
+    storage.on_transaction_queue_event(QueueEvent::Removed(&other));
+    assert_eq!(queued(&storage), vec![hash]);
+
+    storage.on_transaction_queue_event(QueueEvent::Removed(&hash));
+    assert!(queued(&storage).is_empty());
+}
+
+#[test]
+fn test_state_sync_between_two_nodes() {
+    // the serving node has the mainnet genesis state, the new one a different genesis.
+    let server = get_client(&new_spec());
+    let client = get_client(&Spec::new_test());
+    let root = *server
+        .block_header(BlockId::Latest)
+        .expect("genesis header")
+        .decode()
+        .state_root();
+    assert!(server.has_state_data(None, &root));
+    assert!(!client.has_state_data(None, &root));
+
+    let best = 100;
+    let peers = vec![PeerHead {
+        node_hash: 1,
+        best_block_num: best,
+    }];
+    let genesis = client
+        .block_header(BlockId::Number(0))
+        .expect("genesis header")
+        .decode();
+    let genesis_difficulty = client
+        .block_total_difficulty(BlockId::Number(0))
+        .expect("genesis total difficulty");
+    let mut state_sync = StateSync::new();
+    state_sync.start(genesis.clone(), genesis_difficulty);
+
+    // headers linked to the genesis up to the pivot, which carries the state root served.
+    let pivot = best - PIVOT_DISTANCE;
+    let mut headers: Vec<BlockHeader> = Vec::new();
+    for number in 1..pivot + 1 {
+        let mut header = BlockHeader::new();
+        header.set_number(number);
+        header.set_parent_hash(headers.last().map_or(genesis.hash(), |parent| parent.hash()));
+        header.set_difficulty(U256::from(1));
+        if number == pivot {
+            header.set_state_root(root);
+        }
+        headers.push(header);
+    }
+    let requests = state_sync.schedule(&peers, Instant::now());
+    assert_eq!(
+        requests,
+        vec![(
+            1,
+            Request::Headers {
+                from: 1,
+                count: pivot,
+            },
+        )]
+    );
+    assert_eq!(state_sync.on_headers(1, headers, &*client), Response::Accepted);
+
+    loop {
+        let requests = state_sync.schedule(&peers, Instant::now());
+        if requests.is_empty() {
+            break;
+        }
+        for (node_hash, request) in requests {
+            match request {
+                Request::Bodies {
+                    ..
+                } => {
+                    let bodies = vec![Vec::new(), Vec::new()];
+                    assert_eq!(state_sync.on_bodies(node_hash, bodies), Response::Accepted);
+                }
+                Request::StateData {
+                    hashes,
+                } => {
+                    let data = hashes
+                        .iter()
+                        .filter_map(|hash| server.state_data(hash))
+                        .collect();
+                    let (response, ready) = state_sync.on_state_data(
+                        node_hash,
+                        data,
+                        |address_hash: Option<&H256>, hash: &H256| {
+                            client.has_state_data(address_hash, hash)
+                        },
+                    );
+                    assert_eq!(response, Response::Accepted);
+                    client.import_state_data(ready).expect("state data written");
+                }
+                request => panic!("unexpected request {:?}", request),
+            }
+        }
+    }
+    assert!(client.has_state_data(None, &root));
+
+    let (header, parent, block, total_difficulty) =
+        state_sync.complete().expect("state of pivot complete");
+    assert_eq!(total_difficulty, genesis_difficulty + U256::from(pivot));
+    client
+        .import_state_pivot(parent, block, total_difficulty)
+        .expect("pivot imported");
+    assert_eq!(client.chain_info().best_block_number, header.number());
+    assert_eq!(client.chain_info().total_difficulty, total_difficulty);
+
+    let premined: Address =
+        "a0eeaeabdbc92953b072afbd21f3e3fd8a4a4f5e6a6e22200db746ab75e9a99a".into();
+    let balance = server.balance(&premined, BlockId::Latest);
+    assert!(balance.unwrap_or_default() > U256::zero());
+    assert_eq!(client.balance(&premined, BlockId::Latest), balance);
+}
+
+#[test]
+fn test_light_account_proof_from_full_node() {
+    let server = get_client(&new_spec());
+    let genesis = server
+        .block_header(BlockId::Number(0))
+        .expect("genesis header")
+        .decode();
+    let total_difficulty = server
+        .block_total_difficulty(BlockId::Number(0))
+        .expect("genesis total difficulty");
+    let mut light_sync = LightSync::new();
+    light_sync.start(genesis.clone(), total_difficulty);
+
+    let premined: Address =
+        "a0eeaeabdbc92953b072afbd21f3e3fd8a4a4f5e6a6e22200db746ab75e9a99a".into();
+    let (sender, receiver) = oneshot::channel();
+    let request = LightRequest::Account {
+        block_hash: genesis.hash(),
+        address_hash: blake2b(&premined),
+    };
+    assert!(light_sync.fetch(request, sender));
+
+    let peers = vec![PeerHead {
+        node_hash: 1,
+        best_block_num: 0,
+    }];
+    let requests = light_sync.schedule(&peers, Instant::now());
+    assert_eq!(requests.len(), 1);
+    let req_id = match requests[0].1 {
+        light::Request::Proof {
+            req_id,
+            request: LightRequest::Account {
+                block_hash,
+                address_hash,
+            },
+        } => {
+            let (nodes, _) = server
+                .prove_account(address_hash, BlockId::Hash(block_hash))
+                .expect("account proved");
+            let mut stream = RlpStream::new_list(3);
+            stream.append(&req_id).append(&light::CREDIT_LIMIT);
+            stream.begin_list(nodes.len());
+            for node in nodes.iter() {
+                stream.append(node);
+            }
+            let response = stream.out();
+            assert_eq!(
+                light_sync.on_proof(1, req_id, 0, &UntrustedRlp::new(&response), Instant::now()),
+                Response::Accepted
+            );
+            req_id
+        }
+        ref request => panic!("unexpected request {:?}", request),
+    };
+    assert_eq!(req_id, 1);
+
+    match receiver.wait() {
+        Ok(LightResponse::Account(Some(account))) => {
+            let balance = server.balance(&premined, BlockId::Latest);
+            assert!(balance.unwrap_or_default() > U256::zero());
+            assert_eq!(Some(account.balance), balance);
+        }
+        response => panic!("unexpected response {:?}", response),
+    }
+}
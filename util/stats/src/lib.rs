@@ -64,6 +64,17 @@ impl<T: Ord> Corpus<T> {
         self.0.get(x - 1)
     }
 
+    /// Get the element at given percentile, which may be fractional, using the nearest-rank
+    /// method. Unlike `percentile` it yields an element for any non-empty corpus.
+    pub fn nearest_rank(&self, percentile: f64) -> Option<&T> {
+        let len = self.0.len();
+        if len == 0 {
+            return None;
+        }
+        let rank = (percentile / 100.0 * len as f64).ceil() as usize;
+        self.0.get(::std::cmp::min(::std::cmp::max(rank, 1), len) - 1)
+    }
+
     /// Get the median element, if it exists.
     pub fn median(&self) -> Option<&T> { self.0.get(self.0.len() / 2) }
 
@@ -162,6 +173,17 @@ mod tests {
         assert_eq!(corpus.median(), Some(&6));
     }
 
+    #[test]
+    fn check_nearest_rank() {
+        let corpus = Corpus::from(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(corpus.nearest_rank(0.0), Some(&1));
+        assert_eq!(corpus.nearest_rank(1.0), Some(&1));
+        assert_eq!(corpus.nearest_rank(50.0), Some(&5));
+        assert_eq!(corpus.nearest_rank(55.5), Some(&6));
+        assert_eq!(corpus.nearest_rank(100.0), Some(&10));
+        assert_eq!(Corpus::<u64>::from(vec![]).nearest_rank(50.0), None);
+    }
+
     #[test]
     fn check_histogram() {
         let hist = Histogram::create(